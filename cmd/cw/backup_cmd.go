@@ -0,0 +1,156 @@
+package main
+
+import (
+	"fmt"
+	"io"
+	"net"
+	"os"
+	"path/filepath"
+
+	"github.com/spf13/cobra"
+
+	"github.com/codewiresh/codewire/internal/backup"
+	"github.com/codewiresh/codewire/internal/client"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+func backupCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "backup",
+		Short: "Back up or restore node state",
+		Long: `Save a node's state to a gzipped tarball, or restore one into the data
+directory — for moving a node to a new machine.
+
+A backup holds config.toml, the auth token, the node key, saved servers
+(servers.toml) and session metadata, and with --logs every session's output
+and event logs. Sockets, PID files and caches are not included.`,
+	}
+	cmd.AddCommand(backupCreateCmd(), backupRestoreCmd())
+	return cmd
+}
+
+func backupCreateCmd() *cobra.Command {
+	var logs bool
+
+	cmd := &cobra.Command{
+		Use:   "create <file>",
+		Short: "Write the node's state to a tarball",
+		Long: `Write the node's state to <file> (a .tar.gz).
+
+When the node is running it builds the archive itself, holding off metadata
+writes meanwhile, so the session list and journal in the backup agree with
+each other. When it is not, cw reads the data directory directly. A backup
+holds the node's secrets, so it is only taken on the node's own machine.`,
+		Example: `  cw backup create node.tar.gz
+  cw backup create --logs node-full.tar.gz`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveTarget()
+			if err != nil {
+				return err
+			}
+			if !target.IsLocal() {
+				return fmt.Errorf("backups hold the node's secrets and are only taken locally; run cw backup create on the node")
+			}
+			path := args[0]
+			tmp, err := os.CreateTemp(filepath.Dir(path), "."+filepath.Base(path)+".tmp-*")
+			if err != nil {
+				return err
+			}
+			defer os.Remove(tmp.Name())
+
+			var files uint
+			if target.IsLocal() && !nodeRunning() {
+				files, err = backupDataDir(tmp, logs)
+			} else {
+				files, err = client.Backup(target, logs, tmp)
+			}
+			if closeErr := tmp.Close(); err == nil {
+				err = closeErr
+			}
+			if err != nil {
+				return err
+			}
+			if err := os.Rename(tmp.Name(), path); err != nil {
+				return err
+			}
+			fmt.Printf("Backed up %d files to %s\n", files, path)
+			return nil
+		},
+	}
+	cmd.Flags().BoolVar(&logs, "logs", false, "Include every session's output and event logs")
+	return cmd
+}
+
+// backupDataDir archives the local data directory while no node is using it.
+func backupDataDir(w io.Writer, logs bool) (uint, error) {
+	dir := dataDir()
+	host, _ := os.Hostname()
+	dataDirVersion, err := session.DataDirVersionOf(dir)
+	if err != nil {
+		return 0, err
+	}
+	m, err := backup.Create(w, dir, logs, backup.Manifest{
+		Node:           host,
+		Version:        version,
+		DataDirVersion: dataDirVersion,
+	})
+	if err != nil {
+		return 0, err
+	}
+	return uint(len(m.Files)), nil
+}
+
+func backupRestoreCmd() *cobra.Command {
+	var force bool
+
+	cmd := &cobra.Command{
+		Use:   "restore <file>",
+		Short: "Restore node state from a tarball",
+		Long: `Unpack a backup made by "cw backup create" into the data directory
+(~/.codewire). The node must be stopped. A data directory that already has
+sessions is refused unless --force is given, in which case files in the
+backup replace the ones there.
+
+A backup from an older cw is migrated when the node next starts
+(see "cw node migrate").`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			if nodeRunning() {
+				return fmt.Errorf("the node is running; stop it first (cw node stop)")
+			}
+			f, err := os.Open(args[0])
+			if err != nil {
+				return err
+			}
+			defer f.Close()
+
+			dir := dataDir()
+			m, err := backup.Restore(f, dir, force)
+			if err != nil {
+				return err
+			}
+			from := m.Node
+			if from == "" {
+				from = "unknown host"
+			}
+			fmt.Printf("Restored %d files from %s (%s) into %s\n", len(m.Files), from, m.CreatedAt.Local().Format("2006-01-02 15:04"), dir)
+			if m.DataDirVersion < session.DataDirVersion {
+				fmt.Printf("The data directory will be migrated from layout version %d when the node starts.\n", m.DataDirVersion)
+			}
+			return nil
+		},
+	}
+	cmd.Flags().BoolVar(&force, "force", false, "Restore over a data directory that already has sessions")
+	return cmd
+}
+
+// nodeRunning reports whether a node is listening on the local socket.
+func nodeRunning() bool {
+	conn, err := net.Dial("unix", filepath.Join(dataDir(), "codewire.sock"))
+	if err != nil {
+		return false
+	}
+	conn.Close()
+	return true
+}
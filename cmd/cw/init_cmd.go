@@ -0,0 +1,246 @@
+package main
+
+import (
+	"crypto/ed25519"
+	"crypto/tls"
+	"fmt"
+	"net"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"github.com/spf13/cobra"
+	"golang.org/x/term"
+
+	"github.com/codewiresh/codewire/internal/auth"
+	"github.com/codewiresh/codewire/internal/config"
+)
+
+// defaultListen is the listen address cw init offers for remote access.
+const defaultListen = "0.0.0.0:9100"
+
+func initCmd() *cobra.Command {
+	var (
+		name        string
+		listen      string
+		externalURL string
+		tlsCert     string
+		tlsKey      string
+		localOnly   bool
+		yes         bool
+		force       bool
+	)
+
+	cmd := &cobra.Command{
+		Use:   "init",
+		Short: "Set up this machine's node: name, listener, TLS and tokens",
+		Long: `Set up the node on this machine in one go: create the data directory,
+write the [node] section of config.toml (name, WebSocket listen address, TLS
+certificate and external URL), generate the auth token and node identity key,
+and print the cw server add command to run on other machines.
+
+On a terminal every setting is asked for, with flags as the defaults offered.
+With --yes, or when stdin is not a terminal, the flags are used as given.
+An existing config.toml has its [node] section updated only with --force or
+after confirming. Only the settings that change are rewritten; comments, other
+keys and the other sections are kept.`,
+		Args: cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			dir := dataDir()
+			cfgPath := filepath.Join(dir, "config.toml")
+			interactive := !yes && term.IsTerminal(int(os.Stdin.Fd()))
+			ask := func(flag, label, value string) (string, error) {
+				if !interactive || cmd.Flags().Changed(flag) {
+					return value, nil
+				}
+				return promptDefault(label, value)
+			}
+
+			// Values from the environment must not end up in the file.
+			if set := config.NodeEnvOverrides(); len(set) > 0 {
+				return fmt.Errorf("%s set in the environment, overriding config.toml; unset before running cw init", strings.Join(set, ", "))
+			}
+			if _, err := os.Stat(cfgPath); err == nil && !force {
+				if !interactive {
+					return fmt.Errorf("%s already exists; pass --force to replace its [node] section", cfgPath)
+				}
+				answer, err := promptDefault(fmt.Sprintf("%s already exists. Replace its [node] section? (y/n)", cfgPath), "n")
+				if err != nil {
+					return err
+				}
+				if !isYes(answer) {
+					return nil
+				}
+			}
+
+			cfg, err := config.LoadConfig(dir)
+			if err != nil {
+				return err
+			}
+			node := cfg.Node
+
+			if name == "" {
+				name = node.Name
+			}
+			if name, err = ask("name", "Node name", name); err != nil {
+				return err
+			}
+			if err := config.ValidateNodeName(name); err != nil {
+				return err
+			}
+			node.Name = name
+
+			remote := !localOnly && (listen != "" || node.Listen != nil)
+			if interactive && !localOnly && !cmd.Flags().Changed("listen") {
+				def := "n"
+				if remote {
+					def = "y"
+				}
+				answer, err := promptDefault("Accept connections from other machines? (y/n)", def)
+				if err != nil {
+					return err
+				}
+				remote = isYes(answer)
+			}
+
+			node.Listen, node.TLSCert, node.TLSKey, node.ExternalURL = nil, "", "", nil
+			if remote {
+				if listen == "" && cfg.Node.Listen != nil {
+					listen = *cfg.Node.Listen
+				}
+				if listen, err = ask("listen", "Listen address", orDefault(listen, defaultListen)); err != nil {
+					return err
+				}
+				if _, _, err := net.SplitHostPort(listen); err != nil {
+					return fmt.Errorf("invalid listen address %q: %w", listen, err)
+				}
+				node.Listen = &listen
+
+				if tlsCert == "" && tlsKey == "" {
+					tlsCert, tlsKey = cfg.Node.TLSCert, cfg.Node.TLSKey
+				}
+				if tlsCert, err = ask("tls-cert", "TLS certificate file (PEM; empty serves plain ws://, e.g. behind a TLS proxy)", tlsCert); err != nil {
+					return err
+				}
+				if tlsCert != "" {
+					if tlsKey, err = ask("tls-key", "TLS private key file (PEM)", tlsKey); err != nil {
+						return err
+					}
+				}
+				if (tlsCert == "") != (tlsKey == "") {
+					return fmt.Errorf("--tls-cert and --tls-key must be given together")
+				}
+				if tlsCert != "" {
+					if tlsCert, err = filepath.Abs(tlsCert); err != nil {
+						return err
+					}
+					if tlsKey, err = filepath.Abs(tlsKey); err != nil {
+						return err
+					}
+					if _, err := tls.LoadX509KeyPair(tlsCert, tlsKey); err != nil {
+						return fmt.Errorf("loading TLS certificate: %w", err)
+					}
+				}
+				node.TLSCert, node.TLSKey = tlsCert, tlsKey
+
+				if externalURL == "" && cfg.Node.ExternalURL != nil {
+					externalURL = *cfg.Node.ExternalURL
+				}
+				if externalURL, err = ask("external-url", "External URL, if clients reach the node through a proxy (empty: the listen address)", externalURL); err != nil {
+					return err
+				}
+				if externalURL != "" {
+					node.ExternalURL = &externalURL
+				}
+			}
+
+			if err := config.SaveNodeConfig(dir, node); err != nil {
+				return err
+			}
+			token, err := auth.LoadOrGenerateToken(dir)
+			if err != nil {
+				return err
+			}
+			key, err := auth.LoadOrGenerateNodeKey(dir)
+			if err != nil {
+				return err
+			}
+
+			fmt.Println()
+			fmt.Printf("Wrote %s\n", cfgPath)
+			fmt.Printf("Node:     %s\n", name)
+			fmt.Printf("Identity: %s\n", auth.Fingerprint(key.Public().(ed25519.PublicKey)))
+			if remote {
+				url := nodeURL(listen, externalURL, tlsCert != "")
+				fmt.Printf("Listen:   %s (%s)\n", listen, url)
+				fmt.Println()
+				fmt.Println("On other machines, run:")
+				fmt.Println()
+				fmt.Printf("  cw server add %s %s --token %s\n", name, url, token)
+				fmt.Println()
+				fmt.Println("It pins the node's identity; check that it shows the fingerprint above.")
+				if tlsCert == "" && externalURL == "" && !isLoopback(listen) {
+					fmt.Fprintln(os.Stderr, "[cw] warning: the listener serves plain ws://; the token crosses the network unencrypted unless a TLS proxy or private network is in between")
+				}
+			} else {
+				fmt.Println("Listen:   local socket only (run cw init again to accept remote connections)")
+			}
+			fmt.Println()
+			if nodeRunning() {
+				fmt.Println("The node is running: restart it to apply (cw node stop; it starts again on the next cw command).")
+			} else {
+				fmt.Println("The node starts on the next cw command, or run cw node to start it in the foreground.")
+			}
+			return nil
+		},
+	}
+
+	cmd.Flags().StringVar(&name, "name", "", "Node name (default: from $HOSTNAME)")
+	cmd.Flags().StringVar(&listen, "listen", "", "WebSocket listen address for remote clients, e.g. "+defaultListen)
+	cmd.Flags().StringVar(&externalURL, "external-url", "", "URL clients reach the node at when it is behind a proxy")
+	cmd.Flags().StringVar(&tlsCert, "tls-cert", "", "TLS certificate (PEM) for the listener")
+	cmd.Flags().StringVar(&tlsKey, "tls-key", "", "TLS private key (PEM) for the listener")
+	cmd.Flags().BoolVar(&localOnly, "local", false, "Only accept clients on this machine (no listener)")
+	cmd.Flags().BoolVarP(&yes, "yes", "y", false, "Do not prompt; use the flags as given")
+	cmd.Flags().BoolVar(&force, "force", false, "Replace the [node] section of an existing config.toml")
+	cmd.MarkFlagsMutuallyExclusive("local", "listen")
+
+	return cmd
+}
+
+// nodeURL is the URL other machines give cw server add: the external URL
+// when there is one, else the listen address with this machine's hostname
+// in place of a wildcard host.
+func nodeURL(listen, externalURL string, useTLS bool) string {
+	if externalURL != "" {
+		return externalURL
+	}
+	host, port, _ := net.SplitHostPort(listen)
+	if ip := net.ParseIP(host); host == "" || (ip != nil && ip.IsUnspecified()) {
+		host, _ = os.Hostname()
+	}
+	scheme := "ws"
+	if useTLS {
+		scheme = "wss"
+	}
+	return fmt.Sprintf("%s://%s/ws", scheme, net.JoinHostPort(host, port))
+}
+
+func isLoopback(listen string) bool {
+	host, _, _ := net.SplitHostPort(listen)
+	ip := net.ParseIP(host)
+	return host == "localhost" || (ip != nil && ip.IsLoopback())
+}
+
+func isYes(answer string) bool {
+	answer = strings.ToLower(answer)
+	return answer == "y" || answer == "yes"
+}
+
+// orDefault returns s, or def when s is empty.
+func orDefault(s, def string) string {
+	if s == "" {
+		return def
+	}
+	return s
+}
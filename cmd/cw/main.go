@@ -2,24 +2,34 @@ package main
 
 import (
 	"context"
+	"encoding/base64"
+	"errors"
 	"fmt"
+	"io"
 	"net"
 	"os"
 	"os/exec"
 	"os/signal"
 	"path/filepath"
-	"strconv"
+	"slices"
+	"sort"
 	"strings"
 	"syscall"
 	"time"
 
 	"github.com/spf13/cobra"
+	"golang.org/x/term"
 
+	"github.com/codewiresh/codewire/internal/ansi"
+	"github.com/codewiresh/codewire/internal/auth"
 	"github.com/codewiresh/codewire/internal/client"
 	"github.com/codewiresh/codewire/internal/config"
 	"github.com/codewiresh/codewire/internal/mcp"
 	"github.com/codewiresh/codewire/internal/node"
 	"github.com/codewiresh/codewire/internal/relay"
+	"github.com/codewiresh/codewire/internal/session"
+	"github.com/codewiresh/codewire/internal/terminal"
+	"github.com/codewiresh/codewire/internal/tmpl"
 	"github.com/codewiresh/codewire/internal/update"
 )
 
@@ -27,8 +37,9 @@ var (
 	// version is set at build time via -ldflags "-X main.version=..."
 	version = "dev"
 
-	serverFlag string
-	tokenFlag  string
+	serverFlag         string
+	tokenFlag          string
+	requestTimeoutFlag time.Duration
 )
 
 func main() {
@@ -41,6 +52,9 @@ func main() {
 	}
 	rootCmd.PersistentFlags().StringVarP(&serverFlag, "server", "s", "", "Connect to a remote server (name from servers.toml or ws://host:port)")
 	rootCmd.PersistentFlags().StringVar(&tokenFlag, "token", "", "Auth token for remote server")
+	rootCmd.PersistentFlags().DurationVar(&requestTimeoutFlag, "request-timeout", 0, "Give up on node requests after this long (e.g. 30s); the node drops work that arrives or runs past it")
+
+	_ = rootCmd.RegisterFlagCompletionFunc("server", serverNameCompletionFunc)
 
 	// Disable cobra's auto-generated completion command; we supply our own with --install support.
 	rootCmd.CompletionOptions.DisableDefaultCmd = true
@@ -64,13 +78,26 @@ func main() {
 		grouped(runCmd(), "session"),
 		grouped(attachCmd(), "session"),
 		grouped(killCmd(), "session"),
+		grouped(signalCmd(), "session"),
+		grouped(noteCmd(), "session"),
+		grouped(mirrorCmd(), "session"),
+		grouped(extendCmd(), "session"),
+		grouped(setPriorityCmd(), "session"),
 		grouped(logsCmd(), "session"),
+		grouped(peekCmd(), "session"),
 		grouped(sendCmd(), "session"),
+		grouped(replayCmd(), "session"),
+		grouped(diffCmd(), "session"),
 		grouped(watchCmd(), "session"),
+		grouped(monitorCmd(), "session"),
 		grouped(statusCmd(), "session"),
+		grouped(findCmd(), "session"),
 		grouped(platformListCmd(), "session"),
 		grouped(subscribeCmd(), "session"),
+		grouped(eventsCmd(), "session"),
 		grouped(waitSessionCmd(), "session"),
+		grouped(historyCmd(), "session"),
+		grouped(replCmd(), "session"),
 		// Platform
 		grouped(loginCmd(), "platform"),
 		grouped(logoutCmd(), "platform"),
@@ -105,6 +132,8 @@ func main() {
 		// System
 		grouped(completionCmd(rootCmd), "system"),
 		grouped(updateCmd(), "system"),
+		grouped(initCmd(), "system"),
+		grouped(backupCmd(), "system"),
 	)
 
 	printUpdateNotice := update.BackgroundCheck(version)
@@ -141,15 +170,47 @@ func grouped(cmd *cobra.Command, id string) *cobra.Command {
 // ---------------------------------------------------------------------------
 
 func nodeCmd() *cobra.Command {
+	var (
+		watchdog         bool
+		watchdogInterval time.Duration
+		watchdogFailures int
+	)
+
 	cmd := &cobra.Command{
 		Use:   "node",
 		Short: "Start the codewire node",
+		Long: `Start the codewire node in the foreground.
+
+With --watchdog, cw runs a small supervisor that starts the node, probes its
+Unix socket, and restarts it if it exits or stops responding. Under systemd
+(Type=notify, WatchdogSec=) both modes report readiness and liveness via
+sd_notify. Sessions cannot outlive the node process that owns their PTYs, so a
+restart keeps session IDs and on-disk logs but not running sessions.`,
 		RunE: func(cmd *cobra.Command, args []string) error {
 			dir := dataDir()
 			if err := os.MkdirAll(dir, 0o755); err != nil {
 				return fmt.Errorf("creating data dir: %w", err)
 			}
 
+			if watchdog {
+				if watchdogInterval <= 0 || watchdogFailures < 1 {
+					return fmt.Errorf("--watchdog-interval must be positive and --watchdog-failures at least 1")
+				}
+				exe, err := os.Executable()
+				if err != nil {
+					return fmt.Errorf("locating cw binary: %w", err)
+				}
+				ctx, cancel := signal.NotifyContext(context.Background(), syscall.SIGTERM, syscall.SIGINT)
+				defer cancel()
+				return node.RunWatchdog(ctx, dir, node.WatchdogConfig{
+					Interval: watchdogInterval,
+					Failures: watchdogFailures,
+					Exe:      exe,
+					Args:     []string{"node"},
+				})
+			}
+
+			node.Version = version
 			n, err := node.NewNode(dir)
 			if err != nil {
 				return fmt.Errorf("initializing node: %w", err)
@@ -167,10 +228,142 @@ func nodeCmd() *cobra.Command {
 				cancel()
 			}()
 
-			return n.Run(ctx)
+			err = n.Run(ctx)
+			if errors.Is(err, node.ErrRestartRequested) {
+				// An upgrade replaced the binary on disk; start it in place
+				// so the PID (and any supervisor) stays the same.
+				exe, exeErr := os.Executable()
+				if exeErr != nil {
+					return fmt.Errorf("locating cw binary: %w", exeErr)
+				}
+				fmt.Fprintln(os.Stderr, "[cw] restarting upgraded node...")
+				return syscall.Exec(exe, os.Args, os.Environ())
+			}
+			return err
+		},
+	}
+	cmd.Flags().BoolVar(&watchdog, "watchdog", false, "Supervise the node and restart it if it exits or stops responding")
+	cmd.Flags().DurationVar(&watchdogInterval, "watchdog-interval", 10*time.Second, "Interval between node health probes")
+	cmd.Flags().IntVar(&watchdogFailures, "watchdog-failures", 3, "Consecutive failed probes before the node is restarted")
+	cmd.AddCommand(nodeStopCmd(), nodeStatusCmd(), nodeMigrateCmd(), nodeObserveTokenCmd())
+	return cmd
+}
+
+func nodeObserveTokenCmd() *cobra.Command {
+	var rotate bool
+
+	cmd := &cobra.Command{
+		Use:   "observe-token",
+		Short: "Print the read-only token for /observe streams",
+		Long: `Print the observe token, creating it the first time.
+
+The observe token only opens /observe/<session> on the node's WebSocket
+listener, which streams a session's output and accepts no input. It is meant
+for embedding live output in dashboards and CI pages; since browsers cannot
+set headers on a WebSocket it may be passed as ?token=. It is refused on /ws.
+--rotate replaces it, cutting off existing embeds.`,
+		Example: `  cw node observe-token
+  # in a page: new WebSocket("wss://host:9100/observe/build?tail=200&token=...")`,
+		Args: cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			dir := dataDir()
+			if err := os.MkdirAll(dir, 0o755); err != nil {
+				return fmt.Errorf("creating data dir: %w", err)
+			}
+			var token string
+			var err error
+			if rotate {
+				token, err = auth.GenerateObserveToken(dir)
+			} else {
+				token, err = auth.LoadOrGenerateObserveToken(dir)
+			}
+			if err != nil {
+				return err
+			}
+			fmt.Println(token)
+			return nil
+		},
+	}
+	cmd.Flags().BoolVar(&rotate, "rotate", false, "Replace the token with a new one")
+	return cmd
+}
+
+func nodeMigrateCmd() *cobra.Command {
+	var dryRun bool
+
+	cmd := &cobra.Command{
+		Use:   "migrate",
+		Short: "Upgrade the data directory to this version's layout",
+		Long: `Bring the data directory (~/.codewire) up to the layout this build uses.
+
+The node runs pending migrations itself when it starts, so this is only
+needed to see what a new release will change first (--dry-run), or to
+migrate before starting the node. Each migration records the version it
+reached in datadir.version, so one that fails is retried from there.
+A data directory written by a newer cw is refused rather than rewritten.`,
+		Args: cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			dir := dataDir()
+			if !dryRun {
+				if conn, err := net.Dial("unix", filepath.Join(dir, "codewire.sock")); err == nil {
+					conn.Close()
+					return fmt.Errorf("the node is running; stop it first (cw node stop), or pass --dry-run")
+				}
+			}
+			from, steps, err := session.MigrateDataDir(dir, dryRun)
+			if errors.Is(err, session.ErrDataDirTooNew) {
+				return err
+			}
+			if from == session.DataDirVersion && err == nil {
+				fmt.Printf("%s is at layout version %d; nothing to migrate\n", dir, from)
+				return nil
+			}
+			fmt.Printf("%s: layout version %d -> %d\n", dir, from, session.DataDirVersion)
+			for _, step := range steps {
+				fmt.Printf("  v%d: %s\n", step.Version, step.Description)
+				for _, c := range step.Changes {
+					fmt.Printf("      %s\n", c)
+				}
+				if len(step.Changes) == 0 {
+					fmt.Println("      (nothing to change)")
+				}
+			}
+			if err != nil {
+				return err
+			}
+			if dryRun {
+				fmt.Println("Dry run: nothing was changed.")
+			}
+			return nil
+		},
+	}
+	cmd.Flags().BoolVar(&dryRun, "dry-run", false, "List the migrations and changes without writing anything")
+	return cmd
+}
+
+func nodeStatusCmd() *cobra.Command {
+	var jsonOutput bool
+
+	cmd := &cobra.Command{
+		Use:   "status",
+		Short: "Show node version and remote client bandwidth",
+		Long: `Show the node's version and bytes sent to and received from remote
+(WebSocket) clients: one row per open connection, plus running totals per auth
+token since the node started. Tokens are shown as a short hash.`,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveTarget()
+			if err != nil {
+				return err
+			}
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+			return client.NodeStatus(target, jsonOutput)
 		},
 	}
-	cmd.AddCommand(nodeStopCmd())
+	cmd.Flags().BoolVar(&jsonOutput, "json", false, "Output as JSON")
 	return cmd
 }
 
@@ -179,15 +372,16 @@ func nodeStopCmd() *cobra.Command {
 		Use:   "stop",
 		Short: "Stop the running node",
 		RunE: func(cmd *cobra.Command, args []string) error {
-			pidPath := filepath.Join(dataDir(), "codewire.pid")
-			data, err := os.ReadFile(pidPath)
-			if err != nil {
-				return fmt.Errorf("reading pid file: %w (is the node running?)", err)
+			// Under --watchdog, stop the supervisor so it does not restart
+			// the node; it forwards SIGTERM to the node itself.
+			pid, pidPath, err := node.RunningPID(dataDir())
+			if errors.Is(err, node.ErrStalePIDFile) {
+				_ = os.Remove(pidPath)
+				fmt.Fprintln(os.Stderr, "[cw] node already stopped (stale pid file removed)")
+				return nil
 			}
-
-			pid, err := strconv.Atoi(strings.TrimSpace(string(data)))
 			if err != nil {
-				return fmt.Errorf("invalid pid file: %w", err)
+				return fmt.Errorf("reading pid file: %w", err)
 			}
 
 			if err := syscall.Kill(pid, syscall.SIGTERM); err != nil {
@@ -218,13 +412,45 @@ func runCmd() *cobra.Command {
 		envVars     []string
 		autoApprove bool
 		promptFile  string
+		attach      bool
+		snapshot    bool
+		snapshotCmd string
+		shellScript string
+		workspace   bool
+		wsSource    string
+		batchFile   string
+		notes       []string
+		maxRuntime  string
+		idleTimeout string
+		term        string
+		encoding    string
+		priority    string
+		mirrors     []string
+		noLog       bool
+		inputsOnly  bool
+		recordInput bool
+		dryRun      bool
 	)
 
 	cmd := &cobra.Command{
 		Use:     "run [name] [tag] -- command...",
 		Aliases: []string{},
 		Short:   "Launch a new session",
+		Long: `Launch a new session on the node.
+
+With --dry-run nothing is started: the node runs the checks a launch would —
+resolving the command against the PATH the session would see, the working
+directory, the name, limits, and guardrails — and cw prints the binary,
+directory and settings it would use, or why it would refuse. Works with
+--batch, where each launch is checked as if the ones before it had started.
+Exits non-zero if any launch would be refused.`,
 		RunE: func(cmd *cobra.Command, args []string) error {
+			if noLog && inputsOnly {
+				return fmt.Errorf("--no-log cannot be combined with --log-inputs-only")
+			}
+			if noLog && recordInput {
+				return fmt.Errorf("--no-log cannot be combined with --record-input")
+			}
 			target, err := resolveTarget()
 			if err != nil {
 				return err
@@ -236,8 +462,41 @@ func runCmd() *cobra.Command {
 				}
 			}
 
+			if batchFile != "" {
+				if len(args) > 0 {
+					return fmt.Errorf("--batch takes no command; describe each launch in the file")
+				}
+				in := io.Reader(os.Stdin)
+				if batchFile != "-" {
+					f, err := os.Open(batchFile)
+					if err != nil {
+						return fmt.Errorf("opening batch file: %w", err)
+					}
+					defer f.Close()
+					in = f
+				}
+				if workDir == "" {
+					workDir, _ = os.Getwd()
+				}
+				return client.RunBatch(target, in, workDir, dryRun)
+			}
+
 			dash := cmd.ArgsLenAtDash()
-			if dash == -1 {
+			if shellScript != "" {
+				// cw run [name] [tag] --shell "npm test && claude -p '...'"
+				if dash != -1 {
+					return fmt.Errorf("use either --shell or '-- command', not both")
+				}
+				if len(args) > 2 {
+					return fmt.Errorf("expected at most two positional args (name, tag) with --shell")
+				}
+				if len(args) > 0 && name == "" {
+					name = args[0]
+				}
+				if len(args) > 1 {
+					tags = append(tags, args[1])
+				}
+			} else if dash == -1 {
 				if len(args) > 0 {
 					return fmt.Errorf("missing '--' before command\n\nDid you mean: cw run -- %s\n\nUsage: cw run [name] [tag] -- <command> [args...]", strings.Join(args, " "))
 				}
@@ -245,7 +504,9 @@ func runCmd() *cobra.Command {
 			}
 
 			var command []string
-			if dash == 2 {
+			if shellScript != "" {
+				// The node wraps the script in its configured shell.
+			} else if dash == 2 {
 				// cw run planner my-cohort -- claude -p "..."
 				if name == "" {
 					name = args[0]
@@ -265,7 +526,7 @@ func runCmd() *cobra.Command {
 				return fmt.Errorf("expected at most two positional args (name, tag) before --")
 			}
 
-			if len(command) == 0 {
+			if len(command) == 0 && shellScript == "" {
 				return fmt.Errorf("command required after --")
 			}
 
@@ -288,7 +549,55 @@ func runCmd() *cobra.Command {
 				}
 			}
 
-			return client.Run(target, command, workDir, name, envVars, stdinData, tags...)
+			mirrors, err = mirrorSpecs(target, mirrors)
+			if err != nil {
+				return err
+			}
+
+			opts := client.RunOptions{
+				Snapshot:        snapshot,
+				SnapshotCommand: snapshotCmd,
+				Script:          shellScript,
+				Workspace:       workspace || wsSource != "",
+				WorkspaceSource: wsSource,
+				Notes:           notes,
+				MaxRuntime:      maxRuntime,
+				IdleTimeout:     idleTimeout,
+				Term:            term,
+				Encoding:        encoding,
+				Priority:        priority,
+				Mirrors:         mirrors,
+				RecordInput:     recordInput,
+			}
+			switch {
+			case noLog:
+				opts.History = "none"
+			case inputsOnly:
+				opts.History = "inputs"
+			}
+			if dryRun {
+				if attach {
+					return fmt.Errorf("--dry-run cannot be combined with --attach")
+				}
+				return client.ValidateRun(target, command, workDir, name, envVars, stdinData, opts, tags...)
+			}
+			if attach {
+				// The session renders for the terminal attaching to it.
+				if opts.Term == "" {
+					opts.Term = os.Getenv("TERM")
+				}
+				if cols, rows, ok := client.AttachSize(); ok {
+					opts.Cols, opts.Rows = cols, rows
+				}
+			}
+			id, err := client.Run(target, command, workDir, name, envVars, stdinData, opts, tags...)
+			if err != nil {
+				return err
+			}
+			if attach {
+				return exitWith(client.Attach(target, &id, client.AttachOptions{}))
+			}
+			return nil
 		},
 	}
 
@@ -298,6 +607,24 @@ func runCmd() *cobra.Command {
 	cmd.Flags().StringArrayVarP(&envVars, "env", "e", nil, "Environment variable overrides (KEY=VALUE, can be repeated)")
 	cmd.Flags().BoolVar(&autoApprove, "auto-approve", false, "Inject --dangerously-skip-permissions after the command binary")
 	cmd.Flags().StringVar(&promptFile, "prompt-file", "", "File whose contents are injected as stdin after launch")
+	cmd.Flags().StringVar(&shellScript, "shell", "", "Run a shell command line through the node's configured shell (default sh -c) instead of '-- command'")
+	cmd.Flags().StringVar(&batchFile, "batch", "", "Launch every job in a JSONL file (- for stdin); each line has command or shell, and optional dir, env, name, tags")
+	cmd.Flags().BoolVar(&workspace, "workspace", false, "Run in a fresh per-session directory on the node instead of --dir")
+	cmd.Flags().StringVar(&wsSource, "workspace-from", "", "Git URL to clone or template directory to copy into the workspace (implies --workspace)")
+	cmd.Flags().BoolVar(&attach, "attach", false, "Attach to the session immediately after launch")
+	cmd.Flags().StringArrayVar(&notes, "note", nil, "Note shown to everyone who attaches (can be repeated)")
+	cmd.Flags().StringVar(&maxRuntime, "max-runtime", "", "Kill the session after this long (e.g. 2h; default from [launch] max_runtime)")
+	cmd.Flags().StringVar(&idleTimeout, "idle-timeout", "", "Kill the session after this long without input or output (e.g. 30m)")
+	cmd.Flags().StringVar(&term, "term", "", "TERM for the session (default: [launch] term on the node; with --attach, this terminal's TERM)")
+	cmd.Flags().StringVar(&encoding, "encoding", "", "Character encoding the session writes, e.g. latin1 or shift_jis (default: from its locale, else UTF-8)")
+	cmd.Flags().StringVar(&priority, "priority", "", "Priority class: high, normal (default) or background (see cw set-priority)")
+	cmd.Flags().StringArrayVar(&mirrors, "mirror", nil, "Also copy output to file:/path or cmd:<command> (can be repeated)")
+	cmd.Flags().BoolVar(&noLog, "no-log", false, "Record nothing on disk: no output log and no input log (output cannot be replayed or read back)")
+	cmd.Flags().BoolVar(&inputsOnly, "log-inputs-only", false, "Record only input sent to the session, not its output")
+	cmd.Flags().BoolVar(&recordInput, "record-input", false, "Record input sent to the session, attach keystrokes included, for cw replay")
+	cmd.Flags().BoolVar(&dryRun, "dry-run", false, "Check the launch on the node and print what would run, without starting it")
+	cmd.Flags().BoolVar(&snapshot, "snapshot", false, "Capture git status/diff of the working directory when the session exits")
+	cmd.Flags().StringVar(&snapshotCmd, "snapshot-cmd", "", "Shell command to capture on exit instead of git status/diff (implies --snapshot)")
 	_ = cmd.RegisterFlagCompletionFunc("tag", tagCompletionFunc)
 
 	return cmd
@@ -309,8 +636,28 @@ func runCmd() *cobra.Command {
 // attachCmd
 // ---------------------------------------------------------------------------
 
+// exitWith exits with an attach's exit code when it is non-zero, once the
+// attach has restored the terminal.
+func exitWith(code int, err error) error {
+	if err == nil && code != 0 {
+		os.Exit(code)
+	}
+	return err
+}
+
 func attachCmd() *cobra.Command {
-	var noHistory bool
+	var (
+		noHistory      bool
+		historyLines   uint
+		exitOnComplete string
+		confirmEnter   bool
+		localEcho      bool
+		raw            bool
+		scriptPath     string
+		colors         string
+		latest         bool
+		followNew      bool
+	)
 
 	cmd := &cobra.Command{
 		Use:               "attach [session]",
@@ -320,10 +667,16 @@ func attachCmd() *cobra.Command {
 
 Detach without killing: press Ctrl+B d
 The session continues running after you detach.
+Show session details: press Ctrl+B i
+Bookmark this moment in the output: press Ctrl+B m, type a name, Enter
 
-Warning: Ctrl+C sends SIGINT to the session process — use Ctrl+B d to detach safely.`,
+Warning: Ctrl+C sends SIGINT to the session process — use Ctrl+B d to detach safely.
+
+To track a pipeline of short tasks launched one after another, use
+'cw attach --latest --follow-new': it attaches to the newest running session
+and, each time the session ends, to the next one launched.`,
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -334,6 +687,29 @@ Warning: Ctrl+C sends SIGINT to the session process — use Ctrl+B d to detach s
 				}
 			}
 
+			if noHistory && cmd.Flags().Changed("history-lines") {
+				return fmt.Errorf("--no-history cannot be combined with --history-lines")
+			}
+			if exitOnComplete != "" && exitOnComplete != "code-passthrough" {
+				return fmt.Errorf("invalid --exit-on-complete mode %q (expected code-passthrough)", exitOnComplete)
+			}
+			if raw && (confirmEnter || localEcho || scriptPath != "" || followNew) {
+				return fmt.Errorf("--raw cannot be combined with --confirm-enter, --local-echo, --script or --follow-new")
+			}
+			if latest && len(args) > 0 {
+				return fmt.Errorf("--latest cannot be combined with a session argument")
+			}
+			if followNew && exitOnComplete != "" {
+				return fmt.Errorf("--follow-new cannot be combined with --exit-on-complete")
+			}
+			var script *client.AttachScript
+			if scriptPath != "" {
+				script, err = client.LoadAttachScript(scriptPath)
+				if err != nil {
+					return err
+				}
+			}
+
 			var id *uint32
 			if len(args) > 0 {
 				resolved, err := client.ResolveSessionArg(target, args[0])
@@ -343,11 +719,273 @@ Warning: Ctrl+C sends SIGINT to the session process — use Ctrl+B d to detach s
 				id = &resolved
 			}
 
-			return client.Attach(target, id, noHistory)
+			var historyLinesPtr *uint
+			if cmd.Flags().Changed("history-lines") {
+				historyLinesPtr = &historyLines
+			}
+
+			return exitWith(client.Attach(target, id, client.AttachOptions{
+				NoHistory:      noHistory,
+				HistoryLines:   historyLinesPtr,
+				ExitOnComplete: exitOnComplete != "",
+				ConfirmEnter:   confirmEnter,
+				LocalEcho:      localEcho,
+				LatencyDir:     dataDir(),
+				Raw:            raw,
+				Script:         script,
+				Colors:         colors,
+				Latest:         latest,
+				FollowNew:      followNew,
+			}))
 		},
 	}
 
 	cmd.Flags().BoolVar(&noHistory, "no-history", false, "Do not replay session history")
+	cmd.Flags().UintVar(&historyLines, "history-lines", 0, "Replay only the last N lines of session history")
+	cmd.Flags().StringVar(&exitOnComplete, "exit-on-complete", "", "When the session ends, exit with its exit code (mode: code-passthrough)")
+	cmd.Flags().Lookup("exit-on-complete").NoOptDefVal = "code-passthrough"
+	cmd.Flags().BoolVar(&confirmEnter, "confirm-enter", false, "Require Enter twice (or Ctrl+B Enter) before sending a newline")
+	cmd.Flags().BoolVar(&localEcho, "local-echo", false, "Show typed characters immediately instead of waiting for the remote echo")
+	cmd.Flags().StringVar(&scriptPath, "script", "", "Run a send/expect TOML script before handing control to you")
+	cmd.Flags().BoolVar(&raw, "raw", false, "Pass bytes through unchanged: no status bar and no detach key (detach by closing the connection or stdin)")
+	cmd.Flags().StringVar(&colors, "colors", "", "Color depth of this terminal: truecolor, 256 or 16 (default: detected from COLORTERM and TERM)")
+	cmd.Flags().BoolVar(&latest, "latest", false, "Attach to the newest running session instead of the oldest unattached one")
+	cmd.Flags().BoolVar(&followNew, "follow-new", false, "When the session ends, attach to the next session launched (until you detach)")
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// signalCmd
+// ---------------------------------------------------------------------------
+
+func signalCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "signal <session> [signal]",
+		Short: "Send a signal to a session (default INT, like Ctrl+C)",
+		Long: `Send a signal to the foreground process group of a session's terminal —
+the processes Ctrl+C would reach — without killing the session.
+
+The signal may be a name with or without the SIG prefix (INT, SIGTERM, usr1)
+or a number. It defaults to INT.`,
+		Args:              cobra.RangeArgs(1, 2),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			resolved, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+
+			signal := "INT"
+			if len(args) > 1 {
+				signal = args[1]
+			}
+			return client.Signal(target, resolved, signal)
+		},
+	}
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// noteCmd
+// ---------------------------------------------------------------------------
+
+func noteCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "note <session> <text>",
+		Short: "Leave a note on a session, shown to everyone who attaches",
+		Long: `Leave a note on a session. Notes are printed above the replay when anyone
+attaches, and listed by 'cw status', so whoever picks the session up next
+sees the context left for them. Use 'cw run --note' to add notes at launch.`,
+		Args:              cobra.MinimumNArgs(2),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			resolved, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+			return client.AddNote(target, resolved, strings.Join(args[1:], " "))
+		},
+	}
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// mirrorCmd
+// ---------------------------------------------------------------------------
+
+func mirrorCmd() *cobra.Command {
+	var to []string
+
+	cmd := &cobra.Command{
+		Use:   "mirror <session> --to <sink>",
+		Short: "Copy a running session's output to a file or command",
+		Long: `Copy a running session's output, from now on, to extra sinks alongside its
+log. A sink is file:/path (appended to) or cmd:<command> (run through sh -c
+in the session's directory, output on its stdin). Sinks run on the node and
+stop when the session ends. Use 'cw run --mirror' to mirror from launch.
+
+  cw mirror 3 --to file:/var/log/agents/3.log
+  cw mirror worker --to 'cmd:logger -t worker'`,
+		Args:              cobra.ExactArgs(1),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			if len(to) == 0 {
+				return fmt.Errorf("--to is required")
+			}
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			specs, err := mirrorSpecs(target, to)
+			if err != nil {
+				return err
+			}
+			resolved, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+			return client.AddMirror(target, resolved, specs)
+		},
+	}
+
+	cmd.Flags().StringArrayVar(&to, "to", nil, "Sink: file:/path or cmd:<command> (can be repeated)")
+
+	return cmd
+}
+
+// mirrorSpecs makes relative file: sinks absolute for the local node, which
+// runs in a different directory; the node validates the rest.
+func mirrorSpecs(target *client.Target, specs []string) ([]string, error) {
+	out := make([]string, len(specs))
+	for i, spec := range specs {
+		if path, ok := strings.CutPrefix(spec, "file:"); ok && target.IsLocal() && path != "" && !filepath.IsAbs(path) {
+			abs, err := filepath.Abs(path)
+			if err != nil {
+				return nil, err
+			}
+			spec = "file:" + abs
+		}
+		out[i] = spec
+	}
+	return out, nil
+}
+
+// ---------------------------------------------------------------------------
+// extendCmd
+// ---------------------------------------------------------------------------
+
+func extendCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "extend <session> <duration>",
+		Short: "Push back a session's max-runtime and idle deadlines",
+		Long: `Push back the limits set by --max-runtime and --idle-timeout (or the node's
+[launch] defaults). The max-runtime deadline moves by the given duration, and
+the idle limit is held off for that long past a full idle period from now.
+
+  cw extend 3 1h
+  cw extend worker 30m`,
+		Args:              cobra.ExactArgs(2),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			resolved, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+			return client.Extend(target, resolved, args[1])
+		},
+	}
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// setPriorityCmd
+// ---------------------------------------------------------------------------
+
+func setPriorityCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "set-priority <session> <high|normal|background>",
+		Short: "Change a session's priority class",
+		Long: `Change the priority class a session was launched with (cw run --priority).
+Queued launches of higher classes start first, high-priority output counts
+half against a remote token's bandwidth cap and background output double,
+and with guardrails.preempt a breached launch kills the newest session of
+the lowest class below its own.
+
+  cw set-priority 3 background
+  cw set-priority deploy high`,
+		Args: cobra.ExactArgs(2),
+		ValidArgsFunction: func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+			switch len(args) {
+			case 0:
+				return sessionCompletionFunc(cmd, args, toComplete)
+			case 1:
+				return []string{"high", "normal", "background"}, cobra.ShellCompDirectiveNoFileComp
+			}
+			return nil, cobra.ShellCompDirectiveNoFileComp
+		},
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			resolved, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+			return client.SetPriority(target, resolved, args[1])
+		},
+	}
 
 	return cmd
 }
@@ -356,10 +994,16 @@ Warning: Ctrl+C sends SIGINT to the session process — use Ctrl+B d to detach s
 // killCmd
 // ---------------------------------------------------------------------------
 
+// bulkKillConfirmThreshold is how many matching sessions a bulk kill may
+// hit before cw asks for confirmation (skipped with --yes).
+const bulkKillConfirmThreshold = 3
+
 func killCmd() *cobra.Command {
 	var (
-		all  bool
-		tags []string
+		all    bool
+		tags   []string
+		dryRun bool
+		yes    bool
 	)
 
 	cmd := &cobra.Command{
@@ -367,7 +1011,7 @@ func killCmd() *cobra.Command {
 		Short:             "Kill a session (by ID, name, or tag), or all sessions",
 		ValidArgsFunction: sessionCompletionFunc,
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -378,12 +1022,42 @@ func killCmd() *cobra.Command {
 				}
 			}
 
+			bulk := func(tags []string) error {
+				matches, err := client.KillMatches(target, tags)
+				if err != nil {
+					return err
+				}
+				if len(matches) == 0 {
+					fmt.Fprintln(os.Stderr, "No running sessions match")
+					return nil
+				}
+				if dryRun {
+					client.PrintKillMatches(matches)
+					fmt.Fprintf(os.Stderr, "\n%d session(s) would be killed\n", len(matches))
+					return nil
+				}
+				if len(matches) > bulkKillConfirmThreshold && !yes {
+					if !term.IsTerminal(int(os.Stdin.Fd())) {
+						return fmt.Errorf("refusing to kill %d sessions without --yes", len(matches))
+					}
+					client.PrintKillMatches(matches)
+					answer, err := prompt(fmt.Sprintf("\nKill these %d sessions? [y/N]: ", len(matches)))
+					if err != nil {
+						return err
+					}
+					if answer = strings.ToLower(answer); answer != "y" && answer != "yes" {
+						return fmt.Errorf("aborted")
+					}
+				}
+				return client.KillSessions(target, matches)
+			}
+
 			if all {
-				return client.KillAll(target)
+				return bulk(nil)
 			}
 
 			if len(tags) > 0 {
-				return client.KillByTags(target, tags)
+				return bulk(tags)
 			}
 
 			if len(args) == 0 {
@@ -395,7 +1069,11 @@ func killCmd() *cobra.Command {
 				return err
 			}
 			if len(tagList) > 0 {
-				return client.KillByTags(target, tagList)
+				return bulk(tagList)
+			}
+			if dryRun {
+				fmt.Fprintf(os.Stderr, "Session %d would be killed\n", *id)
+				return nil
 			}
 			return client.Kill(target, *id)
 		},
@@ -403,6 +1081,8 @@ func killCmd() *cobra.Command {
 
 	cmd.Flags().BoolVarP(&all, "all", "a", false, "Kill all sessions")
 	cmd.Flags().StringSliceVarP(&tags, "tag", "t", nil, "Kill sessions matching tag (can be repeated)")
+	cmd.Flags().BoolVar(&dryRun, "dry-run", false, "List the sessions that would be killed without killing them")
+	cmd.Flags().BoolVarP(&yes, "yes", "y", false, fmt.Sprintf("Skip confirmation when more than %d sessions match", bulkKillConfirmThreshold))
 	_ = cmd.RegisterFlagCompletionFunc("tag", tagCompletionFunc)
 
 	return cmd
@@ -414,18 +1094,35 @@ func killCmd() *cobra.Command {
 
 func logsCmd() *cobra.Command {
 	var (
-		follow bool
-		tail   int
-		raw    bool
+		follow        bool
+		tail          int
+		raw           bool
+		inputs        bool
+		noANSI        bool
+		verify        bool
+		unsafeEscapes bool
+		noPager       bool
+		replica       bool
+		rng           client.LogRange
 	)
 
 	cmd := &cobra.Command{
-		Use:               "logs <session>",
-		Short:             "View session output logs (by ID or name)",
+		Use:   "logs <session>",
+		Short: "View session output logs (by ID or name)",
+		Long: `Print a session's output log.
+
+On a terminal, output without --follow is shown in a pager: $CW_PAGER, else
+$PAGER, else "less" (run with LESS=FRX unless LESS is set, so output that fits
+on one screen is printed directly and colors from --raw come through). Set
+CW_PAGER=cat or pass --no-pager to print directly. Redirected or piped output
+is never paged.
+
+--from-bookmark and --to-bookmark print only the output written between two
+bookmarks (dropped with Ctrl+B m while attached; cw status lists them).`,
 		Args:              cobra.ExactArgs(1),
 		ValidArgsFunction: sessionCompletionFunc,
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -436,6 +1133,7 @@ func logsCmd() *cobra.Command {
 				}
 			}
 
+			target.Replica = replica
 			resolved, err := client.ResolveSessionArg(target, args[0])
 			if err != nil {
 				return err
@@ -446,13 +1144,149 @@ func logsCmd() *cobra.Command {
 				tailPtr = &tail
 			}
 
-			return client.Logs(target, resolved, follow, tailPtr, raw)
+			if inputs {
+				return client.LogInputs(target, resolved, tailPtr)
+			}
+			if verify {
+				return client.VerifyLogs(target, resolved)
+			}
+
+			if raw && noANSI {
+				return fmt.Errorf("--raw and --no-ansi are mutually exclusive")
+			}
+			// --no-ansi fetches the raw log and strips it locally, so
+			// sequences split across follow chunks are handled too.
+			if noANSI {
+				raw = true
+			}
+			return withPager(!follow && !noPager, func(w io.Writer, stop <-chan struct{}) error {
+				return client.LogsUntil(stop, target, resolved, follow, tailPtr, raw, rng, sessionOutputWriter(w, noANSI, unsafeEscapes))
+			})
 		},
 	}
 
 	cmd.Flags().BoolVarP(&follow, "follow", "f", false, "Follow log output")
 	cmd.Flags().IntVarP(&tail, "tail", "t", 0, "Number of lines to show from end")
 	cmd.Flags().BoolVar(&raw, "raw", false, "Output raw log data without stripping ANSI escape codes")
+	cmd.Flags().BoolVar(&inputs, "inputs", false, "Show recorded input sent to the session instead of its output")
+	cmd.Flags().BoolVar(&noANSI, "no-ansi", false, "Strip ANSI escape sequences client-side for clean text")
+	cmd.Flags().BoolVar(&verify, "verify", false, "Check the log against its checksums for truncation or corruption")
+	cmd.Flags().BoolVar(&unsafeEscapes, "unsafe-escapes", false, "With --raw, pass title, clipboard and report-request escape sequences to the terminal")
+	cmd.Flags().BoolVar(&noPager, "no-pager", false, "Print directly instead of through $PAGER")
+	cmd.Flags().BoolVar(&replica, "replica", false, "Read the copy a standby node keeps of its primary's session")
+	cmd.Flags().StringVar(&rng.FromBookmark, "from-bookmark", "", "Start at this bookmark")
+	cmd.Flags().StringVar(&rng.ToBookmark, "to-bookmark", "", "Stop at this bookmark")
+
+	return cmd
+}
+
+// sessionOutputWriter wraps w, which cw logs and cw watch write session
+// output to. --no-ansi strips every escape sequence. Otherwise, on a terminal,
+// sequences a session could use against it (title and clipboard writes,
+// requests that make the terminal type a reply) are removed unless
+// unsafeEscapes is set; colors and cursor movement are kept.
+func sessionOutputWriter(w io.Writer, noANSI, unsafeEscapes bool) io.Writer {
+	switch {
+	case noANSI:
+		return ansi.NewWriter(w)
+	case !unsafeEscapes && term.IsTerminal(int(os.Stdout.Fd())):
+		return ansi.NewSanitizeWriter(w)
+	}
+	return w
+}
+
+// ---------------------------------------------------------------------------
+// peekCmd
+// ---------------------------------------------------------------------------
+
+func peekCmd() *cobra.Command {
+	var (
+		lines int
+		raw   bool
+	)
+
+	cmd := &cobra.Command{
+		Use:               "peek <session>",
+		Short:             "Print the last lines of a session's output and exit",
+		Args:              cobra.ExactArgs(1),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			resolved, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+
+			if lines < 1 {
+				return fmt.Errorf("--lines must be at least 1")
+			}
+			return client.Logs(target, resolved, false, &lines, raw, client.LogRange{}, os.Stdout)
+		},
+	}
+
+	cmd.Flags().IntVarP(&lines, "lines", "n", 40, "Number of lines to show")
+	cmd.Flags().BoolVar(&raw, "raw", false, "Keep ANSI escape codes in the output")
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// replayCmd
+// ---------------------------------------------------------------------------
+
+func replayCmd() *cobra.Command {
+	var (
+		onto    string
+		noDelay bool
+	)
+
+	cmd := &cobra.Command{
+		Use:               "replay <session> --onto <session>",
+		Short:             "Re-send a session's recorded input to another session",
+		Args:              cobra.ExactArgs(1),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			from, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+			to, err := client.ResolveSessionArg(target, onto)
+			if err != nil {
+				return err
+			}
+			if from == to {
+				return fmt.Errorf("cannot replay a session onto itself")
+			}
+
+			return client.Replay(target, from, to, noDelay)
+		},
+	}
+
+	cmd.Flags().StringVar(&onto, "onto", "", "Session (ID or name) to send the recorded input to")
+	cmd.Flags().BoolVar(&noDelay, "no-delay", false, "Send inputs back to back instead of preserving original timing")
+	_ = cmd.MarkFlagRequired("onto")
+	_ = cmd.RegisterFlagCompletionFunc("onto", sessionCompletionFunc)
 
 	return cmd
 }
@@ -466,15 +1300,27 @@ func sendCmd() *cobra.Command {
 		useStdin  bool
 		file      string
 		noNewline bool
+		keys      []string
+		template  string
+		vars      []string
 	)
 
 	cmd := &cobra.Command{
 		Use:               "send <session> [input]",
 		Short:             "Send input to a session (by ID or name)",
+		Long: `Send input to a session without attaching.
+
+--key sends a named key after any text, in the order given: ctrl-<letter>,
+esc, tab, enter, backspace, delete, up, down, left, right, home, end, pgup,
+pgdn, shift-tab and f1-f12. When --key is used no newline is appended, so end
+with --key enter to submit.
+
+--template renders a file before sending it: each {{name}} is replaced by
+the value given with --var name=value. Sending fails if a variable is unset.`,
 		Args:              cobra.RangeArgs(1, 2),
 		ValidArgsFunction: sessionCompletionFunc,
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -494,19 +1340,92 @@ func sendCmd() *cobra.Command {
 			if len(args) > 1 {
 				input = &args[1]
 			}
+			if template != "" {
+				if input != nil || useStdin || cmd.Flags().Changed("file") {
+					return fmt.Errorf("--template cannot be combined with other input")
+				}
+				values, err := tmpl.ParseVars(vars)
+				if err != nil {
+					return err
+				}
+				rendered, err := tmpl.RenderFile(template, values)
+				if err != nil {
+					return err
+				}
+				input = &rendered
+			} else if len(vars) > 0 {
+				return fmt.Errorf("--var requires --template")
+			}
 
 			var filePtr *string
 			if cmd.Flags().Changed("file") {
 				filePtr = &file
 			}
 
-			return client.SendInput(target, resolved, input, useStdin, filePtr, noNewline)
+			return client.SendInput(target, resolved, input, useStdin, filePtr, noNewline, keys)
 		},
 	}
 
 	cmd.Flags().BoolVar(&useStdin, "stdin", false, "Read input from stdin")
 	cmd.Flags().StringVarP(&file, "file", "f", "", "Read input from file")
 	cmd.Flags().BoolVarP(&noNewline, "no-newline", "n", false, "Do not append newline")
+	cmd.Flags().StringVar(&template, "template", "", "Render a template file with --var values and send it")
+	cmd.Flags().StringArrayVar(&vars, "var", nil, "Template variable as name=value (repeatable)")
+	cmd.Flags().StringArrayVarP(&keys, "key", "k", nil, "Send a named key after the text, e.g. ctrl-c, esc, tab, up, enter (repeatable)")
+	_ = cmd.RegisterFlagCompletionFunc("key", func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+		return append(terminal.KeyNames(), "ctrl-c", "ctrl-d"), cobra.ShellCompDirectiveNoFileComp
+	})
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// diffCmd
+// ---------------------------------------------------------------------------
+
+func diffCmd() *cobra.Command {
+	var stripANSI bool
+
+	cmd := &cobra.Command{
+		Use:   "diff <session> <session>",
+		Short: "Show a unified diff of two sessions' output",
+		Long: `Compare the output of two sessions, for example two agent attempts at the
+same task. Either session may be a node:id reference on another server.
+Exits 1 when the outputs differ, like diff(1).`,
+		Args:              cobra.ExactArgs(2),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			var targets [2]*client.Target
+			var ids [2]uint32
+			for i, arg := range args {
+				target, err := resolveSessionTarget([]string{arg})
+				if err != nil {
+					return err
+				}
+				if target.IsLocal() {
+					if err := ensureNode(); err != nil {
+						return err
+					}
+				}
+				id, err := client.ResolveSessionArg(target, arg)
+				if err != nil {
+					return err
+				}
+				targets[i], ids[i] = target, id
+			}
+
+			differ, err := client.Diff(targets[0], ids[0], targets[1], ids[1], stripANSI, os.Stdout)
+			if err != nil {
+				return err
+			}
+			if differ {
+				os.Exit(1)
+			}
+			return nil
+		},
+	}
+
+	cmd.Flags().BoolVar(&stripANSI, "strip-ansi", false, "Remove ANSI escape sequences before comparing")
 
 	return cmd
 }
@@ -517,9 +1436,14 @@ func sendCmd() *cobra.Command {
 
 func watchCmd() *cobra.Command {
 	var (
-		tail      int
-		noHistory bool
-		timeout   uint64
+		tail          int
+		noHistory     bool
+		timeout       uint64
+		noANSI        bool
+		summary       time.Duration
+		maxRate       uint32
+		unsafeEscapes bool
+		resumeFrom    uint64
 	)
 
 	cmd := &cobra.Command{
@@ -527,46 +1451,174 @@ func watchCmd() *cobra.Command {
 		Short:             "Watch session output in real-time (by ID, name, or tag for multi-session)",
 		Args:              cobra.ExactArgs(1),
 		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			id, tagList, err := client.ResolveSessionOrTag(target, args[0])
+			if err != nil {
+				return err
+			}
+
+			if summary > 0 {
+				var timeoutPtr *uint64
+				if cmd.Flags().Changed("timeout") {
+					timeoutPtr = &timeout
+				}
+				if len(tagList) > 0 {
+					return client.WatchSummaryByTag(target, tagList[0], summary, timeoutPtr, os.Stdout)
+				}
+				return client.WatchSummary(target, *id, summary, timeoutPtr, os.Stdout)
+			}
+
+			out := sessionOutputWriter(os.Stdout, noANSI, unsafeEscapes)
+
+			if len(tagList) > 0 {
+				var timeoutPtr *uint64
+				if cmd.Flags().Changed("timeout") {
+					timeoutPtr = &timeout
+				}
+				return client.WatchMultiByTag(target, tagList[0], out, timeoutPtr)
+			}
+
+			var tailPtr *int
+			if cmd.Flags().Changed("tail") {
+				tailPtr = &tail
+			}
+			var timeoutPtr *uint64
+			if cmd.Flags().Changed("timeout") {
+				timeoutPtr = &timeout
+			}
+			var resumePtr *uint64
+			if cmd.Flags().Changed("resume-from") {
+				resumePtr = &resumeFrom
+			}
+			return client.WatchSession(target, *id, tailPtr, noHistory, timeoutPtr, maxRate, resumePtr, out)
+		},
+	}
+
+	cmd.Flags().IntVarP(&tail, "tail", "t", 0, "Number of lines to show from end")
+	cmd.Flags().BoolVar(&noHistory, "no-history", false, "Do not replay session history")
+	cmd.Flags().Uint64Var(&resumeFrom, "resume-from", 0, "Continue from the output cursor an earlier watch stopped at, instead of replaying history")
+	cmd.Flags().Uint64Var(&timeout, "timeout", 0, "Timeout in seconds")
+	cmd.Flags().BoolVar(&noANSI, "no-ansi", false, "Strip ANSI escape sequences for clean text")
+	cmd.Flags().BoolVar(&unsafeEscapes, "unsafe-escapes", false, "Pass title, clipboard and report-request escape sequences to the terminal")
+	cmd.Flags().Uint32Var(&maxRate, "max-rate", 0, "Most updates per second the node sends; output in between is batched (default 20)")
+	cmd.Flags().DurationVar(&summary, "summary", 0, "Instead of output, print a summary (lines/sec, last line, status changes) at this interval, e.g. 30s")
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// monitorCmd
+// ---------------------------------------------------------------------------
+
+func monitorCmd() *cobra.Command {
+	var (
+		patterns []string
+		once     bool
+		cooldown time.Duration
+		timeout  uint64
+	)
+
+	cmd := &cobra.Command{
+		Use:   "monitor <session> --on <pattern> <command> [--on <pattern> <command>...]",
+		Short: "Run commands when a session's output matches patterns",
+		Long: `Watch a running session's output and run a command whenever a line matches
+a rule. Each --on takes a regular expression; the commands follow in the
+same order. Commands run through sh -c with {id} replaced by the session's
+ID and {name} by its name; the matching line is in $CW_MATCH_LINE and the
+pattern in $CW_MATCH_PATTERN. Monitoring stops when the session ends.
+
+  cw monitor 3 --on 'ERROR' 'cw send {id} "please fix the error above"' \
+               --on 'ALL TESTS PASSED' 'cw kill {id}'`,
+		Args:              cobra.MinimumNArgs(1),
+		ValidArgsFunction: sessionCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			rules, err := client.ParseMonitorRules(patterns, args[1:])
+			if err != nil {
+				return err
+			}
+
+			target, err := resolveSessionTarget(args[:1])
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			id, err := client.ResolveSessionArg(target, args[0])
+			if err != nil {
+				return err
+			}
+
+			opts := client.MonitorOptions{Rules: rules, Cooldown: cooldown, Once: once}
+			if cmd.Flags().Changed("timeout") {
+				opts.Timeout = &timeout
+			}
+			return client.Monitor(target, id, opts, os.Stdout)
+		},
+	}
+
+	cmd.Flags().StringArrayVar(&patterns, "on", nil, "Regular expression to react to; its command is the matching positional argument (can be repeated)")
+	cmd.Flags().BoolVar(&once, "once", false, "Run each rule at most once")
+	cmd.Flags().DurationVar(&cooldown, "cooldown", 5*time.Second, "Least time between two runs of the same rule")
+	cmd.Flags().Uint64Var(&timeout, "timeout", 0, "Stop monitoring after this many seconds")
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// findCmd
+// ---------------------------------------------------------------------------
+
+func findCmd() *cobra.Command {
+	var (
+		jsonOutput bool
+		limit      uint32
+		table      client.TableOptions
+	)
+
+	cmd := &cobra.Command{
+		Use:   "find <query>",
+		Short: "Fuzzy-search sessions by name, tag, prompt or working directory",
+		Long: `Search sessions on the node, best match first. The query's characters
+must appear in order (ignoring case) in a session's name, one of its tags,
+its prompt or its working directory; a session ID matches exactly.`,
+		Args: cobra.ExactArgs(1),
 		RunE: func(cmd *cobra.Command, args []string) error {
 			target, err := resolveTarget()
 			if err != nil {
 				return err
 			}
-
 			if target.IsLocal() {
 				if err := ensureNode(); err != nil {
 					return err
 				}
 			}
-
-			id, tagList, err := client.ResolveSessionOrTag(target, args[0])
-			if err != nil {
-				return err
-			}
-
-			if len(tagList) > 0 {
-				var timeoutPtr *uint64
-				if cmd.Flags().Changed("timeout") {
-					timeoutPtr = &timeout
-				}
-				return client.WatchMultiByTag(target, tagList[0], os.Stdout, timeoutPtr)
-			}
-
-			var tailPtr *int
-			if cmd.Flags().Changed("tail") {
-				tailPtr = &tail
-			}
-			var timeoutPtr *uint64
-			if cmd.Flags().Changed("timeout") {
-				timeoutPtr = &timeout
-			}
-			return client.WatchSession(target, *id, tailPtr, noHistory, timeoutPtr)
+			return client.Find(target, args[0], limit, jsonOutput, table)
 		},
 	}
 
-	cmd.Flags().IntVarP(&tail, "tail", "t", 0, "Number of lines to show from end")
-	cmd.Flags().BoolVar(&noHistory, "no-history", false, "Do not replay session history")
-	cmd.Flags().Uint64Var(&timeout, "timeout", 0, "Timeout in seconds")
+	cmd.Flags().BoolVarP(&jsonOutput, "json", "j", false, "Output as JSON")
+	cmd.Flags().Uint32VarP(&limit, "limit", "n", 20, "Maximum number of matches (0 for all)")
+	cmd.Flags().StringVar(&table.Columns, "columns", client.DefaultColumns, "Comma-separated columns: "+strings.Join(client.ColumnNames(), ", "))
+	cmd.Flags().StringVar(&table.Time, "time", client.TimeRelative, "Timestamp style: relative, absolute or iso")
+	_ = cmd.RegisterFlagCompletionFunc("time", func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+		return client.TimeFormats(), cobra.ShellCompDirectiveNoFileComp
+	})
 
 	return cmd
 }
@@ -576,7 +1628,12 @@ func watchCmd() *cobra.Command {
 // ---------------------------------------------------------------------------
 
 func statusCmd() *cobra.Command {
-	var jsonOutput bool
+	var (
+		jsonOutput bool
+		diff       bool
+		timeMode   string
+		replica    bool
+	)
 
 	cmd := &cobra.Command{
 		Use:               "status <session>",
@@ -584,7 +1641,11 @@ func statusCmd() *cobra.Command {
 		Args:              cobra.ExactArgs(1),
 		ValidArgsFunction: sessionCompletionFunc,
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			if err := client.ValidateTimeFormat(timeMode); err != nil {
+				return err
+			}
+
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -595,16 +1656,23 @@ func statusCmd() *cobra.Command {
 				}
 			}
 
+			target.Replica = replica
 			resolved, err := client.ResolveSessionArg(target, args[0])
 			if err != nil {
 				return err
 			}
 
-			return client.GetStatus(target, resolved, jsonOutput)
+			return client.GetStatus(target, resolved, jsonOutput, diff, timeMode)
 		},
 	}
 
 	cmd.Flags().BoolVarP(&jsonOutput, "json", "j", false, "Output as JSON")
+	cmd.Flags().BoolVar(&diff, "diff", false, "Print the working directory snapshot captured on exit (see cw run --snapshot)")
+	cmd.Flags().StringVar(&timeMode, "time", client.TimeISO, "Timestamp style: relative, absolute or iso")
+	_ = cmd.RegisterFlagCompletionFunc("time", func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+		return client.TimeFormats(), cobra.ShellCompDirectiveNoFileComp
+	})
+	cmd.Flags().BoolVar(&replica, "replica", false, "Read the copy a standby node keeps of its primary's session")
 
 	return cmd
 }
@@ -614,7 +1682,9 @@ func statusCmd() *cobra.Command {
 // ---------------------------------------------------------------------------
 
 func mcpServerCmd() *cobra.Command {
-	return &cobra.Command{
+	var opts mcp.Options
+
+	cmd := &cobra.Command{
 		Use:   "mcp-server",
 		Short: "Run the MCP (Model Context Protocol) server",
 		Long: `Run the Codewire MCP server (communicates over stdio).
@@ -625,14 +1695,27 @@ To register with Claude Code:
 The node must be running before MCP tools work:
   cw node -d
 
-The MCP server does NOT auto-start a node.`,
+The MCP server does NOT auto-start a node.
+
+When the host declares roots (e.g. the folders open in an IDE), launches
+without a working_dir run in the first root. With --strict-roots, launches
+outside every declared root are refused, so an agent cannot start sessions
+in $HOME by accident.
+
+Tool calls run concurrently (--max-concurrent-tools) and each is answered
+within --tool-timeout, plus the wait it asks for (timeout_seconds or
+max_duration_seconds), so one stuck call cannot stall the session.`,
 		RunE: func(cmd *cobra.Command, args []string) error {
 			if err := ensureNode(); err != nil {
 				return err
 			}
-			return mcp.RunMCPServer(dataDir())
+			return mcp.RunMCPServer(dataDir(), opts)
 		},
 	}
+	cmd.Flags().BoolVar(&opts.StrictRoots, "strict-roots", false, "Refuse launches outside the roots declared by the MCP host")
+	cmd.Flags().DurationVar(&opts.ToolTimeout, "tool-timeout", 60*time.Second, "Answer a tool call with an error if it runs longer than this (plus the wait it asks for)")
+	cmd.Flags().IntVar(&opts.MaxConcurrentTools, "max-concurrent-tools", 4, "Tool calls allowed to run at once")
+	return cmd
 }
 
 // ---------------------------------------------------------------------------
@@ -640,17 +1723,37 @@ The MCP server does NOT auto-start a node.`,
 // ---------------------------------------------------------------------------
 
 func nodesCmd() *cobra.Command {
-	return &cobra.Command{
+	var opts client.NodesOptions
+
+	cmd := &cobra.Command{
 		Use:   "nodes",
 		Short: "List registered nodes from the relay",
+		Long: `List the nodes registered with the relay.
+
+Each successful listing is cached in the data directory. Scripts that run
+many commands can reuse it instead of asking the relay every time: --cached
+uses the cached list whatever its age, --max-age only when it is recent
+enough. Either way the relay is asked when nothing usable is cached.
+
+The relay gets --timeout to answer. If it fails or is too slow, the last
+cached listing is shown with a warning instead of an error.
+
+--wait-for asks the relay again until the named nodes are online, for up to
+--timeout. Nodes still missing are listed and the command exits non-zero.`,
 		RunE: func(cmd *cobra.Command, args []string) error {
 			relayURL, err := resolveRelayURL()
 			if err != nil {
 				return err
 			}
-			return client.Nodes(relayURL)
+			opts.DataDir = dataDir()
+			return client.Nodes(relayURL, opts)
 		},
 	}
+	cmd.Flags().BoolVar(&opts.Cached, "cached", false, "Use the last successful listing instead of asking the relay")
+	cmd.Flags().DurationVar(&opts.MaxAge, "max-age", 0, "Use the last listing if it is younger than this (e.g. 30s)")
+	cmd.Flags().DurationVar(&opts.Timeout, "timeout", 5*time.Second, "How long to wait for the relay (and for --wait-for)")
+	cmd.Flags().StringSliceVar(&opts.WaitFor, "wait-for", nil, "Wait until these nodes are online (comma-separated)")
+	return cmd
 }
 
 // ---------------------------------------------------------------------------
@@ -668,7 +1771,7 @@ func subscribeCmd() *cobra.Command {
 		Short: "Subscribe to session events",
 		Args:  cobra.MaximumNArgs(1),
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -698,20 +1801,101 @@ func subscribeCmd() *cobra.Command {
 	cmd.Flags().StringSliceVarP(&tags, "tag", "t", nil, "Filter by tag (can be repeated)")
 	cmd.Flags().StringSliceVarP(&eventTypes, "event", "e", nil, "Filter by event type (can be repeated)")
 	_ = cmd.RegisterFlagCompletionFunc("tag", tagCompletionFunc)
-	_ = cmd.RegisterFlagCompletionFunc("event", func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
-		return []string{
-			"session.created",
-			"session.status",
-			"session.output_summary",
-			"message.direct",
-			"message.request",
-			"message.reply",
-		}, cobra.ShellCompDirectiveNoFileComp
-	})
+	_ = cmd.RegisterFlagCompletionFunc("event", eventTypeCompletionFunc)
+
+	return cmd
+}
+
+// ---------------------------------------------------------------------------
+// eventsCmd — query the node's event store
+// ---------------------------------------------------------------------------
+
+func eventsCmd() *cobra.Command {
+	var (
+		tags       []string
+		eventTypes []string
+		since      string
+		until      string
+		limit      uint32
+		follow     bool
+		jsonOutput bool
+	)
+
+	cmd := &cobra.Command{
+		Use:   "events [target]",
+		Short: "Show recorded session events",
+		Long: `Show session events the node has recorded: launches, status changes,
+output milestones, input sent, attaches and detaches, expiry and messages.
+The node keeps them in events.db for node.event_retention (default 30 days),
+so unlike subscribe this covers sessions that have already finished.
+
+  cw events 3                        # the last 100 events of session 3
+  cw events -t build --since 1h      # events of sessions tagged build
+  cw events -e session.status -f     # status changes, following new ones`,
+		Args: cobra.MaximumNArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveSessionTarget(args)
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			q := client.EventQuery{
+				Tags:  tags,
+				Types: eventTypes,
+				Since: since,
+				Until: until,
+				Limit: limit,
+			}
+			if len(args) > 0 {
+				id, tagList, err := client.ResolveSessionOrTag(target, args[0])
+				if err != nil {
+					return err
+				}
+				q.SessionID = id
+				q.Tags = append(tagList, tags...)
+			}
+
+			return client.Events(target, q, jsonOutput, follow, os.Stdout)
+		},
+	}
+
+	cmd.Flags().StringSliceVarP(&tags, "tag", "t", nil, "Filter by tag (can be repeated)")
+	cmd.Flags().StringSliceVarP(&eventTypes, "event", "e", nil, "Filter by event type (can be repeated)")
+	cmd.Flags().StringVar(&since, "since", "", "Only events within this long (e.g. 12h, 30d) or after an RFC 3339 time")
+	cmd.Flags().StringVar(&until, "until", "", "Only events before this long ago or before an RFC 3339 time")
+	cmd.Flags().Uint32VarP(&limit, "limit", "n", 100, "Show at most this many of the newest matching events")
+	cmd.Flags().BoolVarP(&follow, "follow", "f", false, "Keep printing new events as they are recorded")
+	cmd.Flags().BoolVar(&jsonOutput, "json", false, "Print one JSON object per event")
+	_ = cmd.RegisterFlagCompletionFunc("tag", tagCompletionFunc)
+	_ = cmd.RegisterFlagCompletionFunc("event", eventTypeCompletionFunc)
 
 	return cmd
 }
 
+// eventTypeCompletionFunc completes session event types.
+func eventTypeCompletionFunc(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+	return []string{
+		"session.created",
+		"session.status",
+		"session.output_summary",
+		"session.input",
+		"session.input_undelivered",
+		"session.attached",
+		"session.detached",
+		"session.expiring",
+		"session.extended",
+		"direct.message",
+		"message.request",
+		"message.reply",
+	}, cobra.ShellCompDirectiveNoFileComp
+}
+
 // ---------------------------------------------------------------------------
 // waitSessionCmd — wait for session(s) to complete
 // ---------------------------------------------------------------------------
@@ -727,7 +1911,7 @@ func waitSessionCmd() *cobra.Command {
 		Use:   "wait [session]",
 		Short: "Wait for session(s) to complete (by ID or name)",
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -770,6 +1954,98 @@ func waitSessionCmd() *cobra.Command {
 	return cmd
 }
 
+// ---------------------------------------------------------------------------
+// historyCmd — export/import of finished sessions
+// ---------------------------------------------------------------------------
+
+func historyCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "history",
+		Short: "Export or import finished session records",
+		Long: `Move finished sessions between nodes or archive them centrally. Records
+carry each session's metadata (command, status, exit code, tags, notes,
+timings) and, with --transcripts, its output log.
+
+  cw history export --since 30d --transcripts > runs.json
+  cw --server other-box history import runs.json`,
+	}
+
+	cmd.AddCommand(
+		historyExportCmd(),
+		historyImportCmd(),
+	)
+
+	return cmd
+}
+
+func historyExportCmd() *cobra.Command {
+	var (
+		since       string
+		transcripts bool
+		rng         client.LogRange
+	)
+
+	cmd := &cobra.Command{
+		Use:   "export",
+		Short: "Write finished sessions as JSON to stdout",
+		Args:  cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveTarget()
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			return client.ExportHistory(target, since, transcripts, rng, os.Stdout)
+		},
+	}
+
+	cmd.Flags().StringVar(&since, "since", "", "Only sessions created within this long (e.g. 12h, 30d)")
+	cmd.Flags().BoolVar(&transcripts, "transcripts", false, "Include each session's output log")
+	cmd.Flags().StringVar(&rng.FromBookmark, "from-bookmark", "", "Only sessions with this bookmark; transcripts start at it")
+	cmd.Flags().StringVar(&rng.ToBookmark, "to-bookmark", "", "Only sessions with this bookmark; transcripts stop at it")
+
+	return cmd
+}
+
+func historyImportCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "import [file]",
+		Short: "Add exported sessions to a node (reads stdin without a file)",
+		Args:  cobra.MaximumNArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveTarget()
+			if err != nil {
+				return err
+			}
+
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+
+			in := io.Reader(os.Stdin)
+			if len(args) == 1 {
+				f, err := os.Open(args[0])
+				if err != nil {
+					return err
+				}
+				defer f.Close()
+				in = f
+			}
+			return client.ImportHistory(target, in)
+		},
+	}
+
+	return cmd
+}
+
 // ---------------------------------------------------------------------------
 // kvCmd — key-value store subcommand group
 // ---------------------------------------------------------------------------
@@ -925,6 +2201,9 @@ func serverCmd() *cobra.Command {
 		serverAddCmd(),
 		serverRemoveCmd(),
 		serverListCmd(),
+		serverUpgradeCmd(),
+		serverTestCmd(),
+		serverTrustCmd(),
 	)
 
 	return cmd
@@ -947,10 +2226,18 @@ func serverAddCmd() *cobra.Command {
 				return err
 			}
 
-			servers.Servers[name] = config.ServerEntry{
+			entry := config.ServerEntry{
 				URL:   url,
 				Token: token,
 			}
+			if _, pub, err := client.Identify(&client.Target{URL: url, Token: token, Name: name}); err != nil {
+				fmt.Fprintf(os.Stderr, "[cw] warning: could not verify the node's identity, so its key is not pinned: %v\n", err)
+				fmt.Fprintf(os.Stderr, "[cw] run 'cw server trust %s' once it is reachable\n", name)
+			} else {
+				entry.NodeKey = base64.StdEncoding.EncodeToString(pub)
+				fmt.Fprintf(os.Stderr, "Pinned node key %s\n", auth.Fingerprint(pub))
+			}
+			servers.Servers[name] = entry
 
 			if err := servers.Save(dir); err != nil {
 				return err
@@ -968,9 +2255,10 @@ func serverAddCmd() *cobra.Command {
 
 func serverRemoveCmd() *cobra.Command {
 	return &cobra.Command{
-		Use:   "remove <name>",
-		Short: "Remove a saved server",
-		Args:  cobra.ExactArgs(1),
+		Use:               "remove <name>",
+		Short:             "Remove a saved server",
+		Args:              cobra.ExactArgs(1),
+		ValidArgsFunction: serverNameCompletionFunc,
 		RunE: func(cmd *cobra.Command, args []string) error {
 			name := args[0]
 			dir := dataDir()
@@ -1011,10 +2299,166 @@ func serverListCmd() *cobra.Command {
 				return nil
 			}
 
-			fmt.Printf("%-20s %s\n", "NAME", "URL")
+			fmt.Printf("%-20s %-52s %s\n", "NAME", "NODE KEY", "URL")
 			for name, entry := range servers.Servers {
-				fmt.Printf("%-20s %s\n", name, entry.URL)
+				key := "-"
+				if pub := entry.PinnedKey(); pub != nil {
+					key = auth.Fingerprint(pub)
+				}
+				fmt.Printf("%-20s %-52s %s\n", name, key, entry.URL)
+			}
+			return nil
+		},
+	}
+}
+
+func serverUpgradeCmd() *cobra.Command {
+	var (
+		to             string
+		drainTimeout   time.Duration
+		allowDowngrade bool
+	)
+
+	cmd := &cobra.Command{
+		Use:   "upgrade [name...]",
+		Short: "Roll a new cw release out to saved servers, one at a time",
+		Long: `Upgrade saved servers (default: all of them) one at a time.
+
+Each node downloads the binary for --to (a release tag, or the URL of a
+release asset named cw-<version>-<platform> with a signed <url>.sha256 naming
+it), verifies its checksum, stops accepting new sessions, waits for running
+sessions to finish, replaces itself, and restarts. The rollout stops at the
+first node that fails so the rest keep the old version.
+
+A node refuses a release older than the one it runs unless --allow-downgrade
+is passed, so an old signed release cannot be used to roll it back.`,
+		ValidArgsFunction: serverNameCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			servers, err := config.LoadServersConfig(dataDir())
+			if err != nil {
+				return err
+			}
+
+			names := args
+			if len(names) == 0 {
+				for name := range servers.Servers {
+					names = append(names, name)
+				}
+				sort.Strings(names)
+			}
+			if len(names) == 0 {
+				return fmt.Errorf("no saved servers (add one with: cw server add)")
+			}
+
+			for i, name := range names {
+				entry, ok := servers.Servers[name]
+				if !ok {
+					return fmt.Errorf("unknown server %q", name)
+				}
+				target := serverTarget(name, entry)
+
+				start := time.Now()
+				fmt.Fprintf(os.Stderr, "[cw] [%d/%d] %s: upgrading\n", i+1, len(names), name)
+				got, err := client.UpgradeNode(target, to, drainTimeout, allowDowngrade, func(msg string) {
+					fmt.Fprintf(os.Stderr, "[cw] [%d/%d] %s: %s\n", i+1, len(names), name, msg)
+				})
+				if err != nil {
+					if rest := names[i+1:]; len(rest) > 0 {
+						fmt.Fprintf(os.Stderr, "[cw] not upgraded: %s\n", strings.Join(rest, ", "))
+					}
+					return fmt.Errorf("%s: %w", name, err)
+				}
+				fmt.Fprintf(os.Stderr, "[cw] [%d/%d] %s: upgraded to %s (%s)\n", i+1, len(names), name, got, time.Since(start).Round(100*time.Millisecond))
+			}
+			return nil
+		},
+	}
+
+	cmd.Flags().StringVar(&to, "to", "", "Release tag (e.g. v0.3.1) or binary URL to install")
+	cmd.Flags().DurationVar(&drainTimeout, "drain-timeout", 10*time.Minute, "How long each node waits for running sessions before aborting")
+	cmd.Flags().BoolVar(&allowDowngrade, "allow-downgrade", false, "Allow installing a release older than the node's")
+	_ = cmd.MarkFlagRequired("to")
+
+	return cmd
+}
+
+func serverTestCmd() *cobra.Command {
+	var count int
+
+	cmd := &cobra.Command{
+		Use:   "test [name]",
+		Short: "Measure round-trip latency to a server",
+		Long: `Send --count pings to a saved server (or the --server target, or the local
+node) and print the round-trip times, followed by the latency histogram
+recorded while attached to sessions on that node.`,
+		Args:              cobra.MaximumNArgs(1),
+		ValidArgsFunction: serverNameCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			var target *client.Target
+			if len(args) > 0 {
+				t, ok := savedServerTarget(args[0])
+				if !ok {
+					return fmt.Errorf("server %q not found", args[0])
+				}
+				target = t
+			} else {
+				t, err := resolveTarget()
+				if err != nil {
+					return err
+				}
+				if t.IsLocal() {
+					if err := ensureNode(); err != nil {
+						return err
+					}
+				}
+				target = t
+			}
+			return client.TestLatency(target, count, dataDir())
+		},
+	}
+
+	cmd.Flags().IntVarP(&count, "count", "c", 20, "Number of pings to send")
+
+	return cmd
+}
+
+func serverTrustCmd() *cobra.Command {
+	return &cobra.Command{
+		Use:   "trust <name>",
+		Short: "Pin a saved server's current node identity key",
+		Long: `Ask a saved server for its node identity key and pin it in servers.toml.
+Commands then refuse to talk to the server unless the node proves it holds
+that key. Use this after adding a server that was unreachable, or after
+its node was reinstalled and its key changed.`,
+		Args:              cobra.ExactArgs(1),
+		ValidArgsFunction: serverNameCompletionFunc,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			name := args[0]
+			dir := dataDir()
+			servers, err := config.LoadServersConfig(dir)
+			if err != nil {
+				return err
+			}
+			entry, ok := servers.Servers[name]
+			if !ok {
+				return fmt.Errorf("server %q not found", name)
 			}
+
+			target := serverTarget(name, entry)
+			target.NodeKey = nil
+			_, pub, err := client.Identify(target)
+			if err != nil {
+				return fmt.Errorf("verifying identity of %s: %w", name, err)
+			}
+			if old := entry.PinnedKey(); old != nil {
+				fmt.Fprintf(os.Stderr, "Replacing pinned key %s\n", auth.Fingerprint(old))
+			}
+			entry.NodeKey = base64.StdEncoding.EncodeToString(pub)
+			servers.Servers[name] = entry
+			if err := servers.Save(dir); err != nil {
+				return err
+			}
+			fmt.Fprintf(os.Stderr, "Pinned node key %s for %q\n", auth.Fingerprint(pub), name)
 			return nil
 		},
 	}
@@ -1235,7 +2679,7 @@ func msgCmd() *cobra.Command {
 		Short: "Send a message to a session (by ID or name)",
 		Args:  cobra.ExactArgs(2),
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -1302,7 +2746,7 @@ func inboxCmd() *cobra.Command {
 		Args:              cobra.ExactArgs(1),
 		ValidArgsFunction: sessionCompletionFunc,
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -1384,7 +2828,7 @@ func requestCmd() *cobra.Command {
 		Short: "Send a request to a session and wait for a reply",
 		Args:  cobra.ExactArgs(2),
 		RunE: func(cmd *cobra.Command, args []string) error {
-			target, err := resolveTarget()
+			target, err := resolveSessionTarget(args)
 			if err != nil {
 				return err
 			}
@@ -1581,11 +3025,16 @@ func completionCmd(rootCmd *cobra.Command) *cobra.Command {
 
 	cmd := &cobra.Command{
 		Use:       "completion [bash|zsh|fish|powershell]",
+		Aliases:   []string{"completions"},
 		Short:     "Generate or install shell completion scripts",
 		ValidArgs: []string{"bash", "zsh", "fish", "powershell"},
 		Args:      cobra.MaximumNArgs(1),
 		Long: `Generate shell completion scripts for cw.
 
+Session IDs and names, tags and saved server names complete from the
+node at Tab time, through the hidden "cw __complete" command; pass
+--server first to complete another node's sessions.
+
 Load in current session:
   source <(cw completion zsh)
   source <(cw completion bash)
@@ -1778,8 +3227,25 @@ func brewPrefix() string {
 // Completion helpers
 // ---------------------------------------------------------------------------
 
-func sessionCompletionFunc(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+// completionTimeout bounds the node queries made while completing, so Tab
+// against an unreachable server gives nothing instead of hanging the shell.
+const completionTimeout = 2 * time.Second
+
+// completionTarget is resolveTarget with the request timeout capped for
+// completion.
+func completionTarget() (*client.Target, error) {
 	target, err := resolveTarget()
+	if err != nil {
+		return nil, err
+	}
+	if target.Timeout == 0 || target.Timeout > completionTimeout {
+		target.Timeout = completionTimeout
+	}
+	return target, nil
+}
+
+func sessionCompletionFunc(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+	target, err := completionTarget()
 	if err != nil {
 		return nil, cobra.ShellCompDirectiveNoFileComp
 	}
@@ -1787,13 +3253,29 @@ func sessionCompletionFunc(cmd *cobra.Command, args []string, toComplete string)
 }
 
 func tagCompletionFunc(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
-	target, err := resolveTarget()
+	target, err := completionTarget()
 	if err != nil {
 		return nil, cobra.ShellCompDirectiveNoFileComp
 	}
 	return client.ListTagsForCompletion(target), cobra.ShellCompDirectiveNoFileComp
 }
 
+// serverNameCompletionFunc completes the names saved in servers.toml.
+func serverNameCompletionFunc(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+	servers, err := config.LoadServersConfig(dataDir())
+	if err != nil {
+		return nil, cobra.ShellCompDirectiveNoFileComp
+	}
+	var names []string
+	for name, entry := range servers.Servers {
+		if !slices.Contains(args, name) {
+			names = append(names, name+"\t"+entry.URL)
+		}
+	}
+	sort.Strings(names)
+	return names, cobra.ShellCompDirectiveNoFileComp
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -1812,26 +3294,19 @@ func resolveTarget() (*client.Target, error) {
 	dir := dataDir()
 
 	if serverFlag == "" {
-		return &client.Target{Local: dir}, nil
+		return &client.Target{Local: dir, Timeout: requestTimeoutFlag}, nil
 	}
 
 	// Check servers.toml for a named entry.
-	servers, err := config.LoadServersConfig(dir)
-	if err == nil {
-		if entry, ok := servers.Servers[serverFlag]; ok {
-			token := tokenFlag
-			if token == "" {
-				token = entry.Token
-			}
-			return &client.Target{URL: entry.URL, Token: token}, nil
-		}
+	if target, ok := savedServerTarget(serverFlag); ok {
+		return target, nil
 	}
 
 	// Treat serverFlag as a direct URL.
 	url := serverFlag
 	if strings.HasPrefix(url, "https://") || strings.HasPrefix(url, "http://") {
 		// Relay URL — token is optional (relay handles auth).
-		return &client.Target{URL: url, Token: tokenFlag}, nil
+		return &client.Target{URL: url, Token: tokenFlag, Timeout: requestTimeoutFlag}, nil
 	}
 
 	if tokenFlag == "" {
@@ -1842,7 +3317,47 @@ func resolveTarget() (*client.Target, error) {
 		url = "ws://" + url
 	}
 
-	return &client.Target{URL: url, Token: tokenFlag}, nil
+	return &client.Target{URL: url, Token: tokenFlag, Timeout: requestTimeoutFlag}, nil
+}
+
+// savedServerTarget returns the target for a servers.toml entry.
+func savedServerTarget(name string) (*client.Target, bool) {
+	servers, err := config.LoadServersConfig(dataDir())
+	if err != nil {
+		return nil, false
+	}
+	entry, ok := servers.Servers[name]
+	if !ok {
+		return nil, false
+	}
+	return serverTarget(name, entry), true
+}
+
+// serverTarget builds the target for a servers.toml entry. --token
+// overrides the saved token.
+func serverTarget(name string, entry config.ServerEntry) *client.Target {
+	token := tokenFlag
+	if token == "" {
+		token = entry.Token
+	}
+	return &client.Target{URL: entry.URL, Token: token, Name: name, NodeKey: entry.PinnedKey(), Timeout: requestTimeoutFlag}
+}
+
+// resolveSessionTarget resolves the target for a command whose first
+// argument is a session reference. Without --server, a node:id reference
+// ("gpu-box:3") connects to the saved server it names, so IDs printed by
+// `cw --server gpu-box list` work as-is.
+func resolveSessionTarget(args []string) (*client.Target, error) {
+	if serverFlag == "" && len(args) > 0 {
+		if node, _, ok := client.SplitSessionRef(strings.TrimPrefix(args[0], "@")); ok && node != "local" {
+			target, found := savedServerTarget(node)
+			if !found {
+				return nil, fmt.Errorf("unknown server %q in %q (add it with: cw server add %s <url>)", node, args[0], node)
+			}
+			return target, nil
+		}
+	}
+	return resolveTarget()
 }
 
 func ensureNode() error {
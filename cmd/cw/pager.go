@@ -0,0 +1,113 @@
+package main
+
+import (
+	"errors"
+	"io"
+	"os"
+	"os/exec"
+	"os/signal"
+	"strings"
+
+	"golang.org/x/term"
+)
+
+// errPagerClosed is returned by writes after the user quit the pager; the
+// command that was writing should stop quietly.
+var errPagerClosed = errors.New("pager closed")
+
+// pager is a running $PAGER that output is piped into.
+type pager struct {
+	cmd    *exec.Cmd
+	in     io.WriteCloser
+	exited chan struct{} // closed once the pager has exited
+	err    error         // the pager's exit status, set before exited closes
+}
+
+// startPager starts the user's pager on stdout, or returns nil when output
+// should go straight to stdout: when stdout is not a terminal (redirected
+// or piped), or the pager is disabled with CW_PAGER or PAGER set to "" or
+// "cat". The pager is $CW_PAGER, then $PAGER, then less; less gets
+// LESS=FRX unless set, so short output prints without a pager screen and
+// colors come through.
+func startPager() *pager {
+	if !term.IsTerminal(int(os.Stdout.Fd())) {
+		return nil
+	}
+	command, ok := os.LookupEnv("CW_PAGER")
+	if !ok {
+		command, ok = os.LookupEnv("PAGER")
+	}
+	if !ok {
+		command = "less"
+	}
+	command = strings.TrimSpace(command)
+	if command == "" || command == "cat" {
+		return nil
+	}
+
+	cmd := exec.Command("/bin/sh", "-c", command)
+	cmd.Stdout = os.Stdout
+	cmd.Stderr = os.Stderr
+	cmd.Env = os.Environ()
+	if _, set := os.LookupEnv("LESS"); !set {
+		cmd.Env = append(cmd.Env, "LESS=FRX")
+	}
+	in, err := cmd.StdinPipe()
+	if err != nil {
+		return nil
+	}
+	if err := cmd.Start(); err != nil {
+		return nil
+	}
+	// Ctrl-C is the pager's to handle; cw stops once the pager exits.
+	signal.Ignore(os.Interrupt)
+	p := &pager{cmd: cmd, in: in, exited: make(chan struct{})}
+	go func() {
+		p.err = cmd.Wait()
+		close(p.exited)
+	}()
+	return p
+}
+
+func (p *pager) Write(b []byte) (int, error) {
+	n, err := p.in.Write(b)
+	if err != nil {
+		return n, errPagerClosed
+	}
+	return n, nil
+}
+
+// Close ends the pager's input and waits for the user to quit it.
+func (p *pager) Close() error {
+	p.in.Close()
+	<-p.exited
+	signal.Reset(os.Interrupt)
+	return p.err
+}
+
+// withPager runs fn with a writer that pages its output when paging applies
+// (see startPager), and stdout otherwise. stop is closed when the user quits
+// the pager, so fn can drop the request it is waiting on rather than run on
+// until its next write fails; it is nil without a pager. Quitting the pager
+// early is not an error.
+func withPager(enabled bool, fn func(w io.Writer, stop <-chan struct{}) error) error {
+	var p *pager
+	if enabled {
+		p = startPager()
+	}
+	if p == nil {
+		return fn(os.Stdout, nil)
+	}
+	err := fn(p, p.exited)
+	quit := false
+	select {
+	case <-p.exited:
+		quit = true // fn was cut short by the user
+	default:
+	}
+	p.Close()
+	if quit || errors.Is(err, errPagerClosed) {
+		return nil
+	}
+	return err
+}
@@ -4,6 +4,7 @@ import (
 	"encoding/json"
 	"fmt"
 	"os"
+	"strings"
 
 	"github.com/spf13/cobra"
 
@@ -14,24 +15,29 @@ import (
 func platformListCmd() *cobra.Command {
 	var jsonOutput bool
 	var statusFilter string
+	var tags []string
+	var table client.TableOptions
+	var replica bool
 
 	cmd := &cobra.Command{
-		Use:   "list",
+		Use:   "list [node]",
 		Short: "List environments and sessions",
-		Long:  "In platform mode: show environments grouped by org.\nIn standalone mode: list local sessions.",
+		Long:  "In platform mode: show environments grouped by org.\nIn standalone mode: list local sessions, or the sessions on a saved server.\nSessions on remote nodes are listed as node:id, which attach, kill and\nstatus accept directly.",
+		Args:  cobra.MaximumNArgs(1),
 		RunE: func(cmd *cobra.Command, args []string) error {
 			// If not in platform mode, fall back to local session list
-			if !platform.HasConfig() {
-				target, err := resolveTarget()
+			if !platform.HasConfig() || len(args) > 0 || replica {
+				target, err := listTarget(args)
 				if err != nil {
 					return err
 				}
+				target.Replica = replica
 				if target.IsLocal() {
 					if err := ensureNode(); err != nil {
 						return err
 					}
 				}
-				return client.List(target, jsonOutput, statusFilter)
+				return client.List(target, jsonOutput, statusFilter, tags, table)
 			}
 
 			orgID, pc, err := getDefaultOrg()
@@ -73,5 +79,33 @@ func platformListCmd() *cobra.Command {
 	_ = cmd.RegisterFlagCompletionFunc("status", func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
 		return []string{"all", "running", "completed", "killed"}, cobra.ShellCompDirectiveNoFileComp
 	})
+	cmd.Flags().StringSliceVarP(&tags, "tag", "t", nil, "Only sessions with this tag; repeat to require several (standalone mode)")
+	cmd.Flags().BoolVar(&replica, "replica", false, "List the sessions a standby node copied from its primary")
+	_ = cmd.RegisterFlagCompletionFunc("tag", tagCompletionFunc)
+	cmd.Flags().StringVar(&table.Columns, "columns", client.DefaultColumns, "Comma-separated columns (standalone mode): "+strings.Join(client.ColumnNames(), ", "))
+	cmd.Flags().StringVar(&table.Sort, "sort", "", "Sort by key, prefix with - for descending (standalone mode): "+strings.Join(client.SortKeys(), ", "))
+	_ = cmd.RegisterFlagCompletionFunc("sort", func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+		return client.SortKeys(), cobra.ShellCompDirectiveNoFileComp
+	})
+	cmd.Flags().StringVar(&table.Time, "time", client.TimeRelative, "Timestamp style (standalone mode): relative, absolute or iso")
+	_ = cmd.RegisterFlagCompletionFunc("time", func(cmd *cobra.Command, args []string, toComplete string) ([]string, cobra.ShellCompDirective) {
+		return client.TimeFormats(), cobra.ShellCompDirectiveNoFileComp
+	})
 	return cmd
 }
+
+// listTarget resolves the node named by `cw list [node]`, falling back to
+// --server or the local node.
+func listTarget(args []string) (*client.Target, error) {
+	if len(args) == 0 {
+		return resolveTarget()
+	}
+	if args[0] == "local" {
+		return &client.Target{Local: dataDir()}, nil
+	}
+	target, ok := savedServerTarget(args[0])
+	if !ok {
+		return nil, fmt.Errorf("unknown server %q (add it with: cw server add %s <url>)", args[0], args[0])
+	}
+	return target, nil
+}
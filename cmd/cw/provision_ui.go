@@ -8,6 +8,7 @@ import (
 	"time"
 
 	"github.com/codewiresh/codewire/internal/platform"
+	"github.com/codewiresh/codewire/internal/textwidth"
 )
 
 // provisionTimeline renders a live provisioning timeline to the terminal.
@@ -138,9 +139,7 @@ func (t *provisionTimeline) render() {
 		if t.podStatus.logTail != "" {
 			logLines := strings.Split(t.podStatus.logTail, "\n")
 			last := logLines[len(logLines)-1]
-			if len(last) > 80 {
-				last = last[:80] + "..."
-			}
+			last = textwidth.Truncate(last, 83)
 			lines = append(lines, fmt.Sprintf("  Last log: %s", last))
 		}
 	}
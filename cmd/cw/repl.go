@@ -0,0 +1,457 @@
+package main
+
+import (
+	"errors"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"os/signal"
+	"slices"
+	"sort"
+	"strings"
+	"sync"
+
+	"github.com/spf13/cobra"
+	"golang.org/x/term"
+
+	"github.com/codewiresh/codewire/internal/client"
+	"github.com/codewiresh/codewire/internal/config"
+)
+
+// replCommands are the commands the shell understands, for completion.
+var replCommands = []string{"attach", "exit", "help", "kill", "launch", "ls", "nodes", "send", "use", "watch"}
+
+const replHelp = `Commands:
+  nodes                         list known nodes (* marks the current one)
+  use <node>                    switch the current node
+  ls [node]                     list sessions
+  launch [-name N] [-dir D] [-tag T]... -- <command...>
+  send <session> <text...>      send a line of input
+  watch <session>               stream output (Ctrl+C returns to the prompt)
+  attach <session>              attach (Ctrl+B d returns to the prompt)
+  kill <session>                kill a session
+  exit                          leave the shell
+
+Sessions are IDs or names on the current node, or node:id on any node.
+Tab completes commands, node names and the sessions last listed.`
+
+// replShell is the state `cw repl` keeps between commands: one target per
+// node, so a pinned node's identity is checked once per shell rather than
+// once per command, and the node and session names used for completion.
+type replShell struct {
+	node      string                    // current node
+	targets   map[string]*client.Target // by node name
+	nodes     []string                  // completion candidates for node names
+	sessions  map[string][]string       // completion candidates by node, from the last ls
+	interrupt chan os.Signal
+}
+
+func replCmd() *cobra.Command {
+	return &cobra.Command{
+		Use:   "repl",
+		Short: "Interactive shell for supervising sessions across nodes",
+		Long: `Start an interactive shell with ls, launch, send, watch and attach.
+
+The shell keeps the targets it has resolved and the node and session names
+it has seen, so switching between nodes and sessions during a supervision
+session needs no re-resolving and completes with Tab. Nodes are "local" and
+the entries of servers.toml; --server picks the node the shell starts on.`,
+		Args: cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			target, err := resolveTarget()
+			if err != nil {
+				return err
+			}
+			if target.IsLocal() {
+				if err := ensureNode(); err != nil {
+					return err
+				}
+			}
+			return newReplShell(target).run()
+		},
+	}
+}
+
+func newReplShell(target *client.Target) *replShell {
+	r := &replShell{
+		node:     target.NodeName(),
+		targets:  map[string]*client.Target{target.NodeName(): target},
+		sessions: map[string][]string{},
+		nodes:    []string{"local", target.NodeName()},
+	}
+	if servers, err := config.LoadServersConfig(dataDir()); err == nil {
+		for name := range servers.Servers {
+			r.nodes = append(r.nodes, name)
+		}
+	}
+	sort.Strings(r.nodes)
+	r.nodes = slices.Compact(r.nodes)
+	return r
+}
+
+func (r *replShell) run() error {
+	fd := int(os.Stdin.Fd())
+	if !term.IsTerminal(fd) {
+		return fmt.Errorf("cw repl needs a terminal")
+	}
+	t := term.NewTerminal(struct {
+		io.Reader
+		io.Writer
+	}{os.Stdin, os.Stdout}, r.prompt())
+	t.AutoCompleteCallback = func(line string, pos int, key rune) (string, int, bool) {
+		if key != '\t' {
+			return "", 0, false
+		}
+		return r.complete(line, pos)
+	}
+
+	// Ctrl+C while a command runs stops watch instead of the shell.
+	r.interrupt = make(chan os.Signal, 1)
+	signal.Notify(r.interrupt, os.Interrupt)
+	defer signal.Stop(r.interrupt)
+
+	r.refreshSessions(r.node)
+	fmt.Println("Type help for commands.")
+	for {
+		state, err := term.MakeRaw(fd)
+		if err != nil {
+			return err
+		}
+		line, err := t.ReadLine()
+		_ = term.Restore(fd, state)
+		if errors.Is(err, io.EOF) {
+			fmt.Println()
+			return nil
+		}
+		if err != nil {
+			return err
+		}
+		args, err := splitWords(line)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			continue
+		}
+		if len(args) == 0 {
+			continue
+		}
+		if args[0] == "exit" || args[0] == "quit" {
+			return nil
+		}
+		if err := r.exec(args); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+		}
+		t.SetPrompt(r.prompt())
+	}
+}
+
+func (r *replShell) prompt() string {
+	return r.node + "> "
+}
+
+// exec runs one command line.
+func (r *replShell) exec(args []string) error {
+	switch cmd, rest := args[0], args[1:]; cmd {
+	case "help":
+		fmt.Println(replHelp)
+		return nil
+
+	case "nodes":
+		for _, n := range r.nodes {
+			mark := " "
+			if n == r.node {
+				mark = "*"
+			}
+			fmt.Printf("%s %s\n", mark, n)
+		}
+		return nil
+
+	case "use":
+		if len(rest) != 1 {
+			return fmt.Errorf("usage: use <node>")
+		}
+		if _, err := r.target(rest[0]); err != nil {
+			return err
+		}
+		r.node = rest[0]
+		r.refreshSessions(r.node)
+		return nil
+
+	case "ls":
+		node := r.node
+		if len(rest) > 0 {
+			node = rest[0]
+		}
+		target, err := r.target(node)
+		if err != nil {
+			return err
+		}
+		defer r.refreshSessions(node)
+		return client.List(target, false, "all", nil, client.TableOptions{})
+
+	case "launch":
+		return r.launch(rest)
+
+	case "send":
+		if len(rest) < 2 {
+			return fmt.Errorf("usage: send <session> <text...>")
+		}
+		target, id, err := r.session(rest[0])
+		if err != nil {
+			return err
+		}
+		text := strings.Join(rest[1:], " ")
+		return client.SendInput(target, id, &text, false, nil, false, nil)
+
+	case "watch":
+		if len(rest) != 1 {
+			return fmt.Errorf("usage: watch <session>")
+		}
+		target, id, err := r.session(rest[0])
+		if err != nil {
+			return err
+		}
+		return r.watch(target, id)
+
+	case "attach":
+		if len(rest) != 1 {
+			return fmt.Errorf("usage: attach <session>")
+		}
+		target, id, err := r.session(rest[0])
+		if err != nil {
+			return err
+		}
+		_, err = client.Attach(target, &id, client.AttachOptions{})
+		return err
+
+	case "kill":
+		if len(rest) != 1 {
+			return fmt.Errorf("usage: kill <session>")
+		}
+		target, id, err := r.session(rest[0])
+		if err != nil {
+			return err
+		}
+		return client.Kill(target, id)
+
+	default:
+		return fmt.Errorf("unknown command %q (type help)", cmd)
+	}
+}
+
+// launch parses "[-name N] [-dir D] [-tag T]... -- command..." and starts
+// the session on the current node.
+func (r *replShell) launch(args []string) error {
+	flags := flag.NewFlagSet("launch", flag.ContinueOnError)
+	flags.SetOutput(os.Stderr)
+	name := flags.String("name", "", "session name")
+	dir := flags.String("dir", "", "working directory (default: current directory)")
+	var tags []string
+	flags.Func("tag", "tag the session (repeatable)", func(v string) error {
+		tags = append(tags, v)
+		return nil
+	})
+	if err := flags.Parse(args); err != nil {
+		return err
+	}
+	if flags.NArg() == 0 {
+		return fmt.Errorf("usage: launch [-name N] [-dir D] [-tag T]... -- <command...>")
+	}
+	if *dir == "" {
+		*dir, _ = os.Getwd()
+	}
+	target, err := r.target(r.node)
+	if err != nil {
+		return err
+	}
+	if _, err := client.Run(target, flags.Args(), *dir, *name, nil, nil, client.RunOptions{}, tags...); err != nil {
+		return err
+	}
+	r.refreshSessions(r.node)
+	return nil
+}
+
+// watch streams a session's output until it ends or Ctrl+C is pressed.
+// Ctrl+C stops the stream and closes its connection; nothing is written
+// once the prompt is back.
+func (r *replShell) watch(target *client.Target, id uint32) error {
+	select {
+	case <-r.interrupt:
+	default:
+	}
+	out := &gatedWriter{w: os.Stdout}
+	stop := make(chan struct{})
+	done := make(chan error, 1)
+	go func() {
+		done <- client.WatchSessionUntil(stop, target, id, nil, false, nil, 0, nil, out)
+	}()
+	select {
+	case err := <-done:
+		return err
+	case <-r.interrupt:
+		out.close()
+		close(stop)
+		fmt.Println()
+		return nil
+	}
+}
+
+// gatedWriter passes writes through until closed, then discards them.
+type gatedWriter struct {
+	mu     sync.Mutex
+	w      io.Writer
+	closed bool
+}
+
+func (g *gatedWriter) Write(p []byte) (int, error) {
+	g.mu.Lock()
+	defer g.mu.Unlock()
+	if g.closed {
+		return len(p), nil
+	}
+	return g.w.Write(p)
+}
+
+func (g *gatedWriter) close() {
+	g.mu.Lock()
+	g.closed = true
+	g.mu.Unlock()
+}
+
+// target returns the target for a node, resolving it on first use.
+func (r *replShell) target(node string) (*client.Target, error) {
+	if t, ok := r.targets[node]; ok {
+		return t, nil
+	}
+	var t *client.Target
+	if node == "local" {
+		t = &client.Target{Local: dataDir(), Timeout: requestTimeoutFlag}
+	} else {
+		var found bool
+		if t, found = savedServerTarget(node); !found {
+			return nil, fmt.Errorf("unknown node %q (add it with: cw server add %s <url>)", node, node)
+		}
+	}
+	r.targets[node] = t
+	return t, nil
+}
+
+// session resolves a session reference: an ID or name on the current node,
+// or node:id on any node.
+func (r *replShell) session(arg string) (*client.Target, uint32, error) {
+	if node, id, ok := client.SplitSessionRef(arg); ok {
+		target, err := r.target(node)
+		return target, id, err
+	}
+	target, err := r.target(r.node)
+	if err != nil {
+		return nil, 0, err
+	}
+	id, err := client.ResolveSessionArg(target, arg)
+	return target, id, err
+}
+
+// refreshSessions reloads the completion candidates for node's sessions.
+func (r *replShell) refreshSessions(node string) {
+	if target, err := r.target(node); err == nil {
+		r.sessions[node] = client.ListSessionsForCompletion(target)
+	}
+}
+
+// complete completes the word before the cursor: a command name, a node
+// name after use and ls, or a session after the session commands. Sessions
+// on other nodes are offered as node:id.
+func (r *replShell) complete(line string, pos int) (string, int, bool) {
+	head := line[:pos]
+	start := strings.LastIndexByte(head, ' ') + 1
+	word := head[start:]
+
+	var candidates []string
+	switch fields := strings.Fields(head[:start]); {
+	case len(fields) == 0:
+		candidates = replCommands
+	case len(fields) > 1:
+		// Only the first argument is completed.
+	case fields[0] == "use" || fields[0] == "ls":
+		candidates = r.nodes
+	case slices.Contains([]string{"send", "watch", "attach", "kill"}, fields[0]):
+		candidates = r.sessionRefs()
+	}
+
+	var matches []string
+	for _, c := range candidates {
+		if strings.HasPrefix(c, word) {
+			matches = append(matches, c)
+		}
+	}
+	if len(matches) == 0 {
+		return "", 0, false
+	}
+	completion := matches[0]
+	for _, m := range matches[1:] {
+		for !strings.HasPrefix(m, completion) {
+			completion = completion[:len(completion)-1]
+		}
+	}
+	if len(matches) == 1 {
+		completion += " "
+	} else if completion == word {
+		return "", 0, false
+	}
+	return head[:start] + completion + line[pos:], start + len(completion), true
+}
+
+// sessionRefs lists the sessions last seen: bare on the current node,
+// node:id elsewhere.
+func (r *replShell) sessionRefs() []string {
+	refs := slices.Clone(r.sessions[r.node])
+	for node, ids := range r.sessions {
+		if node == r.node {
+			continue
+		}
+		for _, id := range ids {
+			if _, _, ok := client.SplitSessionRef(node + ":" + id); ok {
+				refs = append(refs, node+":"+id)
+			}
+		}
+	}
+	sort.Strings(refs)
+	return refs
+}
+
+// splitWords splits a command line on spaces, keeping single- and
+// double-quoted strings together.
+func splitWords(line string) ([]string, error) {
+	var (
+		words  []string
+		cur    strings.Builder
+		inWord bool
+		quote  rune
+	)
+	for _, c := range line {
+		switch {
+		case quote != 0 && c == quote:
+			quote = 0
+		case quote != 0:
+			cur.WriteRune(c)
+		case c == '"' || c == '\'':
+			quote, inWord = c, true
+		case c == ' ' || c == '\t':
+			if inWord {
+				words = append(words, cur.String())
+				cur.Reset()
+				inWord = false
+			}
+		default:
+			cur.WriteRune(c)
+			inWord = true
+		}
+	}
+	if quote != 0 {
+		return nil, fmt.Errorf("unterminated %c quote", quote)
+	}
+	if inWord {
+		words = append(words, cur.String())
+	}
+	return words, nil
+}
@@ -0,0 +1,45 @@
+package main
+
+import (
+	"slices"
+	"testing"
+)
+
+func TestReplComplete(t *testing.T) {
+	r := &replShell{
+		node:     "local",
+		nodes:    []string{"gpu-box", "gpu-two", "local"},
+		sessions: map[string][]string{"local": {"planner", "1", "2"}, "gpu-box": {"fixer", "7"}},
+	}
+	for _, tc := range []struct {
+		line, want string
+		ok         bool
+	}{
+		{"la", "launch ", true},
+		{"use gpu-b", "use gpu-box ", true},
+		{"use gpu", "use gpu-", true},
+		{"use gpu-", "", false}, // ambiguous, nothing more to add
+		{"attach pl", "attach planner ", true},
+		{"send gpu-box:", "send gpu-box:7 ", true},
+		{"send planner fi", "", false}, // only the first argument completes
+	} {
+		got, pos, ok := r.complete(tc.line, len(tc.line))
+		if ok != tc.ok || got != tc.want || (ok && pos != len(got)) {
+			t.Errorf("complete(%q) = %q, %d, %v; want %q, %v", tc.line, got, pos, ok, tc.want, tc.ok)
+		}
+	}
+}
+
+func TestSplitWords(t *testing.T) {
+	got, err := splitWords(`launch -name fix -- claude -p "fix the 'flaky' test"`)
+	if err != nil {
+		t.Fatal(err)
+	}
+	want := []string{"launch", "-name", "fix", "--", "claude", "-p", "fix the 'flaky' test"}
+	if !slices.Equal(got, want) {
+		t.Fatalf("got %q, want %q", got, want)
+	}
+	if _, err := splitWords(`send 1 "oops`); err == nil {
+		t.Fatal("expected an unterminated quote error")
+	}
+}
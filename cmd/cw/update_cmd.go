@@ -2,6 +2,7 @@ package main
 
 import (
 	"fmt"
+	"os"
 
 	"github.com/spf13/cobra"
 
@@ -9,26 +10,61 @@ import (
 )
 
 func updateCmd() *cobra.Command {
-	return &cobra.Command{
+	var (
+		check   bool
+		channel string
+	)
+
+	cmd := &cobra.Command{
 		Use:   "update",
 		Short: "Update cw to the latest version",
+		Long: `Update cw to the latest release on --channel.
+
+The release binary for this platform is downloaded next to the current
+executable, verified against the release's SHA256SUMS, and renamed over it
+atomically. Installs managed by Homebrew, apt or the AUR print the package
+manager command instead.
+
+The checksum file must carry a valid signature by the release key, the
+one in the repository's GPG_PUBLIC_KEY.asc, or nothing is installed.
+
+With --check, nothing is downloaded: cw prints whether an update is available
+and exits 1 if there is one, 0 if it is up to date, and 2 if this build's
+version is unknown (a dev build).`,
 		RunE: func(cmd *cobra.Command, args []string) error {
-			if version == "dev" {
-				return fmt.Errorf("cannot update a dev build — install a release build first")
+			release := update.IsRelease(version)
+			if !release && !check {
+				return fmt.Errorf("cannot update a dev build (version %q) — install a release build first", version)
 			}
 
-			fmt.Println("Checking for updates...")
-			latest, err := update.FetchLatestVersion()
+			if !check {
+				fmt.Println("Checking for updates...")
+			}
+			latest, err := update.FetchChannelVersion(channel)
 			if err != nil {
 				return fmt.Errorf("checking for updates: %w", err)
 			}
 
+			if !release {
+				fmt.Printf("Current version unknown (%s build); latest release is %s.\n", version, latest)
+				os.Exit(2)
+			}
+
 			if !update.IsNewer(version, latest) {
 				fmt.Printf("Already up to date (%s).\n", version)
 				return nil
 			}
 
 			fmt.Printf("New version available: %s → %s\n", version, latest)
+			if check {
+				os.Exit(1)
+			}
+
+			if method := update.DetectInstallMethod(); method != update.DirectBinary {
+				fmt.Printf("cw was installed with %s; upgrade with:\n  %s\n", method, update.UpgradeCommand(method))
+				return nil
+			}
+
 			fmt.Printf("Downloading %s...\n", latest)
 			if err := update.SelfUpdate(version, latest); err != nil {
 				return err
@@ -38,4 +74,9 @@ func updateCmd() *cobra.Command {
 			return nil
 		},
 	}
+
+	cmd.Flags().BoolVar(&check, "check", false, "Only report whether an update is available (exit 1 if so)")
+	cmd.Flags().StringVar(&channel, "channel", update.ChannelStable, "Release channel: stable or beta")
+
+	return cmd
 }
@@ -0,0 +1,95 @@
+// Package ansi strips ANSI/VT100 escape sequences from terminal output using
+// a small state machine rather than a regex, so sequences split across
+// reads are handled correctly.
+package ansi
+
+import "io"
+
+type state uint8
+
+const (
+	stateGround state = iota
+	stateEsc          // saw ESC
+	stateCSI          // inside ESC [ ... final byte
+	stateOSC          // inside ESC ] ... BEL or ESC \
+	stateOSCEsc       // saw ESC inside an OSC string
+)
+
+// Stripper removes escape sequences from a byte stream. It keeps parser state
+// between calls, so output can be fed in arbitrary chunks.
+type Stripper struct {
+	st state
+}
+
+// Strip returns p with escape sequences removed, continuing any sequence left
+// open by the previous call.
+func (s *Stripper) Strip(p []byte) []byte {
+	out := make([]byte, 0, len(p))
+	for i := 0; i < len(p); i++ {
+		c := p[i]
+		switch s.st {
+		case stateGround:
+			if c == '\x1b' {
+				s.st = stateEsc
+				continue
+			}
+			out = append(out, c)
+		case stateEsc:
+			switch c {
+			case '[':
+				s.st = stateCSI
+			case ']':
+				s.st = stateOSC
+			default:
+				// Two-byte sequence (e.g. ESC =, ESC 7): drop both.
+				s.st = stateGround
+			}
+		case stateCSI:
+			if c >= 0x40 && c <= 0x7E {
+				s.st = stateGround
+			}
+		case stateOSC:
+			switch c {
+			case '\x07':
+				s.st = stateGround
+			case '\x1b':
+				s.st = stateOSCEsc
+			}
+		case stateOSCEsc:
+			if c == '\\' {
+				s.st = stateGround
+				continue
+			}
+			// Lone ESC inside an OSC string; reprocess c as OSC content.
+			s.st = stateOSC
+			i--
+		}
+	}
+	return out
+}
+
+// Strip removes escape sequences from s.
+func Strip(s string) string {
+	var st Stripper
+	return string(st.Strip([]byte(s)))
+}
+
+// Writer strips escape sequences from everything written through it.
+type Writer struct {
+	w  io.Writer
+	st Stripper
+}
+
+// NewWriter returns a Writer that forwards stripped output to w.
+func NewWriter(w io.Writer) *Writer {
+	return &Writer{w: w}
+}
+
+// Write strips p and writes the result to the underlying writer. It reports
+// len(p) on success since escape bytes are consumed, not dropped in error.
+func (w *Writer) Write(p []byte) (int, error) {
+	if _, err := w.w.Write(w.st.Strip(p)); err != nil {
+		return 0, err
+	}
+	return len(p), nil
+}
@@ -0,0 +1,56 @@
+package ansi
+
+import (
+	"bytes"
+	"testing"
+)
+
+func TestStrip(t *testing.T) {
+	cases := []struct {
+		name, input, want string
+	}{
+		{"plain text", "hello world", "hello world"},
+		{"CSI color", "\x1b[32mgreen\x1b[0m text", "green text"},
+		{"CSI cursor move", "\x1b[2J\x1b[H", ""},
+		{"OSC title BEL", "\x1b]0;title\x07rest", "rest"},
+		{"OSC title ST", "\x1b]0;title\x1b\\rest", "rest"},
+		{"nested codes", "\x1b[1;32mBold\x1b[0m normal", "Bold normal"},
+		{"preserves newlines", "line1\nline2\n", "line1\nline2\n"},
+	}
+	for _, tc := range cases {
+		t.Run(tc.name, func(t *testing.T) {
+			got := Strip(tc.input)
+			if got != tc.want {
+				t.Errorf("Strip(%q) = %q, want %q", tc.input, got, tc.want)
+			}
+		})
+	}
+}
+
+func TestStripperAcrossChunks(t *testing.T) {
+	input := "a\x1b[1;32mb\x1b]0;title\x1b\\c\x1b]2;t\x07d\x1b=e"
+	want := "abcde"
+	// Feed one byte at a time so every sequence is split.
+	var st Stripper
+	var got []byte
+	for i := 0; i < len(input); i++ {
+		got = append(got, st.Strip([]byte{input[i]})...)
+	}
+	if string(got) != want {
+		t.Fatalf("chunked strip = %q, want %q", got, want)
+	}
+}
+
+func TestWriter(t *testing.T) {
+	var buf bytes.Buffer
+	w := NewWriter(&buf)
+	for _, chunk := range []string{"hi \x1b[3", "1mred\x1b[0", "m!"} {
+		n, err := w.Write([]byte(chunk))
+		if err != nil || n != len(chunk) {
+			t.Fatalf("Write(%q) = %d, %v", chunk, n, err)
+		}
+	}
+	if got := buf.String(); got != "hi red!" {
+		t.Fatalf("got %q, want %q", got, "hi red!")
+	}
+}
@@ -0,0 +1,273 @@
+package ansi
+
+import (
+	"fmt"
+	"strconv"
+	"strings"
+)
+
+// ColorDepth is the number of colors a terminal can display.
+type ColorDepth uint8
+
+const (
+	ColorsTrue ColorDepth = iota // 24-bit RGB
+	Colors256
+	Colors16
+)
+
+func (d ColorDepth) String() string {
+	switch d {
+	case Colors256:
+		return "256"
+	case Colors16:
+		return "16"
+	default:
+		return "truecolor"
+	}
+}
+
+// ParseColorDepth parses "truecolor" (or "24bit"), "256" or "16".
+func ParseColorDepth(s string) (ColorDepth, error) {
+	switch strings.ToLower(s) {
+	case "truecolor", "24bit":
+		return ColorsTrue, nil
+	case "256":
+		return Colors256, nil
+	case "16":
+		return Colors16, nil
+	}
+	return 0, fmt.Errorf("invalid color depth %q: expected truecolor, 256 or 16", s)
+}
+
+// DetectColorDepth guesses a terminal's color depth from its COLORTERM and
+// TERM. Without a TERM nothing is assumed to be limited.
+func DetectColorDepth(colorterm, term string) ColorDepth {
+	switch {
+	case colorterm == "truecolor" || colorterm == "24bit":
+		return ColorsTrue
+	case term == "" || strings.HasSuffix(term, "-direct"):
+		return ColorsTrue
+	case strings.Contains(term, "256color"):
+		return Colors256
+	default:
+		return Colors16
+	}
+}
+
+// maxSGRLen bounds a buffered CSI sequence; longer ones are passed through
+// unconverted.
+const maxSGRLen = 256
+
+// Downconverter rewrites SGR color sequences (ESC [ ... m) for a terminal
+// with fewer colors and passes everything else through. Like Stripper it
+// keeps state between calls, so sequences split across chunks are handled.
+type Downconverter struct {
+	depth ColorDepth
+	st    state
+	seq   []byte // the escape sequence being buffered
+}
+
+// NewDownconverter returns a Downconverter for depth.
+func NewDownconverter(depth ColorDepth) *Downconverter {
+	return &Downconverter{depth: depth}
+}
+
+// Convert returns p with colors reduced to the converter's depth.
+func (d *Downconverter) Convert(p []byte) []byte {
+	if d.depth == ColorsTrue {
+		return p
+	}
+	out := make([]byte, 0, len(p))
+	for _, c := range p {
+		switch d.st {
+		case stateGround:
+			if c == '\x1b' {
+				d.st = stateEsc
+				d.seq = append(d.seq[:0], c)
+				continue
+			}
+			out = append(out, c)
+		case stateEsc:
+			d.seq = append(d.seq, c)
+			if c == '[' {
+				d.st = stateCSI
+				continue
+			}
+			out = append(out, d.seq...)
+			d.st = stateGround
+		case stateCSI:
+			d.seq = append(d.seq, c)
+			if c >= 0x40 && c <= 0x7E {
+				if c == 'm' {
+					out = append(out, d.convertSGR(d.seq)...)
+				} else {
+					out = append(out, d.seq...)
+				}
+				d.st = stateGround
+			} else if len(d.seq) > maxSGRLen {
+				out = append(out, d.seq...)
+				d.st = stateGround
+			}
+		}
+	}
+	return out
+}
+
+// convertSGR rewrites the color parameters of one complete SGR sequence.
+func (d *Downconverter) convertSGR(seq []byte) []byte {
+	params := string(seq[2 : len(seq)-1])
+	if params == "" || strings.IndexFunc(params, func(r rune) bool { return (r < '0' || r > '9') && r != ';' && r != ':' }) >= 0 {
+		return seq // private or malformed sequence
+	}
+	ps := strings.Split(params, ";")
+	out := make([]string, 0, len(ps))
+	for i := 0; i < len(ps); i++ {
+		p := ps[i]
+		if strings.Contains(p, ":") {
+			// ITU form: 38:2::r:g:b, 38:2:r:g:b or 38:5:n.
+			sub := strings.Split(p, ":")
+			if len(sub) >= 3 && (sub[0] == "38" || sub[0] == "48") {
+				switch {
+				case sub[1] == "2" && len(sub) >= 5:
+					rgb := sub[len(sub)-3:]
+					out = append(out, d.rgb(sub[0], rgb[0], rgb[1], rgb[2]))
+					continue
+				case sub[1] == "5":
+					out = append(out, d.indexed(sub[0], sub[2]))
+					continue
+				}
+			}
+			out = append(out, p)
+			continue
+		}
+		if (p == "38" || p == "48") && i+1 < len(ps) {
+			switch {
+			case ps[i+1] == "2" && i+4 < len(ps):
+				out = append(out, d.rgb(p, ps[i+2], ps[i+3], ps[i+4]))
+				i += 4
+				continue
+			case ps[i+1] == "5" && i+2 < len(ps):
+				out = append(out, d.indexed(p, ps[i+2]))
+				i += 2
+				continue
+			}
+		}
+		out = append(out, p)
+	}
+	return []byte("\x1b[" + strings.Join(out, ";") + "m")
+}
+
+// rgb converts a 24-bit color for code 38 (foreground) or 48 (background).
+func (d *Downconverter) rgb(code, rs, gs, bs string) string {
+	r, g, b := component(rs), component(gs), component(bs)
+	if d.depth == Colors256 {
+		return fmt.Sprintf("%s;5;%d", code, rgbTo256(r, g, b))
+	}
+	return ansi16(code, nearest16(r, g, b))
+}
+
+// indexed converts a 256-color index for code 38 or 48.
+func (d *Downconverter) indexed(code, ns string) string {
+	n, err := strconv.Atoi(ns)
+	if err != nil || n < 0 || n > 255 {
+		return code + ";5;" + ns
+	}
+	if d.depth == Colors256 {
+		return fmt.Sprintf("%s;5;%d", code, n)
+	}
+	if n < 16 {
+		return ansi16(code, n)
+	}
+	r, g, b := color256(n)
+	return ansi16(code, nearest16(r, g, b))
+}
+
+func component(s string) int {
+	v, _ := strconv.Atoi(s)
+	return min(max(v, 0), 255)
+}
+
+// ansi16 formats palette color n (0-15) as a basic SGR parameter.
+func ansi16(code string, n int) string {
+	base := 30
+	if code == "48" {
+		base = 40
+	}
+	if n >= 8 {
+		base += 60
+		n -= 8
+	}
+	return strconv.Itoa(base + n)
+}
+
+// palette16 is xterm's default 16-color palette.
+var palette16 = [16][3]int{
+	{0, 0, 0}, {205, 0, 0}, {0, 205, 0}, {205, 205, 0},
+	{0, 0, 238}, {205, 0, 205}, {0, 205, 205}, {229, 229, 229},
+	{127, 127, 127}, {255, 0, 0}, {0, 255, 0}, {255, 255, 0},
+	{92, 92, 255}, {255, 0, 255}, {0, 255, 255}, {255, 255, 255},
+}
+
+// cubeLevels are the channel values of the 6x6x6 color cube (16-231).
+var cubeLevels = [6]int{0, 95, 135, 175, 215, 255}
+
+// color256 returns the RGB value of a 256-color index.
+func color256(n int) (int, int, int) {
+	switch {
+	case n < 16:
+		c := palette16[n]
+		return c[0], c[1], c[2]
+	case n < 232:
+		n -= 16
+		return cubeLevels[n/36], cubeLevels[n/6%6], cubeLevels[n%6]
+	default:
+		v := 8 + 10*(n-232)
+		return v, v, v
+	}
+}
+
+// rgbTo256 returns the closest color in the cube or the grayscale ramp.
+func rgbTo256(r, g, b int) int {
+	level := func(v int) int {
+		switch {
+		case v < 48:
+			return 0
+		case v < 115:
+			return 1
+		default:
+			return (v - 35) / 40
+		}
+	}
+	qr, qg, qb := level(r), level(g), level(b)
+	cube := 16 + 36*qr + 6*qg + qb
+
+	avg := (r + g + b) / 3
+	grayIdx := 23
+	if avg < 238 {
+		grayIdx = max((avg-3)/10, 0)
+	}
+	gray := 232 + grayIdx
+
+	cr, cg, cb := color256(cube)
+	gv, _, _ := color256(gray)
+	if dist(r, g, b, gv, gv, gv) < dist(r, g, b, cr, cg, cb) {
+		return gray
+	}
+	return cube
+}
+
+// nearest16 returns the closest palette16 color.
+func nearest16(r, g, b int) int {
+	best, bestDist := 0, -1
+	for i, c := range palette16 {
+		if d := dist(r, g, b, c[0], c[1], c[2]); bestDist < 0 || d < bestDist {
+			best, bestDist = i, d
+		}
+	}
+	return best
+}
+
+func dist(r1, g1, b1, r2, g2, b2 int) int {
+	dr, dg, db := r1-r2, g1-g2, b1-b2
+	return dr*dr + dg*dg + db*db
+}
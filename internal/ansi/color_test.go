@@ -0,0 +1,62 @@
+package ansi
+
+import "testing"
+
+func TestDownconvert(t *testing.T) {
+	cases := []struct {
+		name  string
+		depth ColorDepth
+		input string
+		want  string
+	}{
+		{"truecolor untouched", ColorsTrue, "\x1b[38;2;255;0;0mred", "\x1b[38;2;255;0;0mred"},
+		{"rgb to 256", Colors256, "\x1b[38;2;255;0;0mred", "\x1b[38;5;196mred"},
+		{"rgb gray to 256", Colors256, "\x1b[48;2;128;128;128m", "\x1b[48;5;244m"},
+		{"rgb to 16", Colors16, "\x1b[1;38;2;250;10;10;48;2;0;0;0mx", "\x1b[1;91;40mx"},
+		{"256 to 16", Colors16, "\x1b[38;5;21m", "\x1b[34m"},
+		{"low 256 index to 16", Colors16, "\x1b[48;5;3m", "\x1b[43m"},
+		{"colon form", Colors256, "\x1b[38:2::0:255:0m", "\x1b[38;5;46m"},
+		{"basic colors kept", Colors16, "\x1b[0;31mred\x1b[m", "\x1b[0;31mred\x1b[m"},
+		{"other CSI kept", Colors16, "\x1b[2J\x1b[?25l", "\x1b[2J\x1b[?25l"},
+		{"OSC kept", Colors16, "\x1b]0;title\x07ok", "\x1b]0;title\x07ok"},
+	}
+	for _, tc := range cases {
+		t.Run(tc.name, func(t *testing.T) {
+			got := string(NewDownconverter(tc.depth).Convert([]byte(tc.input)))
+			if got != tc.want {
+				t.Errorf("Convert(%q) = %q, want %q", tc.input, got, tc.want)
+			}
+		})
+	}
+}
+
+func TestDownconverterAcrossChunks(t *testing.T) {
+	input := "a\x1b[38;2;255;0;0mb\x1b[0mc"
+	d := NewDownconverter(Colors256)
+	var got []byte
+	for i := 0; i < len(input); i++ {
+		got = append(got, d.Convert([]byte{input[i]})...)
+	}
+	if want := "a\x1b[38;5;196mb\x1b[0mc"; string(got) != want {
+		t.Fatalf("chunked convert = %q, want %q", got, want)
+	}
+}
+
+func TestDetectColorDepth(t *testing.T) {
+	cases := []struct {
+		colorterm, term string
+		want            ColorDepth
+	}{
+		{"truecolor", "xterm", ColorsTrue},
+		{"", "xterm-256color", Colors256},
+		{"", "tmux-256color", Colors256},
+		{"", "xterm", Colors16},
+		{"", "linux", Colors16},
+		{"", "", ColorsTrue},
+	}
+	for _, tc := range cases {
+		if got := DetectColorDepth(tc.colorterm, tc.term); got != tc.want {
+			t.Errorf("DetectColorDepth(%q, %q) = %s, want %s", tc.colorterm, tc.term, got, tc.want)
+		}
+	}
+}
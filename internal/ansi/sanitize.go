@@ -0,0 +1,269 @@
+package ansi
+
+import (
+	"io"
+	"strings"
+)
+
+// Sanitizer makes untrusted terminal output safe to display. Colors, cursor
+// movement, erasing and mode switches pass through, so full-screen programs
+// still render; anything a terminal could be tricked into acting on is
+// dropped:
+//
+//   - OSC strings (ESC ]): window title writes, OSC 52 clipboard reads and
+//     writes, color queries and hyperlinks
+//   - DCS, SOS, PM and APC strings (ESC P, ESC X, ESC ^, ESC _)
+//   - CSI sequences that make the terminal report back, such as device
+//     status (n), device attributes (c) and window reports (t), and any
+//     other final byte not known to be harmless
+//   - mode switches (h and l) other than those in safePrivateModes and
+//     safeANSIModes, so mouse and focus reporting cannot be turned on
+//   - ESC sequences other than cursor save/restore, keypad modes, index
+//     and charset selection
+//
+// C1 controls encoded in UTF-8 (U+0080 to U+009F) are read as the ESC
+// sequences they abbreviate, so U+009B is a CSI and U+009D an OSC; what is
+// kept is passed on in its 7-bit form.
+//
+// Like Stripper it keeps state between calls, so sequences split across
+// chunks are handled.
+type Sanitizer struct {
+	st       sanitizeState
+	seq      []byte // the escape sequence being buffered
+	overlong bool   // the CSI sequence outgrew maxSGRLen and is dropped
+	c2       bool   // the last byte of a string was 0xC2, maybe starting ST
+}
+
+type sanitizeState uint8
+
+const (
+	sanGround    sanitizeState = iota
+	sanEsc                     // saw ESC
+	sanEscInter                // ESC followed by an intermediate byte
+	sanCSI                     // inside ESC [ ... final byte
+	sanString                  // inside an OSC, DCS, SOS, PM or APC string
+	sanStringEsc               // saw ESC inside a string
+	sanC2                      // saw 0xC2, which may start a C1 control
+)
+
+// safeCSIFinals are the CSI final bytes passed through: cursor movement
+// and positioning, erasing, scrolling, inserting and deleting, tab stops,
+// modes, SGR and cursor save/restore. None of them makes the terminal
+// answer.
+const safeCSIFinals = "@ABCDEFGHIJKLMPSTXZ`abdefghlmrsu"
+
+// safePrivateModes are the DEC private modes (CSI ? Pm h and l) passed
+// through: cursor keys, column mode, reverse video, origin, autowrap,
+// cursor blink and visibility, the alternate screen, bracketed paste and
+// synchronized output. None of them makes the terminal send anything on
+// its own.
+var safePrivateModes = map[string]bool{
+	"1": true, "3": true, "5": true, "6": true, "7": true, "12": true, "25": true,
+	"47": true, "1047": true, "1048": true, "1049": true, "2004": true, "2026": true,
+}
+
+// safeANSIModes are the ANSI modes (CSI Pm h and l) passed through: insert
+// and automatic newline.
+var safeANSIModes = map[string]bool{"4": true, "20": true}
+
+// safeEscFinals are the two-byte ESC sequences passed through: cursor
+// save/restore, keypad modes, index, next line, tab set, reverse index and
+// reset.
+const safeEscFinals = "78=>DEHMc"
+
+// safeEscIntermediates introduce charset designations (ESC ( B and the
+// like), ESC # line attributes and ESC % charset selection.
+const safeEscIntermediates = "()*+-./#%"
+
+// Sanitize returns p with unsafe escape sequences removed, continuing any
+// sequence left open by the previous call.
+func (s *Sanitizer) Sanitize(p []byte) []byte {
+	out := make([]byte, 0, len(p))
+	for i := 0; i < len(p); i++ {
+		c := p[i]
+		switch s.st {
+		case sanGround:
+			switch c {
+			case '\x1b':
+				s.st = sanEsc
+				s.seq = append(s.seq[:0], c)
+			case 0xC2:
+				s.st = sanC2
+			default:
+				out = append(out, c)
+			}
+		case sanC2:
+			s.st = sanGround
+			if c < 0x80 || c > 0x9F {
+				// An ordinary character such as U+00A9.
+				out = append(out, 0xC2)
+				i--
+				continue
+			}
+			// The C1 control is ESC followed by c - 0x40.
+			s.seq = append(s.seq[:0], '\x1b')
+			out = s.escape(c-0x40, out)
+		case sanEsc:
+			if c < 0x20 {
+				// Terminals execute C0 controls met inside a sequence;
+				// another ESC starts over.
+				if c != '\x1b' {
+					out = append(out, c)
+				}
+				continue
+			}
+			if c >= 0x80 {
+				// Not an escape sequence; the byte may start a C1 control.
+				s.st = sanGround
+				i--
+				continue
+			}
+			out = s.escape(c, out)
+		case sanEscInter:
+			if c >= 0x80 {
+				s.st = sanGround
+				i--
+				continue
+			}
+			s.seq = append(s.seq, c)
+			if c >= 0x20 && c <= 0x2F {
+				continue
+			}
+			if c >= 0x30 && c <= 0x7E && strings.IndexByte(safeEscIntermediates, s.seq[1]) >= 0 {
+				out = append(out, s.seq...)
+			}
+			s.st = sanGround
+		case sanCSI:
+			switch {
+			case c == '\x18' || c == '\x1a':
+				// CAN and SUB cancel the sequence.
+				s.st = sanGround
+			case c == '\x1b':
+				s.st = sanEsc
+				s.seq = append(s.seq[:0], c)
+			case c < 0x20:
+				// Terminals execute C0 controls met inside a sequence.
+				out = append(out, c)
+			case c >= 0x80:
+				// A C1 control would start a new sequence in the
+				// terminal; drop this one and read the control.
+				s.st = sanGround
+				i--
+			case c >= 0x40 && c <= 0x7E:
+				s.seq = append(s.seq, c)
+				if !s.overlong && s.safeCSI(c) {
+					out = append(out, s.seq...)
+				}
+				s.st = sanGround
+			case len(s.seq) >= maxSGRLen:
+				// Too long to be anything legitimate; drop it whole.
+				s.overlong = true
+			default:
+				s.seq = append(s.seq, c)
+			}
+		case sanString:
+			if s.c2 && c == 0x9C {
+				// U+009C, the C1 string terminator.
+				s.c2 = false
+				s.st = sanGround
+				continue
+			}
+			s.c2 = c == 0xC2
+			switch c {
+			case '\x07', '\x18', '\x1a':
+				s.st = sanGround
+			case '\x1b':
+				s.st = sanStringEsc
+			}
+		case sanStringEsc:
+			if c == '\\' {
+				s.st = sanGround
+				continue
+			}
+			s.st = sanString
+			i--
+		}
+	}
+	return out
+}
+
+// escape handles c following ESC in the buffered sequence.
+func (s *Sanitizer) escape(c byte, out []byte) []byte {
+	s.seq = append(s.seq, c)
+	switch {
+	case c == '[':
+		s.st = sanCSI
+		s.overlong = false
+	case c == ']' || c == 'P' || c == 'X' || c == '^' || c == '_':
+		s.st = sanString
+		s.c2 = false
+	case c >= 0x20 && c <= 0x2F:
+		s.st = sanEscInter
+	default:
+		if strings.IndexByte(safeEscFinals, c) >= 0 {
+			out = append(out, s.seq...)
+		}
+		s.st = sanGround
+	}
+	return out
+}
+
+// safeCSI reports whether the buffered CSI sequence ending in final may be
+// shown.
+func (s *Sanitizer) safeCSI(final byte) bool {
+	if strings.IndexByte(safeCSIFinals, final) < 0 {
+		return false
+	}
+	switch final {
+	case 'h', 'l':
+		return s.safeModes()
+	case 'u':
+		// CSI ? u asks for the keyboard protocol flags.
+		return len(s.seq) < 3 || s.seq[2] != '?'
+	}
+	return true
+}
+
+// safeModes reports whether every mode the buffered CSI h or l sequence
+// sets or resets is known to be harmless.
+func (s *Sanitizer) safeModes() bool {
+	params := string(s.seq[2 : len(s.seq)-1])
+	modes := safeANSIModes
+	if strings.HasPrefix(params, "?") {
+		params, modes = params[1:], safePrivateModes
+	}
+	for _, mode := range strings.Split(params, ";") {
+		if !modes[mode] {
+			return false
+		}
+	}
+	return true
+}
+
+// Sanitize removes unsafe escape sequences from s.
+func Sanitize(s string) string {
+	var st Sanitizer
+	return string(st.Sanitize([]byte(s)))
+}
+
+// SanitizeWriter removes unsafe escape sequences from everything written
+// through it.
+type SanitizeWriter struct {
+	w  io.Writer
+	st Sanitizer
+}
+
+// NewSanitizeWriter returns a SanitizeWriter that forwards sanitized output
+// to w.
+func NewSanitizeWriter(w io.Writer) *SanitizeWriter {
+	return &SanitizeWriter{w: w}
+}
+
+// Write sanitizes p and writes the result to the underlying writer. Like
+// Writer it reports len(p) on success.
+func (w *SanitizeWriter) Write(p []byte) (int, error) {
+	if _, err := w.w.Write(w.st.Sanitize(p)); err != nil {
+		return 0, err
+	}
+	return len(p), nil
+}
@@ -0,0 +1,59 @@
+package ansi
+
+import "testing"
+
+func TestSanitize(t *testing.T) {
+	cases := []struct {
+		name, input, want string
+	}{
+		{"plain text", "hello world\r\n", "hello world\r\n"},
+		{"keeps colors", "\x1b[1;38;5;196mred\x1b[0m", "\x1b[1;38;5;196mred\x1b[0m"},
+		{"keeps cursor and erase", "\x1b[2J\x1b[H\x1b[?1049h\x1b[3;1r", "\x1b[2J\x1b[H\x1b[?1049h\x1b[3;1r"},
+		{"keeps charset and save", "\x1b(B\x1b7x\x1b8", "\x1b(B\x1b7x\x1b8"},
+		{"drops title BEL", "\x1b]0;pwned\x07rest", "rest"},
+		{"drops title ST", "\x1b]2;pwned\x1b\\rest", "rest"},
+		{"drops OSC 52 read", "a\x1b]52;c;?\x07b", "ab"},
+		{"drops OSC 52 write", "a\x1b]52;c;cm0gLXJmIH4=\x1b\\b", "ab"},
+		{"drops DCS", "a\x1bP$qm\x1b\\b", "ab"},
+		{"drops APC", "a\x1b_payload\x1b\\b", "ab"},
+		{"drops device status report", "a\x1b[6nb", "ab"},
+		{"drops device attributes", "a\x1b[>cb", "ab"},
+		{"drops window report", "a\x1b[21tb", "ab"},
+		{"drops keyboard query", "a\x1b[?ub", "ab"},
+		{"drops DECID", "a\x1bZb", "ab"},
+		{"drops 8-bit control switch", "a\x1b Gb", "ab"},
+		{"cancelled CSI", "a\x1b[12\x18b", "ab"},
+		{"keeps safe private modes", "\x1b[?25l\x1b[?7;2004h", "\x1b[?25l\x1b[?7;2004h"},
+		{"drops mouse reporting", "a\x1b[?1000hb\x1b[?1006hc", "abc"},
+		{"drops focus reporting", "a\x1b[?1004hb", "ab"},
+		{"drops mode list with an unsafe mode", "a\x1b[?25;1000hb", "ab"},
+		{"drops keyboard lock", "a\x1b[2hb", "ab"},
+		{"keeps non-control UTF-8", "\u00a9 caf\u00e9 \u201cq\u201d", "\u00a9 caf\u00e9 \u201cq\u201d"},
+		{"drops C1 OSC title", "a\u009d0;pwned\u0007b", "ab"},
+		{"drops C1 OSC ended by C1 ST", "a\u009d52;c;?\u009cb", "ab"},
+		{"drops C1 DCS", "a\u0090$qm\u009cb", "ab"},
+		{"drops C1 device status report", "a\u009b6nb", "ab"},
+		{"keeps C1 SGR as 7-bit", "a\u009b31mb", "a\x1b[31mb"},
+		{"C1 CSI inside a CSI", "a\x1b[1;\u009b6nb", "ab"},
+	}
+	for _, tc := range cases {
+		t.Run(tc.name, func(t *testing.T) {
+			if got := Sanitize(tc.input); got != tc.want {
+				t.Errorf("Sanitize(%q) = %q, want %q", tc.input, got, tc.want)
+			}
+		})
+	}
+}
+
+func TestSanitizerAcrossChunks(t *testing.T) {
+	input := "a\x1b[32mb\x1b]52;c;?\x1b\\c\x1b[6nd\x1bP1$r\x07e\u009b6nf\u009b1mg\u00a9"
+	want := "a\x1b[32mbcdef\x1b[1mg\u00a9"
+	var st Sanitizer
+	var got []byte
+	for i := 0; i < len(input); i++ {
+		got = append(got, st.Sanitize([]byte{input[i]})...)
+	}
+	if string(got) != want {
+		t.Fatalf("chunked sanitize = %q, want %q", got, want)
+	}
+}
@@ -0,0 +1,66 @@
+package auth
+
+import (
+	"crypto/aes"
+	"crypto/cipher"
+	"crypto/hkdf"
+	"crypto/sha256"
+	"encoding/binary"
+	"fmt"
+)
+
+// handshakeContext prefixes the signature a node makes over a key exchange,
+// keeping it apart from plain identity signatures.
+const handshakeContext = "codewire node handshake v1"
+
+// HandshakeMessage is what a node signs when a client connects to it
+// without a token: its name, the client's nonce and both sides' X25519 key
+// shares. The shares are fresh for every connection, so the signature
+// vouches for this connection's key exchange and no other.
+func HandshakeMessage(node string, nonce, clientShare, nodeShare []byte) []byte {
+	msg := make([]byte, 0, len(handshakeContext)+1+len(node)+len(nonce)+len(clientShare)+len(nodeShare)+8)
+	msg = append(msg, handshakeContext...)
+	msg = append(msg, 0)
+	for _, part := range [][]byte{[]byte(node), nonce, clientShare, nodeShare} {
+		msg = binary.BigEndian.AppendUint16(msg, uint16(len(part)))
+		msg = append(msg, part...)
+	}
+	return msg
+}
+
+// TokenKey derives the key a client seals its token with from the X25519
+// shared secret of a handshake and the message the node signed for it.
+func TokenKey(secret, handshake []byte) ([]byte, error) {
+	return hkdf.Key(sha256.New, secret, handshake, "codewire token v1", 32)
+}
+
+// SealToken encrypts token under a handshake's TokenKey. Each key seals a
+// single token, so the nonce is fixed.
+func SealToken(key []byte, token string) ([]byte, error) {
+	aead, err := tokenAEAD(key)
+	if err != nil {
+		return nil, err
+	}
+	return aead.Seal(nil, make([]byte, aead.NonceSize()), []byte(token), nil), nil
+}
+
+// OpenToken decrypts a token sealed with SealToken.
+func OpenToken(key, sealed []byte) (string, error) {
+	aead, err := tokenAEAD(key)
+	if err != nil {
+		return "", err
+	}
+	token, err := aead.Open(nil, make([]byte, aead.NonceSize()), sealed, nil)
+	if err != nil {
+		return "", fmt.Errorf("token was not sealed for this connection")
+	}
+	return string(token), nil
+}
+
+func tokenAEAD(key []byte) (cipher.AEAD, error) {
+	block, err := aes.NewCipher(key)
+	if err != nil {
+		return nil, err
+	}
+	return cipher.NewGCM(block)
+}
@@ -0,0 +1,60 @@
+package auth
+
+import (
+	"crypto/subtle"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+)
+
+// Observe tokens grant read-only access to session output through the
+// node's /observe endpoint and nothing else. They are kept apart from the
+// node token so one can be embedded in a dashboard or CI page without
+// handing out control of the node.
+
+// GenerateObserveToken creates a new observe token, replacing any previous
+// one, and writes it to dataDir/observe.token with permissions 0600.
+func GenerateObserveToken(dataDir string) (string, error) {
+	token, err := randomAlphanumeric(tokenLength)
+	if err != nil {
+		return "", fmt.Errorf("generating random token: %w", err)
+	}
+
+	path := observeTokenPath(dataDir)
+	if err := os.WriteFile(path, []byte(token), 0600); err != nil {
+		return "", fmt.Errorf("writing observe token to %s: %w", path, err)
+	}
+
+	return token, nil
+}
+
+// LoadOrGenerateObserveToken returns the observe token, generating one the
+// first time it is asked for.
+func LoadOrGenerateObserveToken(dataDir string) (string, error) {
+	if data, err := os.ReadFile(observeTokenPath(dataDir)); err == nil {
+		if token := strings.TrimSpace(string(data)); token != "" {
+			return token, nil
+		}
+	}
+	return GenerateObserveToken(dataDir)
+}
+
+// ValidateObserveToken compares a candidate against the stored observe
+// token. Returns false when no observe token has been generated.
+func ValidateObserveToken(dataDir string, candidate string) bool {
+	data, err := os.ReadFile(observeTokenPath(dataDir))
+	if err != nil {
+		return false
+	}
+	stored := strings.TrimSpace(string(data))
+	candidate = strings.TrimSpace(candidate)
+	if stored == "" {
+		return false
+	}
+	return subtle.ConstantTimeCompare([]byte(stored), []byte(candidate)) == 1
+}
+
+func observeTokenPath(dataDir string) string {
+	return filepath.Join(dataDir, "observe.token")
+}
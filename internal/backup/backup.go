@@ -0,0 +1,230 @@
+// Package backup writes a node's state to a gzipped tarball and restores it
+// into a data directory, for moving a node to a new machine.
+//
+// An archive holds backup.json (the Manifest) followed by files under their
+// paths relative to the data directory: the configuration, auth token,
+// identity key, saved servers, session metadata and journal, and optionally
+// every session's logs. Sockets, PID files, caches and access logs are left
+// out.
+package backup
+
+import (
+	"archive/tar"
+	"compress/gzip"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"io/fs"
+	"os"
+	"path"
+	"path/filepath"
+	"strings"
+	"time"
+)
+
+// FormatVersion is the archive layout this build writes and reads.
+const FormatVersion = 1
+
+// manifestName is the first entry of every archive.
+const manifestName = "backup.json"
+
+// stateFiles are the files at the top of the data directory that make up a
+// node's state.
+var stateFiles = []string{
+	"config.toml",
+	"token",
+	"observe.token",
+	"node.key",
+	"servers.toml",
+	"sessions.json",
+	"sessions.journal",
+	"sessions.journal.1",
+	"sessions.journal.2",
+	"sessions.journal.3",
+	"sessions.journal.4",
+	"datadir.version",
+}
+
+// ErrNotEmpty is returned by Restore when the data directory already holds
+// session metadata and overwriting was not allowed.
+var ErrNotEmpty = errors.New("data directory already has sessions")
+
+// Manifest describes an archive.
+type Manifest struct {
+	Format         int       `json:"format"`
+	CreatedAt      time.Time `json:"created_at"`
+	Node           string    `json:"node,omitempty"`
+	Version        string    `json:"version,omitempty"`          // cw version that wrote it
+	DataDirVersion int       `json:"data_dir_version,omitempty"` // layout of the backed-up data dir
+	Logs           bool      `json:"logs"`
+	Files          []string  `json:"files"`
+}
+
+// Create writes an archive of dataDir's state to w. m supplies the node
+// details; the file list and format are filled in and the result returned.
+// The caller is responsible for the data directory not changing meanwhile
+// (see SessionManager.Quiesce); session logs are copied up to their size
+// when the archive reaches them.
+func Create(w io.Writer, dataDir string, logs bool, m Manifest) (Manifest, error) {
+	m.Format = FormatVersion
+	m.Logs = logs
+	m.Files = nil
+	if m.CreatedAt.IsZero() {
+		m.CreatedAt = time.Now().UTC()
+	}
+	for _, name := range stateFiles {
+		if _, err := os.Stat(filepath.Join(dataDir, name)); err == nil {
+			m.Files = append(m.Files, name)
+		}
+	}
+	if logs {
+		err := filepath.WalkDir(filepath.Join(dataDir, "sessions"), func(p string, d fs.DirEntry, err error) error {
+			if errors.Is(err, fs.ErrNotExist) {
+				return nil
+			}
+			if err != nil || !d.Type().IsRegular() {
+				return err
+			}
+			rel, err := filepath.Rel(dataDir, p)
+			if err != nil {
+				return err
+			}
+			m.Files = append(m.Files, filepath.ToSlash(rel))
+			return nil
+		})
+		if err != nil {
+			return m, fmt.Errorf("listing session logs: %w", err)
+		}
+	}
+
+	gz := gzip.NewWriter(w)
+	tw := tar.NewWriter(gz)
+	manifest, err := json.MarshalIndent(m, "", "  ")
+	if err != nil {
+		return m, err
+	}
+	if err := tw.WriteHeader(&tar.Header{Name: manifestName, Mode: 0o644, Size: int64(len(manifest)), ModTime: m.CreatedAt}); err != nil {
+		return m, err
+	}
+	if _, err := tw.Write(manifest); err != nil {
+		return m, err
+	}
+	for _, name := range m.Files {
+		if err := addFile(tw, dataDir, name); err != nil {
+			return m, fmt.Errorf("adding %s: %w", name, err)
+		}
+	}
+	if err := tw.Close(); err != nil {
+		return m, err
+	}
+	return m, gz.Close()
+}
+
+// addFile copies one file into the archive. A log still being written is
+// copied up to the size it had when its header was written.
+func addFile(tw *tar.Writer, dataDir, name string) error {
+	f, err := os.Open(filepath.Join(dataDir, filepath.FromSlash(name)))
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	fi, err := f.Stat()
+	if err != nil {
+		return err
+	}
+	hdr := &tar.Header{Name: name, Mode: int64(fi.Mode().Perm()), Size: fi.Size(), ModTime: fi.ModTime()}
+	if err := tw.WriteHeader(hdr); err != nil {
+		return err
+	}
+	_, err = io.CopyN(tw, f, fi.Size())
+	return err
+}
+
+// Restore unpacks an archive into dataDir. Unless overwrite is set it
+// refuses a data directory that already has session metadata. Files are
+// written with the permissions they were backed up with. The node must not
+// be running.
+func Restore(r io.Reader, dataDir string, overwrite bool) (Manifest, error) {
+	var m Manifest
+	if !overwrite {
+		if _, err := os.Stat(filepath.Join(dataDir, "sessions.json")); err == nil {
+			return m, fmt.Errorf("%w: %s", ErrNotEmpty, dataDir)
+		}
+	}
+	gz, err := gzip.NewReader(r)
+	if err != nil {
+		return m, fmt.Errorf("not a cw backup: %w", err)
+	}
+	tr := tar.NewReader(gz)
+
+	hdr, err := tr.Next()
+	if err != nil || hdr.Name != manifestName {
+		return m, fmt.Errorf("not a cw backup: missing %s", manifestName)
+	}
+	if err := json.NewDecoder(tr).Decode(&m); err != nil {
+		return m, fmt.Errorf("reading %s: %w", manifestName, err)
+	}
+	if m.Format > FormatVersion {
+		return m, fmt.Errorf("backup format %d is newer than this cw understands (%d); upgrade cw", m.Format, FormatVersion)
+	}
+
+	if err := os.MkdirAll(dataDir, 0o755); err != nil {
+		return m, err
+	}
+	for {
+		hdr, err := tr.Next()
+		if err == io.EOF {
+			return m, gz.Close()
+		}
+		if err != nil {
+			return m, fmt.Errorf("reading backup: %w", err)
+		}
+		if hdr.Typeflag != tar.TypeReg {
+			continue
+		}
+		dst, err := entryPath(dataDir, hdr.Name)
+		if err != nil {
+			return m, err
+		}
+		if err := restoreFile(tr, dst, fs.FileMode(hdr.Mode).Perm()); err != nil {
+			return m, fmt.Errorf("restoring %s: %w", hdr.Name, err)
+		}
+	}
+}
+
+// entryPath maps an archive entry to its place in dataDir, refusing paths
+// that would land outside it.
+func entryPath(dataDir, name string) (string, error) {
+	clean := path.Clean(name)
+	if path.IsAbs(clean) || clean == ".." || strings.HasPrefix(clean, "../") {
+		return "", fmt.Errorf("backup entry %q points outside the data directory", name)
+	}
+	return filepath.Join(dataDir, filepath.FromSlash(clean)), nil
+}
+
+// restoreFile writes one file through a temporary file, so an interrupted
+// restore never leaves a half-written config or key in place.
+func restoreFile(r io.Reader, dst string, mode fs.FileMode) error {
+	if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(dst), "."+filepath.Base(dst)+".restore-*")
+	if err != nil {
+		return err
+	}
+	if _, err := io.Copy(tmp, r); err != nil {
+		tmp.Close()
+		os.Remove(tmp.Name())
+		return err
+	}
+	if err := tmp.Close(); err != nil {
+		os.Remove(tmp.Name())
+		return err
+	}
+	if err := os.Chmod(tmp.Name(), mode); err != nil {
+		os.Remove(tmp.Name())
+		return err
+	}
+	return os.Rename(tmp.Name(), dst)
+}
@@ -0,0 +1,114 @@
+package backup
+
+import (
+	"archive/tar"
+	"bytes"
+	"compress/gzip"
+	"errors"
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+func writeFile(t *testing.T, path, data string, mode os.FileMode) {
+	t.Helper()
+	if err := os.MkdirAll(filepath.Dir(path), 0o755); err != nil {
+		t.Fatal(err)
+	}
+	if err := os.WriteFile(path, []byte(data), mode); err != nil {
+		t.Fatal(err)
+	}
+}
+
+func TestCreateRestoreRoundTrip(t *testing.T) {
+	src := t.TempDir()
+	writeFile(t, filepath.Join(src, "config.toml"), "[node]\nname = \"box\"\n", 0o644)
+	writeFile(t, filepath.Join(src, "token"), "secret", 0o600)
+	writeFile(t, filepath.Join(src, "sessions.json"), "[]", 0o644)
+	writeFile(t, filepath.Join(src, "sessions", "1", "output.log"), "hello\n", 0o644)
+	writeFile(t, filepath.Join(src, "codewire.sock"), "", 0o644)
+
+	for _, logs := range []bool{false, true} {
+		var buf bytes.Buffer
+		m, err := Create(&buf, src, logs, Manifest{Node: "box"})
+		if err != nil {
+			t.Fatalf("create (logs=%v): %v", logs, err)
+		}
+		want := 3
+		if logs {
+			want = 4
+		}
+		if len(m.Files) != want {
+			t.Fatalf("logs=%v: files %v, want %d", logs, m.Files, want)
+		}
+
+		dst := t.TempDir()
+		got, err := Restore(&buf, dst, false)
+		if err != nil {
+			t.Fatalf("restore (logs=%v): %v", logs, err)
+		}
+		if got.Node != "box" || got.Format != FormatVersion || got.Logs != logs {
+			t.Fatalf("manifest %+v", got)
+		}
+		fi, err := os.Stat(filepath.Join(dst, "token"))
+		if err != nil || fi.Mode().Perm() != 0o600 {
+			t.Fatalf("token restored as %v, %v", fi, err)
+		}
+		if _, err := os.Stat(filepath.Join(dst, "codewire.sock")); err == nil {
+			t.Fatal("socket was backed up")
+		}
+		_, err = os.Stat(filepath.Join(dst, "sessions", "1", "output.log"))
+		if logs != (err == nil) {
+			t.Fatalf("logs=%v: output.log restored: %v", logs, err)
+		}
+	}
+}
+
+func TestRestoreRefusesExistingSessions(t *testing.T) {
+	src := t.TempDir()
+	writeFile(t, filepath.Join(src, "sessions.json"), "[]", 0o644)
+	var buf bytes.Buffer
+	if _, err := Create(&buf, src, false, Manifest{}); err != nil {
+		t.Fatal(err)
+	}
+	archive := buf.Bytes()
+
+	dst := t.TempDir()
+	writeFile(t, filepath.Join(dst, "sessions.json"), `[{"id":1}]`, 0o644)
+	if _, err := Restore(bytes.NewReader(archive), dst, false); !errors.Is(err, ErrNotEmpty) {
+		t.Fatalf("expected ErrNotEmpty, got %v", err)
+	}
+	if _, err := Restore(bytes.NewReader(archive), dst, true); err != nil {
+		t.Fatalf("overwrite: %v", err)
+	}
+	if data, _ := os.ReadFile(filepath.Join(dst, "sessions.json")); string(data) != "[]" {
+		t.Fatalf("sessions.json = %q", data)
+	}
+}
+
+func TestRestoreRejectsPathsOutsideDataDir(t *testing.T) {
+	var buf bytes.Buffer
+	gz := gzip.NewWriter(&buf)
+	tw := tar.NewWriter(gz)
+	for _, e := range []struct{ name, body string }{
+		{manifestName, `{"format":1}`},
+		{"../escape", "x"},
+	} {
+		if err := tw.WriteHeader(&tar.Header{Name: e.name, Mode: 0o644, Size: int64(len(e.body)), Typeflag: tar.TypeReg}); err != nil {
+			t.Fatal(err)
+		}
+		if _, err := tw.Write([]byte(e.body)); err != nil {
+			t.Fatal(err)
+		}
+	}
+	tw.Close()
+	gz.Close()
+
+	dst := filepath.Join(t.TempDir(), "data")
+	if _, err := Restore(&buf, dst, false); err == nil {
+		t.Fatal("expected an error for an entry outside the data directory")
+	}
+	if _, err := os.Stat(filepath.Join(filepath.Dir(dst), "escape")); err == nil {
+		t.Fatal("entry was written outside the data directory")
+	}
+}
@@ -0,0 +1,187 @@
+// Package charset converts session output to UTF-8 for the node's text
+// APIs (logs, watch updates, the captured result) according to the
+// session's encoding. Output logs keep the bytes the session wrote; only
+// what is sent as text is converted.
+//
+// Encodings are named by their WHATWG labels ("latin1", "shift_jis",
+// "gbk"...). Encodings that are not ASCII-compatible, such as UTF-16, are
+// refused: terminal escape sequences could not be found in them.
+package charset
+
+import (
+	"bytes"
+	"errors"
+	"fmt"
+	"strings"
+	"unicode/utf8"
+
+	"golang.org/x/text/encoding"
+	"golang.org/x/text/encoding/htmlindex"
+	"golang.org/x/text/transform"
+)
+
+// UTF8 is the encoding sessions are assumed to write unless they are
+// launched with another or their locale names one.
+const UTF8 = "utf-8"
+
+// replacement stands in for bytes that are not valid in the encoding.
+const replacement = "\uFFFD"
+
+// Lookup returns the canonical name of the encoding with the given label.
+func Lookup(label string) (string, error) {
+	enc, err := htmlindex.Get(label)
+	if err != nil {
+		return "", fmt.Errorf("unknown encoding %q", label)
+	}
+	name, err := htmlindex.Name(enc)
+	if err != nil {
+		return "", fmt.Errorf("unknown encoding %q", label)
+	}
+	if strings.HasPrefix(name, "utf-16") || name == "replacement" {
+		return "", fmt.Errorf("encoding %q is not supported: session output must be ASCII-compatible", label)
+	}
+	return name, nil
+}
+
+// codesetAliases maps glibc locale codesets that are not WHATWG labels.
+var codesetAliases = map[string]string{
+	"utf8":      "utf-8",
+	"eucjp":     "euc-jp",
+	"euckr":     "euc-kr",
+	"euccn":     "gb2312",
+	"sjis":      "shift_jis",
+	"big5hkscs": "big5",
+}
+
+// FromLocale returns the encoding named by the codeset of the locale in env
+// (LC_ALL, then LC_CTYPE, then LANG, as in "ja_JP.eucJP"), or "" when the
+// locale names none this package knows.
+func FromLocale(env []string) string {
+	vars := make(map[string]string)
+	for _, kv := range env {
+		if k, v, ok := strings.Cut(kv, "="); ok {
+			vars[k] = v
+		}
+	}
+	var locale string
+	for _, k := range []string{"LC_ALL", "LC_CTYPE", "LANG"} {
+		if vars[k] != "" {
+			locale = vars[k]
+			break
+		}
+	}
+	_, codeset, ok := strings.Cut(locale, ".")
+	if !ok {
+		return ""
+	}
+	codeset, _, _ = strings.Cut(codeset, "@")
+	codeset = strings.ToLower(codeset)
+	if alias, ok := codesetAliases[codeset]; ok {
+		codeset = alias
+	} else if rest, ok := strings.CutPrefix(codeset, "iso8859"); ok {
+		codeset = "iso-8859-" + strings.TrimPrefix(rest, "-")
+	}
+	name, err := Lookup(codeset)
+	if err != nil {
+		return ""
+	}
+	return name
+}
+
+// Decoder converts a stream of output to UTF-8. A character split across
+// Decode calls is held back until the rest of it arrives.
+type Decoder struct {
+	dec     *encoding.Decoder // nil for UTF-8
+	pending []byte
+}
+
+// NewDecoder returns a Decoder for the named encoding; "" and unknown
+// names decode UTF-8.
+func NewDecoder(name string) *Decoder {
+	if name == "" || name == UTF8 {
+		return &Decoder{}
+	}
+	enc, err := htmlindex.Get(name)
+	if err != nil {
+		return &Decoder{}
+	}
+	return &Decoder{dec: enc.NewDecoder()}
+}
+
+// Decode converts p, after any bytes held back by the previous call. It
+// returns the text, how many bytes of input it stands for in total (held
+// back bytes count once they are decoded), and whether invalid bytes were
+// replaced with U+FFFD.
+func (d *Decoder) Decode(p []byte) (text []byte, consumed int, lossy bool) {
+	src := append(d.pending, p...)
+	var rest []byte
+	if d.dec == nil {
+		cut := incompleteSuffix(src)
+		text, rest = src[:cut], src[cut:]
+		if !utf8.Valid(text) {
+			text, lossy = bytes.ToValidUTF8(text, []byte(replacement)), true
+		}
+	} else {
+		var n int
+		text, n = d.transform(src)
+		rest = src[n:]
+		lossy = bytes.ContainsRune(text, utf8.RuneError)
+	}
+	consumed = len(src) - len(rest)
+	d.pending = append([]byte(nil), rest...)
+	return text, consumed, lossy
+}
+
+// Flush returns the bytes still held back, which will never be completed,
+// as U+FFFD.
+func (d *Decoder) Flush() (text []byte, consumed int, lossy bool) {
+	if len(d.pending) == 0 {
+		return nil, 0, false
+	}
+	consumed = len(d.pending)
+	d.pending = nil
+	return []byte(replacement), consumed, true
+}
+
+// transform runs the encoding's decoder over src, returning the text and
+// how much of src it covers.
+func (d *Decoder) transform(src []byte) ([]byte, int) {
+	dst := make([]byte, 3*len(src)+utf8.UTFMax)
+	for {
+		nDst, nSrc, err := d.dec.Transform(dst, src, false)
+		if errors.Is(err, transform.ErrShortDst) {
+			dst = make([]byte, 2*len(dst))
+			continue
+		}
+		if err != nil && !errors.Is(err, transform.ErrShortSrc) {
+			// Decoders replace invalid input themselves; anything else
+			// is passed through as replacement characters.
+			return append(dst[:nDst], []byte(replacement)...), len(src)
+		}
+		return dst[:nDst], nSrc
+	}
+}
+
+// incompleteSuffix returns where a UTF-8 character cut off at the end of p
+// starts, or len(p) when p does not end in one.
+func incompleteSuffix(p []byte) int {
+	for i := len(p) - 1; i >= 0 && i > len(p)-utf8.UTFMax; i-- {
+		if utf8.RuneStart(p[i]) {
+			if !utf8.FullRune(p[i:]) {
+				return i
+			}
+			break
+		}
+	}
+	return len(p)
+}
+
+// String decodes a complete piece of output, such as a whole log.
+func String(name string, p []byte) (string, bool) {
+	d := NewDecoder(name)
+	text, _, lossy := d.Decode(p)
+	if tail, _, tailLossy := d.Flush(); tail != nil {
+		text, lossy = append(text, tail...), lossy || tailLossy
+	}
+	return string(text), lossy
+}
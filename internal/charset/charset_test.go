@@ -0,0 +1,85 @@
+package charset
+
+import "testing"
+
+func TestDecodeHoldsBackSplitCharacters(t *testing.T) {
+	d := NewDecoder(UTF8)
+	in := []byte("héllo")
+	text, n, lossy := d.Decode(in[:2]) // "h" and the first byte of "é"
+	if string(text) != "h" || n != 1 || lossy {
+		t.Fatalf("first half: %q, %d, %v", text, n, lossy)
+	}
+	text, n, lossy = d.Decode(in[2:])
+	if string(text) != "éllo" || n != len(in)-1 || lossy {
+		t.Fatalf("second half: %q, %d, %v", text, n, lossy)
+	}
+}
+
+func TestDecodeReportsInvalidBytes(t *testing.T) {
+	text, lossy := String(UTF8, []byte("caf\xe9 ok"))
+	if text != "caf\uFFFD ok" || !lossy {
+		t.Fatalf("got %q, %v", text, lossy)
+	}
+	if text, lossy := String("", []byte("ok\n")); text != "ok\n" || lossy {
+		t.Fatalf("valid output: %q, %v", text, lossy)
+	}
+}
+
+func TestDecodeLegacyEncodings(t *testing.T) {
+	for _, tc := range []struct {
+		label string
+		in    string
+		want  string
+	}{
+		{"latin1", "caf\xe9", "café"},
+		{"shift_jis", "\x93\xfa\x96\x7b", "日本"},
+		{"euc-jp", "\xc6\xfc\xcb\xdc", "日本"},
+	} {
+		name, err := Lookup(tc.label)
+		if err != nil {
+			t.Fatal(err)
+		}
+		d := NewDecoder(name)
+		// Feed one byte at a time so multi-byte characters are split.
+		var got []byte
+		for i := range len(tc.in) {
+			text, _, lossy := d.Decode([]byte{tc.in[i]})
+			if lossy {
+				t.Fatalf("%s: lossy at byte %d", tc.label, i)
+			}
+			got = append(got, text...)
+		}
+		if string(got) != tc.want {
+			t.Errorf("%s: got %q, want %q", tc.label, got, tc.want)
+		}
+	}
+}
+
+func TestLookup(t *testing.T) {
+	if name, err := Lookup("UTF8"); err != nil || name != UTF8 {
+		t.Fatalf("utf8: %q, %v", name, err)
+	}
+	if _, err := Lookup("utf-16le"); err == nil {
+		t.Fatal("expected UTF-16 to be refused")
+	}
+	if _, err := Lookup("klingon"); err == nil {
+		t.Fatal("expected an unknown encoding to be refused")
+	}
+}
+
+func TestFromLocale(t *testing.T) {
+	for _, tc := range []struct {
+		env  []string
+		want string
+	}{
+		{[]string{"LANG=ja_JP.eucJP"}, "euc-jp"},
+		{[]string{"LANG=en_US.UTF-8"}, "utf-8"},
+		{[]string{"LANG=en_US.UTF-8", "LC_ALL=de_DE.ISO-8859-15@euro"}, "iso-8859-15"},
+		{[]string{"LC_CTYPE=C"}, ""},
+		{nil, ""},
+	} {
+		if got := FromLocale(tc.env); got != tc.want {
+			t.Errorf("FromLocale(%q) = %q, want %q", tc.env, got, tc.want)
+		}
+	}
+}
@@ -0,0 +1,278 @@
+package client
+
+import (
+	"bytes"
+	"fmt"
+	"path/filepath"
+	"regexp"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/BurntSushi/toml"
+
+	"github.com/codewiresh/codewire/internal/ansi"
+)
+
+// defaultExpectTimeout bounds each expect step unless the script overrides it.
+const defaultExpectTimeout = 30 * time.Second
+
+// maxScriptBuffer caps the unconsumed output an expect step searches.
+const maxScriptBuffer = 64 * 1024
+
+// AttachScript is a send/expect script run at the start of an attach, before
+// control passes to the user. Scripts are TOML:
+//
+//	timeout = "1m"          # default per expect step
+//
+//	[[step]]
+//	expect = "login:"       # wait for text (ANSI codes are ignored)
+//
+//	[[step]]
+//	sendline = "admin"      # send text followed by Enter
+//
+//	[[step]]
+//	expect_regex = "\\$ $"
+//	timeout = "10s"
+//
+//	[[step]]
+//	send = "\u0003"         # send raw bytes (here Ctrl+C)
+//
+//	[[step]]
+//	sleep = "500ms"
+type AttachScript struct {
+	Timeout string       `toml:"timeout"`
+	Steps   []ScriptStep `toml:"step"`
+
+	timeout time.Duration
+}
+
+// ScriptStep is one action of an AttachScript. Exactly one of Expect,
+// ExpectRegex, Send, SendLine or Sleep is set.
+type ScriptStep struct {
+	Expect      string `toml:"expect"`
+	ExpectRegex string `toml:"expect_regex"`
+	Send        string `toml:"send"`
+	SendLine    string `toml:"sendline"`
+	Sleep       string `toml:"sleep"`
+	Timeout     string `toml:"timeout"`
+
+	re      *regexp.Regexp
+	timeout time.Duration
+	sleep   time.Duration
+}
+
+// LoadAttachScript reads and validates a TOML attach script.
+func LoadAttachScript(path string) (*AttachScript, error) {
+	if ext := strings.ToLower(filepath.Ext(path)); ext != ".toml" {
+		return nil, fmt.Errorf("unsupported attach script %q: only TOML scripts are supported", path)
+	}
+	var script AttachScript
+	if _, err := toml.DecodeFile(path, &script); err != nil {
+		return nil, fmt.Errorf("parsing %s: %w", path, err)
+	}
+	if err := script.compile(); err != nil {
+		return nil, fmt.Errorf("%s: %w", path, err)
+	}
+	return &script, nil
+}
+
+func (s *AttachScript) compile() error {
+	if len(s.Steps) == 0 {
+		return fmt.Errorf("script has no steps")
+	}
+	s.timeout = defaultExpectTimeout
+	if s.Timeout != "" {
+		d, err := time.ParseDuration(s.Timeout)
+		if err != nil {
+			return fmt.Errorf("invalid timeout %q: %w", s.Timeout, err)
+		}
+		s.timeout = d
+	}
+	for i := range s.Steps {
+		st := &s.Steps[i]
+		set := 0
+		for _, v := range []string{st.Expect, st.ExpectRegex, st.Send, st.SendLine, st.Sleep} {
+			if v != "" {
+				set++
+			}
+		}
+		if set != 1 {
+			return fmt.Errorf("step %d: set exactly one of expect, expect_regex, send, sendline or sleep", i+1)
+		}
+		st.timeout = s.timeout
+		if st.Timeout != "" {
+			d, err := time.ParseDuration(st.Timeout)
+			if err != nil {
+				return fmt.Errorf("step %d: invalid timeout %q: %w", i+1, st.Timeout, err)
+			}
+			st.timeout = d
+		}
+		if st.ExpectRegex != "" {
+			re, err := regexp.Compile(st.ExpectRegex)
+			if err != nil {
+				return fmt.Errorf("step %d: invalid expect_regex: %w", i+1, err)
+			}
+			st.re = re
+		}
+		if st.Sleep != "" {
+			d, err := time.ParseDuration(st.Sleep)
+			if err != nil {
+				return fmt.Errorf("step %d: invalid sleep %q: %w", i+1, st.Sleep, err)
+			}
+			st.sleep = d
+		}
+	}
+	return nil
+}
+
+// match returns the offset just past the step's expected text in buf, or -1.
+func (st *ScriptStep) match(buf []byte) int {
+	if st.re != nil {
+		if loc := st.re.FindIndex(buf); loc != nil {
+			return loc[1]
+		}
+		return -1
+	}
+	if i := bytes.Index(buf, []byte(st.Expect)); i >= 0 {
+		return i + len(st.Expect)
+	}
+	return -1
+}
+
+// describe summarises a step for the status bar.
+func (st *ScriptStep) describe() string {
+	switch {
+	case st.Expect != "":
+		return fmt.Sprintf("expect %q", st.Expect)
+	case st.ExpectRegex != "":
+		return fmt.Sprintf("expect /%s/", st.ExpectRegex)
+	case st.SendLine != "":
+		return "sendline"
+	case st.Send != "":
+		return "send"
+	default:
+		return "sleep " + st.Sleep
+	}
+}
+
+// scriptRunner executes an AttachScript against a live attach. The attach
+// loop feeds it session output with Output and writes whatever arrives on
+// Send to the PTY; Progress and Done report status back.
+type scriptRunner struct {
+	script *AttachScript
+
+	mu       sync.Mutex
+	stripper ansi.Stripper
+	buf      []byte // ANSI-stripped output not yet consumed by an expect
+	notify   chan struct{}
+
+	Send     chan []byte
+	Progress chan string
+	Done     chan error
+	stop     chan struct{}
+}
+
+func newScriptRunner(script *AttachScript) *scriptRunner {
+	return &scriptRunner{
+		script:   script,
+		notify:   make(chan struct{}, 1),
+		Send:     make(chan []byte),
+		Progress: make(chan string, 1),
+		Done:     make(chan error, 1),
+		stop:     make(chan struct{}),
+	}
+}
+
+// Output records session output for expect steps. It never blocks.
+func (r *scriptRunner) Output(p []byte) {
+	r.mu.Lock()
+	r.buf = append(r.buf, r.stripper.Strip(p)...)
+	if over := len(r.buf) - maxScriptBuffer; over > 0 {
+		r.buf = append(r.buf[:0], r.buf[over:]...)
+	}
+	r.mu.Unlock()
+	select {
+	case r.notify <- struct{}{}:
+	default:
+	}
+}
+
+// Stop abandons the script, e.g. when the attach ends.
+func (r *scriptRunner) Stop() {
+	select {
+	case <-r.stop:
+	default:
+		close(r.stop)
+	}
+}
+
+// Run executes the steps in order and reports the result on Done.
+func (r *scriptRunner) Run() {
+	r.Done <- r.run()
+}
+
+func (r *scriptRunner) run() error {
+	steps := r.script.Steps
+	for i := range steps {
+		st := &steps[i]
+		r.progress(fmt.Sprintf("script %d/%d: %s", i+1, len(steps), st.describe()))
+		switch {
+		case st.Expect != "" || st.re != nil:
+			if err := r.expect(st); err != nil {
+				return fmt.Errorf("step %d: %w", i+1, err)
+			}
+		case st.Send != "" || st.SendLine != "":
+			data := st.Send
+			if st.SendLine != "" {
+				data = st.SendLine + "\r"
+			}
+			select {
+			case r.Send <- []byte(data):
+			case <-r.stop:
+				return fmt.Errorf("stopped")
+			}
+		default:
+			select {
+			case <-time.After(st.sleep):
+			case <-r.stop:
+				return fmt.Errorf("stopped")
+			}
+		}
+	}
+	return nil
+}
+
+func (r *scriptRunner) progress(msg string) {
+	select {
+	case <-r.Progress:
+	default:
+	}
+	r.Progress <- msg
+}
+
+// expect waits until the output received since the previous expect matches
+// the step, then consumes that output.
+func (r *scriptRunner) expect(st *ScriptStep) error {
+	deadline := time.NewTimer(st.timeout)
+	defer deadline.Stop()
+	for {
+		r.mu.Lock()
+		end := st.match(r.buf)
+		if end >= 0 {
+			r.buf = append(r.buf[:0], r.buf[end:]...)
+		}
+		r.mu.Unlock()
+		if end >= 0 {
+			return nil
+		}
+
+		select {
+		case <-r.notify:
+		case <-deadline.C:
+			return fmt.Errorf("timed out after %s waiting for %s", st.timeout, st.describe())
+		case <-r.stop:
+			return fmt.Errorf("stopped")
+		}
+	}
+}
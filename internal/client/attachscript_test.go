@@ -0,0 +1,59 @@
+package client
+
+import (
+	"os"
+	"path/filepath"
+	"testing"
+	"time"
+)
+
+func TestLoadAttachScriptValidates(t *testing.T) {
+	dir := t.TempDir()
+	bad := filepath.Join(dir, "bad.toml")
+	if err := os.WriteFile(bad, []byte("[[step]]\nexpect = \"a\"\nsend = \"b\"\n"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	if _, err := LoadAttachScript(bad); err == nil {
+		t.Error("expected error for a step with two actions")
+	}
+	if _, err := LoadAttachScript(filepath.Join(dir, "auto.js")); err == nil {
+		t.Error("expected error for a non-TOML script")
+	}
+}
+
+func TestScriptRunnerExpectThenSend(t *testing.T) {
+	script := &AttachScript{Steps: []ScriptStep{
+		{Expect: "login:"},
+		{SendLine: "admin"},
+		{ExpectRegex: `\$ $`, Timeout: "50ms"},
+	}}
+	if err := script.compile(); err != nil {
+		t.Fatal(err)
+	}
+	r := newScriptRunner(script)
+	defer r.Stop()
+	go r.Run()
+
+	r.Output([]byte("\x1b[1mlog"))
+	r.Output([]byte("in:\x1b[0m "))
+	select {
+	case data := <-r.Send:
+		if string(data) != "admin\r" {
+			t.Fatalf("sent %q, want %q", data, "admin\r")
+		}
+	case err := <-r.Done:
+		t.Fatalf("script ended early: %v", err)
+	case <-time.After(2 * time.Second):
+		t.Fatal("timed out waiting for send")
+	}
+
+	// The prompt never appears, so the last expect times out.
+	select {
+	case err := <-r.Done:
+		if err == nil {
+			t.Fatal("expected timeout error")
+		}
+	case <-time.After(2 * time.Second):
+		t.Fatal("timed out waiting for script to finish")
+	}
+}
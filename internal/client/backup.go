@@ -0,0 +1,56 @@
+package client
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// Backup asks the target's node for an archive of its state and writes it
+// to w, returning the number of files in it. logs includes every session's
+// output and event logs.
+func Backup(target *Target, logs bool, w io.Writer) (uint, error) {
+	reader, writer, err := target.Connect()
+	if err != nil {
+		return 0, err
+	}
+	defer reader.Close()
+	defer writer.Close()
+	if err := writer.SendRequest(&protocol.Request{Type: "Backup", Transcripts: logs}); err != nil {
+		return 0, fmt.Errorf("sending backup request: %w", err)
+	}
+
+	for {
+		frame, err := reader.ReadFrame()
+		if err != nil {
+			return 0, fmt.Errorf("reading backup: %w", err)
+		}
+		if frame == nil {
+			return 0, fmt.Errorf("connection closed before the backup finished")
+		}
+		if frame.Type == protocol.FrameData {
+			if _, err := w.Write(frame.Payload); err != nil {
+				return 0, err
+			}
+			continue
+		}
+		var resp protocol.Response
+		if err := json.Unmarshal(frame.Payload, &resp); err != nil {
+			return 0, fmt.Errorf("parsing response: %w", err)
+		}
+		switch resp.Type {
+		case "BackupDone":
+			var files uint
+			if resp.Count != nil {
+				files = *resp.Count
+			}
+			return files, nil
+		case "Error":
+			return 0, fmt.Errorf("%s", formatError(resp.Message))
+		default:
+			return 0, fmt.Errorf("unexpected response type: %s", resp.Type)
+		}
+	}
+}
@@ -0,0 +1,44 @@
+package client
+
+// bookmarkKey is the prefix key (Ctrl+B m) that drops a bookmark at the
+// current end of the session's output, after asking for its name in the
+// status bar.
+const bookmarkKey = 'm'
+
+// maxBookmarkName is the node's limit on bookmark names.
+const maxBookmarkName = 32
+
+// bookmarkPrompt collects a bookmark name while attached. Typed input goes
+// to the prompt instead of the session until Enter saves the bookmark (an
+// empty name lets the node pick one) or Esc or Ctrl+C cancels it.
+type bookmarkPrompt struct {
+	name []byte
+}
+
+// feed takes typed bytes. done is set once the prompt is finished, and
+// save when the bookmark should be added.
+func (p *bookmarkPrompt) feed(input []byte) (done, save bool) {
+	for _, b := range input {
+		switch {
+		case b == '\r' || b == '\n':
+			return true, true
+		case b == 0x1b || b == 0x03:
+			return true, false
+		case b == 0x7f || b == 0x08:
+			if len(p.name) > 0 {
+				p.name = p.name[:len(p.name)-1]
+			}
+		case b > 0x20 && b < 0x7f && len(p.name) < maxBookmarkName:
+			p.name = append(p.name, b)
+		case b == ' ' && len(p.name) > 0 && len(p.name) < maxBookmarkName:
+			// Names cannot contain spaces; use the usual separator.
+			p.name = append(p.name, '-')
+		}
+	}
+	return false, false
+}
+
+// notice is the prompt as shown in the status bar.
+func (p *bookmarkPrompt) notice() string {
+	return "bookmark: " + string(p.name) + "_ (Enter saves, Esc cancels)"
+}
@@ -0,0 +1,22 @@
+package client
+
+import "testing"
+
+func TestBookmarkPrompt(t *testing.T) {
+	var p bookmarkPrompt
+	if done, _ := p.feed([]byte("fix attempt")); done {
+		t.Fatal("prompt finished before Enter")
+	}
+	if done, _ := p.feed([]byte("\x7f-2")); done {
+		t.Fatal("prompt finished before Enter")
+	}
+	done, save := p.feed([]byte("\r"))
+	if !done || !save || string(p.name) != "fix-attemp-2" {
+		t.Fatalf("got done=%v save=%v name=%q", done, save, p.name)
+	}
+
+	p = bookmarkPrompt{}
+	if done, save := p.feed([]byte("x\x1b")); !done || save {
+		t.Fatalf("Esc should cancel: done=%v save=%v", done, save)
+	}
+}
@@ -2,11 +2,15 @@ package client
 
 import (
 	"context"
+	"crypto/ed25519"
 	"encoding/json"
 	"fmt"
 	"net"
+	"net/url"
 	"path/filepath"
 	"strings"
+	"sync/atomic"
+	"time"
 
 	"nhooyr.io/websocket"
 
@@ -20,14 +24,61 @@ type Target struct {
 	Local string // dataDir path (empty if remote)
 	URL   string // ws:// or wss:// URL for remote
 	Token string // auth token for remote
+	Name  string // servers.toml entry the target came from, if any
+
+	// Timeout, when set, bounds each request/response exchange. The node is
+	// sent the matching deadline so it drops work the client has stopped
+	// waiting for.
+	Timeout time.Duration
+
+	// Replica reads sessions from the node's copy of its primary (see
+	// [standby]) rather than its own. Only list, status and logs work so.
+	Replica bool
+
+	// NodeKey is the node identity key pinned for a saved server. When set,
+	// Connect only sends the token to a node that proves it holds the key.
+	NodeKey ed25519.PublicKey
 }
 
 // IsLocal returns true when the target is a local Unix socket connection.
 func (t *Target) IsLocal() bool { return t.Local != "" }
 
+// NodeName labels the node a target points at: "local" for the local
+// socket, the servers.toml name for saved servers, otherwise the host of
+// the remote URL. It is the prefix of node:id session references.
+func (t *Target) NodeName() string {
+	switch {
+	case t.IsLocal():
+		return "local"
+	case t.Name != "":
+		return t.Name
+	}
+	if u, err := url.Parse(t.URL); err == nil && u.Host != "" {
+		return u.Host
+	}
+	return t.URL
+}
+
 // Connect establishes a connection to the target and returns a FrameReader
-// and FrameWriter pair. The caller is responsible for closing both.
+// and FrameWriter pair. The caller is responsible for closing both. A remote
+// node must prove its identity on the connection before it is sent the
+// token (see authenticate).
 func (t *Target) Connect() (connection.FrameReader, connection.FrameWriter, error) {
+	reader, writer, err := t.dial()
+	if err != nil || t.IsLocal() {
+		return reader, writer, err
+	}
+	if err := t.authenticate(reader, writer); err != nil {
+		reader.Close()
+		writer.Close()
+		return nil, nil, err
+	}
+	return reader, writer, nil
+}
+
+// dial connects to the target without authenticating: a remote target is
+// not sent its token.
+func (t *Target) dial() (connection.FrameReader, connection.FrameWriter, error) {
 	if t.IsLocal() {
 		sockPath := filepath.Join(t.Local, "codewire.sock")
 		conn, err := net.Dial("unix", sockPath)
@@ -54,15 +105,8 @@ func (t *Target) Connect() (connection.FrameReader, connection.FrameWriter, erro
 		wsURL += "/ws"
 	}
 
-	// Send token via Authorization header only (not in URL query to avoid log exposure).
 	ctx := context.Background()
-	opts := &websocket.DialOptions{}
-	if t.Token != "" {
-		opts.HTTPHeader = make(map[string][]string)
-		opts.HTTPHeader["Authorization"] = []string{"Bearer " + t.Token}
-	}
-
-	conn, _, err := websocket.Dial(ctx, wsURL, opts)
+	conn, _, err := websocket.Dial(ctx, wsURL, nil)
 	if err != nil {
 		return nil, nil, fmt.Errorf("connecting to remote server: %w", err)
 	}
@@ -82,11 +126,25 @@ func requestResponse(target *Target, req *protocol.Request) (*protocol.Response,
 	defer reader.Close()
 	defer writer.Close()
 
+	req.Replica = target.Replica
+	var timedOut atomic.Bool
+	if target.Timeout > 0 {
+		target.stampDeadline(req)
+		timer := time.AfterFunc(target.Timeout, func() {
+			timedOut.Store(true)
+			reader.Close()
+		})
+		defer timer.Stop()
+	}
+
 	if err := writer.SendRequest(req); err != nil {
 		return nil, fmt.Errorf("sending request: %w", err)
 	}
 
 	frame, err := reader.ReadFrame()
+	if (err != nil || frame == nil) && timedOut.Load() {
+		return nil, fmt.Errorf("no response from node within %s", target.Timeout)
+	}
 	if err != nil {
 		return nil, fmt.Errorf("reading response: %w", err)
 	}
@@ -104,6 +162,16 @@ func requestResponse(target *Target, req *protocol.Request) (*protocol.Response,
 	return &resp, nil
 }
 
+// stampDeadline sends the target's Timeout with req, so the node stops
+// working on it when the client stops waiting. Streaming requests that wait
+// on the node use it so the node ends the wait in time.
+func (t *Target) stampDeadline(req *protocol.Request) {
+	if t.Timeout > 0 {
+		ms := uint64(t.Timeout / time.Millisecond)
+		req.TimeoutMs = &ms
+	}
+}
+
 // formatError appends helpful hints to common error messages.
 func formatError(message string) string {
 	lower := strings.ToLower(message)
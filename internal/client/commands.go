@@ -1,9 +1,12 @@
 package client
 
 import (
+	"bufio"
 	"bytes"
+	"cmp"
 	"context"
 	"encoding/json"
+	"errors"
 	"fmt"
 	"io"
 	"math"
@@ -12,6 +15,7 @@ import (
 	"os/exec"
 	"os/signal"
 	"path/filepath"
+	"slices"
 	"sort"
 	"strconv"
 	"strings"
@@ -21,13 +25,17 @@ import (
 
 	"github.com/BurntSushi/toml"
 	qrcode "github.com/skip2/go-qrcode"
+	"golang.org/x/sys/unix"
+	"golang.org/x/term"
 
+	"github.com/codewiresh/codewire/internal/ansi"
 	"github.com/codewiresh/codewire/internal/config"
 	"github.com/codewiresh/codewire/internal/connection"
 	"github.com/codewiresh/codewire/internal/protocol"
 	"github.com/codewiresh/codewire/internal/relay"
 	"github.com/codewiresh/codewire/internal/statusbar"
 	"github.com/codewiresh/codewire/internal/terminal"
+	"github.com/codewiresh/codewire/internal/textwidth"
 )
 
 // ResolveSessionArg resolves a session argument that can be either a numeric ID
@@ -37,6 +45,14 @@ func ResolveSessionArg(target *Target, arg string) (uint32, error) {
 	// Strip leading @ if present.
 	name := strings.TrimPrefix(arg, "@")
 
+	// Accept node:id references as printed by `cw list` for remote targets.
+	if node, id, ok := SplitSessionRef(name); ok {
+		if node != target.NodeName() {
+			return 0, fmt.Errorf("session %s is on node %q, but cw is connected to %q", arg, node, target.NodeName())
+		}
+		return id, nil
+	}
+
 	// Try numeric ID first.
 	if parsed, err := strconv.ParseUint(name, 10, 32); err == nil {
 		return uint32(parsed), nil
@@ -61,6 +77,30 @@ func ResolveSessionArg(target *Target, arg string) (uint32, error) {
 	return 0, fmt.Errorf("no session named %q", name)
 }
 
+// SplitSessionRef splits a node:id session reference such as "gpu-box:3".
+// Only numeric IDs are qualified, so tags and names containing ':' are left
+// alone.
+func SplitSessionRef(arg string) (node string, id uint32, ok bool) {
+	i := strings.LastIndex(arg, ":")
+	if i <= 0 {
+		return "", 0, false
+	}
+	parsed, err := strconv.ParseUint(arg[i+1:], 10, 32)
+	if err != nil {
+		return "", 0, false
+	}
+	return arg[:i], uint32(parsed), true
+}
+
+// sessionRef formats a session ID the way the user should refer to it:
+// bare for the local node, node:id for remote targets.
+func sessionRef(target *Target, id uint32) string {
+	if target.IsLocal() {
+		return strconv.FormatUint(uint64(id), 10)
+	}
+	return fmt.Sprintf("%s:%d", target.NodeName(), id)
+}
+
 // ResolveSessionOrTag tries to resolve arg as a session ID/name, then as a tag.
 // Returns (sessionID, tags, err). Exactly one of sessionID or tags will be non-nil/non-empty.
 func ResolveSessionOrTag(target *Target, arg string) (*uint32, []string, error) {
@@ -98,12 +138,19 @@ func ResolveSessionOrTag(target *Target, arg string) (*uint32, []string, error)
 // List
 // ---------------------------------------------------------------------------
 
-// List retrieves sessions, optionally filtered by status.
-func List(target *Target, jsonOutput bool, statusFilter string) error {
+// List retrieves sessions, optionally filtered by status and by tags (a
+// session must carry all of them).
+func List(target *Target, jsonOutput bool, statusFilter string, tags []string, table TableOptions) error {
+	if err := ValidateTableOptions(table); err != nil {
+		return err
+	}
 	sessions, err := ListFiltered(target, statusFilter)
 	if err != nil {
 		return err
 	}
+	sessions = slices.DeleteFunc(sessions, func(s protocol.SessionInfo) bool {
+		return slices.ContainsFunc(tags, func(tag string) bool { return !slices.Contains(s.Tags, tag) })
+	})
 	if jsonOutput {
 		data, err := json.MarshalIndent(sessions, "", "  ")
 		if err != nil {
@@ -116,8 +163,11 @@ func List(target *Target, jsonOutput bool, statusFilter string) error {
 		fmt.Println("No sessions")
 		return nil
 	}
-	printSessionTable(sessions)
-	return nil
+	if table.Node == "" {
+		table.Node = target.NodeName()
+	}
+	table.QualifyIDs = !target.IsLocal()
+	return printSessionTableWith(sessions, table)
 }
 
 // ListFiltered returns sessions filtered by status: "all", "running", "completed", "killed".
@@ -132,6 +182,7 @@ func ListFiltered(target *Target, statusFilter string) ([]protocol.SessionInfo,
 	if resp.Sessions == nil {
 		return nil, fmt.Errorf("unexpected response type: %s", resp.Type)
 	}
+	noteReplica(resp.Standby)
 	sessions := *resp.Sessions
 	if statusFilter == "" || statusFilter == "all" {
 		return sessions, nil
@@ -149,22 +200,221 @@ func ListFiltered(target *Target, statusFilter string) ([]protocol.SessionInfo,
 	return filtered, nil
 }
 
+// Find prints the sessions matching a fuzzy query, best match first.
+func Find(target *Target, query string, limit uint32, jsonOutput bool, table TableOptions) error {
+	if err := ValidateTableOptions(table); err != nil {
+		return err
+	}
+	sessions, err := FindSessions(target, query, limit)
+	if err != nil {
+		return err
+	}
+	if jsonOutput {
+		data, err := json.MarshalIndent(sessions, "", "  ")
+		if err != nil {
+			return err
+		}
+		fmt.Println(string(data))
+		return nil
+	}
+	if len(sessions) == 0 {
+		fmt.Println("No matching sessions")
+		return nil
+	}
+	if table.Node == "" {
+		table.Node = target.NodeName()
+	}
+	table.QualifyIDs = !target.IsLocal()
+	return printSessionTableWith(sessions, table)
+}
+
+// FindSessions asks the node for the sessions whose name, tags, prompt or
+// working directory fuzzy-match query, best match first. The matching runs
+// on the node, so only the matches are transferred.
+func FindSessions(target *Target, query string, limit uint32) ([]protocol.SessionInfo, error) {
+	resp, err := requestResponse(target, &protocol.Request{Type: "FindSessions", Query: query, Limit: limit})
+	if err != nil {
+		return nil, err
+	}
+	if resp.Type == "Error" {
+		return nil, fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Sessions == nil {
+		return nil, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	return *resp.Sessions, nil
+}
+
 // ---------------------------------------------------------------------------
 // Run
 // ---------------------------------------------------------------------------
 
+// RunOptions holds optional launch settings for Run.
+type RunOptions struct {
+	// Snapshot captures a working directory snapshot when the session exits.
+	Snapshot bool
+	// SnapshotCommand overrides the node's default snapshot command.
+	SnapshotCommand string
+	// Script is a shell command line run through the node's configured
+	// shell. When set, command may be empty.
+	Script string
+	// Workspace runs the session in a fresh directory on the node, filled
+	// from WorkspaceSource (git URL or template directory) if set.
+	Workspace       bool
+	WorkspaceSource string
+	// Notes are shown to everyone who attaches to the session.
+	Notes []string
+	// MaxRuntime and IdleTimeout (Go durations) limit the session; empty
+	// uses the node's defaults.
+	MaxRuntime  string
+	IdleTimeout string
+	// Term sets TERM for the session; empty uses the node's default.
+	Term string
+	// Encoding is the character encoding the session writes; empty takes
+	// it from the session's locale, else UTF-8.
+	Encoding string
+	// Priority is the session's priority class ("high", "normal" or
+	// "background"); empty is normal.
+	Priority string
+	// Mirrors copy the session's output to extra sinks ("file:/path" or
+	// "cmd:command").
+	Mirrors []string
+	// Cols and Rows start the session's PTY at this size (see AttachSize);
+	// zero uses the node's default.
+	Cols, Rows uint16
+	// History limits what the node records: "inputs" or "none". Empty
+	// records everything but input.
+	History string
+	// RecordInput keeps the session's input, attach keystrokes included,
+	// for cw replay.
+	RecordInput bool
+}
+
 // Run launches a new session on the node with the given command, working
 // directory, and optional tags. If name is non-empty, the session is assigned
-// that name for addressing.
-func Run(target *Target, command []string, workingDir string, name string, env []string, stdinData []byte, tags ...string) error {
+// that name for addressing. The new session ID is returned so callers can
+// chain further commands (e.g. attach) without a list round-trip.
+func Run(target *Target, command []string, workingDir string, name string, env []string, stdinData []byte, opts RunOptions, tags ...string) (uint32, error) {
+	req := runRequest(command, workingDir, name, env, stdinData, opts, tags)
+	resp, err := requestResponse(target, req)
+	if err != nil {
+		return 0, err
+	}
+	if resp.Type == "Error" {
+		return 0, fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "Launched" || resp.ID == nil {
+		return 0, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+
+	display := strings.Join(command, " ")
+	if opts.Script != "" {
+		display = opts.Script
+	}
+	fmt.Fprintf(os.Stderr, "Session %d launched: %s\n", *resp.ID, display)
+	return *resp.ID, nil
+}
+
+// runRequest builds the Launch request for Run.
+func runRequest(command []string, workingDir string, name string, env []string, stdinData []byte, opts RunOptions, tags []string) *protocol.Request {
+	req := &protocol.Request{
+		Type:            "Launch",
+		Command:         command,
+		WorkingDir:      workingDir,
+		Name:            name,
+		Env:             env,
+		StdinData:       stdinData,
+		Tags:            tags,
+		Snapshot:        opts.Snapshot,
+		SnapshotCommand: opts.SnapshotCommand,
+		Script:          opts.Script,
+		Workspace:       opts.Workspace,
+		WorkspaceSource: opts.WorkspaceSource,
+		Notes:           opts.Notes,
+		MaxRuntime:      opts.MaxRuntime,
+		IdleTimeout:     opts.IdleTimeout,
+		Term:            opts.Term,
+		Encoding:        opts.Encoding,
+		Priority:        opts.Priority,
+		Mirrors:         opts.Mirrors,
+		History:         opts.History,
+		RecordInput:     opts.RecordInput,
+	}
+	if opts.Cols > 0 && opts.Rows > 0 {
+		req.Cols, req.Rows = &opts.Cols, &opts.Rows
+	}
+	return req
+}
+
+// batchJob is one line of a `cw run --batch` file.
+type batchJob struct {
+	Command  []string          `json:"command"`
+	Shell    string            `json:"shell"`
+	Dir      string            `json:"dir"`
+	Env      map[string]string `json:"env"`
+	Name     string            `json:"name"`
+	Tags     []string          `json:"tags"`
+	Priority string            `json:"priority"`
+}
+
+// RunBatch reads JSONL launch descriptions from r (blank lines and lines
+// starting with # are skipped), submits them in one LaunchBatch request, and
+// prints the assigned session IDs. Jobs without a dir run in defaultDir.
+// With dryRun the batch is only validated (see ValidateRun).
+func RunBatch(target *Target, r io.Reader, defaultDir string, dryRun bool) error {
+	var launches []protocol.Request
+	scanner := bufio.NewScanner(r)
+	scanner.Buffer(make([]byte, 1024*1024), 1024*1024)
+	lineNo := 0
+	for scanner.Scan() {
+		lineNo++
+		line := strings.TrimSpace(scanner.Text())
+		if line == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+		var job batchJob
+		if err := json.Unmarshal([]byte(line), &job); err != nil {
+			return fmt.Errorf("line %d: %w", lineNo, err)
+		}
+		if len(job.Command) == 0 && job.Shell == "" {
+			return fmt.Errorf("line %d: command or shell required", lineNo)
+		}
+		if len(job.Command) > 0 && job.Shell != "" {
+			return fmt.Errorf("line %d: use either command or shell, not both", lineNo)
+		}
+		dir := job.Dir
+		if dir == "" {
+			dir = defaultDir
+		}
+		env := make([]string, 0, len(job.Env))
+		for k, v := range job.Env {
+			env = append(env, k+"="+v)
+		}
+		sort.Strings(env)
+		launches = append(launches, protocol.Request{
+			Type:       "Launch",
+			Command:    job.Command,
+			Script:     job.Shell,
+			WorkingDir: dir,
+			Env:        env,
+			Name:       job.Name,
+			Tags:       job.Tags,
+			Priority:   job.Priority,
+		})
+	}
+	if err := scanner.Err(); err != nil {
+		return fmt.Errorf("reading batch: %w", err)
+	}
+	if len(launches) == 0 {
+		return fmt.Errorf("batch contains no launches")
+	}
+	if dryRun {
+		return validateLaunch(target, &protocol.Request{Launches: launches})
+	}
+
 	resp, err := requestResponse(target, &protocol.Request{
-		Type:       "Launch",
-		Command:    command,
-		WorkingDir: workingDir,
-		Name:       name,
-		Env:        env,
-		StdinData:  stdinData,
-		Tags:       tags,
+		Type:     "LaunchBatch",
+		Launches: launches,
 	})
 	if err != nil {
 		return err
@@ -172,12 +422,31 @@ func Run(target *Target, command []string, workingDir string, name string, env [
 	if resp.Type == "Error" {
 		return fmt.Errorf("%s", formatError(resp.Message))
 	}
-	if resp.Type != "Launched" || resp.ID == nil {
+	if resp.Type != "BatchLaunched" || resp.Results == nil {
 		return fmt.Errorf("unexpected response type: %s", resp.Type)
 	}
 
-	display := strings.Join(command, " ")
-	fmt.Fprintf(os.Stderr, "Session %d launched: %s\n", *resp.ID, display)
+	failed := 0
+	fmt.Printf("%-4s %-6s %-20s %s\n", "#", "ID", "NAME", "COMMAND")
+	for i, res := range *resp.Results {
+		display := launches[i].Script
+		if display == "" {
+			display = strings.Join(launches[i].Command, " ")
+		}
+		name := res.Name
+		if name == "" {
+			name = "-"
+		}
+		if res.ID == nil {
+			failed++
+			fmt.Printf("%-4d %-6s %s error: %s\n", i+1, "-", textwidth.Pad(name, 20), res.Error)
+			continue
+		}
+		fmt.Printf("%-4d %-6d %s %s\n", i+1, *res.ID, textwidth.Pad(name, 20), display)
+	}
+	if failed > 0 {
+		return fmt.Errorf("%d of %d launches failed", failed, len(launches))
+	}
 	return nil
 }
 
@@ -189,6 +458,7 @@ func Run(target *Target, command []string, workingDir string, name string, env [
 type stdinEvent struct {
 	detach  bool
 	forward []byte
+	keys    []byte // bound prefix keys pressed (see DetachDetector.Bind)
 	err     error
 }
 
@@ -198,41 +468,303 @@ type frameEvent struct {
 	err   error
 }
 
+// AttachSize returns the PTY size that attaching from this terminal will
+// set, accounting for the status bar, so a session launched to be attached
+// can start at that size. ok is false when stdout is not a terminal.
+func AttachSize() (cols, rows uint16, ok bool) {
+	cols, rows, err := terminal.TerminalSize()
+	if err != nil {
+		return 0, 0, false
+	}
+	cols, rows = statusbar.New(0, cols, rows).PtySize()
+	return cols, rows, true
+}
+
+// AttachOptions controls optional attach behaviour.
+type AttachOptions struct {
+	// NoHistory skips replaying the session's scrollback on attach.
+	NoHistory bool
+	// HistoryLines limits the replay to the last N lines of the session's
+	// log; nil replays all of it. The node reads only the end of the log.
+	HistoryLines *uint
+	// ExitOnComplete makes the client exit with the child's exit code when
+	// the session ends while attached, instead of always exiting 0.
+	ExitOnComplete bool
+	// ConfirmEnter holds each Enter until it is pressed a second time (or
+	// sent as Ctrl+B Enter); see terminal.EnterGuard.
+	ConfirmEnter bool
+	// LocalEcho shows typed characters before the session echoes them; see
+	// terminal.LocalEcho.
+	LocalEcho bool
+	// LatencyDir is the client data directory where measured round trips
+	// are recorded for `cw server test`. Empty disables recording.
+	LatencyDir string
+	// Raw passes bytes through unchanged: no status bar, no detach key and
+	// no other input handling. See attachRaw.
+	Raw bool
+	// Script runs a send/expect script before the user gets control. Typed
+	// input other than the detach key is ignored while it runs.
+	Script *AttachScript
+	// Colors is the terminal's color depth ("truecolor", "256" or "16"),
+	// detected from COLORTERM and TERM when empty. The node downconverts
+	// output colors to it. Raw attaches are never converted.
+	Colors string
+	// Latest picks the newest running session, attached or not, when no
+	// session is given, instead of the oldest unattached one.
+	Latest bool
+	// FollowNew keeps the client attached across sessions: when the
+	// session ends, it waits for the next session launched on the node and
+	// attaches to that, until the user detaches. Not supported with Raw.
+	FollowNew bool
+}
+
+// errSessionEnded ends one attach of a FollowNew loop when its session
+// finishes.
+var errSessionEnded = errors.New("session ended")
+
+// errStopWaiting ends a FollowNew loop when the user detaches while it waits
+// for the next session.
+var errStopWaiting = errors.New("stopped waiting for a new session")
+
+// localEchoExpiry is how long a local echo prediction may stay unconfirmed
+// before it is erased.
+const localEchoExpiry = 2 * time.Second
+
+// clientsBannerDuration is how long the status bar shows that another
+// client attached or detached.
+const clientsBannerDuration = 5 * time.Second
+
 // Attach connects to a session's PTY. If id is nil, the oldest running
-// unattached session is selected automatically. The terminal is put into raw
-// mode and a status bar is drawn at the bottom of the screen.
-func Attach(target *Target, id *uint32, noHistory bool) error {
+// unattached session is selected automatically (the newest running one with
+// opts.Latest). The terminal is put into raw mode and a status bar is drawn
+// at the bottom of the screen. It returns the exit code the caller should
+// exit with, which is non-zero only when the session failed and
+// opts.ExitOnComplete is set, or in raw mode.
+func Attach(target *Target, id *uint32, opts AttachOptions) (int, error) {
+	if !opts.FollowNew {
+		_, code, err := attachSession(target, id, opts, nil)
+		return code, err
+	}
+	if opts.Raw {
+		return 0, fmt.Errorf("following new sessions is not supported in raw mode")
+	}
+
+	// One stdin reader serves every attach, so no keystroke is lost to a
+	// reader left behind by the previous session.
+	stop := make(chan struct{})
+	defer close(stop)
+	stdinCh := readStdin(stop)
+	for {
+		last, code, err := attachSession(target, id, opts, stdinCh)
+		if !errors.Is(err, errSessionEnded) {
+			return code, err
+		}
+		fmt.Fprintf(os.Stderr, "[cw] waiting for a new session (Ctrl+B d to stop)...\n")
+		next, err := waitNewSession(target, last, stdinCh)
+		if errors.Is(err, errStopWaiting) {
+			fmt.Fprintf(os.Stderr, "[cw] stopped following new sessions\n")
+			return 0, nil
+		}
+		if err != nil {
+			return 0, err
+		}
+		id = &next
+	}
+}
+
+// waitNewSession returns the ID of the first running session newer than
+// last, waiting for one to be launched if there is none yet. Meanwhile the
+// terminal stays in raw mode: Ctrl+B d or Ctrl+C stops waiting with
+// errStopWaiting, and anything else typed is discarded rather than sent to
+// the next session.
+func waitNewSession(target *Target, last uint32, stdinCh <-chan stdinEvent) (uint32, error) {
+	reader, writer, err := target.Connect()
+	if err != nil {
+		return 0, err
+	}
+	defer reader.Close()
+	defer writer.Close()
+
+	// Subscribe, and wait until the node has the subscription in place,
+	// before listing, so a launch in between is not missed.
+	if err := writer.SendRequest(&protocol.Request{
+		Type:       "Subscribe",
+		EventTypes: []string{"session.created"},
+	}); err != nil {
+		return 0, err
+	}
+	ack, err := readControlResponse(reader)
+	if err != nil {
+		return 0, err
+	}
+	if ack.Type == "Error" {
+		return 0, fmt.Errorf("%s", formatError(ack.Message))
+	}
+	if ack.Type != "SubscribeAck" {
+		return 0, fmt.Errorf("unexpected response type: %s", ack.Type)
+	}
+	resp, err := requestResponse(target, &protocol.Request{Type: "ListSessions"})
+	if err != nil {
+		return 0, err
+	}
+	if resp.Sessions != nil {
+		for _, s := range *resp.Sessions {
+			if s.ID > last && s.Status == "running" {
+				return s.ID, nil
+			}
+		}
+	}
+
+	guard, err := terminal.EnableRawMode()
+	if err != nil {
+		return 0, fmt.Errorf("enabling raw mode: %w", err)
+	}
+	defer guard.Restore()
+
+	done := make(chan struct{})
+	defer close(done)
+	frameCh := make(chan frameEvent, 1)
+	go func() {
+		for {
+			f, readErr := reader.ReadFrame()
+			select {
+			case frameCh <- frameEvent{frame: f, err: readErr}:
+			case <-done:
+				return
+			}
+			if readErr != nil || f == nil {
+				return
+			}
+		}
+	}()
+
+	for {
+		select {
+		case fe := <-frameCh:
+			if fe.err != nil {
+				return 0, fe.err
+			}
+			if fe.frame == nil {
+				return 0, fmt.Errorf("connection closed while waiting for a new session")
+			}
+			if fe.frame.Type != protocol.FrameControl {
+				continue
+			}
+			var ev protocol.Response
+			if err := json.Unmarshal(fe.frame.Payload, &ev); err != nil {
+				continue
+			}
+			switch ev.Type {
+			case "Event":
+				if ev.SessionID != nil && *ev.SessionID > last {
+					return *ev.SessionID, nil
+				}
+			case "Error":
+				return 0, fmt.Errorf("%s", formatError(ev.Message))
+			}
+		case se := <-stdinCh:
+			if se.detach || bytes.IndexByte(se.forward, 0x03) >= 0 {
+				return 0, errStopWaiting
+			}
+			if se.err != nil {
+				// Without a terminal, keep waiting for the session.
+				stdinCh = nil
+			}
+		}
+	}
+}
+
+// stdinPollInterval is how often a stdin reader waiting for input checks
+// whether it has been stopped.
+const stdinPollInterval = 100 * time.Millisecond
+
+// readStdin reads the terminal on a goroutine, splitting out the detach key,
+// until stop is closed. It only reads once input is waiting, so a stopped
+// reader leaves later keystrokes to whoever reads the terminal next, such
+// as the cw repl prompt.
+func readStdin(stop <-chan struct{}) <-chan stdinEvent {
+	detector := terminal.NewDetachDetector()
+	detector.Bind(string([]rune{infoOverlayKey, bookmarkKey}))
+	stdinCh := make(chan stdinEvent, 1)
+	send := func(ev stdinEvent) bool {
+		select {
+		case stdinCh <- ev:
+			return true
+		case <-stop:
+			return false
+		}
+	}
+	go func() {
+		fds := []unix.PollFd{{Fd: int32(os.Stdin.Fd()), Events: unix.POLLIN}}
+		for {
+			select {
+			case <-stop:
+				return
+			default:
+			}
+			if n, err := unix.Poll(fds, int(stdinPollInterval/time.Millisecond)); n == 0 || err == unix.EINTR {
+				continue
+			}
+			buf := make([]byte, 4096)
+			n, readErr := os.Stdin.Read(buf)
+			if n > 0 {
+				detach, fwd := detector.FeedBuf(buf[:n])
+				if !send(stdinEvent{detach: detach, forward: fwd, keys: detector.TakePressed(), err: nil}) || detach {
+					return
+				}
+			}
+			if readErr != nil {
+				send(stdinEvent{err: readErr})
+				return
+			}
+		}
+	}()
+	return stdinCh
+}
+
+// attachSession runs a single attach and returns the ID of the session it
+// attached to. stdinCh is the terminal reader to use; nil starts one. With
+// opts.FollowNew, the end of the session is returned as errSessionEnded
+// instead of exiting.
+func attachSession(target *Target, id *uint32, opts AttachOptions, stdinCh <-chan stdinEvent) (uint32, error) {
 	// ---------------------------------------------------------------
 	// Step 1: auto-select session if no ID given
 	// ---------------------------------------------------------------
 	if id == nil {
 		resp, err := requestResponse(target, &protocol.Request{Type: "ListSessions"})
 		if err != nil {
-			return err
+			return 0, 0, err
 		}
 		if resp.Type == "Error" {
-			return fmt.Errorf("%s", formatError(resp.Message))
+			return 0, 0, fmt.Errorf("%s", formatError(resp.Message))
 		}
 		if resp.Sessions == nil {
-			return fmt.Errorf("unexpected response type: %s", resp.Type)
+			return 0, 0, fmt.Errorf("unexpected response type: %s", resp.Type)
 		}
 		sessions := *resp.Sessions
 
 		// Filter running and unattached.
 		var candidates []protocol.SessionInfo
 		for _, s := range sessions {
-			if s.Status == "running" && !s.Attached {
+			if s.Status == "running" && (opts.Latest || !s.Attached) {
 				candidates = append(candidates, s)
 			}
 		}
 		if len(candidates) == 0 {
-			return fmt.Errorf("no running unattached sessions available\n\nUse 'cw list' to see active sessions")
+			if opts.Latest {
+				return 0, 0, fmt.Errorf("no running sessions available\n\nUse 'cw list' to see active sessions")
+			}
+			return 0, 0, fmt.Errorf("no running unattached sessions available\n\nUse 'cw list' to see active sessions")
 		}
 		// Sort by created_at ascending (oldest first).
 		sort.Slice(candidates, func(i, j int) bool {
 			return candidates[i].CreatedAt < candidates[j].CreatedAt
 		})
-		id = &candidates[0].ID
+		if opts.Latest {
+			id = &candidates[len(candidates)-1].ID
+		} else {
+			id = &candidates[0].ID
+		}
 	}
 
 	// ---------------------------------------------------------------
@@ -240,53 +772,73 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 	// ---------------------------------------------------------------
 	reader, writer, err := target.Connect()
 	if err != nil {
-		return err
+		return 0, 0, err
 	}
 	defer reader.Close()
 	defer writer.Close()
 
-	includeHistory := !noHistory
+	includeHistory := !opts.NoHistory
 	req := &protocol.Request{
 		Type:           "Attach",
 		ID:             id,
 		IncludeHistory: &includeHistory,
+		HistoryLines:   opts.HistoryLines,
+	}
+	if !opts.Raw {
+		req.Colors = opts.Colors
+		if req.Colors == "" {
+			req.Colors = ansi.DetectColorDepth(os.Getenv("COLORTERM"), os.Getenv("TERM")).String()
+		} else if _, err := ansi.ParseColorDepth(req.Colors); err != nil {
+			return 0, 0, err
+		}
 	}
 	if err := writer.SendRequest(req); err != nil {
-		return fmt.Errorf("sending attach request: %w", err)
+		return 0, 0, fmt.Errorf("sending attach request: %w", err)
 	}
 
 	// Read the Attached response.
 	frame, err := reader.ReadFrame()
 	if err != nil {
-		return fmt.Errorf("reading attach response: %w", err)
+		return 0, 0, fmt.Errorf("reading attach response: %w", err)
 	}
 	if frame == nil {
-		return fmt.Errorf("connection closed before attach response")
+		return 0, 0, fmt.Errorf("connection closed before attach response")
 	}
 	if frame.Type != protocol.FrameControl {
-		return fmt.Errorf("expected control frame, got type 0x%02x", frame.Type)
+		return 0, 0, fmt.Errorf("expected control frame, got type 0x%02x", frame.Type)
 	}
 
 	var resp protocol.Response
 	if err := json.Unmarshal(frame.Payload, &resp); err != nil {
-		return fmt.Errorf("parsing attach response: %w", err)
+		return 0, 0, fmt.Errorf("parsing attach response: %w", err)
 	}
 	if resp.Type == "Error" {
-		return fmt.Errorf("%s", formatError(resp.Message))
+		return 0, 0, fmt.Errorf("%s", formatError(resp.Message))
 	}
 	if resp.Type != "Attached" {
-		return fmt.Errorf("unexpected response: %s", resp.Type)
+		return 0, 0, fmt.Errorf("unexpected response: %s", resp.Type)
 	}
 
 	sessionID := *id
-	fmt.Fprintf(os.Stderr, "[cw] attached to session %d\n", sessionID)
+	fmt.Fprintf(os.Stderr, "[cw] attached to session %s\n", sessionRef(target, sessionID))
+	if resp.Info != nil {
+		printSessionContext(os.Stderr, resp.Info)
+		if term := os.Getenv("TERM"); term != "" && resp.Info.Term != "" && term != resp.Info.Term {
+			fmt.Fprintf(os.Stderr, "[cw] session runs with TERM=%s; this terminal is TERM=%s\n", resp.Info.Term, term)
+		}
+	}
+
+	if opts.Raw {
+		code, err := attachRaw(reader, writer, sessionID, opts)
+		return sessionID, code, err
+	}
 
 	// ---------------------------------------------------------------
 	// Step 3: enter raw mode
 	// ---------------------------------------------------------------
 	guard, err := terminal.EnableRawMode()
 	if err != nil {
-		return fmt.Errorf("enabling raw mode: %w", err)
+		return 0, 0, fmt.Errorf("enabling raw mode: %w", err)
 	}
 	defer guard.Restore()
 
@@ -296,10 +848,13 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 	cols, rows, err := terminal.TerminalSize()
 	if err != nil {
 		guard.Restore()
-		return fmt.Errorf("getting terminal size: %w", err)
+		return 0, 0, fmt.Errorf("getting terminal size: %w", err)
 	}
 
 	bar := statusbar.New(uint32(sessionID), cols, rows)
+	if resp.Info != nil {
+		setBarExpiry(bar, resp.Info.ExpiresAt, resp.Info.ExpiryLimit)
+	}
 	if setup := bar.Setup(); setup != nil {
 		os.Stdout.Write(setup)
 	}
@@ -314,7 +869,7 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 	}
 	if err := writer.SendRequest(resizeReq); err != nil {
 		guard.Restore()
-		return fmt.Errorf("sending initial resize: %w", err)
+		return 0, 0, fmt.Errorf("sending initial resize: %w", err)
 	}
 
 	// ---------------------------------------------------------------
@@ -329,28 +884,47 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 	ticker := time.NewTicker(10 * time.Second)
 	defer ticker.Stop()
 
+	// Round-trip probes: one Ping in flight at a time.
+	pingTicker := time.NewTicker(attachPingInterval)
+	defer pingTicker.Stop()
+	var pingSeq uint64
+	var pingSent time.Time
+	pingInFlight := false
+
+	var script *scriptRunner
+	var (
+		scriptSend     <-chan []byte
+		scriptProgress <-chan string
+		scriptDone     <-chan error
+	)
+	if opts.Script != nil {
+		script = newScriptRunner(opts.Script)
+		defer script.Stop()
+		scriptSend, scriptProgress, scriptDone = script.Send, script.Progress, script.Done
+		go script.Run()
+	}
+
+	var localEcho *terminal.LocalEcho
+	var echoTick <-chan time.Time
+	if opts.LocalEcho {
+		localEcho = terminal.NewLocalEcho()
+		echoTicker := time.NewTicker(localEchoExpiry / 4)
+		defer echoTicker.Stop()
+		echoTick = echoTicker.C
+	}
+
 	// ---------------------------------------------------------------
 	// Step 7: stdin reader goroutine
 	// ---------------------------------------------------------------
-	detector := terminal.NewDetachDetector()
-	stdinCh := make(chan stdinEvent, 1)
-	go func() {
-		for {
-			buf := make([]byte, 4096)
-			n, readErr := os.Stdin.Read(buf)
-			if n > 0 {
-				detach, fwd := detector.FeedBuf(buf[:n])
-				stdinCh <- stdinEvent{detach: detach, forward: fwd, err: nil}
-				if detach {
-					return
-				}
-			}
-			if readErr != nil {
-				stdinCh <- stdinEvent{err: readErr}
-				return
-			}
-		}
-	}()
+	var enterGuard *terminal.EnterGuard
+	if opts.ConfirmEnter {
+		enterGuard = terminal.NewEnterGuard()
+	}
+	done := make(chan struct{})
+	defer close(done)
+	if stdinCh == nil {
+		stdinCh = readStdin(done)
+	}
 
 	// ---------------------------------------------------------------
 	// Step 8: frame reader goroutine
@@ -359,13 +933,55 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 	go func() {
 		for {
 			f, readErr := reader.ReadFrame()
-			frameCh <- frameEvent{frame: f, err: readErr}
+			select {
+			case frameCh <- frameEvent{frame: f, err: readErr}:
+			case <-done:
+				return
+			}
 			if readErr != nil || f == nil {
 				return
 			}
 		}
 	}()
 
+	// Banner shown while another client attaches or detaches.
+	bannerTimer := time.NewTimer(clientsBannerDuration)
+	bannerTimer.Stop()
+	defer bannerTimer.Stop()
+
+	// Ctrl+B m asks for a bookmark name in the status bar.
+	var prompt *bookmarkPrompt
+
+	// Ctrl+B i shows session details over the terminal.
+	var overlay *infoOverlay
+	var altScreen altScreenTracker
+	openOverlay := func() {
+		resp, err := requestResponse(target, &protocol.Request{Type: "GetStatus", ID: &sessionID})
+		if err == nil && resp.Info == nil {
+			err = fmt.Errorf("%s", formatError(resp.Message))
+		}
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "\r\n[cw] session info unavailable: %v\r\n", err)
+			return
+		}
+		overlay = &infoOverlay{onAltScreen: altScreen.active}
+		os.Stdout.Write(overlay.show(resp.Info, bar.Cols, bar.Rows))
+	}
+	closeOverlay := func() {
+		os.Stdout.Write(overlay.hide())
+		if overlay.onAltScreen {
+			// Nudge the PTY size so the full-screen program redraws.
+			ptyCols, ptyRows := bar.PtySize()
+			nudged := ptyRows - 1
+			_ = writer.SendRequest(&protocol.Request{Type: "Resize", ID: &sessionID, Cols: &ptyCols, Rows: &nudged})
+			_ = writer.SendRequest(&protocol.Request{Type: "Resize", ID: &sessionID, Cols: &ptyCols, Rows: &ptyRows})
+		}
+		overlay = nil
+		if draw := bar.Draw(); draw != nil {
+			os.Stdout.Write(draw)
+		}
+	}
+
 	// ---------------------------------------------------------------
 	// Step 9: main select loop
 	// ---------------------------------------------------------------
@@ -374,33 +990,90 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 		case fe := <-frameCh:
 			if fe.err != nil {
 				teardown(bar, guard)
-				fmt.Fprintf(os.Stderr, "\n[cw] connection error: %v\n", fe.err)
-				os.Exit(1)
+				return sessionID, 0, fmt.Errorf("connection error: %w", fe.err)
 			}
 			if fe.frame == nil {
 				teardown(bar, guard)
-				fmt.Fprintf(os.Stderr, "\n[cw] connection lost\n")
-				os.Exit(1)
+				return sessionID, 0, fmt.Errorf("connection lost")
 			}
 			switch fe.frame.Type {
 			case protocol.FrameData:
-				os.Stdout.Write(fe.frame.Payload)
+				payload := fe.frame.Payload
+				if script != nil {
+					script.Output(payload)
+				}
+				if localEcho != nil {
+					payload = localEcho.Reconcile(payload)
+				}
+				altScreen.Feed(payload)
+				if overlay != nil {
+					overlay.held.Write(payload)
+					if overlay.held.Len() > maxOverlayHeld {
+						closeOverlay()
+					}
+					continue
+				}
+				os.Stdout.Write(payload)
 			case protocol.FrameControl:
 				var ctrlResp protocol.Response
 				if err := json.Unmarshal(fe.frame.Payload, &ctrlResp); err != nil {
 					teardown(bar, guard)
-					fmt.Fprintf(os.Stderr, "\n[cw] bad control frame: %v\n", err)
-					os.Exit(1)
+					return sessionID, 0, fmt.Errorf("bad control frame: %w", err)
 				}
 				switch ctrlResp.Type {
 				case "Detached":
 					teardown(bar, guard)
+					if ctrlResp.Message != "" {
+						fmt.Fprintf(os.Stderr, "\n[cw] %s\n", ctrlResp.Message)
+					}
 					fmt.Fprintf(os.Stderr, "\n[cw] detached from session %d\n", sessionID)
-					os.Exit(0)
+					return sessionID, 0, nil
+				case "IdleWarning", "ExpiryWarning":
+					// Raw mode: return to column 0 before and after.
+					fmt.Fprintf(os.Stderr, "\r\n[cw] %s\r\n", ctrlResp.Message)
+				case "Clients":
+					if ctrlResp.Count == nil || int(*ctrlResp.Count) == bar.Clients {
+						continue
+					}
+					n := int(*ctrlResp.Count)
+					switch {
+					case n > 1:
+						bar.Banner = fmt.Sprintf("%d clients attached", n)
+					case bar.Clients > 1:
+						bar.Banner = "other clients detached"
+					}
+					bar.Clients = n
+					if bar.Banner != "" {
+						bannerTimer.Reset(clientsBannerDuration)
+					}
+					if draw := bar.Draw(); draw != nil {
+						os.Stdout.Write(draw)
+					}
+				case "Expiry":
+					setBarExpiry(bar, ctrlResp.ExpiresAt, ctrlResp.ExpiryLimit)
+					if draw := bar.Draw(); draw != nil {
+						os.Stdout.Write(draw)
+					}
+				case "Pong":
+					if !pingInFlight || ctrlResp.Seq != pingSeq {
+						continue
+					}
+					pingInFlight = false
+					rtt := time.Since(pingSent)
+					bar.Latency = rtt
+					if draw := bar.Draw(); draw != nil {
+						os.Stdout.Write(draw)
+					}
+					if opts.LatencyDir != "" {
+						_ = recordLatency(opts.LatencyDir, target.NodeName(), rtt)
+					}
 				case "Error":
 					teardown(bar, guard)
 					fmt.Fprintf(os.Stderr, "\n[cw] %s\n", formatError(ctrlResp.Message))
-					os.Exit(0)
+					if opts.FollowNew && ctrlResp.Status != "" {
+						return sessionID, 0, errSessionEnded
+					}
+					return sessionID, attachExitCode(&ctrlResp, opts.ExitOnComplete), nil
 				default:
 					// Ignore other control messages.
 				}
@@ -420,11 +1093,71 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 				_ = writer.SendRequest(detachReq)
 				continue
 			}
+			if overlay != nil {
+				// Any key closes the overlay and is not sent.
+				if len(se.forward) > 0 || len(se.keys) > 0 {
+					closeOverlay()
+				}
+				continue
+			}
+			if prompt != nil {
+				done, save := prompt.feed(se.forward)
+				bar.Notice = prompt.notice()
+				if done {
+					bar.Notice = ""
+					if save {
+						if b, err := AddBookmark(target, sessionID, string(prompt.name)); err != nil {
+							bar.Banner = "bookmark failed: " + err.Error()
+						} else {
+							bar.Banner = fmt.Sprintf("bookmarked %s at %s", b.Name, formatBytes(uint64(b.Offset)))
+						}
+						bannerTimer.Reset(clientsBannerDuration)
+					}
+					prompt = nil
+				}
+				if draw := bar.Draw(); draw != nil {
+					os.Stdout.Write(draw)
+				}
+				continue
+			}
+			if bytes.IndexByte(se.keys, infoOverlayKey) >= 0 {
+				openOverlay()
+				continue
+			}
+			if bytes.IndexByte(se.keys, bookmarkKey) >= 0 {
+				prompt = &bookmarkPrompt{}
+				bar.Notice = prompt.notice()
+				if draw := bar.Draw(); draw != nil {
+					os.Stdout.Write(draw)
+				}
+				continue
+			}
+			if script != nil {
+				// The script has the keyboard until it finishes.
+				continue
+			}
+			if enterGuard != nil {
+				se.forward = enterGuard.Filter(se.forward)
+				notice := ""
+				if enterGuard.Pending() {
+					notice = "Enter again to send"
+				}
+				if notice != bar.Notice {
+					bar.Notice = notice
+					if draw := bar.Draw(); draw != nil {
+						os.Stdout.Write(draw)
+					}
+				}
+			}
+			if localEcho != nil {
+				if echo := localEcho.Predict(se.forward); echo != nil {
+					os.Stdout.Write(echo)
+				}
+			}
 			if len(se.forward) > 0 {
 				if err := writer.SendData(se.forward); err != nil {
 					teardown(bar, guard)
-					fmt.Fprintf(os.Stderr, "\n[cw] write error: %v\n", err)
-					os.Exit(1)
+					return sessionID, 0, fmt.Errorf("write error: %w", err)
 				}
 			}
 
@@ -433,6 +1166,9 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 			if err != nil {
 				continue
 			}
+			if overlay != nil {
+				closeOverlay()
+			}
 			if resize := bar.Resize(newCols, newRows); resize != nil {
 				os.Stdout.Write(resize)
 			}
@@ -449,6 +1185,196 @@ func Attach(target *Target, id *uint32, noHistory bool) error {
 			if draw := bar.Draw(); draw != nil {
 				os.Stdout.Write(draw)
 			}
+
+		case <-bannerTimer.C:
+			bar.Banner = ""
+			if draw := bar.Draw(); draw != nil {
+				os.Stdout.Write(draw)
+			}
+
+		case data := <-scriptSend:
+			if err := writer.SendData(data); err != nil {
+				teardown(bar, guard)
+				return sessionID, 0, fmt.Errorf("write error: %w", err)
+			}
+
+		case msg := <-scriptProgress:
+			bar.Notice = msg
+			if draw := bar.Draw(); draw != nil {
+				os.Stdout.Write(draw)
+			}
+
+		case err := <-scriptDone:
+			script = nil
+			scriptSend, scriptProgress, scriptDone = nil, nil, nil
+			bar.Notice = ""
+			if draw := bar.Draw(); draw != nil {
+				os.Stdout.Write(draw)
+			}
+			if err != nil {
+				fmt.Fprintf(os.Stderr, "\r\n[cw] script failed: %v; you have control\r\n", err)
+			} else {
+				fmt.Fprintf(os.Stderr, "\r\n[cw] script finished; you have control\r\n")
+			}
+
+		case <-pingTicker.C:
+			if pingInFlight {
+				// Still waiting: show the time so far as a lower bound.
+				// Nodes that never answered a Ping show no latency at all.
+				if waited := time.Since(pingSent); bar.Latency > 0 && waited > bar.Latency {
+					bar.Latency = waited
+				}
+				continue
+			}
+			pingSeq++
+			pingSent = time.Now()
+			pingInFlight = true
+			_ = writer.SendRequest(&protocol.Request{Type: "Ping", ID: &sessionID, Seq: pingSeq})
+
+		case <-echoTick:
+			if erase := localEcho.Expire(localEchoExpiry); erase != nil {
+				os.Stdout.Write(erase)
+			}
+		}
+	}
+}
+
+// attachExitCode picks the client's exit status when the node ends an attach
+// with an Error frame. Without passthrough the client always exits 0. With
+// passthrough a completed session yields its own exit code, and a killed
+// session yields 1.
+func attachExitCode(resp *protocol.Response, passthrough bool) int {
+	if !passthrough {
+		return 0
+	}
+	if resp.ExitCode != nil {
+		return *resp.ExitCode
+	}
+	if resp.Status == "killed" {
+		return 1
+	}
+	return 0
+}
+
+// setBarExpiry shows the countdown to a session limit in the status bar.
+func setBarExpiry(bar *statusbar.StatusBar, expiresAt, limit string) {
+	t, err := time.Parse(time.RFC3339, expiresAt)
+	if err != nil {
+		bar.Expires = time.Time{}
+		return
+	}
+	bar.Expires = t
+	bar.ExpiryLabel = "ends"
+	if limit == "idle" {
+		bar.ExpiryLabel = "idle timeout"
+	}
+}
+
+// attachRaw runs an attach with no client-side interception: stdin bytes go
+// to the PTY and PTY output goes to stdout unchanged. There is no status bar
+// and no detach key — the attach ends when the session ends, the connection
+// closes, or stdin reaches EOF. The terminal is put into raw mode only when
+// stdin is one, so raw attaches also work with pipes.
+func attachRaw(reader connection.FrameReader, writer connection.FrameWriter, sessionID uint32, opts AttachOptions) (int, error) {
+	var guard *terminal.RawModeGuard
+	if term.IsTerminal(int(os.Stdin.Fd())) {
+		g, err := terminal.EnableRawMode()
+		if err != nil {
+			return 0, fmt.Errorf("enabling raw mode: %w", err)
+		}
+		guard = g
+		defer guard.Restore()
+	}
+	restore := func() {
+		if guard != nil {
+			guard.Restore()
+		}
+	}
+
+	sendSize := func() {
+		cols, rows, err := terminal.TerminalSize()
+		if err != nil {
+			return
+		}
+		_ = writer.SendRequest(&protocol.Request{
+			Type: "Resize",
+			ID:   &sessionID,
+			Cols: &cols,
+			Rows: &rows,
+		})
+	}
+	sendSize()
+
+	winchCh, winchCleanup := terminal.ResizeSignal()
+	defer winchCleanup()
+
+	stdinCh := make(chan stdinEvent, 1)
+	go func() {
+		for {
+			buf := make([]byte, 4096)
+			n, readErr := os.Stdin.Read(buf)
+			if n > 0 {
+				stdinCh <- stdinEvent{forward: buf[:n]}
+			}
+			if readErr != nil {
+				stdinCh <- stdinEvent{err: readErr}
+				return
+			}
+		}
+	}()
+
+	frameCh := make(chan frameEvent, 1)
+	go func() {
+		for {
+			f, readErr := reader.ReadFrame()
+			frameCh <- frameEvent{frame: f, err: readErr}
+			if readErr != nil || f == nil {
+				return
+			}
+		}
+	}()
+
+	for {
+		select {
+		case fe := <-frameCh:
+			if fe.err != nil || fe.frame == nil {
+				restore()
+				return 0, fmt.Errorf("connection closed")
+			}
+			switch fe.frame.Type {
+			case protocol.FrameData:
+				os.Stdout.Write(fe.frame.Payload)
+			case protocol.FrameControl:
+				var ctrlResp protocol.Response
+				if err := json.Unmarshal(fe.frame.Payload, &ctrlResp); err != nil {
+					continue
+				}
+				switch ctrlResp.Type {
+				case "Detached":
+					restore()
+					fmt.Fprintf(os.Stderr, "\n[cw] detached from session %d\n", sessionID)
+					return 0, nil
+				case "Error":
+					restore()
+					fmt.Fprintf(os.Stderr, "\n[cw] %s\n", formatError(ctrlResp.Message))
+					return attachExitCode(&ctrlResp, opts.ExitOnComplete), nil
+				}
+			}
+
+		case se := <-stdinCh:
+			if se.err != nil {
+				// EOF on stdin closes the connection, which detaches.
+				restore()
+				fmt.Fprintf(os.Stderr, "\n[cw] detached from session %d\n", sessionID)
+				return 0, nil
+			}
+			if err := writer.SendData(se.forward); err != nil {
+				restore()
+				return 0, fmt.Errorf("write error: %w", err)
+			}
+
+		case <-winchCh:
+			sendSize()
 		}
 	}
 }
@@ -477,10 +1403,166 @@ func Kill(target *Target, id uint32) error {
 	if resp.Type == "Error" {
 		return fmt.Errorf("%s", formatError(resp.Message))
 	}
-	fmt.Fprintf(os.Stderr, "Session %d killed\n", id)
+	fmt.Fprintf(os.Stderr, "Session %s killed\n", sessionRef(target, id))
+	return nil
+}
+
+// Signal sends a signal (e.g. "INT") to a session's foreground process
+// group without killing the session.
+func Signal(target *Target, id uint32, signal string) error {
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:   "Kill",
+		ID:     &id,
+		Signal: signal,
+	})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "Signaled" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	fmt.Fprintf(os.Stderr, "Sent %s to session %s\n", resp.Message, sessionRef(target, id))
+	return nil
+}
+
+// ---------------------------------------------------------------------------
+// Extend
+// ---------------------------------------------------------------------------
+
+// Extend pushes back a session's max-runtime and idle deadlines by extra (a
+// Go duration such as "1h") and prints when the session now expires.
+func Extend(target *Target, id uint32, extra string) error {
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:  "Extend",
+		ID:    &id,
+		Extra: extra,
+	})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "Extended" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	fmt.Fprintf(os.Stderr, "Extended session %s; next limit (%s) at %s\n",
+		sessionRef(target, id), strings.ReplaceAll(resp.ExpiryLimit, "_", " "), formatTimestamp(resp.ExpiresAt, TimeAbsolute))
+	return nil
+}
+
+// SetPriority changes a session's priority class ("high", "normal" or
+// "background").
+func SetPriority(target *Target, id uint32, priority string) error {
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:     "SetPriority",
+		ID:       &id,
+		Priority: priority,
+	})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "PrioritySet" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	fmt.Fprintf(os.Stderr, "Session %s is now %s priority\n", sessionRef(target, id), priority)
+	return nil
+}
+
+// ---------------------------------------------------------------------------
+// Notes
+// ---------------------------------------------------------------------------
+
+// AddNote leaves a note on a session; it is shown to everyone who attaches.
+func AddNote(target *Target, id uint32, text string) error {
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:  "AddNote",
+		ID:    &id,
+		Notes: []string{text},
+	})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "NoteAdded" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	fmt.Fprintf(os.Stderr, "Added note to session %s\n", sessionRef(target, id))
 	return nil
 }
 
+// ---------------------------------------------------------------------------
+// Bookmarks
+// ---------------------------------------------------------------------------
+
+// LogRange slices a session's output log between two bookmarks; either may
+// be empty for the start or end of the log. The zero value is the whole log.
+type LogRange struct {
+	FromBookmark string
+	ToBookmark   string
+}
+
+// AddBookmark marks the current end of a session's output under name, or
+// an automatic "mark-N" name when it is empty.
+func AddBookmark(target *Target, id uint32, name string) (*protocol.SessionBookmark, error) {
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:     "AddBookmark",
+		ID:       &id,
+		Bookmark: name,
+	})
+	if err != nil {
+		return nil, err
+	}
+	if resp.Type == "Error" {
+		return nil, fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "BookmarkAdded" || resp.Bookmark == nil {
+		return nil, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	return resp.Bookmark, nil
+}
+
+// AddMirror copies a running session's output from now on to extra sinks.
+func AddMirror(target *Target, id uint32, specs []string) error {
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:    "AddMirror",
+		ID:      &id,
+		Mirrors: specs,
+	})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "MirrorAdded" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	for _, spec := range specs {
+		fmt.Fprintf(os.Stderr, "Mirroring session %s to %s\n", sessionRef(target, id), spec)
+	}
+	return nil
+}
+
+// printSessionContext writes a session's notes and last status-change
+// reason, as shown above the replay when attaching.
+func printSessionContext(w io.Writer, info *protocol.SessionInfo) {
+	if info.StatusReason != "" {
+		fmt.Fprintf(w, "[cw] %s: %s (%s)\n", info.Status, info.StatusReason, formatTimestamp(info.StatusChangedAt, TimeRelative))
+	}
+	for _, n := range info.Notes {
+		fmt.Fprintf(w, "[cw] note (%s): %s\n", formatTimestamp(n.Time, TimeRelative), n.Text)
+	}
+}
+
 // ---------------------------------------------------------------------------
 // KillByTags
 // ---------------------------------------------------------------------------
@@ -526,26 +1608,93 @@ func KillAll(target *Target) error {
 	return nil
 }
 
+// KillMatches returns the running sessions that KillByTags(tags) would
+// terminate, or KillAll when tags is empty, without killing anything.
+func KillMatches(target *Target, tags []string) ([]protocol.SessionInfo, error) {
+	req := &protocol.Request{Type: "KillAll", DryRun: true}
+	if len(tags) > 0 {
+		req = &protocol.Request{Type: "KillByTags", Tags: tags, DryRun: true}
+	}
+	resp, err := requestResponse(target, req)
+	if err != nil {
+		return nil, err
+	}
+	if resp.Type == "Error" {
+		return nil, fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "KillMatches" || resp.Sessions == nil {
+		return nil, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	return *resp.Sessions, nil
+}
+
+// PrintKillMatches lists sessions selected for a bulk kill.
+func PrintKillMatches(sessions []protocol.SessionInfo) {
+	printSessionTable(sessions)
+}
+
+// KillSessions kills exactly the given sessions, so a confirmed bulk kill
+// never reaches sessions launched after the match list was fetched.
+func KillSessions(target *Target, sessions []protocol.SessionInfo) error {
+	var failed int
+	for _, s := range sessions {
+		if err := Kill(target, s.ID); err != nil {
+			fmt.Fprintf(os.Stderr, "[cw] session %d: %v\n", s.ID, err)
+			failed++
+		}
+	}
+	if failed > 0 {
+		return fmt.Errorf("failed to kill %d of %d session(s)", failed, len(sessions))
+	}
+	return nil
+}
+
 // ---------------------------------------------------------------------------
 // Logs
 // ---------------------------------------------------------------------------
 
-// Logs retrieves the output log for a session. When follow is true, the client
-// streams new output as it arrives until the session ends or the connection
-// drops.
-func Logs(target *Target, id uint32, follow bool, tail *int, raw bool) error {
+// lossyWarning is printed once when output the node sends could not be
+// decoded in the session's encoding.
+const lossyWarning = "some output was not valid in the session's encoding and was replaced with U+FFFD; the log on the node keeps the original bytes (see cw run --encoding)"
+
+// Logs retrieves the output log for a session and writes it to w. When follow
+// is true, the client streams new output as it arrives until the session ends
+// or the connection drops. rng slices the log between bookmarks.
+func Logs(target *Target, id uint32, follow bool, tail *int, raw bool, rng LogRange, w io.Writer) error {
+	return LogsUntil(nil, target, id, follow, tail, raw, rng, w)
+}
+
+// LogsUntil is Logs that also stops once stop is closed, closing its
+// connection so the node stops sending the log.
+func LogsUntil(stop <-chan struct{}, target *Target, id uint32, follow bool, tail *int, raw bool, rng LogRange, w io.Writer) error {
 	reader, writer, err := target.Connect()
 	if err != nil {
 		return err
 	}
 	defer reader.Close()
 	defer writer.Close()
+	if stop != nil {
+		done := make(chan struct{})
+		defer close(done)
+		go func() {
+			select {
+			case <-stop:
+				reader.Close()
+				writer.Close()
+			case <-done:
+			}
+		}()
+	}
 
 	req := &protocol.Request{
-		Type:   "Logs",
-		ID:     &id,
-		Follow: &follow,
-	}
+		Type:         "Logs",
+		ID:           &id,
+		Follow:       &follow,
+		Replica:      target.Replica,
+		FromBookmark: rng.FromBookmark,
+		ToBookmark:   rng.ToBookmark,
+	}
+	var warnedLossy bool
 	if tail != nil {
 		t := uint(*tail)
 		req.Tail = &t
@@ -580,8 +1729,17 @@ func Logs(target *Target, id uint32, follow bool, tail *int, raw bool) error {
 
 		switch resp.Type {
 		case "LogData":
+			if resp.Warning != "" {
+				fmt.Fprintf(os.Stderr, "[cw] warning: %s\n", resp.Warning)
+			}
+			if resp.Lossy && !warnedLossy {
+				warnedLossy = true
+				fmt.Fprintf(os.Stderr, "[cw] warning: %s\n", lossyWarning)
+			}
 			if resp.Data != "" {
-				os.Stdout.Write([]byte(resp.Data))
+				if _, err := w.Write([]byte(resp.Data)); err != nil {
+					return err
+				}
 			}
 			if resp.Done != nil && *resp.Done {
 				return nil
@@ -601,7 +1759,7 @@ func Logs(target *Target, id uint32, follow bool, tail *int, raw bool) error {
 // SendInput sends input to a session without attaching. The input can come
 // from a direct argument, stdin, or a file. Unless noNewline is set, a
 // trailing newline is appended.
-func SendInput(target *Target, id uint32, input *string, useStdin bool, file *string, noNewline bool) error {
+func SendInput(target *Target, id uint32, input *string, useStdin bool, file *string, noNewline bool, keys []string) error {
 	var data []byte
 
 	switch {
@@ -619,31 +1777,155 @@ func SendInput(target *Target, id uint32, input *string, useStdin bool, file *st
 		if err != nil {
 			return fmt.Errorf("reading file: %w", err)
 		}
+	case len(keys) > 0:
+		// Keys only.
 	default:
 		return fmt.Errorf("no input source specified")
 	}
 
-	if !noNewline {
+	// Named keys follow the text. They replace the automatic newline:
+	// finish with --key enter to submit.
+	if len(keys) > 0 {
+		for _, name := range keys {
+			seq, err := terminal.KeyBytes(name)
+			if err != nil {
+				return err
+			}
+			data = append(data, seq...)
+		}
+	} else if !noNewline {
 		data = append(data, '\n')
 	}
 
-	resp, err := requestResponse(target, &protocol.Request{
-		Type: "SendInput",
-		ID:   &id,
-		Data: data,
-	})
+	resp, err := requestResponse(target, &protocol.Request{
+		Type: "SendInput",
+		ID:   &id,
+		Data: data,
+	})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+
+	bytes := uint(0)
+	if resp.Bytes != nil {
+		bytes = *resp.Bytes
+	}
+	fmt.Fprintf(os.Stderr, "Sent %d bytes to session %d\n", bytes, id)
+	return nil
+}
+
+// VerifyLogs checks a session's output log against its CRC checkpoints and
+// returns an error if it is truncated or corrupted.
+func VerifyLogs(target *Target, id uint32) error {
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:   "Logs",
+		ID:     &id,
+		Verify: true,
+	})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "LogIntegrity" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	if resp.Status != "ok" {
+		return fmt.Errorf("session %d: %s", id, resp.Message)
+	}
+	fmt.Printf("session %d: %s\n", id, resp.Message)
+	return nil
+}
+
+// ---------------------------------------------------------------------------
+// Input history
+// ---------------------------------------------------------------------------
+
+// fetchInputs retrieves the recorded PTY input history of a session.
+func fetchInputs(target *Target, id uint32, tail *int) ([]protocol.InputRecord, error) {
+	req := &protocol.Request{
+		Type:   "Logs",
+		ID:     &id,
+		Inputs: true,
+	}
+	if tail != nil {
+		t := uint(*tail)
+		req.Tail = &t
+	}
+	resp, err := requestResponse(target, req)
+	if err != nil {
+		return nil, err
+	}
+	if resp.Type == "Error" {
+		return nil, fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "InputLog" || resp.Inputs == nil {
+		return nil, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	return *resp.Inputs, nil
+}
+
+// LogInputs prints the input recorded for a session, one chunk per line with
+// its timestamp and a quoted rendering of the bytes.
+func LogInputs(target *Target, id uint32, tail *int) error {
+	records, err := fetchInputs(target, id, tail)
+	if err != nil {
+		return err
+	}
+	for _, r := range records {
+		fmt.Printf("%s  %q\n", r.Timestamp, r.Data)
+	}
+	return nil
+}
+
+// maxReplayGap caps the pause between replayed inputs so long idle periods
+// in the original session do not stall a replay.
+const maxReplayGap = 5 * time.Second
+
+// Replay re-sends the input recorded for session from onto session onto. By
+// default the original gaps between inputs are preserved (capped at
+// maxReplayGap); noDelay sends everything back to back.
+func Replay(target *Target, from, onto uint32, noDelay bool) error {
+	records, err := fetchInputs(target, from, nil)
 	if err != nil {
 		return err
 	}
-	if resp.Type == "Error" {
-		return fmt.Errorf("%s", formatError(resp.Message))
+	if len(records) == 0 {
+		return fmt.Errorf("session %d has no recorded input", from)
 	}
 
-	bytes := uint(0)
-	if resp.Bytes != nil {
-		bytes = *resp.Bytes
+	var prev time.Time
+	for i, r := range records {
+		ts, _ := time.Parse(time.RFC3339Nano, r.Timestamp)
+		if !noDelay && i > 0 && !prev.IsZero() && !ts.IsZero() {
+			gap := ts.Sub(prev)
+			if gap > maxReplayGap {
+				gap = maxReplayGap
+			}
+			if gap > 0 {
+				time.Sleep(gap)
+			}
+		}
+		prev = ts
+
+		resp, err := requestResponse(target, &protocol.Request{
+			Type: "SendInput",
+			ID:   &onto,
+			Data: r.Data,
+		})
+		if err != nil {
+			return err
+		}
+		if resp.Type == "Error" {
+			return fmt.Errorf("replaying input %d/%d: %s", i+1, len(records), formatError(resp.Message))
+		}
 	}
-	fmt.Fprintf(os.Stderr, "Sent %d bytes to session %d\n", bytes, id)
+
+	fmt.Fprintf(os.Stderr, "[cw] replayed %d inputs from session %d onto session %d\n", len(records), from, onto)
 	return nil
 }
 
@@ -651,31 +1933,39 @@ func SendInput(target *Target, id uint32, input *string, useStdin bool, file *st
 // WatchSession
 // ---------------------------------------------------------------------------
 
-// WatchSession watches a session's output in real-time without attaching.
-// An optional timeout (in seconds) limits how long to wait.
-func WatchSession(target *Target, id uint32, tail *int, noHistory bool, timeout *uint64) error {
-	reader, writer, err := target.Connect()
-	if err != nil {
-		return err
-	}
-	defer reader.Close()
-	defer writer.Close()
+// watchReconnects is how many times in a row WatchSession tries to resume a
+// watch whose connection dropped before it gives up.
+const watchReconnects = 5
+
+// errWatchClosed is a watch connection closed before the session ended.
+var errWatchClosed = errors.New("connection closed")
+
+// WatchSession watches a session's output in real-time without attaching,
+// writing it to w. An optional timeout (in seconds) limits how long to wait.
+// maxRate caps the updates per second the node sends (0 for its default).
+// resumeFrom continues from the cursor of an earlier watch instead of
+// replaying history. A dropped connection is resumed from the last cursor,
+// so output is neither repeated nor lost.
+func WatchSession(target *Target, id uint32, tail *int, noHistory bool, timeout *uint64, maxRate uint32, resumeFrom *uint64, w io.Writer) error {
+	return WatchSessionUntil(nil, target, id, tail, noHistory, timeout, maxRate, resumeFrom, w)
+}
 
+// WatchSessionUntil is WatchSession that also stops, closing its connection,
+// once stop is closed.
+func WatchSessionUntil(stop <-chan struct{}, target *Target, id uint32, tail *int, noHistory bool, timeout *uint64, maxRate uint32, resumeFrom *uint64, w io.Writer) error {
 	includeHistory := !noHistory
 	req := &protocol.Request{
 		Type:           "WatchSession",
 		ID:             &id,
 		IncludeHistory: &includeHistory,
+		MaxRate:        maxRate,
+		ResumeFrom:     resumeFrom,
 	}
 	if tail != nil {
 		t := uint(*tail)
 		req.Tail = &t
 	}
 
-	if err := writer.SendRequest(req); err != nil {
-		return fmt.Errorf("sending watch request: %w", err)
-	}
-
 	// Set up timeout timer.
 	var timeoutDuration time.Duration
 	if timeout != nil {
@@ -687,50 +1977,126 @@ func WatchSession(target *Target, id uint32, tail *int, noHistory bool, timeout
 	timer := time.NewTimer(timeoutDuration)
 	defer timer.Stop()
 
+	failures := 0
+	for {
+		progressed, retry, err := watchStream(target, req, timer.C, stop, w)
+		if !retry || req.ResumeFrom == nil {
+			if errors.Is(err, errWatchClosed) {
+				return nil // a node without cursors; nothing to resume from
+			}
+			return err
+		}
+		if progressed {
+			failures = 0
+		}
+		if failures++; failures > watchReconnects {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "\n[cw] watch interrupted (%v); resuming at offset %d\n", err, *req.ResumeFrom)
+		select {
+		case <-timer.C:
+			fmt.Fprintf(os.Stderr, "[cw] watch timeout reached\n")
+			return nil
+		case <-stop:
+			return nil
+		case <-time.After(time.Duration(failures) * time.Second):
+		}
+	}
+}
+
+// watchStream runs one WatchSession connection, moving req.ResumeFrom past
+// each update it writes to w. retry reports that the connection was lost
+// before the session ended, so the watch can be resumed from req.ResumeFrom.
+func watchStream(target *Target, req *protocol.Request, timeout <-chan time.Time, stop <-chan struct{}, w io.Writer) (progressed, retry bool, err error) {
+	reader, writer, err := target.Connect()
+	if err != nil {
+		return false, true, err
+	}
+	defer reader.Close()
+	defer writer.Close()
+
+	if err := writer.SendRequest(req); err != nil {
+		return false, true, fmt.Errorf("sending watch request: %w", err)
+	}
+
 	// Frame reader goroutine.
 	frameCh := make(chan frameEvent, 1)
-	go readFrames(reader, frameCh)
+	done := make(chan struct{})
+	defer close(done)
+	go readFrames(reader, frameCh, done)
 
+	var lastSeq uint64
+	var warnedLossy bool
 	for {
 		select {
 		case fe := <-frameCh:
 			if fe.err != nil {
-				return fmt.Errorf("reading watch frame: %w", fe.err)
+				return progressed, true, fmt.Errorf("reading watch frame: %w", fe.err)
 			}
 			if fe.frame == nil {
-				return nil // clean EOF
+				return progressed, true, errWatchClosed
 			}
 			if fe.frame.Type != protocol.FrameControl {
 				continue
 			}
 			var resp protocol.Response
 			if err := json.Unmarshal(fe.frame.Payload, &resp); err != nil {
-				return fmt.Errorf("parsing watch response: %w", err)
+				return progressed, false, fmt.Errorf("parsing watch response: %w", err)
 			}
 			switch resp.Type {
 			case "WatchUpdate":
+				if resp.Seq != 0 {
+					if lastSeq != 0 && resp.Seq != lastSeq+1 {
+						fmt.Fprintf(os.Stderr, "\n[cw] warning: %d watch updates missing, output may be incomplete\n", resp.Seq-lastSeq-1)
+					}
+					lastSeq = resp.Seq
+				}
+				if resp.Missed > 0 {
+					fmt.Fprintf(os.Stderr, "\n[cw] warning: %d bytes of output could not be replayed (the session's log is not kept on the node)\n", resp.Missed)
+				}
+				if resp.Lossy && !warnedLossy {
+					warnedLossy = true
+					fmt.Fprintf(os.Stderr, "\n[cw] warning: %s\n", lossyWarning)
+				}
 				if resp.Output != nil {
-					os.Stdout.Write([]byte(*resp.Output))
+					w.Write([]byte(*resp.Output))
+				}
+				if resp.Cursor != 0 {
+					cursor := resp.Cursor
+					req.ResumeFrom = &cursor
 				}
+				progressed = true
 				if resp.Done != nil && *resp.Done {
-					return nil
+					return progressed, false, nil
 				}
 			case "Error":
-				return fmt.Errorf("%s", formatError(resp.Message))
+				return progressed, false, fmt.Errorf("%s", formatError(resp.Message))
 			}
 
-		case <-timer.C:
-			fmt.Fprintf(os.Stderr, "\n[cw] watch timeout reached\n")
-			return nil
+		case <-timeout:
+			if req.ResumeFrom != nil {
+				fmt.Fprintf(os.Stderr, "\n[cw] watch timeout reached (continue with --resume-from %d)\n", *req.ResumeFrom)
+			} else {
+				fmt.Fprintf(os.Stderr, "\n[cw] watch timeout reached\n")
+			}
+			return progressed, false, nil
+
+		case <-stop:
+			return progressed, false, nil
 		}
 	}
 }
 
-// readFrames reads frames in a loop and sends them to the channel.
-func readFrames(reader connection.FrameReader, ch chan<- frameEvent) {
+// readFrames reads frames in a loop and sends them to the channel, until
+// the connection ends or done is closed.
+func readFrames(reader connection.FrameReader, ch chan<- frameEvent, done <-chan struct{}) {
 	for {
 		f, err := reader.ReadFrame()
-		ch <- frameEvent{frame: f, err: err}
+		select {
+		case ch <- frameEvent{frame: f, err: err}:
+		case <-done:
+			return
+		}
 		if err != nil || f == nil {
 			return
 		}
@@ -760,30 +2126,10 @@ const colorReset = "\x1b[0m"
 // tests). If timeout is non-nil, it stops after that many seconds.
 func WatchMultiByTag(target *Target, tag string, w io.Writer, timeout *uint64) error {
 	// 1. List sessions, filter by tag.
-	resp, err := requestResponse(target, &protocol.Request{Type: "ListSessions"})
+	matched, err := sessionsWithTag(target, tag)
 	if err != nil {
 		return err
 	}
-	if resp.Type == "Error" {
-		return fmt.Errorf("%s", formatError(resp.Message))
-	}
-	if resp.Sessions == nil {
-		return fmt.Errorf("unexpected response type: %s", resp.Type)
-	}
-
-	var matched []protocol.SessionInfo
-	for _, s := range *resp.Sessions {
-		for _, t := range s.Tags {
-			if t == tag {
-				matched = append(matched, s)
-				break
-			}
-		}
-	}
-
-	if len(matched) == 0 {
-		return fmt.Errorf("no sessions found with tag %q", tag)
-	}
 
 	// 2. For each matched session, spawn a goroutine to watch it.
 	merged := make(chan watchLine, len(matched)*64)
@@ -841,6 +2187,34 @@ func WatchMultiByTag(target *Target, tag string, w io.Writer, timeout *uint64) e
 	}
 }
 
+// sessionsWithTag lists the target's sessions that have tag.
+func sessionsWithTag(target *Target, tag string) ([]protocol.SessionInfo, error) {
+	resp, err := requestResponse(target, &protocol.Request{Type: "ListSessions"})
+	if err != nil {
+		return nil, err
+	}
+	if resp.Type == "Error" {
+		return nil, fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Sessions == nil {
+		return nil, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+
+	var matched []protocol.SessionInfo
+	for _, s := range *resp.Sessions {
+		for _, t := range s.Tags {
+			if t == tag {
+				matched = append(matched, s)
+				break
+			}
+		}
+	}
+	if len(matched) == 0 {
+		return nil, fmt.Errorf("no sessions found with tag %q", tag)
+	}
+	return matched, nil
+}
+
 // watchSingleToChannel connects to a single session's WatchSession stream
 // and sends output lines to the merged channel.
 func watchSingleToChannel(target *Target, sessionID uint32, label, color string, merged chan<- watchLine) {
@@ -864,7 +2238,9 @@ func watchSingleToChannel(target *Target, sessionID uint32, label, color string,
 	}
 
 	frameCh := make(chan frameEvent, 1)
-	go readFrames(reader, frameCh)
+	done := make(chan struct{})
+	defer close(done)
+	go readFrames(reader, frameCh, done)
 
 	for fe := range frameCh {
 		if fe.err != nil {
@@ -899,11 +2275,14 @@ func watchSingleToChannel(target *Target, sessionID uint32, label, color string,
 // GetStatus
 // ---------------------------------------------------------------------------
 
-// GetStatus retrieves detailed status information for a single session.
-func GetStatus(target *Target, id uint32, jsonOutput bool) error {
+// GetStatus retrieves detailed status information for a single session. When
+// snapshot is set, only the working directory snapshot captured at exit is
+// printed. Timestamps are rendered in the given --time style.
+func GetStatus(target *Target, id uint32, jsonOutput bool, snapshot bool, timeMode string) error {
 	resp, err := requestResponse(target, &protocol.Request{
-		Type: "GetStatus",
-		ID:   &id,
+		Type:     "GetStatus",
+		ID:       &id,
+		Snapshot: snapshot,
 	})
 	if err != nil {
 		return err
@@ -916,6 +2295,17 @@ func GetStatus(target *Target, id uint32, jsonOutput bool) error {
 	}
 
 	info := resp.Info
+	noteReplica(resp.Standby)
+
+	if snapshot {
+		// Nodes that predate snapshots ignore the flag and reply with the
+		// plain status; don't pass that off as the diff.
+		if resp.Snapshot == nil {
+			return fmt.Errorf("session %s has no working directory snapshot (launch it with cw run --snapshot)", sessionRef(target, info.ID))
+		}
+		fmt.Print(*resp.Snapshot)
+		return nil
+	}
 
 	if jsonOutput {
 		data, err := json.MarshalIndent(info, "", "  ")
@@ -927,21 +2317,86 @@ func GetStatus(target *Target, id uint32, jsonOutput bool) error {
 	}
 
 	// Print a structured status view.
-	fmt.Printf("Session %d\n", info.ID)
+	fmt.Printf("Session %s\n", sessionRef(target, info.ID))
 	fmt.Printf("  Command:     %s\n", info.Prompt)
-	fmt.Printf("  Working Dir: %s\n", info.WorkingDir)
+	if info.Workspace != "" {
+		fmt.Printf("  Working Dir: %s (workspace)\n", info.WorkingDir)
+	} else {
+		fmt.Printf("  Working Dir: %s\n", info.WorkingDir)
+	}
+	if info.GitRoot != "" {
+		fmt.Printf("  Git:         %s @ %s (%s)\n", info.GitBranch, shortCommit(info.GitCommit), info.GitRoot)
+	}
 	fmt.Printf("  Status:      %s\n", info.Status)
-	fmt.Printf("  Created:     %s\n", info.CreatedAt)
+	fmt.Printf("  Created:     %s\n", formatTimestamp(info.CreatedAt, timeMode))
 	fmt.Printf("  Attached:    %v\n", info.Attached)
 	if info.PID != nil {
 		fmt.Printf("  PID:         %d\n", *info.PID)
 	}
+	if info.Term != "" {
+		fmt.Printf("  TERM:        %s\n", info.Term)
+	}
+	if info.ExitCode != nil {
+		fmt.Printf("  Exit Code:   %d\n", *info.ExitCode)
+	}
+	if info.CompletedAt != nil {
+		fmt.Printf("  Completed:   %s\n", formatTimestamp(*info.CompletedAt, timeMode))
+	}
+	fmt.Printf("  Duration:    %s\n", sessionRuntime(*info))
+	if info.ExpiresAt != "" {
+		fmt.Printf("  Expires:     %s (%s)\n", formatTimestamp(info.ExpiresAt, timeMode), strings.ReplaceAll(info.ExpiryLimit, "_", " "))
+	}
 	if info.OutputSizeBytes != nil {
 		fmt.Printf("  Output Size: %d bytes\n", *info.OutputSizeBytes)
 	}
 	if resp.OutputSize != nil {
 		fmt.Printf("  Log Size:    %d bytes\n", *resp.OutputSize)
 	}
+	if info.LogURL != "" {
+		fmt.Printf("  Log URL:     %s\n", info.LogURL)
+	}
+	for _, spec := range info.Mirrors {
+		fmt.Printf("  Mirror:      %s\n", spec)
+	}
+	switch info.History {
+	case "inputs":
+		fmt.Printf("  History:     inputs only (output not recorded, cannot be replayed)\n")
+	case "none":
+		fmt.Printf("  History:     none (nothing recorded, cannot be replayed)\n")
+	}
+	if info.RecordInput {
+		fmt.Printf("  Input:       recorded, keystrokes included (cw logs --inputs)\n")
+	}
+	if info.Encoding != "" && info.Encoding != "utf-8" {
+		fmt.Printf("  Encoding:    %s\n", info.Encoding)
+	}
+	if info.Priority != "" && info.Priority != "normal" {
+		fmt.Printf("  Priority:    %s\n", info.Priority)
+	}
+	if info.ImportedFrom != "" {
+		fmt.Printf("  Imported:    from %s\n", info.ImportedFrom)
+	}
+	if info.StatusReason != "" {
+		fmt.Printf("  Reason:      %s\n", info.StatusReason)
+	}
+	if len(info.UndeliveredInput) > 0 {
+		fmt.Printf("  Undelivered:\n")
+		for _, u := range info.UndeliveredInput {
+			fmt.Printf("    %-10s  %d bytes  %q\n", u.Source, u.Bytes, u.Preview)
+		}
+	}
+	if len(info.Notes) > 0 {
+		fmt.Printf("  Notes:\n")
+		for _, n := range info.Notes {
+			fmt.Printf("    %s  %s\n", formatTimestamp(n.Time, timeMode), n.Text)
+		}
+	}
+	if len(info.Bookmarks) > 0 {
+		fmt.Printf("  Bookmarks:\n")
+		for _, b := range info.Bookmarks {
+			fmt.Printf("    %s  %-20s at %s\n", formatTimestamp(b.Time, timeMode), b.Name, formatBytes(uint64(b.Offset)))
+		}
+	}
 	if info.LastOutputSnippet != nil {
 		fmt.Printf("  Last Output:\n%s\n", *info.LastOutputSnippet)
 	}
@@ -949,69 +2404,269 @@ func GetStatus(target *Target, id uint32, jsonOutput bool) error {
 }
 
 // ---------------------------------------------------------------------------
-// Helpers
+// Nodes (relay discovery)
 // ---------------------------------------------------------------------------
 
-// printSessionTable prints a formatted table of sessions.
-func printSessionTable(sessions []protocol.SessionInfo) {
-	// Column headers.
-	fmt.Printf("%-4s %-14s %-32s %-10s %-8s\n", "ID", "NAME", "COMMAND", "STATUS", "AGE")
-
-	for _, s := range sessions {
-		name := s.Name
-		if name == "" {
-			name = "-"
+// Nodes fetches the list of registered nodes from a relay URL and prints them.
+// Every successful fetch is cached, so later calls can skip the relay round
+// trip, and a relay that fails or times out is answered from the cache with
+// a warning (see NodesOptions). With opts.WaitFor, the relay is asked again
+// until those nodes are online; any still missing are listed as such and
+// reported as an error.
+func Nodes(relayURL string, opts NodesOptions) error {
+	timeout := cmp.Or(opts.Timeout, defaultNodesTimeout)
+	deadline := time.Now().Add(timeout)
+	var d discovery
+	var missing []string
+	for {
+		var err error
+		d, err = discoverNodes(relayURL, opts, time.Until(deadline))
+		if err != nil && (len(opts.WaitFor) == 0 || !time.Now().Before(deadline)) {
+			return err
+		}
+		if err == nil {
+			missing = missingNodes(d.Nodes, opts.WaitFor)
+			if len(missing) == 0 || !time.Now().Before(deadline) {
+				break
+			}
 		}
-		if len(name) > 14 {
-			name = name[:11] + "..."
+		time.Sleep(min(time.Second, time.Until(deadline)))
+	}
+	switch {
+	case d.Err != nil:
+		fmt.Fprintf(os.Stderr, "[cw] relay unavailable (%v); showing the listing cached %s ago\n", d.Err, formatDuration(d.Age))
+	case d.Age > 0:
+		fmt.Fprintf(os.Stderr, "[cw] cached %s ago\n", formatDuration(d.Age))
+	}
+
+	if len(d.Nodes) == 0 && len(missing) == 0 {
+		fmt.Println("No registered nodes")
+		return nil
+	}
+
+	fmt.Printf("%-20s %-40s %-10s\n", "NAME", "TUNNEL URL", "STATUS")
+	for _, n := range d.Nodes {
+		status := "offline"
+		if n.Connected {
+			status = "online"
 		}
-		prompt := s.Prompt
-		if len(prompt) > 32 {
-			prompt = prompt[:29] + "..."
+		fmt.Printf("%s %-40s %-10s\n", textwidth.Pad(n.Name, 20), n.TunnelURL, status)
+	}
+	for _, name := range missing {
+		if !slices.ContainsFunc(d.Nodes, func(n RelayNode) bool { return n.Name == name }) {
+			fmt.Printf("%s %-40s %-10s\n", textwidth.Pad(name, 20), "-", "unregistered")
 		}
-		age := formatRelativeTime(s.CreatedAt)
-		fmt.Printf("%-4d %-14s %-32s %-10s %-8s\n", s.ID, name, prompt, s.Status, age)
 	}
+	if len(missing) > 0 {
+		return fmt.Errorf("not online after %s: %s", timeout, strings.Join(missing, ", "))
+	}
+	return nil
 }
 
 // ---------------------------------------------------------------------------
-// Nodes (relay discovery)
+// Node status
 // ---------------------------------------------------------------------------
 
-// Nodes fetches the list of registered nodes from a relay URL and prints them.
-func Nodes(relayURL string) error {
-	resp, err := fetchJSON(relayURL + "/api/v1/nodes")
+// NodeStatus prints the node's version, relay connection and remote client
+// bandwidth totals.
+func NodeStatus(target *Target, jsonOutput bool) error {
+	resp, err := requestResponse(target, &protocol.Request{Type: "NodeStatus"})
 	if err != nil {
 		return err
 	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
 
-	var nodes []struct {
-		Name      string `json:"name"`
-		TunnelURL string `json:"tunnel_url"`
-		Connected bool   `json:"connected"`
+	var stats []protocol.BandwidthStat
+	if resp.Bandwidth != nil {
+		stats = *resp.Bandwidth
 	}
-	if err := json.Unmarshal(resp, &nodes); err != nil {
-		return fmt.Errorf("parsing nodes: %w", err)
+
+	if jsonOutput {
+		data, err := json.MarshalIndent(map[string]any{
+			"version":   resp.Version,
+			"relay":     resp.Relay,
+			"standby":   resp.Standby,
+			"bandwidth": stats,
+		}, "", "  ")
+		if err != nil {
+			return err
+		}
+		fmt.Println(string(data))
+		return nil
 	}
 
-	if len(nodes) == 0 {
-		fmt.Println("No registered nodes")
+	fmt.Printf("Version: %s\n", resp.Version)
+	if rs := resp.Relay; rs != nil {
+		state := "disconnected"
+		if rs.Connected {
+			state = "connected"
+		}
+		if since, err := time.Parse(time.RFC3339, rs.Since); err == nil {
+			state += " for " + formatDuration(time.Since(since))
+		}
+		fmt.Printf("Relay:   %s (%s, %d disconnects)\n", rs.URL, state, rs.Disconnects)
+		if !rs.Connected && rs.LastError != "" {
+			fmt.Printf("         last error: %s\n", rs.LastError)
+		}
+	}
+	if st := resp.Standby; st != nil {
+		synced := "never synced"
+		if t, err := time.Parse(time.RFC3339, st.SyncedAt); err == nil {
+			synced = "synced " + formatDuration(time.Since(t)) + " ago"
+		}
+		fmt.Printf("Standby: of %s (%s, %d sessions)\n", st.Primary, synced, st.Sessions)
+		if st.LastError != "" {
+			fmt.Printf("         last error: %s\n", st.LastError)
+		}
+	}
+	if len(stats) == 0 {
+		fmt.Println("No remote clients since the node started")
 		return nil
 	}
 
-	fmt.Printf("%-20s %-40s %-10s\n", "NAME", "TUNNEL URL", "STATUS")
-	for _, n := range nodes {
-		status := "offline"
-		if n.Connected {
-			status = "online"
+	fmt.Println()
+	fmt.Printf("%-12s %-16s %-22s %10s %10s %s\n", "KIND", "TOKEN", "REMOTE", "SENT", "RECEIVED", "LIMIT")
+	for _, st := range stats {
+		remote := st.RemoteAddr
+		if remote == "" {
+			remote = "-"
+		}
+		limit := "-"
+		if st.LimitBps > 0 {
+			limit = formatBytes(uint64(st.LimitBps)) + "/s"
 		}
-		fmt.Printf("%-20s %-40s %-10s\n", n.Name, n.TunnelURL, status)
+		fmt.Printf("%-12s %-16s %-22s %10s %10s %s\n", st.Kind, st.Token, remote, formatBytes(st.BytesSent), formatBytes(st.BytesRecv), limit)
 	}
 	return nil
 }
 
-func fetchJSON(url string) ([]byte, error) {
-	resp, err := http.Get(url)
+// formatBytes renders n using binary units (e.g. "1.5 MiB").
+func formatBytes(n uint64) string {
+	const unit = 1024
+	if n < unit {
+		return fmt.Sprintf("%d B", n)
+	}
+	div, exp := uint64(unit), 0
+	for m := n / unit; m >= unit; m /= unit {
+		div *= unit
+		exp++
+	}
+	return fmt.Sprintf("%.1f %ciB", float64(n)/float64(div), "KMGTPE"[exp])
+}
+
+// ---------------------------------------------------------------------------
+// Upgrade (rolling node self-update)
+// ---------------------------------------------------------------------------
+
+// upgradeRestartTimeout bounds how long UpgradeNode waits for a node to
+// answer again after it restarts on the new binary.
+const upgradeRestartTimeout = 60 * time.Second
+
+// NodeVersion asks the node which cw release it is running.
+func NodeVersion(target *Target) (string, error) {
+	resp, err := requestResponse(target, &protocol.Request{Type: "Version"})
+	if err != nil {
+		return "", err
+	}
+	if resp.Type == "Error" {
+		return "", fmt.Errorf("%s", formatError(resp.Message))
+	}
+	return resp.Version, nil
+}
+
+// NodeSummary is a node's health and load, for deciding where to place new
+// sessions.
+type NodeSummary struct {
+	Name    string                `json:"name"`
+	URL     string                `json:"url,omitempty"`
+	Health  string                `json:"health"` // "ok" or "unreachable"
+	Error   string                `json:"error,omitempty"`
+	Version string                `json:"version,omitempty"`
+	Running int                   `json:"running_sessions"`
+	Total   int                   `json:"total_sessions"`
+	Tags    map[string]int        `json:"running_by_tag,omitempty"`
+	Relay   *protocol.RelayStatus `json:"relay,omitempty"`
+}
+
+// SummarizeNode asks target for its status and sessions. A node that cannot
+// be reached is reported as unreachable rather than as an error, so callers
+// can summarize many nodes at once.
+func SummarizeNode(target *Target) NodeSummary {
+	sum := NodeSummary{Name: target.NodeName(), URL: target.URL, Health: "ok"}
+	resp, err := requestResponse(target, &protocol.Request{Type: "NodeStatus"})
+	if err == nil && resp.Type == "Error" {
+		err = fmt.Errorf("%s", resp.Message)
+	}
+	var sessions []protocol.SessionInfo
+	if err == nil {
+		sum.Version, sum.Relay = resp.Version, resp.Relay
+		sessions, err = ListFiltered(target, "all")
+	}
+	if err != nil {
+		sum.Health, sum.Error = "unreachable", err.Error()
+		return sum
+	}
+	sum.Total = len(sessions)
+	for _, s := range sessions {
+		if s.Status != "running" {
+			continue
+		}
+		sum.Running++
+		for _, tag := range s.Tags {
+			if sum.Tags == nil {
+				sum.Tags = map[string]int{}
+			}
+			sum.Tags[tag]++
+		}
+	}
+	return sum
+}
+
+// UpgradeNode asks a node to download and verify source (a release tag or a
+// binary URL), drain its running sessions, install the new binary, and
+// restart. It waits until the node answers again and returns the version it
+// reports. progress receives a line for each stage. Without allowDowngrade
+// the node refuses a release older than its own.
+func UpgradeNode(target *Target, source string, drainTimeout time.Duration, allowDowngrade bool, progress func(string)) (string, error) {
+	before, err := NodeVersion(target)
+	if err != nil {
+		return "", err
+	}
+	progress(fmt.Sprintf("running %s; downloading %s and draining sessions", before, source))
+
+	secs := uint64(drainTimeout.Seconds())
+	resp, err := requestResponse(target, &protocol.Request{
+		Type:           "Upgrade",
+		Version:        source,
+		AllowDowngrade: allowDowngrade,
+		TimeoutSeconds: &secs,
+	})
+	if err != nil {
+		return "", err
+	}
+	if resp.Type == "Error" {
+		return "", fmt.Errorf("%s", formatError(resp.Message))
+	}
+	progress(fmt.Sprintf("installed %s; restarting", resp.Version))
+
+	deadline := time.Now().Add(upgradeRestartTimeout)
+	for time.Now().Before(deadline) {
+		time.Sleep(time.Second)
+		v, err := NodeVersion(target)
+		if err != nil {
+			continue
+		}
+		if v == resp.Version {
+			return v, nil
+		}
+	}
+	return "", fmt.Errorf("node did not come back on %s within %s", resp.Version, upgradeRestartTimeout)
+}
+
+func fetchJSON(url string, timeout time.Duration) ([]byte, error) {
+	resp, err := (&http.Client{Timeout: timeout}).Get(url)
 	if err != nil {
 		return nil, err
 	}
@@ -1100,6 +2755,7 @@ func WaitForSession(target *Target, sessionID *uint32, tags []string, condition
 		Condition:      condition,
 		TimeoutSeconds: timeout,
 	}
+	target.stampDeadline(req)
 	if err := writer.SendRequest(req); err != nil {
 		return err
 	}
@@ -1327,6 +2983,7 @@ func Request(target *Target, fromID *uint32, toID uint32, body string, timeout u
 		TimeoutSeconds: &timeout,
 		Delivery:       delivery,
 	}
+	target.stampDeadline(req)
 	if err := writer.SendRequest(req); err != nil {
 		return fmt.Errorf("sending request: %w", err)
 	}
@@ -2080,3 +3737,39 @@ func formatRelativeTime(iso string) string {
 		return fmt.Sprintf("%dd ago", int(d.Hours()/24))
 	}
 }
+
+// sessionRuntime returns how long a session ran: the recorded duration for
+// finished sessions, or the elapsed time since launch for running ones.
+func sessionRuntime(s protocol.SessionInfo) string {
+	if s.DurationMs != nil {
+		return formatDuration(time.Duration(*s.DurationMs) * time.Millisecond)
+	}
+	t, err := time.Parse(time.RFC3339, s.CreatedAt)
+	if err != nil {
+		return "-"
+	}
+	return formatDuration(time.Since(t))
+}
+
+// shortCommit abbreviates a git commit hash to 7 characters.
+func shortCommit(commit string) string {
+	if len(commit) > 7 {
+		return commit[:7]
+	}
+	return commit
+}
+
+// formatDuration renders a duration compactly, e.g. "45s", "2m10s", "1h5m".
+func formatDuration(d time.Duration) string {
+	if d < 0 {
+		d = 0
+	}
+	switch {
+	case d < time.Minute:
+		return fmt.Sprintf("%ds", int(d.Seconds()))
+	case d < time.Hour:
+		return fmt.Sprintf("%dm%ds", int(d.Minutes()), int(d.Seconds())%60)
+	default:
+		return fmt.Sprintf("%dh%dm", int(d.Hours()), int(d.Minutes())%60)
+	}
+}
@@ -0,0 +1,208 @@
+package client
+
+import (
+	"bytes"
+	"fmt"
+	"io"
+	"strings"
+)
+
+// diffContext is the number of unchanged lines shown around each change.
+const diffContext = 3
+
+// maxDiffEdits bounds the edit distance diffLines searches; transcripts
+// that differ more than this are shown as one replacement.
+const maxDiffEdits = 2000
+
+// Diff writes a unified diff of two sessions' output logs to w and reports
+// whether they differ. The sessions may be on different nodes. Lines are
+// compared without trailing carriage returns; stripANSI also removes escape
+// sequences, which is usually what you want when comparing TUI output.
+func Diff(targetA *Target, idA uint32, targetB *Target, idB uint32, stripANSI bool, w io.Writer) (bool, error) {
+	a, err := transcriptLines(targetA, idA, stripANSI)
+	if err != nil {
+		return false, err
+	}
+	b, err := transcriptLines(targetB, idB, stripANSI)
+	if err != nil {
+		return false, err
+	}
+	diff := unifiedDiff(sessionRef(targetA, idA), sessionRef(targetB, idB), a, b)
+	_, err = io.WriteString(w, diff)
+	return diff != "", err
+}
+
+// transcriptLines fetches a session's output log as lines.
+func transcriptLines(target *Target, id uint32, stripANSI bool) ([]string, error) {
+	var buf bytes.Buffer
+	if err := Logs(target, id, false, nil, !stripANSI, &buf); err != nil {
+		return nil, err
+	}
+	text := strings.TrimSuffix(buf.String(), "\n")
+	if text == "" {
+		return nil, nil
+	}
+	lines := strings.Split(text, "\n")
+	for i, l := range lines {
+		lines[i] = strings.TrimSuffix(l, "\r")
+	}
+	return lines, nil
+}
+
+// diffOp is one line of an edit script: ' ' kept, '-' deleted, '+' inserted.
+type diffOp struct {
+	kind byte
+	line string
+}
+
+// diffLines returns the shortest edit script turning a into b, using
+// Myers' O(ND) algorithm.
+func diffLines(a, b []string) []diffOp {
+	n, m := len(a), len(b)
+	// trace[d][k+d] is the furthest x reached on diagonal k with d edits.
+	var trace [][]int
+	for d := 0; ; d++ {
+		if d > maxDiffEdits {
+			ops := make([]diffOp, 0, n+m)
+			for _, l := range a {
+				ops = append(ops, diffOp{'-', l})
+			}
+			for _, l := range b {
+				ops = append(ops, diffOp{'+', l})
+			}
+			return ops
+		}
+		cur := make([]int, 2*d+1)
+		for k := -d; k <= d; k += 2 {
+			var x int
+			switch {
+			case d == 0:
+			case k == -d || (k != d && trace[d-1][k-1+d-1] < trace[d-1][k+1+d-1]):
+				x = trace[d-1][k+1+d-1]
+			default:
+				x = trace[d-1][k-1+d-1] + 1
+			}
+			y := x - k
+			for x < n && y < m && a[x] == b[y] {
+				x++
+				y++
+			}
+			cur[k+d] = x
+			if x >= n && y >= m {
+				return backtrack(append(trace, cur), a, b)
+			}
+		}
+		trace = append(trace, cur)
+	}
+}
+
+// backtrack walks the Myers trace from the end back to the start.
+func backtrack(trace [][]int, a, b []string) []diffOp {
+	var ops []diffOp
+	x, y := len(a), len(b)
+	for d := len(trace) - 1; d > 0; d-- {
+		prev := trace[d-1]
+		k := x - y
+		down := k == -d || (k != d && prev[k-1+d-1] < prev[k+1+d-1])
+		prevK := k - 1
+		if down {
+			prevK = k + 1
+		}
+		prevX := prev[prevK+d-1]
+		prevY := prevX - prevK
+		startX := prevX + 1
+		if down {
+			startX = prevX
+		}
+		for x > startX {
+			ops = append(ops, diffOp{' ', a[x-1]})
+			x--
+			y--
+		}
+		if down {
+			ops = append(ops, diffOp{'+', b[prevY]})
+		} else {
+			ops = append(ops, diffOp{'-', a[prevX]})
+		}
+		x, y = prevX, prevY
+	}
+	for x > 0 {
+		ops = append(ops, diffOp{' ', a[x-1]})
+		x--
+	}
+	for i, j := 0, len(ops)-1; i < j; i, j = i+1, j-1 {
+		ops[i], ops[j] = ops[j], ops[i]
+	}
+	return ops
+}
+
+// unifiedDiff formats the differences between a and b in unified diff
+// format, or returns "" when they are equal.
+func unifiedDiff(nameA, nameB string, a, b []string) string {
+	ops := diffLines(a, b)
+
+	// Line numbers in a and b at each op.
+	lineA := make([]int, len(ops)+1)
+	lineB := make([]int, len(ops)+1)
+	for i, op := range ops {
+		lineA[i+1], lineB[i+1] = lineA[i], lineB[i]
+		if op.kind != '+' {
+			lineA[i+1]++
+		}
+		if op.kind != '-' {
+			lineB[i+1]++
+		}
+	}
+
+	var out strings.Builder
+	for i := 0; i < len(ops); {
+		for i < len(ops) && ops[i].kind == ' ' {
+			i++
+		}
+		if i == len(ops) {
+			break
+		}
+		start := max(i-diffContext, 0)
+		end := i
+		for {
+			for end < len(ops) && ops[end].kind != ' ' {
+				end++
+			}
+			next := end
+			for next < len(ops) && ops[next].kind == ' ' {
+				next++
+			}
+			if next < len(ops) && next-end <= 2*diffContext {
+				end = next
+				continue
+			}
+			end = min(end+diffContext, len(ops))
+			break
+		}
+
+		if out.Len() == 0 {
+			fmt.Fprintf(&out, "--- %s\n+++ %s\n", nameA, nameB)
+		}
+		fmt.Fprintf(&out, "@@ -%s +%s @@\n",
+			hunkRange(lineA[start], lineA[end]-lineA[start]),
+			hunkRange(lineB[start], lineB[end]-lineB[start]))
+		for _, op := range ops[start:end] {
+			out.WriteByte(op.kind)
+			out.WriteString(op.line)
+			out.WriteByte('\n')
+		}
+		i = end
+	}
+	return out.String()
+}
+
+// hunkRange formats a hunk's line range; start is 0-based.
+func hunkRange(start, count int) string {
+	if count == 0 {
+		return fmt.Sprintf("%d,0", start)
+	}
+	if count == 1 {
+		return fmt.Sprintf("%d", start+1)
+	}
+	return fmt.Sprintf("%d,%d", start+1, count)
+}
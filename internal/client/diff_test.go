@@ -0,0 +1,59 @@
+package client
+
+import (
+	"strconv"
+	"strings"
+	"testing"
+)
+
+func TestUnifiedDiff(t *testing.T) {
+	var a []string
+	for i := 1; i <= 10; i++ {
+		a = append(a, strconv.Itoa(i))
+	}
+	b := append([]string(nil), a...)
+	b[4] = "five"
+
+	want := `--- a
++++ b
+@@ -2,7 +2,7 @@
+ 2
+ 3
+ 4
+-5
++five
+ 6
+ 7
+ 8
+`
+	if got := unifiedDiff("a", "b", a, b); got != want {
+		t.Errorf("unifiedDiff =\n%s\nwant\n%s", got, want)
+	}
+	if got := unifiedDiff("a", "b", a, a); got != "" {
+		t.Errorf("equal inputs produced a diff:\n%s", got)
+	}
+}
+
+func TestUnifiedDiffEmptySide(t *testing.T) {
+	got := unifiedDiff("a", "b", nil, []string{"only"})
+	if !strings.Contains(got, "@@ -0,0 +1 @@\n+only\n") {
+		t.Errorf("unexpected diff:\n%s", got)
+	}
+}
+
+func TestDiffLinesRoundTrips(t *testing.T) {
+	a := strings.Split("the quick brown fox jumps over the lazy dog", " ")
+	b := strings.Split("the slow brown cat jumps over the dog again", " ")
+	var gotA, gotB []string
+	for _, op := range diffLines(a, b) {
+		if op.kind != '+' {
+			gotA = append(gotA, op.line)
+		}
+		if op.kind != '-' {
+			gotB = append(gotB, op.line)
+		}
+	}
+	if strings.Join(gotA, " ") != strings.Join(a, " ") || strings.Join(gotB, " ") != strings.Join(b, " ") {
+		t.Errorf("edit script does not reproduce inputs: %v / %v", gotA, gotB)
+	}
+}
@@ -0,0 +1,112 @@
+package client
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// EventQuery selects events from a node's event store.
+type EventQuery struct {
+	SessionID *uint32
+	Tags      []string
+	Types     []string
+	// Since and Until bound the events' times: an age ("12h", "30d") or an
+	// RFC 3339 time. Until is exclusive.
+	Since string
+	Until string
+	Limit uint32
+}
+
+// eventTime turns a --since or --until value into an RFC 3339 time.
+func eventTime(flag, s string) (string, error) {
+	if s == "" {
+		return "", nil
+	}
+	if t, err := time.Parse(time.RFC3339, s); err == nil {
+		return t.UTC().Format(time.RFC3339), nil
+	}
+	age, err := parseAge(s)
+	if err != nil {
+		return "", fmt.Errorf("invalid --%s %q: expected a duration like 12h or 30d, or an RFC 3339 time", flag, s)
+	}
+	return time.Now().Add(-age).UTC().Format(time.RFC3339), nil
+}
+
+// QueryEvents returns the events matching q, oldest first: the newest
+// q.Limit of them, or with after the q.Limit events following that
+// sequence number.
+func QueryEvents(target *Target, q EventQuery, after uint64) ([]protocol.SessionEvent, error) {
+	req := &protocol.Request{
+		Type:       "QueryEvents",
+		ID:         q.SessionID,
+		Tags:       q.Tags,
+		EventTypes: q.Types,
+		Limit:      q.Limit,
+		After:      after,
+	}
+	var err error
+	if req.Since, err = eventTime("since", q.Since); err != nil {
+		return nil, err
+	}
+	if req.Until, err = eventTime("until", q.Until); err != nil {
+		return nil, err
+	}
+
+	resp, err := requestResponse(target, req)
+	if err != nil {
+		return nil, err
+	}
+	if resp.Type == "Error" {
+		return nil, fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "Events" {
+		return nil, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	return resp.Events, nil
+}
+
+// Events prints the events matching q to w, one per line (JSON lines with
+// jsonOutput). With follow it then polls for new events until interrupted.
+func Events(target *Target, q EventQuery, jsonOutput, follow bool, w io.Writer) error {
+	events, err := QueryEvents(target, q, 0)
+	if err != nil {
+		return err
+	}
+	var last uint64
+	for {
+		for _, e := range events {
+			if err := printEvent(target, e, jsonOutput, w); err != nil {
+				return err
+			}
+			last = max(last, e.Seq)
+		}
+		if !follow {
+			return nil
+		}
+		time.Sleep(time.Second)
+		if events, err = QueryEvents(target, q, last); err != nil {
+			return err
+		}
+	}
+}
+
+func printEvent(target *Target, e protocol.SessionEvent, jsonOutput bool, w io.Writer) error {
+	if jsonOutput {
+		data, err := json.Marshal(e)
+		if err != nil {
+			return err
+		}
+		_, err = fmt.Fprintf(w, "%s\n", data)
+		return err
+	}
+	ts := e.Timestamp
+	if t, err := time.Parse(time.RFC3339Nano, e.Timestamp); err == nil {
+		ts = t.Local().Format("2006-01-02 15:04:05")
+	}
+	_, err := fmt.Fprintf(w, "%s  %-8s %-26s %s\n", ts, sessionRef(target, e.SessionID), e.EventType, e.Data)
+	return err
+}
@@ -0,0 +1,122 @@
+package client
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"os"
+	"strconv"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// parseAge parses a --since value: a Go duration ("12h") or a number of
+// days ("30d").
+func parseAge(s string) (time.Duration, error) {
+	if days, ok := strings.CutSuffix(s, "d"); ok {
+		n, err := strconv.Atoi(days)
+		if err == nil && n > 0 {
+			return time.Duration(n) * 24 * time.Hour, nil
+		}
+	} else if d, err := time.ParseDuration(s); err == nil && d > 0 {
+		return d, nil
+	}
+	return 0, fmt.Errorf("invalid --since %q: expected a duration like 12h or 30d", s)
+}
+
+// ExportHistory writes the target's finished sessions to w as a JSON array
+// of history records. since ("30d", "12h") limits the export to recently
+// created sessions; transcripts includes each session's output log. A
+// bookmark range limits the export to sessions with those bookmarks, with
+// transcripts sliced between them.
+func ExportHistory(target *Target, since string, transcripts bool, rng LogRange, w io.Writer) error {
+	req := &protocol.Request{
+		Type:         "ExportHistory",
+		Transcripts:  transcripts,
+		FromBookmark: rng.FromBookmark,
+		ToBookmark:   rng.ToBookmark,
+	}
+	if since != "" {
+		age, err := parseAge(since)
+		if err != nil {
+			return err
+		}
+		req.Since = time.Now().Add(-age).UTC().Format(time.RFC3339)
+	}
+
+	reader, writer, err := target.Connect()
+	if err != nil {
+		return err
+	}
+	defer reader.Close()
+	defer writer.Close()
+	if err := writer.SendRequest(req); err != nil {
+		return fmt.Errorf("sending export request: %w", err)
+	}
+
+	records := make([]protocol.HistoryRecord, 0)
+	for {
+		resp, err := readControlResponse(reader)
+		if err != nil {
+			return err
+		}
+		switch resp.Type {
+		case "HistoryRecord":
+			if resp.Record == nil {
+				continue
+			}
+			rec := *resp.Record
+			if rec.Node == "" {
+				rec.Node = target.NodeName()
+			}
+			if rec.TranscriptTruncated {
+				fmt.Fprintf(os.Stderr, "[cw] warning: transcript of session %s exported from its tail only\n", sessionRef(target, rec.Session.ID))
+			}
+			records = append(records, rec)
+		case "HistoryEnd":
+			data, err := json.MarshalIndent(records, "", "  ")
+			if err != nil {
+				return err
+			}
+			if _, err := fmt.Fprintf(w, "%s\n", data); err != nil {
+				return err
+			}
+			fmt.Fprintf(os.Stderr, "Exported %d sessions\n", len(records))
+			return nil
+		case "Error":
+			return fmt.Errorf("%s", formatError(resp.Message))
+		default:
+			return fmt.Errorf("unexpected response type: %s", resp.Type)
+		}
+	}
+}
+
+// ImportHistory reads records written by ExportHistory from r and adds them
+// to the target as finished sessions with new IDs.
+func ImportHistory(target *Target, r io.Reader) error {
+	var records []protocol.HistoryRecord
+	if err := json.NewDecoder(r).Decode(&records); err != nil {
+		return fmt.Errorf("parsing history: %w", err)
+	}
+	for _, rec := range records {
+		resp, err := requestResponse(target, &protocol.Request{Type: "ImportHistory", Record: &rec})
+		if err != nil {
+			return err
+		}
+		if resp.Type == "Error" {
+			return fmt.Errorf("%s", formatError(resp.Message))
+		}
+		if resp.Type != "HistoryImported" || resp.ID == nil {
+			return fmt.Errorf("unexpected response type: %s", resp.Type)
+		}
+		from := fmt.Sprintf("%s:%d", rec.Node, rec.Session.ID)
+		if rec.Node == "" {
+			from = fmt.Sprintf("%d", rec.Session.ID)
+		}
+		fmt.Printf("Imported %s as %s\n", from, sessionRef(target, *resp.ID))
+	}
+	fmt.Fprintf(os.Stderr, "Imported %d sessions\n", len(records))
+	return nil
+}
@@ -0,0 +1,108 @@
+package client
+
+import (
+	"bytes"
+	"crypto/ecdh"
+	"crypto/ed25519"
+	"crypto/rand"
+	"fmt"
+
+	"github.com/codewiresh/codewire/internal/auth"
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// Identify challenges the target to sign a random nonce with its node key
+// and returns the node's name and public key once the signature checks out.
+// It does not check the key pinned for the target, and sends no token.
+func Identify(target *Target) (string, ed25519.PublicKey, error) {
+	reader, writer, err := target.dial()
+	if err != nil {
+		return "", nil, err
+	}
+	defer reader.Close()
+	defer writer.Close()
+	name, pub, _, err := handshake(reader, writer, target.NodeName())
+	return name, pub, err
+}
+
+// handshake has the node at the other end of a connection sign a fresh
+// nonce together with both sides' X25519 key shares for this connection.
+// It returns the node's name and public key, and the key a token sent on
+// this connection is sealed with: only the node that made the exchange can
+// open it, so a relayed handshake does not give the token away.
+func handshake(reader connection.FrameReader, writer connection.FrameWriter, label string) (string, ed25519.PublicKey, []byte, error) {
+	nonce := make([]byte, 32)
+	if _, err := rand.Read(nonce); err != nil {
+		return "", nil, nil, err
+	}
+	priv, err := ecdh.X25519().GenerateKey(rand.Reader)
+	if err != nil {
+		return "", nil, nil, err
+	}
+	clientShare := priv.PublicKey().Bytes()
+	if err := writer.SendRequest(&protocol.Request{Type: "Identify", Nonce: nonce, KeyShare: clientShare}); err != nil {
+		return "", nil, nil, fmt.Errorf("sending identify request: %w", err)
+	}
+	resp, err := readControlResponse(reader)
+	if err != nil {
+		return "", nil, nil, err
+	}
+	if resp.Type == "Error" {
+		return "", nil, nil, fmt.Errorf("%s", resp.Message)
+	}
+	if resp.Type != "Identity" {
+		return "", nil, nil, fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	pub := ed25519.PublicKey(resp.PublicKey)
+	msg := auth.HandshakeMessage(resp.NodeName, nonce, clientShare, resp.KeyShare)
+	if len(pub) != ed25519.PublicKeySize || !ed25519.Verify(pub, msg, resp.Signature) {
+		return "", nil, nil, fmt.Errorf("node %s sent an invalid identity signature", label)
+	}
+	nodeShare, err := ecdh.X25519().NewPublicKey(resp.KeyShare)
+	if err != nil {
+		return "", nil, nil, fmt.Errorf("node %s sent an invalid key share", label)
+	}
+	secret, err := priv.ECDH(nodeShare)
+	if err != nil {
+		return "", nil, nil, fmt.Errorf("node %s sent an invalid key share", label)
+	}
+	key, err := auth.TokenKey(secret, msg)
+	if err != nil {
+		return "", nil, nil, err
+	}
+	return resp.NodeName, pub, key, nil
+}
+
+// authenticate proves a remote node's identity on a fresh connection, then
+// sends it the target's token sealed to that connection's handshake. When a
+// key is pinned for the target, the node must hold that key.
+func (t *Target) authenticate(reader connection.FrameReader, writer connection.FrameWriter) error {
+	_, pub, key, err := handshake(reader, writer, t.NodeName())
+	if err != nil {
+		return fmt.Errorf("verifying identity of %s: %w", t.NodeName(), err)
+	}
+	if len(t.NodeKey) > 0 && !bytes.Equal(pub, t.NodeKey) {
+		return fmt.Errorf("identity of %s has changed: expected key %s, got %s\n\n"+
+			"Someone may be impersonating the node, or it was reinstalled. If you trust it, run 'cw server trust %s'",
+			t.NodeName(), auth.Fingerprint(t.NodeKey), auth.Fingerprint(pub), t.NodeName())
+	}
+	sealed, err := auth.SealToken(key, t.Token)
+	if err != nil {
+		return err
+	}
+	if err := writer.SendRequest(&protocol.Request{Type: "Authenticate", SealedToken: sealed}); err != nil {
+		return fmt.Errorf("sending token: %w", err)
+	}
+	resp, err := readControlResponse(reader)
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "Authenticated" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	return nil
+}
@@ -0,0 +1,192 @@
+package client
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// attachPingInterval is how often an attached client measures the round trip
+// to the node.
+const attachPingInterval = 5 * time.Second
+
+// latencyBounds are the upper bounds of the histogram buckets, in
+// milliseconds. A final bucket collects everything slower.
+var latencyBounds = []int64{10, 25, 50, 100, 250, 500, 1000, 2500}
+
+// LatencyHistogram accumulates round-trip samples for one node.
+type LatencyHistogram struct {
+	Buckets []uint64 `json:"buckets"` // len(latencyBounds)+1 counts
+	Count   uint64   `json:"count"`
+	SumMs   int64    `json:"sum_ms"`
+	MinMs   int64    `json:"min_ms"`
+	MaxMs   int64    `json:"max_ms"`
+	Updated string   `json:"updated,omitempty"`
+}
+
+// Add records one round trip.
+func (h *LatencyHistogram) Add(d time.Duration) {
+	if len(h.Buckets) != len(latencyBounds)+1 {
+		h.Buckets = make([]uint64, len(latencyBounds)+1)
+	}
+	ms := d.Milliseconds()
+	i := sort.Search(len(latencyBounds), func(i int) bool { return ms <= latencyBounds[i] })
+	h.Buckets[i]++
+	if h.Count == 0 || ms < h.MinMs {
+		h.MinMs = ms
+	}
+	if ms > h.MaxMs {
+		h.MaxMs = ms
+	}
+	h.Count++
+	h.SumMs += ms
+	h.Updated = time.Now().UTC().Format(time.RFC3339)
+}
+
+// Print writes the histogram as labelled bars.
+func (h *LatencyHistogram) Print() {
+	if h.Count == 0 {
+		fmt.Println("  no samples")
+		return
+	}
+	fmt.Printf("  %d samples: min %dms, avg %dms, max %dms\n", h.Count, h.MinMs, h.SumMs/int64(h.Count), h.MaxMs)
+	var peak uint64
+	for _, n := range h.Buckets {
+		peak = max(peak, n)
+	}
+	const barWidth = 40
+	for i, n := range h.Buckets {
+		label := fmt.Sprintf("> %dms", latencyBounds[len(latencyBounds)-1])
+		if i < len(latencyBounds) {
+			label = fmt.Sprintf("<= %dms", latencyBounds[i])
+		}
+		bar := strings.Repeat("#", int(n*barWidth/peak))
+		fmt.Printf("  %9s  %-*s %d\n", label, barWidth, bar, n)
+	}
+}
+
+// latencyFile is where attach round trips are recorded, keyed by node name.
+func latencyFile(dataDir string) string {
+	return filepath.Join(dataDir, "latency.json")
+}
+
+func loadLatency(dataDir string) (map[string]*LatencyHistogram, error) {
+	hists := make(map[string]*LatencyHistogram)
+	data, err := os.ReadFile(latencyFile(dataDir))
+	if os.IsNotExist(err) {
+		return hists, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	if err := json.Unmarshal(data, &hists); err != nil {
+		return nil, fmt.Errorf("parsing %s: %w", latencyFile(dataDir), err)
+	}
+	return hists, nil
+}
+
+// recordLatency adds a sample to the node's stored histogram.
+func recordLatency(dataDir, node string, d time.Duration) error {
+	hists, err := loadLatency(dataDir)
+	if err != nil {
+		return err
+	}
+	h := hists[node]
+	if h == nil {
+		h = &LatencyHistogram{}
+		hists[node] = h
+	}
+	h.Add(d)
+	data, err := json.MarshalIndent(hists, "", "  ")
+	if err != nil {
+		return err
+	}
+	tmp := latencyFile(dataDir) + ".tmp"
+	if err := os.WriteFile(tmp, data, 0o644); err != nil {
+		return err
+	}
+	return os.Rename(tmp, latencyFile(dataDir))
+}
+
+// TestLatency sends count pings to the target over one connection, prints
+// the round trips, and then prints the histogram recorded during attaches
+// to the same node (stored under dataDir).
+func TestLatency(target *Target, count int, dataDir string) error {
+	if count < 1 {
+		return fmt.Errorf("--count must be at least 1")
+	}
+	node := target.NodeName()
+	fmt.Printf("Pinging %s...\n", node)
+
+	reader, writer, err := target.Connect()
+	if err != nil {
+		return err
+	}
+	defer reader.Close()
+	defer writer.Close()
+
+	var probe LatencyHistogram
+	var samples []time.Duration
+	for seq := uint64(1); seq <= uint64(count); seq++ {
+		start := time.Now()
+		if err := writer.SendRequest(&protocol.Request{Type: "Ping", Seq: seq}); err != nil {
+			return fmt.Errorf("sending ping: %w", err)
+		}
+		resp, err := readControlResponse(reader)
+		if err != nil {
+			return err
+		}
+		if resp.Type == "Error" {
+			return fmt.Errorf("%s", formatError(resp.Message))
+		}
+		if resp.Type != "Pong" || resp.Seq != seq {
+			return fmt.Errorf("unexpected response to ping %d: %s", seq, resp.Type)
+		}
+		rtt := time.Since(start)
+		probe.Add(rtt)
+		samples = append(samples, rtt)
+	}
+
+	sort.Slice(samples, func(i, j int) bool { return samples[i] < samples[j] })
+	p95 := samples[len(samples)*95/100]
+	fmt.Printf("\nProbe (%d pings, p50 %dms, p95 %dms):\n", count, samples[len(samples)/2].Milliseconds(), p95.Milliseconds())
+	probe.Print()
+
+	hists, err := loadLatency(dataDir)
+	if err != nil {
+		return err
+	}
+	fmt.Printf("\nRecorded during attach:\n")
+	if h := hists[node]; h != nil {
+		h.Print()
+	} else {
+		fmt.Println("  no samples (attach to a session on this node to record some)")
+	}
+	return nil
+}
+
+// readControlResponse reads the next control frame and decodes it.
+func readControlResponse(reader connection.FrameReader) (*protocol.Response, error) {
+	frame, err := reader.ReadFrame()
+	if err != nil {
+		return nil, fmt.Errorf("reading response: %w", err)
+	}
+	if frame == nil {
+		return nil, fmt.Errorf("connection closed before response")
+	}
+	if frame.Type != protocol.FrameControl {
+		return nil, fmt.Errorf("expected control frame, got type 0x%02x", frame.Type)
+	}
+	var resp protocol.Response
+	if err := json.Unmarshal(frame.Payload, &resp); err != nil {
+		return nil, fmt.Errorf("parsing response: %w", err)
+	}
+	return &resp, nil
+}
@@ -0,0 +1,39 @@
+package client
+
+import (
+	"testing"
+	"time"
+)
+
+func TestLatencyHistogramBuckets(t *testing.T) {
+	var h LatencyHistogram
+	for _, ms := range []int{5, 10, 11, 400, 9000} {
+		h.Add(time.Duration(ms) * time.Millisecond)
+	}
+	want := []uint64{2, 1, 0, 0, 0, 1, 0, 0, 1}
+	for i, n := range want {
+		if h.Buckets[i] != n {
+			t.Errorf("bucket %d = %d, want %d (all: %v)", i, h.Buckets[i], n, h.Buckets)
+		}
+	}
+	if h.Count != 5 || h.MinMs != 5 || h.MaxMs != 9000 {
+		t.Errorf("count/min/max = %d/%d/%d", h.Count, h.MinMs, h.MaxMs)
+	}
+}
+
+func TestRecordLatencyPersistsPerNode(t *testing.T) {
+	dir := t.TempDir()
+	if err := recordLatency(dir, "gpu-box", 30*time.Millisecond); err != nil {
+		t.Fatal(err)
+	}
+	if err := recordLatency(dir, "gpu-box", 60*time.Millisecond); err != nil {
+		t.Fatal(err)
+	}
+	hists, err := loadLatency(dir)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if h := hists["gpu-box"]; h == nil || h.Count != 2 || h.SumMs != 90 {
+		t.Fatalf("unexpected histogram: %+v", hists["gpu-box"])
+	}
+}
@@ -0,0 +1,203 @@
+package client
+
+import (
+	"cmp"
+	"fmt"
+	"io"
+	"math"
+	"os"
+	"os/exec"
+	"regexp"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/ansi"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/textwidth"
+)
+
+// maxMonitorLine bounds the unfinished line kept for matching.
+const maxMonitorLine = 4096
+
+// MonitorRule runs Command through sh -c when a line of output matches
+// Pattern. {id} in the command is replaced with the session's reference and
+// {name} with its name (its reference when unnamed). The matching line is
+// passed in $CW_MATCH_LINE rather than substituted, so session output never
+// becomes shell code.
+type MonitorRule struct {
+	Pattern *regexp.Regexp
+	Command string
+
+	last  time.Time // when the rule last fired
+	fired int
+}
+
+// ParseMonitorRules pairs --on patterns (regular expressions) with their
+// commands.
+func ParseMonitorRules(patterns, commands []string) ([]*MonitorRule, error) {
+	if len(patterns) == 0 {
+		return nil, fmt.Errorf("at least one --on <pattern> <command> rule is required")
+	}
+	if len(patterns) != len(commands) {
+		return nil, fmt.Errorf("got %d --on patterns but %d commands: each pattern needs a command, as in cw monitor <session> --on <pattern> <command>", len(patterns), len(commands))
+	}
+	rules := make([]*MonitorRule, len(patterns))
+	for i, p := range patterns {
+		re, err := regexp.Compile(p)
+		if err != nil {
+			return nil, fmt.Errorf("invalid pattern %q: %w", p, err)
+		}
+		if strings.TrimSpace(commands[i]) == "" {
+			return nil, fmt.Errorf("empty command for pattern %q", p)
+		}
+		rules[i] = &MonitorRule{Pattern: re, Command: commands[i]}
+	}
+	return rules, nil
+}
+
+// MonitorOptions controls Monitor.
+type MonitorOptions struct {
+	Rules []*MonitorRule
+	// Cooldown is the least time between two runs of the same rule, so an
+	// action whose effect shows up in the output again cannot loop.
+	Cooldown time.Duration
+	// Once lets each rule fire only once.
+	Once bool
+	// Timeout stops monitoring after this many seconds; nil means never.
+	Timeout *uint64
+}
+
+// monitorMatch is a rule that fired on a line.
+type monitorMatch struct {
+	rule *MonitorRule
+	line string
+}
+
+// outputMatcher splits a session's output into lines, without escape
+// sequences, and matches them against the rules. The line being written is
+// matched too, so prompts that wait without a newline are seen; a rule
+// fires at most once per line.
+type outputMatcher struct {
+	rules    []*MonitorRule
+	cooldown time.Duration
+	once     bool
+	partial  string
+	fired    map[*MonitorRule]bool // rules fired on the unfinished line
+	strip    ansi.Stripper
+}
+
+func (m *outputMatcher) feed(output string, now time.Time) []monitorMatch {
+	text := m.partial + string(m.strip.Strip([]byte(output)))
+	lines := strings.Split(text, "\n")
+	m.partial = lines[len(lines)-1]
+	if len(m.partial) > maxMonitorLine {
+		m.partial = textwidth.Tail(m.partial, maxMonitorLine)
+	}
+	var matches []monitorMatch
+	for _, l := range lines[:len(lines)-1] {
+		matches = m.match(lastSegment(l), now, matches)
+		m.fired = nil
+	}
+	return m.match(lastSegment(m.partial), now, matches)
+}
+
+func (m *outputMatcher) match(line string, now time.Time, matches []monitorMatch) []monitorMatch {
+	if strings.TrimSpace(line) == "" {
+		return matches
+	}
+	for _, r := range m.rules {
+		if m.fired[r] || !r.Pattern.MatchString(line) {
+			continue
+		}
+		if m.fired == nil {
+			m.fired = make(map[*MonitorRule]bool)
+		}
+		m.fired[r] = true
+		if (m.once && r.fired > 0) || (!r.last.IsZero() && now.Sub(r.last) < m.cooldown) {
+			continue
+		}
+		r.last = now
+		r.fired++
+		matches = append(matches, monitorMatch{rule: r, line: line})
+	}
+	return matches
+}
+
+// Monitor watches a running session's output from now on and runs the
+// command of every rule whose pattern a line matches, until the session
+// ends or the timeout passes. Matches and failed actions are reported to
+// w; actions' own output goes to this process's stdout and stderr.
+func Monitor(target *Target, id uint32, opts MonitorOptions, w io.Writer) error {
+	resp, err := requestResponse(target, &protocol.Request{Type: "GetStatus", ID: &id})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Info == nil {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	ref := sessionRef(target, id)
+	if resp.Info.Status != "running" {
+		return fmt.Errorf("session %s is not running (%s)", ref, resp.Info.Status)
+	}
+	expand := strings.NewReplacer("{id}", ref, "{name}", cmp.Or(resp.Info.Name, ref))
+
+	matcher := &outputMatcher{rules: opts.Rules, cooldown: opts.Cooldown, once: opts.Once}
+	events := make(chan summaryEvent, 64)
+	go watchSummaryEvents(target, 0, id, events)
+
+	timeoutDuration := time.Duration(math.MaxInt64)
+	if opts.Timeout != nil {
+		timeoutDuration = time.Duration(*opts.Timeout) * time.Second
+	}
+	timer := time.NewTimer(timeoutDuration)
+	defer timer.Stop()
+
+	var actions sync.WaitGroup
+	defer actions.Wait()
+	fmt.Fprintf(os.Stderr, "[cw] monitoring session %s with %d rules\n", ref, len(opts.Rules))
+	for {
+		select {
+		case ev := <-events:
+			if ev.err != nil {
+				return ev.err
+			}
+			now := time.Now()
+			for _, m := range matcher.feed(ev.output, now) {
+				command := expand.Replace(m.rule.Command)
+				fmt.Fprintf(w, "%s /%s/ matched: %s\n", now.Format(time.TimeOnly), m.rule.Pattern, textwidth.Truncate(strings.TrimSpace(m.line), maxSummaryLine))
+				fmt.Fprintf(w, "%s   running: %s\n", now.Format(time.TimeOnly), command)
+				actions.Add(1)
+				go func() {
+					defer actions.Done()
+					if err := runMonitorAction(command, ref, m); err != nil {
+						fmt.Fprintf(w, "%s   /%s/ action failed: %v\n", time.Now().Format(time.TimeOnly), m.rule.Pattern, err)
+					}
+				}()
+			}
+			if ev.done {
+				fmt.Fprintf(w, "%s session %s ended: %s\n", now.Format(time.TimeOnly), ref, cmp.Or(ev.status, "disconnected"))
+				return nil
+			}
+		case <-timer.C:
+			fmt.Fprintf(os.Stderr, "\n[cw] monitor timeout reached\n")
+			return nil
+		}
+	}
+}
+
+// runMonitorAction runs a rule's command through sh -c.
+func runMonitorAction(command, ref string, m monitorMatch) error {
+	cmd := exec.Command("sh", "-c", command)
+	cmd.Stdout = os.Stdout
+	cmd.Stderr = os.Stderr
+	cmd.Env = append(os.Environ(),
+		"CW_MATCH_SESSION="+ref,
+		"CW_MATCH_PATTERN="+m.rule.Pattern.String(),
+		"CW_MATCH_LINE="+m.line,
+	)
+	return cmd.Run()
+}
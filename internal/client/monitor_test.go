@@ -0,0 +1,50 @@
+package client
+
+import (
+	"testing"
+	"time"
+)
+
+func TestOutputMatcher(t *testing.T) {
+	rules, err := ParseMonitorRules([]string{"ERROR", `^ALL TESTS PASSED$`, `\[y/n\]`}, []string{"cw send {id} fix", "cw kill {id}", "cw send {id} y"})
+	if err != nil {
+		t.Fatalf("ParseMonitorRules: %v", err)
+	}
+	m := &outputMatcher{rules: rules, cooldown: time.Minute}
+	now := time.Now()
+
+	got := m.feed("building\n\x1b[31mERROR\x1b[0m: x\nERR", now)
+	if len(got) != 1 || got[0].rule != rules[0] || got[0].line != "ERROR: x" {
+		t.Fatalf("first feed: %+v", got)
+	}
+	// The rule is cooling down, so the completed "ERROR" line is skipped.
+	if got := m.feed("OR again\n", now.Add(time.Second)); len(got) != 0 {
+		t.Fatalf("expected cooldown, got %+v", got)
+	}
+	if got := m.feed("ERROR later\n", now.Add(2*time.Minute)); len(got) != 1 {
+		t.Fatalf("expected a match after the cooldown, got %+v", got)
+	}
+
+	// A prompt without a newline matches once, not again when it ends.
+	if got := m.feed("Continue? [y/n] ", now); len(got) != 1 || got[0].rule != rules[2] {
+		t.Fatalf("prompt: %+v", got)
+	}
+	if got := m.feed("\n", now.Add(2*time.Minute)); len(got) != 0 {
+		t.Fatalf("prompt matched twice: %+v", got)
+	}
+	if got := m.feed("ALL TESTS PASSED\r\n", now); len(got) != 1 || got[0].rule != rules[1] {
+		t.Fatalf("anchored pattern: %+v", got)
+	}
+
+	m.once = true
+	if got := m.feed("ALL TESTS PASSED\n", now.Add(time.Hour)); len(got) != 0 {
+		t.Fatalf("expected --once to stop a second run, got %+v", got)
+	}
+
+	if _, err := ParseMonitorRules([]string{"a", "b"}, []string{"true"}); err == nil {
+		t.Fatal("expected unpaired patterns to be rejected")
+	}
+	if _, err := ParseMonitorRules([]string{"("}, []string{"true"}); err == nil {
+		t.Fatal("expected an invalid pattern to be rejected")
+	}
+}
@@ -0,0 +1,147 @@
+package client
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+	"time"
+)
+
+// RelayNode is a node registered with a relay, as listed by Nodes.
+type RelayNode struct {
+	Name      string `json:"name"`
+	TunnelURL string `json:"tunnel_url"`
+	Connected bool   `json:"connected"`
+}
+
+// defaultNodesTimeout bounds a relay listing when NodesOptions.Timeout is
+// zero.
+const defaultNodesTimeout = 5 * time.Second
+
+// NodesOptions controls how Nodes asks the relay and when it may answer
+// from the discovery cache instead.
+type NodesOptions struct {
+	// DataDir holds the cache (nodes-cache.json). Empty disables caching.
+	DataDir string
+	// Cached uses the cached list whatever its age, if there is one.
+	Cached bool
+	// MaxAge uses the cached list when it is younger than this.
+	MaxAge time.Duration
+	// Timeout bounds the whole listing, including waiting for WaitFor
+	// (default 5s).
+	Timeout time.Duration
+	// WaitFor names nodes that must be online: the relay is asked again
+	// until they are, instead of answering from the cache.
+	WaitFor []string
+}
+
+// discovery is the outcome of one relay listing.
+type discovery struct {
+	Nodes []RelayNode
+	Age   time.Duration // how old a cached answer is; zero when fetched now
+	Err   error         // why the relay was not used, for a stale fallback
+}
+
+// nodesCache is the last successful discovery from a relay.
+type nodesCache struct {
+	RelayURL  string      `json:"relay_url"`
+	FetchedAt time.Time   `json:"fetched_at"`
+	Nodes     []RelayNode `json:"nodes"`
+}
+
+func nodesCacheFile(dataDir string) string {
+	return filepath.Join(dataDir, "nodes-cache.json")
+}
+
+// loadNodesCache returns the cached discovery for relayURL, or nil when
+// there is none.
+func loadNodesCache(dataDir, relayURL string) *nodesCache {
+	data, err := os.ReadFile(nodesCacheFile(dataDir))
+	if err != nil {
+		return nil
+	}
+	var c nodesCache
+	if json.Unmarshal(data, &c) != nil || c.RelayURL != relayURL {
+		return nil
+	}
+	return &c
+}
+
+// saveNodesCache records a successful discovery, replacing the file
+// atomically so concurrent scripts never read half of it.
+func saveNodesCache(dataDir, relayURL string, nodes []RelayNode) error {
+	data, err := json.MarshalIndent(nodesCache{RelayURL: relayURL, FetchedAt: time.Now().UTC(), Nodes: nodes}, "", "  ")
+	if err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(dataDir, "nodes-cache-*.tmp")
+	if err != nil {
+		return err
+	}
+	if _, err := tmp.Write(data); err != nil {
+		tmp.Close()
+		os.Remove(tmp.Name())
+		return err
+	}
+	if err := tmp.Close(); err != nil {
+		os.Remove(tmp.Name())
+		return err
+	}
+	return os.Rename(tmp.Name(), nodesCacheFile(dataDir))
+}
+
+// discoverNodes returns the relay's nodes, from the cache when opts allow.
+// When the relay fails or takes longer than timeout, the cached list is
+// returned with the error in discovery.Err, unless opts.WaitFor needs a
+// live answer.
+func discoverNodes(relayURL string, opts NodesOptions, timeout time.Duration) (discovery, error) {
+	var cached *nodesCache
+	if opts.DataDir != "" {
+		cached = loadNodesCache(opts.DataDir, relayURL)
+	}
+	if cached != nil && len(opts.WaitFor) == 0 {
+		age := time.Since(cached.FetchedAt)
+		if opts.Cached || age < opts.MaxAge {
+			return discovery{Nodes: cached.Nodes, Age: max(age, time.Nanosecond)}, nil
+		}
+	}
+
+	nodes, err := fetchNodes(relayURL, timeout)
+	if err != nil {
+		if cached != nil && len(opts.WaitFor) == 0 {
+			return discovery{Nodes: cached.Nodes, Age: max(time.Since(cached.FetchedAt), time.Nanosecond), Err: err}, nil
+		}
+		return discovery{}, err
+	}
+	if opts.DataDir != "" {
+		_ = saveNodesCache(opts.DataDir, relayURL, nodes)
+	}
+	return discovery{Nodes: nodes}, nil
+}
+
+// fetchNodes asks the relay for its registered nodes.
+func fetchNodes(relayURL string, timeout time.Duration) ([]RelayNode, error) {
+	resp, err := fetchJSON(relayURL+"/api/v1/nodes", timeout)
+	if err != nil {
+		return nil, err
+	}
+	var nodes []RelayNode
+	if err := json.Unmarshal(resp, &nodes); err != nil {
+		return nil, fmt.Errorf("parsing nodes: %w", err)
+	}
+	return nodes, nil
+}
+
+// missingNodes returns the names in want that are not online in nodes.
+func missingNodes(nodes []RelayNode, want []string) []string {
+	var missing []string
+	for _, name := range want {
+		online := slices.ContainsFunc(nodes, func(n RelayNode) bool { return n.Name == name && n.Connected })
+		if !online {
+			missing = append(missing, name)
+		}
+	}
+	return missing
+}
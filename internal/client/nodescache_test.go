@@ -0,0 +1,85 @@
+package client
+
+import (
+	"encoding/json"
+	"net/http"
+	"net/http/httptest"
+	"sync/atomic"
+	"testing"
+	"time"
+)
+
+func TestDiscoverNodesUsesCache(t *testing.T) {
+	var fetches atomic.Int32
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		fetches.Add(1)
+		json.NewEncoder(w).Encode([]RelayNode{{Name: "gpu-box", Connected: true}})
+	}))
+	defer srv.Close()
+	dir := t.TempDir()
+
+	d, err := discoverNodes(srv.URL, NodesOptions{DataDir: dir, MaxAge: time.Minute}, time.Second)
+	if err != nil || len(d.Nodes) != 1 || d.Age != 0 {
+		t.Fatalf("expected a fresh fetch, got %v (age %s): %v", d.Nodes, d.Age, err)
+	}
+	d, err = discoverNodes(srv.URL, NodesOptions{DataDir: dir, MaxAge: time.Minute}, time.Second)
+	if err != nil || len(d.Nodes) != 1 || d.Nodes[0].Name != "gpu-box" || d.Age == 0 {
+		t.Fatalf("expected the cached listing, got %v (age %s): %v", d.Nodes, d.Age, err)
+	}
+	if n := fetches.Load(); n != 1 {
+		t.Fatalf("expected one fetch, got %d", n)
+	}
+
+	// Too old for --max-age, but fine for --cached.
+	if _, err := discoverNodes(srv.URL, NodesOptions{DataDir: dir, MaxAge: time.Nanosecond}, time.Second); err != nil {
+		t.Fatal(err)
+	}
+	if n := fetches.Load(); n != 2 {
+		t.Fatalf("expected a stale cache to be refetched, got %d fetches", n)
+	}
+	if _, err := discoverNodes(srv.URL, NodesOptions{DataDir: dir, Cached: true}, time.Second); err != nil {
+		t.Fatal(err)
+	}
+	if n := fetches.Load(); n != 2 {
+		t.Fatalf("expected --cached not to fetch, got %d fetches", n)
+	}
+
+	// A cache from another relay is ignored.
+	if c := loadNodesCache(dir, "https://other.example"); c != nil {
+		t.Fatal("expected no cache for a different relay")
+	}
+}
+
+func TestDiscoverNodesFallsBackToStaleCache(t *testing.T) {
+	var slow atomic.Bool
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if slow.Load() {
+			time.Sleep(time.Second)
+		}
+		json.NewEncoder(w).Encode([]RelayNode{{Name: "gpu-box", Connected: true}})
+	}))
+	defer srv.Close()
+	dir := t.TempDir()
+
+	if _, err := discoverNodes(srv.URL, NodesOptions{DataDir: dir}, time.Second); err != nil {
+		t.Fatal(err)
+	}
+	slow.Store(true)
+	d, err := discoverNodes(srv.URL, NodesOptions{DataDir: dir}, 100*time.Millisecond)
+	if err != nil || d.Err == nil || len(d.Nodes) != 1 || d.Age == 0 {
+		t.Fatalf("expected the cached listing with the relay error, got %+v: %v", d, err)
+	}
+
+	// Waiting for nodes needs a live answer.
+	if _, err := discoverNodes(srv.URL, NodesOptions{DataDir: dir, WaitFor: []string{"gpu-box"}}, 100*time.Millisecond); err == nil {
+		t.Fatal("expected the timeout to be reported with --wait-for")
+	}
+}
+
+func TestMissingNodes(t *testing.T) {
+	nodes := []RelayNode{{Name: "a", Connected: true}, {Name: "b"}}
+	got := missingNodes(nodes, []string{"a", "b", "c"})
+	if len(got) != 2 || got[0] != "b" || got[1] != "c" {
+		t.Fatalf("expected [b c], got %v", got)
+	}
+}
@@ -0,0 +1,165 @@
+package client
+
+import (
+	"bytes"
+	"fmt"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/textwidth"
+)
+
+// infoOverlayKey is the prefix key (Ctrl+B i) that shows session details
+// over the attached terminal.
+const infoOverlayKey = 'i'
+
+// maxOverlayHeld caps the session output held back while the overlay is
+// shown; past it the overlay is closed so the output can be written.
+const maxOverlayHeld = 1 << 20
+
+// infoOverlay is the session details box drawn over an attach. Output that
+// arrives while it is shown is held and written once it is closed.
+//
+// The client keeps no copy of the screen, so how it is restored depends on
+// the session: over a normal screen the box is drawn on the alternate
+// screen, and leaving it brings the session's screen back untouched. Over a
+// full-screen program (already on the alternate screen) the box is drawn in
+// place, and closing it asks the program to redraw by nudging the PTY size.
+type infoOverlay struct {
+	onAltScreen bool // the session was on the alternate screen when shown
+	held        bytes.Buffer
+}
+
+// altScreenTracker follows whether a session's output has switched the
+// terminal to the alternate screen.
+type altScreenTracker struct {
+	active bool
+	tail   []byte // end of the previous chunk, for sequences split across chunks
+}
+
+var (
+	altScreenOn  = [][]byte{[]byte("\x1b[?1049h"), []byte("\x1b[?1047h"), []byte("\x1b[?47h")}
+	altScreenOff = [][]byte{[]byte("\x1b[?1049l"), []byte("\x1b[?1047l"), []byte("\x1b[?47l")}
+)
+
+// Feed scans a chunk of session output.
+func (a *altScreenTracker) Feed(p []byte) {
+	data := append(a.tail, p...)
+	last, on := -1, false
+	for _, seq := range altScreenOn {
+		if i := bytes.LastIndex(data, seq); i > last {
+			last, on = i, true
+		}
+	}
+	for _, seq := range altScreenOff {
+		if i := bytes.LastIndex(data, seq); i > last {
+			last, on = i, false
+		}
+	}
+	if last >= 0 {
+		a.active = on
+	}
+	keep := min(len(data), len("\x1b[?1049h")-1)
+	a.tail = append(a.tail[:0], data[len(data)-keep:]...)
+}
+
+// show returns the bytes that draw the overlay for info on a cols x rows
+// terminal.
+func (o *infoOverlay) show(info *protocol.SessionInfo, cols, rows uint16) []byte {
+	var out []byte
+	if o.onAltScreen {
+		out = append(out, "\x1b7"...)
+	} else {
+		out = append(out, "\x1b[?1049h\x1b[2J"...)
+	}
+	out = append(out, renderInfoOverlay(info, time.Now(), int(cols), int(rows))...)
+	return out
+}
+
+// hide returns the bytes that close the overlay, followed by the output
+// held while it was shown.
+func (o *infoOverlay) hide() []byte {
+	var out []byte
+	if o.onAltScreen {
+		out = append(out, "\x1b8"...)
+	} else {
+		out = append(out, "\x1b[?1049l"...)
+	}
+	return append(out, o.held.Bytes()...)
+}
+
+// renderInfoOverlay draws a box with info's details centered on a cols x rows
+// screen.
+func renderInfoOverlay(info *protocol.SessionInfo, now time.Time, cols, rows int) []byte {
+	title := fmt.Sprintf("Session %d", info.ID)
+	if info.Name != "" {
+		title += " (" + info.Name + ")"
+	}
+	type field struct{ label, value string }
+	fields := []field{
+		{"Command", info.Prompt},
+		{"Directory", info.WorkingDir},
+		{"Status", info.Status},
+	}
+	if created, err := time.Parse(time.RFC3339, info.CreatedAt); err == nil {
+		fields = append(fields, field{"Uptime", formatDuration(now.Sub(created))})
+	}
+	if pid := info.PID; pid != nil {
+		fields = append(fields, field{"PID", fmt.Sprint(*pid)})
+	}
+	limit := "none"
+	if t, err := time.Parse(time.RFC3339, info.ExpiresAt); err == nil {
+		limit = fmt.Sprintf("%s in %s", info.ExpiryLimit, formatDuration(t.Sub(now)))
+	}
+	fields = append(fields, field{"Limit", limit})
+	clients := fmt.Sprintf("%d attached", info.AttachedCount)
+	if info.AttachedCount > 1 {
+		clients += fmt.Sprintf(" (%d besides you)", info.AttachedCount-1)
+	}
+	fields = append(fields, field{"Clients", clients})
+	if len(info.Tags) > 0 {
+		fields = append(fields, field{"Tags", strings.Join(info.Tags, ", ")})
+	}
+	if info.GitBranch != "" {
+		fields = append(fields, field{"Branch", info.GitBranch})
+	}
+	for _, m := range info.Mirrors {
+		fields = append(fields, field{"Mirror", m})
+	}
+	if info.History != "" && info.History != "all" {
+		fields = append(fields, field{"History", info.History + " (output not recorded)"})
+	}
+	for _, n := range info.Notes {
+		fields = append(fields, field{"Note", n.Text})
+	}
+
+	width := min(cols-4, 76) // inner width, between the borders
+	if width < 20 || rows < 5 {
+		return nil
+	}
+	lines := []string{" " + title, ""}
+	for _, f := range fields {
+		lines = append(lines, fmt.Sprintf(" %-10s %s", f.label+":", f.value))
+	}
+	lines = append(lines, "", " Press any key to close")
+	if len(lines) > rows-2 {
+		lines = append(lines[:rows-3], " ...")
+	}
+
+	top := max((rows-len(lines)-2)/2, 0) + 1
+	left := (cols-width-2)/2 + 1
+	var b bytes.Buffer
+	b.WriteString("\x1b[0m")
+	row := top
+	fmt.Fprintf(&b, "\x1b[%d;%dH┌%s┐", row, left, strings.Repeat("─", width))
+	for _, line := range lines {
+		row++
+		line = textwidth.Truncate(line, width)
+		pad := width - textwidth.Width(line)
+		fmt.Fprintf(&b, "\x1b[%d;%dH│%s%s│", row, left, line, strings.Repeat(" ", pad))
+	}
+	row++
+	fmt.Fprintf(&b, "\x1b[%d;%dH└%s┘", row, left, strings.Repeat("─", width))
+	return b.Bytes()
+}
@@ -0,0 +1,46 @@
+package client
+
+import (
+	"strings"
+	"testing"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+func TestAltScreenTracker(t *testing.T) {
+	var a altScreenTracker
+	a.Feed([]byte("hello \x1b[?10"))
+	a.Feed([]byte("49h vim"))
+	if !a.active {
+		t.Fatal("expected a split 1049h to switch to the alternate screen")
+	}
+	a.Feed([]byte("\x1b[?1049l\x1b[?1049h\x1b[?1049l$ "))
+	if a.active {
+		t.Fatal("expected the last switch in a chunk to win")
+	}
+}
+
+func TestRenderInfoOverlay(t *testing.T) {
+	now := time.Date(2026, 1, 2, 15, 0, 0, 0, time.UTC)
+	info := &protocol.SessionInfo{
+		ID:            7,
+		Name:          "planner",
+		Prompt:        "claude -p 'plan the release'",
+		WorkingDir:    "/src/app",
+		Status:        "running",
+		CreatedAt:     now.Add(-90 * time.Minute).Format(time.RFC3339),
+		ExpiresAt:     now.Add(10 * time.Minute).Format(time.RFC3339),
+		ExpiryLimit:   "idle",
+		AttachedCount: 3,
+	}
+	out := string(renderInfoOverlay(info, now, 100, 30))
+	for _, want := range []string{"Session 7 (planner)", "claude -p 'plan the release'", "/src/app", "1h30m", "idle in 10m0s", "3 attached (2 besides you)"} {
+		if !strings.Contains(out, want) {
+			t.Errorf("expected overlay to contain %q", want)
+		}
+	}
+	if renderInfoOverlay(info, now, 10, 3) != nil {
+		t.Error("expected nothing on a terminal too small for the box")
+	}
+}
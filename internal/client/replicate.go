@@ -0,0 +1,60 @@
+package client
+
+import (
+	"fmt"
+	"os"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// Replicate fetches every session on the target for a standby, calling fn
+// with each. offsets is how much of each output log the standby holds.
+func Replicate(target *Target, offsets map[uint32]uint64, fn func(protocol.HistoryRecord) error) error {
+	reader, writer, err := target.Connect()
+	if err != nil {
+		return err
+	}
+	defer reader.Close()
+	defer writer.Close()
+	if err := writer.SendRequest(&protocol.Request{Type: "Replicate", Offsets: offsets}); err != nil {
+		return fmt.Errorf("sending replicate request: %w", err)
+	}
+	for {
+		resp, err := readControlResponse(reader)
+		if err != nil {
+			return err
+		}
+		switch resp.Type {
+		case "ReplicaRecord":
+			if resp.Record == nil {
+				continue
+			}
+			if err := fn(*resp.Record); err != nil {
+				return err
+			}
+		case "ReplicaEnd":
+			return nil
+		case "Error":
+			return fmt.Errorf("%s", formatError(resp.Message))
+		default:
+			return fmt.Errorf("unexpected response type: %s", resp.Type)
+		}
+	}
+}
+
+// noteReplica tells the user that what follows comes from a standby's copy
+// of its primary, and how old it is.
+func noteReplica(st *protocol.StandbyStatus) {
+	if st == nil {
+		return
+	}
+	age := "never synced"
+	if t, err := time.Parse(time.RFC3339, st.SyncedAt); err == nil {
+		age = "synced " + formatDuration(time.Since(t)) + " ago"
+	}
+	fmt.Fprintf(os.Stderr, "[cw] replica of %s (%s); sessions shown as running were running then\n", st.Primary, age)
+	if st.LastError != "" {
+		fmt.Fprintf(os.Stderr, "[cw] last sync failed: %s\n", st.LastError)
+	}
+}
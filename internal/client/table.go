@@ -0,0 +1,396 @@
+package client
+
+import (
+	"fmt"
+	"io"
+	"os"
+	"sort"
+	"strings"
+	"time"
+
+	"github.com/mattn/go-isatty"
+	"golang.org/x/term"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/textwidth"
+)
+
+// DefaultColumns is the column set printed by `cw list` when --columns is
+// not given.
+const DefaultColumns = "id,name,cmd,status,attached,age,finished,duration,branch"
+
+// TableOptions controls how a session table is rendered.
+type TableOptions struct {
+	Columns string // comma-separated column names; empty means DefaultColumns
+	Sort    string // sort key, optionally prefixed with "-" for descending
+	Time    string // timestamp style: TimeRelative (default), TimeAbsolute or TimeISO
+	Node    string // value of the node column
+	// QualifyIDs prefixes IDs with Node ("gpu-box:3") so they can be pasted
+	// into commands run against another target.
+	QualifyIDs bool
+}
+
+// Timestamp display styles accepted by --time.
+const (
+	TimeRelative = "relative" // "3d ago"
+	TimeAbsolute = "absolute" // "Tue 2026-10-13 23:41:07" in local time
+	TimeISO      = "iso"      // RFC 3339, as recorded by the node
+)
+
+// TimeFormats returns the values accepted by --time.
+func TimeFormats() []string {
+	return []string{TimeRelative, TimeAbsolute, TimeISO}
+}
+
+// ValidateTimeFormat reports an unknown --time value.
+func ValidateTimeFormat(mode string) error {
+	switch mode {
+	case "", TimeRelative, TimeAbsolute, TimeISO:
+		return nil
+	}
+	return fmt.Errorf("unknown time format %q (valid: %s)", mode, strings.Join(TimeFormats(), ", "))
+}
+
+// formatTimestamp renders an RFC 3339 timestamp in the given --time style.
+func formatTimestamp(iso, mode string) string {
+	switch mode {
+	case TimeISO:
+		return iso
+	case TimeAbsolute:
+		t, err := time.Parse(time.RFC3339, iso)
+		if err != nil {
+			return iso
+		}
+		return t.Local().Format("Mon 2006-01-02 15:04:05")
+	default:
+		return formatRelativeTime(iso)
+	}
+}
+
+// sessionColumn describes one selectable column of the session table.
+type sessionColumn struct {
+	header string
+	// timeHeader replaces header when timestamps are not shown relative.
+	timeHeader string
+	value      func(s protocol.SessionInfo, opts TableOptions) string
+	// flex columns absorb terminal width limits by truncation; at most one
+	// flex column is shrunk, the last one selected.
+	flex bool
+}
+
+var sessionColumns = map[string]sessionColumn{
+	"id": {header: "ID", value: func(s protocol.SessionInfo, opts TableOptions) string {
+		if opts.QualifyIDs && opts.Node != "" {
+			return fmt.Sprintf("%s:%d", opts.Node, s.ID)
+		}
+		return fmt.Sprintf("%d", s.ID)
+	}},
+	"name": {header: "NAME", value: func(s protocol.SessionInfo, _ TableOptions) string {
+		return orDash(s.Name)
+	}},
+	"cmd": {header: "COMMAND", flex: true, value: func(s protocol.SessionInfo, _ TableOptions) string {
+		return s.Prompt
+	}},
+	"status": {header: "STATUS", value: func(s protocol.SessionInfo, _ TableOptions) string {
+		return s.Status
+	}},
+	// How many clients are attached, so a busy session stands out.
+	"attached": {header: "ATTACHED", value: func(s protocol.SessionInfo, _ TableOptions) string {
+		if s.AttachedCount == 0 {
+			return "-"
+		}
+		return fmt.Sprintf("%d", s.AttachedCount)
+	}},
+	"age": {header: "AGE", timeHeader: "CREATED", value: func(s protocol.SessionInfo, opts TableOptions) string {
+		return formatTimestamp(s.CreatedAt, opts.Time)
+	}},
+	"finished": {header: "FINISHED", value: func(s protocol.SessionInfo, opts TableOptions) string {
+		if s.CompletedAt == nil {
+			return "-"
+		}
+		return formatTimestamp(*s.CompletedAt, opts.Time)
+	}},
+	"duration": {header: "DURATION", value: func(s protocol.SessionInfo, _ TableOptions) string {
+		return sessionRuntime(s)
+	}},
+	"branch": {header: "BRANCH", value: func(s protocol.SessionInfo, _ TableOptions) string {
+		if s.GitRoot == "" {
+			return "-"
+		}
+		return s.GitBranch + "@" + shortCommit(s.GitCommit)
+	}},
+	"node": {header: "NODE", value: func(_ protocol.SessionInfo, opts TableOptions) string {
+		return orDash(opts.Node)
+	}},
+	"tags": {header: "TAGS", value: func(s protocol.SessionInfo, _ TableOptions) string {
+		return orDash(strings.Join(s.Tags, ","))
+	}},
+	"dir": {header: "DIR", flex: true, value: func(s protocol.SessionInfo, _ TableOptions) string {
+		return s.WorkingDir
+	}},
+	"exit": {header: "EXIT", value: func(s protocol.SessionInfo, _ TableOptions) string {
+		if s.ExitCode == nil {
+			return "-"
+		}
+		return fmt.Sprintf("%d", *s.ExitCode)
+	}},
+}
+
+// columnAliases maps alternative spellings accepted by --columns.
+var columnAliases = map[string]string{
+	"command": "cmd",
+	"prompt":  "cmd",
+	"state":   "status",
+	"created": "age",
+	"runtime": "duration",
+	"workdir": "dir",
+	"code":    "exit",
+	"ended":   "finished",
+	"clients": "attached",
+}
+
+// sessionSorts maps --sort keys to ascending less functions.
+var sessionSorts = map[string]func(a, b protocol.SessionInfo) bool{
+	"id":   func(a, b protocol.SessionInfo) bool { return a.ID < b.ID },
+	"name": func(a, b protocol.SessionInfo) bool { return a.Name < b.Name },
+	"status": func(a, b protocol.SessionInfo) bool {
+		return a.Status < b.Status
+	},
+	// Oldest first, so "-age" lists the most recent sessions first.
+	"age": func(a, b protocol.SessionInfo) bool { return a.CreatedAt < b.CreatedAt },
+	// Unfinished sessions sort after finished ones.
+	"finished": func(a, b protocol.SessionInfo) bool {
+		if a.CompletedAt == nil || b.CompletedAt == nil {
+			return a.CompletedAt != nil && b.CompletedAt == nil
+		}
+		return *a.CompletedAt < *b.CompletedAt
+	},
+	"duration": func(a, b protocol.SessionInfo) bool {
+		return sessionRuntimeMs(a) < sessionRuntimeMs(b)
+	},
+}
+
+// ColumnNames returns the names accepted by --columns, sorted.
+func ColumnNames() []string {
+	names := make([]string, 0, len(sessionColumns))
+	for name := range sessionColumns {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	return names
+}
+
+// SortKeys returns the keys accepted by --sort, sorted.
+func SortKeys() []string {
+	keys := make([]string, 0, len(sessionSorts))
+	for key := range sessionSorts {
+		keys = append(keys, key)
+	}
+	sort.Strings(keys)
+	return keys
+}
+
+// parseColumns resolves a --columns value into column names.
+func parseColumns(spec string) ([]string, error) {
+	if strings.TrimSpace(spec) == "" {
+		spec = DefaultColumns
+	}
+	var cols []string
+	for _, name := range strings.Split(spec, ",") {
+		name = strings.ToLower(strings.TrimSpace(name))
+		if name == "" {
+			continue
+		}
+		if alias, ok := columnAliases[name]; ok {
+			name = alias
+		}
+		if _, ok := sessionColumns[name]; !ok {
+			return nil, fmt.Errorf("unknown column %q (valid: %s)", name, strings.Join(ColumnNames(), ", "))
+		}
+		cols = append(cols, name)
+	}
+	if len(cols) == 0 {
+		return nil, fmt.Errorf("no columns selected")
+	}
+	return cols, nil
+}
+
+// sortSessions orders sessions in place by a --sort key.
+func sortSessions(sessions []protocol.SessionInfo, key string) error {
+	if key == "" {
+		return nil
+	}
+	desc := strings.HasPrefix(key, "-")
+	key = strings.ToLower(strings.TrimPrefix(key, "-"))
+	less, ok := sessionSorts[key]
+	if !ok {
+		return fmt.Errorf("unknown sort key %q (valid: %s)", key, strings.Join(SortKeys(), ", "))
+	}
+	sort.SliceStable(sessions, func(i, j int) bool {
+		if desc {
+			return less(sessions[j], sessions[i])
+		}
+		return less(sessions[i], sessions[j])
+	})
+	return nil
+}
+
+// ValidateTableOptions reports an invalid --columns or --sort value before
+// any request is made.
+func ValidateTableOptions(opts TableOptions) error {
+	if _, err := parseColumns(opts.Columns); err != nil {
+		return err
+	}
+	if err := ValidateTimeFormat(opts.Time); err != nil {
+		return err
+	}
+	return sortSessions(nil, opts.Sort)
+}
+
+// printSessionTable prints sessions with the default columns to stdout.
+func printSessionTable(sessions []protocol.SessionInfo) {
+	_ = printSessionTableWith(sessions, TableOptions{})
+}
+
+// printSessionTableWith prints sessions to stdout, coloring statuses and
+// fitting the table to the terminal when stdout is one.
+func printSessionTableWith(sessions []protocol.SessionInfo, opts TableOptions) error {
+	width := 0
+	color := false
+	if fd := os.Stdout.Fd(); isatty.IsTerminal(fd) || isatty.IsCygwinTerminal(fd) {
+		if w, _, err := term.GetSize(int(fd)); err == nil {
+			width = w
+		}
+		color = os.Getenv("NO_COLOR") == ""
+	}
+	return renderSessionTable(os.Stdout, sessions, opts, width, color)
+}
+
+// renderSessionTable writes an aligned session table to w. Column widths
+// follow the content; when maxWidth > 0 and a row would overflow it, the
+// last flex column (command or directory) is truncated to fit.
+func renderSessionTable(w io.Writer, sessions []protocol.SessionInfo, opts TableOptions, maxWidth int, color bool) error {
+	cols, err := parseColumns(opts.Columns)
+	if err != nil {
+		return err
+	}
+	sorted := append([]protocol.SessionInfo(nil), sessions...)
+	if err := sortSessions(sorted, opts.Sort); err != nil {
+		return err
+	}
+
+	const gap = 2
+	rows := make([][]string, len(sorted))
+	widths := make([]int, len(cols))
+	headers := make([]string, len(cols))
+	for i, name := range cols {
+		headers[i] = sessionColumns[name].header
+		if h := sessionColumns[name].timeHeader; h != "" && opts.Time != "" && opts.Time != TimeRelative {
+			headers[i] = h
+		}
+		widths[i] = textwidth.Width(headers[i])
+	}
+	for r, s := range sorted {
+		row := make([]string, len(cols))
+		for i, name := range cols {
+			row[i] = sessionColumns[name].value(s, opts)
+			if n := textwidth.Width(row[i]); n > widths[i] {
+				widths[i] = n
+			}
+		}
+		rows[r] = row
+	}
+
+	if maxWidth > 0 {
+		flex := -1
+		for i, name := range cols {
+			if sessionColumns[name].flex {
+				flex = i
+			}
+		}
+		total := gap * (len(cols) - 1)
+		for _, n := range widths {
+			total += n
+		}
+		if flex >= 0 && total > maxWidth {
+			const minFlex = 12
+			widths[flex] = max(widths[flex]-(total-maxWidth), minFlex)
+		}
+	}
+
+	writeRow := func(cells []string, style func(col, cell string) string) {
+		var b strings.Builder
+		for i, cell := range cells {
+			cell = textwidth.Truncate(cell, widths[i])
+			pad := widths[i] - textwidth.Width(cell)
+			if style != nil {
+				cell = style(cols[i], cell)
+			}
+			b.WriteString(cell)
+			if i < len(cells)-1 {
+				b.WriteString(strings.Repeat(" ", pad+gap))
+			}
+		}
+		fmt.Fprintln(w, b.String())
+	}
+
+	if color {
+		writeRow(headers, func(_, cell string) string { return "\033[1m" + cell + "\033[0m" })
+	} else {
+		writeRow(headers, nil)
+	}
+
+	for _, row := range rows {
+		if color {
+			writeRow(row, func(col, cell string) string {
+				if col == "status" {
+					return colorStatus(cell)
+				}
+				return cell
+			})
+		} else {
+			writeRow(row, nil)
+		}
+	}
+	return nil
+}
+
+// colorStatus wraps a session status in its ANSI color.
+func colorStatus(status string) string {
+	code := ""
+	switch {
+	case status == "running":
+		code = "32" // green
+	case strings.HasPrefix(status, "completed"):
+		if status == "completed (0)" {
+			code = "2" // dim: finished cleanly
+		} else {
+			code = "33" // yellow: non-zero exit
+		}
+	case status == "killed":
+		code = "31" // red
+	}
+	if code == "" {
+		return status
+	}
+	return "\033[" + code + "m" + status + "\033[0m"
+}
+
+// sessionRuntimeMs returns a session's runtime in milliseconds for sorting.
+func sessionRuntimeMs(s protocol.SessionInfo) int64 {
+	if s.DurationMs != nil {
+		return *s.DurationMs
+	}
+	t, err := time.Parse(time.RFC3339, s.CreatedAt)
+	if err != nil {
+		return 0
+	}
+	return time.Since(t).Milliseconds()
+}
+
+func orDash(s string) string {
+	if s == "" {
+		return "-"
+	}
+	return s
+}
@@ -0,0 +1,112 @@
+package client
+
+import (
+	"bytes"
+	"strings"
+	"testing"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+func TestRenderSessionTableColumnsAndSort(t *testing.T) {
+	sessions := []protocol.SessionInfo{
+		{ID: 1, Name: "planner", Prompt: "claude -p plan", Status: "running", CreatedAt: "2026-01-01T00:00:00Z"},
+		{ID: 2, Prompt: "make test", Status: "completed (0)", CreatedAt: "2026-01-02T00:00:00Z"},
+	}
+
+	var buf bytes.Buffer
+	opts := TableOptions{Columns: "id,name,node,command", Sort: "-id", Node: "dev-1"}
+	if err := renderSessionTable(&buf, sessions, opts, 0, false); err != nil {
+		t.Fatal(err)
+	}
+	lines := strings.Split(strings.TrimRight(buf.String(), "\n"), "\n")
+	want := []string{
+		"ID  NAME     NODE   COMMAND",
+		"2   -        dev-1  make test",
+		"1   planner  dev-1  claude -p plan",
+	}
+	if strings.Join(lines, "\n") != strings.Join(want, "\n") {
+		t.Fatalf("got:\n%s\nwant:\n%s", buf.String(), strings.Join(want, "\n"))
+	}
+}
+
+func TestRenderSessionTableFitsWidth(t *testing.T) {
+	sessions := []protocol.SessionInfo{
+		{ID: 1, Prompt: strings.Repeat("x", 80), Status: "running"},
+	}
+	var buf bytes.Buffer
+	if err := renderSessionTable(&buf, sessions, TableOptions{Columns: "id,cmd,status"}, 40, false); err != nil {
+		t.Fatal(err)
+	}
+	for _, line := range strings.Split(strings.TrimRight(buf.String(), "\n"), "\n") {
+		if len(line) > 40 {
+			t.Errorf("line exceeds width: %q (%d)", line, len(line))
+		}
+	}
+	if !strings.Contains(buf.String(), "...") {
+		t.Errorf("expected truncated command, got:\n%s", buf.String())
+	}
+}
+
+func TestParseColumnsRejectsUnknown(t *testing.T) {
+	if _, err := parseColumns("id,bogus"); err == nil {
+		t.Fatal("expected error for unknown column")
+	}
+	if err := ValidateTableOptions(TableOptions{Sort: "-nope"}); err == nil {
+		t.Fatal("expected error for unknown sort key")
+	}
+}
+
+func TestRenderSessionTableTimeStyles(t *testing.T) {
+	completed := "2026-01-01T00:05:00Z"
+	sessions := []protocol.SessionInfo{
+		{ID: 1, Status: "completed (0)", CreatedAt: "2026-01-01T00:00:00Z", CompletedAt: &completed},
+	}
+	var buf bytes.Buffer
+	opts := TableOptions{Columns: "id,age,finished", Time: TimeISO}
+	if err := renderSessionTable(&buf, sessions, opts, 0, false); err != nil {
+		t.Fatal(err)
+	}
+	out := buf.String()
+	for _, want := range []string{"CREATED", "FINISHED", "2026-01-01T00:00:00Z", completed} {
+		if !strings.Contains(out, want) {
+			t.Errorf("missing %q in:\n%s", want, out)
+		}
+	}
+	if err := ValidateTimeFormat("weekday"); err == nil {
+		t.Error("expected error for unknown time format")
+	}
+}
+
+func TestSplitSessionRef(t *testing.T) {
+	tests := []struct {
+		arg  string
+		node string
+		id   uint32
+		ok   bool
+	}{
+		{"gpu-box:3", "gpu-box", 3, true},
+		{"10.0.0.5:9100:12", "10.0.0.5:9100", 12, true},
+		{"3", "", 0, false},
+		{"team:backend", "", 0, false},
+		{":3", "", 0, false},
+	}
+	for _, tt := range tests {
+		node, id, ok := SplitSessionRef(tt.arg)
+		if node != tt.node || id != tt.id || ok != tt.ok {
+			t.Errorf("SplitSessionRef(%q) = %q, %d, %v; want %q, %d, %v", tt.arg, node, id, ok, tt.node, tt.id, tt.ok)
+		}
+	}
+}
+
+func TestRenderSessionTableQualifiesRemoteIDs(t *testing.T) {
+	sessions := []protocol.SessionInfo{{ID: 7, Status: "running"}}
+	var buf bytes.Buffer
+	opts := TableOptions{Columns: "id,status", Node: "gpu-box", QualifyIDs: true}
+	if err := renderSessionTable(&buf, sessions, opts, 0, false); err != nil {
+		t.Fatal(err)
+	}
+	if !strings.Contains(buf.String(), "gpu-box:7") {
+		t.Errorf("expected node:id in output, got:\n%s", buf.String())
+	}
+}
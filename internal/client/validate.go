@@ -0,0 +1,78 @@
+package client
+
+import (
+	"fmt"
+	"strings"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// ValidateRun asks the node what Run would do with the same arguments —
+// resolving the command against the node's PATH, checking the working
+// directory, name, limits and guardrails — without launching anything, and
+// prints the plan. It fails when the node would refuse the launch.
+func ValidateRun(target *Target, command []string, workingDir string, name string, env []string, stdinData []byte, opts RunOptions, tags ...string) error {
+	return validateLaunch(target, runRequest(command, workingDir, name, env, stdinData, opts, tags))
+}
+
+// validateLaunch sends req as a ValidateLaunch and prints one plan per
+// launch.
+func validateLaunch(target *Target, req *protocol.Request) error {
+	req.Type = "ValidateLaunch"
+	resp, err := requestResponse(target, req)
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Type != "LaunchValidated" {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+
+	refused := 0
+	for i, p := range resp.Plans {
+		label := p.Name
+		if label == "" {
+			label = p.Command
+		}
+		if len(resp.Plans) > 1 {
+			label = fmt.Sprintf("%d. %s", i+1, label)
+		}
+		if !p.OK {
+			refused++
+			fmt.Printf("✗ %s: would be refused\n", label)
+			for _, problem := range p.Problems {
+				fmt.Printf("    %s\n", strings.ReplaceAll(problem, "\n", "\n    "))
+			}
+			continue
+		}
+		fmt.Printf("✓ %s\n", label)
+		fmt.Printf("    exec:    %s\n", strings.Join(append([]string{p.Path}, p.Argv[1:]...), " "))
+		switch {
+		case p.Workspace:
+			fmt.Printf("    dir:     new workspace\n")
+		default:
+			fmt.Printf("    dir:     %s\n", p.WorkingDir)
+		}
+		details := []string{"TERM=" + p.Term, "encoding " + p.Encoding, "history " + p.History}
+		if p.Priority != "" && p.Priority != "normal" {
+			details = append(details, p.Priority+" priority")
+		}
+		if p.MaxRuntime != "" {
+			details = append(details, "max runtime "+p.MaxRuntime)
+		}
+		if p.IdleTimeout != "" {
+			details = append(details, "idle timeout "+p.IdleTimeout)
+		}
+		fmt.Printf("    session: %s\n", strings.Join(details, ", "))
+		if p.Queued && p.Guardrail != nil {
+			fmt.Printf("    queued:  guardrail %s breached (%s); would wait for it to clear\n", p.Guardrail.Guardrail, p.Guardrail.Detail)
+		}
+	}
+	fmt.Println("Dry run: nothing was launched.")
+	if refused > 0 {
+		return fmt.Errorf("%d of %d launches would be refused", refused, len(resp.Plans))
+	}
+	return nil
+}
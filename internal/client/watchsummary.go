@@ -0,0 +1,250 @@
+package client
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"math"
+	"os"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/ansi"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/textwidth"
+)
+
+// maxSummaryLine is how much of a session's last line a summary shows.
+const maxSummaryLine = 80
+
+// outputSummary accumulates one session's output between summaries.
+type outputSummary struct {
+	label   string
+	status  string
+	lines   uint64 // complete lines since the watch started
+	window  uint64 // complete lines since the last summary
+	partial string // text after the last newline
+	last    string // last non-empty complete line
+	done    bool
+	strip   ansi.Stripper
+}
+
+// add counts the lines in a chunk of output and remembers the last
+// non-empty one. Escape sequences are ignored, and of a line redrawn with
+// carriage returns only the final text counts.
+func (s *outputSummary) add(output string) {
+	text := s.partial + string(s.strip.Strip([]byte(output)))
+	lines := strings.Split(text, "\n")
+	s.partial = lines[len(lines)-1]
+	if len(s.partial) > 4096 {
+		s.partial = textwidth.Tail(s.partial, 4096)
+	}
+	for _, l := range lines[:len(lines)-1] {
+		s.lines++
+		s.window++
+		if l = lastSegment(l); strings.TrimSpace(l) != "" {
+			s.last = l
+		}
+	}
+}
+
+// lastLine returns the line a summary shows: a partial line still being
+// written (such as a progress bar) wins over the last complete one.
+func (s *outputSummary) lastLine() string {
+	line := s.last
+	if p := lastSegment(s.partial); strings.TrimSpace(p) != "" {
+		line = p
+	}
+	return textwidth.Truncate(strings.TrimSpace(line), maxSummaryLine)
+}
+
+// lastSegment returns the text after the last carriage return in a line.
+func lastSegment(l string) string {
+	l = strings.TrimRight(l, "\r")
+	if i := strings.LastIndexByte(l, '\r'); i >= 0 {
+		l = l[i+1:]
+	}
+	return l
+}
+
+// summaryEvent is one WatchSession update for session idx.
+type summaryEvent struct {
+	idx    int
+	output string
+	status string
+	done   bool
+	err    error
+}
+
+// WatchSummary watches a session like WatchSession but, instead of its
+// output, writes a line to w every interval with the session's output rate
+// and last non-empty line, plus a line whenever its status changes.
+func WatchSummary(target *Target, id uint32, interval time.Duration, timeout *uint64, w io.Writer) error {
+	resp, err := requestResponse(target, &protocol.Request{Type: "GetStatus", ID: &id})
+	if err != nil {
+		return err
+	}
+	if resp.Type == "Error" {
+		return fmt.Errorf("%s", formatError(resp.Message))
+	}
+	if resp.Info == nil {
+		return fmt.Errorf("unexpected response type: %s", resp.Type)
+	}
+	return watchSummaries(target, []protocol.SessionInfo{*resp.Info}, interval, timeout, w)
+}
+
+// WatchSummaryByTag is WatchSummary for every session with a tag, one line
+// per session at each interval.
+func WatchSummaryByTag(target *Target, tag string, interval time.Duration, timeout *uint64, w io.Writer) error {
+	matched, err := sessionsWithTag(target, tag)
+	if err != nil {
+		return err
+	}
+	return watchSummaries(target, matched, interval, timeout, w)
+}
+
+func watchSummaries(target *Target, sessions []protocol.SessionInfo, interval time.Duration, timeout *uint64, w io.Writer) error {
+	summaries := make([]*outputSummary, len(sessions))
+	width := 0
+	events := make(chan summaryEvent, len(sessions)*64)
+	running := 0
+	for i, info := range sessions {
+		s := &outputSummary{label: sessionRef(target, info.ID), status: info.Status}
+		if info.Name != "" {
+			s.label = info.Name
+		}
+		width = max(width, len(s.label))
+		summaries[i] = s
+		if info.Status != "running" {
+			s.done = true
+			continue
+		}
+		running++
+		go watchSummaryEvents(target, i, info.ID, events)
+	}
+	for _, s := range summaries {
+		if s.done {
+			fmt.Fprintf(w, "%s %-*s  status: %s\n", time.Now().Format(time.TimeOnly), width, s.label, s.status)
+		}
+	}
+
+	var timeoutDuration time.Duration
+	if timeout != nil {
+		timeoutDuration = time.Duration(*timeout) * time.Second
+	} else {
+		timeoutDuration = time.Duration(math.MaxInt64)
+	}
+	timer := time.NewTimer(timeoutDuration)
+	defer timer.Stop()
+	ticker := time.NewTicker(interval)
+	defer ticker.Stop()
+	windowStart := time.Now()
+
+	printSummary := func(s *outputSummary, now time.Time) {
+		rate := float64(s.window) / max(now.Sub(windowStart).Seconds(), 0.001)
+		fmt.Fprintf(w, "%s %-*s  %7.1f lines/s  %d lines", now.Format(time.TimeOnly), width, s.label, rate, s.lines)
+		if last := s.lastLine(); last != "" {
+			fmt.Fprintf(w, "  last: %s", last)
+		}
+		fmt.Fprintln(w)
+	}
+
+	for running > 0 {
+		select {
+		case ev := <-events:
+			s := summaries[ev.idx]
+			if s.done {
+				continue
+			}
+			now := time.Now()
+			if ev.err != nil {
+				fmt.Fprintf(w, "%s %-*s  error: %v\n", now.Format(time.TimeOnly), width, s.label, ev.err)
+				s.done = true
+				running--
+				continue
+			}
+			if ev.output != "" {
+				s.add(ev.output)
+			}
+			if ev.status != "" && ev.status != s.status {
+				if ev.done && s.window > 0 {
+					printSummary(s, now)
+				}
+				fmt.Fprintf(w, "%s %-*s  status: %s -> %s\n", now.Format(time.TimeOnly), width, s.label, s.status, ev.status)
+				s.status = ev.status
+			}
+			if ev.done {
+				s.done = true
+				running--
+			}
+
+		case now := <-ticker.C:
+			for _, s := range summaries {
+				if !s.done {
+					printSummary(s, now)
+				}
+				s.window = 0
+			}
+			windowStart = now
+
+		case <-timer.C:
+			fmt.Fprintf(os.Stderr, "\n[cw] watch timeout reached\n")
+			return nil
+		}
+	}
+	return nil
+}
+
+// watchSummaryEvents streams one session's WatchSession updates, without
+// history, to events.
+func watchSummaryEvents(target *Target, idx int, id uint32, events chan<- summaryEvent) {
+	reader, writer, err := target.Connect()
+	if err != nil {
+		events <- summaryEvent{idx: idx, err: err}
+		return
+	}
+	defer reader.Close()
+	defer writer.Close()
+
+	includeHistory := false
+	if err := writer.SendRequest(&protocol.Request{Type: "WatchSession", ID: &id, IncludeHistory: &includeHistory}); err != nil {
+		events <- summaryEvent{idx: idx, err: err}
+		return
+	}
+
+	frameCh := make(chan frameEvent, 1)
+	done := make(chan struct{})
+	defer close(done)
+	go readFrames(reader, frameCh, done)
+	for fe := range frameCh {
+		if fe.err != nil {
+			events <- summaryEvent{idx: idx, err: fe.err}
+			return
+		}
+		if fe.frame == nil {
+			events <- summaryEvent{idx: idx, done: true}
+			return
+		}
+		if fe.frame.Type != protocol.FrameControl {
+			continue
+		}
+		var resp protocol.Response
+		if json.Unmarshal(fe.frame.Payload, &resp) != nil {
+			continue
+		}
+		switch resp.Type {
+		case "WatchUpdate":
+			ev := summaryEvent{idx: idx, status: resp.Status, done: resp.Done != nil && *resp.Done}
+			if resp.Output != nil {
+				ev.output = *resp.Output
+			}
+			events <- ev
+			if ev.done {
+				return
+			}
+		case "Error":
+			events <- summaryEvent{idx: idx, err: fmt.Errorf("%s", formatError(resp.Message))}
+			return
+		}
+	}
+}
@@ -0,0 +1,23 @@
+package client
+
+import "testing"
+
+func TestOutputSummaryAdd(t *testing.T) {
+	var s outputSummary
+	s.add("\x1b[32mbuilding\x1b[0m foo\r\n")
+	s.add("\n   \nstep 1/3")
+	if s.lines != 3 || s.window != 3 {
+		t.Fatalf("lines = %d, window = %d; want 3, 3", s.lines, s.window)
+	}
+	if s.last != "building foo" {
+		t.Fatalf("last = %q", s.last)
+	}
+	if got := s.lastLine(); got != "step 1/3" {
+		t.Fatalf("lastLine() = %q, want the partial line", got)
+	}
+
+	s.add("\rstep 3/3\n")
+	if s.lines != 4 || s.lastLine() != "step 3/3" {
+		t.Fatalf("lines = %d, lastLine() = %q", s.lines, s.lastLine())
+	}
+}
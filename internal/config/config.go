@@ -1,20 +1,146 @@
 package config
 
 import (
+	"bytes"
+	"crypto/ed25519"
+	"encoding/base64"
+	"errors"
 	"fmt"
 	"os"
 	"path/filepath"
+	"reflect"
 	"regexp"
+	"slices"
+	"strings"
 
 	"github.com/BurntSushi/toml"
 )
 
 // Config is the top-level configuration loaded from config.toml.
 type Config struct {
-	Node         NodeConfig `toml:"node"`
-	RelayURL     *string    `toml:"relay_url,omitempty"`
-	RelaySession *string    `toml:"relay_session,omitempty"` // OAuth session token
-	RelayToken   *string    `toml:"relay_token,omitempty"`   // node auth token for relay agent
+	Node         NodeConfig        `toml:"node"`
+	RelayURL     *string           `toml:"relay_url,omitempty"`
+	RelaySession *string           `toml:"relay_session,omitempty"` // OAuth session token
+	RelayToken   *string           `toml:"relay_token,omitempty"`   // node auth token for relay agent
+	Launch       LaunchConfig      `toml:"launch"`
+	Guardrails   GuardrailsConfig  `toml:"guardrails"`
+	LogShipping  LogShippingConfig `toml:"log_shipping"`
+	Shutdown     ShutdownConfig    `toml:"shutdown"`
+	Standby      StandbyConfig     `toml:"standby"`
+	Tokens       []TokenConfig     `toml:"tokens,omitempty"`
+}
+
+// TokenConfig is a scoped token ([[tokens]]): an extra auth token for the
+// WebSocket listener whose launches are limited to the commands it allows,
+// e.g. for a remote automation, so a leaked token cannot run anything else.
+// Scoped tokens may only make the requests the node allows them (launching,
+// listing, and using the sessions they launched), never e.g. upgrade the
+// node or take a backup.
+type TokenConfig struct {
+	Name  string `toml:"name"` // shown in errors and the node's log
+	Token string `toml:"token"`
+	// Command patterns (see session.CommandPolicy): "claude" allows that
+	// program with any arguments; "bash -c git *" a command line, with *
+	// matching anything but shell control characters. Deny wins over allow;
+	// an empty allow list allows everything not denied.
+	Allow []string `toml:"allow,omitempty"`
+	Deny  []string `toml:"deny,omitempty"`
+	// Env names the environment variables its launches may set besides
+	// TERM, LANG, LC_* and a few other harmless ones; a trailing * matches
+	// a prefix.
+	Env []string `toml:"env,omitempty"`
+}
+
+// StandbyConfig makes the node a standby of another node, its primary: the
+// primary's session metadata and output logs are copied here every
+// interval, so their history, transcripts and last known status can still
+// be read (cw list/status/logs --replica) if the primary's host is lost.
+// Off unless Primary is set.
+type StandbyConfig struct {
+	// Primary is the primary's WebSocket URL (its node.listen or
+	// external_url), e.g. "wss://gpu-box:9100/ws".
+	Primary string `toml:"primary,omitempty"`
+	Token   string `toml:"token,omitempty"` // the primary's auth token
+	// NodeKey pins the primary's identity key (base64 Ed25519, as cw server
+	// add records it in servers.toml); syncs fail if it cannot prove it.
+	NodeKey string `toml:"node_key,omitempty"`
+	// How often to sync (Go duration, default "10s").
+	Interval string `toml:"interval,omitempty"`
+}
+
+// ShutdownConfig controls how running sessions are wound down when the node
+// stops (SIGTERM or SIGINT; not during an upgrade, which drains them). With
+// none of it set, sessions are simply hung up when the node exits.
+type ShutdownConfig struct {
+	// Input written to every running session before any signal, e.g.
+	// "/exit\r" or "\u0003" (Ctrl+C).
+	WrapUp string `toml:"wrap_up,omitempty"`
+	// How long sessions get to exit after wrap_up (Go duration, default
+	// "10s") before their process groups are sent SIGTERM.
+	Grace string `toml:"grace,omitempty"`
+	// How long after SIGTERM before SIGKILL (Go duration, default "5s").
+	KillAfter string `toml:"kill_after,omitempty"`
+}
+
+// LogShippingConfig uploads finished sessions' logs to S3-compatible object
+// storage. Shipping is off unless Bucket is set.
+type LogShippingConfig struct {
+	// Endpoint is the storage URL, e.g. "https://s3.eu-west-1.amazonaws.com"
+	// or a MinIO/R2 endpoint. Default https://s3.amazonaws.com.
+	Endpoint string `toml:"endpoint,omitempty"`
+	Region   string `toml:"region,omitempty"` // default us-east-1
+	Bucket   string `toml:"bucket,omitempty"`
+	// Prefix is prepended to every key; logs land under
+	// prefix/<node>/<id>-<created>/.
+	Prefix string `toml:"prefix,omitempty"`
+	// Credentials; AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY are used
+	// when these are empty.
+	AccessKeyID     string `toml:"access_key_id,omitempty"`
+	SecretAccessKey string `toml:"secret_access_key,omitempty"`
+	// DeleteLocal removes the output and input logs from the node once
+	// they are uploaded.
+	DeleteLocal bool `toml:"delete_local,omitempty"`
+}
+
+// GuardrailsConfig limits what the node accepts, checked at every launch.
+// Zero values disable a check.
+type GuardrailsConfig struct {
+	// Most sessions that may run at once.
+	MaxSessions int `toml:"max_sessions,omitempty"`
+	// Least free space (MiB) on the filesystem holding the data directory.
+	MinFreeDiskMB uint64 `toml:"min_free_disk_mb,omitempty"`
+	// Highest 1-minute load average at which launches are accepted.
+	MaxLoadAverage float64 `toml:"max_load_average,omitempty"`
+	// "reject" (default) fails a launch on a breach; "queue" holds it until
+	// the node is back within limits, for at most queue_timeout (default 10m).
+	OnBreach     string `toml:"on_breach,omitempty"`
+	QueueTimeout string `toml:"queue_timeout,omitempty"`
+	// On a breach, kill the most recently launched running session of the
+	// lowest priority class below the launch's to make room, before
+	// rejecting or queueing it.
+	Preempt bool `toml:"preempt,omitempty"`
+}
+
+// LaunchConfig holds defaults applied to every session the node spawns.
+type LaunchConfig struct {
+	// Extra environment variables; per-launch env takes precedence.
+	Env map[string]string `toml:"env,omitempty"`
+	// Directories placed in front of PATH (e.g. ~/.local/bin for agent CLIs).
+	PathPrepend []string `toml:"path_prepend,omitempty"`
+	// Shell that wraps every command, e.g. "bash -lc".
+	Shell string `toml:"shell,omitempty"`
+	// How long --workspace directories are kept after their session ends
+	// (Go duration, e.g. "24h"). Empty keeps them until removed by hand.
+	WorkspaceRetention string `toml:"workspace_retention,omitempty"`
+	// Default session limits (Go durations): kill sessions that run longer
+	// than max_runtime, or see no input or output for idle_timeout. A
+	// session.expiring event is emitted expiry_warning (default 5m) before.
+	MaxRuntime    string `toml:"max_runtime,omitempty"`
+	IdleTimeout   string `toml:"idle_timeout,omitempty"`
+	ExpiryWarning string `toml:"expiry_warning,omitempty"`
+	// TERM for sessions launched without one (e.g. "xterm-256color").
+	// Empty inherits the node's own TERM.
+	Term string `toml:"term,omitempty"`
 }
 
 // NodeConfig describes the local node identity and network settings.
@@ -26,12 +152,59 @@ type NodeConfig struct {
 	// Externally-accessible WSS URL for fleet discovery
 	// (e.g. "wss://9100--workspace.coder.codewire.sh/ws").
 	ExternalURL *string `toml:"external_url,omitempty"`
+	// Cap on output sent to remote clients, in KiB/s per auth token.
+	// Zero means unlimited.
+	BandwidthLimitKiB int64 `toml:"bandwidth_limit_kib,omitempty"`
+	// Detach attached clients that send no input or resize for this long
+	// (Go duration, e.g. "2h"). Empty means never.
+	AttachIdleTimeout string `toml:"attach_idle_timeout,omitempty"`
+	// How long session metadata changes are batched before sessions.json is
+	// written (Go duration, default "500ms"). Launches and kills are always
+	// written straight away.
+	PersistDebounce string `toml:"persist_debounce,omitempty"`
+	// When writes of session metadata are synced to disk: "never",
+	// "critical" (default; launches and kills) or "always".
+	PersistFsync string `toml:"persist_fsync,omitempty"`
+	// How long session events are kept in events.db for QueryEvents (Go
+	// duration, default "720h"); "0" keeps them forever.
+	EventRetention string `toml:"event_retention,omitempty"`
+	// PEM certificate and key files for the WebSocket listener, which then
+	// serves wss://. Both or neither: without them it serves plain ws://,
+	// e.g. behind a TLS-terminating proxy or on a private network.
+	TLSCert string `toml:"tls_cert,omitempty"`
+	TLSKey  string `toml:"tls_key,omitempty"`
+	// Also accept the auth token as a ?token= query parameter on /ws, for
+	// old clients. Off by default: URLs end up in proxy and access logs.
+	AllowQueryToken bool `toml:"allow_query_token,omitempty"`
+	// File the WebSocket listener logs each request to, as JSON lines
+	// (default access.log in the data directory; "off" disables it).
+	AccessLog string `toml:"access_log,omitempty"`
+	// Fraction (0 to 1, default 1) of successful requests written to the
+	// access log. Failed requests are always logged.
+	AccessLogSample *float64 `toml:"access_log_sample,omitempty"`
 }
 
 // ServerEntry is a saved remote server (client-side).
 type ServerEntry struct {
 	URL   string `toml:"url"`
 	Token string `toml:"token"`
+	// NodeKey is the node's identity key (base64 Ed25519), pinned when the
+	// server was added. Connections fail if the node cannot prove it.
+	NodeKey string `toml:"node_key,omitempty"`
+}
+
+// PinnedKey decodes NodeKey; nil when none is pinned.
+func (e ServerEntry) PinnedKey() ed25519.PublicKey {
+	if e.NodeKey == "" {
+		return nil
+	}
+	pub, err := base64.StdEncoding.DecodeString(e.NodeKey)
+	if err != nil {
+		// Keep a malformed key pinned so connections fail instead of
+		// skipping the check.
+		return ed25519.PublicKey(e.NodeKey)
+	}
+	return pub
 }
 
 // ServersConfig is the client-side servers list (~/.codewire/servers.toml).
@@ -130,6 +303,149 @@ func LoadConfig(dataDir string) (*Config, error) {
 	return cfg, nil
 }
 
+// nodeEnvVars override the [node] section of config.toml (see LoadConfig).
+var nodeEnvVars = []string{"CODEWIRE_NODE_NAME", "CODEWIRE_LISTEN", "CODEWIRE_EXTERNAL_URL"}
+
+// NodeEnvOverrides returns the environment variables that are set and
+// override the [node] section of config.toml.
+func NodeEnvOverrides() []string {
+	var set []string
+	for _, name := range nodeEnvVars {
+		if os.Getenv(name) != "" {
+			set = append(set, name)
+		}
+	}
+	return set
+}
+
+// SaveNodeConfig sets the [node] section of config.toml in dataDir to node,
+// and creates the file and directory if needed. Only the keys whose value
+// changes are rewritten, so comments, the other sections and keys this
+// release does not know are kept as they are.
+func SaveNodeConfig(dataDir string, node NodeConfig) error {
+	if err := os.MkdirAll(dataDir, 0o755); err != nil {
+		return fmt.Errorf("creating data dir: %w", err)
+	}
+	path := filepath.Join(dataDir, "config.toml")
+	data, err := os.ReadFile(path)
+	if err != nil && !errors.Is(err, os.ErrNotExist) {
+		return err
+	}
+	var old Config
+	if _, err := toml.Decode(string(data), &old); err != nil {
+		return fmt.Errorf("parsing %s: %w", path, err)
+	}
+
+	lines := strings.Split(string(data), "\n")
+	oldV, newV := reflect.ValueOf(old.Node), reflect.ValueOf(node)
+	for i := range oldV.NumField() {
+		key, _, _ := strings.Cut(oldV.Type().Field(i).Tag.Get("toml"), ",")
+		if key == "" || key == "-" || reflect.DeepEqual(oldV.Field(i).Interface(), newV.Field(i).Interface()) {
+			continue
+		}
+		line, err := tomlKeyLine(key, newV.Field(i))
+		if err != nil {
+			return fmt.Errorf("encoding node.%s: %w", key, err)
+		}
+		lines = setNodeKey(lines, key, line)
+	}
+	out := strings.Join(lines, "\n")
+
+	// The edit must leave a file that reads back as node.
+	var check Config
+	if _, err := toml.Decode(out, &check); err != nil || !reflect.DeepEqual(check.Node, node) {
+		return fmt.Errorf("cannot update the [node] section of %s in place; edit it by hand", path)
+	}
+
+	tmp := path + ".tmp"
+	if err := os.WriteFile(tmp, []byte(out), 0o600); err != nil {
+		os.Remove(tmp)
+		return fmt.Errorf("writing %s: %w", path, err)
+	}
+	return os.Rename(tmp, path)
+}
+
+// tomlKeyLine encodes the line that sets key to v, or returns "" when v is
+// unset (nil or the zero value), so the key is removed.
+func tomlKeyLine(key string, v reflect.Value) (string, error) {
+	if v.IsZero() {
+		return "", nil
+	}
+	if v.Kind() == reflect.Pointer {
+		v = v.Elem()
+	}
+	var buf bytes.Buffer
+	if err := toml.NewEncoder(&buf).Encode(map[string]any{key: v.Interface()}); err != nil {
+		return "", err
+	}
+	return strings.TrimRight(buf.String(), "\n"), nil
+}
+
+// setNodeKey sets key in the [node] table of a config file's lines to line,
+// or removes it when line is empty. A key the table lacks is added after its
+// last key, and a missing table at the end of the file.
+func setNodeKey(lines []string, key, line string) []string {
+	start, end := -1, len(lines)
+	for i, l := range lines {
+		name, ok := tableHeader(l)
+		if !ok {
+			continue
+		}
+		if start >= 0 {
+			end = i
+			break
+		}
+		if name == "node" {
+			start = i
+		}
+	}
+	if start < 0 {
+		if line == "" {
+			return lines
+		}
+		for len(lines) > 0 && strings.TrimSpace(lines[len(lines)-1]) == "" {
+			lines = lines[:len(lines)-1]
+		}
+		if len(lines) > 0 {
+			lines = append(lines, "")
+		}
+		return append(lines, "[node]", line, "")
+	}
+
+	last := start
+	for i := start + 1; i < end; i++ {
+		l := strings.TrimSpace(lines[i])
+		k, _, ok := strings.Cut(l, "=")
+		if !ok || strings.HasPrefix(l, "#") {
+			continue
+		}
+		last = i
+		if strings.Trim(strings.TrimSpace(k), `"'`) != key {
+			continue
+		}
+		if line == "" {
+			return slices.Delete(lines, i, i+1)
+		}
+		lines[i] = line
+		return lines
+	}
+	if line == "" {
+		return lines
+	}
+	return slices.Insert(lines, last+1, line)
+}
+
+// tableHeader returns the name of the table a line opens, e.g. "node" for
+// "[node]" or "tokens" for "[[tokens]]".
+func tableHeader(line string) (string, bool) {
+	line, _, _ = strings.Cut(strings.TrimSpace(line), "#")
+	line = strings.TrimSpace(line)
+	if !strings.HasPrefix(line, "[") || !strings.HasSuffix(line, "]") {
+		return "", false
+	}
+	return strings.TrimSpace(strings.Trim(line, "[]")), true
+}
+
 // LoadServersConfig reads servers.toml from dataDir. If the file does not
 // exist an empty ServersConfig is returned.
 func LoadServersConfig(dataDir string) (*ServersConfig, error) {
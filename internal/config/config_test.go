@@ -0,0 +1,62 @@
+package config
+
+import (
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestSaveNodeConfigKeepsTheRestOfTheFile(t *testing.T) {
+	dir := t.TempDir()
+	path := filepath.Join(dir, "config.toml")
+	orig := `# my node
+[node]
+name = "old" # renamed below
+# listener for the lab network
+bandwidth_limit_kib = 512
+future_key = true
+
+[guardrails]
+max_sessions = 4 # keep small
+`
+	if err := os.WriteFile(path, []byte(orig), 0o600); err != nil {
+		t.Fatal(err)
+	}
+	listen := "0.0.0.0:9100"
+	if err := SaveNodeConfig(dir, NodeConfig{Name: "new", Listen: &listen}); err != nil {
+		t.Fatalf("SaveNodeConfig: %v", err)
+	}
+	data, err := os.ReadFile(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	got := string(data)
+	for _, want := range []string{"# my node", "# listener for the lab network", "future_key = true", "max_sessions = 4 # keep small", `name = "new"`, `listen = "0.0.0.0:9100"`} {
+		if !strings.Contains(got, want) {
+			t.Errorf("expected %q in:\n%s", want, got)
+		}
+	}
+	if strings.Contains(got, "bandwidth_limit_kib") {
+		t.Errorf("expected the unset key to be removed:\n%s", got)
+	}
+
+	cfg, err := LoadConfig(dir)
+	if err != nil {
+		t.Fatalf("LoadConfig: %v", err)
+	}
+	if cfg.Node.Name != "new" || cfg.Node.Listen == nil || *cfg.Node.Listen != listen || cfg.Guardrails.MaxSessions != 4 {
+		t.Fatalf("unexpected config after saving: %+v", cfg)
+	}
+}
+
+func TestSaveNodeConfigCreatesFile(t *testing.T) {
+	dir := filepath.Join(t.TempDir(), "data")
+	if err := SaveNodeConfig(dir, NodeConfig{Name: "fresh"}); err != nil {
+		t.Fatalf("SaveNodeConfig: %v", err)
+	}
+	cfg, err := LoadConfig(dir)
+	if err != nil || cfg.Node.Name != "fresh" {
+		t.Fatalf("expected node name fresh, got %+v, %v", cfg, err)
+	}
+}
@@ -0,0 +1,160 @@
+package mcp
+
+import (
+	"cmp"
+	"encoding/json"
+	"fmt"
+	"os"
+	"sync"
+	"time"
+)
+
+// Defaults for Options.ToolTimeout and Options.MaxConcurrentTools.
+const (
+	defaultToolTimeout        = 60 * time.Second
+	defaultMaxConcurrentTools = 4
+)
+
+// blockingToolWaits are how long the tools that wait by design wait when
+// the call sets none of timeout_seconds, max_duration_seconds or
+// wait_seconds.
+var blockingToolWaits = map[string]time.Duration{
+	"codewire_wait_for":      300 * time.Second,
+	"codewire_request":       60 * time.Second,
+	"codewire_watch_session": 30 * time.Second,
+	"codewire_subscribe":     30 * time.Second,
+}
+
+// toolTimeout bounds one tool call: base, plus however long the call asks
+// to wait for the tools that block by design.
+func toolTimeout(name string, args map[string]interface{}, base time.Duration) time.Duration {
+	for _, key := range []string{"timeout_seconds", "max_duration_seconds", "wait_seconds"} {
+		if v, ok := args[key].(float64); ok && v > 0 {
+			return base + time.Duration(v*float64(time.Second))
+		}
+	}
+	return base + blockingToolWaits[name]
+}
+
+// stdoutMu serializes the messages written to the host, which now come from
+// tool calls running concurrently with the stdin loop.
+var stdoutMu sync.Mutex
+
+func writeMessage(v interface{}) {
+	out, _ := json.Marshal(v)
+	stdoutMu.Lock()
+	defer stdoutMu.Unlock()
+	fmt.Fprintf(os.Stdout, "%s\n", out)
+}
+
+// toolRunner runs tool calls off the stdin loop, so a hung node connection
+// or a long watch cannot stall the session. At most cap(slots) calls run at
+// once, and every call is answered by its timeout. The tool is given the
+// same deadline for its node requests, so it stops soon after; it keeps its
+// slot until it returns.
+type toolRunner struct {
+	dataDir string
+	roots   *rootSet
+	timeout time.Duration
+	slots   chan struct{}
+	calls   sync.WaitGroup // calls not yet answered
+
+	mu         sync.Mutex
+	clientName string
+}
+
+// callContext is what a tool call knows about the MCP session it runs in.
+type callContext struct {
+	roots      *rootSet
+	clientName string    // clientInfo.name from initialize
+	deadline   time.Time // when the call is answered with a timeout
+}
+
+// launchTags are added to every session launched through MCP, so agent
+// launches can be told apart from human ones (cw list --tag launched-by=mcp).
+func (cc *callContext) launchTags() []string {
+	tags := []string{"launched-by=mcp"}
+	if cc.clientName != "" {
+		tags = append(tags, "mcp-client="+cc.clientName)
+	}
+	return tags
+}
+
+func (r *toolRunner) setClientName(name string) {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	r.clientName = name
+}
+
+func newToolRunner(dataDir string, roots *rootSet, opts Options) *toolRunner {
+	return &toolRunner{
+		dataDir: dataDir,
+		roots:   roots,
+		timeout: cmp.Or(opts.ToolTimeout, defaultToolTimeout),
+		slots:   make(chan struct{}, cmp.Or(opts.MaxConcurrentTools, defaultMaxConcurrentTools)),
+	}
+}
+
+// call starts a tools/call request and answers it when it finishes or times
+// out.
+func (r *toolRunner) call(id *json.RawMessage, params json.RawMessage) {
+	var p struct {
+		Name      string                 `json:"name"`
+		Arguments map[string]interface{} `json:"arguments"`
+	}
+	_ = json.Unmarshal(params, &p)
+	timeout := toolTimeout(p.Name, p.Arguments, r.timeout)
+	r.mu.Lock()
+	cc := &callContext{roots: r.roots, clientName: r.clientName, deadline: time.Now().Add(timeout)}
+	r.mu.Unlock()
+
+	r.calls.Add(1)
+	go func() {
+		defer r.calls.Done()
+		timer := time.NewTimer(timeout)
+		defer timer.Stop()
+		select {
+		case r.slots <- struct{}{}:
+		case <-timer.C:
+			r.reply(id, "", fmt.Errorf("%s not started within %s: %d tool calls are still running", p.Name, timeout, cap(r.slots)))
+			return
+		}
+
+		type result struct {
+			text string
+			err  error
+		}
+		done := make(chan result, 1)
+		go func() {
+			defer func() { <-r.slots }()
+			text, err := handleToolCall(r.dataDir, cc, params)
+			done <- result{text, err}
+		}()
+		select {
+		case res := <-done:
+			r.reply(id, res.text, res.err)
+		case <-timer.C:
+			r.reply(id, "", fmt.Errorf("%s timed out after %s", p.Name, timeout))
+		}
+	}()
+}
+
+// wait blocks until every call started so far has been answered, so no
+// answer is lost when the host closes stdin right after its last call.
+func (r *toolRunner) wait() {
+	r.calls.Wait()
+}
+
+func (r *toolRunner) reply(id *json.RawMessage, text string, err error) {
+	resp := jsonRpcResponse{Jsonrpc: "2.0", ID: id}
+	if err != nil {
+		resp.Error = &jsonRpcError{Code: -32603, Message: err.Error()}
+	} else {
+		resp.Result = map[string]interface{}{
+			"content": []map[string]interface{}{
+				{"type": "text", "text": text},
+			},
+		}
+	}
+	writeMessage(resp)
+}
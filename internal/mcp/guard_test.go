@@ -0,0 +1,69 @@
+package mcp
+
+import (
+	"net"
+	"path/filepath"
+	"testing"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+func TestToolTimeout(t *testing.T) {
+	base := 10 * time.Second
+	for _, tc := range []struct {
+		name string
+		args map[string]interface{}
+		want time.Duration
+	}{
+		{"codewire_list_sessions", nil, base},
+		{"codewire_wait_for", nil, base + 300*time.Second},
+		{"codewire_wait_for", map[string]interface{}{"timeout_seconds": float64(5)}, base + 5*time.Second},
+		{"codewire_watch_session", map[string]interface{}{"max_duration_seconds": float64(120)}, base + 120*time.Second},
+	} {
+		if got := toolTimeout(tc.name, tc.args, base); got != tc.want {
+			t.Errorf("toolTimeout(%s, %v) = %s, want %s", tc.name, tc.args, got, tc.want)
+		}
+	}
+}
+
+func TestLaunchTags(t *testing.T) {
+	got := (&callContext{clientName: "claude-code"}).launchTags()
+	if len(got) != 2 || got[0] != "launched-by=mcp" || got[1] != "mcp-client=claude-code" {
+		t.Fatalf("unexpected tags %v", got)
+	}
+	if got := (&callContext{}).launchTags(); len(got) != 1 {
+		t.Fatalf("expected only launched-by without a client name, got %v", got)
+	}
+}
+
+func TestNodeRequestStopsAtDeadline(t *testing.T) {
+	dir := t.TempDir()
+	ln, err := net.Listen("unix", filepath.Join(dir, "codewire.sock"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ln.Close()
+	// A node that accepts the request and never answers.
+	go func() {
+		for {
+			conn, err := ln.Accept()
+			if err != nil {
+				return
+			}
+			defer conn.Close()
+		}
+	}()
+
+	req := &protocol.Request{Type: "ListSessions"}
+	start := time.Now()
+	if _, err := nodeRequest(dir, start.Add(100*time.Millisecond), req); err == nil {
+		t.Fatal("expected the request to fail at the deadline")
+	}
+	if d := time.Since(start); d > 5*time.Second {
+		t.Fatalf("request ignored the deadline, took %s", d)
+	}
+	if req.TimeoutMs == nil || *req.TimeoutMs > 100 {
+		t.Fatalf("expected the node to be sent the time left, got %v", req.TimeoutMs)
+	}
+}
@@ -0,0 +1,104 @@
+package mcp
+
+import (
+	"encoding/json"
+	"fmt"
+	"regexp"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/ansi"
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/textwidth"
+)
+
+// maxLaunchWait caps wait_seconds on codewire_launch_session.
+const maxLaunchWait = 60 * time.Second
+
+// maxInitialOutput caps the output returned with a launch result.
+const maxInitialOutput = 4096
+
+// defaultReadyPattern matches a last line that looks like a prompt waiting
+// for input: a shell ($ # %), a REPL or agent CLI (> ❯ ›), or a question.
+var defaultReadyPattern = regexp.MustCompile(`(?:[$#%>❯›?:]|\]\s*>)\s*$`)
+
+// launchResult is what codewire_launch_session returns when asked to wait.
+type launchResult struct {
+	SessionID uint32 `json:"session_id"`
+	Status    string `json:"status"` // "running", or the final status if it already ended
+	Ready     bool   `json:"ready"`  // the output ended with a prompt
+	Output    string `json:"output"` // first output, ANSI stripped
+	WaitedMS  int64  `json:"waited_ms"`
+}
+
+// awaitInitialOutput watches a just-launched session for up to wait and
+// returns its first output. It stops early once the last line of output
+// matches ready or the session ends, or the tool call's deadline passes.
+func awaitInitialOutput(dataDir string, callDeadline time.Time, id uint32, wait time.Duration, ready *regexp.Regexp) (*launchResult, error) {
+	includeHistory := true
+	req := &protocol.Request{Type: "WatchSession", ID: &id, IncludeHistory: &includeHistory}
+	conn, err := dialNode(dataDir, callDeadline, req)
+	if err != nil {
+		return nil, err
+	}
+	defer conn.Close()
+	reader := connection.NewUnixReader(conn)
+	writer := connection.NewUnixWriter(conn)
+
+	if err := writer.SendRequest(req); err != nil {
+		return nil, err
+	}
+	start := time.Now()
+	readDeadline := start.Add(wait)
+	if !callDeadline.IsZero() && callDeadline.Before(readDeadline) {
+		readDeadline = callDeadline
+	}
+	_ = conn.SetReadDeadline(readDeadline)
+
+	res := &launchResult{SessionID: id, Status: "running"}
+	var raw strings.Builder
+	for !res.Ready {
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			break // wait elapsed or the node closed the stream
+		}
+		if f.Type != protocol.FrameControl {
+			continue
+		}
+		var resp protocol.Response
+		if json.Unmarshal(f.Payload, &resp) != nil {
+			continue
+		}
+		if resp.Type == "Error" {
+			return nil, fmt.Errorf("watch error: %s", resp.Message)
+		}
+		if resp.Type != "WatchUpdate" {
+			continue
+		}
+		if resp.Output != nil && raw.Len() < 4*maxInitialOutput {
+			raw.WriteString(*resp.Output)
+			res.Ready = ready.MatchString(lastLine(ansi.Strip(raw.String())))
+		}
+		if resp.Done != nil && *resp.Done {
+			res.Status = resp.Status
+			break
+		}
+	}
+
+	res.Output = ansi.Strip(raw.String())
+	if textwidth.Width(res.Output) > maxInitialOutput {
+		res.Output = textwidth.Truncate(res.Output, maxInitialOutput) + "\n[truncated]"
+	}
+	res.WaitedMS = time.Since(start).Milliseconds()
+	return res, nil
+}
+
+// lastLine returns the last non-blank line of s.
+func lastLine(s string) string {
+	s = strings.TrimRight(s, " \t\r\n")
+	if i := strings.LastIndexAny(s, "\r\n"); i >= 0 {
+		s = s[i+1:]
+	}
+	return s
+}
@@ -0,0 +1,29 @@
+package mcp
+
+import (
+	"testing"
+
+	"github.com/codewiresh/codewire/internal/ansi"
+)
+
+func TestDefaultReadyPattern(t *testing.T) {
+	for _, tc := range []struct {
+		output string
+		ready  bool
+	}{
+		{"Welcome\nuser@host:~/src$ ", true},
+		{"root@box:/# ", true},
+		{"Python 3.12\n>>> ", true},
+		{"\x1b[1m❯\x1b[0m \r\n", true},
+		{"Do you trust this folder? ", true},
+		{"Password: ", true},
+		{"Installing dependencies...\n", false},
+		{"compiling 12/40\r", false},
+		{"", false},
+	} {
+		got := defaultReadyPattern.MatchString(lastLine(ansi.Strip(tc.output)))
+		if got != tc.ready {
+			t.Errorf("ready(%q) = %v, want %v", tc.output, got, tc.ready)
+		}
+	}
+}
@@ -0,0 +1,120 @@
+package mcp
+
+import (
+	"encoding/json"
+	"fmt"
+	"net/url"
+	"os"
+	"path/filepath"
+	"strings"
+	"sync"
+)
+
+// rootsRequestID identifies the server's roots/list requests, so their
+// responses can be told apart from the host's requests on stdin.
+const rootsRequestID = `"codewire-roots"`
+
+// rootSet holds the directories the MCP host declared as roots. Launches
+// default to the first one, and with strict set they are refused outside
+// all of them.
+type rootSet struct {
+	supported bool // the host advertised the roots capability
+	strict    bool
+
+	mu      sync.Mutex
+	dirs    []string
+	pending bool // a roots/list request is unanswered
+}
+
+func (r *rootSet) setDirs(dirs []string) {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	r.dirs = dirs
+	r.pending = false
+}
+
+// requested records that the roots were asked for, and are not known until
+// the host answers.
+func (r *rootSet) requested() {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	r.pending = true
+}
+
+// known reports whether tool calls may run: in strict mode, not while the
+// host's roots are asked for, so no call is checked against stale or
+// missing roots.
+func (r *rootSet) known() bool {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	return !r.strict || !r.pending
+}
+
+// parseRoots returns the directories of a roots/list result. Roots that are
+// not file:// URIs are ignored.
+func parseRoots(result json.RawMessage) []string {
+	var r struct {
+		Roots []struct {
+			URI string `json:"uri"`
+		} `json:"roots"`
+	}
+	if json.Unmarshal(result, &r) != nil {
+		return nil
+	}
+	var dirs []string
+	for _, root := range r.Roots {
+		u, err := url.Parse(root.URI)
+		if err != nil || u.Scheme != "file" || u.Path == "" {
+			continue
+		}
+		dirs = append(dirs, filepath.Clean(u.Path))
+	}
+	return dirs
+}
+
+// workingDir picks the directory a launch runs in. An explicit requested
+// directory wins; a relative one is taken against the first root. Without
+// one, the first root is used, falling back to the server's own directory.
+func (r *rootSet) workingDir(requested string) (string, error) {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	dir := requested
+	switch {
+	case dir != "" && !filepath.IsAbs(dir) && len(r.dirs) > 0:
+		dir = filepath.Join(r.dirs[0], dir)
+	case dir == "" && len(r.dirs) > 0:
+		dir = r.dirs[0]
+	case dir == "":
+		wd, err := os.Getwd()
+		if err != nil {
+			wd = "."
+		}
+		dir = wd
+	}
+	if !r.strict {
+		return dir, nil
+	}
+
+	abs, err := filepath.Abs(dir)
+	if err != nil {
+		return "", err
+	}
+	if len(r.dirs) == 0 {
+		return "", fmt.Errorf("launch refused: strict roots mode is on and the MCP host declared no roots")
+	}
+	// Compare real paths, so a symlink inside a root cannot lead out of it.
+	real, err := filepath.EvalSymlinks(abs)
+	if err != nil {
+		return "", fmt.Errorf("launch refused: %w", err)
+	}
+	for _, root := range r.dirs {
+		realRoot, err := filepath.EvalSymlinks(root)
+		if err != nil {
+			continue
+		}
+		if rel, err := filepath.Rel(realRoot, real); err == nil && rel != ".." && !strings.HasPrefix(rel, "../") {
+			return abs, nil
+		}
+	}
+	return "", fmt.Errorf("launch refused: %s is outside the MCP roots (%s)", abs, strings.Join(r.dirs, ", "))
+}
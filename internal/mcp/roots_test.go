@@ -0,0 +1,79 @@
+package mcp
+
+import (
+	"encoding/json"
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+func TestRootsWorkingDir(t *testing.T) {
+	dirs := parseRoots(json.RawMessage(`{"roots":[{"uri":"file:///work/app","name":"app"},{"uri":"https://example.com"}]}`))
+	if len(dirs) != 1 || dirs[0] != "/work/app" {
+		t.Fatalf("expected [/work/app], got %v", dirs)
+	}
+
+	r := &rootSet{dirs: dirs}
+	for requested, want := range map[string]string{
+		"":           "/work/app",
+		"sub":        "/work/app/sub",
+		"/elsewhere": "/elsewhere",
+	} {
+		if got, err := r.workingDir(requested); err != nil || got != want {
+			t.Errorf("workingDir(%q) = %q, %v; want %q", requested, got, err, want)
+		}
+	}
+
+	if _, err := (&rootSet{strict: true}).workingDir("/tmp"); err == nil {
+		t.Error("expected strict mode without roots to refuse launches")
+	}
+}
+
+func TestStrictRootsResolveSymlinks(t *testing.T) {
+	base := t.TempDir()
+	root := filepath.Join(base, "app")
+	for _, dir := range []string{filepath.Join(root, "sub"), filepath.Join(base, "application"), filepath.Join(base, "elsewhere")} {
+		if err := os.MkdirAll(dir, 0o755); err != nil {
+			t.Fatal(err)
+		}
+	}
+	// A link inside the root that leads out of it, and a link to the root.
+	if err := os.Symlink(filepath.Join(base, "elsewhere"), filepath.Join(root, "escape")); err != nil {
+		t.Fatal(err)
+	}
+	if err := os.Symlink(root, filepath.Join(base, "link")); err != nil {
+		t.Fatal(err)
+	}
+
+	r := &rootSet{strict: true, dirs: []string{filepath.Join(base, "link")}}
+	if _, err := r.workingDir(filepath.Join(root, "sub")); err != nil {
+		t.Errorf("expected a directory inside the root to be allowed: %v", err)
+	}
+	for _, dir := range []string{
+		filepath.Join(base, "elsewhere"),
+		filepath.Join(base, "application"),
+		filepath.Join(root, "escape"),
+		filepath.Join(root, "missing"),
+	} {
+		if _, err := r.workingDir(dir); err == nil {
+			t.Errorf("expected %q to be refused in strict mode", dir)
+		}
+	}
+}
+
+func TestStrictRootsKnown(t *testing.T) {
+	r := &rootSet{strict: true}
+	r.requested()
+	if r.known() {
+		t.Fatal("tool calls should wait for the host's roots in strict mode")
+	}
+	r.setDirs([]string{"/work"})
+	if !r.known() {
+		t.Fatal("roots should be known once the host answers")
+	}
+	lax := &rootSet{}
+	lax.requested()
+	if !lax.known() {
+		t.Fatal("tool calls should not wait for roots outside strict mode")
+	}
+}
@@ -4,16 +4,23 @@ import (
 	"bufio"
 	"encoding/json"
 	"fmt"
+	"maps"
 	"net"
 	"net/http"
 	"os"
 	"path/filepath"
+	"regexp"
+	"slices"
 	"strings"
+	"sync"
 	"time"
 
+	"github.com/codewiresh/codewire/internal/client"
 	"github.com/codewiresh/codewire/internal/config"
 	"github.com/codewiresh/codewire/internal/connection"
 	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/textwidth"
+	"github.com/codewiresh/codewire/internal/tmpl"
 )
 
 // ---------------------------------------------------------------------------
@@ -25,6 +32,9 @@ type jsonRpcRequest struct {
 	ID      *json.RawMessage `json:"id,omitempty"`
 	Method  string           `json:"method"`
 	Params  json.RawMessage  `json:"params,omitempty"`
+	// Result is set, and Method empty, on the host's response to a request
+	// the server sent (roots/list).
+	Result json.RawMessage `json:"result,omitempty"`
 }
 
 type jsonRpcResponse struct {
@@ -50,14 +60,33 @@ type tool struct {
 // MCP Server
 // ---------------------------------------------------------------------------
 
+// Options configures the MCP server.
+type Options struct {
+	// StrictRoots refuses launches outside the roots declared by the MCP
+	// host (see rootSet).
+	StrictRoots bool
+	// ToolTimeout bounds each tool call, on top of the wait the blocking
+	// tools are asked for (default 60s).
+	ToolTimeout time.Duration
+	// MaxConcurrentTools caps the tool calls running at once (default 4).
+	MaxConcurrentTools int
+}
+
 // RunMCPServer reads JSON-RPC requests from stdin, dispatches them, and writes
 // responses to stdout. It communicates with the codewire node over a Unix
 // socket at dataDir/codewire.sock.
-func RunMCPServer(dataDir string) error {
+func RunMCPServer(dataDir string, opts Options) error {
 	scanner := bufio.NewScanner(os.Stdin)
 	scanner.Buffer(make([]byte, 1024*1024), 1024*1024) // 1 MB buffer
 
 	version := "0.1.0"
+	roots := &rootSet{strict: opts.StrictRoots}
+	requestRoots := func() {
+		roots.requested()
+		id := json.RawMessage(rootsRequestID)
+		writeMessage(jsonRpcRequest{Jsonrpc: "2.0", ID: &id, Method: "roots/list"})
+	}
+	tools := newToolRunner(dataDir, roots, opts)
 
 	for scanner.Scan() {
 		line := scanner.Text()
@@ -71,12 +100,40 @@ func RunMCPServer(dataDir string) error {
 			continue
 		}
 
+		// The host's answer to roots/list.
+		if req.Method == "" {
+			if req.ID != nil && string(*req.ID) == rootsRequestID {
+				roots.setDirs(parseRoots(req.Result))
+			}
+			continue
+		}
+
 		var resp jsonRpcResponse
 		resp.Jsonrpc = "2.0"
 		resp.ID = req.ID
 
 		switch req.Method {
+		case "notifications/initialized", "notifications/roots/list_changed":
+			if roots.supported {
+				requestRoots()
+			}
+			continue
+
 		case "initialize":
+			var params struct {
+				Capabilities struct {
+					Roots *json.RawMessage `json:"roots"`
+				} `json:"capabilities"`
+				ClientInfo struct {
+					Name string `json:"name"`
+				} `json:"clientInfo"`
+			}
+			_ = json.Unmarshal(req.Params, &params)
+			roots.supported = params.Capabilities.Roots != nil
+			if roots.supported {
+				roots.requested() // once the host is initialized
+			}
+			tools.setClientName(params.ClientInfo.Name)
 			resp.Result = map[string]interface{}{
 				"protocolVersion": "2024-11-05",
 				"capabilities": map[string]interface{}{
@@ -94,16 +151,15 @@ func RunMCPServer(dataDir string) error {
 			}
 
 		case "tools/call":
-			result, err := handleToolCall(dataDir, req.Params)
-			if err != nil {
-				resp.Error = &jsonRpcError{Code: -32603, Message: err.Error()}
-			} else {
-				resp.Result = map[string]interface{}{
-					"content": []map[string]interface{}{
-						{"type": "text", "text": result},
-					},
+			if !roots.known() {
+				resp.Error = &jsonRpcError{
+					Code:    -32002,
+					Message: "strict roots mode is on and the MCP host has not answered roots/list yet; retry shortly",
 				}
+				break
 			}
+			tools.call(req.ID, req.Params)
+			continue
 
 		default:
 			resp.Error = &jsonRpcError{
@@ -112,9 +168,9 @@ func RunMCPServer(dataDir string) error {
 			}
 		}
 
-		out, _ := json.Marshal(resp)
-		fmt.Fprintf(os.Stdout, "%s\n", out)
+		writeMessage(resp)
 	}
+	tools.wait()
 	return scanner.Err()
 }
 
@@ -133,7 +189,7 @@ func getNodeTools() []tool {
 	return []tool{
 		{
 			Name:        "codewire_list_sessions",
-			Description: "List all CodeWire sessions with their status",
+			Description: "List all CodeWire sessions with their status. Finished sessions include exit_code and completed_at",
 			InputSchema: map[string]interface{}{
 				"type": "object",
 				"properties": map[string]interface{}{
@@ -145,6 +201,24 @@ func getNodeTools() []tool {
 				},
 			},
 		},
+		{
+			Name:        "codewire_find_sessions",
+			Description: "Fuzzy-search sessions by name, tag, prompt or working directory, best match first",
+			InputSchema: map[string]interface{}{
+				"type": "object",
+				"properties": map[string]interface{}{
+					"query": map[string]interface{}{
+						"type":        "string",
+						"description": "Text whose characters appear in order in the session's name, a tag, its prompt or working directory; a session ID matches exactly",
+					},
+					"limit": map[string]interface{}{
+						"type":        "integer",
+						"description": "Maximum number of matches (default: 20)",
+					},
+				},
+				"required": []string{"query"},
+			},
+		},
 		{
 			Name:        "codewire_read_session_output",
 			Description: "Read output from a session (snapshot, not live)",
@@ -179,14 +253,23 @@ func getNodeTools() []tool {
 					},
 					"input": map[string]interface{}{
 						"type":        "string",
-						"description": "The input text to send",
+						"description": "The input text to send (or use template)",
+					},
+					"template": map[string]interface{}{
+						"type":        "string",
+						"description": "Path to a template file to render and send instead of input; {{name}} placeholders are filled from vars",
+					},
+					"vars": map[string]interface{}{
+						"type":                 "object",
+						"additionalProperties": map[string]interface{}{"type": "string"},
+						"description":          "Template variables, e.g. {\"pr\": \"123\"}",
 					},
 					"auto_newline": map[string]interface{}{
 						"type":        "boolean",
 						"description": "Automatically add newline (default: true)",
 					},
 				},
-				"required": []string{"session_id", "input"},
+				"required": []string{"session_id"},
 			},
 		},
 		{
@@ -211,13 +294,17 @@ func getNodeTools() []tool {
 						"type":        "integer",
 						"description": "Maximum watch duration in seconds (default: 30)",
 					},
+					"resume_from": map[string]interface{}{
+						"type":        "integer",
+						"description": "Output cursor from a previous watch's timeout line; continues exactly after it instead of including history",
+					},
 				},
 				"required": []string{"session_id"},
 			},
 		},
 		{
 			Name:        "codewire_get_session_status",
-			Description: "Get detailed status information for a session",
+			Description: "Get detailed status information for a session, including exit_code and completed_at once it has finished",
 			InputSchema: map[string]interface{}{
 				"type": "object",
 				"properties": map[string]interface{}{
@@ -225,24 +312,32 @@ func getNodeTools() []tool {
 						"type":        "integer",
 						"description": "The session ID to query",
 					},
+					"include_snapshot": map[string]interface{}{
+						"type":        "boolean",
+						"description": "Include the working directory snapshot captured on exit (session must be launched with snapshot)",
+					},
 				},
 				"required": []string{"session_id"},
 			},
 		},
 		{
 			Name:        "codewire_launch_session",
-			Description: "Launch a new CodeWire session with optional name and tags for grouping and filtering",
+			Description: "Launch a new CodeWire session with optional name and tags for grouping and filtering. With wait_seconds, waits for the first output and reports whether the program is ready for input",
 			InputSchema: map[string]interface{}{
 				"type": "object",
 				"properties": map[string]interface{}{
 					"command": map[string]interface{}{
 						"type":        "array",
 						"items":       map[string]interface{}{"type": "string"},
-						"description": "Command and arguments to run",
+						"description": "Command and arguments to run (required unless shell is given)",
+					},
+					"shell": map[string]interface{}{
+						"type":        "string",
+						"description": "Shell command line to run instead of command, e.g. \"npm test && claude -p 'fix failures'\". Runs through the node's configured shell (default sh -c)",
 					},
 					"working_dir": map[string]interface{}{
 						"type":        "string",
-						"description": "Working directory (defaults to current dir)",
+						"description": "Working directory (defaults to the first root declared by the MCP host, else the server's current dir; relative paths are taken against that root)",
 					},
 					"name": map[string]interface{}{
 						"type":        "string",
@@ -251,10 +346,21 @@ func getNodeTools() []tool {
 					"tags": map[string]interface{}{
 						"type":        "array",
 						"items":       map[string]interface{}{"type": "string"},
-						"description": "Tags for grouping/filtering (e.g. ['worker', 'build'])",
+						"description": "Tags for grouping/filtering (e.g. ['worker', 'build']). launched-by=mcp and mcp-client=<client name> are always added",
+					},
+					"snapshot": map[string]interface{}{
+						"type":        "boolean",
+						"description": "Capture git status/diff of the working directory when the session exits (default: false)",
+					},
+					"wait_seconds": map[string]interface{}{
+						"type":        "number",
+						"description": "Wait up to this long (max 60) for the session to show a prompt, then return JSON with session_id, status, ready and the first output (ANSI stripped, up to 4KB). Default: return as soon as it is launched",
+					},
+					"ready_pattern": map[string]interface{}{
+						"type":        "string",
+						"description": "Regex the last line of output must match to count as ready (default: a line ending in a prompt character such as $ # > ❯ ? or :)",
 					},
 				},
-				"required": []string{"command"},
 			},
 		},
 		{
@@ -275,6 +381,42 @@ func getNodeTools() []tool {
 				},
 			},
 		},
+		{
+			Name:        "codewire_signal_session",
+			Description: "Send a signal to a session's foreground processes without killing the session. Defaults to INT (Ctrl+C), e.g. to interrupt a worker agent mid-task.",
+			InputSchema: map[string]interface{}{
+				"type": "object",
+				"properties": map[string]interface{}{
+					"session_id": map[string]interface{}{
+						"type":        "integer",
+						"description": "The session ID to signal",
+					},
+					"signal": map[string]interface{}{
+						"type":        "string",
+						"description": "Signal name or number: INT (default), TERM, HUP, QUIT, USR1, USR2, ...",
+					},
+				},
+				"required": []string{"session_id"},
+			},
+		},
+		{
+			Name:        "codewire_extend_session",
+			Description: "Push back a session's max-runtime and idle-timeout deadlines, e.g. after a session.expiring event for a worker that still has work to do.",
+			InputSchema: map[string]interface{}{
+				"type": "object",
+				"properties": map[string]interface{}{
+					"session_id": map[string]interface{}{
+						"type":        "integer",
+						"description": "The session ID to extend",
+					},
+					"extra": map[string]interface{}{
+						"type":        "string",
+						"description": "How much longer to allow, as a duration like 30m or 2h",
+					},
+				},
+				"required": []string{"session_id", "extra"},
+			},
+		},
 		{
 			Name:        "codewire_subscribe",
 			Description: "Subscribe to session events (returns events as they arrive, time-bounded)",
@@ -432,6 +574,14 @@ func getNodeTools() []tool {
 				"properties": map[string]interface{}{},
 			},
 		},
+		{
+			Name:        "codewire_fleet_nodes",
+			Description: "List the nodes this machine can launch on (the local node and saved servers) with their health, version, relay connection, and running and total session counts, including running sessions per tag. Use it to decide where to place new work.",
+			InputSchema: map[string]interface{}{
+				"type":       "object",
+				"properties": map[string]interface{}{},
+			},
+		},
 		{
 			Name:        "codewire_kv_set",
 			Description: "Set a key-value pair in the shared relay store",
@@ -519,7 +669,7 @@ func getNodeTools() []tool {
 // ---------------------------------------------------------------------------
 
 // handleToolCall dispatches to the appropriate tool handler.
-func handleToolCall(dataDir string, params json.RawMessage) (string, error) {
+func handleToolCall(dataDir string, cc *callContext, params json.RawMessage) (string, error) {
 	var p struct {
 		Name      string                 `json:"name"`
 		Arguments map[string]interface{} `json:"arguments"`
@@ -532,41 +682,49 @@ func handleToolCall(dataDir string, params json.RawMessage) (string, error) {
 
 	switch p.Name {
 	case "codewire_list_sessions":
-		return toolListSessions(dataDir, args)
+		return toolListSessions(dataDir, cc, args)
+	case "codewire_find_sessions":
+		return toolFindSessions(dataDir, cc, args)
 	case "codewire_read_session_output":
-		return toolReadSessionOutput(dataDir, args)
+		return toolReadSessionOutput(dataDir, cc, args)
 	case "codewire_send_input":
-		return toolSendInput(dataDir, args)
+		return toolSendInput(dataDir, cc, args)
 	case "codewire_watch_session":
-		return toolWatchSession(dataDir, args)
+		return toolWatchSession(dataDir, cc, args)
 	case "codewire_get_session_status":
-		return toolGetSessionStatus(dataDir, args)
+		return toolGetSessionStatus(dataDir, cc, args)
 	case "codewire_launch_session":
-		return toolLaunchSession(dataDir, args)
+		return toolLaunchSession(dataDir, cc, args)
 	case "codewire_kill_session":
-		return toolKillSession(dataDir, args)
+		return toolKillSession(dataDir, cc, args)
+	case "codewire_signal_session":
+		return toolSignalSession(dataDir, cc, args)
+	case "codewire_extend_session":
+		return toolExtendSession(dataDir, cc, args)
 	case "codewire_subscribe":
-		return toolSubscribe(dataDir, args)
+		return toolSubscribe(dataDir, cc, args)
 	case "codewire_wait_for":
-		return toolWaitFor(dataDir, args)
+		return toolWaitFor(dataDir, cc, args)
 	case "codewire_msg":
-		return toolMsg(dataDir, args)
+		return toolMsg(dataDir, cc, args)
 	case "codewire_read_messages":
-		return toolReadMessages(dataDir, args)
+		return toolReadMessages(dataDir, cc, args)
 	case "codewire_request":
-		return toolRequest(dataDir, args)
+		return toolRequest(dataDir, cc, args)
 	case "codewire_reply":
-		return toolReply(dataDir, args)
+		return toolReply(dataDir, cc, args)
 	case "codewire_list_nodes":
-		return toolListNodes(dataDir, args)
+		return toolListNodes(dataDir, cc, args)
+	case "codewire_fleet_nodes":
+		return toolFleetNodes(dataDir, cc, args)
 	case "codewire_kv_set":
-		return toolKVSet(dataDir, args)
+		return toolKVSet(dataDir, cc, args)
 	case "codewire_kv_get":
-		return toolKVGet(dataDir, args)
+		return toolKVGet(dataDir, cc, args)
 	case "codewire_kv_list":
-		return toolKVList(dataDir, args)
+		return toolKVList(dataDir, cc, args)
 	case "codewire_kv_delete":
-		return toolKVDelete(dataDir, args)
+		return toolKVDelete(dataDir, cc, args)
 	// Platform environment tools (use API, not local node)
 	case "codewire_list_environments":
 		return toolListEnvironments(args)
@@ -595,8 +753,8 @@ func handleToolCall(dataDir string, params json.RawMessage) (string, error) {
 // Tool handlers
 // ---------------------------------------------------------------------------
 
-func toolListSessions(dataDir string, args map[string]interface{}) (string, error) {
-	resp, err := nodeRequest(dataDir, &protocol.Request{Type: "ListSessions"})
+func toolListSessions(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{Type: "ListSessions"})
 	if err != nil {
 		return "", err
 	}
@@ -637,7 +795,35 @@ func toolListSessions(dataDir string, args map[string]interface{}) (string, erro
 	return string(out), nil
 }
 
-func toolReadSessionOutput(dataDir string, args map[string]interface{}) (string, error) {
+func toolFindSessions(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
+	query, _ := args["query"].(string)
+	if query == "" {
+		return "", fmt.Errorf("query is required")
+	}
+	limit := uint32(20)
+	if v, ok := args["limit"].(float64); ok {
+		limit = uint32(v)
+	}
+
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{Type: "FindSessions", Query: query, Limit: limit})
+	if err != nil {
+		return "", err
+	}
+	if resp.Type == "Error" {
+		return fmt.Sprintf("Error: %s", resp.Message), nil
+	}
+	if resp.Sessions == nil {
+		return "Unexpected response", nil
+	}
+
+	out, err := json.MarshalIndent(*resp.Sessions, "", "  ")
+	if err != nil {
+		return "", err
+	}
+	return string(out), nil
+}
+
+func toolReadSessionOutput(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	sessionID, err := argUint32(args, "session_id")
 	if err != nil {
 		return "", err
@@ -655,7 +841,7 @@ func toolReadSessionOutput(dataDir string, args map[string]interface{}) (string,
 	}
 
 	f := false
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type:   "Logs",
 		ID:     &sessionID,
 		Follow: &f,
@@ -676,16 +862,35 @@ func toolReadSessionOutput(dataDir string, args map[string]interface{}) (string,
 	if uint64(len(data)) > maxChars {
 		data = data[:maxChars] + "... [truncated]"
 	}
+	if resp.Lossy {
+		data += "\n[some output was not valid in the session's encoding and was replaced with U+FFFD]"
+	}
 	return data, nil
 }
 
-func toolSendInput(dataDir string, args map[string]interface{}) (string, error) {
+func toolSendInput(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	sessionID, err := argUint32(args, "session_id")
 	if err != nil {
 		return "", err
 	}
 
 	input, ok := args["input"].(string)
+	if path, isTemplate := args["template"].(string); isTemplate && path != "" {
+		if ok {
+			return "", fmt.Errorf("input and template are mutually exclusive")
+		}
+		vars := map[string]string{}
+		if raw, isMap := args["vars"].(map[string]interface{}); isMap {
+			for k, v := range raw {
+				vars[k] = fmt.Sprint(v)
+			}
+		}
+		rendered, err := tmpl.RenderFile(path, vars)
+		if err != nil {
+			return fmt.Sprintf("Error: %s", err), nil
+		}
+		input, ok = rendered, true
+	}
 	if !ok {
 		return "", fmt.Errorf("missing input")
 	}
@@ -700,7 +905,7 @@ func toolSendInput(dataDir string, args map[string]interface{}) (string, error)
 		data = append(data, '\n')
 	}
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type: "SendInput",
 		ID:   &sessionID,
 		Data: data,
@@ -722,7 +927,7 @@ func toolSendInput(dataDir string, args map[string]interface{}) (string, error)
 	return "Unexpected response", nil
 }
 
-func toolWatchSession(dataDir string, args map[string]interface{}) (string, error) {
+func toolWatchSession(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	sessionID, err := argUint32(args, "session_id")
 	if err != nil {
 		return "", err
@@ -744,18 +949,27 @@ func toolWatchSession(dataDir string, args map[string]interface{}) (string, erro
 		maxDuration = uint64(v)
 	}
 
-	return watchSessionTimed(dataDir, sessionID, includeHistory, historyLines, maxDuration)
+	var resumeFrom *uint64
+	if v, ok := args["resume_from"].(float64); ok {
+		r := uint64(v)
+		resumeFrom = &r
+	}
+
+	return watchSessionTimed(dataDir, cc.deadline, sessionID, includeHistory, historyLines, resumeFrom, maxDuration)
 }
 
-func toolGetSessionStatus(dataDir string, args map[string]interface{}) (string, error) {
+func toolGetSessionStatus(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	sessionID, err := argUint32(args, "session_id")
 	if err != nil {
 		return "", err
 	}
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
-		Type: "GetStatus",
-		ID:   &sessionID,
+	includeSnapshot, _ := args["include_snapshot"].(bool)
+
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
+		Type:     "GetStatus",
+		ID:       &sessionID,
+		Snapshot: includeSnapshot,
 	})
 	if err != nil {
 		return "", err
@@ -780,6 +994,9 @@ func toolGetSessionStatus(dataDir string, args map[string]interface{}) (string,
 	if resp.OutputSize != nil {
 		obj["output_size"] = *resp.OutputSize
 	}
+	if resp.Snapshot != nil {
+		obj["snapshot"] = *resp.Snapshot
+	}
 
 	out, err := json.MarshalIndent(obj, "", "  ")
 	if err != nil {
@@ -788,31 +1005,33 @@ func toolGetSessionStatus(dataDir string, args map[string]interface{}) (string,
 	return string(out), nil
 }
 
-func toolLaunchSession(dataDir string, args map[string]interface{}) (string, error) {
+func toolLaunchSession(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
+	script, _ := args["shell"].(string)
 	cmdRaw, ok := args["command"]
-	if !ok {
-		return "", fmt.Errorf("missing command")
-	}
-	cmdArr, ok := cmdRaw.([]interface{})
-	if !ok {
-		return "", fmt.Errorf("command must be an array")
+	if !ok && script == "" {
+		return "", fmt.Errorf("missing command or shell")
 	}
 	var command []string
-	for _, v := range cmdArr {
-		s, ok := v.(string)
-		if ok {
-			command = append(command, s)
+	if ok {
+		cmdArr, ok := cmdRaw.([]interface{})
+		if !ok {
+			return "", fmt.Errorf("command must be an array")
+		}
+		for _, v := range cmdArr {
+			s, ok := v.(string)
+			if ok {
+				command = append(command, s)
+			}
 		}
 	}
+	if script != "" && len(command) > 0 {
+		return "", fmt.Errorf("pass either command or shell, not both")
+	}
 
 	workingDir, _ := args["working_dir"].(string)
-	if workingDir == "" {
-		wd, err := os.Getwd()
-		if err != nil {
-			workingDir = "."
-		} else {
-			workingDir = wd
-		}
+	workingDir, err := cc.roots.workingDir(workingDir)
+	if err != nil {
+		return "", err
 	}
 
 	name, _ := args["name"].(string)
@@ -825,13 +1044,29 @@ func toolLaunchSession(dataDir string, args map[string]interface{}) (string, err
 			}
 		}
 	}
+	tags = append(tags, cc.launchTags()...)
+
+	snapshot, _ := args["snapshot"].(bool)
+
+	var wait time.Duration
+	if v, ok := args["wait_seconds"].(float64); ok && v > 0 {
+		wait = min(time.Duration(v*float64(time.Second)), maxLaunchWait)
+	}
+	ready := defaultReadyPattern
+	if p, _ := args["ready_pattern"].(string); p != "" {
+		if ready, err = regexp.Compile(p); err != nil {
+			return "", fmt.Errorf("invalid ready_pattern: %w", err)
+		}
+	}
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type:       "Launch",
 		Command:    command,
 		WorkingDir: workingDir,
 		Name:       name,
 		Tags:       tags,
+		Snapshot:   snapshot,
+		Script:     script,
 	})
 	if err != nil {
 		return "", err
@@ -840,13 +1075,72 @@ func toolLaunchSession(dataDir string, args map[string]interface{}) (string, err
 	if resp.Type == "Error" {
 		return fmt.Sprintf("Error: %s", resp.Message), nil
 	}
-	if resp.Type == "Launched" && resp.ID != nil {
+	if resp.Type != "Launched" || resp.ID == nil {
+		return "Unexpected response", nil
+	}
+	if wait == 0 {
 		return fmt.Sprintf("Launched session %d", *resp.ID), nil
 	}
-	return "Unexpected response", nil
+	result, err := awaitInitialOutput(dataDir, cc.deadline, *resp.ID, wait, ready)
+	if err != nil {
+		return "", fmt.Errorf("launched session %d, but reading its output failed: %w", *resp.ID, err)
+	}
+	out, err := json.MarshalIndent(result, "", "  ")
+	if err != nil {
+		return "", err
+	}
+	return string(out), nil
+}
+
+func toolSignalSession(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
+	sessionID, err := argUint32(args, "session_id")
+	if err != nil {
+		return "", err
+	}
+	signal := "INT"
+	if s, ok := args["signal"].(string); ok && s != "" {
+		signal = s
+	}
+
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
+		Type:   "Kill",
+		ID:     &sessionID,
+		Signal: signal,
+	})
+	if err != nil {
+		return "", err
+	}
+	if resp.Type == "Error" {
+		return fmt.Sprintf("Error: %s", resp.Message), nil
+	}
+	return fmt.Sprintf("Sent %s to session %d", resp.Message, sessionID), nil
+}
+
+func toolExtendSession(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
+	sessionID, err := argUint32(args, "session_id")
+	if err != nil {
+		return "", err
+	}
+	extra, _ := args["extra"].(string)
+	if extra == "" {
+		return "", fmt.Errorf("extra is required")
+	}
+
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
+		Type:  "Extend",
+		ID:    &sessionID,
+		Extra: extra,
+	})
+	if err != nil {
+		return "", err
+	}
+	if resp.Type == "Error" {
+		return fmt.Sprintf("Error: %s", resp.Message), nil
+	}
+	return fmt.Sprintf("Extended session %d; next limit (%s) at %s", sessionID, resp.ExpiryLimit, resp.ExpiresAt), nil
 }
 
-func toolKillSession(dataDir string, args map[string]interface{}) (string, error) {
+func toolKillSession(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	// Check if killing by tags.
 	var tags []string
 	if tagsRaw, ok := args["tags"].([]interface{}); ok {
@@ -858,7 +1152,7 @@ func toolKillSession(dataDir string, args map[string]interface{}) (string, error
 	}
 
 	if len(tags) > 0 {
-		resp, err := nodeRequest(dataDir, &protocol.Request{
+		resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 			Type: "KillByTags",
 			Tags: tags,
 		})
@@ -880,7 +1174,7 @@ func toolKillSession(dataDir string, args map[string]interface{}) (string, error
 		return "", fmt.Errorf("either session_id or tags required")
 	}
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type: "Kill",
 		ID:   &sessionID,
 	})
@@ -897,7 +1191,7 @@ func toolKillSession(dataDir string, args map[string]interface{}) (string, error
 	return "Unexpected response", nil
 }
 
-func toolSubscribe(dataDir string, args map[string]interface{}) (string, error) {
+func toolSubscribe(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	maxDuration := uint64(30)
 	if v, ok := args["max_duration_seconds"].(float64); ok {
 		maxDuration = uint64(v)
@@ -927,10 +1221,10 @@ func toolSubscribe(dataDir string, args map[string]interface{}) (string, error)
 		}
 	}
 
-	return subscribeTimed(dataDir, sessionID, tags, eventTypes, maxDuration)
+	return subscribeTimed(dataDir, cc.deadline, sessionID, tags, eventTypes, maxDuration)
 }
 
-func toolWaitFor(dataDir string, args map[string]interface{}) (string, error) {
+func toolWaitFor(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	var sessionID *uint32
 	if v, ok := args["session_id"].(float64); ok {
 		id := uint32(v)
@@ -956,10 +1250,10 @@ func toolWaitFor(dataDir string, args map[string]interface{}) (string, error) {
 		timeoutSecs = uint64(v)
 	}
 
-	return waitForTimed(dataDir, sessionID, tags, condition, timeoutSecs)
+	return waitForTimed(dataDir, cc.deadline, sessionID, tags, condition, timeoutSecs)
 }
 
-func toolMsg(dataDir string, args map[string]interface{}) (string, error) {
+func toolMsg(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	body, _ := args["body"].(string)
 	if body == "" {
 		return "", fmt.Errorf("missing body")
@@ -982,7 +1276,7 @@ func toolMsg(dataDir string, args map[string]interface{}) (string, error) {
 		req.ID = &id
 	}
 
-	resp, err := nodeRequest(dataDir, req)
+	resp, err := nodeRequest(dataDir, cc.deadline, req)
 	if err != nil {
 		return "", err
 	}
@@ -992,7 +1286,7 @@ func toolMsg(dataDir string, args map[string]interface{}) (string, error) {
 	return fmt.Sprintf("Message sent: %s", resp.MessageID), nil
 }
 
-func toolReadMessages(dataDir string, args map[string]interface{}) (string, error) {
+func toolReadMessages(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	var sessionID *uint32
 	if v, ok := args["session_id"].(float64); ok {
 		id := uint32(v)
@@ -1010,7 +1304,7 @@ func toolReadMessages(dataDir string, args map[string]interface{}) (string, erro
 		Tail: &tail,
 	}
 
-	resp, err := nodeRequest(dataDir, req)
+	resp, err := nodeRequest(dataDir, cc.deadline, req)
 	if err != nil {
 		return "", err
 	}
@@ -1027,7 +1321,7 @@ func toolReadMessages(dataDir string, args map[string]interface{}) (string, erro
 	return string(out), nil
 }
 
-func toolRequest(dataDir string, args map[string]interface{}) (string, error) {
+func toolRequest(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	body, _ := args["body"].(string)
 	if body == "" {
 		return "", fmt.Errorf("missing body")
@@ -1056,10 +1350,9 @@ func toolRequest(dataDir string, args map[string]interface{}) (string, error) {
 	}
 
 	// This blocks until reply or timeout — use a long-lived connection.
-	sockPath := filepath.Join(dataDir, "codewire.sock")
-	conn, err := net.Dial("unix", sockPath)
+	conn, err := dialNode(dataDir, cc.deadline, req)
 	if err != nil {
-		return "", fmt.Errorf("no node running — start one with: cw node -d\n(socket: %s)", sockPath)
+		return "", err
 	}
 	defer conn.Close()
 
@@ -1103,7 +1396,7 @@ func toolRequest(dataDir string, args map[string]interface{}) (string, error) {
 	}
 }
 
-func toolReply(dataDir string, args map[string]interface{}) (string, error) {
+func toolReply(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	requestID, _ := args["request_id"].(string)
 	if requestID == "" {
 		return "", fmt.Errorf("missing request_id")
@@ -1120,7 +1413,7 @@ func toolReply(dataDir string, args map[string]interface{}) (string, error) {
 		req.ID = &id
 	}
 
-	resp, err := nodeRequest(dataDir, req)
+	resp, err := nodeRequest(dataDir, cc.deadline, req)
 	if err != nil {
 		return "", err
 	}
@@ -1130,7 +1423,7 @@ func toolReply(dataDir string, args map[string]interface{}) (string, error) {
 	return fmt.Sprintf("Reply sent for request %s", requestID), nil
 }
 
-func toolListNodes(dataDir string, _ map[string]interface{}) (string, error) {
+func toolListNodes(dataDir string, cc *callContext, _ map[string]interface{}) (string, error) {
 	cfg, err := loadRelayConfig(dataDir)
 	if err != nil {
 		return "", err
@@ -1143,7 +1436,46 @@ func toolListNodes(dataDir string, _ map[string]interface{}) (string, error) {
 	return string(resp), nil
 }
 
-func toolKVSet(dataDir string, args map[string]interface{}) (string, error) {
+// fleetProbeTimeout bounds each node's answer to codewire_fleet_nodes, so one
+// unreachable server does not hold up the listing.
+const fleetProbeTimeout = 5 * time.Second
+
+func toolFleetNodes(dataDir string, cc *callContext, _ map[string]interface{}) (string, error) {
+	servers, err := config.LoadServersConfig(dataDir)
+	if err != nil {
+		return "", err
+	}
+	targets := []*client.Target{{Local: dataDir, Timeout: fleetProbeTimeout}}
+	for _, name := range slices.Sorted(maps.Keys(servers.Servers)) {
+		entry := servers.Servers[name]
+		targets = append(targets, &client.Target{
+			URL:     entry.URL,
+			Token:   entry.Token,
+			Name:    name,
+			NodeKey: entry.PinnedKey(),
+			Timeout: fleetProbeTimeout,
+		})
+	}
+
+	summaries := make([]client.NodeSummary, len(targets))
+	var wg sync.WaitGroup
+	for i, t := range targets {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			summaries[i] = client.SummarizeNode(t)
+		}()
+	}
+	wg.Wait()
+
+	out, err := json.MarshalIndent(summaries, "", "  ")
+	if err != nil {
+		return "", err
+	}
+	return string(out), nil
+}
+
+func toolKVSet(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	key, _ := args["key"].(string)
 	if key == "" {
 		return "", fmt.Errorf("missing key")
@@ -1155,7 +1487,7 @@ func toolKVSet(dataDir string, args map[string]interface{}) (string, error) {
 	}
 	ttl, _ := args["ttl"].(string)
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type:      "KVSet",
 		Namespace: namespace,
 		Key:       key,
@@ -1171,7 +1503,7 @@ func toolKVSet(dataDir string, args map[string]interface{}) (string, error) {
 	return fmt.Sprintf("Set %s/%s", namespace, key), nil
 }
 
-func toolKVGet(dataDir string, args map[string]interface{}) (string, error) {
+func toolKVGet(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	key, _ := args["key"].(string)
 	if key == "" {
 		return "", fmt.Errorf("missing key")
@@ -1181,7 +1513,7 @@ func toolKVGet(dataDir string, args map[string]interface{}) (string, error) {
 		namespace = "default"
 	}
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type:      "KVGet",
 		Namespace: namespace,
 		Key:       key,
@@ -1198,14 +1530,14 @@ func toolKVGet(dataDir string, args map[string]interface{}) (string, error) {
 	return string(resp.Value), nil
 }
 
-func toolKVList(dataDir string, args map[string]interface{}) (string, error) {
+func toolKVList(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	prefix, _ := args["prefix"].(string)
 	namespace, _ := args["namespace"].(string)
 	if namespace == "" {
 		namespace = "default"
 	}
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type:      "KVList",
 		Namespace: namespace,
 		Key:       prefix,
@@ -1226,7 +1558,7 @@ func toolKVList(dataDir string, args map[string]interface{}) (string, error) {
 	return string(out), nil
 }
 
-func toolKVDelete(dataDir string, args map[string]interface{}) (string, error) {
+func toolKVDelete(dataDir string, cc *callContext, args map[string]interface{}) (string, error) {
 	key, _ := args["key"].(string)
 	if key == "" {
 		return "", fmt.Errorf("missing key")
@@ -1236,7 +1568,7 @@ func toolKVDelete(dataDir string, args map[string]interface{}) (string, error) {
 		namespace = "default"
 	}
 
-	resp, err := nodeRequest(dataDir, &protocol.Request{
+	resp, err := nodeRequest(dataDir, cc.deadline, &protocol.Request{
 		Type:      "KVDelete",
 		Namespace: namespace,
 		Key:       key,
@@ -1254,14 +1586,31 @@ func toolKVDelete(dataDir string, args map[string]interface{}) (string, error) {
 // Node communication
 // ---------------------------------------------------------------------------
 
-// nodeRequest connects to the Unix socket and sends a single request,
-// returning the response.
-func nodeRequest(dataDir string, req *protocol.Request) (*protocol.Response, error) {
+// dialNode connects to the node's Unix socket for a tool call. Reads and
+// writes on the connection fail once the call's deadline passes, and req
+// carries the time left so the node stops working on it then too.
+func dialNode(dataDir string, deadline time.Time, req *protocol.Request) (net.Conn, error) {
 	sockPath := filepath.Join(dataDir, "codewire.sock")
-	conn, err := net.Dial("unix", sockPath)
+	dialer := net.Dialer{Deadline: deadline}
+	conn, err := dialer.Dial("unix", sockPath)
 	if err != nil {
 		return nil, fmt.Errorf("no node running — start one with: cw node -d\n(socket: %s)", sockPath)
 	}
+	if !deadline.IsZero() {
+		_ = conn.SetDeadline(deadline)
+		ms := uint64(max(time.Until(deadline), 0) / time.Millisecond)
+		req.TimeoutMs = &ms
+	}
+	return conn, nil
+}
+
+// nodeRequest connects to the Unix socket and sends a single request,
+// returning the response.
+func nodeRequest(dataDir string, deadline time.Time, req *protocol.Request) (*protocol.Response, error) {
+	conn, err := dialNode(dataDir, deadline, req)
+	if err != nil {
+		return nil, err
+	}
 	defer conn.Close()
 
 	reader := connection.NewUnixReader(conn)
@@ -1291,28 +1640,35 @@ func nodeRequest(dataDir string, req *protocol.Request) (*protocol.Response, err
 
 // watchSessionTimed connects and watches a session with a maximum duration,
 // collecting all output.
-func watchSessionTimed(dataDir string, sessionID uint32, includeHistory bool, historyLines *uint, maxDurationSecs uint64) (string, error) {
-	sockPath := filepath.Join(dataDir, "codewire.sock")
-	conn, err := net.Dial("unix", sockPath)
+// watchSessionTimed returns a session's output for up to maxDurationSecs.
+// The result ends with the output cursor when the watch times out, so the
+// next call can pass it as resume_from and continue where this one stopped.
+func watchSessionTimed(dataDir string, callDeadline time.Time, sessionID uint32, includeHistory bool, historyLines *uint, resumeFrom *uint64, maxDurationSecs uint64) (string, error) {
+	req := &protocol.Request{
+		Type:           "WatchSession",
+		ID:             &sessionID,
+		IncludeHistory: &includeHistory,
+		HistoryLines:   historyLines,
+		ResumeFrom:     resumeFrom,
+	}
+	conn, err := dialNode(dataDir, callDeadline, req)
 	if err != nil {
-		return "", fmt.Errorf("no node running — start one with: cw node -d\n(socket: %s)", sockPath)
+		return "", err
 	}
 	defer conn.Close()
 
 	reader := connection.NewUnixReader(conn)
 	writer := connection.NewUnixWriter(conn)
 
-	req := &protocol.Request{
-		Type:           "WatchSession",
-		ID:             &sessionID,
-		IncludeHistory: &includeHistory,
-		HistoryLines:   historyLines,
-	}
 	if err := writer.SendRequest(req); err != nil {
 		return "", err
 	}
 
 	var output string
+	var cursor, missed uint64
+	if resumeFrom != nil {
+		cursor = *resumeFrom
+	}
 	deadline := time.After(time.Duration(maxDurationSecs) * time.Second)
 
 	type frameResult struct {
@@ -1349,6 +1705,10 @@ func watchSessionTimed(dataDir string, sessionID uint32, includeHistory bool, hi
 					if resp.Output != nil {
 						output += *resp.Output
 					}
+					if resp.Cursor != 0 {
+						cursor = resp.Cursor
+					}
+					missed += resp.Missed
 					if resp.Done != nil && *resp.Done {
 						output += fmt.Sprintf("\n[Session %s]\n", resp.Status)
 						return output, nil
@@ -1359,10 +1719,19 @@ func watchSessionTimed(dataDir string, sessionID uint32, includeHistory bool, hi
 			}
 
 		case <-deadline:
-			output += "\n[Watch timeout]\n"
-			if len(output) > 500000 {
-				output = output[:500000] + "\n... [output truncated to 500KB]"
+			const maxOutput = 500000
+			if textwidth.Width(output) > maxOutput {
+				// Point the cursor at the end of what is returned, so the
+				// rest is read by the next call. Truncate always marks a
+				// cut here, and the marker is not session output.
+				kept := strings.TrimSuffix(textwidth.Truncate(output, maxOutput), "...")
+				cursor -= uint64(len(output) - len(kept))
+				output = kept + "\n... [output truncated to 500KB]"
+			}
+			if missed > 0 {
+				output += fmt.Sprintf("\n[%d bytes of earlier output were no longer on the node]", missed)
 			}
+			output += fmt.Sprintf("\n[Watch timeout; continue with resume_from=%d]\n", cursor)
 			return output, nil
 		}
 	}
@@ -1388,23 +1757,22 @@ func endsWithNewline(data []byte) bool {
 }
 
 // subscribeTimed subscribes to events and collects them for up to maxDurationSecs.
-func subscribeTimed(dataDir string, sessionID *uint32, tags, eventTypes []string, maxDurationSecs uint64) (string, error) {
-	sockPath := filepath.Join(dataDir, "codewire.sock")
-	conn, err := net.Dial("unix", sockPath)
+func subscribeTimed(dataDir string, callDeadline time.Time, sessionID *uint32, tags, eventTypes []string, maxDurationSecs uint64) (string, error) {
+	req := &protocol.Request{
+		Type:       "Subscribe",
+		ID:         sessionID,
+		Tags:       tags,
+		EventTypes: eventTypes,
+	}
+	conn, err := dialNode(dataDir, callDeadline, req)
 	if err != nil {
-		return "", fmt.Errorf("no node running — start one with: cw node -d\n(socket: %s)", sockPath)
+		return "", err
 	}
 	defer conn.Close()
 
 	reader := connection.NewUnixReader(conn)
 	writer := connection.NewUnixWriter(conn)
 
-	req := &protocol.Request{
-		Type:       "Subscribe",
-		ID:         sessionID,
-		Tags:       tags,
-		EventTypes: eventTypes,
-	}
 	if err := writer.SendRequest(req); err != nil {
 		return "", err
 	}
@@ -1475,9 +1843,15 @@ func subscribeTimed(dataDir string, sessionID *uint32, tags, eventTypes []string
 }
 
 // waitForTimed sends a Wait request and blocks for the result.
-func waitForTimed(dataDir string, sessionID *uint32, tags []string, condition string, timeoutSecs uint64) (string, error) {
-	sockPath := filepath.Join(dataDir, "codewire.sock")
-	conn, err := net.Dial("unix", sockPath)
+func waitForTimed(dataDir string, callDeadline time.Time, sessionID *uint32, tags []string, condition string, timeoutSecs uint64) (string, error) {
+	req := &protocol.Request{
+		Type:           "Wait",
+		ID:             sessionID,
+		Tags:           tags,
+		Condition:      condition,
+		TimeoutSeconds: &timeoutSecs,
+	}
+	conn, err := dialNode(dataDir, callDeadline, req)
 	if err != nil {
 		return "", err
 	}
@@ -1486,13 +1860,6 @@ func waitForTimed(dataDir string, sessionID *uint32, tags []string, condition st
 	reader := connection.NewUnixReader(conn)
 	writer := connection.NewUnixWriter(conn)
 
-	req := &protocol.Request{
-		Type:           "Wait",
-		ID:             sessionID,
-		Tags:           tags,
-		Condition:      condition,
-		TimeoutSeconds: &timeoutSecs,
-	}
 	if err := writer.SendRequest(req); err != nil {
 		return "", err
 	}
@@ -0,0 +1,118 @@
+package node
+
+import (
+	"bufio"
+	"fmt"
+	"log/slog"
+	"math/rand/v2"
+	"net"
+	"net/http"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/config"
+)
+
+// accessLog records one JSON line per request to the WebSocket listener —
+// when, from where, with which token, and how it ended — in a file of its
+// own, so operators can audit who connected without wading through the
+// node log.
+type accessLog struct {
+	logger *slog.Logger
+	sample float64 // fraction of successful requests logged
+}
+
+// newAccessLog opens the access log configured in cfg, or returns nil when
+// it is turned off.
+func newAccessLog(cfg config.NodeConfig, dataDir string) (*accessLog, error) {
+	path := cfg.AccessLog
+	switch path {
+	case "off":
+		return nil, nil
+	case "":
+		path = filepath.Join(dataDir, "access.log")
+	}
+	sample := 1.0
+	if cfg.AccessLogSample != nil {
+		sample = *cfg.AccessLogSample
+		if sample < 0 || sample > 1 {
+			return nil, fmt.Errorf("invalid node.access_log_sample %v: expected 0 to 1", sample)
+		}
+	}
+	f, err := os.OpenFile(path, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o600)
+	if err != nil {
+		return nil, fmt.Errorf("opening access log: %w", err)
+	}
+	return &accessLog{logger: slog.New(slog.NewJSONHandler(f, nil)), sample: sample}, nil
+}
+
+// wrap logs every request handled by next once it completes. For WebSocket
+// connections that is when the connection closes, so the duration is how
+// long the client stayed connected. Failed requests are always logged;
+// successful ones are sampled.
+func (a *accessLog) wrap(next http.Handler) http.Handler {
+	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		start := time.Now()
+		rec := &statusRecorder{ResponseWriter: w}
+		next.ServeHTTP(rec, r)
+
+		status := rec.status
+		if status == 0 {
+			status = http.StatusOK
+		}
+		if status < 400 && a.sample < 1 && rand.Float64() >= a.sample {
+			return
+		}
+		token := "-"
+		if h := r.Header.Get("Authorization"); strings.HasPrefix(h, "Bearer ") {
+			token = tokenLabel(strings.TrimPrefix(h, "Bearer "))
+		} else if r.URL.Query().Has("token") {
+			token = tokenLabel(r.URL.Query().Get("token"))
+		}
+		a.logger.Info("request",
+			"remote", r.RemoteAddr,
+			"method", r.Method,
+			"path", r.URL.Path,
+			"token", token,
+			"status", status,
+			"duration_ms", time.Since(start).Milliseconds(),
+			"user_agent", r.UserAgent(),
+		)
+	})
+}
+
+// statusRecorder remembers the status written through it. It passes
+// Hijack through so WebSocket upgrades still work; a hijacked connection
+// is recorded as 101 Switching Protocols.
+type statusRecorder struct {
+	http.ResponseWriter
+	status int
+}
+
+func (s *statusRecorder) WriteHeader(code int) {
+	if s.status == 0 {
+		s.status = code
+	}
+	s.ResponseWriter.WriteHeader(code)
+}
+
+func (s *statusRecorder) Write(p []byte) (int, error) {
+	if s.status == 0 {
+		s.status = http.StatusOK
+	}
+	return s.ResponseWriter.Write(p)
+}
+
+func (s *statusRecorder) Hijack() (net.Conn, *bufio.ReadWriter, error) {
+	h, ok := s.ResponseWriter.(http.Hijacker)
+	if !ok {
+		return nil, nil, fmt.Errorf("response writer does not support hijacking")
+	}
+	conn, rw, err := h.Hijack()
+	if err == nil && s.status == 0 {
+		s.status = http.StatusSwitchingProtocols
+	}
+	return conn, rw, err
+}
@@ -0,0 +1,56 @@
+package node
+
+import (
+	"encoding/json"
+	"net/http"
+	"net/http/httptest"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+
+	"github.com/codewiresh/codewire/internal/config"
+)
+
+func TestAccessLog(t *testing.T) {
+	dir := t.TempDir()
+	sample := 0.0
+	alog, err := newAccessLog(config.NodeConfig{AccessLogSample: &sample}, dir)
+	if err != nil {
+		t.Fatalf("newAccessLog: %v", err)
+	}
+	h := alog.wrap(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.Header.Get("Authorization") == "" {
+			http.Error(w, "unauthorized", http.StatusUnauthorized)
+		}
+	}))
+
+	ok := httptest.NewRequest("GET", "/ws", nil)
+	ok.Header.Set("Authorization", "Bearer secret")
+	h.ServeHTTP(httptest.NewRecorder(), ok)
+	h.ServeHTTP(httptest.NewRecorder(), httptest.NewRequest("GET", "/ws?token=secret", nil))
+
+	data, err := os.ReadFile(filepath.Join(dir, "access.log"))
+	if err != nil {
+		t.Fatalf("reading access log: %v", err)
+	}
+	lines := strings.Split(strings.TrimSpace(string(data)), "\n")
+	if len(lines) != 1 {
+		t.Fatalf("expected only the failed request with sample 0, got %d lines:\n%s", len(lines), data)
+	}
+	var entry map[string]any
+	if err := json.Unmarshal([]byte(lines[0]), &entry); err != nil {
+		t.Fatalf("parsing entry: %v", err)
+	}
+	if entry["status"] != float64(401) || entry["path"] != "/ws" || entry["token"] != tokenLabel("secret") {
+		t.Fatalf("unexpected entry: %v", entry)
+	}
+	if strings.Contains(string(data), "secret") {
+		t.Fatal("access log contains the raw token")
+	}
+
+	sample = 2
+	if _, err := newAccessLog(config.NodeConfig{AccessLogSample: &sample}, dir); err == nil {
+		t.Fatal("expected an error for access_log_sample = 2")
+	}
+}
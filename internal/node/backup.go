@@ -0,0 +1,83 @@
+package node
+
+import (
+	"bufio"
+	"io"
+	"os"
+
+	"github.com/codewiresh/codewire/internal/backup"
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+// backupChunk is the size of the data frames a backup is streamed in.
+const backupChunk = 256 << 10
+
+// handleBackup streams an archive of the node's state (see package backup)
+// as data frames, then BackupDone with the number of files. The archive
+// holds the auth token and node key, so only local clients on the Unix
+// socket may ask for it. Metadata writes are held off while it is written
+// to a temporary file, so sessions.json and the journal in it agree with
+// each other; logs are copied as far as they had been written. It is
+// streamed once writes resume, so a slow client cannot hold them off.
+func handleBackup(writer connection.FrameWriter, manager *session.SessionManager, access clientAccess, logs bool) {
+	fail := func(msg string) {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: msg})
+	}
+	if access.remote || access.policy != nil {
+		fail("backups hold the node's secrets and are only served on its Unix socket; run cw backup create on the node")
+		return
+	}
+
+	tmp, err := os.CreateTemp("", "codewire-backup-*.tar.gz")
+	if err != nil {
+		fail("backup failed: " + err.Error())
+		return
+	}
+	defer os.Remove(tmp.Name())
+	defer tmp.Close()
+
+	host, _ := os.Hostname()
+	dataDirVersion, _ := session.DataDirVersionOf(manager.DataDir())
+	var m backup.Manifest
+	err = manager.Quiesce(func() error {
+		var err error
+		m, err = backup.Create(tmp, manager.DataDir(), logs, backup.Manifest{
+			Node:           host,
+			Version:        Version,
+			DataDirVersion: dataDirVersion,
+		})
+		return err
+	})
+	if err != nil {
+		fail("backup failed: " + err.Error())
+		return
+	}
+
+	if _, err := tmp.Seek(0, io.SeekStart); err != nil {
+		fail("backup failed: " + err.Error())
+		return
+	}
+	out := bufio.NewWriterSize(dataFrameWriter{writer}, backupChunk)
+	if _, err := io.Copy(out, tmp); err != nil {
+		return // the client is gone
+	}
+	if err := out.Flush(); err != nil {
+		return
+	}
+	files := uint(len(m.Files))
+	_ = writer.SendResponse(&protocol.Response{Type: "BackupDone", Count: &files})
+}
+
+// dataFrameWriter sends everything written to it as data frames.
+type dataFrameWriter struct {
+	connection.FrameWriter
+}
+
+func (w dataFrameWriter) Write(p []byte) (int, error) {
+	if err := w.SendData(p); err != nil {
+		return 0, err
+	}
+	return len(p), nil
+}
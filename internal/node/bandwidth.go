@@ -0,0 +1,226 @@
+package node
+
+import (
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"sort"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+// bandwidthMeter accounts for bytes moved over remote (WebSocket) clients,
+// per connection and per auth token, and optionally caps how fast each token
+// may receive output.
+type bandwidthMeter struct {
+	mu     sync.Mutex
+	nextID uint64
+	conns  map[uint64]*meterCounters
+	tokens map[string]*meterCounters
+	limit  int64 // bytes/second sent per token; 0 means unlimited
+}
+
+// meterCounters holds the running totals for one connection or token.
+type meterCounters struct {
+	label       string
+	remoteAddr  string
+	connectedAt time.Time
+	sent        atomic.Uint64
+	received    atomic.Uint64
+	throttle    *throttle // tokens only
+}
+
+func newBandwidthMeter(limit int64) *bandwidthMeter {
+	return &bandwidthMeter{
+		conns:  make(map[uint64]*meterCounters),
+		tokens: make(map[string]*meterCounters),
+		limit:  limit,
+	}
+}
+
+// tokenLabel identifies a token in status output without revealing it.
+func tokenLabel(token string) string {
+	sum := sha256.Sum256([]byte(token))
+	return "token:" + hex.EncodeToString(sum[:4])
+}
+
+// wrap meters reader and writer for a new connection authenticated with
+// token. The returned done func must be called when the connection closes.
+func (b *bandwidthMeter) wrap(token, remoteAddr string, reader connection.FrameReader, writer connection.FrameWriter) (connection.FrameReader, connection.FrameWriter, func()) {
+	label := tokenLabel(token)
+
+	b.mu.Lock()
+	b.nextID++
+	id := b.nextID
+	conn := &meterCounters{label: label, remoteAddr: remoteAddr, connectedAt: time.Now()}
+	b.conns[id] = conn
+	tok, ok := b.tokens[label]
+	if !ok {
+		tok = &meterCounters{label: label, connectedAt: time.Now()}
+		if b.limit > 0 {
+			tok.throttle = &throttle{rate: b.limit}
+		}
+		b.tokens[label] = tok
+	}
+	b.mu.Unlock()
+
+	done := func() {
+		b.mu.Lock()
+		delete(b.conns, id)
+		b.mu.Unlock()
+	}
+	return &meteredReader{FrameReader: reader, conn: conn, token: tok},
+		&meteredWriter{FrameWriter: writer, conn: conn, token: tok},
+		done
+}
+
+// Stats returns totals for open connections followed by per-token totals
+// since the node started.
+func (b *bandwidthMeter) Stats() []protocol.BandwidthStat {
+	b.mu.Lock()
+	defer b.mu.Unlock()
+
+	ids := make([]uint64, 0, len(b.conns))
+	for id := range b.conns {
+		ids = append(ids, id)
+	}
+	sort.Slice(ids, func(i, j int) bool { return ids[i] < ids[j] })
+
+	stats := make([]protocol.BandwidthStat, 0, len(b.conns)+len(b.tokens))
+	for _, id := range ids {
+		c := b.conns[id]
+		stats = append(stats, protocol.BandwidthStat{
+			Kind:        "connection",
+			Token:       c.label,
+			RemoteAddr:  c.remoteAddr,
+			ConnectedAt: c.connectedAt.UTC().Format(time.RFC3339),
+			BytesSent:   c.sent.Load(),
+			BytesRecv:   c.received.Load(),
+		})
+	}
+
+	labels := make([]string, 0, len(b.tokens))
+	for label := range b.tokens {
+		labels = append(labels, label)
+	}
+	sort.Strings(labels)
+	for _, label := range labels {
+		t := b.tokens[label]
+		stats = append(stats, protocol.BandwidthStat{
+			Kind:        "token",
+			Token:       label,
+			ConnectedAt: t.connectedAt.UTC().Format(time.RFC3339),
+			BytesSent:   t.sent.Load(),
+			BytesRecv:   t.received.Load(),
+			LimitBps:    b.limit,
+		})
+	}
+	return stats
+}
+
+// meteredReader counts bytes received from a client.
+type meteredReader struct {
+	connection.FrameReader
+	conn, token *meterCounters
+}
+
+func (r *meteredReader) ReadFrame() (*protocol.Frame, error) {
+	f, err := r.FrameReader.ReadFrame()
+	if f != nil {
+		n := uint64(len(f.Payload))
+		r.conn.received.Add(n)
+		r.token.received.Add(n)
+	}
+	return f, err
+}
+
+// meteredWriter counts bytes sent to a client and applies the token's cap.
+// The Send* helpers are reimplemented so every frame passes through
+// WriteFrame here rather than the wrapped writer's own.
+type meteredWriter struct {
+	connection.FrameWriter
+	conn, token *meterCounters
+	// priority is the class of the session whose output the connection
+	// carries (see prioritize); nil counts frames at face value.
+	priority func() string
+}
+
+func (w *meteredWriter) WriteFrame(f *protocol.Frame) error {
+	if w.token.throttle != nil {
+		n := len(f.Payload)
+		if w.priority != nil {
+			switch w.priority() {
+			case session.PriorityHigh:
+				n /= 2
+			case session.PriorityBackground:
+				n *= 2
+			}
+		}
+		w.token.throttle.wait(n)
+	}
+	if err := w.FrameWriter.WriteFrame(f); err != nil {
+		return err
+	}
+	n := uint64(len(f.Payload))
+	w.conn.sent.Add(n)
+	w.token.sent.Add(n)
+	return nil
+}
+
+func (w *meteredWriter) SendResponse(resp *protocol.Response) error {
+	data, err := json.Marshal(resp)
+	if err != nil {
+		return err
+	}
+	return w.WriteFrame(&protocol.Frame{Type: protocol.FrameControl, Payload: data})
+}
+
+func (w *meteredWriter) SendRequest(req *protocol.Request) error {
+	data, err := json.Marshal(req)
+	if err != nil {
+		return err
+	}
+	return w.WriteFrame(&protocol.Frame{Type: protocol.FrameControl, Payload: data})
+}
+
+func (w *meteredWriter) SendData(data []byte) error {
+	return w.WriteFrame(&protocol.Frame{Type: protocol.FrameData, Payload: data})
+}
+
+// prioritize applies session id's priority class to the token cap on a
+// remote client's output: high-priority output counts half against the
+// rate, background output double. Local clients are not capped.
+func prioritize(writer connection.FrameWriter, manager *session.SessionManager, id uint32) {
+	if w, ok := writer.(*meteredWriter); ok {
+		w.priority = func() string { return manager.Priority(id) }
+	}
+}
+
+// throttle paces writes to rate bytes/second, shared by every connection
+// using the same token.
+type throttle struct {
+	mu   sync.Mutex
+	rate int64
+	next time.Time // when the budget already handed out is used up
+}
+
+// wait blocks until n more bytes fit within the rate.
+func (t *throttle) wait(n int) {
+	t.mu.Lock()
+	now := time.Now()
+	if t.next.Before(now) {
+		t.next = now
+	}
+	delay := t.next.Sub(now)
+	t.next = t.next.Add(time.Duration(int64(n) * int64(time.Second) / t.rate))
+	t.mu.Unlock()
+
+	if delay > 0 {
+		time.Sleep(delay)
+	}
+}
@@ -0,0 +1,62 @@
+package node
+
+import (
+	"testing"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// nopConn is a FrameReader and FrameWriter that discards writes and returns
+// one fixed frame per read.
+type nopConn struct{ frame protocol.Frame }
+
+func (c *nopConn) ReadFrame() (*protocol.Frame, error) { f := c.frame; return &f, nil }
+func (c *nopConn) WriteFrame(*protocol.Frame) error { return nil }
+func (c *nopConn) SendResponse(*protocol.Response) error { return nil }
+func (c *nopConn) SendRequest(*protocol.Request) error { return nil }
+func (c *nopConn) SendData([]byte) error { return nil }
+func (c *nopConn) Close() error { return nil }
+
+func TestBandwidthMeterCountsPerConnectionAndToken(t *testing.T) {
+	b := newBandwidthMeter(0)
+	conn := &nopConn{frame: protocol.Frame{Type: protocol.FrameData, Payload: []byte("hello")}}
+
+	r1, w1, done1 := b.wrap("secret", "10.0.0.1:5000", conn, conn)
+	r2, w2, _ := b.wrap("secret", "10.0.0.2:5000", conn, conn)
+
+	_, _ = r1.ReadFrame()
+	_ = w1.SendData(make([]byte, 100))
+	_, _ = r2.ReadFrame()
+	_ = w2.SendData(make([]byte, 50))
+
+	stats := b.Stats()
+	if len(stats) != 3 {
+		t.Fatalf("expected 2 connections + 1 token, got %d: %+v", len(stats), stats)
+	}
+	if stats[0].BytesSent != 100 || stats[0].BytesRecv != 5 {
+		t.Errorf("conn 1: %+v", stats[0])
+	}
+	tok := stats[2]
+	if tok.Kind != "token" || tok.BytesSent != 150 || tok.BytesRecv != 10 {
+		t.Errorf("token totals: %+v", tok)
+	}
+	if tok.Token == "secret" || tok.Token != tokenLabel("secret") {
+		t.Errorf("token label should be a hash, got %q", tok.Token)
+	}
+
+	done1()
+	if stats = b.Stats(); len(stats) != 2 || stats[1].BytesSent != 150 {
+		t.Errorf("closed connection should drop but token totals remain: %+v", stats)
+	}
+}
+
+func TestThrottlePacesWrites(t *testing.T) {
+	th := &throttle{rate: 10000} // 10 KB/s
+	start := time.Now()
+	th.wait(5000) // first write goes immediately
+	th.wait(1000) // waits for the first 5000 bytes: ~500ms
+	if elapsed := time.Since(start); elapsed < 400*time.Millisecond {
+		t.Errorf("throttle did not pace writes: %s", elapsed)
+	}
+}
@@ -0,0 +1,24 @@
+package node
+
+import (
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// requestDeadline returns when the requester stops waiting for req, given
+// when the node received it, or the zero time when it sent no timeout.
+func requestDeadline(req *protocol.Request, received time.Time) time.Time {
+	if req.TimeoutMs == nil {
+		return time.Time{}
+	}
+	return received.Add(time.Duration(*req.TimeoutMs) * time.Millisecond)
+}
+
+// capTimeout shortens timeout so it ends at deadline, if that is sooner.
+func capTimeout(timeout time.Duration, deadline time.Time) time.Duration {
+	if deadline.IsZero() {
+		return timeout
+	}
+	return max(min(timeout, time.Until(deadline)), 0)
+}
@@ -0,0 +1,68 @@
+package node
+
+import (
+	"fmt"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+// handleQueryEvents answers QueryEvents from the node's event store.
+func handleQueryEvents(writer connection.FrameWriter, manager *session.SessionManager, req protocol.Request) {
+	sendError := func(msg string) {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: msg})
+	}
+	store := manager.Events()
+	if store == nil {
+		sendError("this node does not keep an event store")
+		return
+	}
+
+	filter := session.EventFilter{
+		SessionID: req.ID,
+		Tags:      req.Tags,
+		After:     req.After,
+		Limit:     int(req.Limit),
+	}
+	for _, et := range req.EventTypes {
+		filter.Types = append(filter.Types, session.EventType(et))
+	}
+	for _, bound := range []struct {
+		name  string
+		value string
+		dst   *time.Time
+	}{
+		{"since", req.Since, &filter.Since},
+		{"until", req.Until, &filter.Until},
+	} {
+		if bound.value == "" {
+			continue
+		}
+		t, err := time.Parse(time.RFC3339, bound.value)
+		if err != nil {
+			sendError(fmt.Sprintf("invalid %s %q: expected an RFC 3339 time", bound.name, bound.value))
+			return
+		}
+		*bound.dst = t
+	}
+
+	records, err := store.Query(filter)
+	if err != nil {
+		sendError(err.Error())
+		return
+	}
+	events := make([]protocol.SessionEvent, 0, len(records))
+	for _, rec := range records {
+		events = append(events, protocol.SessionEvent{
+			Timestamp: rec.Event.Timestamp.Format(time.RFC3339Nano),
+			EventType: string(rec.Event.Type),
+			Data:      rec.Event.Data,
+			Seq:       rec.Seq,
+			SessionID: rec.SessionID,
+			Tags:      rec.Tags,
+		})
+	}
+	_ = writer.SendResponse(&protocol.Response{Type: "Events", Events: events})
+}
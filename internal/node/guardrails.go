@@ -0,0 +1,309 @@
+package node
+
+import (
+	"cmp"
+	"fmt"
+	"log/slog"
+	"os"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+
+	"golang.org/x/sys/unix"
+
+	"github.com/codewiresh/codewire/internal/config"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+// Guardrail names, as reported in protocol.GuardrailBreach.
+const (
+	guardMaxSessions    = "max_sessions"
+	guardMinFreeDisk    = "min_free_disk_mb"
+	guardMaxLoadAverage = "max_load_average"
+)
+
+// defaultQueueTimeout bounds how long a queued launch waits for the node to
+// come back within its guardrails.
+const defaultQueueTimeout = 10 * time.Minute
+
+// guardrailPollInterval is how often a queued launch re-checks the node.
+const guardrailPollInterval = 2 * time.Second
+
+// guardrails protect the host from too many or too heavy sessions. They are
+// checked before every launch; the zero value checks nothing.
+type guardrails struct {
+	maxSessions    int
+	minFreeDiskMB  uint64
+	maxLoadAverage float64
+	queue          bool
+	queueTimeout   time.Duration
+	preempt        bool
+	dataDir        string
+
+	// queued counts the launches waiting in the queue per priority rank
+	// (see session.PriorityRank); a launch is only admitted while none of a
+	// higher class is waiting.
+	mu     sync.Mutex
+	queued [3]int
+	// reserved counts admitted launches that have not started yet. They
+	// count towards max_sessions, so concurrent launches cannot overshoot it.
+	reserved int
+	// admitMu makes checking the guardrails and reserving a slot atomic.
+	admitMu sync.Mutex
+
+	// loadAverage and freeDiskMB are replaced in tests.
+	loadAverage func() (float64, error)
+	freeDiskMB  func(dir string) (uint64, error)
+}
+
+func newGuardrails(cfg config.GuardrailsConfig, dataDir string) (*guardrails, error) {
+	g := &guardrails{
+		maxSessions:    cfg.MaxSessions,
+		minFreeDiskMB:  cfg.MinFreeDiskMB,
+		maxLoadAverage: cfg.MaxLoadAverage,
+		queueTimeout:   defaultQueueTimeout,
+		preempt:        cfg.Preempt,
+		dataDir:        dataDir,
+		loadAverage:    loadAverage,
+		freeDiskMB:     freeDiskMB,
+	}
+	switch cfg.OnBreach {
+	case "", "reject":
+	case "queue":
+		g.queue = true
+	default:
+		return nil, fmt.Errorf("invalid guardrails.on_breach %q: expected reject or queue", cfg.OnBreach)
+	}
+	if cfg.QueueTimeout != "" {
+		d, err := parseLimit("guardrails.queue_timeout", cfg.QueueTimeout)
+		if err != nil {
+			return nil, err
+		}
+		g.queueTimeout = d
+	}
+	if cfg.MaxSessions < 0 || cfg.MaxLoadAverage < 0 {
+		return nil, fmt.Errorf("guardrails limits must not be negative")
+	}
+	return g, nil
+}
+
+// guardrailError is a launch refused by a guardrail. The handler sends the
+// breach alongside the error message.
+type guardrailError struct {
+	breach protocol.GuardrailBreach
+	queued time.Duration // how long the launch waited, if it was queued
+}
+
+func (e *guardrailError) Error() string {
+	msg := fmt.Sprintf("node guardrail %s breached: %s", e.breach.Guardrail, e.breach.Detail)
+	if e.queued > 0 {
+		msg += fmt.Sprintf(" (still breached after queueing for %s)", e.queued.Round(time.Second))
+	}
+	return msg
+}
+
+// check returns the first breached guardrail, or nil.
+func (g *guardrails) check(manager *session.SessionManager) *guardrailError {
+	if gerr := g.checkSessions(manager); gerr != nil {
+		return gerr
+	}
+	return g.checkHost()
+}
+
+// checkSessions checks max_sessions, counting reserved slots.
+func (g *guardrails) checkSessions(manager *session.SessionManager) *guardrailError {
+	if g.maxSessions > 0 {
+		g.mu.Lock()
+		n := len(manager.ListRunning(nil)) + g.reserved
+		g.mu.Unlock()
+		if n >= g.maxSessions {
+			return &guardrailError{breach: protocol.GuardrailBreach{
+				Guardrail: guardMaxSessions,
+				Limit:     float64(g.maxSessions),
+				Value:     float64(n),
+				Detail:    fmt.Sprintf("%d sessions running (limit %d)", n, g.maxSessions),
+			}}
+		}
+	}
+	return nil
+}
+
+// checkHost checks the guardrails on the host's resources, which killing a
+// session does not reliably bring back within limits.
+func (g *guardrails) checkHost() *guardrailError {
+	if g.minFreeDiskMB > 0 {
+		free, err := g.freeDiskMB(g.dataDir)
+		if err != nil {
+			slog.Warn("guardrails: checking free disk", "err", err)
+		} else if free < g.minFreeDiskMB {
+			return &guardrailError{breach: protocol.GuardrailBreach{
+				Guardrail: guardMinFreeDisk,
+				Limit:     float64(g.minFreeDiskMB),
+				Value:     float64(free),
+				Detail:    fmt.Sprintf("%d MB free under %s (minimum %d MB)", free, g.dataDir, g.minFreeDiskMB),
+			}}
+		}
+	}
+	if g.maxLoadAverage > 0 {
+		load, err := g.loadAverage()
+		if err != nil {
+			slog.Warn("guardrails: reading load average", "err", err)
+		} else if load > g.maxLoadAverage {
+			return &guardrailError{breach: protocol.GuardrailBreach{
+				Guardrail: guardMaxLoadAverage,
+				Limit:     g.maxLoadAverage,
+				Value:     load,
+				Detail:    fmt.Sprintf("1-minute load average %.2f (limit %.2f)", load, g.maxLoadAverage),
+			}}
+		}
+	}
+	return nil
+}
+
+// checkFor is check for a launch of the given priority class. With preempt
+// set, a max_sessions breach kills the newest running session of the lowest
+// class below it, and the guardrails are checked again. Only a full node is
+// cleared by preemption, and only while the host guardrails hold; each
+// launch preempts at most one session, tracked by preempted.
+func (g *guardrails) checkFor(manager *session.SessionManager, priority string, preempted *bool) *guardrailError {
+	gerr := g.check(manager)
+	if gerr == nil || !g.preempt || *preempted || gerr.breach.Guardrail != guardMaxSessions || g.checkHost() != nil {
+		return gerr
+	}
+	priority = cmp.Or(priority, session.PriorityNormal)
+	reason := fmt.Sprintf("preempted for a %s-priority launch (guardrail %s: %s)", priority, gerr.breach.Guardrail, gerr.breach.Detail)
+	id, ok := manager.Preempt(priority, reason)
+	if !ok {
+		return gerr
+	}
+	*preempted = true
+	slog.Info("session preempted by guardrail", "id", id, "guardrail", gerr.breach.Guardrail, "launch_priority", priority)
+	return g.check(manager)
+}
+
+// higherQueued reports whether a launch of a class above rank is waiting.
+func (g *guardrails) higherQueued(rank int) bool {
+	g.mu.Lock()
+	defer g.mu.Unlock()
+	for r := rank + 1; r < len(g.queued); r++ {
+		if g.queued[r] > 0 {
+			return true
+		}
+	}
+	return false
+}
+
+// tryAdmit checks the guardrails for a launch of the given class and, when
+// they hold and no higher class is waiting, reserves a session slot for it.
+func (g *guardrails) tryAdmit(manager *session.SessionManager, priority string, rank int, preempted *bool) (*guardrailError, bool) {
+	g.admitMu.Lock()
+	defer g.admitMu.Unlock()
+	gerr := g.checkFor(manager, priority, preempted)
+	if gerr != nil || g.higherQueued(rank) {
+		return gerr, false
+	}
+	g.reserve()
+	return nil, true
+}
+
+// reserve holds a session slot for an admitted launch until release.
+func (g *guardrails) reserve() {
+	g.mu.Lock()
+	g.reserved++
+	g.mu.Unlock()
+}
+
+// release frees the slot reserve took. The launch calls it once it has
+// finished, whether the session started (and now counts as running) or not.
+func (g *guardrails) release() {
+	if g == nil {
+		return
+	}
+	g.mu.Lock()
+	g.reserved--
+	g.mu.Unlock()
+}
+
+// admit decides whether a launch of the given priority class may proceed.
+// With on_breach = "queue" it waits for the node to come back within its
+// guardrails, up to the queue timeout, and for queued launches of higher
+// classes to go first; otherwise a breach is returned immediately. An
+// admitted launch holds a reserved slot that the caller frees with release.
+func (g *guardrails) admit(manager *session.SessionManager, priority string) error {
+	if g == nil {
+		return nil
+	}
+	rank := session.PriorityRank(priority)
+	var preempted bool
+	gerr, ok := g.tryAdmit(manager, priority, rank, &preempted)
+	if ok {
+		return nil
+	}
+	if !g.queue {
+		return gerr
+	}
+
+	if gerr != nil {
+		slog.Info("launch queued by guardrail", "guardrail", gerr.breach.Guardrail, "detail", gerr.breach.Detail, "priority", priority)
+	} else {
+		slog.Info("launch queued behind higher-priority launches", "priority", priority)
+	}
+	g.mu.Lock()
+	g.queued[rank]++
+	g.mu.Unlock()
+	defer func() {
+		g.mu.Lock()
+		g.queued[rank]--
+		g.mu.Unlock()
+	}()
+
+	start := time.Now()
+	timeout := time.NewTimer(g.queueTimeout)
+	defer timeout.Stop()
+	ticker := time.NewTicker(guardrailPollInterval)
+	defer ticker.Stop()
+	for {
+		select {
+		case <-timeout.C:
+			if gerr == nil {
+				g.reserve()
+				return nil
+			}
+			gerr.queued = time.Since(start)
+			return gerr
+		case <-ticker.C:
+		}
+		next, ok := g.tryAdmit(manager, priority, rank, &preempted)
+		if ok {
+			return nil
+		}
+		if next != nil {
+			gerr = next
+		}
+	}
+}
+
+// loadAverage reads the 1-minute load average from /proc/loadavg.
+func loadAverage() (float64, error) {
+	data, err := os.ReadFile("/proc/loadavg")
+	if err != nil {
+		return 0, err
+	}
+	fields := strings.Fields(string(data))
+	if len(fields) == 0 {
+		return 0, fmt.Errorf("empty /proc/loadavg")
+	}
+	return strconv.ParseFloat(fields[0], 64)
+}
+
+// freeDiskMB returns the space available to unprivileged users on the
+// filesystem holding dir.
+func freeDiskMB(dir string) (uint64, error) {
+	var st unix.Statfs_t
+	if err := unix.Statfs(dir, &st); err != nil {
+		return 0, err
+	}
+	return st.Bavail * uint64(st.Bsize) / (1024 * 1024), nil
+}
@@ -0,0 +1,168 @@
+package node
+
+import (
+	"errors"
+	"strings"
+	"testing"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/config"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+func testGuardrails(t *testing.T, cfg config.GuardrailsConfig) (*guardrails, *session.SessionManager) {
+	t.Helper()
+	dir := t.TempDir()
+	g, err := newGuardrails(cfg, dir)
+	if err != nil {
+		t.Fatalf("newGuardrails: %v", err)
+	}
+	mgr, err := session.NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	return g, mgr
+}
+
+func TestGuardrailsRejectBreaches(t *testing.T) {
+	g, mgr := testGuardrails(t, config.GuardrailsConfig{MinFreeDiskMB: 1024, MaxLoadAverage: 4})
+	g.freeDiskMB = func(string) (uint64, error) { return 4096, nil }
+	g.loadAverage = func() (float64, error) { return 1.5, nil }
+	if err := g.admit(mgr, ""); err != nil {
+		t.Fatalf("expected launch admitted, got %v", err)
+	}
+
+	g.loadAverage = func() (float64, error) { return 6.25, nil }
+	err := g.admit(mgr, "")
+	var gerr *guardrailError
+	if !errors.As(err, &gerr) {
+		t.Fatalf("expected guardrailError, got %v", err)
+	}
+	if gerr.breach.Guardrail != guardMaxLoadAverage || gerr.breach.Value != 6.25 || gerr.breach.Limit != 4 {
+		t.Fatalf("unexpected breach: %+v", gerr.breach)
+	}
+
+	g.freeDiskMB = func(string) (uint64, error) { return 100, nil }
+	if err := g.admit(mgr, ""); !errors.As(err, &gerr) || gerr.breach.Guardrail != guardMinFreeDisk {
+		t.Fatalf("expected min_free_disk_mb breach first, got %v", err)
+	}
+}
+
+func TestGuardrailsIgnoreUnreadableMetrics(t *testing.T) {
+	g, mgr := testGuardrails(t, config.GuardrailsConfig{MinFreeDiskMB: 1024, MaxLoadAverage: 4})
+	g.freeDiskMB = func(string) (uint64, error) { return 0, errors.New("statfs failed") }
+	g.loadAverage = func() (float64, error) { return 0, errors.New("no /proc") }
+	if err := g.admit(mgr, ""); err != nil {
+		t.Fatalf("unreadable metrics should not block launches, got %v", err)
+	}
+}
+
+func TestGuardrailsQueueUntilClear(t *testing.T) {
+	g, mgr := testGuardrails(t, config.GuardrailsConfig{MaxLoadAverage: 4, OnBreach: "queue", QueueTimeout: "10s"})
+	calls := 0
+	g.loadAverage = func() (float64, error) {
+		calls++
+		if calls < 2 {
+			return 9, nil
+		}
+		return 1, nil
+	}
+	if err := g.admit(mgr, ""); err != nil {
+		t.Fatalf("queued launch should be admitted once load drops, got %v", err)
+	}
+
+	g.queueTimeout = 10 * time.Millisecond
+	g.loadAverage = func() (float64, error) { return 9, nil }
+	err := g.admit(mgr, "")
+	if err == nil || !strings.Contains(err.Error(), "after queueing") {
+		t.Fatalf("expected queue timeout, got %v", err)
+	}
+}
+
+func TestGuardrailsReserveSessionSlots(t *testing.T) {
+	g, mgr := testGuardrails(t, config.GuardrailsConfig{MaxSessions: 1})
+	if err := g.admit(mgr, ""); err != nil {
+		t.Fatalf("first launch should be admitted, got %v", err)
+	}
+	// The first launch has not started yet, but its slot is taken.
+	var gerr *guardrailError
+	if err := g.admit(mgr, ""); !errors.As(err, &gerr) || gerr.breach.Guardrail != guardMaxSessions {
+		t.Fatalf("expected max_sessions breach while a launch holds the slot, got %v", err)
+	}
+	// A failed launch gives its slot back.
+	g.release()
+	if err := g.admit(mgr, ""); err != nil {
+		t.Fatalf("launch should be admitted after the slot is released, got %v", err)
+	}
+}
+
+func TestGuardrailsQueueByPriority(t *testing.T) {
+	g, mgr := testGuardrails(t, config.GuardrailsConfig{OnBreach: "queue", QueueTimeout: "10ms"})
+	g.queued[session.PriorityRank(session.PriorityHigh)]++
+	if !g.higherQueued(session.PriorityRank(session.PriorityNormal)) {
+		t.Fatal("normal launch should see the queued high-priority launch")
+	}
+	if g.higherQueued(session.PriorityRank(session.PriorityHigh)) {
+		t.Fatal("high launch should not wait on its own class")
+	}
+	if err := g.admit(mgr, session.PriorityHigh); err != nil {
+		t.Fatalf("high-priority launch should be admitted, got %v", err)
+	}
+
+	// A lower class waits behind the high launch until the queue timeout,
+	// then proceeds since no guardrail was breached.
+	start := time.Now()
+	if err := g.admit(mgr, session.PriorityBackground); err != nil {
+		t.Fatalf("background launch should be admitted after waiting, got %v", err)
+	}
+	if time.Since(start) < 10*time.Millisecond {
+		t.Fatal("background launch should have queued behind the high-priority launch")
+	}
+}
+
+func TestGuardrailsConfigValidation(t *testing.T) {
+	for _, cfg := range []config.GuardrailsConfig{
+		{OnBreach: "drop"},
+		{QueueTimeout: "soon"},
+		{MaxSessions: -1},
+	} {
+		if _, err := newGuardrails(cfg, t.TempDir()); err == nil {
+			t.Fatalf("expected error for %+v", cfg)
+		}
+	}
+}
+
+func TestGuardrailsPreemptOnlyForMaxSessions(t *testing.T) {
+	g, mgr := testGuardrails(t, config.GuardrailsConfig{MaxSessions: 2, MaxLoadAverage: 4, Preempt: true})
+	for i := 0; i < 2; i++ {
+		if _, err := mgr.LaunchWithOptions([]string{"sleep", "30"}, "/tmp", nil, nil, "", session.LaunchOptions{Priority: session.PriorityBackground}); err != nil {
+			t.Fatalf("Launch: %v", err)
+		}
+	}
+	t.Cleanup(func() { mgr.KillAll() })
+
+	// Killing a session does not lower the load, so nothing is preempted.
+	g.loadAverage = func() (float64, error) { return 9, nil }
+	if err := g.admit(mgr, session.PriorityHigh); err == nil {
+		t.Fatal("expected the launch refused while the node is full and loaded")
+	}
+	if n := len(mgr.ListRunning(nil)); n != 2 {
+		t.Fatalf("expected no session preempted for a load breach, %d running", n)
+	}
+
+	g.loadAverage = func() (float64, error) { return 1, nil }
+	var preempted bool
+	g.checkFor(mgr, session.PriorityHigh, &preempted)
+	g.checkFor(mgr, session.PriorityHigh, &preempted)
+	if !preempted {
+		t.Fatal("expected a background session preempted for max_sessions")
+	}
+	deadline := time.Now().Add(5 * time.Second)
+	for len(mgr.ListRunning(nil)) > 1 && time.Now().Before(deadline) {
+		time.Sleep(10 * time.Millisecond)
+	}
+	time.Sleep(100 * time.Millisecond)
+	if n := len(mgr.ListRunning(nil)); n != 1 {
+		t.Fatalf("expected exactly one session preempted by one launch, %d running", n)
+	}
+}
@@ -2,21 +2,26 @@ package node
 
 import (
 	"encoding/json"
+	"errors"
 	"fmt"
 	"log/slog"
 	"os"
 	"strings"
 	"time"
 
+	"github.com/codewiresh/codewire/internal/ansi"
+	"github.com/codewiresh/codewire/internal/charset"
 	"github.com/codewiresh/codewire/internal/connection"
 	"github.com/codewiresh/codewire/internal/protocol"
 	"github.com/codewiresh/codewire/internal/session"
+	"github.com/codewiresh/codewire/internal/standby"
 )
 
 // handleClient reads the first control frame from a client, dispatches the
 // request by type, and returns. Each Unix/WebSocket connection is handled
-// by exactly one goroutine calling this function.
-func handleClient(reader connection.FrameReader, writer connection.FrameWriter, manager *session.SessionManager, kvStore *session.KVStore) {
+// by exactly one goroutine calling this function. access says whether the
+// client is remote and which scoped token, if any, it authenticated with.
+func handleClient(reader connection.FrameReader, writer connection.FrameWriter, manager *session.SessionManager, kvStore *session.KVStore, up *upgrader, meter *bandwidthMeter, attachIdle time.Duration, guard *guardrails, ident *nodeIdentity, relayStatus func() *protocol.RelayStatus, replica *standby.Store, access clientAccess) {
 	defer reader.Close()
 	defer writer.Close()
 
@@ -28,6 +33,7 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 	if f == nil {
 		return // clean disconnect
 	}
+	received := time.Now()
 	if f.Type != protocol.FrameControl {
 		slog.Error("expected control frame, got data frame")
 		return
@@ -39,36 +45,90 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 		return
 	}
 
+	// Work the requester has already given up on is not started: its
+	// answer would arrive after the client stopped listening.
+	deadline := requestDeadline(&req, received)
+	if !deadline.IsZero() && !time.Now().Before(deadline) {
+		slog.Warn("dropping request past its deadline", "type", req.Type, "timeout_ms", *req.TimeoutMs)
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: "deadline exceeded before the node handled the request"})
+		return
+	}
+
+	if err := access.allows(manager, &req); err != nil {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: err.Error()})
+		return
+	}
+
+	if req.Replica {
+		handleReplica(writer, replica, &req)
+		return
+	}
+
 	switch req.Type {
 	case "ListSessions":
-		sessions := manager.List()
+		sessions := access.visible(manager.List())
+		_ = writer.SendResponse(&protocol.Response{
+			Type:     "SessionList",
+			Sessions: &sessions,
+		})
+
+	case "FindSessions":
+		sessions := access.visible(manager.Find(req.Query, int(req.Limit)))
 		_ = writer.SendResponse(&protocol.Response{
 			Type:     "SessionList",
 			Sessions: &sessions,
 		})
 
 	case "Launch":
-		id, launchErr := manager.Launch(req.Command, req.WorkingDir, req.Env, req.StdinData, req.Name, req.Tags...)
+		id, launchErr := launchSession(manager, up, guard, access, &req, deadline)
 		if launchErr != nil {
-			msg := launchErr.Error()
+			resp := &protocol.Response{
+				Type:    "Error",
+				Message: launchErr.Error(),
+			}
+			var gerr *guardrailError
+			if errors.As(launchErr, &gerr) {
+				resp.Guardrail = &gerr.breach
+			}
+			_ = writer.SendResponse(resp)
+			return
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type: "Launched",
+			ID:   &id,
+		})
+
+	case "LaunchBatch":
+		if len(req.Launches) == 0 {
 			_ = writer.SendResponse(&protocol.Response{
 				Type:    "Error",
-				Message: msg,
+				Message: "batch contains no launches",
 			})
 			return
 		}
-		if req.Name != "" {
-			if nameErr := manager.SetName(id, req.Name); nameErr != nil {
-				_ = writer.SendResponse(&protocol.Response{
-					Type:    "Error",
-					Message: nameErr.Error(),
-				})
-				return
+		results := make([]protocol.BatchResult, len(req.Launches))
+		for i := range req.Launches {
+			results[i].Name = req.Launches[i].Name
+			id, launchErr := launchSession(manager, up, guard, access, &req.Launches[i], deadline)
+			if launchErr != nil {
+				results[i].Error = launchErr.Error()
+				continue
 			}
+			results[i].ID = &id
 		}
 		_ = writer.SendResponse(&protocol.Response{
-			Type: "Launched",
-			ID:   &id,
+			Type:    "BatchLaunched",
+			Results: &results,
+		})
+
+	case "ValidateLaunch":
+		launches := req.Launches
+		if len(launches) == 0 {
+			launches = []protocol.Request{req}
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type:  "LaunchValidated",
+			Plans: validateLaunches(manager, up, guard, access, launches),
 		})
 
 	case "Attach":
@@ -89,30 +149,42 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 			})
 			return
 		}
-		defer manager.Detach(sessionID)
+		defer manager.Detach(sessionID, channels.ClientID)
+		prioritize(writer, manager, sessionID)
 
 		// Unsubscribe the output broadcast when we are done.
 		defer manager.UnsubscribeOutput(sessionID, channels.OutputID)
 
-		// Send Attached confirmation.
-		_ = writer.SendResponse(&protocol.Response{
+		// Send Attached confirmation, with the session's notes and status
+		// reason for the client to show above the replay.
+		attached := &protocol.Response{
 			Type: "Attached",
 			ID:   &sessionID,
-		})
+		}
+		if info, _, infoErr := manager.GetStatus(sessionID); infoErr == nil {
+			attached.Info = &info
+		}
+		_ = writer.SendResponse(attached)
+
+		// Reduce colors for terminals that cannot show them all.
+		out := writer
+		if depth, depthErr := ansi.ParseColorDepth(req.Colors); depthErr == nil && depth != ansi.ColorsTrue {
+			out = &colorWriter{FrameWriter: writer, conv: ansi.NewDownconverter(depth)}
+		}
 
 		// Replay history if requested.
 		includeHistory := req.IncludeHistory == nil || *req.IncludeHistory
 		if includeHistory {
 			logPath, logErr := manager.LogPath(sessionID)
 			if logErr == nil {
-				if histErr := replayHistory(writer, logPath, req.HistoryLines); histErr != nil {
+				if histErr := replayHistory(out, logPath, req.HistoryLines); histErr != nil {
 					slog.Warn("failed to replay history", "id", sessionID, "err", histErr)
 				}
 			}
 		}
 
 		// Bridge PTY and client until detach or disconnect.
-		if bridgeErr := handleAttachSession(reader, writer, channels, sessionID, manager); bridgeErr != nil {
+		if bridgeErr := handleAttachSession(reader, out, channels, sessionID, manager, attachIdle); bridgeErr != nil {
 			slog.Debug("attach session ended", "id", sessionID, "err", bridgeErr)
 		}
 
@@ -124,6 +196,10 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 			})
 			return
 		}
+		if req.Signal != "" {
+			handleSignal(writer, manager, &req)
+			return
+		}
 		if killErr := manager.Kill(*req.ID); killErr != nil {
 			_ = writer.SendResponse(&protocol.Response{
 				Type:    "Error",
@@ -136,7 +212,185 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 			ID:   req.ID,
 		})
 
+	case "AddNote":
+		if req.ID == nil || len(req.Notes) == 0 {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: "missing session id or note",
+			})
+			return
+		}
+		for _, text := range req.Notes {
+			if noteErr := manager.AddNote(*req.ID, text); noteErr != nil {
+				_ = writer.SendResponse(&protocol.Response{
+					Type:    "Error",
+					Message: noteErr.Error(),
+				})
+				return
+			}
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type: "NoteAdded",
+			ID:   req.ID,
+		})
+
+	case "AddBookmark":
+		if req.ID == nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: "missing session id",
+			})
+			return
+		}
+		bookmark, bookmarkErr := manager.AddBookmark(*req.ID, req.Bookmark)
+		if bookmarkErr != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: bookmarkErr.Error(),
+			})
+			return
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type: "BookmarkAdded",
+			ID:   req.ID,
+			Bookmark: &protocol.SessionBookmark{
+				Name:   bookmark.Name,
+				Time:   bookmark.Time.Format(time.RFC3339),
+				Offset: bookmark.Offset,
+			},
+		})
+
+	case "AddMirror":
+		if req.ID == nil || len(req.Mirrors) == 0 {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: "missing session id or mirror",
+			})
+			return
+		}
+		if mirrorErr := addMirrors(manager, access, *req.ID, req.Mirrors); mirrorErr != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: mirrorErr.Error(),
+			})
+			return
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type: "MirrorAdded",
+			ID:   req.ID,
+		})
+
+	case "Extend":
+		if req.ID == nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: "missing session id",
+			})
+			return
+		}
+		extra, extErr := parseLimit("extension", req.Extra)
+		var e session.Expiry
+		if extErr == nil && extra == 0 {
+			extErr = fmt.Errorf("missing extension")
+		}
+		if extErr == nil {
+			e, extErr = manager.Extend(*req.ID, extra)
+		}
+		if extErr != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: extErr.Error(),
+			})
+			return
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type:        "Extended",
+			ID:          req.ID,
+			ExpiresAt:   e.At.UTC().Format(time.RFC3339),
+			ExpiryLimit: e.Limit,
+		})
+
+	case "SetPriority":
+		// A higher class can preempt other sessions, so it is not left to
+		// scoped tokens.
+		if access.policy != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: fmt.Sprintf("token %q may not change session priorities", access.policy.Name),
+			})
+			return
+		}
+		if req.ID == nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: "missing session id",
+			})
+			return
+		}
+		if prioErr := manager.SetPriority(*req.ID, req.Priority); prioErr != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: prioErr.Error(),
+			})
+			return
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type: "PrioritySet",
+			ID:   req.ID,
+		})
+
+	case "ExportHistory":
+		handleExportHistory(writer, manager, &req)
+
+	case "Backup":
+		handleBackup(writer, manager, access, req.Transcripts)
+
+	case "Replicate":
+		handleReplicate(writer, manager, req.Offsets)
+
+	case "ImportHistory":
+		// Imported records become sessions on this node, so they are held to
+		// the same bar as a launch: no scoped tokens, and not while draining.
+		if access.policy != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: fmt.Sprintf("token %q may not import history", access.policy.Name),
+			})
+			return
+		}
+		if up.Draining() {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: "node is draining for an upgrade; not accepting new sessions",
+			})
+			return
+		}
+		if req.Record == nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: "missing history record",
+			})
+			return
+		}
+		id, importErr := manager.ImportHistory(*req.Record)
+		if importErr != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: importErr.Error(),
+			})
+			return
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type: "HistoryImported",
+			ID:   &id,
+		})
+
 	case "KillAll":
+		if req.DryRun {
+			matches := manager.ListRunning(nil)
+			_ = writer.SendResponse(&protocol.Response{Type: "KillMatches", Sessions: &matches})
+			return
+		}
 		count := manager.KillAll()
 		c := uint(count)
 		_ = writer.SendResponse(&protocol.Response{
@@ -145,6 +399,11 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 		})
 
 	case "KillByTags":
+		if req.DryRun {
+			matches := manager.ListRunning(req.Tags)
+			_ = writer.SendResponse(&protocol.Response{Type: "KillMatches", Sessions: &matches})
+			return
+		}
 		count := manager.KillByTags(req.Tags)
 		c := uint(count)
 		_ = writer.SendResponse(&protocol.Response{
@@ -170,6 +429,11 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 			})
 			return
 		}
+		if req.Inputs {
+			handleInputLog(writer, manager, *req.ID, req.Tail)
+			return
+		}
+		prioritize(writer, manager, *req.ID)
 		logPath, logErr := manager.LogPath(*req.ID)
 		if logErr != nil {
 			_ = writer.SendResponse(&protocol.Response{
@@ -178,9 +442,56 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 			})
 			return
 		}
+		idxPath, _ := manager.CheckpointPath(*req.ID)
+		if req.Verify {
+			li, verifyErr := session.VerifyLog(logPath, idxPath)
+			if verifyErr != nil {
+				_ = writer.SendResponse(&protocol.Response{
+					Type:    "Error",
+					Message: fmt.Sprintf("verifying log: %v", verifyErr),
+				})
+				return
+			}
+			status := "ok"
+			if li.CorruptAt >= 0 {
+				status = "corrupted"
+			} else if li.Truncated {
+				status = "truncated"
+			}
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "LogIntegrity",
+				Status:  status,
+				Message: li.String(),
+			})
+			return
+		}
+		// Cheap truncation check so readers notice damaged logs.
+		var warning string
+		if li, checkErr := session.CheckTruncation(logPath, idxPath); checkErr == nil && !li.OK() {
+			warning = li.String()
+		}
 		follow := req.Follow != nil && *req.Follow
+		from, to := int64(0), int64(-1)
+		if req.FromBookmark != "" || req.ToBookmark != "" {
+			var rangeErr error
+			switch {
+			case req.Tail != nil:
+				rangeErr = fmt.Errorf("tail cannot be combined with bookmarks")
+			case follow && req.ToBookmark != "":
+				rangeErr = fmt.Errorf("logs ending at a bookmark cannot be followed")
+			default:
+				from, to, rangeErr = manager.BookmarkRange(*req.ID, req.FromBookmark, req.ToBookmark)
+			}
+			if rangeErr != nil {
+				_ = writer.SendResponse(&protocol.Response{
+					Type:    "Error",
+					Message: rangeErr.Error(),
+				})
+				return
+			}
+		}
 		strip := req.StripANSI == nil || *req.StripANSI // default: strip
-		if logsErr := handleLogs(writer, logPath, follow, req.Tail, strip); logsErr != nil {
+		if logsErr := handleLogs(writer, logPath, manager.Encoding(*req.ID), follow, req.Tail, from, to, strip, warning); logsErr != nil {
 			slog.Debug("logs handler ended", "id", *req.ID, "err", logsErr)
 		}
 
@@ -222,11 +533,23 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 			})
 			return
 		}
-		_ = writer.SendResponse(&protocol.Response{
+		resp := &protocol.Response{
 			Type:       "SessionStatus",
 			Info:       &info,
 			OutputSize: &outputSize,
-		})
+		}
+		if req.Snapshot {
+			snapshot, snapErr := manager.Snapshot(*req.ID)
+			if snapErr != nil {
+				_ = writer.SendResponse(&protocol.Response{
+					Type:    "Error",
+					Message: snapErr.Error(),
+				})
+				return
+			}
+			resp.Snapshot = &snapshot
+		}
+		_ = writer.SendResponse(resp)
 
 	case "WatchSession":
 		if req.ID == nil {
@@ -237,7 +560,8 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 			return
 		}
 		includeHistory := req.IncludeHistory == nil || *req.IncludeHistory
-		if watchErr := handleWatchSession(reader, writer, manager, *req.ID, includeHistory, req.HistoryLines); watchErr != nil {
+		prioritize(writer, manager, *req.ID)
+		if watchErr := handleWatchSession(reader, writer, manager, *req.ID, includeHistory, req.HistoryLines, req.ResumeFrom, req.MaxRate); watchErr != nil {
 			slog.Debug("watch session ended", "id", *req.ID, "err", watchErr)
 		}
 
@@ -300,7 +624,10 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 		}
 
 	case "Wait":
-		handleWait(reader, writer, manager, req)
+		handleWait(reader, writer, manager, req, deadline)
+
+	case "QueryEvents":
+		handleQueryEvents(writer, manager, req)
 
 	case "MsgSend":
 		handleMsgSend(writer, manager, req)
@@ -309,7 +636,7 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 		handleMsgRead(writer, manager, req)
 
 	case "MsgRequest":
-		handleMsgRequest(reader, writer, manager, req)
+		handleMsgRequest(reader, writer, manager, req, deadline)
 
 	case "MsgReply":
 		handleMsgReply(writer, manager, req)
@@ -329,6 +656,38 @@ func handleClient(reader connection.FrameReader, writer connection.FrameWriter,
 	case "KVList":
 		handleKVList(writer, kvStore, req)
 
+	case "Version":
+		_ = writer.SendResponse(&protocol.Response{
+			Type:    "Version",
+			Version: Version,
+		})
+
+	case "NodeStatus":
+		stats := meter.Stats()
+		_ = writer.SendResponse(&protocol.Response{
+			Type:      "NodeStatus",
+			Version:   Version,
+			Bandwidth: &stats,
+			Relay:     relayStatus(),
+			Standby:   replica.Status(),
+		})
+
+	case "Upgrade":
+		if access.policy != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: fmt.Sprintf("token %q may not upgrade the node", access.policy.Name),
+			})
+			return
+		}
+		up.handleUpgrade(&req, writer, manager)
+
+	case "Ping":
+		handlePing(reader, writer, req)
+
+	case "Identify":
+		ident.handleIdentify(&req, writer)
+
 	default:
 		_ = writer.SendResponse(&protocol.Response{
 			Type:    "Error",
@@ -351,6 +710,7 @@ func handleAttachSession(
 	channels *session.AttachChannels,
 	sessionID uint32,
 	manager *session.SessionManager,
+	idleTimeout time.Duration,
 ) error {
 	// Spawn a goroutine to read frames from the client, since ReadFrame blocks.
 	frameCh := make(chan frameOrError, 1)
@@ -364,8 +724,98 @@ func handleAttachSession(
 		}
 	}()
 
+	// With an idle timeout, warn the client shortly before detaching it.
+	// Input and resizes reset the clock; output alone does not.
+	var idleCh <-chan time.Time
+	var idleTimer *time.Timer
+	warned := false
+	if idleTimeout > 0 {
+		idleTimer = time.NewTimer(idleTimeout - idleWarningLead(idleTimeout))
+		defer idleTimer.Stop()
+		idleCh = idleTimer.C
+	}
+	touch := func() {
+		if idleTimer == nil {
+			return
+		}
+		if !idleTimer.Stop() {
+			select {
+			case <-idleTimer.C:
+			default:
+			}
+		}
+		idleTimer.Reset(idleTimeout - idleWarningLead(idleTimeout))
+		warned = false
+	}
+
+	// Push the session's next limit so the client can count down to it, and
+	// warn once when it comes within the node's expiry warning period.
+	expiryTicker := time.NewTicker(time.Second)
+	defer expiryTicker.Stop()
+	var sentExpiry session.Expiry
+	expiryWarned := false
+
+	// Tell the client how many clients are attached when others already
+	// are, and again whenever another one attaches or detaches, so nobody
+	// types into a session unaware that others are watching.
+	clientsCh := channels.Clients.Changed()
+	sendClients := func() {
+		n := uint(channels.Clients.Load())
+		_ = writer.SendResponse(&protocol.Response{Type: "Clients", ID: &sessionID, Count: &n})
+	}
+	if channels.Clients.Load() > 1 {
+		sendClients()
+	}
+
 	for {
 		select {
+		case <-clientsCh:
+			clientsCh = channels.Clients.Changed()
+			sendClients()
+
+		case <-expiryTicker.C:
+			e, ok := manager.Expiry(sessionID)
+			if !ok {
+				continue
+			}
+			if e.Limit != sentExpiry.Limit || e.At.Sub(sentExpiry.At).Abs() >= time.Second {
+				sentExpiry = e
+				_ = writer.SendResponse(&protocol.Response{
+					Type:        "Expiry",
+					ID:          &sessionID,
+					ExpiresAt:   e.At.UTC().Format(time.RFC3339),
+					ExpiryLimit: e.Limit,
+				})
+			}
+			if e.Warning && !expiryWarned {
+				_ = writer.SendResponse(&protocol.Response{
+					Type:    "ExpiryWarning",
+					ID:      &sessionID,
+					Message: expiryWarningMessage(sessionID, e),
+				})
+			}
+			expiryWarned = e.Warning
+
+		case <-idleCh:
+			if !warned {
+				lead := idleWarningLead(idleTimeout)
+				_ = writer.SendResponse(&protocol.Response{
+					Type:    "IdleWarning",
+					ID:      &sessionID,
+					Message: fmt.Sprintf("no input for %s; detaching in %s unless you type or resize", idleTimeout-lead, lead),
+				})
+				warned = true
+				idleTimer.Reset(lead)
+				continue
+			}
+			slog.Info("detaching idle client", "id", sessionID, "idle", idleTimeout)
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Detached",
+				ID:      &sessionID,
+				Message: fmt.Sprintf("detached after %s without input", idleTimeout),
+			})
+			return nil
+
 		case data := <-channels.OutputCh:
 			// PTY output to client.
 			if err := writer.SendData(data); err != nil {
@@ -383,6 +833,7 @@ func handleAttachSession(
 
 			if fe.frame.Type == protocol.FrameData {
 				// Client sending PTY input.
+				touch()
 				select {
 				case channels.InputCh <- fe.frame.Payload:
 				default:
@@ -407,12 +858,17 @@ func handleAttachSession(
 				return nil
 
 			case "Resize":
+				touch()
 				if req.Cols != nil && req.Rows != nil {
 					if err := manager.Resize(sessionID, *req.Cols, *req.Rows); err != nil {
 						slog.Error("resize failed", "id", sessionID, "err", err)
 					}
 				}
 
+			case "Ping":
+				// Latency probes do not count as activity for the idle timeout.
+				_ = writer.SendResponse(&protocol.Response{Type: "Pong", Seq: req.Seq})
+
 			default:
 				slog.Warn("unexpected control frame during attach", "type", req.Type)
 			}
@@ -421,38 +877,281 @@ func handleAttachSession(
 			status := channels.Status.Get()
 			if status.State != "running" {
 				msg := fmt.Sprintf("session %s", status.String())
-				_ = writer.SendResponse(&protocol.Response{
+				resp := &protocol.Response{
 					Type:    "Error",
 					Message: msg,
-				})
+					Status:  status.State,
+				}
+				if status.State == "completed" {
+					code := status.ExitCode
+					resp.ExitCode = &code
+				}
+				_ = writer.SendResponse(resp)
 				return nil
 			}
 		}
 	}
 }
 
-// replayHistory reads the session log file and sends its contents as a data
-// frame. If historyLines is non-nil, only the last N lines are sent.
-func replayHistory(writer connection.FrameWriter, logPath string, historyLines *uint) error {
-	content, err := os.ReadFile(logPath)
+// parseLimit parses a Launch limit; empty means the node default.
+func parseLimit(name, value string) (time.Duration, error) {
+	if value == "" {
+		return 0, nil
+	}
+	d, err := time.ParseDuration(value)
+	if err != nil || d <= 0 {
+		return 0, fmt.Errorf("invalid %s %q: expected a positive duration like 30m or 2h", name, value)
+	}
+	return d, nil
+}
+
+// expiryWarningMessage tells attached clients that a limit is close.
+func expiryWarningMessage(id uint32, e session.Expiry) string {
+	remaining := time.Until(e.At).Round(time.Second)
+	if e.Limit == session.LimitIdle {
+		return fmt.Sprintf("session idle; it will be killed in %s unless there is input or output (extend with: cw extend %d 1h)", remaining, id)
+	}
+	return fmt.Sprintf("session reaches its max runtime in %s and will be killed (extend with: cw extend %d 1h)", remaining, id)
+}
+
+// handleSignal serves a Kill request carrying a Signal.
+func handleSignal(writer connection.FrameWriter, manager *session.SessionManager, req *protocol.Request) {
+	sig, err := session.ParseSignal(req.Signal)
+	if err == nil {
+		err = manager.Signal(*req.ID, sig)
+	}
 	if err != nil {
-		if os.IsNotExist(err) {
-			return nil // no history yet
+		_ = writer.SendResponse(&protocol.Response{
+			Type:    "Error",
+			Message: err.Error(),
+		})
+		return
+	}
+	_ = writer.SendResponse(&protocol.Response{
+		Type:    "Signaled",
+		ID:      req.ID,
+		Message: sig.String(),
+	})
+}
+
+// handlePing answers a Ping and keeps answering further Pings on the same
+// connection until the client disconnects, so round trips can be measured
+// without the cost of a new connection per sample.
+func handlePing(reader connection.FrameReader, writer connection.FrameWriter, req protocol.Request) {
+	for {
+		if err := writer.SendResponse(&protocol.Response{Type: "Pong", Seq: req.Seq}); err != nil {
+			return
+		}
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			return
+		}
+		if f.Type != protocol.FrameControl {
+			continue
+		}
+		req = protocol.Request{}
+		if err := json.Unmarshal(f.Payload, &req); err != nil || req.Type != "Ping" {
+			return
 		}
-		return fmt.Errorf("reading log file: %w", err)
 	}
+}
 
-	if len(content) == 0 {
-		return nil
+// launchSession starts the session described by a Launch request and
+// applies its name. It is shared by Launch and LaunchBatch.
+func launchSession(manager *session.SessionManager, up *upgrader, guard *guardrails, access clientAccess, req *protocol.Request, deadline time.Time) (uint32, error) {
+	if up.Draining() {
+		return 0, fmt.Errorf("node is draining for an upgrade; not accepting new sessions")
+	}
+	if _, err := session.ParsePriority(req.Priority); err != nil {
+		return 0, err
+	}
+	if access.policy != nil && session.PriorityRank(req.Priority) > session.PriorityRank(session.PriorityNormal) {
+		return 0, fmt.Errorf("token %q may not launch %s-priority sessions", access.policy.Name, req.Priority)
+	}
+	opts, err := launchOptions(req, access)
+	if err != nil {
+		return 0, err
+	}
+	opts.Deadline = deadline
+	// A scoped token's launch is checked before it can queue or preempt
+	// anything; LaunchWithOptions checks it again.
+	if access.policy != nil {
+		if _, err := manager.PlanLaunch(req.Command, req.WorkingDir, req.Env, req.Name, opts); err != nil {
+			return 0, err
+		}
+	}
+	if err := guard.admit(manager, req.Priority); err != nil {
+		return 0, err
+	}
+	id, err := manager.LaunchWithOptions(req.Command, req.WorkingDir, req.Env, req.StdinData, req.Name, opts, req.Tags...)
+	guard.release()
+	if err != nil {
+		return 0, err
+	}
+	if req.Name != "" {
+		if err := manager.SetName(id, req.Name); err != nil {
+			return 0, err
+		}
+	}
+	return id, nil
+}
+
+// addMirrors adds mirror sinks to a running session, each checked against
+// the client's token first.
+func addMirrors(manager *session.SessionManager, access clientAccess, id uint32, specs []string) error {
+	for _, spec := range specs {
+		if err := access.policy.AllowsMirror(spec); err != nil {
+			return err
+		}
+		if err := manager.AddMirror(id, spec); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// launchOptions converts the per-session settings of a Launch request made
+// by a client with access.
+func launchOptions(req *protocol.Request, access clientAccess) (session.LaunchOptions, error) {
+	opts := session.LaunchOptions{
+		Script:          req.Script,
+		Workspace:       req.Workspace || req.WorkspaceSource != "",
+		WorkspaceSource: req.WorkspaceSource,
+		Notes:           req.Notes,
+		Term:            req.Term,
+		Encoding:        req.Encoding,
+		Mirrors:         req.Mirrors,
+		History:         req.History,
+		RecordInput:     req.RecordInput,
+		Priority:        req.Priority,
+		Policy:          access.policy,
+		Remote:          access.remote,
+	}
+	if req.Cols != nil && req.Rows != nil {
+		opts.Cols, opts.Rows = *req.Cols, *req.Rows
+	}
+	var err error
+	if opts.MaxRuntime, err = parseLimit("max_runtime", req.MaxRuntime); err != nil {
+		return opts, err
+	}
+	if opts.IdleTimeout, err = parseLimit("idle_timeout", req.IdleTimeout); err != nil {
+		return opts, err
+	}
+	if req.Snapshot || req.SnapshotCommand != "" {
+		opts.SnapshotCommand = req.SnapshotCommand
+		if opts.SnapshotCommand == "" {
+			opts.SnapshotCommand = session.DefaultSnapshotCommand
+		}
+	}
+	return opts, nil
+}
+
+// idleWarningLead is how long before an idle detach the client is warned:
+// five minutes, or a tenth of the timeout when that is shorter.
+func idleWarningLead(timeout time.Duration) time.Duration {
+	lead := 5 * time.Minute
+	if timeout/10 < lead {
+		lead = timeout / 10
+	}
+	return lead
+}
+
+// handleInputLog sends a session's recorded PTY input as a single InputLog
+// response.
+func handleInputLog(writer connection.FrameWriter, manager *session.SessionManager, id uint32, tail *uint) {
+	n := 0
+	if tail != nil {
+		n = int(*tail)
+	}
+	events, err := manager.ReadInputs(id, n)
+	if err != nil {
+		_ = writer.SendResponse(&protocol.Response{
+			Type:    "Error",
+			Message: err.Error(),
+		})
+		return
+	}
+	records := make([]protocol.InputRecord, 0, len(events))
+	for _, e := range events {
+		var d session.InputData
+		if json.Unmarshal(e.Data, &d) != nil {
+			continue
+		}
+		records = append(records, protocol.InputRecord{
+			Timestamp: e.Timestamp.Format(time.RFC3339Nano),
+			Data:      d.Data,
+		})
+	}
+	_ = writer.SendResponse(&protocol.Response{
+		Type:   "InputLog",
+		Inputs: &records,
+	})
+}
+
+// handleExportHistory streams one HistoryRecord response per finished
+// session, then a HistoryEnd with the number of records sent.
+func handleExportHistory(writer connection.FrameWriter, manager *session.SessionManager, req *protocol.Request) {
+	var since time.Time
+	if req.Since != "" {
+		t, err := time.Parse(time.RFC3339, req.Since)
+		if err != nil {
+			_ = writer.SendResponse(&protocol.Response{
+				Type:    "Error",
+				Message: fmt.Sprintf("invalid since %q: expected an RFC 3339 time", req.Since),
+			})
+			return
+		}
+		since = t
+	}
+	var count uint
+	err := manager.ExportHistory(since, req.Transcripts, req.FromBookmark, req.ToBookmark, func(rec protocol.HistoryRecord) error {
+		count++
+		return writer.SendResponse(&protocol.Response{Type: "HistoryRecord", Record: &rec})
+	})
+	if err != nil {
+		_ = writer.SendResponse(&protocol.Response{
+			Type:    "Error",
+			Message: err.Error(),
+		})
+		return
 	}
+	_ = writer.SendResponse(&protocol.Response{Type: "HistoryEnd", Count: &count})
+}
 
+// colorWriter downconverts the colors in output sent to an attached client
+// whose terminal reported fewer colors (see Request.Colors).
+type colorWriter struct {
+	connection.FrameWriter
+	conv *ansi.Downconverter
+}
+
+func (w *colorWriter) SendData(data []byte) error {
+	data = w.conv.Convert(data)
+	if len(data) == 0 {
+		return nil // only part of an escape sequence so far
+	}
+	return w.FrameWriter.SendData(data)
+}
+
+// replayHistory reads the session log file and sends its contents as a data
+// frame. If historyLines is non-nil, only the last N lines are sent.
+func replayHistory(writer connection.FrameWriter, logPath string, historyLines *uint) error {
+	// With a line limit only the end of the log is read, so attaching to a
+	// long-running session with a short replay stays cheap.
+	var (
+		content []byte
+		err     error
+	)
 	if historyLines != nil {
-		lines := strings.Split(string(content), "\n")
-		n := int(*historyLines)
-		if n < len(lines) {
-			lines = lines[len(lines)-n:]
+		content, _, err = readLogTail(logPath, int(*historyLines))
+	} else {
+		content, err = os.ReadFile(logPath)
+	}
+	if err != nil {
+		if os.IsNotExist(err) {
+			return nil // no history yet
 		}
-		content = []byte(strings.Join(lines, "\n"))
+		return fmt.Errorf("reading log file: %w", err)
 	}
 
 	if len(content) > 0 {
@@ -461,8 +1160,19 @@ func replayHistory(writer connection.FrameWriter, logPath string, historyLines *
 	return nil
 }
 
+// defaultWatchRate is how many WatchUpdates per second a watcher gets at
+// most when it does not ask for a rate; output in between is coalesced.
+const defaultWatchRate = 20
+
+// maxWatchDelta flushes coalesced output early once this much is pending.
+const maxWatchDelta = 256 << 10
+
 // handleWatchSession subscribes to a session's output and status, streaming
 // updates to the client until the session ends or the client disconnects.
+// Output is coalesced into at most maxRate updates per second (default
+// defaultWatchRate), and every update carries the next sequence number and
+// the output cursor. With resumeFrom set, the output after that cursor is
+// replayed from the log in place of the history.
 func handleWatchSession(
 	reader connection.FrameReader,
 	writer connection.FrameWriter,
@@ -470,8 +1180,10 @@ func handleWatchSession(
 	id uint32,
 	includeHistory bool,
 	historyLines *uint,
+	resumeFrom *uint64,
+	maxRate uint32,
 ) error {
-	subID, outputCh, err := manager.SubscribeOutput(id)
+	subID, outputCh, offset, err := manager.SubscribeOutput(id)
 	if err != nil {
 		return writer.SendResponse(&protocol.Response{
 			Type:    "Error",
@@ -488,33 +1200,37 @@ func handleWatchSession(
 		})
 	}
 
-	// Send history if requested.
-	if includeHistory {
-		logPath, logErr := manager.LogPath(id)
-		if logErr == nil {
-			content, readErr := os.ReadFile(logPath)
-			if readErr == nil && len(content) > 0 {
-				data := content
-				if historyLines != nil {
-					lines := strings.Split(string(data), "\n")
-					n := int(*historyLines)
-					if n < len(lines) {
-						lines = lines[len(lines)-n:]
-					}
-					data = []byte(strings.Join(lines, "\n"))
-				}
-				if len(data) > 0 {
-					output := string(data)
-					f := false
-					_ = writer.SendResponse(&protocol.Response{
-						Type:   "WatchUpdate",
-						Status: "running",
-						Output: &output,
-						Done:   &f,
-					})
-				}
-			}
+	backlog, start, missed := watchBacklog(manager, id, offset, includeHistory, historyLines, resumeFrom)
+	var seq uint64
+	cursor := start
+	// Output is decoded from the session's encoding; the cursor only moves
+	// past the bytes decoded, so a character held back for its remaining
+	// bytes is replayed by a resumed watch.
+	dec := charset.NewDecoder(manager.Encoding(id))
+	send := func(status string, raw []byte, done bool) error {
+		seq++
+		resp := &protocol.Response{
+			Type:   "WatchUpdate",
+			Status: status,
+			Done:   &done,
+			Missed: missed,
+		}
+		if raw != nil {
+			text, n, lossy := dec.Decode(raw)
+			cursor += uint64(n)
+			output := string(text)
+			resp.Output, resp.Lossy = &output, lossy
 		}
+		resp.Seq, resp.Cursor = seq, cursor
+		missed = 0
+		return writer.SendResponse(resp)
+	}
+
+	if len(backlog) > 0 || missed > 0 {
+		if backlog == nil {
+			backlog = []byte{}
+		}
+		_ = send("running", backlog, false)
 	}
 
 	// Spawn a goroutine to detect client disconnect.
@@ -533,29 +1249,72 @@ func handleWatchSession(
 		}
 	}()
 
+	if maxRate == 0 {
+		maxRate = defaultWatchRate
+	}
+	interval := time.Second / time.Duration(maxRate)
+	var pending []byte
+	var flushC <-chan time.Time
+	flush := func() error {
+		flushC = nil
+		if len(pending) == 0 {
+			return nil
+		}
+		err := send("running", pending, false)
+		pending = pending[:0]
+		return err
+	}
+
+	next := offset // output offset of the next live byte
 	for {
 		select {
-		case data := <-outputCh:
-			output := string(data)
-			f := false
-			if sendErr := writer.SendResponse(&protocol.Response{
-				Type:   "WatchUpdate",
-				Status: "running",
-				Output: &output,
-				Done:   &f,
-			}); sendErr != nil {
+		case chunk := <-outputCh:
+			// Output dropped while this watch lagged behind is read back
+			// from the log. Failing that, it is skipped and reported as
+			// missed, and the cursor moves to the node's offset.
+			for next < chunk.Offset {
+				to := min(next+maxWatchDelta, chunk.Offset)
+				gap, err := readLogRange(manager, id, next, to)
+				if err != nil {
+					if sendErr := flush(); sendErr != nil {
+						return sendErr
+					}
+					missed += chunk.Offset - next
+					cursor = chunk.Offset
+					dec = charset.NewDecoder(manager.Encoding(id))
+					break
+				}
+				pending = append(pending, gap...)
+				next = to
+				if len(pending) >= maxWatchDelta {
+					if sendErr := flush(); sendErr != nil {
+						return sendErr
+					}
+				}
+			}
+			next = chunk.Offset + uint64(len(chunk.Data))
+			if len(pending) == 0 {
+				flushC = time.After(interval)
+			}
+			pending = append(pending, chunk.Data...)
+			if len(pending) >= maxWatchDelta {
+				if sendErr := flush(); sendErr != nil {
+					return sendErr
+				}
+			}
+
+		case <-flushC:
+			if sendErr := flush(); sendErr != nil {
 				return sendErr
 			}
 
 		case <-statusWatcher.Changed():
+			if sendErr := flush(); sendErr != nil {
+				return sendErr
+			}
 			s := statusWatcher.Get()
 			done := s.State != "running"
-			_ = writer.SendResponse(&protocol.Response{
-				Type:   "WatchUpdate",
-				Status: s.String(),
-				Output: nil,
-				Done:   &done,
-			})
+			_ = send(s.String(), nil, done)
 			if done {
 				return nil
 			}
@@ -566,12 +1325,73 @@ func handleWatchSession(
 	}
 }
 
+// watchBacklog returns the output a watch sends before live output, which
+// starts at offset, and the cursor the backlog starts at: the output after
+// resumeFrom when resuming, else the history (the last historyLines lines,
+// or all of it). When resumed output can no longer be read from the log,
+// missed counts the bytes skipped.
+func watchBacklog(manager *session.SessionManager, id uint32, offset uint64, includeHistory bool, historyLines *uint, resumeFrom *uint64) (backlog []byte, start, missed uint64) {
+	if resumeFrom != nil {
+		from := min(*resumeFrom, offset)
+		if from == offset {
+			return nil, offset, 0
+		}
+		data, err := readLogRange(manager, id, from, offset)
+		if err != nil {
+			return nil, offset, offset - from
+		}
+		return data, from, 0
+	}
+	if !includeHistory {
+		return nil, offset, 0
+	}
+	if historyLines == nil {
+		data, err := readLogRange(manager, id, 0, offset)
+		if err != nil {
+			return nil, offset, 0
+		}
+		return data, 0, 0
+	}
+	logPath, err := manager.LogPath(id)
+	if err != nil {
+		return nil, offset, 0
+	}
+	data, size, err := readLogTail(logPath, int(*historyLines))
+	if err != nil {
+		return nil, offset, 0
+	}
+	// Output written after offset arrives live; drop it here.
+	if extra := max(uint64(size), offset) - offset; extra > 0 {
+		data = data[:uint64(len(data))-min(uint64(len(data)), extra)]
+	}
+	return data, offset - uint64(len(data)), 0
+}
+
+// readLogRange reads bytes [from, to) of a session's output log.
+func readLogRange(manager *session.SessionManager, id uint32, from, to uint64) ([]byte, error) {
+	logPath, err := manager.LogPath(id)
+	if err != nil {
+		return nil, err
+	}
+	f, err := os.Open(logPath)
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+	data := make([]byte, to-from)
+	if _, err := f.ReadAt(data, int64(from)); err != nil {
+		return nil, err
+	}
+	return data, nil
+}
+
 // handleWait blocks until the target session(s) complete or timeout.
 func handleWait(
 	reader connection.FrameReader,
 	writer connection.FrameWriter,
 	manager *session.SessionManager,
 	req protocol.Request,
+	deadline time.Time,
 ) {
 	var timeout time.Duration
 	if req.TimeoutSeconds != nil && *req.TimeoutSeconds > 0 {
@@ -579,6 +1399,7 @@ func handleWait(
 	} else {
 		timeout = 24 * time.Hour // default: very long
 	}
+	timeout = capTimeout(timeout, deadline)
 
 	timer := time.NewTimer(timeout)
 	defer timer.Stop()
@@ -681,11 +1502,28 @@ func handleWait(
 
 // handleLogs reads a session's log file and sends it to the client. If follow
 // is true, it polls for new data every 500ms until the connection is closed.
-func handleLogs(writer connection.FrameWriter, logPath string, follow bool, tail *uint, strip bool) error {
-	content, err := os.ReadFile(logPath)
+// A non-empty warning is attached to the first response.
+func handleLogs(writer connection.FrameWriter, logPath, encoding string, follow bool, tail *uint, from, to int64, strip bool, warning string) error {
+	// With a tail, read backwards from the end instead of loading the
+	// whole log; offset is where follow mode resumes either way. from and
+	// to (-1 for the end) slice the log between bookmarks.
+	var (
+		content []byte
+		offset  int64
+		err     error
+	)
+	if tail != nil {
+		content, offset, err = readLogTail(logPath, int(*tail))
+	} else if from > 0 || to >= 0 {
+		content, err = readLogFileRange(logPath, from, to)
+		offset = from + int64(len(content))
+	} else {
+		content, err = os.ReadFile(logPath)
+		offset = int64(len(content))
+	}
 	if err != nil {
 		if os.IsNotExist(err) {
-			content = nil
+			content, offset = nil, 0
 		} else {
 			return writer.SendResponse(&protocol.Response{
 				Type:    "Error",
@@ -694,26 +1532,28 @@ func handleLogs(writer connection.FrameWriter, logPath string, follow bool, tail
 		}
 	}
 
-	data := string(content)
-	if strip {
-		data = stripANSI(data)
-	}
-
-	// Apply tail.
-	if tail != nil && len(content) > 0 {
-		lines := strings.Split(data, "\n")
-		n := int(*tail)
-		if n < len(lines) {
-			lines = lines[len(lines)-n:]
+	// One decoder and stripper for the whole stream so characters and
+	// escape sequences split across follow-mode reads are still handled.
+	dec := charset.NewDecoder(encoding)
+	var stripper ansi.Stripper
+	text, _, lossy := dec.Decode(content)
+	if !follow {
+		if rest, _, restLossy := dec.Flush(); rest != nil {
+			text, lossy = append(text, rest...), lossy || restLossy
 		}
-		data = strings.Join(lines, "\n")
+	}
+	data := string(text)
+	if strip {
+		data = string(stripper.Strip(text))
 	}
 
 	done := !follow
 	if sendErr := writer.SendResponse(&protocol.Response{
-		Type: "LogData",
-		Data: data,
-		Done: &done,
+		Type:    "LogData",
+		Data:    data,
+		Done:    &done,
+		Warning: warning,
+		Lossy:   lossy,
 	}); sendErr != nil {
 		return sendErr
 	}
@@ -723,7 +1563,6 @@ func handleLogs(writer connection.FrameWriter, logPath string, follow bool, tail
 	}
 
 	// Follow mode: poll for new data.
-	offset := int64(len(content))
 	ticker := time.NewTicker(500 * time.Millisecond)
 	defer ticker.Stop()
 
@@ -755,15 +1594,17 @@ func handleLogs(writer connection.FrameWriter, logPath string, follow bool, tail
 		}
 
 		offset += int64(n)
-		chunk := string(buf[:n])
+		text, _, lossy := dec.Decode(buf[:n])
+		chunk := string(text)
 		if strip {
-			chunk = stripANSI(chunk)
+			chunk = string(stripper.Strip(text))
 		}
 		notDone := false
 		if sendErr := writer.SendResponse(&protocol.Response{
-			Type: "LogData",
-			Data: chunk,
-			Done: &notDone,
+			Type:  "LogData",
+			Data:  chunk,
+			Done:  &notDone,
+			Lossy: lossy,
 		}); sendErr != nil {
 			return sendErr
 		}
@@ -938,6 +1779,7 @@ func handleMsgRequest(
 	writer connection.FrameWriter,
 	manager *session.SessionManager,
 	req protocol.Request,
+	deadline time.Time,
 ) {
 	toID, err := resolveRecipient(manager, req.ToID, req.ToName)
 	if err != nil {
@@ -974,7 +1816,7 @@ func handleMsgRequest(
 	if req.TimeoutSeconds != nil && *req.TimeoutSeconds > 0 {
 		timeoutSecs = int(*req.TimeoutSeconds)
 	}
-	timer := time.NewTimer(time.Duration(timeoutSecs) * time.Second)
+	timer := time.NewTimer(capTimeout(time.Duration(timeoutSecs)*time.Second, deadline))
 	defer timer.Stop()
 
 	// Also detect client disconnect.
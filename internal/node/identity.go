@@ -0,0 +1,127 @@
+package node
+
+import (
+	"crypto/ecdh"
+	"crypto/ed25519"
+	"crypto/rand"
+	"encoding/json"
+	"fmt"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/auth"
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+// handshakeTimeout bounds how long a WebSocket client that connected
+// without a token may take to identify the node and authenticate.
+const handshakeTimeout = 10 * time.Second
+
+// nodeIdentity is the node's name and identity key, used to answer Identify
+// requests so clients can tell the node from an impostor at the same URL.
+type nodeIdentity struct {
+	name string
+	key  ed25519.PrivateKey
+}
+
+// handleIdentify signs the client's nonce with the node key.
+func (id *nodeIdentity) handleIdentify(req *protocol.Request, writer connection.FrameWriter) {
+	resp, _, err := id.answer(req)
+	if err != nil {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: err.Error()})
+		return
+	}
+	_ = writer.SendResponse(resp)
+}
+
+// answer signs an Identify request. With a key share the node adds a share
+// of its own and signs the handshake; it then also returns the key the
+// client seals its token with.
+func (id *nodeIdentity) answer(req *protocol.Request) (*protocol.Response, []byte, error) {
+	if len(req.Nonce) < 16 || len(req.Nonce) > 64 {
+		return nil, nil, fmt.Errorf("identify needs a nonce of 16 to 64 bytes")
+	}
+	resp := &protocol.Response{
+		Type:      "Identity",
+		NodeName:  id.name,
+		PublicKey: id.key.Public().(ed25519.PublicKey),
+	}
+	if req.KeyShare == nil {
+		resp.Signature = ed25519.Sign(id.key, auth.IdentityMessage(id.name, req.Nonce))
+		return resp, nil, nil
+	}
+	clientShare, err := ecdh.X25519().NewPublicKey(req.KeyShare)
+	if err != nil {
+		return nil, nil, fmt.Errorf("invalid key share: %w", err)
+	}
+	priv, err := ecdh.X25519().GenerateKey(rand.Reader)
+	if err != nil {
+		return nil, nil, err
+	}
+	secret, err := priv.ECDH(clientShare)
+	if err != nil {
+		return nil, nil, fmt.Errorf("invalid key share: %w", err)
+	}
+	resp.KeyShare = priv.PublicKey().Bytes()
+	msg := auth.HandshakeMessage(id.name, req.Nonce, req.KeyShare, resp.KeyShare)
+	key, err := auth.TokenKey(secret, msg)
+	if err != nil {
+		return nil, nil, err
+	}
+	resp.Signature = ed25519.Sign(id.key, msg)
+	return resp, key, nil
+}
+
+// authenticateInBand authenticates a WebSocket client that presented no
+// token with its HTTP request. The client first has the node sign an
+// Identify handshake, then sends its token sealed with the handshake's key
+// (Authenticate), so a token only ever reaches the holder of the node key.
+// A plain Identify is answered without authenticating anything.
+func (n *Node) authenticateInBand(reader connection.FrameReader, writer connection.FrameWriter) (string, *session.CommandPolicy, bool) {
+	refuse := func(msg string) (string, *session.CommandPolicy, bool) {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: msg})
+		return "", nil, false
+	}
+	req, ok := readHandshakeRequest(reader)
+	if !ok || req.Type != "Identify" {
+		return refuse("unauthorized: identify the node, then authenticate")
+	}
+	resp, key, err := n.identity.answer(req)
+	if err != nil {
+		return refuse(err.Error())
+	}
+	if writer.SendResponse(resp) != nil || key == nil {
+		return "", nil, false
+	}
+
+	req, ok = readHandshakeRequest(reader)
+	if !ok || req.Type != "Authenticate" {
+		return refuse("unauthorized: expected Authenticate")
+	}
+	token, err := auth.OpenToken(key, req.SealedToken)
+	if err != nil {
+		return refuse("unauthorized: " + err.Error())
+	}
+	policy, ok := n.authenticate(token)
+	if !ok {
+		return refuse("unauthorized")
+	}
+	if writer.SendResponse(&protocol.Response{Type: "Authenticated"}) != nil {
+		return "", nil, false
+	}
+	return token, policy, true
+}
+
+// readHandshakeRequest reads one control request of the handshake.
+func readHandshakeRequest(reader connection.FrameReader) (*protocol.Request, bool) {
+	f, err := reader.ReadFrame()
+	if err != nil || f == nil || f.Type != protocol.FrameControl {
+		return nil, false
+	}
+	var req protocol.Request
+	if err := json.Unmarshal(f.Payload, &req); err != nil {
+		return nil, false
+	}
+	return &req, true
+}
@@ -0,0 +1,74 @@
+package node
+
+import (
+	"io"
+	"os"
+)
+
+// tailChunkSize is how much of the log is read per step when scanning
+// backwards for line breaks.
+const tailChunkSize = 64 * 1024
+
+// readLogTail returns the last n lines of the file at path without reading
+// the whole file, along with the file size at the time of reading. A
+// trailing newline ends the last line rather than starting an empty one.
+func readLogTail(path string, n int) ([]byte, int64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, 0, err
+	}
+	defer f.Close()
+
+	fi, err := f.Stat()
+	if err != nil {
+		return nil, 0, err
+	}
+	size := fi.Size()
+	if n <= 0 {
+		return nil, size, nil
+	}
+
+	var tail []byte
+	pos := size
+	seen := 0
+	for pos > 0 {
+		readSize := int64(tailChunkSize)
+		if pos < readSize {
+			readSize = pos
+		}
+		pos -= readSize
+
+		chunk := make([]byte, readSize)
+		if _, err := f.ReadAt(chunk, pos); err != nil && err != io.EOF {
+			return nil, 0, err
+		}
+		for i := len(chunk) - 1; i >= 0; i-- {
+			if chunk[i] != '\n' || pos+int64(i) == size-1 {
+				continue
+			}
+			seen++
+			if seen == n {
+				return append(chunk[i+1:], tail...), size, nil
+			}
+		}
+		tail = append(chunk, tail...)
+	}
+	return tail, size, nil
+}
+
+// readLogFileRange returns bytes [from, to) of the file at path, or from from
+// to the end when to is negative.
+func readLogFileRange(path string, from, to int64) ([]byte, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+	if _, err := f.Seek(from, io.SeekStart); err != nil {
+		return nil, err
+	}
+	if to < 0 {
+		return io.ReadAll(f)
+	}
+	return io.ReadAll(io.LimitReader(f, to-from))
+}
@@ -0,0 +1,44 @@
+package node
+
+import (
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestReadLogTail(t *testing.T) {
+	long := strings.Repeat("x", tailChunkSize+10) + "\nlast\n"
+	cases := []struct {
+		name, content string
+		n             int
+		want          string
+	}{
+		{"trailing newline", "a\nb\nc\n", 2, "b\nc\n"},
+		{"no trailing newline", "a\nb\nc", 2, "b\nc"},
+		{"more than available", "a\nb\n", 10, "a\nb\n"},
+		{"zero lines", "a\nb\n", 0, ""},
+		{"empty file", "", 3, ""},
+		{"spans chunks", long, 3, long},
+		{"across chunk boundary", long, 1, "last\n"},
+		{"only a newline", "\n", 1, "\n"},
+	}
+	for _, tc := range cases {
+		t.Run(tc.name, func(t *testing.T) {
+			path := filepath.Join(t.TempDir(), "output.log")
+			if err := os.WriteFile(path, []byte(tc.content), 0o644); err != nil {
+				t.Fatal(err)
+			}
+			got, size, err := readLogTail(path, tc.n)
+			if err != nil {
+				t.Fatalf("readLogTail: %v", err)
+			}
+			if string(got) != tc.want {
+				t.Errorf("readLogTail(%q, %d) = %q, want %q", tc.content, tc.n, got, tc.want)
+			}
+			if size != int64(len(tc.content)) {
+				t.Errorf("size = %d, want %d", size, len(tc.content))
+			}
+		})
+	}
+}
@@ -2,6 +2,9 @@ package node
 
 import (
 	"context"
+	"crypto/ed25519"
+	"crypto/tls"
+	"encoding/json"
 	"fmt"
 	"log/slog"
 	"net"
@@ -14,10 +17,14 @@ import (
 	"nhooyr.io/websocket"
 
 	"github.com/codewiresh/codewire/internal/auth"
+	"github.com/codewiresh/codewire/internal/client"
 	"github.com/codewiresh/codewire/internal/config"
 	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/logship"
+	"github.com/codewiresh/codewire/internal/protocol"
 	"github.com/codewiresh/codewire/internal/relay"
 	"github.com/codewiresh/codewire/internal/session"
+	"github.com/codewiresh/codewire/internal/standby"
 )
 
 // Node manages PTY sessions, accepting connections over a Unix domain socket
@@ -29,6 +36,22 @@ type Node struct {
 	pidPath    string
 	config     *config.Config
 	dataDir    string
+	upgrader   *upgrader
+	bandwidth  *bandwidthMeter
+	attachIdle time.Duration
+	guardrails *guardrails
+	tokens     []scopedToken    // [[tokens]]
+	shipper    *logship.Shipper // nil unless [log_shipping] is configured
+	identity   *nodeIdentity
+	accessLog  *accessLog // nil without a WebSocket listener
+	shutdown   shutdownPolicy
+	relay      *relay.AgentStatus // nil without a relay
+	standby    *standby.Store     // nil unless [standby] is configured
+	primary    *client.Target
+	syncEvery  time.Duration
+	// Zero keeps --workspace directories forever.
+	workspaceRetention time.Duration
+	persistDebounce    time.Duration
 }
 
 // NewNode creates a Node rooted at dataDir. It loads the configuration,
@@ -39,10 +62,95 @@ func NewNode(dataDir string) (*Node, error) {
 		return nil, fmt.Errorf("loading config: %w", err)
 	}
 
+	from, steps, err := session.MigrateDataDir(dataDir, false)
+	if err != nil {
+		return nil, err
+	}
+	for _, step := range steps {
+		slog.Info("migrated data dir", "from", from, "to", step.Version, "migration", step.Description, "changes", len(step.Changes))
+	}
+
 	mgr, err := session.NewSessionManager(dataDir)
 	if err != nil {
 		return nil, fmt.Errorf("creating session manager: %w", err)
 	}
+	defaults := session.LaunchDefaults{
+		Env:         cfg.Launch.Env,
+		PathPrepend: cfg.Launch.PathPrepend,
+		Shell:       cfg.Launch.Shell,
+		Term:        cfg.Launch.Term,
+	}
+	for _, limit := range []struct {
+		name  string
+		value string
+		dst   *time.Duration
+	}{
+		{"launch.max_runtime", cfg.Launch.MaxRuntime, &defaults.MaxRuntime},
+		{"launch.idle_timeout", cfg.Launch.IdleTimeout, &defaults.IdleTimeout},
+		{"launch.expiry_warning", cfg.Launch.ExpiryWarning, &defaults.ExpiryWarning},
+	} {
+		if *limit.dst, err = parseLimit(limit.name, limit.value); err != nil {
+			return nil, err
+		}
+	}
+	mgr.SetLaunchDefaults(defaults)
+
+	var attachIdle time.Duration
+	if cfg.Node.AttachIdleTimeout != "" {
+		attachIdle, err = time.ParseDuration(cfg.Node.AttachIdleTimeout)
+		if err != nil || attachIdle <= 0 {
+			return nil, fmt.Errorf("invalid node.attach_idle_timeout %q", cfg.Node.AttachIdleTimeout)
+		}
+	}
+
+	persistDebounce := 500 * time.Millisecond
+	if cfg.Node.PersistDebounce != "" {
+		persistDebounce, err = time.ParseDuration(cfg.Node.PersistDebounce)
+		if err != nil || persistDebounce < 0 {
+			return nil, fmt.Errorf("invalid node.persist_debounce %q", cfg.Node.PersistDebounce)
+		}
+	}
+	fsync, err := session.ParseFsyncPolicy(cfg.Node.PersistFsync)
+	if err != nil {
+		return nil, err
+	}
+	mgr.SetFsyncPolicy(fsync)
+
+	eventRetention := 30 * 24 * time.Hour
+	if cfg.Node.EventRetention != "" {
+		eventRetention, err = time.ParseDuration(cfg.Node.EventRetention)
+		if err != nil || eventRetention < 0 {
+			return nil, fmt.Errorf("invalid node.event_retention %q", cfg.Node.EventRetention)
+		}
+	}
+
+	var workspaceRetention time.Duration
+	if cfg.Launch.WorkspaceRetention != "" {
+		workspaceRetention, err = time.ParseDuration(cfg.Launch.WorkspaceRetention)
+		if err != nil || workspaceRetention <= 0 {
+			return nil, fmt.Errorf("invalid launch.workspace_retention %q", cfg.Launch.WorkspaceRetention)
+		}
+	}
+
+	guard, err := newGuardrails(cfg.Guardrails, dataDir)
+	if err != nil {
+		return nil, err
+	}
+
+	tokens, err := newScopedTokens(cfg.Tokens)
+	if err != nil {
+		return nil, err
+	}
+
+	shutdown, err := newShutdownPolicy(cfg.Shutdown)
+	if err != nil {
+		return nil, err
+	}
+
+	shipper, err := logship.New(cfg.LogShipping)
+	if err != nil {
+		return nil, err
+	}
 
 	token, err := auth.LoadOrGenerateToken(dataDir)
 	if err != nil {
@@ -50,20 +158,96 @@ func NewNode(dataDir string) (*Node, error) {
 	}
 	slog.Info("auth token ready", "token", token)
 
+	if (cfg.Node.TLSCert == "") != (cfg.Node.TLSKey == "") {
+		return nil, fmt.Errorf("node.tls_cert and node.tls_key must be set together")
+	}
+	if cfg.Node.TLSCert != "" {
+		if _, err := tls.LoadX509KeyPair(cfg.Node.TLSCert, cfg.Node.TLSKey); err != nil {
+			return nil, fmt.Errorf("loading node TLS certificate: %w", err)
+		}
+	}
+
+	var alog *accessLog
+	if cfg.Node.Listen != nil {
+		if alog, err = newAccessLog(cfg.Node, dataDir); err != nil {
+			return nil, err
+		}
+	}
+
+	key, err := auth.LoadOrGenerateNodeKey(dataDir)
+	if err != nil {
+		return nil, err
+	}
+	slog.Info("node identity ready", "fingerprint", auth.Fingerprint(key.Public().(ed25519.PublicKey)))
+
+	var relayStatus *relay.AgentStatus
+	if cfg.RelayURL != nil && cfg.RelayToken != nil {
+		relayStatus = &relay.AgentStatus{}
+	}
+
+	var (
+		replica   *standby.Store
+		primary   *client.Target
+		syncEvery = 10 * time.Second
+	)
+	if sc := cfg.Standby; sc.Primary != "" {
+		if sc.Interval != "" {
+			syncEvery, err = time.ParseDuration(sc.Interval)
+			if err != nil || syncEvery <= 0 {
+				return nil, fmt.Errorf("invalid standby.interval %q", sc.Interval)
+			}
+		}
+		primary = &client.Target{
+			URL:     sc.Primary,
+			Token:   sc.Token,
+			NodeKey: config.ServerEntry{NodeKey: sc.NodeKey}.PinnedKey(),
+		}
+		if replica, err = standby.Open(filepath.Join(dataDir, "replica"), primary.NodeName()); err != nil {
+			return nil, err
+		}
+	}
+
+	events, err := session.OpenEventStore(dataDir, eventRetention)
+	if err != nil {
+		return nil, err
+	}
+	mgr.SetEventStore(events)
+
 	return &Node{
 		Manager:    mgr,
 		KVStore:    session.NewKVStore(),
 		socketPath: filepath.Join(dataDir, "codewire.sock"),
-		pidPath:    filepath.Join(dataDir, "codewire.pid"),
+		pidPath:    filepath.Join(dataDir, PIDFile),
 		config:     cfg,
 		dataDir:    dataDir,
+		bandwidth:  newBandwidthMeter(cfg.Node.BandwidthLimitKiB * 1024),
+		attachIdle: attachIdle,
+		guardrails: guard,
+		tokens:     tokens,
+		shipper:    shipper,
+		identity:   &nodeIdentity{name: cfg.Node.Name, key: key},
+		accessLog:  alog,
+		relay:      relayStatus,
+		standby:    replica,
+		primary:    primary,
+		syncEvery:  syncEvery,
+
+		shutdown:           shutdown,
+		workspaceRetention: workspaceRetention,
+		persistDebounce:    persistDebounce,
 	}, nil
 }
 
 // Run starts the node. It writes a PID file, listens on a Unix socket,
-// and optionally starts a WebSocket server. It blocks until ctx is cancelled.
+// and optionally starts a WebSocket server. It blocks until ctx is cancelled,
+// or returns ErrRestartRequested once an Upgrade has replaced the binary.
 func (n *Node) Run(ctx context.Context) error {
+	ctx, stop := context.WithCancel(ctx)
+	defer stop()
+	n.upgrader = &upgrader{restart: stop}
+
 	// Write PID file.
+	removeStalePIDFiles(n.dataDir)
 	pid := os.Getpid()
 	if err := os.WriteFile(n.pidPath, []byte(fmt.Sprintf("%d", pid)), 0o644); err != nil {
 		return fmt.Errorf("writing pid file: %w", err)
@@ -79,6 +263,7 @@ func (n *Node) Run(ctx context.Context) error {
 	slog.Info("listening on unix socket", "path", n.socketPath)
 
 	defer n.Cleanup()
+	defer n.Manager.Events().Close()
 
 	// Start WebSocket server if configured (direct mode).
 	if n.config.Node.Listen != nil {
@@ -91,30 +276,58 @@ func (n *Node) Run(ctx context.Context) error {
 	}
 
 	// Start relay agent if relay URL and token are configured.
-	if n.config.RelayURL != nil && n.config.RelayToken != nil {
+	if n.relay != nil {
 		go relay.RunAgent(ctx, relay.AgentConfig{
 			RelayURL:  *n.config.RelayURL,
 			NodeName:  n.config.Node.Name,
 			NodeToken: *n.config.RelayToken,
+			Status:    n.relay,
 		})
 	}
 
-	// Start periodic status refresh (every 5 seconds).
-	go func() {
-		ticker := time.NewTicker(5 * time.Second)
-		defer ticker.Stop()
-		for {
-			select {
-			case <-ctx.Done():
-				return
-			case <-ticker.C:
-				n.Manager.RefreshStatuses()
+	// Prune expired --workspace directories.
+	if n.workspaceRetention > 0 {
+		go func() {
+			ticker := time.NewTicker(time.Minute)
+			defer ticker.Stop()
+			for {
+				n.Manager.PruneWorkspaces(n.workspaceRetention)
+				select {
+				case <-ctx.Done():
+					return
+				case <-ticker.C:
+				}
 			}
-		}
-	}()
+		}()
+	}
+
+	// Copy the primary's sessions when this node is its standby.
+	if n.standby != nil {
+		go standby.Follow(ctx, n.primary, n.standby, n.syncEvery)
+	}
+
+	// Upload finished sessions' logs to object storage.
+	if n.shipper != nil {
+		go func() {
+			ticker := time.NewTicker(30 * time.Second)
+			defer ticker.Stop()
+			for {
+				n.Manager.ShipLogs(n.shipLogs, n.config.LogShipping.DeleteLocal)
+				select {
+				case <-ctx.Done():
+					return
+				case <-ticker.C:
+				}
+			}
+		}()
+	}
 
 	// Start persistence manager.
-	go persistenceManager(n.Manager)
+	go persistenceManager(n.Manager, n.persistDebounce)
+
+	// Report readiness and liveness to systemd when supervised by it.
+	_ = sdNotify("READY=1")
+	go runSystemdWatchdog(ctx, n.socketPath)
 
 	// Close the listener when ctx is cancelled so Accept unblocks.
 	go func() {
@@ -129,6 +342,12 @@ func (n *Node) Run(ctx context.Context) error {
 			// Check if we were shut down.
 			select {
 			case <-ctx.Done():
+				if n.upgrader.restarted.Load() {
+					return ErrRestartRequested
+				}
+				if n.shutdown.enabled {
+					n.Manager.Shutdown(n.shutdown.wrapUp, n.shutdown.grace, n.shutdown.killAfter)
+				}
 				return ctx.Err()
 			default:
 			}
@@ -140,10 +359,44 @@ func (n *Node) Run(ctx context.Context) error {
 			connection.NewUnixWriter(conn),
 			n.Manager,
 			n.KVStore,
+			n.upgrader,
+			n.bandwidth,
+			n.attachIdle,
+			n.guardrails,
+			n.identity,
+			n.relayStatus,
+			n.standby,
+			clientAccess{},
 		)
 	}
 }
 
+// relayStatus reports the relay agent's connection for NodeStatus, or nil
+// when no relay is configured.
+func (n *Node) relayStatus() *protocol.RelayStatus {
+	if n.relay == nil {
+		return nil
+	}
+	st := n.relay.Snapshot()
+	rs := &protocol.RelayStatus{
+		URL:         *n.config.RelayURL,
+		Connected:   st.Connected,
+		LastError:   st.LastError,
+		Disconnects: st.Disconnects,
+	}
+	if !st.Since.IsZero() {
+		rs.Since = st.Since.UTC().Format(time.RFC3339)
+	}
+	return rs
+}
+
+// shipLogs uploads a session's files under <node>/<id>-<created>/, so runs
+// from several nodes can share a bucket and prefix.
+func (n *Node) shipLogs(meta session.SessionMeta, files []string) (string, error) {
+	dir := fmt.Sprintf("%s/%d-%s", n.config.Node.Name, meta.ID, meta.CreatedAt.UTC().Format("20060102T150405Z"))
+	return n.shipper.Ship(dir, files)
+}
+
 // Cleanup removes the Unix socket and PID files.
 func (n *Node) Cleanup() {
 	_ = os.Remove(n.socketPath)
@@ -152,41 +405,79 @@ func (n *Node) Cleanup() {
 
 // runWSServer starts an HTTP server that upgrades /ws connections to WebSocket
 // and dispatches them through the standard client handler after validating the
-// auth token.
+// auth token. /observe/{id} streams a session's output read-only.
 func (n *Node) runWSServer(ctx context.Context, addr string) error {
 	mux := http.NewServeMux()
 	mux.HandleFunc("/ws", func(w http.ResponseWriter, r *http.Request) {
-		// Check Authorization header first, fall back to query param.
-		token := ""
-		if authHeader := r.Header.Get("Authorization"); strings.HasPrefix(authHeader, "Bearer ") {
-			token = strings.TrimPrefix(authHeader, "Bearer ")
-		}
-		if token == "" {
-			token = r.URL.Query().Get("token")
-		}
-		if !auth.ValidateToken(n.dataDir, token) {
-			http.Error(w, "unauthorized", http.StatusUnauthorized)
+		token, err := requestToken(r, n.config.Node.AllowQueryToken)
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusUnauthorized)
 			return
 		}
+		// Current clients send no token here: they authenticate over the
+		// WebSocket once the node has proved its identity.
+		var policy *session.CommandPolicy
+		if token != "" {
+			var ok bool
+			if policy, ok = n.authenticate(token); !ok {
+				http.Error(w, "unauthorized", http.StatusUnauthorized)
+				return
+			}
+		}
 
 		wsConn, err := websocket.Accept(w, r, nil)
 		if err != nil {
 			slog.Error("websocket accept error", "err", err)
 			return
 		}
+		// Allow frames as large as the protocol does; the library default
+		// (32 KiB) would refuse history imports and large stdin data.
+		wsConn.SetReadLimit(int64(protocol.MaxPayload))
 
 		wsCtx := r.Context()
-		reader := connection.NewWSReader(wsCtx, wsConn)
-		writer := connection.NewWSWriter(wsCtx, wsConn)
-		handleClient(reader, writer, n.Manager, n.KVStore)
+		if token == "" {
+			hsCtx, cancel := context.WithTimeout(wsCtx, handshakeTimeout)
+			var ok bool
+			token, policy, ok = n.authenticateInBand(connection.NewWSReader(hsCtx, wsConn), connection.NewWSWriter(hsCtx, wsConn))
+			cancel()
+			if !ok {
+				wsConn.Close(websocket.StatusPolicyViolation, "unauthorized")
+				return
+			}
+		}
+		reader, writer, done := n.bandwidth.wrap(token, r.RemoteAddr,
+			connection.NewWSReader(wsCtx, wsConn),
+			connection.NewWSWriter(wsCtx, wsConn))
+		defer done()
+		handleClient(reader, writer, n.Manager, n.KVStore, n.upgrader, n.bandwidth, n.attachIdle, n.guardrails, n.identity, n.relayStatus, n.standby, clientAccess{remote: true, policy: policy})
+	})
+	mux.HandleFunc("GET /observe/{id}", n.handleObserve)
+	mux.HandleFunc("GET /healthz", func(w http.ResponseWriter, r *http.Request) {
+		health := map[string]any{"status": "ok"}
+		if rs := n.relayStatus(); rs != nil {
+			// The relay's error text stays out of this unauthenticated
+			// endpoint; cw node status shows it.
+			health["relay"] = map[string]any{
+				"connected":   rs.Connected,
+				"since":       rs.Since,
+				"disconnects": rs.Disconnects,
+			}
+		}
+		w.Header().Set("Content-Type", "application/json")
+		_ = json.NewEncoder(w).Encode(health)
 	})
 
+	var handler http.Handler = mux
+	if n.accessLog != nil {
+		handler = n.accessLog.wrap(mux)
+	}
 	srv := &http.Server{
 		Addr:    addr,
-		Handler: mux,
+		Handler: handler,
 	}
 
-	slog.Info("websocket server listening", "addr", addr)
+	useTLS := n.config.Node.TLSCert != ""
+	slog.Info("websocket server listening", "addr", addr, "tls", useTLS)
 
 	// Shut down gracefully when ctx is cancelled.
 	go func() {
@@ -196,16 +487,40 @@ func (n *Node) runWSServer(ctx context.Context, addr string) error {
 		_ = srv.Shutdown(shutdownCtx)
 	}()
 
-	if err := srv.ListenAndServe(); err != nil && err != http.ErrServerClosed {
+	var err error
+	if useTLS {
+		err = srv.ListenAndServeTLS(n.config.Node.TLSCert, n.config.Node.TLSKey)
+	} else {
+		err = srv.ListenAndServe()
+	}
+	if err != nil && err != http.ErrServerClosed {
 		return fmt.Errorf("websocket server: %w", err)
 	}
 	return nil
 }
 
+// requestToken returns the auth token of a /ws request from its
+// Authorization header. The ?token= query form is accepted only when
+// allowQuery is set ([node] allow_query_token), since URLs are logged by
+// proxies along the way.
+func requestToken(r *http.Request, allowQuery bool) (string, error) {
+	if authHeader := r.Header.Get("Authorization"); strings.HasPrefix(authHeader, "Bearer ") {
+		return strings.TrimPrefix(authHeader, "Bearer "), nil
+	}
+	if !r.URL.Query().Has("token") {
+		return "", nil
+	}
+	if !allowQuery {
+		return "", fmt.Errorf("unauthorized: send the token in an Authorization header (query tokens are disabled; see node.allow_query_token)")
+	}
+	slog.Warn("auth token passed in URL query; upgrade the client to send an Authorization header", "remote", r.RemoteAddr)
+	return r.URL.Query().Get("token"), nil
+}
+
 // persistenceManager debounces persist signals from the session manager.
-// After receiving a signal it waits 500ms for additional signals before
-// flushing metadata to disk.
-func persistenceManager(manager *session.SessionManager) {
+// After receiving a signal it waits debounce ([node] persist_debounce,
+// default 500ms) for additional signals before flushing metadata to disk.
+func persistenceManager(manager *session.SessionManager, debounce time.Duration) {
 	timer := time.NewTimer(0)
 	if !timer.Stop() {
 		<-timer.C
@@ -230,7 +545,7 @@ func persistenceManager(manager *session.SessionManager) {
 				default:
 				}
 			}
-			timer.Reset(500 * time.Millisecond)
+			timer.Reset(debounce)
 			pending = true
 
 		case <-timer.C:
@@ -0,0 +1,27 @@
+package node
+
+import (
+	"net/http/httptest"
+	"testing"
+)
+
+func TestRequestToken(t *testing.T) {
+	r := httptest.NewRequest("GET", "/ws?token=from-query", nil)
+	r.Header.Set("Authorization", "Bearer from-header")
+	if got, err := requestToken(r, false); err != nil || got != "from-header" {
+		t.Fatalf("header token: got %q, %v", got, err)
+	}
+
+	r = httptest.NewRequest("GET", "/ws?token=from-query", nil)
+	if _, err := requestToken(r, false); err == nil {
+		t.Fatal("query token accepted without allow_query_token")
+	}
+	if got, err := requestToken(r, true); err != nil || got != "from-query" {
+		t.Fatalf("query token with allow_query_token: got %q, %v", got, err)
+	}
+
+	r = httptest.NewRequest("GET", "/ws", nil)
+	if got, err := requestToken(r, false); err != nil || got != "" {
+		t.Fatalf("no token: got %q, %v", got, err)
+	}
+}
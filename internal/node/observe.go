@@ -0,0 +1,136 @@
+package node
+
+import (
+	"context"
+	"log/slog"
+	"net/http"
+	"strconv"
+	"time"
+
+	"nhooyr.io/websocket"
+
+	"github.com/codewiresh/codewire/internal/auth"
+	"github.com/codewiresh/codewire/internal/textwidth"
+)
+
+// observeWriteTimeout drops an observer that stops reading rather than
+// letting it hold the session's output subscription.
+const observeWriteTimeout = 10 * time.Second
+
+// handleObserve serves /observe/{id}: a read-only WebSocket that streams a
+// session's output as binary messages — its history (the last ?tail= lines,
+// or all of it) and then live output — and closes with the session's final
+// status when it exits. Messages from the client are refused.
+//
+// It accepts the observe token (see auth.ValidateObserveToken) as well as
+// the node token. Browsers cannot set headers on a WebSocket, so the observe
+// token may always be passed as ?token=; the node token only when
+// allow_query_token is set, as on /ws.
+func (n *Node) handleObserve(w http.ResponseWriter, r *http.Request) {
+	token, err := requestToken(r, true)
+	if err != nil || token == "" {
+		http.Error(w, "unauthorized", http.StatusUnauthorized)
+		return
+	}
+	fromQuery := r.Header.Get("Authorization") == ""
+	switch {
+	case auth.ValidateObserveToken(n.dataDir, token):
+	case auth.ValidateToken(n.dataDir, token) && (!fromQuery || n.config.Node.AllowQueryToken):
+	default:
+		http.Error(w, "unauthorized", http.StatusUnauthorized)
+		return
+	}
+
+	id, err := n.resolveObserved(r.PathValue("id"))
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusNotFound)
+		return
+	}
+	var historyLines *uint
+	if v := r.URL.Query().Get("tail"); v != "" {
+		lines, err := strconv.ParseUint(v, 10, 32)
+		if err != nil {
+			http.Error(w, "invalid tail", http.StatusBadRequest)
+			return
+		}
+		l := uint(lines)
+		historyLines = &l
+	}
+
+	subID, outputCh, offset, err := n.Manager.SubscribeOutput(id)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusNotFound)
+		return
+	}
+	defer n.Manager.UnsubscribeOutput(id, subID)
+	statusWatcher, err := n.Manager.SubscribeStatus(id)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusNotFound)
+		return
+	}
+
+	// Access is by token rather than cookie, so pages on any origin may
+	// embed the stream.
+	conn, err := websocket.Accept(w, r, &websocket.AcceptOptions{OriginPatterns: []string{"*"}})
+	if err != nil {
+		slog.Error("websocket accept error", "err", err)
+		return
+	}
+	defer conn.CloseNow()
+	// CloseRead closes the connection with a policy violation if the client
+	// sends anything, and cancels ctx when it goes away.
+	ctx := conn.CloseRead(r.Context())
+
+	send := func(data []byte) error {
+		if len(data) == 0 {
+			return nil
+		}
+		wctx, cancel := context.WithTimeout(ctx, observeWriteTimeout)
+		defer cancel()
+		return conn.Write(wctx, websocket.MessageBinary, data)
+	}
+
+	backlog, _, _ := watchBacklog(n.Manager, id, offset, true, historyLines, nil)
+	if send(backlog) != nil {
+		return
+	}
+
+	changed := statusWatcher.Changed()
+	for {
+		if s := statusWatcher.Get(); s.State != "running" {
+			// Output written before the exit is already queued.
+			for drained := false; !drained; {
+				select {
+				case chunk := <-outputCh:
+					if send(chunk.Data) != nil {
+						return
+					}
+				default:
+					drained = true
+				}
+			}
+			// A close reason may be at most 123 bytes.
+			conn.Close(websocket.StatusNormalClosure, textwidth.Truncate(s.String(), 120))
+			return
+		}
+		select {
+		case chunk := <-outputCh:
+			if send(chunk.Data) != nil {
+				return
+			}
+		case <-changed:
+			changed = statusWatcher.Changed()
+		case <-ctx.Done():
+			return
+		}
+	}
+}
+
+// resolveObserved maps the {id} of an /observe path, a session ID or name,
+// to a session ID.
+func (n *Node) resolveObserved(ref string) (uint32, error) {
+	if id, err := strconv.ParseUint(ref, 10, 32); err == nil {
+		return uint32(id), nil
+	}
+	return n.Manager.ResolveByName(ref)
+}
@@ -0,0 +1,130 @@
+package node
+
+import (
+	"bytes"
+	"errors"
+	"fmt"
+	"net"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"syscall"
+	"time"
+)
+
+// PIDFile holds the node's PID while it runs.
+const PIDFile = "codewire.pid"
+
+// legacyPIDFiles are where earlier releases, which called the node a
+// daemon, recorded its PID. An install upgraded while such a node was
+// running still has one.
+var legacyPIDFiles = []string{"node.pid", "daemon.pid"}
+
+// ErrStalePIDFile is returned by RunningPID when the only PID files left
+// name processes that are not a cw node.
+var ErrStalePIDFile = errors.New("stale pid file")
+
+// RunningPID returns the PID recorded in dataDir for the running node and
+// the file it was read from. The watchdog's PID comes first, so stopping it
+// does not lead to a restart; the legacy files are read when there is no
+// current one. A file whose PID is no longer a cw process, as after a crash
+// once the PID has been reused, is skipped, so the process now holding the
+// PID is never signalled.
+func RunningPID(dataDir string) (int, string, error) {
+	current := []string{WatchdogPIDFile, PIDFile}
+	var stale string
+	for i, name := range append(current, legacyPIDFiles...) {
+		path := filepath.Join(dataDir, name)
+		data, err := os.ReadFile(path)
+		if errors.Is(err, os.ErrNotExist) {
+			continue
+		}
+		if err != nil {
+			return 0, path, err
+		}
+		pid, err := strconv.Atoi(strings.TrimSpace(string(data)))
+		if err != nil {
+			return 0, path, fmt.Errorf("invalid pid file %s: %w", path, err)
+		}
+		isNode, known := nodeProcess(pid)
+		if !known {
+			// Without /proc, a legacy file is trusted only while a node
+			// answers on the socket.
+			isNode = i < len(current) || socketLive(dataDir)
+		}
+		if !isNode {
+			if stale == "" {
+				stale = path
+			}
+			continue
+		}
+		return pid, path, nil
+	}
+	if stale != "" {
+		return 0, stale, fmt.Errorf("%w %s: its process is not a running cw node", ErrStalePIDFile, stale)
+	}
+	return 0, "", fmt.Errorf("no pid file in %s (is the node running?)", dataDir)
+}
+
+// nodeProcess is defaultNodeProcess, replaced in tests.
+var nodeProcess = defaultNodeProcess
+
+// defaultNodeProcess reports whether pid is a running cw process, judged by
+// its executable or, failing that, its command line in /proc. known is
+// false where /proc cannot tell.
+func defaultNodeProcess(pid int) (isNode, known bool) {
+	if err := syscall.Kill(pid, 0); errors.Is(err, syscall.ESRCH) {
+		return false, true
+	}
+	names := []string{"cw"}
+	if self, err := os.Executable(); err == nil {
+		names = append(names, filepath.Base(self))
+	}
+	proc := filepath.Join("/proc", strconv.Itoa(pid))
+	var exe string
+	if link, err := os.Readlink(filepath.Join(proc, "exe")); err == nil {
+		exe = strings.TrimSuffix(link, " (deleted)") // replaced by an upgrade
+	} else if cmdline, err := os.ReadFile(filepath.Join(proc, "cmdline")); err == nil {
+		argv0, _, _ := bytes.Cut(cmdline, []byte{0})
+		exe = string(argv0)
+	} else {
+		return false, false
+	}
+	for _, name := range names {
+		if filepath.Base(exe) == name {
+			return true, true
+		}
+	}
+	return false, true
+}
+
+// socketLive reports whether a node answers on dataDir's socket.
+func socketLive(dataDir string) bool {
+	conn, err := net.DialTimeout("unix", filepath.Join(dataDir, "codewire.sock"), time.Second)
+	if err != nil {
+		return false
+	}
+	conn.Close()
+	return true
+}
+
+// removeStalePIDFiles deletes legacy PID files whose process has exited or
+// is no longer cw, so RunningPID does not find them once this node has
+// replaced the old one.
+func removeStalePIDFiles(dataDir string) {
+	for _, name := range legacyPIDFiles {
+		path := filepath.Join(dataDir, name)
+		data, err := os.ReadFile(path)
+		if err != nil {
+			continue
+		}
+		pid, err := strconv.Atoi(strings.TrimSpace(string(data)))
+		if err == nil {
+			if isNode, known := nodeProcess(pid); isNode || (!known && syscall.Kill(pid, 0) == nil) {
+				continue
+			}
+		}
+		_ = os.Remove(path)
+	}
+}
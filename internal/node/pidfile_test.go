@@ -0,0 +1,72 @@
+package node
+
+import (
+	"errors"
+	"os"
+	"path/filepath"
+	"strconv"
+	"testing"
+)
+
+func TestRunningPIDReadsLegacyFiles(t *testing.T) {
+	dir := t.TempDir()
+	if _, _, err := RunningPID(dir); err == nil {
+		t.Fatal("expected an error with no pid file")
+	}
+
+	self := strconv.Itoa(os.Getpid())
+	if err := os.WriteFile(filepath.Join(dir, "daemon.pid"), []byte(self+"\n"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	pid, path, err := RunningPID(dir)
+	if err != nil || pid != os.Getpid() || filepath.Base(path) != "daemon.pid" {
+		t.Fatalf("legacy file: pid %d from %s, %v", pid, path, err)
+	}
+
+	if err := os.WriteFile(filepath.Join(dir, PIDFile), []byte(self+"\n"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	if pid, path, _ := RunningPID(dir); pid != os.Getpid() || filepath.Base(path) != PIDFile {
+		t.Fatalf("current file should win: pid %d from %s", pid, path)
+	}
+}
+
+func TestRunningPIDSkipsReusedPIDs(t *testing.T) {
+	dir := t.TempDir()
+	// The node crashed and its PID now belongs to another program.
+	nodeProcess = func(int) (bool, bool) { return false, true }
+	t.Cleanup(func() { nodeProcess = defaultNodeProcess })
+	if err := os.WriteFile(filepath.Join(dir, PIDFile), []byte("42\n"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	_, path, err := RunningPID(dir)
+	if !errors.Is(err, ErrStalePIDFile) || filepath.Base(path) != PIDFile {
+		t.Fatalf("expected the stale pid file to be reported, got %s, %v", path, err)
+	}
+
+	// Without /proc, a legacy file needs a node answering on the socket.
+	nodeProcess = func(int) (bool, bool) { return false, false }
+	os.Remove(filepath.Join(dir, PIDFile))
+	if err := os.WriteFile(filepath.Join(dir, "node.pid"), []byte("42\n"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	if _, _, err := RunningPID(dir); !errors.Is(err, ErrStalePIDFile) {
+		t.Fatalf("expected a legacy file without a live socket to be stale, got %v", err)
+	}
+}
+
+func TestRemoveStalePIDFiles(t *testing.T) {
+	dir := t.TempDir()
+	live := filepath.Join(dir, "node.pid")
+	stale := filepath.Join(dir, "daemon.pid")
+	os.WriteFile(live, []byte(strconv.Itoa(os.Getpid())), 0o644)
+	os.WriteFile(stale, []byte("not a pid"), 0o644)
+
+	removeStalePIDFiles(dir)
+	if _, err := os.Stat(live); err != nil {
+		t.Fatalf("removed the pid file of a live process: %v", err)
+	}
+	if _, err := os.Stat(stale); err == nil {
+		t.Fatal("kept a stale pid file")
+	}
+}
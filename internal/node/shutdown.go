@@ -0,0 +1,45 @@
+package node
+
+import (
+	"fmt"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/config"
+)
+
+// shutdownPolicy is the parsed [shutdown] section: how running sessions are
+// wound down when the node stops.
+type shutdownPolicy struct {
+	enabled   bool
+	wrapUp    []byte
+	grace     time.Duration
+	killAfter time.Duration
+}
+
+// newShutdownPolicy parses cfg. The policy is disabled when no key is set.
+func newShutdownPolicy(cfg config.ShutdownConfig) (shutdownPolicy, error) {
+	p := shutdownPolicy{
+		enabled:   cfg.WrapUp != "" || cfg.Grace != "" || cfg.KillAfter != "",
+		wrapUp:    []byte(cfg.WrapUp),
+		grace:     10 * time.Second,
+		killAfter: 5 * time.Second,
+	}
+	for _, d := range []struct {
+		name  string
+		value string
+		dst   *time.Duration
+	}{
+		{"shutdown.grace", cfg.Grace, &p.grace},
+		{"shutdown.kill_after", cfg.KillAfter, &p.killAfter},
+	} {
+		if d.value == "" {
+			continue
+		}
+		v, err := time.ParseDuration(d.value)
+		if err != nil || v < 0 {
+			return shutdownPolicy{}, fmt.Errorf("invalid %s %q", d.name, d.value)
+		}
+		*d.dst = v
+	}
+	return p, nil
+}
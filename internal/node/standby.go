@@ -0,0 +1,104 @@
+package node
+
+import (
+	"fmt"
+	"os"
+
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+	"github.com/codewiresh/codewire/internal/standby"
+)
+
+// handleReplicate streams every session to a standby as ReplicaRecord
+// responses, with the output its offsets say it is missing, followed by
+// ReplicaEnd.
+func handleReplicate(writer connection.FrameWriter, manager *session.SessionManager, offsets map[uint32]uint64) {
+	var count uint
+	err := manager.Replicate(offsets, func(rec protocol.HistoryRecord) error {
+		count++
+		return writer.SendResponse(&protocol.Response{Type: "ReplicaRecord", Record: &rec})
+	})
+	if err != nil {
+		_ = writer.SendResponse(&protocol.Response{
+			Type:    "Error",
+			Message: err.Error(),
+		})
+		return
+	}
+	_ = writer.SendResponse(&protocol.Response{Type: "ReplicaEnd", Count: &count})
+}
+
+// handleReplica answers a read from the node's copy of its primary. Only
+// the session list, a session's status and its output log are kept there.
+func handleReplica(writer connection.FrameWriter, replica *standby.Store, req *protocol.Request) {
+	sendError := func(msg string) {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: msg})
+	}
+	if replica == nil {
+		sendError("this node is not a standby: set [standby] primary in its config.toml")
+		return
+	}
+
+	switch req.Type {
+	case "ListSessions":
+		sessions := replica.List()
+		_ = writer.SendResponse(&protocol.Response{
+			Type:     "SessionList",
+			Sessions: &sessions,
+			Standby:  replica.Status(),
+		})
+
+	case "GetStatus":
+		if req.ID == nil {
+			sendError("missing session id")
+			return
+		}
+		info, err := replica.Get(*req.ID)
+		if err != nil {
+			sendError(err.Error())
+			return
+		}
+		if req.Snapshot {
+			sendError("working directory snapshots are not kept on the standby")
+			return
+		}
+		var size uint64
+		if fi, err := os.Stat(replica.LogPath(*req.ID)); err == nil {
+			size = uint64(fi.Size())
+		}
+		_ = writer.SendResponse(&protocol.Response{
+			Type:       "SessionStatus",
+			Info:       &info,
+			OutputSize: &size,
+			Standby:    replica.Status(),
+		})
+
+	case "Logs":
+		if req.ID == nil {
+			sendError("missing session id")
+			return
+		}
+		info, err := replica.Get(*req.ID)
+		if err != nil {
+			sendError(err.Error())
+			return
+		}
+		switch {
+		case req.Follow != nil && *req.Follow:
+			sendError("logs of a replicated session cannot be followed")
+			return
+		case req.Inputs, req.Verify:
+			sendError("only the output log is kept on the standby")
+			return
+		case req.FromBookmark != "" || req.ToBookmark != "":
+			sendError("logs of a replicated session cannot be sliced by bookmark")
+			return
+		}
+		strip := req.StripANSI == nil || *req.StripANSI
+		_ = handleLogs(writer, replica.LogPath(*req.ID), info.Encoding, false, req.Tail, 0, -1, strip, "")
+
+	default:
+		sendError(fmt.Sprintf("%s cannot be answered from the replica: only list, status and logs can", req.Type))
+	}
+}
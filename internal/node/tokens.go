@@ -0,0 +1,132 @@
+package node
+
+import (
+	"crypto/subtle"
+	"fmt"
+	"slices"
+	"strings"
+
+	"github.com/codewiresh/codewire/internal/auth"
+	"github.com/codewiresh/codewire/internal/config"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+// scopedToken is a [[tokens]] entry: an auth token whose launches are
+// limited by a command policy.
+type scopedToken struct {
+	token  string
+	policy *session.CommandPolicy
+}
+
+// clientAccess describes the client on the other end of a connection:
+// remote clients came in over the WebSocket listener rather than the Unix
+// socket, and policy is the command policy of the scoped token they
+// authenticated with (nil for the node token).
+type clientAccess struct {
+	remote bool
+	policy *session.CommandPolicy
+}
+
+// scopedRequests are the requests a scoped token may make; every other
+// request type is refused. Those mapped to true act on one session, named
+// by ID, and are allowed only on sessions the token launched itself.
+var scopedRequests = map[string]bool{
+	"Launch":         false,
+	"LaunchBatch":    false,
+	"ValidateLaunch": false,
+	"ListSessions":   false,
+	"FindSessions":   false,
+	"Version":        false,
+	"Ping":           false,
+	"Identify":       false,
+
+	"Attach":       true,
+	"Resize":       true,
+	"Detach":       true,
+	"SendInput":    true,
+	"Kill":         true,
+	"Logs":         true,
+	"GetStatus":    true,
+	"WatchSession": true,
+	"Subscribe":    true,
+	"Wait":         true,
+	"QueryEvents":  true,
+	"AddNote":      true,
+	"AddBookmark":  true,
+	"AddMirror":    true,
+	"Extend":       true,
+}
+
+// allows refuses a request the client's scoped token may not make. The
+// node token may make any request.
+func (a clientAccess) allows(manager *session.SessionManager, req *protocol.Request) error {
+	if a.policy == nil {
+		return nil
+	}
+	perSession, ok := scopedRequests[req.Type]
+	if !ok || req.Replica {
+		return fmt.Errorf("token %q may not make %s requests", a.policy.Name, req.Type)
+	}
+	if !perSession {
+		return nil
+	}
+	if req.ID == nil || len(req.Tags) > 0 {
+		return fmt.Errorf("token %q may only make %s requests for one session it launched, by ID", a.policy.Name, req.Type)
+	}
+	if owner, found := manager.LaunchedBy(*req.ID); found && owner != a.policy.Name {
+		return fmt.Errorf("token %q may not make %s requests for session %d: it did not launch it", a.policy.Name, req.Type, *req.ID)
+	}
+	return nil
+}
+
+// visible filters a session list down to the sessions the client's scoped
+// token launched.
+func (a clientAccess) visible(sessions []protocol.SessionInfo) []protocol.SessionInfo {
+	if a.policy == nil {
+		return sessions
+	}
+	return slices.DeleteFunc(sessions, func(s protocol.SessionInfo) bool {
+		return s.Token != a.policy.Name
+	})
+}
+
+// newScopedTokens compiles the [[tokens]] entries of the config.
+func newScopedTokens(cfgs []config.TokenConfig) ([]scopedToken, error) {
+	tokens := make([]scopedToken, 0, len(cfgs))
+	names := make(map[string]bool)
+	for i, c := range cfgs {
+		token := strings.TrimSpace(c.Token)
+		if c.Name == "" || token == "" {
+			return nil, fmt.Errorf("tokens[%d]: name and token are required", i)
+		}
+		if names[c.Name] {
+			return nil, fmt.Errorf("tokens[%d]: duplicate token name %q", i, c.Name)
+		}
+		names[c.Name] = true
+		policy, err := session.NewCommandPolicy(c.Name, c.Allow, c.Deny, c.Env)
+		if err != nil {
+			return nil, err
+		}
+		tokens = append(tokens, scopedToken{token: token, policy: policy})
+	}
+	return tokens, nil
+}
+
+// authenticate checks a /ws token against the node token and the scoped
+// tokens. It returns the command policy to apply, nil for the node token.
+func (n *Node) authenticate(token string) (*session.CommandPolicy, bool) {
+	if auth.ValidateToken(n.dataDir, token) {
+		return nil, true
+	}
+	token = strings.TrimSpace(token)
+	if token == "" {
+		return nil, false
+	}
+	for _, t := range n.tokens {
+		if subtle.ConstantTimeCompare([]byte(t.token), []byte(token)) == 1 {
+			return t.policy, true
+		}
+	}
+	return nil, false
+}
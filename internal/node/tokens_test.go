@@ -0,0 +1,75 @@
+package node
+
+import (
+	"os"
+	"path/filepath"
+	"testing"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+func TestScopedTokenRequests(t *testing.T) {
+	mgr, err := session.NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	policy, err := session.NewCommandPolicy("ci", []string{"sleep"}, nil, nil)
+	if err != nil {
+		t.Fatalf("NewCommandPolicy: %v", err)
+	}
+	own, err := mgr.LaunchWithOptions([]string{"sleep", "30"}, "/tmp", nil, nil, "", session.LaunchOptions{Policy: policy})
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	other, err := mgr.LaunchWithOptions([]string{"sleep", "30"}, "/tmp", nil, nil, "", session.LaunchOptions{})
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	t.Cleanup(func() { mgr.KillAll() })
+
+	scoped := clientAccess{remote: true, policy: policy}
+	for _, tc := range []struct {
+		req protocol.Request
+		ok  bool
+	}{
+		{protocol.Request{Type: "Launch"}, true},
+		{protocol.Request{Type: "ListSessions"}, true},
+		{protocol.Request{Type: "SendInput", ID: &own}, true},
+		{protocol.Request{Type: "Kill", ID: &own}, true},
+		{protocol.Request{Type: "SendInput", ID: &other}, false},
+		{protocol.Request{Type: "Attach", ID: &other}, false},
+		{protocol.Request{Type: "Wait", Tags: []string{"build"}}, false},
+		{protocol.Request{Type: "KillAll"}, false},
+		{protocol.Request{Type: "Backup"}, false},
+		{protocol.Request{Type: "ImportHistory"}, false},
+		{protocol.Request{Type: "SetPriority", ID: &own}, false},
+		{protocol.Request{Type: "MsgSend"}, false},
+		{protocol.Request{Type: "Upgrade"}, false},
+		{protocol.Request{Type: "Replicate"}, false},
+		{protocol.Request{Type: "SomethingNew"}, false},
+	} {
+		if err := scoped.allows(mgr, &tc.req); (err == nil) != tc.ok {
+			t.Errorf("%s: allows = %v, want allowed %v", tc.req.Type, err, tc.ok)
+		}
+		if err := (clientAccess{}).allows(mgr, &tc.req); err != nil {
+			t.Errorf("%s: node token refused: %v", tc.req.Type, err)
+		}
+	}
+
+	rc := filepath.Join(t.TempDir(), ".bashrc")
+	if err := addMirrors(mgr, scoped, own, []string{"file:" + rc}); err == nil {
+		t.Fatal("expected a file mirror refused for a scoped token")
+	}
+	if _, err := os.Stat(rc); !os.IsNotExist(err) {
+		t.Fatalf("refused mirror should not create %s, got %v", rc, err)
+	}
+	if _, err := mgr.LaunchWithOptions([]string{"sleep", "30"}, "/tmp", nil, nil, "", session.LaunchOptions{Policy: policy, Mirrors: []string{"file:" + rc}}); err == nil {
+		t.Fatal("expected a scoped launch with a file mirror refused")
+	}
+
+	visible := scoped.visible(mgr.List())
+	if len(visible) != 1 || visible[0].ID != own {
+		t.Fatalf("scoped token should list only its session %d, got %+v", own, visible)
+	}
+}
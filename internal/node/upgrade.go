@@ -0,0 +1,112 @@
+package node
+
+import (
+	"errors"
+	"fmt"
+	"log/slog"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+	"github.com/codewiresh/codewire/internal/update"
+)
+
+// Version is the cw release the node is running. It is set by the cw binary
+// at startup and reported by Version requests.
+var Version = "dev"
+
+// ErrRestartRequested is returned by Run after an Upgrade request has
+// installed a new binary. The caller should re-exec the executable.
+var ErrRestartRequested = errors.New("node restart requested")
+
+// defaultDrainTimeout bounds how long an Upgrade waits for running sessions
+// to finish when the request does not set TimeoutSeconds.
+const defaultDrainTimeout = 10 * time.Minute
+
+// upgrader coordinates in-place upgrades of a node. While draining, new
+// sessions are refused so the node can restart without killing work.
+type upgrader struct {
+	mu        sync.Mutex // serialises upgrades
+	draining  atomic.Bool
+	restarted atomic.Bool
+	restart   func() // stops the node's Run loop
+}
+
+// Draining reports whether the node is refusing new sessions.
+func (u *upgrader) Draining() bool {
+	return u != nil && u.draining.Load()
+}
+
+// handleUpgrade downloads and verifies the requested binary, drains running
+// sessions, installs the binary, replies "Upgraded", and then restarts the
+// node. Sessions cannot survive the restart because their PTYs belong to this
+// process, which is why the node drains before replacing itself.
+func (u *upgrader) handleUpgrade(req *protocol.Request, writer connection.FrameWriter, manager *session.SessionManager) {
+	if u == nil {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: "this node cannot be upgraded remotely"})
+		return
+	}
+	if req.Version == "" {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: "upgrade requires a version or URL"})
+		return
+	}
+	if !u.mu.TryLock() {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: "an upgrade is already in progress"})
+		return
+	}
+	defer u.mu.Unlock()
+
+	slog.Info("upgrade requested", "from", Version, "to", req.Version)
+	staged, err := update.Stage(req.Version, Version, req.AllowDowngrade)
+	if err != nil {
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: fmt.Sprintf("downloading %s: %v", req.Version, err)})
+		return
+	}
+
+	timeout := defaultDrainTimeout
+	if req.TimeoutSeconds != nil {
+		timeout = time.Duration(*req.TimeoutSeconds) * time.Second
+	}
+
+	u.draining.Store(true)
+	if n := waitForDrain(manager, timeout); n > 0 {
+		u.draining.Store(false)
+		staged.Discard()
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: fmt.Sprintf("%d session(s) still running after %s; upgrade aborted", n, timeout)})
+		return
+	}
+
+	if err := staged.Install(); err != nil {
+		u.draining.Store(false)
+		staged.Discard()
+		_ = writer.SendResponse(&protocol.Response{Type: "Error", Message: err.Error()})
+		return
+	}
+
+	slog.Info("upgrade installed, restarting", "version", staged.Version)
+	_ = writer.SendResponse(&protocol.Response{Type: "Upgraded", Version: staged.Version})
+
+	u.restarted.Store(true)
+	u.restart()
+}
+
+// waitForDrain polls until no session is running or timeout elapses, and
+// returns how many sessions are still running.
+func waitForDrain(manager *session.SessionManager, timeout time.Duration) int {
+	deadline := time.Now().Add(timeout)
+	for {
+		running := 0
+		for _, s := range manager.List() {
+			if s.Status == "running" {
+				running++
+			}
+		}
+		if running == 0 || !time.Now().Before(deadline) {
+			return running
+		}
+		time.Sleep(time.Second)
+	}
+}
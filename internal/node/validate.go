@@ -0,0 +1,85 @@
+package node
+
+import (
+	"fmt"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/session"
+)
+
+// validateLaunches runs every check launchSession would make for each
+// launch — draining, guardrails, limits and session.PlanLaunch — without
+// starting anything. Launches in a batch are checked as if the ones before
+// them had started: they count towards max_sessions and claim their names.
+func validateLaunches(manager *session.SessionManager, up *upgrader, guard *guardrails, access clientAccess, reqs []protocol.Request) []protocol.LaunchPlan {
+	plans := make([]protocol.LaunchPlan, len(reqs))
+	var breach *guardrailError
+	if guard != nil {
+		breach = guard.check(manager)
+	}
+	running := len(manager.ListRunning(nil))
+	names := make(map[string]int)
+	started := 0
+	for i := range reqs {
+		req := &reqs[i]
+		plan := &plans[i]
+		plan.Name = req.Name
+		if up.Draining() {
+			plan.Problems = append(plan.Problems, "node is draining for an upgrade; not accepting new sessions")
+		}
+
+		gerr := breach
+		if gerr == nil && guard != nil && guard.maxSessions > 0 && running+started >= guard.maxSessions {
+			gerr = &guardrailError{breach: protocol.GuardrailBreach{
+				Guardrail: guardMaxSessions,
+				Limit:     float64(guard.maxSessions),
+				Value:     float64(running + started),
+				Detail:    fmt.Sprintf("%d sessions running, %d of them from earlier in this batch (limit %d)", running+started, started, guard.maxSessions),
+			}}
+		}
+		if gerr != nil {
+			plan.Guardrail = &gerr.breach
+			if guard.queue {
+				plan.Queued = true
+			} else {
+				plan.Problems = append(plan.Problems, gerr.Error())
+			}
+		}
+
+		opts, err := launchOptions(req, access)
+		if err != nil {
+			plan.Problems = append(plan.Problems, err.Error())
+		} else if p, err := manager.PlanLaunch(req.Command, req.WorkingDir, req.Env, req.Name, opts); err != nil {
+			plan.Problems = append(plan.Problems, err.Error())
+		} else {
+			plan.Command = p.Command
+			plan.Path = p.Path
+			plan.Argv = p.Argv
+			plan.WorkingDir = p.WorkingDir
+			plan.Workspace = opts.Workspace
+			plan.Term = p.Term
+			plan.Encoding = p.Encoding
+			plan.History = p.History
+			plan.Priority = p.Priority
+			if p.MaxRuntime > 0 {
+				plan.MaxRuntime = p.MaxRuntime.String()
+			}
+			if p.IdleTimeout > 0 {
+				plan.IdleTimeout = p.IdleTimeout.String()
+			}
+		}
+		if req.Name != "" {
+			if prev, dup := names[req.Name]; dup {
+				plan.Problems = append(plan.Problems, fmt.Sprintf("name %q is also used by launch %d of this batch", req.Name, prev+1))
+			} else {
+				names[req.Name] = i
+			}
+		}
+
+		plan.OK = len(plan.Problems) == 0
+		if plan.OK {
+			started++
+		}
+	}
+	return plans
+}
@@ -0,0 +1,248 @@
+package node
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"log/slog"
+	"net"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strconv"
+	"syscall"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/connection"
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// WatchdogConfig controls the node supervisor started by `cw node --watchdog`.
+type WatchdogConfig struct {
+	// Interval between health probes of the node's Unix socket.
+	Interval time.Duration
+	// Failures is how many consecutive failed probes trigger a restart.
+	Failures int
+	// Exe and Args start the supervised node process (e.g. cw node).
+	Exe  string
+	Args []string
+}
+
+// WatchdogPIDFile holds the supervisor's PID while `cw node --watchdog` runs.
+const WatchdogPIDFile = "codewire-watchdog.pid"
+
+const (
+	probeTimeout      = 5 * time.Second
+	startupTimeout    = 10 * time.Second
+	minRestartBackoff = time.Second
+	maxRestartBackoff = 30 * time.Second
+	stopGracePeriod   = 10 * time.Second
+)
+
+// RunWatchdog supervises a node process: it starts it, probes its Unix socket
+// every cfg.Interval, and restarts it when it exits or stops answering
+// cfg.Failures probes in a row. Under systemd it reports READY=1 and
+// WATCHDOG=1 via sd_notify. It blocks until ctx is cancelled, then stops the
+// node gracefully.
+func RunWatchdog(ctx context.Context, dataDir string, cfg WatchdogConfig) error {
+	sock := filepath.Join(dataDir, "codewire.sock")
+
+	// `cw node stop` signals the supervisor (not just the node) via this file.
+	pidPath := filepath.Join(dataDir, WatchdogPIDFile)
+	if err := os.WriteFile(pidPath, []byte(strconv.Itoa(os.Getpid())), 0o644); err != nil {
+		return fmt.Errorf("writing watchdog pid file: %w", err)
+	}
+	defer os.Remove(pidPath)
+
+	backoff := minRestartBackoff
+	ready := false
+
+	for {
+		child := exec.Command(cfg.Exe, cfg.Args...)
+		child.Stdout = os.Stdout
+		child.Stderr = os.Stderr
+		// The supervisor owns sd_notify; the child must not talk to systemd.
+		child.Env = withoutEnv(os.Environ(), "NOTIFY_SOCKET", "WATCHDOG_USEC", "WATCHDOG_PID")
+		if err := child.Start(); err != nil {
+			return fmt.Errorf("starting node: %w", err)
+		}
+		slog.Info("watchdog started node", "pid", child.Process.Pid)
+
+		exited := make(chan error, 1)
+		go func() { exited <- child.Wait() }()
+
+		started := time.Now()
+		reason := superviseNode(ctx, sock, cfg, exited, &ready)
+
+		switch reason {
+		case stopRequested:
+			_ = child.Process.Signal(syscall.SIGTERM)
+			select {
+			case <-exited:
+			case <-time.After(stopGracePeriod):
+				_ = child.Process.Kill()
+				<-exited
+			}
+			_ = sdNotify("STOPPING=1")
+			return nil
+		case nodeHung:
+			slog.Error("node unresponsive, restarting", "pid", child.Process.Pid, "failed_probes", cfg.Failures)
+			_ = child.Process.Kill()
+			<-exited
+		case nodeExited:
+			slog.Error("node exited, restarting", "pid", child.Process.Pid)
+		}
+
+		// Back off on crash loops; reset once a node has stayed up a while.
+		if time.Since(started) > maxRestartBackoff {
+			backoff = minRestartBackoff
+		}
+		select {
+		case <-ctx.Done():
+			return nil
+		case <-time.After(backoff):
+		}
+		backoff *= 2
+		if backoff > maxRestartBackoff {
+			backoff = maxRestartBackoff
+		}
+	}
+}
+
+type superviseResult int
+
+const (
+	stopRequested superviseResult = iota
+	nodeHung
+	nodeExited
+)
+
+// superviseNode probes the running node until it exits, hangs, or ctx ends.
+func superviseNode(ctx context.Context, sock string, cfg WatchdogConfig, exited <-chan error, ready *bool) superviseResult {
+	ticker := time.NewTicker(cfg.Interval)
+	defer ticker.Stop()
+
+	deadline := time.Now().Add(startupTimeout)
+	failures := 0
+	up := false
+
+	for {
+		select {
+		case <-ctx.Done():
+			return stopRequested
+		case <-exited:
+			return nodeExited
+		case <-ticker.C:
+		}
+
+		if err := probeNode(sock); err != nil {
+			if !up && time.Now().Before(deadline) {
+				continue // still starting
+			}
+			failures++
+			slog.Warn("node health probe failed", "err", err, "failures", failures)
+			if failures >= cfg.Failures {
+				return nodeHung
+			}
+			continue
+		}
+
+		failures = 0
+		up = true
+		if !*ready {
+			*ready = true
+			_ = sdNotify("READY=1")
+		}
+		_ = sdNotify("WATCHDOG=1")
+	}
+}
+
+// probeNode sends a ListSessions request over the Unix socket and waits for
+// a well-formed reply, proving the accept loop and handlers are alive.
+func probeNode(sock string) error {
+	conn, err := net.DialTimeout("unix", sock, probeTimeout)
+	if err != nil {
+		return err
+	}
+	defer conn.Close()
+	_ = conn.SetDeadline(time.Now().Add(probeTimeout))
+
+	writer := connection.NewUnixWriter(conn)
+	reader := connection.NewUnixReader(conn)
+	if err := writer.SendRequest(&protocol.Request{Type: "ListSessions"}); err != nil {
+		return err
+	}
+	frame, err := reader.ReadFrame()
+	if err != nil {
+		return err
+	}
+	if frame == nil || frame.Type != protocol.FrameControl {
+		return fmt.Errorf("unexpected probe reply")
+	}
+	var resp protocol.Response
+	if err := json.Unmarshal(frame.Payload, &resp); err != nil {
+		return err
+	}
+	if resp.Type != "SessionList" {
+		return fmt.Errorf("unexpected probe reply type %q", resp.Type)
+	}
+	return nil
+}
+
+// runSystemdWatchdog pings systemd while the node's own socket keeps
+// answering. It only runs when systemd set WATCHDOG_USEC for this process.
+func runSystemdWatchdog(ctx context.Context, sock string) {
+	usec, err := strconv.ParseInt(os.Getenv("WATCHDOG_USEC"), 10, 64)
+	if err != nil || usec <= 0 {
+		return
+	}
+	if pid := os.Getenv("WATCHDOG_PID"); pid != "" && pid != strconv.Itoa(os.Getpid()) {
+		return
+	}
+	ticker := time.NewTicker(time.Duration(usec) * time.Microsecond / 2)
+	defer ticker.Stop()
+	for {
+		select {
+		case <-ctx.Done():
+			return
+		case <-ticker.C:
+			if probeNode(sock) == nil {
+				_ = sdNotify("WATCHDOG=1")
+			}
+		}
+	}
+}
+
+// sdNotify sends a state string to systemd's notification socket. It is a
+// no-op when NOTIFY_SOCKET is unset (i.e. not running under systemd).
+func sdNotify(state string) error {
+	addr := os.Getenv("NOTIFY_SOCKET")
+	if addr == "" {
+		return nil
+	}
+	if addr[0] == '@' {
+		addr = "\x00" + addr[1:] // abstract namespace
+	}
+	conn, err := net.DialUnix("unixgram", nil, &net.UnixAddr{Name: addr, Net: "unixgram"})
+	if err != nil {
+		return err
+	}
+	defer conn.Close()
+	_, err = conn.Write([]byte(state))
+	return err
+}
+
+// withoutEnv returns env with the named variables removed.
+func withoutEnv(env []string, names ...string) []string {
+	out := make([]string, 0, len(env))
+outer:
+	for _, e := range env {
+		for _, n := range names {
+			if len(e) > len(n) && e[:len(n)] == n && e[len(n)] == '=' {
+				continue outer
+			}
+		}
+		out = append(out, e)
+	}
+	return out
+}
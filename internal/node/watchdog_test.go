@@ -0,0 +1,58 @@
+package node
+
+import (
+	"net"
+	"path/filepath"
+	"testing"
+	"time"
+)
+
+func TestSdNotify(t *testing.T) {
+	addr := filepath.Join(t.TempDir(), "notify.sock")
+	ln, err := net.ListenUnixgram("unixgram", &net.UnixAddr{Name: addr, Net: "unixgram"})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ln.Close()
+	t.Setenv("NOTIFY_SOCKET", addr)
+
+	if err := sdNotify("READY=1"); err != nil {
+		t.Fatalf("sdNotify: %v", err)
+	}
+	_ = ln.SetReadDeadline(time.Now().Add(2 * time.Second))
+	buf := make([]byte, 64)
+	n, err := ln.Read(buf)
+	if err != nil {
+		t.Fatalf("reading notification: %v", err)
+	}
+	if got := string(buf[:n]); got != "READY=1" {
+		t.Fatalf("got %q, want READY=1", got)
+	}
+}
+
+func TestSdNotifyWithoutSystemd(t *testing.T) {
+	t.Setenv("NOTIFY_SOCKET", "")
+	if err := sdNotify("READY=1"); err != nil {
+		t.Fatalf("expected no-op without NOTIFY_SOCKET, got %v", err)
+	}
+}
+
+func TestWithoutEnv(t *testing.T) {
+	env := []string{"NOTIFY_SOCKET=/run/x", "NOTIFY_SOCKETS=keep", "HOME=/root", "WATCHDOG_USEC=100"}
+	got := withoutEnv(env, "NOTIFY_SOCKET", "WATCHDOG_USEC")
+	want := []string{"NOTIFY_SOCKETS=keep", "HOME=/root"}
+	if len(got) != len(want) {
+		t.Fatalf("got %v, want %v", got, want)
+	}
+	for i := range want {
+		if got[i] != want[i] {
+			t.Fatalf("got %v, want %v", got, want)
+		}
+	}
+}
+
+func TestProbeNodeNoSocket(t *testing.T) {
+	if err := probeNode(filepath.Join(t.TempDir(), "missing.sock")); err == nil {
+		t.Fatal("expected probe of missing socket to fail")
+	}
+}
@@ -24,6 +24,116 @@ type SessionInfo struct {
 	OutputBytes   *uint64  `json:"output_bytes,omitempty"`
 	LastOutputAt  *string  `json:"last_output_at,omitempty"`
 	AttachedCount int32    `json:"attached_count"`
+
+	// Per-session workspace directory created by Launch (see Request.Workspace).
+	Workspace string `json:"workspace,omitempty"`
+
+	// Git checkout of the working directory at launch.
+	GitRoot   string `json:"git_root,omitempty"`
+	GitBranch string `json:"git_branch,omitempty"`
+	GitCommit string `json:"git_commit,omitempty"`
+
+	// Notes left on the session, and why its status last changed.
+	Notes           []SessionNote `json:"notes,omitempty"`
+	StatusReason    string        `json:"status_reason,omitempty"`
+	StatusChangedAt string        `json:"status_changed_at,omitempty"`
+
+	// Bookmarks mark moments in the session's output (see AddBookmark).
+	Bookmarks []SessionBookmark `json:"bookmarks,omitempty"`
+
+	// ExpiresAt is when the next limit (ExpiryLimit: "max_runtime" or
+	// "idle") will end a running session; empty without limits.
+	ExpiresAt   string `json:"expires_at,omitempty"`
+	ExpiryLimit string `json:"expiry_limit,omitempty"`
+
+	// LogURL is where the node shipped the session's logs after it ended.
+	LogURL string `json:"log_url,omitempty"`
+
+	// ImportedFrom is the node:id the session originally ran as, for
+	// sessions added by ImportHistory.
+	ImportedFrom string `json:"imported_from,omitempty"`
+
+	// Term is the TERM the session was started with.
+	Term string `json:"term,omitempty"`
+
+	// Encoding is the character encoding the session's output is decoded
+	// from for logs and watch updates (see cw run --encoding).
+	Encoding string `json:"encoding,omitempty"`
+
+	// Mirrors are the extra sinks the session's output is copied to.
+	Mirrors []string `json:"mirrors,omitempty"`
+
+	// History is what the node records for the session: "all", "inputs"
+	// (output is not kept, so it cannot be replayed or read back) or
+	// "none".
+	History string `json:"history,omitempty"`
+	// RecordInput is set when the session's input is recorded.
+	RecordInput bool `json:"record_input,omitempty"`
+
+	// Priority is the session's priority class: "high", "normal" or
+	// "background".
+	Priority string `json:"priority,omitempty"`
+
+	// Token is the name of the scoped token that launched the session;
+	// empty for the node token.
+	Token string `json:"token,omitempty"`
+
+	// UndeliveredInput is input that was queued for the session but never
+	// written to its terminal because the session ended first.
+	UndeliveredInput []UndeliveredInput `json:"undelivered_input,omitempty"`
+}
+
+// UndeliveredInput is one chunk of input a session never received. Source
+// is "queued" (sent, attached or messaged input still waiting for the
+// terminal) or "stdin_data" (the launch's initial input). Preview is the
+// start of the input, kept only for sessions that record all history.
+type UndeliveredInput struct {
+	Source  string `json:"source"`
+	Bytes   int    `json:"bytes"`
+	Preview string `json:"preview,omitempty"`
+}
+
+// HistoryRecord is a finished session as moved between nodes by
+// ExportHistory and ImportHistory, optionally with its output log. Replicate
+// sends one for every session, running ones included.
+type HistoryRecord struct {
+	Node       string      `json:"node,omitempty"` // node the session ran on
+	Session    SessionInfo `json:"session"`
+	Transcript []byte      `json:"transcript,omitempty"`
+	// TranscriptTruncated is set when only the tail of a large log was
+	// exported.
+	TranscriptTruncated bool `json:"transcript_truncated,omitempty"`
+	// Offset is where Transcript starts in the session's output log: for
+	// Replicate, whose later records carry the rest of the log, and for
+	// exports sliced by bookmark.
+	Offset uint64 `json:"offset,omitempty"`
+	// When the session started and finished, and how long it ran (e.g.
+	// "1h2m3s").
+	StartedAt  string `json:"started_at,omitempty"`
+	FinishedAt string `json:"finished_at,omitempty"`
+	Duration   string `json:"duration,omitempty"`
+}
+
+// GuardrailBreach describes the node guardrail that refused a launch.
+type GuardrailBreach struct {
+	Guardrail string  `json:"guardrail"` // max_sessions, min_free_disk_mb or max_load_average
+	Limit     float64 `json:"limit"`
+	Value     float64 `json:"value"`
+	Detail    string  `json:"detail"`
+}
+
+// SessionNote is an annotation on a session (see Request.Notes).
+type SessionNote struct {
+	Time string `json:"time"`
+	Text string `json:"text"`
+}
+
+// SessionBookmark is a named moment in a session's output: Offset is the
+// size of its output log when the bookmark was added.
+type SessionBookmark struct {
+	Name   string `json:"name"`
+	Time   string `json:"time"`
+	Offset int64  `json:"offset"`
 }
 
 // Request is the union of all client-to-server control messages.
@@ -54,12 +164,160 @@ type Request struct {
 	// StripANSI controls ANSI escape stripping in Logs responses (default: true).
 	StripANSI *bool `json:"strip_ansi,omitempty"`
 
+	// Snapshot asks Launch to capture a working directory snapshot when the
+	// session exits, and asks GetStatus to return it. SnapshotCommand
+	// overrides the node's default snapshot command for Launch.
+	Snapshot        bool   `json:"snapshot,omitempty"`
+	SnapshotCommand string `json:"snapshot_command,omitempty"`
+
+	// Script is a shell command line for Launch, run through the node's
+	// configured shell instead of Command.
+	Script string `json:"script,omitempty"`
+
+	// DryRun asks KillAll and KillByTags to return the matching sessions
+	// (KillMatches) without killing them.
+	DryRun bool `json:"dry_run,omitempty"`
+
+	// Launches holds the individual Launch requests of a LaunchBatch, or of
+	// a ValidateLaunch checking a batch.
+	Launches []Request `json:"launches,omitempty"`
+
+	// Workspace asks Launch to run the session in a fresh per-session
+	// directory, cloned or copied from WorkspaceSource when set.
+	Workspace       bool   `json:"workspace,omitempty"`
+	WorkspaceSource string `json:"workspace_source,omitempty"`
+
+	// Inputs asks Logs for the recorded PTY input history instead of output.
+	Inputs bool `json:"inputs,omitempty"`
+
+	// Verify asks Logs to check the log against its CRC checkpoints and
+	// return a LogIntegrity response instead of log data.
+	Verify bool `json:"verify,omitempty"`
+
+	// Version is the release tag or download URL an Upgrade request installs.
+	Version string `json:"version,omitempty"`
+	// AllowDowngrade lets an Upgrade install a release older than the
+	// node's own.
+	AllowDowngrade bool `json:"allow_downgrade,omitempty"`
+
+	// Seq numbers a Ping; the node echoes it in the Pong.
+	Seq uint64 `json:"seq,omitempty"`
+
+	// Nonce is the random challenge an Identify request asks the node to
+	// sign.
+	Nonce []byte `json:"nonce,omitempty"`
+	// KeyShare is the client's X25519 public key for an Identify handshake.
+	// The node answers with its own share and signs
+	// auth.HandshakeMessage over both instead of the bare nonce.
+	KeyShare []byte `json:"key_share,omitempty"`
+	// SealedToken carries an Authenticate request's token, sealed with the
+	// key of the handshake just made on the same connection.
+	SealedToken []byte `json:"sealed_token,omitempty"`
+
+	// MaxRate caps the WatchUpdates per second a WatchSession sends; output
+	// in between is coalesced. Zero uses the node's default of 20.
+	MaxRate uint32 `json:"max_rate,omitempty"`
+	// ResumeFrom continues a WatchSession from the Cursor of the last
+	// WatchUpdate a watcher got, instead of replaying history: the output
+	// after it is sent once, then live output.
+	ResumeFrom *uint64 `json:"resume_from,omitempty"`
+
+	// Notes are attached to a session by Launch, or appended by AddNote.
+	Notes []string `json:"notes,omitempty"`
+
+	// MaxRuntime and IdleTimeout (Go durations, e.g. "2h") limit a launched
+	// session; it is killed when either is reached.
+	MaxRuntime  string `json:"max_runtime,omitempty"`
+	IdleTimeout string `json:"idle_timeout,omitempty"`
+
+	// Term sets TERM for a launched session; empty uses the node's [launch]
+	// term, then the node's own TERM.
+	Term string `json:"term,omitempty"`
+
+	// Encoding is the character encoding a launched session writes (a
+	// WHATWG label such as "latin1" or "shift_jis"); empty takes it from
+	// the session's locale, else UTF-8.
+	Encoding string `json:"encoding,omitempty"`
+
+	// Mirrors copy a launched session's output to extra sinks:
+	// "file:/abs/path" appends to a file, "cmd:<command>" pipes to a
+	// command's stdin. AddMirror adds one to a running session.
+	Mirrors []string `json:"mirrors,omitempty"`
+
+	// History limits what the node records for a launched session:
+	// "inputs" keeps only PTY input, "none" keeps neither input nor output.
+	// Empty or "all" records everything but input.
+	History string `json:"history,omitempty"`
+	// RecordInput keeps a launched session's PTY input, attach keystrokes
+	// included, for cw replay.
+	RecordInput bool `json:"record_input,omitempty"`
+
+	// Priority is a launched session's priority class ("high", "normal" or
+	// "background"; empty is normal), or the new class for SetPriority.
+	Priority string `json:"priority,omitempty"`
+
+	// Colors is the attaching terminal's color depth ("truecolor", "256" or
+	// "16"); the node downconverts SGR colors in output for the latter two.
+	Colors string `json:"colors,omitempty"`
+
+	// Extra is how far an Extend request pushes back a session's limits
+	// (Go duration, e.g. "1h").
+	Extra string `json:"extra,omitempty"`
+
+	// Signal turns Kill into "send this signal" (e.g. "INT", "SIGTERM",
+	// "2"). The signal goes to the session's foreground process group and
+	// the node replies Signaled; the session is not marked killed.
+	Signal string `json:"signal,omitempty"`
+
+	// Since (RFC 3339) limits ExportHistory to sessions created at or after
+	// it; Transcripts includes each session's output log, in ExportHistory
+	// and in Backup.
+	Since       string `json:"since,omitempty"`
+	Transcripts bool   `json:"transcripts,omitempty"`
+
+	// Bookmark names the bookmark AddBookmark adds (empty picks "mark-N").
+	// FromBookmark and ToBookmark slice the output of Logs, and the
+	// transcripts of ExportHistory, to what was written between two
+	// bookmarks; either may be empty for the start or end of the log.
+	Bookmark     string `json:"bookmark,omitempty"`
+	FromBookmark string `json:"from_bookmark,omitempty"`
+	ToBookmark   string `json:"to_bookmark,omitempty"`
+
+	// QueryEvents reads the node's event store, filtered by ID, Tags,
+	// EventTypes and Since, and Until (RFC 3339, exclusive). It returns the
+	// newest Limit matches, or with After the Limit events following that
+	// sequence number.
+	Until string `json:"until,omitempty"`
+	After uint64 `json:"after,omitempty"`
+
+	// Record is the session an ImportHistory request adds to the node.
+	Record *HistoryRecord `json:"record,omitempty"`
+
+	// Offsets is how much of each session's output log a standby already
+	// holds, for Replicate; only what follows is sent.
+	Offsets map[uint32]uint64 `json:"offsets,omitempty"`
+	// Replica answers ListSessions, GetStatus and Logs from the node's
+	// copy of its primary (see [standby]) instead of its own sessions.
+	Replica bool `json:"replica,omitempty"`
+
+	// Query is what FindSessions fuzzy-matches against session names, tags,
+	// prompts and working directories; Limit caps the matches returned
+	// (zero returns them all).
+	Query string `json:"query,omitempty"`
+	Limit uint32 `json:"limit,omitempty"`
+
 	// New fields for enriched protocol.
 	Tags           []string `json:"tags,omitempty"`
 	EventTypes     []string `json:"event_types,omitempty"`
 	SubscriptionID *uint64  `json:"subscription_id,omitempty"`
 	Condition      string   `json:"condition,omitempty"` // "any", "all"
 	TimeoutSeconds *uint64  `json:"timeout_seconds,omitempty"`
+	// TimeoutMs is how long, in milliseconds from when the node receives
+	// the request, the requester keeps waiting for the response. The node
+	// cuts waits and launch preparation short when it passes, and refuses a
+	// request whose time has already run out (0). Being relative, it does
+	// not depend on the two machines' clocks agreeing.
+	TimeoutMs *uint64 `json:"timeout_ms,omitempty"`
 
 	// KV fields.
 	Namespace string `json:"namespace,omitempty"`
@@ -111,11 +369,75 @@ type Response struct {
 	Status     string         `json:"status,omitempty"`
 	Output     *string        `json:"output,omitempty"`
 	Message    string         `json:"message,omitempty"`
+	ExitCode   *int           `json:"exit_code,omitempty"`
+	Snapshot   *string        `json:"snapshot,omitempty"`
+	Inputs     *[]InputRecord `json:"inputs,omitempty"`
+	Warning    string         `json:"warning,omitempty"`
+	Version    string         `json:"version,omitempty"`
+
+	// Results holds one entry per launch of a LaunchBatch, in order.
+	Results *[]BatchResult `json:"results,omitempty"`
+
+	// Plans holds one entry per launch checked by ValidateLaunch, in order.
+	Plans []LaunchPlan `json:"plans,omitempty"`
+
+	// Bandwidth holds remote client traffic totals for NodeStatus.
+	Bandwidth *[]BandwidthStat `json:"bandwidth,omitempty"`
+	// Relay is the node's relay connection, for NodeStatus. Nil when no
+	// relay is configured.
+	Relay *RelayStatus `json:"relay,omitempty"`
+	// Lossy is set on LogData and WatchUpdate when some of the output was
+	// not valid in the session's encoding and was replaced with U+FFFD.
+	// The log on the node keeps the original bytes.
+	Lossy bool `json:"lossy,omitempty"`
+
+	// Standby is how far the node's copy of its primary is, for NodeStatus
+	// and replica ListSessions. Nil unless the node is a standby.
+	Standby *StandbyStatus `json:"standby,omitempty"`
+
+	// Seq echoes the Ping a Pong answers. WatchUpdates are numbered from 1
+	// so watchers can tell when one is missing.
+	Seq uint64 `json:"seq,omitempty"`
+	// Cursor is the session's output offset just past a WatchUpdate's
+	// output; it stays valid across connections, so pass it back as
+	// ResumeFrom to continue a watch. Missed counts the output bytes before
+	// this update that a resumed watch could not replay, because the
+	// session's log is not kept on the node.
+	Cursor uint64 `json:"cursor,omitempty"`
+	Missed uint64 `json:"missed,omitempty"`
+
+	// ExpiresAt and ExpiryLimit are pushed to attached clients in Expiry
+	// responses whenever the session's next limit moves, and returned by
+	// Extend (see SessionInfo).
+	ExpiresAt   string `json:"expires_at,omitempty"`
+	ExpiryLimit string `json:"expiry_limit,omitempty"`
+
+	// Guardrail is set on the Error answering a Launch that a node guardrail
+	// refused.
+	Guardrail *GuardrailBreach `json:"guardrail,omitempty"`
+
+	// Record is one exported session, streamed as HistoryRecord responses
+	// before a final HistoryEnd carrying Count.
+	Record *HistoryRecord `json:"record,omitempty"`
+
+	// Bookmark answers AddBookmark with the bookmark added.
+	Bookmark *SessionBookmark `json:"bookmark,omitempty"`
+
+	// NodeName, PublicKey and Signature answer Identify: the node's Ed25519
+	// signature over auth.IdentityMessage(NodeName, nonce), or over
+	// auth.HandshakeMessage when the request carried a key share, in which
+	// case KeyShare is the node's.
+	NodeName  string `json:"node_name,omitempty"`
+	PublicKey []byte `json:"public_key,omitempty"`
+	Signature []byte `json:"signature,omitempty"`
+	KeyShare  []byte `json:"key_share,omitempty"`
 
 	// Subscribe/Event fields.
 	SubscriptionID *uint64       `json:"subscription_id,omitempty"`
 	SessionID      *uint32       `json:"session_id,omitempty"`
 	Event          *SessionEvent `json:"event,omitempty"`
+	// Events answers QueryEvents, oldest first.
+	Events []SessionEvent `json:"events,omitempty"`
 
 	// KV fields.
 	Value   []byte    `json:"value,omitempty"`
@@ -143,11 +465,84 @@ type MessageResponse struct {
 	RequestID string `json:"request_id,omitempty"`
 }
 
-// SessionEvent is a typed event pushed to subscribers.
+// SessionEvent is a typed event pushed to subscribers. Events returned by
+// QueryEvents also carry their sequence number in the node's event store,
+// their session and its tags at the time.
 type SessionEvent struct {
 	Timestamp string          `json:"timestamp"`
 	EventType string          `json:"type"`
 	Data      json.RawMessage `json:"data"`
+	Seq       uint64          `json:"seq,omitempty"`
+	SessionID uint32          `json:"session_id,omitempty"`
+	Tags      []string        `json:"tags,omitempty"`
+}
+
+// InputRecord is one chunk of input written to a session's PTY.
+type InputRecord struct {
+	Timestamp string `json:"timestamp"`
+	Data      []byte `json:"data"`
+}
+
+// BatchResult is the outcome of one launch in a LaunchBatch: ID on success,
+// Error otherwise.
+type BatchResult struct {
+	ID    *uint32 `json:"id,omitempty"`
+	Name  string  `json:"name,omitempty"`
+	Error string  `json:"error,omitempty"`
+}
+
+// LaunchPlan is what a launch would do, as reported by ValidateLaunch: the
+// binary and arguments the node would start and where, or the Problems
+// that would make it refuse. A guardrail breach that would queue the launch
+// (on_breach = "queue") is reported in Guardrail with Queued set rather
+// than as a problem.
+type LaunchPlan struct {
+	Name        string           `json:"name,omitempty"`
+	OK          bool             `json:"ok"`
+	Problems    []string         `json:"problems,omitempty"`
+	Guardrail   *GuardrailBreach `json:"guardrail,omitempty"`
+	Queued      bool             `json:"queued,omitempty"`
+	Command     string           `json:"command,omitempty"`
+	Path        string           `json:"path,omitempty"`
+	Argv        []string         `json:"argv,omitempty"`
+	WorkingDir  string           `json:"working_dir,omitempty"`
+	Workspace   bool             `json:"workspace,omitempty"`
+	Term        string           `json:"term,omitempty"`
+	Encoding    string           `json:"encoding,omitempty"`
+	History     string           `json:"history,omitempty"`
+	Priority    string           `json:"priority,omitempty"`
+	MaxRuntime  string           `json:"max_runtime,omitempty"`
+	IdleTimeout string           `json:"idle_timeout,omitempty"`
+}
+
+// BandwidthStat is the traffic total for one open remote connection
+// (Kind "connection") or for one auth token since the node started
+// (Kind "token"). Tokens are identified by a short hash, never the token.
+type BandwidthStat struct {
+	Kind        string `json:"kind"`
+	Token       string `json:"token"`
+	RemoteAddr  string `json:"remote_addr,omitempty"`
+	ConnectedAt string `json:"connected_at"`
+	BytesSent   uint64 `json:"bytes_sent"`
+	BytesRecv   uint64 `json:"bytes_received"`
+	LimitBps    int64  `json:"limit_bytes_per_sec,omitempty"`
+}
+
+// RelayStatus is the state of a node's connection to its relay.
+type RelayStatus struct {
+	URL         string `json:"url"`
+	Connected   bool   `json:"connected"`
+	Since       string `json:"since,omitempty"` // RFC 3339; when it last connected or dropped
+	LastError   string `json:"last_error,omitempty"`
+	Disconnects int    `json:"disconnects"`
+}
+
+// StandbyStatus is the state of a standby node's copy of its primary.
+type StandbyStatus struct {
+	Primary   string `json:"primary"`
+	SyncedAt  string `json:"synced_at,omitempty"` // RFC 3339; last complete sync
+	LastError string `json:"last_error,omitempty"`
+	Sessions  int    `json:"sessions"`
 }
 
 // KVPair is a key-value entry for list responses.
@@ -8,6 +8,7 @@ import (
 	"log/slog"
 	"net/http"
 	"os/exec"
+	"sync"
 	"time"
 
 	"github.com/creack/pty"
@@ -19,17 +20,72 @@ type AgentConfig struct {
 	RelayURL  string // e.g. "https://relay.codewire.sh"
 	NodeName  string
 	NodeToken string
+	// Status, if set, is kept up to date with the connection state.
+	Status *AgentStatus
+}
+
+// AgentStatus tracks the relay agent's connection. It is safe for
+// concurrent use.
+type AgentStatus struct {
+	mu    sync.Mutex
+	state AgentState
+}
+
+// AgentState is a snapshot of an AgentStatus.
+type AgentState struct {
+	Connected   bool
+	Since       time.Time // when the agent last connected or dropped
+	LastError   string    // why the last attempt or connection failed
+	Disconnects int       // failed attempts and dropped connections
+}
+
+// Snapshot returns the current state.
+func (s *AgentStatus) Snapshot() AgentState {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	return s.state
+}
+
+func (s *AgentStatus) connected() {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	s.state.Connected = true
+	s.state.Since = time.Now()
+}
+
+// disconnected records err and reports whether the agent had been
+// connected.
+func (s *AgentStatus) disconnected(err error) bool {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	was := s.state.Connected
+	if was || s.state.Since.IsZero() {
+		s.state.Since = time.Now()
+	}
+	s.state.Connected = false
+	s.state.LastError = err.Error()
+	s.state.Disconnects++
+	return was
 }
 
 // RunAgent connects to the relay and handles incoming SSH requests.
-// It reconnects automatically with exponential backoff.
+// It reconnects automatically with exponential backoff, starting over from
+// one second after a connection that was up, so a relay restart is picked
+// up quickly.
 func RunAgent(ctx context.Context, cfg AgentConfig) {
+	status := cfg.Status
+	if status == nil {
+		status = &AgentStatus{}
+	}
 	backoff := time.Second
 	for {
-		err := runAgentOnce(ctx, cfg)
+		err := runAgentOnce(ctx, cfg, status)
 		if ctx.Err() != nil {
 			return
 		}
+		if status.disconnected(err) {
+			backoff = time.Second
+		}
 		slog.Warn("relay agent disconnected", "err", err, "retry_in", backoff)
 		select {
 		case <-time.After(backoff):
@@ -42,7 +98,7 @@ func RunAgent(ctx context.Context, cfg AgentConfig) {
 	}
 }
 
-func runAgentOnce(ctx context.Context, cfg AgentConfig) error {
+func runAgentOnce(ctx context.Context, cfg AgentConfig, status *AgentStatus) error {
 	wsURL := toWS(cfg.RelayURL) + "/node/connect"
 	ws, _, err := websocket.Dial(ctx, wsURL, &websocket.DialOptions{
 		HTTPHeader: http.Header{"Authorization": {"Bearer " + cfg.NodeToken}},
@@ -53,6 +109,7 @@ func runAgentOnce(ctx context.Context, cfg AgentConfig) error {
 	defer ws.CloseNow()
 
 	slog.Info("relay agent connected", "relay", cfg.RelayURL, "node", cfg.NodeName)
+	status.connected()
 
 	for {
 		_, data, err := ws.Read(ctx)
@@ -0,0 +1,60 @@
+package relay_test
+
+import (
+	"context"
+	"net/http"
+	"net/http/httptest"
+	"sync/atomic"
+	"testing"
+	"time"
+
+	"nhooyr.io/websocket"
+
+	"github.com/codewiresh/codewire/internal/relay"
+)
+
+func TestAgentStatusTracksConnection(t *testing.T) {
+	drop := make(chan struct{})
+	var conns atomic.Int32
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		ws, err := websocket.Accept(w, r, nil)
+		if err != nil {
+			return
+		}
+		// Only the first connection is dropped.
+		if conns.Add(1) == 1 {
+			<-drop
+		} else {
+			_, _, _ = ws.Read(context.Background())
+		}
+		ws.CloseNow()
+	}))
+	defer srv.Close()
+
+	ctx, cancel := context.WithCancel(context.Background())
+	defer cancel()
+	status := &relay.AgentStatus{}
+	go relay.RunAgent(ctx, relay.AgentConfig{RelayURL: srv.URL, NodeName: "n1", NodeToken: "tok", Status: status})
+
+	waitState := func(want bool) relay.AgentState {
+		t.Helper()
+		deadline := time.Now().Add(5 * time.Second)
+		for time.Now().Before(deadline) {
+			if st := status.Snapshot(); st.Connected == want {
+				return st
+			}
+			time.Sleep(20 * time.Millisecond)
+		}
+		t.Fatalf("agent never reached connected=%v: %+v", want, status.Snapshot())
+		return relay.AgentState{}
+	}
+
+	waitState(true)
+	close(drop)
+	st := waitState(false)
+	if st.Disconnects != 1 || st.LastError == "" || st.Since.IsZero() {
+		t.Fatalf("expected one recorded drop, got %+v", st)
+	}
+	// The backoff starts over after a connection that was up.
+	waitState(true)
+}
@@ -0,0 +1,148 @@
+package session
+
+import (
+	"fmt"
+	"io"
+	"os"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// maxBookmarks bounds the bookmarks kept per session.
+const maxBookmarks = 256
+
+// SessionBookmark marks a moment in a session's output, e.g. dropped with
+// Ctrl+B m while attached. Offset is the size of the session's output log
+// when it was added, so logs and exports can be sliced between bookmarks.
+type SessionBookmark struct {
+	Name   string    `json:"name"`
+	Time   time.Time `json:"time"`
+	Offset int64     `json:"offset"`
+}
+
+// AddBookmark marks the current end of a session's output log under name,
+// or "mark-N" when name is empty.
+func (m *SessionManager) AddBookmark(id uint32, name string) (SessionBookmark, error) {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return SessionBookmark{}, fmt.Errorf("session %d not found", id)
+	}
+	if name != "" && !namePattern.MatchString(name) {
+		return SessionBookmark{}, fmt.Errorf("invalid bookmark name %q: must be 1-32 alphanumeric characters or hyphens, starting with alphanumeric", name)
+	}
+
+	var offset int64
+	if fi, err := os.Stat(sess.logPath); err == nil {
+		offset = fi.Size()
+	}
+
+	sess.mu.Lock()
+	defer sess.mu.Unlock()
+	if sess.Meta.History != "" {
+		return SessionBookmark{}, fmt.Errorf("session %d's output is not recorded (history: %s)", id, sess.Meta.History)
+	}
+	if len(sess.Meta.Bookmarks) >= maxBookmarks {
+		return SessionBookmark{}, fmt.Errorf("session %d already has %d bookmarks", id, maxBookmarks)
+	}
+	if name == "" {
+		for n := len(sess.Meta.Bookmarks) + 1; ; n++ {
+			name = fmt.Sprintf("mark-%d", n)
+			if findBookmark(sess.Meta.Bookmarks, name) < 0 {
+				break
+			}
+		}
+	} else if findBookmark(sess.Meta.Bookmarks, name) >= 0 {
+		return SessionBookmark{}, fmt.Errorf("session %d already has a bookmark named %q", id, name)
+	}
+	b := SessionBookmark{Name: name, Time: time.Now().UTC(), Offset: offset}
+	sess.Meta.Bookmarks = append(sess.Meta.Bookmarks, b)
+	m.triggerPersist()
+	return b, nil
+}
+
+// BookmarkRange resolves bookmark names to a byte range [start, end) of a
+// session's output log. An empty from starts at the beginning; an empty to
+// runs to the end, reported as end -1.
+func (m *SessionManager) BookmarkRange(id uint32, from, to string) (start, end int64, err error) {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return 0, 0, fmt.Errorf("session %d not found", id)
+	}
+	sess.mu.Lock()
+	defer sess.mu.Unlock()
+	return bookmarkRange(sess.Meta.Bookmarks, id, from, to)
+}
+
+func bookmarkRange(bookmarks []SessionBookmark, id uint32, from, to string) (start, end int64, err error) {
+	end = -1
+	if from != "" {
+		i := findBookmark(bookmarks, from)
+		if i < 0 {
+			return 0, 0, fmt.Errorf("session %d has no bookmark %q", id, from)
+		}
+		start = bookmarks[i].Offset
+	}
+	if to != "" {
+		i := findBookmark(bookmarks, to)
+		if i < 0 {
+			return 0, 0, fmt.Errorf("session %d has no bookmark %q", id, to)
+		}
+		end = bookmarks[i].Offset
+		if end < start {
+			return 0, 0, fmt.Errorf("bookmark %q comes before %q", to, from)
+		}
+	}
+	return start, end, nil
+}
+
+func findBookmark(bookmarks []SessionBookmark, name string) int {
+	for i, b := range bookmarks {
+		if b.Name == name {
+			return i
+		}
+	}
+	return -1
+}
+
+// readRange reads bytes [start, end) of the file at path (to the end when
+// end is negative), and returns where the data starts: ranges longer than
+// limit are read from their tail.
+func readRange(path string, start, end, limit int64) ([]byte, int64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, start, err
+	}
+	defer f.Close()
+	if end < 0 {
+		info, err := f.Stat()
+		if err != nil {
+			return nil, start, err
+		}
+		end = info.Size()
+	}
+	if end-start > limit {
+		start = end - limit
+	}
+	if _, err := f.Seek(start, io.SeekStart); err != nil {
+		return nil, start, err
+	}
+	data, err := io.ReadAll(io.LimitReader(f, max(end-start, 0)))
+	return data, start, err
+}
+
+// bookmarksInfo converts bookmarks for SessionInfo. Callers hold s.mu.
+func bookmarksInfo(bookmarks []SessionBookmark) []protocol.SessionBookmark {
+	if len(bookmarks) == 0 {
+		return nil
+	}
+	out := make([]protocol.SessionBookmark, len(bookmarks))
+	for i, b := range bookmarks {
+		out[i] = protocol.SessionBookmark{Name: b.Name, Time: b.Time.Format(time.RFC3339), Offset: b.Offset}
+	}
+	return out
+}
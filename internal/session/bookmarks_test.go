@@ -0,0 +1,99 @@
+package session
+
+import (
+	"os"
+	"testing"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+func TestBookmarks(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	id, err := sm.Launch([]string{"sleep", "30"}, t.TempDir(), nil, nil, "")
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	logPath, _ := sm.LogPath(id)
+	appendLog := func(s string) {
+		t.Helper()
+		f, err := os.OpenFile(logPath, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o644)
+		if err != nil {
+			t.Fatal(err)
+		}
+		defer f.Close()
+		if _, err := f.WriteString(s); err != nil {
+			t.Fatal(err)
+		}
+	}
+
+	appendLog("setup\n")
+	if b, err := sm.AddBookmark(id, "fix-attempt-1"); err != nil || b.Offset != 6 {
+		t.Fatalf("AddBookmark: %+v, %v", b, err)
+	}
+	appendLog("first try\n")
+	if b, err := sm.AddBookmark(id, ""); err != nil || b.Name != "mark-2" {
+		t.Fatalf("AddBookmark without a name: %+v, %v", b, err)
+	}
+	appendLog("wrap up\n")
+	if _, err := sm.AddBookmark(id, "fix-attempt-1"); err == nil {
+		t.Fatal("expected a duplicate bookmark name to be rejected")
+	}
+	if _, err := sm.AddBookmark(id, "no spaces"); err == nil {
+		t.Fatal("expected an invalid bookmark name to be rejected")
+	}
+
+	for _, tc := range []struct {
+		from, to string
+		want     string
+	}{
+		{"fix-attempt-1", "", "first try\nwrap up\n"},
+		{"fix-attempt-1", "mark-2", "first try\n"},
+		{"", "fix-attempt-1", "setup\n"},
+	} {
+		start, end, err := sm.BookmarkRange(id, tc.from, tc.to)
+		if err != nil {
+			t.Fatalf("BookmarkRange(%q, %q): %v", tc.from, tc.to, err)
+		}
+		data, _, err := readRange(logPath, start, end, maxHistoryTranscript)
+		if err != nil || string(data) != tc.want {
+			t.Errorf("range %q..%q = %q, %v; want %q", tc.from, tc.to, data, err, tc.want)
+		}
+	}
+	if _, _, err := sm.BookmarkRange(id, "mark-2", "fix-attempt-1"); err == nil {
+		t.Fatal("expected a reversed range to be rejected")
+	}
+	if _, _, err := sm.BookmarkRange(id, "missing", ""); err == nil {
+		t.Fatal("expected an unknown bookmark to be rejected")
+	}
+
+	if err := sm.Kill(id); err != nil {
+		t.Fatalf("Kill: %v", err)
+	}
+	var records []protocol.HistoryRecord
+	err = sm.ExportHistory(time.Time{}, true, "fix-attempt-1", "mark-2", func(rec protocol.HistoryRecord) error {
+		records = append(records, rec)
+		return nil
+	})
+	if err != nil || len(records) != 1 {
+		t.Fatalf("ExportHistory: %d records, %v", len(records), err)
+	}
+	if rec := records[0]; string(rec.Transcript) != "first try\n" || rec.Offset != 6 {
+		t.Fatalf("sliced transcript %q at %d", rec.Transcript, rec.Offset)
+	}
+	if rec := records[0]; rec.StartedAt == "" || rec.FinishedAt == "" || rec.Duration == "" {
+		t.Fatalf("missing timing in exported record: %q %q %q", rec.StartedAt, rec.FinishedAt, rec.Duration)
+	}
+
+	imported, err := sm.ImportHistory(records[0])
+	if err != nil {
+		t.Fatalf("ImportHistory: %v", err)
+	}
+	start, end, err := sm.BookmarkRange(imported, "fix-attempt-1", "mark-2")
+	if err != nil || start != 0 || end != int64(len("first try\n")) {
+		t.Fatalf("imported bookmarks: %d..%d, %v", start, end, err)
+	}
+}
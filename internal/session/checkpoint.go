@@ -0,0 +1,186 @@
+package session
+
+import (
+	"bufio"
+	"encoding/json"
+	"fmt"
+	"hash/crc32"
+	"io"
+	"os"
+)
+
+// checkpointInterval is how many bytes of output are written between
+// checkpoints in a session's output.idx sidecar.
+const checkpointInterval = 64 * 1024
+
+// checkpointFile is the sidecar index stored next to output.log.
+const checkpointFile = "output.idx"
+
+// Checkpoint records that output.log held Offset bytes whose CRC-32 (IEEE)
+// was CRC at the time it was written.
+type Checkpoint struct {
+	Offset int64  `json:"offset"`
+	CRC    uint32 `json:"crc32"`
+}
+
+// checkpointWriter tracks a running CRC over everything written to a session
+// log and appends a Checkpoint to the sidecar every checkpointInterval bytes.
+type checkpointWriter struct {
+	idx    *os.File
+	offset int64
+	crc    uint32
+	last   int64 // offset of the last written checkpoint
+}
+
+// newCheckpointWriter opens (creating if needed) the sidecar at path.
+func newCheckpointWriter(path string) (*checkpointWriter, error) {
+	f, err := os.OpenFile(path, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o644)
+	if err != nil {
+		return nil, fmt.Errorf("opening checkpoint index: %w", err)
+	}
+	return &checkpointWriter{idx: f}, nil
+}
+
+// Track records that p was appended to the log.
+func (c *checkpointWriter) Track(p []byte) {
+	c.crc = crc32.Update(c.crc, crc32.IEEETable, p)
+	c.offset += int64(len(p))
+	if c.offset-c.last >= checkpointInterval {
+		c.write()
+	}
+}
+
+// Close writes a final checkpoint covering all tracked output.
+func (c *checkpointWriter) Close() error {
+	if c.offset != c.last {
+		c.write()
+	}
+	return c.idx.Close()
+}
+
+func (c *checkpointWriter) write() {
+	line, _ := json.Marshal(Checkpoint{Offset: c.offset, CRC: c.crc})
+	c.idx.Write(append(line, '\n'))
+	c.last = c.offset
+}
+
+// LogIntegrity is the result of checking a log against its checkpoints.
+type LogIntegrity struct {
+	Checkpoints int   // checkpoints found in the sidecar
+	Expected    int64 // offset of the last checkpoint
+	Verified    int64 // bytes covered by the last matching checkpoint (VerifyLog only)
+	Size        int64 // current size of output.log
+	Truncated   bool  // log is shorter than the last checkpoint
+	CorruptAt   int64 // offset of the first mismatching checkpoint, or -1
+}
+
+// OK reports whether no truncation or corruption was detected.
+func (li LogIntegrity) OK() bool { return !li.Truncated && li.CorruptAt < 0 }
+
+// String summarises the check for display.
+func (li LogIntegrity) String() string {
+	switch {
+	case li.CorruptAt >= 0:
+		return fmt.Sprintf("log corrupted: checksum mismatch between bytes %d and %d", li.Verified, li.CorruptAt)
+	case li.Truncated:
+		return fmt.Sprintf("log truncated: %d bytes on disk, checkpoints expect %d", li.Size, li.Expected)
+	default:
+		return fmt.Sprintf("log ok: %d bytes, %d checkpoints", li.Size, li.Checkpoints)
+	}
+}
+
+// readCheckpoints loads every checkpoint from the sidecar at path, skipping
+// corrupt lines.
+func readCheckpoints(path string) ([]Checkpoint, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		if os.IsNotExist(err) {
+			return nil, nil
+		}
+		return nil, err
+	}
+	defer f.Close()
+
+	var cps []Checkpoint
+	scanner := bufio.NewScanner(f)
+	for scanner.Scan() {
+		var cp Checkpoint
+		if json.Unmarshal(scanner.Bytes(), &cp) != nil {
+			continue
+		}
+		cps = append(cps, cp)
+	}
+	return cps, scanner.Err()
+}
+
+// CheckTruncation compares the log size with the last checkpoint only. It
+// is cheap enough to run on every Logs request.
+func CheckTruncation(logPath, idxPath string) (LogIntegrity, error) {
+	li := LogIntegrity{CorruptAt: -1}
+	fi, err := os.Stat(logPath)
+	if err != nil && !os.IsNotExist(err) {
+		return li, err
+	}
+	if fi != nil {
+		li.Size = fi.Size()
+	}
+	cps, err := readCheckpoints(idxPath)
+	if err != nil {
+		return li, err
+	}
+	li.Checkpoints = len(cps)
+	if len(cps) > 0 {
+		li.Expected = cps[len(cps)-1].Offset
+		li.Truncated = li.Size < li.Expected
+	}
+	return li, nil
+}
+
+// VerifyLog recomputes the CRC of the log and checks it against every
+// checkpoint in the sidecar.
+func VerifyLog(logPath, idxPath string) (LogIntegrity, error) {
+	li, err := CheckTruncation(logPath, idxPath)
+	if err != nil {
+		return li, err
+	}
+	cps, err := readCheckpoints(idxPath)
+	if err != nil || len(cps) == 0 {
+		return li, err
+	}
+
+	f, err := os.Open(logPath)
+	if err != nil {
+		if os.IsNotExist(err) {
+			return li, nil
+		}
+		return li, err
+	}
+	defer f.Close()
+
+	var crc uint32
+	var pos int64
+	buf := make([]byte, 32*1024)
+	for _, cp := range cps {
+		if cp.Offset > li.Size {
+			break // reported as truncation
+		}
+		for pos < cp.Offset {
+			want := cp.Offset - pos
+			if want > int64(len(buf)) {
+				want = int64(len(buf))
+			}
+			n, rErr := io.ReadFull(f, buf[:want])
+			crc = crc32.Update(crc, crc32.IEEETable, buf[:n])
+			pos += int64(n)
+			if rErr != nil {
+				return li, rErr
+			}
+		}
+		if crc != cp.CRC {
+			li.CorruptAt = cp.Offset
+			return li, nil
+		}
+		li.Verified = cp.Offset
+	}
+	return li, nil
+}
@@ -0,0 +1,102 @@
+package session
+
+import (
+	"bytes"
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+// writeCheckpointedLog writes data to output.log in dir through a
+// checkpointWriter and returns the log and index paths.
+func writeCheckpointedLog(t *testing.T, dir string, data []byte) (string, string) {
+	t.Helper()
+	logPath := filepath.Join(dir, "output.log")
+	idxPath := filepath.Join(dir, checkpointFile)
+	cw, err := newCheckpointWriter(idxPath)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if err := os.WriteFile(logPath, data, 0o644); err != nil {
+		t.Fatal(err)
+	}
+	for off := 0; off < len(data); off += 4096 {
+		end := off + 4096
+		if end > len(data) {
+			end = len(data)
+		}
+		cw.Track(data[off:end])
+	}
+	if err := cw.Close(); err != nil {
+		t.Fatal(err)
+	}
+	return logPath, idxPath
+}
+
+func TestVerifyLogIntact(t *testing.T) {
+	data := bytes.Repeat([]byte("output line\n"), 20000) // ~240 KiB
+	logPath, idxPath := writeCheckpointedLog(t, t.TempDir(), data)
+
+	li, err := VerifyLog(logPath, idxPath)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !li.OK() {
+		t.Fatalf("expected intact log, got %s", li)
+	}
+	if li.Checkpoints < 3 {
+		t.Fatalf("expected several checkpoints, got %d", li.Checkpoints)
+	}
+	if li.Verified != int64(len(data)) {
+		t.Fatalf("expected %d bytes verified, got %d", len(data), li.Verified)
+	}
+}
+
+func TestVerifyLogTruncated(t *testing.T) {
+	data := bytes.Repeat([]byte("x"), 3*checkpointInterval)
+	logPath, idxPath := writeCheckpointedLog(t, t.TempDir(), data)
+	if err := os.Truncate(logPath, int64(len(data)-100)); err != nil {
+		t.Fatal(err)
+	}
+
+	li, err := CheckTruncation(logPath, idxPath)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !li.Truncated {
+		t.Fatalf("expected truncation to be detected, got %s", li)
+	}
+
+	li, err = VerifyLog(logPath, idxPath)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !li.Truncated || li.CorruptAt >= 0 {
+		t.Fatalf("expected truncated but uncorrupted prefix, got %s", li)
+	}
+	if li.Verified != 2*checkpointInterval {
+		t.Fatalf("expected prefix of %d bytes verified, got %d", 2*checkpointInterval, li.Verified)
+	}
+}
+
+func TestVerifyLogCorrupted(t *testing.T) {
+	data := bytes.Repeat([]byte("y"), 2*checkpointInterval)
+	logPath, idxPath := writeCheckpointedLog(t, t.TempDir(), data)
+
+	corrupt := append([]byte(nil), data...)
+	corrupt[checkpointInterval+10] = 'z'
+	if err := os.WriteFile(logPath, corrupt, 0o644); err != nil {
+		t.Fatal(err)
+	}
+
+	li, err := VerifyLog(logPath, idxPath)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if li.CorruptAt != 2*checkpointInterval {
+		t.Fatalf("expected corruption at %d, got %s", 2*checkpointInterval, li)
+	}
+	if li.Verified != checkpointInterval {
+		t.Fatalf("expected first checkpoint verified, got %d", li.Verified)
+	}
+}
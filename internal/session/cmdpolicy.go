@@ -0,0 +1,235 @@
+package session
+
+import (
+	"fmt"
+	"path/filepath"
+	"regexp"
+	"strings"
+)
+
+// commandPatternAny is what * matches in a command pattern: any text
+// without shell control characters, so "bash -c git *" cannot be stretched
+// into "bash -c git status; curl ... | sh".
+const commandPatternAny = "[^;&|<>$`\\n]"
+
+// policyEnv are the environment variables any scoped launch may set. Far
+// more variables change what a program runs than could be listed (PATH,
+// LD_PRELOAD, BASH_FUNC_*, PS4 with SHELLOPTS, NODE_OPTIONS, GIT_*, ...),
+// so a launch may set only these and those its token allows; LC_* counts
+// as one entry.
+var policyEnv = []string{"TERM", "COLORTERM", "LANG", "LANGUAGE", "LC_*", "TZ", "NO_COLOR", "FORCE_COLOR", "CI", "COLUMNS", "LINES"}
+
+// policyEscapes are options through which an allowed program runs other
+// commands, which a pattern allowing it with any arguments would let
+// through: git -c alias.x=!cmd, git --config-env, the helper programs git
+// runs for fetches and pushes, and the commands of rebase --exec and
+// difftool --extcmd.
+var policyEscapes = map[string][]string{
+	"git": {"-c", "--config", "--config-env", "--exec-path", "--upload-pack", "--receive-pack", "--exec", "-x", "--extcmd"},
+}
+
+// CommandPolicy limits what launches made with a scoped token ([[tokens]]
+// in config.toml) may run. A nil policy allows everything.
+//
+// Patterns are matched against the command line as launched (its argv
+// joined by spaces; a script is checked as the shell command that runs
+// it), before [launch] defaults apply. A pattern of one word names a
+// program and matches that argv[0] exactly, with any arguments. Longer
+// patterns match the whole command line, with * and ? standing for any
+// text or character other than shell control characters (; & | < > $ `
+// and newline). Deny patterns win over allow patterns; without allow
+// patterns everything not denied is allowed. Options that make an allowed
+// program run other commands (policyEscapes) are refused wherever the
+// program appears in the command line.
+//
+// A scoped launch may set only the environment variables in policyEnv and
+// the token's own env list.
+type CommandPolicy struct {
+	Name  string // the token's name, for errors and logs
+	allow []commandPattern
+	deny  []commandPattern
+	env   []string
+}
+
+type commandPattern struct {
+	text    string
+	program bool           // one word: matches argv[0]
+	re      *regexp.Regexp // otherwise: matches the command line
+}
+
+// NewCommandPolicy compiles a token's allow and deny patterns. env names
+// the environment variables its launches may set besides policyEnv.
+func NewCommandPolicy(name string, allow, deny, env []string) (*CommandPolicy, error) {
+	p := &CommandPolicy{Name: name, env: append(append([]string(nil), policyEnv...), env...)}
+	for _, key := range env {
+		if key == "" || strings.ContainsAny(key, "= ") {
+			return nil, fmt.Errorf("token %q: invalid environment variable name %q", name, key)
+		}
+	}
+	for _, list := range []struct {
+		patterns []string
+		into     *[]commandPattern
+	}{{allow, &p.allow}, {deny, &p.deny}} {
+		for _, s := range list.patterns {
+			pat, err := compileCommandPattern(s)
+			if err != nil {
+				return nil, fmt.Errorf("token %q: %w", name, err)
+			}
+			*list.into = append(*list.into, pat)
+		}
+	}
+	return p, nil
+}
+
+func compileCommandPattern(s string) (commandPattern, error) {
+	s = strings.Join(strings.Fields(s), " ")
+	if s == "" {
+		return commandPattern{}, fmt.Errorf("empty command pattern")
+	}
+	if !strings.ContainsAny(s, " *?") {
+		return commandPattern{text: s, program: true}, nil
+	}
+	var b strings.Builder
+	b.WriteString("^")
+	for _, r := range s {
+		switch r {
+		case '*':
+			b.WriteString(commandPatternAny + "*")
+		case '?':
+			b.WriteString(commandPatternAny)
+		default:
+			b.WriteString(regexp.QuoteMeta(string(r)))
+		}
+	}
+	b.WriteString("$")
+	re, err := regexp.Compile(b.String())
+	if err != nil {
+		return commandPattern{}, fmt.Errorf("invalid command pattern %q: %w", s, err)
+	}
+	return commandPattern{text: s, re: re}, nil
+}
+
+func (c commandPattern) match(argv []string, line string) bool {
+	if c.program {
+		return argv[0] == c.text
+	}
+	return c.re.MatchString(line)
+}
+
+// Allows reports whether the policy lets argv run, with an error naming
+// the reason when it does not.
+func (p *CommandPolicy) Allows(argv []string) error {
+	if p == nil || len(argv) == 0 {
+		return nil
+	}
+	line := strings.Join(argv, " ")
+	if err := p.checkEscapes(line); err != nil {
+		return err
+	}
+	for _, c := range p.deny {
+		if c.match(argv, line) {
+			return fmt.Errorf("command %q is denied for token %q (matches %q)", line, p.Name, c.text)
+		}
+	}
+	if len(p.allow) == 0 {
+		return nil
+	}
+	for _, c := range p.allow {
+		if c.match(argv, line) {
+			return nil
+		}
+	}
+	allowed := make([]string, len(p.allow))
+	for i, c := range p.allow {
+		allowed[i] = c.text
+	}
+	return fmt.Errorf("command %q is not allowed for token %q (allowed: %s)", line, p.Name, strings.Join(allowed, ", "))
+}
+
+// checkEscapes refuses a command line in which a program of policyEscapes
+// is given one of its command-running options. The line is split into
+// words with shell quotes and backslashes removed, so a program wrapped in
+// sh -c is found too, however its options are quoted.
+func (p *CommandPolicy) checkEscapes(line string) error {
+	var program string
+	for _, word := range strings.Fields(shellQuotes.Replace(line)) {
+		if _, ok := policyEscapes[filepath.Base(word)]; ok {
+			program = filepath.Base(word)
+			continue
+		}
+		if program == "" {
+			continue
+		}
+		for _, opt := range policyEscapes[program] {
+			if word == opt || strings.HasPrefix(word, opt+"=") {
+				return fmt.Errorf("command %q is denied for token %q: %s %s can run other commands", line, p.Name, program, opt)
+			}
+		}
+	}
+	return nil
+}
+
+// shellQuotes removes the characters a shell drops from a word.
+var shellQuotes = strings.NewReplacer(`"`, "", "'", "", `\`, "")
+
+// allowsEnv reports whether a scoped launch may set the variable key.
+func (p *CommandPolicy) allowsEnv(key string) bool {
+	for _, allowed := range p.env {
+		if key == allowed {
+			return true
+		}
+		if prefix, ok := strings.CutSuffix(allowed, "*"); ok && strings.HasPrefix(key, prefix) {
+			return true
+		}
+	}
+	return false
+}
+
+// check validates a launch against the policy: its command, its
+// environment and any cmd: mirrors, which run commands too.
+func (p *CommandPolicy) check(argv, env, mirrors []string) error {
+	if p == nil {
+		return nil
+	}
+	if err := p.Allows(argv); err != nil {
+		return err
+	}
+	for _, kv := range env {
+		key, _, _ := strings.Cut(kv, "=")
+		if !p.allowsEnv(key) {
+			return fmt.Errorf("token %q may not set %s for a launch (allowed: %s)", p.Name, key, strings.Join(p.env, ", "))
+		}
+	}
+	for _, spec := range mirrors {
+		if err := p.AllowsMirror(spec); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// name returns the token's name, or "" for a nil policy.
+func (p *CommandPolicy) name() string {
+	if p == nil {
+		return ""
+	}
+	return p.Name
+}
+
+// AllowsMirror checks a mirror sink against the policy: a cmd: sink is
+// checked as the sh -c command that runs it. file: sinks are refused: the
+// node appends output the token controls to any file it can write, such as
+// a shell's rc file or authorized_keys.
+func (p *CommandPolicy) AllowsMirror(spec string) error {
+	if p == nil {
+		return nil
+	}
+	kind, target, err := ParseMirror(spec)
+	if err != nil {
+		return err
+	}
+	if kind == "file" {
+		return fmt.Errorf("mirror %q is denied for token %q: scoped tokens may not mirror to files", spec, p.Name)
+	}
+	return p.Allows([]string{"sh", "-c", target})
+}
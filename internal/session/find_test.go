@@ -0,0 +1,52 @@
+package session
+
+import "testing"
+
+func TestFuzzyScore(t *testing.T) {
+	if fuzzyScore("bld", "build-api") == 0 {
+		t.Fatal("expected subsequence to match")
+	}
+	if fuzzyScore("xyz", "build-api") != 0 {
+		t.Fatal("expected no match")
+	}
+	if fuzzyScore("api", "build-api") <= fuzzyScore("bai", "build-api") {
+		t.Fatal("expected a substring at a word start to beat a scattered match")
+	}
+	if fuzzyScore("build", "build") <= fuzzyScore("build", "build-api") {
+		t.Fatal("expected an exact match to beat a prefix match")
+	}
+}
+
+func TestFindRanksNameOverPrompt(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	byPrompt := launchSleep(t, sm) // prompt "sleep 5"
+	byName := launchSleep(t, sm)
+	if err := sm.SetName(byName, "sleeper"); err != nil {
+		t.Fatalf("SetName: %v", err)
+	}
+	other := launchSleep(t, sm)
+	if err := sm.SetName(other, "worker"); err != nil {
+		t.Fatalf("SetName: %v", err)
+	}
+
+	got := sm.Find("slee", 0)
+	if len(got) != 3 {
+		t.Fatalf("expected every session to match via its prompt, got %d", len(got))
+	}
+	if got[0].ID != byName {
+		t.Fatalf("expected the named session first, got %d", got[0].ID)
+	}
+	if got[1].ID != other || got[2].ID != byPrompt {
+		t.Fatalf("expected prompt-only ties newest first, got %d, %d", got[1].ID, got[2].ID)
+	}
+
+	if got := sm.Find("slee", 1); len(got) != 1 {
+		t.Fatalf("expected limit to apply, got %d", len(got))
+	}
+	if got := sm.Find("zzz", 0); len(got) != 0 {
+		t.Fatalf("expected no matches, got %d", len(got))
+	}
+}
@@ -0,0 +1,47 @@
+package session
+
+import (
+	"context"
+	"os/exec"
+	"strings"
+	"time"
+)
+
+// gitInfoTimeout bounds the git lookup done for each launch, so a hung
+// repository doesn't leave a git process behind.
+const gitInfoTimeout = 2 * time.Second
+
+// recordGitInfo looks up the git checkout of a new session's working
+// directory and stores it in the session's metadata. Launch runs it in the
+// background; the fields stay empty outside a git work tree.
+func (m *SessionManager) recordGitInfo(sess *Session, dir string) {
+	root, branch, commit := lookupGitInfo(dir)
+	if root == "" {
+		return
+	}
+	sess.mu.Lock()
+	sess.Meta.GitRoot, sess.Meta.GitBranch, sess.Meta.GitCommit = root, branch, commit
+	sess.mu.Unlock()
+	m.triggerPersist()
+}
+
+// lookupGitInfo returns the repository root, branch ("HEAD" when detached),
+// and HEAD commit for dir. All three are empty if dir is not inside a git
+// work tree or git is unavailable.
+func lookupGitInfo(dir string) (root, branch, commit string) {
+	ctx, cancel := context.WithTimeout(context.Background(), gitInfoTimeout)
+	defer cancel()
+
+	// Output order: toplevel, full HEAD hash, then abbreviated HEAD (branch).
+	cmd := exec.CommandContext(ctx, "git", "rev-parse", "--show-toplevel", "HEAD", "--abbrev-ref", "HEAD")
+	cmd.Dir = dir
+	out, err := cmd.Output()
+	if err != nil {
+		return "", "", ""
+	}
+	lines := strings.Split(strings.TrimSpace(string(out)), "\n")
+	if len(lines) != 3 {
+		return "", "", ""
+	}
+	return lines[0], lines[2], lines[1]
+}
@@ -0,0 +1,43 @@
+package session
+
+import (
+	"os/exec"
+	"path/filepath"
+	"testing"
+)
+
+func TestLookupGitInfoOutsideRepo(t *testing.T) {
+	root, branch, commit := lookupGitInfo(t.TempDir())
+	if root != "" || branch != "" || commit != "" {
+		t.Fatalf("expected empty git info, got root=%q branch=%q commit=%q", root, branch, commit)
+	}
+}
+
+func TestLookupGitInfoInRepo(t *testing.T) {
+	if _, err := exec.LookPath("git"); err != nil {
+		t.Skip("git not installed")
+	}
+	dir := t.TempDir()
+	for _, args := range [][]string{
+		{"init", "-q", "-b", "work"},
+		{"-c", "user.name=t", "-c", "user.email=t@example.com", "commit", "-q", "--allow-empty", "-m", "init"},
+	} {
+		cmd := exec.Command("git", args...)
+		cmd.Dir = dir
+		if out, err := cmd.CombinedOutput(); err != nil {
+			t.Fatalf("git %v: %v\n%s", args, err, out)
+		}
+	}
+
+	root, branch, commit := lookupGitInfo(dir)
+	want, _ := filepath.EvalSymlinks(dir)
+	if got, _ := filepath.EvalSymlinks(root); got != want {
+		t.Fatalf("root: expected %q, got %q", want, root)
+	}
+	if branch != "work" {
+		t.Fatalf("branch: expected work, got %q", branch)
+	}
+	if len(commit) != 40 {
+		t.Fatalf("commit: expected 40-char hash, got %q", commit)
+	}
+}
@@ -0,0 +1,191 @@
+package session
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// maxHistoryTranscript caps the output log exported with a session so every
+// record fits in one frame; longer logs are exported from the tail.
+const maxHistoryTranscript = 8 << 20
+
+// ExportHistory calls fn with a record for every finished session created
+// at or after since (all of them when since is zero), oldest first. With
+// transcripts each record carries the session's output log. Bookmark names
+// in from and to limit the export to sessions that have them, with the
+// transcripts sliced to the output between them (see BookmarkRange).
+func (m *SessionManager) ExportHistory(since time.Time, transcripts bool, from, to string, fn func(protocol.HistoryRecord) error) error {
+	m.mu.RLock()
+	finished := make([]*Session, 0, len(m.sessions))
+	for _, s := range m.sessions {
+		if s.statusWatcher.Get().State != "running" && !s.Meta.CreatedAt.Before(since) {
+			finished = append(finished, s)
+		}
+	}
+	m.mu.RUnlock()
+	sort.Slice(finished, func(i, j int) bool { return finished[i].Meta.ID < finished[j].Meta.ID })
+
+	for _, s := range finished {
+		rec := protocol.HistoryRecord{Session: m.buildSessionInfo(s)}
+		s.mu.Lock()
+		rec.StartedAt, rec.FinishedAt, rec.Duration = sessionTiming(s.Meta)
+		removed := s.Meta.LogRemoved
+		start, end, rangeErr := bookmarkRange(s.Meta.Bookmarks, s.Meta.ID, from, to)
+		s.mu.Unlock()
+		if rangeErr != nil {
+			continue
+		}
+		if transcripts && !removed {
+			data, offset, err := readRange(s.logPath, start, end, maxHistoryTranscript)
+			if err != nil && !os.IsNotExist(err) {
+				return fmt.Errorf("reading log of session %d: %w", s.Meta.ID, err)
+			}
+			rec.Transcript, rec.TranscriptTruncated = data, offset > start
+			rec.Offset = uint64(offset)
+		}
+		if err := fn(rec); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// ImportHistory adds a finished session exported from another node under a
+// new ID, writing its transcript (if any) as the session's output log.
+func (m *SessionManager) ImportHistory(rec protocol.HistoryRecord) (uint32, error) {
+	info := rec.Session
+	var status SessionStatus
+	switch {
+	case info.Status == "killed":
+		status = StatusKilled()
+	case strings.HasPrefix(info.Status, "completed") && info.ExitCode != nil:
+		status = StatusCompleted(*info.ExitCode)
+	default:
+		return 0, fmt.Errorf("cannot import session %d with status %q: only finished sessions can be imported", info.ID, info.Status)
+	}
+	createdAt, err := time.Parse(time.RFC3339, info.CreatedAt)
+	if err != nil {
+		return 0, fmt.Errorf("session %d: invalid created_at %q", info.ID, info.CreatedAt)
+	}
+
+	meta := SessionMeta{
+		Name:         info.Name,
+		Prompt:       info.Prompt,
+		WorkingDir:   info.WorkingDir,
+		CreatedAt:    createdAt,
+		Status:       status.String(),
+		Tags:         info.Tags,
+		ExitCode:     info.ExitCode,
+		Result:       info.LastOutputSnippet,
+		Workspace:    info.Workspace,
+		GitRoot:      info.GitRoot,
+		GitBranch:    info.GitBranch,
+		GitCommit:    info.GitCommit,
+		StatusReason: info.StatusReason,
+		LogURL:       info.LogURL,
+		ImportedFrom: info.ImportedFrom,
+		Term:         info.Term,
+		Encoding:     info.Encoding,
+
+		UndeliveredInput: info.UndeliveredInput,
+	}
+	meta.RecordInput = info.RecordInput
+	if info.History != HistoryAll {
+		meta.History = info.History
+	}
+	if meta.ImportedFrom == "" && rec.Node != "" {
+		meta.ImportedFrom = fmt.Sprintf("%s:%d", rec.Node, info.ID)
+	}
+	meta.CompletedAt = parseTimePtr(info.CompletedAt)
+	if info.StatusChangedAt != "" {
+		meta.StatusChangedAt = parseTimePtr(&info.StatusChangedAt)
+	}
+	// Bookmarks are kept relative to the transcript, which starts at
+	// rec.Offset in the original log when it was sliced or truncated.
+	for _, b := range info.Bookmarks {
+		offset := b.Offset - int64(rec.Offset)
+		if offset < 0 || offset > int64(len(rec.Transcript)) {
+			continue
+		}
+		t, _ := time.Parse(time.RFC3339, b.Time)
+		meta.Bookmarks = append(meta.Bookmarks, SessionBookmark{Name: b.Name, Time: t, Offset: offset})
+	}
+	for _, n := range info.Notes {
+		t, _ := time.Parse(time.RFC3339, n.Time)
+		meta.Notes = append(meta.Notes, SessionNote{Time: t, Text: n.Text})
+	}
+
+	id := m.nextID.Add(1) - 1
+	meta.ID = id
+	logDir := filepath.Join(m.dataDir, "sessions", fmt.Sprintf("%d", id))
+	if err := os.MkdirAll(logDir, 0o755); err != nil {
+		return 0, fmt.Errorf("creating log dir: %w", err)
+	}
+	logPath := filepath.Join(logDir, "output.log")
+	if len(rec.Transcript) > 0 {
+		if err := os.WriteFile(logPath, rec.Transcript, 0o644); err != nil {
+			return 0, fmt.Errorf("writing transcript: %w", err)
+		}
+	}
+
+	sess := &Session{
+		Meta:          meta,
+		broadcaster:   NewBroadcaster(),
+		statusWatcher: NewStatusWatcher(status),
+		logPath:       logPath,
+	}
+	if len(rec.Transcript) > 0 {
+		sess.broadcaster.sent = uint64(len(rec.Transcript))
+		sess.outputBytes.Store(uint64(len(rec.Transcript)))
+		sess.outputLines.Store(uint64(strings.Count(string(rec.Transcript), "\n")))
+	} else {
+		if info.OutputBytes != nil {
+			sess.outputBytes.Store(*info.OutputBytes)
+		}
+		if info.OutputLines != nil {
+			sess.outputLines.Store(*info.OutputLines)
+		}
+	}
+	if t := parseTimePtr(info.LastOutputAt); t != nil {
+		sess.lastOutputAt.Store(t.UnixNano())
+	}
+
+	m.mu.Lock()
+	m.sessions[id] = sess
+	m.mu.Unlock()
+	m.triggerPersist()
+	return id, nil
+}
+
+// sessionTiming returns when a finished session started and finished, and
+// how long it ran. A killed session whose process has not been reaped yet
+// finished when its status changed. Callers hold s.mu.
+func sessionTiming(meta SessionMeta) (started, finished, duration string) {
+	end := meta.CompletedAt
+	if end == nil {
+		end = meta.StatusChangedAt
+	}
+	started = meta.CreatedAt.Format(time.RFC3339)
+	if end == nil {
+		return started, "", ""
+	}
+	return started, end.Format(time.RFC3339), end.Sub(meta.CreatedAt).Round(time.Second).String()
+}
+
+func parseTimePtr(s *string) *time.Time {
+	if s == nil {
+		return nil
+	}
+	t, err := time.Parse(time.RFC3339, *s)
+	if err != nil {
+		return nil
+	}
+	t = t.UTC()
+	return &t
+}
@@ -0,0 +1,260 @@
+package session
+
+import (
+	"bufio"
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"sort"
+	"time"
+)
+
+// journalFile is the append-only log of session metadata changes stored
+// next to sessions.json. Every persist appends the sessions that changed
+// since the previous one, so replaying it gives the state at any point.
+const journalFile = "sessions.journal"
+
+// maxJournalSize is the size at which the journal is rotated to
+// sessions.journal.1.
+const maxJournalSize = 16 << 20
+
+// journalRotations is how many rotated journals are kept, from
+// sessions.journal.1 (the newest) to sessions.journal.4; the oldest is
+// dropped on rotation.
+const journalRotations = 4
+
+// FsyncPolicy says when persisting session metadata waits for the data to
+// reach the disk, at the cost of latency.
+type FsyncPolicy string
+
+const (
+	FsyncNever    FsyncPolicy = "never"
+	FsyncCritical FsyncPolicy = "critical" // launches and kills only
+	FsyncAlways   FsyncPolicy = "always"
+)
+
+// ParseFsyncPolicy parses a [node] persist_fsync value; empty means
+// FsyncCritical.
+func ParseFsyncPolicy(s string) (FsyncPolicy, error) {
+	switch p := FsyncPolicy(s); p {
+	case "":
+		return FsyncCritical, nil
+	case FsyncNever, FsyncCritical, FsyncAlways:
+		return p, nil
+	}
+	return "", fmt.Errorf("invalid node.persist_fsync %q: expected never, critical or always", s)
+}
+
+// SetFsyncPolicy sets when PersistMeta syncs to disk (FsyncCritical by
+// default).
+func (m *SessionManager) SetFsyncPolicy(p FsyncPolicy) {
+	m.persistMu.Lock()
+	m.fsync = p
+	m.persistMu.Unlock()
+}
+
+// JournalEntry is one line of the journal: a session's metadata after a
+// change ("put"), or its removal from the node ("delete").
+type JournalEntry struct {
+	Time time.Time    `json:"time"`
+	Op   string       `json:"op"`
+	ID   uint32       `json:"id"`
+	Meta *SessionMeta `json:"meta,omitempty"`
+}
+
+// journalChanges compares metas with the last persisted state and returns
+// entries for what changed, along with the state they lead to. The caller
+// makes that state the persisted one only once the entries are written, so
+// a failed write is retried by the next persist.
+func (m *SessionManager) journalChanges(metas []SessionMeta, now time.Time) ([]JournalEntry, map[uint32][]byte, error) {
+	var entries []JournalEntry
+	state := make(map[uint32][]byte, len(metas))
+	for i := range metas {
+		meta := &metas[i]
+		data, err := json.Marshal(meta)
+		if err != nil {
+			return nil, nil, err
+		}
+		state[meta.ID] = data
+		if bytes.Equal(m.persisted[meta.ID], data) {
+			continue
+		}
+		entries = append(entries, JournalEntry{Time: now, Op: "put", ID: meta.ID, Meta: meta})
+	}
+	var removed []uint32
+	for id := range m.persisted {
+		if _, ok := state[id]; !ok {
+			removed = append(removed, id)
+		}
+	}
+	sort.Slice(removed, func(i, j int) bool { return removed[i] < removed[j] })
+	for _, id := range removed {
+		entries = append(entries, JournalEntry{Time: now, Op: "delete", ID: id})
+	}
+	return entries, state, nil
+}
+
+// rotateJournal moves the journal at path to path.1, shifting older rotated
+// journals up by one and dropping the one past journalRotations.
+func rotateJournal(path string) error {
+	if err := os.Remove(fmt.Sprintf("%s.%d", path, journalRotations)); err != nil && !os.IsNotExist(err) {
+		return err
+	}
+	for i := journalRotations - 1; i >= 1; i-- {
+		err := os.Rename(fmt.Sprintf("%s.%d", path, i), fmt.Sprintf("%s.%d", path, i+1))
+		if err != nil && !os.IsNotExist(err) {
+			return err
+		}
+	}
+	return os.Rename(path, path+".1")
+}
+
+// appendJournal writes entries to the journal with a single write, rotating
+// the journal first when it has grown past maxJournalSize. With sync it
+// returns only once the entries are on disk.
+func appendJournal(dataDir string, entries []JournalEntry, sync bool) error {
+	path := filepath.Join(dataDir, journalFile)
+	if info, err := os.Stat(path); err == nil && info.Size() >= maxJournalSize {
+		if err := rotateJournal(path); err != nil {
+			return fmt.Errorf("rotating journal: %w", err)
+		}
+	}
+
+	var buf bytes.Buffer
+	enc := json.NewEncoder(&buf)
+	for _, e := range entries {
+		if err := enc.Encode(e); err != nil {
+			return err
+		}
+	}
+	f, err := os.OpenFile(path, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o644)
+	if err != nil {
+		return fmt.Errorf("opening journal: %w", err)
+	}
+	if _, err := f.Write(buf.Bytes()); err != nil {
+		f.Close()
+		return fmt.Errorf("writing journal: %w", err)
+	}
+	if sync {
+		if err := f.Sync(); err != nil {
+			f.Close()
+			return fmt.Errorf("syncing journal: %w", err)
+		}
+	}
+	return f.Close()
+}
+
+// ReplayJournal rebuilds session metadata from the journal in dataDir (and
+// its rotated predecessors), applying entries up to and including until, or
+// all of them when until is zero. A torn last line is ignored.
+func ReplayJournal(dataDir string, until time.Time) ([]SessionMeta, error) {
+	state := make(map[uint32]SessionMeta)
+	path := filepath.Join(dataDir, journalFile)
+	found, stop := false, false
+	for _, p := range journalFiles(path) {
+		if stop {
+			break
+		}
+		f, err := os.Open(p)
+		if os.IsNotExist(err) {
+			continue
+		}
+		if err != nil {
+			return nil, err
+		}
+		found = true
+		scanner := bufio.NewScanner(f)
+		scanner.Buffer(make([]byte, 0, 64*1024), 16<<20)
+		for scanner.Scan() {
+			var e JournalEntry
+			if json.Unmarshal(scanner.Bytes(), &e) != nil {
+				continue
+			}
+			if !until.IsZero() && e.Time.After(until) {
+				stop = true
+				break
+			}
+			switch e.Op {
+			case "put":
+				if e.Meta != nil {
+					state[e.ID] = *e.Meta
+				}
+			case "delete":
+				delete(state, e.ID)
+			}
+		}
+		err = scanner.Err()
+		f.Close()
+		if err != nil {
+			return nil, fmt.Errorf("reading %s: %w", p, err)
+		}
+	}
+	if !found {
+		return nil, os.ErrNotExist
+	}
+
+	metas := make([]SessionMeta, 0, len(state))
+	for _, meta := range state {
+		metas = append(metas, meta)
+	}
+	sort.Slice(metas, func(i, j int) bool { return metas[i].ID < metas[j].ID })
+	return metas, nil
+}
+
+// journalFiles returns the journal at path and its rotated predecessors,
+// oldest first.
+func journalFiles(path string) []string {
+	files := make([]string, 0, journalRotations+1)
+	for i := journalRotations; i >= 1; i-- {
+		files = append(files, fmt.Sprintf("%s.%d", path, i))
+	}
+	return append(files, path)
+}
+
+// writeFileAtomic replaces path with data by writing a temporary file in the
+// same directory and renaming it over path, so readers (and a crash) see
+// either the old contents or the new, never a partial write. With sync the
+// file and the rename are flushed to disk before it returns, so the new
+// contents also survive a power loss.
+func writeFileAtomic(path string, data []byte, sync bool) error {
+	f, err := os.CreateTemp(filepath.Dir(path), "."+filepath.Base(path)+".tmp-*")
+	if err != nil {
+		return err
+	}
+	tmp := f.Name()
+	if _, err := f.Write(data); err != nil {
+		f.Close()
+		os.Remove(tmp)
+		return err
+	}
+	if sync {
+		if err := f.Sync(); err != nil {
+			f.Close()
+			os.Remove(tmp)
+			return err
+		}
+	}
+	if err := f.Close(); err != nil {
+		os.Remove(tmp)
+		return err
+	}
+	if err := os.Chmod(tmp, 0o644); err != nil {
+		os.Remove(tmp)
+		return err
+	}
+	if err := os.Rename(tmp, path); err != nil {
+		os.Remove(tmp)
+		return err
+	}
+	if sync {
+		dir, err := os.Open(filepath.Dir(path))
+		if err != nil {
+			return err
+		}
+		defer dir.Close()
+		return dir.Sync()
+	}
+	return nil
+}
@@ -0,0 +1,102 @@
+package session
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"testing"
+	"time"
+)
+
+func TestJournalReplayAndRecovery(t *testing.T) {
+	dir := t.TempDir()
+	sm, err := NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	id := launchSleep(t, sm)
+	sm.PersistMeta()
+	beforeName := time.Now().UTC()
+	time.Sleep(10 * time.Millisecond)
+	if err := sm.SetName(id, "journaled"); err != nil {
+		t.Fatalf("SetName: %v", err)
+	}
+	sm.PersistMeta()
+
+	journal := filepath.Join(dir, journalFile)
+	info, err := os.Stat(journal)
+	if err != nil {
+		t.Fatalf("stat journal: %v", err)
+	}
+	sm.PersistMeta()
+	if again, _ := os.Stat(journal); again.Size() != info.Size() {
+		t.Fatal("journal grew without a state change")
+	}
+
+	metas, err := ReplayJournal(dir, time.Time{})
+	if err != nil || len(metas) != 1 || metas[0].Name != "journaled" {
+		t.Fatalf("ReplayJournal = %+v, %v", metas, err)
+	}
+	metas, err = ReplayJournal(dir, beforeName)
+	if err != nil || len(metas) != 1 || metas[0].Name != "" {
+		t.Fatalf("ReplayJournal before rename = %+v, %v", metas, err)
+	}
+
+	// A lost sessions.json must not cause IDs to be reused.
+	if err := os.Remove(filepath.Join(dir, "sessions.json")); err != nil {
+		t.Fatalf("remove sessions.json: %v", err)
+	}
+	restored, err := NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	if next := restored.nextID.Load(); next != id+1 {
+		t.Fatalf("next ID = %d, want %d", next, id+1)
+	}
+}
+
+func TestJournalRetriesFailedWrites(t *testing.T) {
+	dir := t.TempDir()
+	sm, err := NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	// A directory in the journal's place makes appending fail.
+	journal := filepath.Join(dir, journalFile)
+	if err := os.Mkdir(journal, 0o755); err != nil {
+		t.Fatalf("mkdir: %v", err)
+	}
+	id := launchSleep(t, sm)
+	sm.PersistMeta()
+
+	if err := os.Remove(journal); err != nil {
+		t.Fatalf("remove: %v", err)
+	}
+	sm.PersistMeta()
+	metas, err := ReplayJournal(dir, time.Time{})
+	if err != nil || len(metas) != 1 || metas[0].ID != id {
+		t.Fatalf("ReplayJournal after a failed write = %+v, %v", metas, err)
+	}
+}
+
+func TestRotateJournalKeepsGenerations(t *testing.T) {
+	path := filepath.Join(t.TempDir(), journalFile)
+	for i := 0; i < journalRotations+2; i++ {
+		if err := os.WriteFile(path, []byte{byte('a' + i)}, 0o644); err != nil {
+			t.Fatalf("write: %v", err)
+		}
+		if err := rotateJournal(path); err != nil {
+			t.Fatalf("rotateJournal: %v", err)
+		}
+	}
+	// The newest rotation is .1; anything past journalRotations is dropped.
+	for i := 1; i <= journalRotations; i++ {
+		data, err := os.ReadFile(fmt.Sprintf("%s.%d", path, i))
+		if want := byte('a' + journalRotations + 2 - i); err != nil || len(data) != 1 || data[0] != want {
+			t.Fatalf("%s.%d = %q, %v; want %q", path, i, data, err, want)
+		}
+	}
+	if _, err := os.Stat(fmt.Sprintf("%s.%d", path, journalRotations+1)); !os.IsNotExist(err) {
+		t.Fatalf("expected at most %d rotated journals, got %v", journalRotations, err)
+	}
+}
@@ -0,0 +1,126 @@
+package session
+
+import (
+	"fmt"
+	"os"
+	"regexp"
+	"sort"
+	"strings"
+	"time"
+)
+
+// LaunchDefaults are node-wide settings applied to every spawned session,
+// configured under [launch] in config.toml.
+type LaunchDefaults struct {
+	// Env is merged into each session's environment. Per-launch env wins.
+	Env map[string]string
+	// PathPrepend is placed in front of PATH, both for the session and for
+	// resolving the command itself.
+	PathPrepend []string
+	// Shell wraps every command, e.g. "bash -lc": the argv is quoted into a
+	// single script argument so login-shell setup runs first.
+	Shell string
+	// MaxRuntime and IdleTimeout limit sessions launched without their own.
+	MaxRuntime  time.Duration
+	IdleTimeout time.Duration
+	// ExpiryWarning is how long before a limit is enforced that sessions
+	// emit session.expiring. Zero means DefaultExpiryWarning.
+	ExpiryWarning time.Duration
+	// Term is TERM for sessions launched without one; empty inherits the
+	// node's own.
+	Term string
+}
+
+// termPattern validates TERM values such as xterm-256color or screen.linux.
+var termPattern = regexp.MustCompile(`^[A-Za-z0-9][A-Za-z0-9._+-]{0,63}$`)
+
+// withTerm puts TERM=term in front of the per-launch env overrides, so an
+// explicit TERM in env still wins.
+func withTerm(env []string, term string) ([]string, error) {
+	if term == "" {
+		return env, nil
+	}
+	if !termPattern.MatchString(term) {
+		return nil, fmt.Errorf("invalid TERM %q", term)
+	}
+	return append([]string{"TERM=" + term}, env...), nil
+}
+
+// SetLaunchDefaults sets the defaults used by subsequent launches. It should
+// be called once at startup, before any session is launched.
+func (m *SessionManager) SetLaunchDefaults(d LaunchDefaults) {
+	m.defaults = d
+}
+
+// apply returns the argv to execute and the env overrides for a launch of
+// command with the caller's env overrides. wrap controls whether Shell is
+// applied; commands already built from a script are not wrapped twice.
+func (d LaunchDefaults) apply(command, env []string, wrap bool) ([]string, []string) {
+	var out []string
+	keys := make([]string, 0, len(d.Env))
+	for k := range d.Env {
+		keys = append(keys, k)
+	}
+	sort.Strings(keys)
+	for _, k := range keys {
+		out = append(out, k+"="+d.Env[k])
+	}
+	out = append(out, env...)
+
+	if len(d.PathPrepend) > 0 {
+		path := os.Getenv("PATH")
+		if v, ok := envLookup(out, "PATH"); ok {
+			path = v
+		}
+		dirs := append(append([]string{}, d.PathPrepend...), path)
+		out = append(out, "PATH="+strings.Join(dirs, string(os.PathListSeparator)))
+	}
+
+	if wrap && d.Shell != "" {
+		command = ShellCommand(d.Shell, ShellJoin(command))
+	}
+	return command, out
+}
+
+// ShellCommand splits shell (e.g. "bash -lc") into argv and appends script
+// as its final argument.
+func ShellCommand(shell, script string) []string {
+	return append(strings.Fields(shell), script)
+}
+
+// ShellJoin quotes argv for a POSIX shell so that running the result
+// reproduces the same argument vector.
+func ShellJoin(argv []string) string {
+	quoted := make([]string, len(argv))
+	for i, a := range argv {
+		quoted[i] = shellQuote(a)
+	}
+	return strings.Join(quoted, " ")
+}
+
+func shellQuote(s string) string {
+	if s == "" {
+		return "''"
+	}
+	safe := true
+	for _, r := range s {
+		if !(r >= 'a' && r <= 'z' || r >= 'A' && r <= 'Z' || r >= '0' && r <= '9' || strings.ContainsRune("@%+=:,./_-", r)) {
+			safe = false
+			break
+		}
+	}
+	if safe {
+		return s
+	}
+	return "'" + strings.ReplaceAll(s, "'", `'\''`) + "'"
+}
+
+// envLookup returns the last value of key in a KEY=VALUE list.
+func envLookup(env []string, key string) (string, bool) {
+	for i := len(env) - 1; i >= 0; i-- {
+		if v, ok := strings.CutPrefix(env[i], key+"="); ok {
+			return v, true
+		}
+	}
+	return "", false
+}
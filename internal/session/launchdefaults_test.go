@@ -0,0 +1,156 @@
+package session
+
+import (
+	"errors"
+	"os"
+	"path/filepath"
+	"reflect"
+	"slices"
+	"strings"
+	"testing"
+)
+
+func TestShellJoinQuotes(t *testing.T) {
+	got := ShellJoin([]string{"claude", "-p", "fix the tests", "it's", ""})
+	want := `claude -p 'fix the tests' 'it'\''s' ''`
+	if got != want {
+		t.Errorf("ShellJoin = %q, want %q", got, want)
+	}
+}
+
+func TestLaunchDefaultsApply(t *testing.T) {
+	t.Setenv("PATH", "/usr/bin")
+	d := LaunchDefaults{
+		Env:         map[string]string{"FOO": "default", "BAR": "1"},
+		PathPrepend: []string{"/opt/agent/bin"},
+		Shell:       "bash -lc",
+	}
+
+	argv, env := d.apply([]string{"claude", "-p", "hi there"}, []string{"FOO=override"}, true)
+
+	if want := []string{"bash", "-lc", "claude -p 'hi there'"}; !reflect.DeepEqual(argv, want) {
+		t.Errorf("argv = %q, want %q", argv, want)
+	}
+	merged := buildEnv(env)
+	if v, _ := envLookup(merged, "FOO"); v != "override" {
+		t.Errorf("FOO = %q, want per-launch override", v)
+	}
+	if v, _ := envLookup(merged, "BAR"); v != "1" {
+		t.Errorf("BAR = %q, want 1", v)
+	}
+	if v, _ := envLookup(merged, "PATH"); v != "/opt/agent/bin:/usr/bin" {
+		t.Errorf("PATH = %q", v)
+	}
+}
+
+func TestWithTermYieldsToExplicitEnv(t *testing.T) {
+	t.Setenv("TERM", "dumb")
+	d := LaunchDefaults{Env: map[string]string{"TERM": "from-config-env"}}
+
+	env, err := withTerm(nil, "xterm-256color")
+	if err != nil {
+		t.Fatal(err)
+	}
+	_, env = d.apply([]string{"sh"}, env, false)
+	if v, _ := envLookup(buildEnv(env), "TERM"); v != "xterm-256color" {
+		t.Errorf("TERM = %q, want xterm-256color", v)
+	}
+
+	env, _ = withTerm([]string{"TERM=vt100"}, "xterm-256color")
+	if v, _ := envLookup(buildEnv(env), "TERM"); v != "vt100" {
+		t.Errorf("TERM = %q, want explicit env to win", v)
+	}
+
+	if _, err := withTerm(nil, "xterm 256"); err == nil {
+		t.Error("expected invalid TERM to be rejected")
+	}
+}
+
+func TestLookPathInUsesGivenPath(t *testing.T) {
+	dir := t.TempDir()
+	bin := filepath.Join(dir, "my-agent")
+	if err := os.WriteFile(bin, []byte("#!/bin/sh\n"), 0o755); err != nil {
+		t.Fatal(err)
+	}
+
+	if _, err := lookPathIn("my-agent", "/usr/bin"); err == nil {
+		t.Error("expected my-agent to be missing from /usr/bin")
+	}
+	got, err := lookPathIn("my-agent", "/usr/bin:"+dir)
+	if err != nil || got != bin {
+		t.Errorf("lookPathIn = %q, %v; want %q", got, err, bin)
+	}
+}
+
+func TestLookPathInExplainsMissingCommand(t *testing.T) {
+	home := t.TempDir()
+	t.Setenv("HOME", home)
+	onPath := t.TempDir()
+	for _, name := range []string{"claude", "claude-code", "unrelated"} {
+		if err := os.WriteFile(filepath.Join(onPath, name), []byte("#!/bin/sh\n"), 0o755); err != nil {
+			t.Fatal(err)
+		}
+	}
+	local := filepath.Join(home, ".local", "bin")
+	if err := os.MkdirAll(local, 0o755); err != nil {
+		t.Fatal(err)
+	}
+	if err := os.WriteFile(filepath.Join(local, "claud"), []byte("#!/bin/sh\n"), 0o755); err != nil {
+		t.Fatal(err)
+	}
+
+	_, err := lookPathIn("claud", onPath)
+	var notFound *CommandNotFoundError
+	if !errors.As(err, &notFound) {
+		t.Fatalf("expected CommandNotFoundError, got %v", err)
+	}
+	if !slices.Equal(notFound.Suggestions, []string{"claude", "claude-code"}) {
+		t.Errorf("suggestions = %v", notFound.Suggestions)
+	}
+	if !slices.Equal(notFound.Elsewhere, []string{filepath.Join(local, "claud")}) {
+		t.Errorf("elsewhere = %v", notFound.Elsewhere)
+	}
+	msg := err.Error()
+	for _, want := range []string{"PATH: " + onPath, "did you mean: claude", "path_prepend"} {
+		if !strings.Contains(msg, want) {
+			t.Errorf("message %q lacks %q", msg, want)
+		}
+	}
+}
+
+func TestEditDistance(t *testing.T) {
+	for _, tc := range []struct {
+		a, b string
+		want int
+	}{
+		{"claude", "claude", 0},
+		{"claud", "claude", 1},
+		{"codex", "coder", 1},
+		{"aider", "adier", 2},
+		{"", "goose", 5},
+	} {
+		if got := editDistance(tc.a, tc.b); got != tc.want {
+			t.Errorf("editDistance(%q, %q) = %d, want %d", tc.a, tc.b, got, tc.want)
+		}
+	}
+}
+
+func TestPlanLaunchEncoding(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatal(err)
+	}
+	dir := t.TempDir()
+
+	plan, err := sm.PlanLaunch([]string{"true"}, dir, []string{"LANG=ja_JP.eucJP"}, "", LaunchOptions{})
+	if err != nil || plan.Encoding != "euc-jp" {
+		t.Fatalf("from locale: %q, %v", plan.Encoding, err)
+	}
+	plan, err = sm.PlanLaunch([]string{"true"}, dir, []string{"LANG=ja_JP.eucJP"}, "", LaunchOptions{Encoding: "latin1"})
+	if err != nil || plan.Encoding != "windows-1252" {
+		t.Fatalf("override: %q, %v", plan.Encoding, err)
+	}
+	if _, err := sm.PlanLaunch([]string{"true"}, dir, nil, "", LaunchOptions{Encoding: "utf-16le"}); err == nil {
+		t.Fatal("expected UTF-16 to be refused")
+	}
+}
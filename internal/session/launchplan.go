@@ -0,0 +1,164 @@
+package session
+
+import (
+	"cmp"
+	"errors"
+	"fmt"
+	"os"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/charset"
+)
+
+// LaunchPlan is what LaunchWithOptions would start, as worked out by
+// PlanLaunch: every check a launch makes before spawning, without spawning.
+type LaunchPlan struct {
+	Command     string   // as listed: the script, or the command line
+	Path        string   // binary resolved against the session's PATH
+	Argv        []string // after [launch] defaults and shell wrapping
+	WorkingDir  string   // empty when a workspace is created instead
+	Term        string
+	Encoding    string // canonical name, see charset.Lookup
+	History     string // HistoryAll, HistoryInputs or HistoryNone
+	RecordInput bool   // input is kept in inputs.jsonl
+	Priority    string // PriorityHigh, PriorityNormal or PriorityBackground
+	MaxRuntime  time.Duration
+	IdleTimeout time.Duration
+
+	env      []string // launch env with TERM and [launch] env applied
+	notes    []SessionNote
+	history  string // as stored in SessionMeta: empty for HistoryAll
+	encoding string // as stored in SessionMeta: empty for UTF-8
+	priority string // as stored in SessionMeta: empty for PriorityNormal
+}
+
+// PlanLaunch validates a launch against the node — the command against the
+// PATH the session will see, the working directory, the name, notes,
+// mirrors, history mode, priority and the token's command policy — and
+// returns what would be started.
+func (m *SessionManager) PlanLaunch(command []string, workingDir string, env []string, name string, opts LaunchOptions) (LaunchPlan, error) {
+	plan := LaunchPlan{Command: strings.Join(command, " ")}
+	wrap := true
+	if opts.Script != "" {
+		shell := m.defaults.Shell
+		if shell == "" {
+			shell = DefaultShell
+		}
+		command = ShellCommand(shell, opts.Script)
+		plan.Command = opts.Script
+		wrap = false
+	}
+	if len(command) == 0 {
+		return plan, fmt.Errorf("command must not be empty")
+	}
+	if err := opts.Policy.check(command, env, opts.Mirrors); err != nil {
+		return plan, err
+	}
+	if opts.SnapshotCommand != "" && opts.SnapshotCommand != DefaultSnapshotCommand {
+		if err := opts.Policy.Allows([]string{"sh", "-c", opts.SnapshotCommand}); err != nil {
+			return plan, err
+		}
+	}
+
+	env, err := withTerm(env, cmp.Or(opts.Term, m.defaults.Term))
+	if err != nil {
+		return plan, err
+	}
+
+	// Apply node-wide [launch] defaults, then validate the command binary
+	// against the PATH the session will actually see.
+	plan.Argv, plan.env = m.defaults.apply(command, env, wrap)
+	sessionEnv := buildEnv(plan.env)
+	pathEnv, _ := envLookup(sessionEnv, "PATH")
+	plan.Term, _ = envLookup(sessionEnv, "TERM")
+	if opts.Encoding != "" {
+		if plan.Encoding, err = charset.Lookup(opts.Encoding); err != nil {
+			return plan, err
+		}
+	} else {
+		plan.Encoding = cmp.Or(charset.FromLocale(sessionEnv), charset.UTF8)
+	}
+	if plan.Encoding != charset.UTF8 {
+		plan.encoding = plan.Encoding
+	}
+	if plan.Path, err = lookPathIn(plan.Argv[0], pathEnv); err != nil {
+		var notFound *CommandNotFoundError
+		if errors.As(err, &notFound) {
+			notFound.Prepended = len(m.defaults.PathPrepend) > 0
+		}
+		return plan, err
+	}
+
+	// Validate working directory (a workspace replaces it).
+	if opts.Workspace {
+		if err := checkWorkspaceSource(opts.WorkspaceSource, opts.Remote); err != nil {
+			return plan, err
+		}
+		// Filling the workspace runs git clone for the launch.
+		if opts.WorkspaceSource != "" {
+			if err := opts.Policy.Allows([]string{"git", "clone", opts.WorkspaceSource}); err != nil {
+				return plan, err
+			}
+		}
+	} else {
+		info, err := os.Stat(workingDir)
+		if err != nil {
+			return plan, fmt.Errorf("working directory %q does not exist", workingDir)
+		}
+		if !info.IsDir() {
+			return plan, fmt.Errorf("working directory %q is not a directory", workingDir)
+		}
+		plan.WorkingDir = workingDir
+	}
+
+	if name != "" {
+		if !namePattern.MatchString(name) {
+			return plan, fmt.Errorf("invalid name %q: must be 1-32 alphanumeric characters or hyphens, starting with alphanumeric", name)
+		}
+		m.mu.RLock()
+		existing, taken := m.nameIndex[name]
+		m.mu.RUnlock()
+		if taken {
+			return plan, fmt.Errorf("name %q already in use by session %d", name, existing)
+		}
+	}
+
+	for _, text := range opts.Notes {
+		note, err := newNote(text)
+		if err != nil {
+			return plan, err
+		}
+		plan.notes = append(plan.notes, note)
+	}
+	for _, spec := range opts.Mirrors {
+		if _, _, err := ParseMirror(spec); err != nil {
+			return plan, err
+		}
+	}
+	switch opts.History {
+	case "", HistoryAll:
+		plan.History = HistoryAll
+	case HistoryInputs, HistoryNone:
+		plan.History, plan.history = opts.History, opts.History
+	default:
+		return plan, fmt.Errorf("invalid history mode %q: expected all, inputs or none", opts.History)
+	}
+	if opts.RecordInput && plan.History == HistoryNone {
+		return plan, fmt.Errorf("input cannot be recorded for a session that records nothing (history: none)")
+	}
+	plan.RecordInput = opts.RecordInput || plan.History == HistoryInputs
+	if plan.Priority, err = ParsePriority(opts.Priority); err != nil {
+		return plan, err
+	}
+	if plan.Priority != PriorityNormal {
+		plan.priority = plan.Priority
+	}
+	if !opts.Deadline.IsZero() && !time.Now().Before(opts.Deadline) {
+		return plan, ErrDeadlineExceeded
+	}
+
+	plan.MaxRuntime = cmp.Or(opts.MaxRuntime, m.defaults.MaxRuntime)
+	plan.IdleTimeout = cmp.Or(opts.IdleTimeout, m.defaults.IdleTimeout)
+	return plan, nil
+}
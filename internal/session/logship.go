@@ -0,0 +1,80 @@
+package session
+
+import (
+	"fmt"
+	"log/slog"
+	"os"
+	"path/filepath"
+)
+
+// shippedFiles are the per-session files uploaded by ShipLogs; removable
+// marks the bulky ones deleted afterwards when deleteLocal is set.
+var shippedFiles = []struct {
+	name      string
+	removable bool
+}{
+	{"output.log", true},
+	{checkpointFile, true},
+	{"inputs.jsonl", true},
+	{"events.jsonl", false},
+	{"messages.jsonl", false},
+	{snapshotFile, false},
+}
+
+// LogShipFunc uploads a finished session's files and returns the remote URL
+// they can be found under.
+type LogShipFunc func(meta SessionMeta, files []string) (string, error)
+
+// ShipLogs uploads the files of every finished session that has not been
+// shipped yet and records the remote URL in its metadata. With deleteLocal
+// the output and input logs are removed from disk afterwards. Failed uploads
+// are retried on the next call.
+func (m *SessionManager) ShipLogs(ship LogShipFunc, deleteLocal bool) {
+	m.mu.RLock()
+	pending := make([]*Session, 0)
+	for _, s := range m.sessions {
+		if s.statusWatcher.Get().State == "running" {
+			continue
+		}
+		s.mu.Lock()
+		shipped := s.Meta.LogURL != "" || s.Meta.CompletedAt == nil
+		s.mu.Unlock()
+		if !shipped {
+			pending = append(pending, s)
+		}
+	}
+	m.mu.RUnlock()
+
+	for _, s := range pending {
+		s.mu.Lock()
+		meta := s.Meta
+		s.mu.Unlock()
+
+		dir := filepath.Join(m.dataDir, "sessions", fmt.Sprintf("%d", meta.ID))
+		files := make([]string, len(shippedFiles))
+		for i, f := range shippedFiles {
+			files[i] = filepath.Join(dir, f.name)
+		}
+		url, err := ship(meta, files)
+		if err != nil {
+			slog.Warn("log shipping failed", "id", meta.ID, "err", err)
+			continue
+		}
+		slog.Info("session logs shipped", "id", meta.ID, "url", url)
+
+		s.mu.Lock()
+		s.Meta.LogURL = url
+		s.mu.Unlock()
+		if deleteLocal {
+			for i, f := range shippedFiles {
+				if f.removable {
+					_ = os.Remove(files[i])
+				}
+			}
+			s.mu.Lock()
+			s.Meta.LogRemoved = true
+			s.mu.Unlock()
+		}
+		m.triggerPersist()
+	}
+}
@@ -0,0 +1,60 @@
+package session
+
+import (
+	"errors"
+	"os"
+	"strings"
+	"testing"
+	"time"
+)
+
+func TestShipLogsUploadsFinishedSessionsOnce(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatal(err)
+	}
+	id, err := sm.Launch([]string{"echo", "done"}, "/tmp", nil, nil, "")
+	if err != nil {
+		t.Fatal(err)
+	}
+	deadline := time.Now().Add(5 * time.Second)
+	for sm.sessions[id].statusWatcher.Get().State == "running" {
+		if time.Now().After(deadline) {
+			t.Fatal("session did not finish")
+		}
+		time.Sleep(50 * time.Millisecond)
+	}
+
+	sm.ShipLogs(func(SessionMeta, []string) (string, error) {
+		return "", errors.New("bucket unavailable")
+	}, true)
+	if _, err := sm.LogPath(id); err != nil {
+		t.Fatalf("failed upload should keep the log: %v", err)
+	}
+
+	calls := 0
+	ship := func(meta SessionMeta, files []string) (string, error) {
+		calls++
+		if meta.ID != id || !strings.HasSuffix(files[0], "output.log") {
+			t.Errorf("unexpected upload of %d: %v", meta.ID, files)
+		}
+		return "https://store.example/logs/1/", nil
+	}
+	logPath, _ := sm.LogPath(id)
+	sm.ShipLogs(ship, true)
+	sm.ShipLogs(ship, true)
+	if calls != 1 {
+		t.Fatalf("expected one upload, got %d", calls)
+	}
+
+	info, _, _ := sm.GetStatus(id)
+	if info.LogURL != "https://store.example/logs/1/" {
+		t.Fatalf("LogURL = %q", info.LogURL)
+	}
+	if _, err := os.Stat(logPath); !os.IsNotExist(err) {
+		t.Fatalf("local log should be removed, stat err = %v", err)
+	}
+	if _, err := sm.LogPath(id); err == nil || !strings.Contains(err.Error(), "shipped to") {
+		t.Fatalf("LogPath should point at the shipped copy, got %v", err)
+	}
+}
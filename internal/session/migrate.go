@@ -0,0 +1,175 @@
+package session
+
+import (
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"sort"
+	"strconv"
+	"strings"
+)
+
+// DataDirVersion is the data directory layout this build reads and writes.
+// A change to the shape of sessions.json, the journal or the per-session
+// files bumps it and appends the migration that brings older directories
+// up to date.
+const DataDirVersion = 1
+
+// dataDirVersionFile records the layout version of a data directory.
+// Directories from before versioning have none and are at version 0.
+const dataDirVersionFile = "datadir.version"
+
+// ErrDataDirTooNew is returned when a data directory was written by a newer
+// cw whose layout this build does not know.
+var ErrDataDirTooNew = errors.New("data directory was written by a newer cw")
+
+// migration upgrades a data directory to version. apply reports the changes
+// it made, or would make when dryRun is set.
+type migration struct {
+	version     int
+	description string
+	apply       func(dataDir string, dryRun bool) ([]string, error)
+}
+
+// migrations run in order; each leaves the directory at its version.
+var migrations = []migration{
+	{1, "index output logs recorded before checkpoints", backfillCheckpoints},
+}
+
+// MigrationStep is one migration run (or planned) by MigrateDataDir.
+type MigrationStep struct {
+	Version     int
+	Description string
+	Changes     []string
+}
+
+// DataDirVersionOf returns the layout version recorded in dataDir. A
+// directory holding no sessions yet is at the current version.
+func DataDirVersionOf(dataDir string) (int, error) {
+	data, err := os.ReadFile(filepath.Join(dataDir, dataDirVersionFile))
+	if errors.Is(err, os.ErrNotExist) {
+		for _, name := range []string{"sessions.json", journalFile, "sessions"} {
+			if _, statErr := os.Stat(filepath.Join(dataDir, name)); statErr == nil {
+				return 0, nil
+			}
+		}
+		return DataDirVersion, nil
+	}
+	if err != nil {
+		return 0, err
+	}
+	v, err := strconv.Atoi(strings.TrimSpace(string(data)))
+	if err != nil || v < 0 {
+		return 0, fmt.Errorf("invalid %s: %q", dataDirVersionFile, strings.TrimSpace(string(data)))
+	}
+	return v, nil
+}
+
+// MigrateDataDir brings dataDir up to DataDirVersion, running the pending
+// migrations in order and recording the version after each, so a migration
+// that fails is retried from that step on the next start. With dryRun
+// nothing is written and the steps list what would change. It must not run
+// while a node is using dataDir.
+func MigrateDataDir(dataDir string, dryRun bool) (from int, steps []MigrationStep, err error) {
+	from, err = DataDirVersionOf(dataDir)
+	if err != nil {
+		return 0, nil, err
+	}
+	if from > DataDirVersion {
+		return from, nil, fmt.Errorf("%w (layout version %d, this build knows %d); upgrade cw", ErrDataDirTooNew, from, DataDirVersion)
+	}
+	for _, m := range migrations {
+		if m.version <= from {
+			continue
+		}
+		changes, err := m.apply(dataDir, dryRun)
+		steps = append(steps, MigrationStep{Version: m.version, Description: m.description, Changes: changes})
+		if err != nil {
+			return from, steps, fmt.Errorf("migrating data dir to version %d (%s): %w", m.version, m.description, err)
+		}
+		if !dryRun {
+			if err := writeDataDirVersion(dataDir, m.version); err != nil {
+				return from, steps, err
+			}
+		}
+	}
+	if !dryRun && from == DataDirVersion {
+		// Stamp new directories, which need no migration.
+		if _, statErr := os.Stat(filepath.Join(dataDir, dataDirVersionFile)); errors.Is(statErr, os.ErrNotExist) {
+			return from, steps, writeDataDirVersion(dataDir, DataDirVersion)
+		}
+	}
+	return from, steps, nil
+}
+
+func writeDataDirVersion(dataDir string, v int) error {
+	if err := os.MkdirAll(dataDir, 0o755); err != nil {
+		return err
+	}
+	return writeFileAtomic(filepath.Join(dataDir, dataDirVersionFile), []byte(strconv.Itoa(v)+"\n"), true)
+}
+
+// backfillCheckpoints writes an output.idx for every session log that has
+// none, so logs from before checkpoints get the same truncation checks as
+// new ones. The index vouches for the log as it is now.
+func backfillCheckpoints(dataDir string, dryRun bool) ([]string, error) {
+	logs, err := filepath.Glob(filepath.Join(dataDir, "sessions", "*", "output.log"))
+	if err != nil {
+		return nil, err
+	}
+	sort.Strings(logs)
+	var changes []string
+	for _, logPath := range logs {
+		idxPath := filepath.Join(filepath.Dir(logPath), checkpointFile)
+		if _, err := os.Stat(idxPath); err == nil {
+			continue
+		}
+		id := filepath.Base(filepath.Dir(logPath))
+		if dryRun {
+			changes = append(changes, fmt.Sprintf("session %s: index output.log", id))
+			continue
+		}
+		size, err := writeCheckpoints(logPath, idxPath)
+		if err != nil {
+			return changes, fmt.Errorf("session %s: %w", id, err)
+		}
+		changes = append(changes, fmt.Sprintf("session %s: indexed output.log (%d bytes)", id, size))
+	}
+	return changes, nil
+}
+
+// writeCheckpoints builds the checkpoint index for an existing log.
+func writeCheckpoints(logPath, idxPath string) (int64, error) {
+	log, err := os.Open(logPath)
+	if err != nil {
+		return 0, err
+	}
+	defer log.Close()
+
+	tmp := idxPath + ".tmp"
+	os.Remove(tmp) // left by an interrupted migration
+	cw, err := newCheckpointWriter(tmp)
+	if err != nil {
+		return 0, err
+	}
+	buf := make([]byte, checkpointInterval)
+	for {
+		n, readErr := log.Read(buf)
+		cw.Track(buf[:n])
+		if readErr == io.EOF {
+			break
+		}
+		if readErr != nil {
+			cw.Close()
+			os.Remove(tmp)
+			return 0, readErr
+		}
+	}
+	if err := cw.Close(); err != nil {
+		os.Remove(tmp)
+		return 0, err
+	}
+	return cw.offset, os.Rename(tmp, idxPath)
+}
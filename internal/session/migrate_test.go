@@ -0,0 +1,72 @@
+package session
+
+import (
+	"errors"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestMigrateDataDirBackfillsCheckpoints(t *testing.T) {
+	dir := t.TempDir()
+	logDir := filepath.Join(dir, "sessions", "1")
+	if err := os.MkdirAll(logDir, 0o755); err != nil {
+		t.Fatal(err)
+	}
+	logPath := filepath.Join(logDir, "output.log")
+	if err := os.WriteFile(logPath, []byte(strings.Repeat("output line\n", 20000)), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	idxPath := filepath.Join(logDir, checkpointFile)
+
+	from, steps, err := MigrateDataDir(dir, true)
+	if err != nil {
+		t.Fatalf("dry run: %v", err)
+	}
+	if from != 0 || len(steps) != 1 || len(steps[0].Changes) != 1 {
+		t.Fatalf("dry run: from %d, steps %+v", from, steps)
+	}
+	if _, err := os.Stat(idxPath); err == nil {
+		t.Fatal("dry run wrote the checkpoint index")
+	}
+	if v, _ := DataDirVersionOf(dir); v != 0 {
+		t.Fatalf("dry run moved the version to %d", v)
+	}
+
+	if _, _, err := MigrateDataDir(dir, false); err != nil {
+		t.Fatalf("migrate: %v", err)
+	}
+	integrity, err := VerifyLog(logPath, idxPath)
+	if err != nil || !integrity.OK() || integrity.Checkpoints == 0 {
+		t.Fatalf("backfilled index: %+v, %v", integrity, err)
+	}
+	if v, _ := DataDirVersionOf(dir); v != DataDirVersion {
+		t.Fatalf("version after migrating = %d, want %d", v, DataDirVersion)
+	}
+
+	if _, steps, err := MigrateDataDir(dir, false); err != nil || len(steps) != 0 {
+		t.Fatalf("second migrate: steps %+v, %v", steps, err)
+	}
+}
+
+func TestMigrateDataDirStampsNewDir(t *testing.T) {
+	dir := t.TempDir()
+	from, steps, err := MigrateDataDir(dir, false)
+	if err != nil || from != DataDirVersion || len(steps) != 0 {
+		t.Fatalf("new dir: from %d, steps %+v, %v", from, steps, err)
+	}
+	if _, err := os.Stat(filepath.Join(dir, dataDirVersionFile)); err != nil {
+		t.Fatalf("version not recorded: %v", err)
+	}
+}
+
+func TestMigrateDataDirRefusesNewerLayout(t *testing.T) {
+	dir := t.TempDir()
+	if err := writeDataDirVersion(dir, DataDirVersion+1); err != nil {
+		t.Fatal(err)
+	}
+	if _, _, err := MigrateDataDir(dir, false); !errors.Is(err, ErrDataDirTooNew) {
+		t.Fatalf("expected ErrDataDirTooNew, got %v", err)
+	}
+}
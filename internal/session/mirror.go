@@ -0,0 +1,176 @@
+package session
+
+import (
+	"fmt"
+	"io"
+	"log/slog"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+	"sync/atomic"
+)
+
+// mirrorBuffer is how many output chunks a mirror may fall behind before
+// further chunks are dropped for it; a slow sink never stalls the session.
+const mirrorBuffer = 1024
+
+// outputMirror copies a session's output to an extra sink (see
+// ParseMirror) from its own goroutine.
+type outputMirror struct {
+	spec    string
+	sink    io.WriteCloser
+	ch      chan []byte
+	dropped atomic.Uint64
+}
+
+// ParseMirror checks a mirror sink spec: "file:/abs/path" appends output to
+// a file, "cmd:<command line>" pipes it to the stdin of a command run
+// through sh -c.
+func ParseMirror(spec string) (kind, target string, err error) {
+	kind, target, ok := strings.Cut(spec, ":")
+	if !ok || target == "" {
+		return "", "", fmt.Errorf("invalid mirror %q: expected file:/path or cmd:command", spec)
+	}
+	switch kind {
+	case "file":
+		if !filepath.IsAbs(target) {
+			return "", "", fmt.Errorf("invalid mirror %q: file path must be absolute", spec)
+		}
+	case "cmd":
+	default:
+		return "", "", fmt.Errorf("unsupported mirror sink %q: expected file:/path or cmd:command", kind)
+	}
+	return kind, target, nil
+}
+
+// openMirror opens the sink for spec and starts copying to it. Commands run
+// in workingDir.
+func openMirror(id uint32, spec, workingDir string) (*outputMirror, error) {
+	kind, target, err := ParseMirror(spec)
+	if err != nil {
+		return nil, err
+	}
+	var sink io.WriteCloser
+	switch kind {
+	case "file":
+		f, err := os.OpenFile(target, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o644)
+		if err != nil {
+			return nil, fmt.Errorf("opening mirror file: %w", err)
+		}
+		sink = f
+	case "cmd":
+		cmd := exec.Command("sh", "-c", target)
+		cmd.Dir = workingDir
+		stdin, err := cmd.StdinPipe()
+		if err != nil {
+			return nil, err
+		}
+		if err := cmd.Start(); err != nil {
+			return nil, fmt.Errorf("starting mirror command: %w", err)
+		}
+		sink = &cmdSink{stdin: stdin, cmd: cmd}
+	}
+	mr := &outputMirror{spec: spec, sink: sink, ch: make(chan []byte, mirrorBuffer)}
+	go mr.run(id)
+	return mr, nil
+}
+
+// run writes queued output to the sink until the channel is closed. After
+// a write error the rest is discarded.
+func (mr *outputMirror) run(id uint32) {
+	var failed bool
+	for data := range mr.ch {
+		if failed {
+			continue
+		}
+		if _, err := mr.sink.Write(data); err != nil {
+			slog.Warn("output mirror failed", "id", id, "mirror", mr.spec, "err", err)
+			failed = true
+		}
+	}
+	if err := mr.sink.Close(); err != nil && !failed {
+		slog.Warn("closing output mirror", "id", id, "mirror", mr.spec, "err", err)
+	}
+	if n := mr.dropped.Load(); n > 0 {
+		slog.Warn("output mirror fell behind", "id", id, "mirror", mr.spec, "dropped_chunks", n)
+	}
+}
+
+// cmdSink is the stdin of a mirror command; closing it waits for the
+// command to exit.
+type cmdSink struct {
+	stdin io.WriteCloser
+	cmd   *exec.Cmd
+}
+
+func (c *cmdSink) Write(p []byte) (int, error) { return c.stdin.Write(p) }
+
+func (c *cmdSink) Close() error {
+	c.stdin.Close()
+	return c.cmd.Wait()
+}
+
+// mirrorOutput queues a chunk of output for every mirror without blocking.
+func (s *Session) mirrorOutput(data []byte) {
+	s.mirrorMu.Lock()
+	defer s.mirrorMu.Unlock()
+	for _, mr := range s.mirrors {
+		select {
+		case mr.ch <- data:
+		default:
+			mr.dropped.Add(1)
+		}
+	}
+}
+
+// closeMirrors flushes and closes every mirror once the session's output
+// has ended; mirrors added later are refused.
+func (s *Session) closeMirrors() {
+	s.mirrorMu.Lock()
+	defer s.mirrorMu.Unlock()
+	for _, mr := range s.mirrors {
+		close(mr.ch)
+	}
+	s.mirrors = nil
+	s.outputDone = true
+}
+
+// AddMirror starts copying a running session's output from now on to the
+// sink described by spec (see ParseMirror).
+func (m *SessionManager) AddMirror(id uint32, spec string) error {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return fmt.Errorf("session %d not found", id)
+	}
+	if _, _, err := ParseMirror(spec); err != nil {
+		return err
+	}
+	if sess.statusWatcher.Get().State != "running" {
+		return fmt.Errorf("session %d is not running", id)
+	}
+	sess.mu.Lock()
+	workingDir := sess.Meta.WorkingDir
+	sess.mu.Unlock()
+
+	sess.mirrorMu.Lock()
+	if sess.outputDone {
+		sess.mirrorMu.Unlock()
+		return fmt.Errorf("session %d has ended", id)
+	}
+	mr, err := openMirror(id, spec, workingDir)
+	if err != nil {
+		sess.mirrorMu.Unlock()
+		return err
+	}
+	sess.mirrors = append(sess.mirrors, mr)
+	sess.mirrorMu.Unlock()
+
+	sess.mu.Lock()
+	sess.Meta.Mirrors = append(sess.Meta.Mirrors, spec)
+	sess.mu.Unlock()
+	m.triggerPersist()
+	return nil
+}
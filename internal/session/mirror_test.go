@@ -0,0 +1,52 @@
+package session
+
+import (
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+	"time"
+)
+
+func TestParseMirror(t *testing.T) {
+	for _, spec := range []string{"file:/tmp/out.log", "cmd:cat >/dev/null"} {
+		if _, _, err := ParseMirror(spec); err != nil {
+			t.Errorf("ParseMirror(%q): %v", spec, err)
+		}
+	}
+	for _, spec := range []string{"file:out.log", "file:", "nats:agents.out", "/tmp/out.log"} {
+		if _, _, err := ParseMirror(spec); err == nil {
+			t.Errorf("ParseMirror(%q): expected an error", spec)
+		}
+	}
+}
+
+func TestMirrorCopiesOutput(t *testing.T) {
+	dir := t.TempDir()
+	sm, err := NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	fileOut := filepath.Join(dir, "file.log")
+	cmdOut := filepath.Join(dir, "cmd.log")
+	id, err := sm.LaunchWithOptions([]string{"sh", "-c", "sleep 0.5; echo MIRRORED"}, "/tmp", nil, nil, "", LaunchOptions{
+		Mirrors: []string{"file:" + fileOut},
+	})
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	if err := sm.AddMirror(id, "cmd:cat >"+cmdOut); err != nil {
+		t.Fatalf("AddMirror: %v", err)
+	}
+
+	deadline := time.Now().Add(5 * time.Second)
+	for time.Now().Before(deadline) {
+		a, _ := os.ReadFile(fileOut)
+		b, _ := os.ReadFile(cmdOut)
+		if strings.Contains(string(a), "MIRRORED") && strings.Contains(string(b), "MIRRORED") {
+			return
+		}
+		time.Sleep(50 * time.Millisecond)
+	}
+	t.Fatal("expected both mirrors to receive the session's output")
+}
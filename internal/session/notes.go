@@ -0,0 +1,70 @@
+package session
+
+import (
+	"fmt"
+	"strings"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// maxNoteLen bounds a single note; notes are context, not documents.
+const maxNoteLen = 2000
+
+// SessionNote is a short annotation left on a session by its launcher or a
+// teammate, shown to everyone who attaches.
+type SessionNote struct {
+	Time time.Time `json:"time"`
+	Text string    `json:"text"`
+}
+
+func newNote(text string) (SessionNote, error) {
+	text = strings.TrimSpace(text)
+	if text == "" {
+		return SessionNote{}, fmt.Errorf("note must not be empty")
+	}
+	if len(text) > maxNoteLen {
+		return SessionNote{}, fmt.Errorf("note is longer than %d bytes", maxNoteLen)
+	}
+	return SessionNote{Time: time.Now().UTC(), Text: text}, nil
+}
+
+// AddNote appends a note to a session.
+func (m *SessionManager) AddNote(id uint32, text string) error {
+	note, err := newNote(text)
+	if err != nil {
+		return err
+	}
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return fmt.Errorf("session %d not found", id)
+	}
+	sess.mu.Lock()
+	sess.Meta.Notes = append(sess.Meta.Notes, note)
+	sess.mu.Unlock()
+	m.triggerPersist()
+	return nil
+}
+
+// setStatusReason records why a session's status last changed.
+func (s *Session) setStatusReason(reason string) {
+	now := time.Now().UTC()
+	s.mu.Lock()
+	s.Meta.StatusReason = reason
+	s.Meta.StatusChangedAt = &now
+	s.mu.Unlock()
+}
+
+// notesInfo converts notes for SessionInfo. Callers hold s.mu.
+func notesInfo(notes []SessionNote) []protocol.SessionNote {
+	if len(notes) == 0 {
+		return nil
+	}
+	out := make([]protocol.SessionNote, len(notes))
+	for i, n := range notes {
+		out[i] = protocol.SessionNote{Time: n.Time.Format(time.RFC3339), Text: n.Text}
+	}
+	return out
+}
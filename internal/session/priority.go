@@ -0,0 +1,118 @@
+package session
+
+import (
+	"cmp"
+	"fmt"
+	"sort"
+)
+
+// Priority classes. A session's class orders launches queued by the node's
+// guardrails, sets how remote clients' bandwidth caps apply to its output,
+// and decides which sessions guardrails.preempt kills first.
+const (
+	PriorityHigh       = "high"
+	PriorityNormal     = "normal"
+	PriorityBackground = "background"
+)
+
+// ParsePriority validates a priority class; empty means PriorityNormal.
+func ParsePriority(s string) (string, error) {
+	switch s {
+	case "":
+		return PriorityNormal, nil
+	case PriorityHigh, PriorityNormal, PriorityBackground:
+		return s, nil
+	}
+	return "", fmt.Errorf("invalid priority %q: expected high, normal or background", s)
+}
+
+// PriorityRank orders priority classes: background 0, normal 1, high 2.
+// Unknown and empty classes rank as normal.
+func PriorityRank(p string) int {
+	switch p {
+	case PriorityBackground:
+		return 0
+	case PriorityHigh:
+		return 2
+	}
+	return 1
+}
+
+// Priority returns a session's priority class, PriorityNormal for unknown
+// sessions.
+func (m *SessionManager) Priority(id uint32) string {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return PriorityNormal
+	}
+	sess.mu.Lock()
+	defer sess.mu.Unlock()
+	return cmp.Or(sess.Meta.Priority, PriorityNormal)
+}
+
+// SetPriority changes a session's priority class.
+func (m *SessionManager) SetPriority(id uint32, priority string) error {
+	p, err := ParsePriority(priority)
+	if err != nil {
+		return err
+	}
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return fmt.Errorf("session %d not found", id)
+	}
+	sess.mu.Lock()
+	sess.Meta.Priority = p
+	if p == PriorityNormal {
+		sess.Meta.Priority = ""
+	}
+	sess.mu.Unlock()
+	m.triggerPersist()
+	return nil
+}
+
+// Preempt kills the running session with the lowest priority class below
+// below, the most recently launched of them first, recording reason. It
+// returns the session killed, if any.
+func (m *SessionManager) Preempt(below string, reason string) (uint32, bool) {
+	limit := PriorityRank(below)
+	m.mu.RLock()
+	var candidates []*Session
+	for _, s := range m.sessions {
+		if s.statusWatcher.Get().State != "running" {
+			continue
+		}
+		s.mu.Lock()
+		rank := PriorityRank(s.Meta.Priority)
+		s.mu.Unlock()
+		if rank < limit {
+			candidates = append(candidates, s)
+		}
+	}
+	m.mu.RUnlock()
+	if len(candidates) == 0 {
+		return 0, false
+	}
+
+	sort.Slice(candidates, func(i, j int) bool {
+		a, b := candidates[i], candidates[j]
+		a.mu.Lock()
+		ra, ca := PriorityRank(a.Meta.Priority), a.Meta.CreatedAt
+		a.mu.Unlock()
+		b.mu.Lock()
+		rb, cb := PriorityRank(b.Meta.Priority), b.Meta.CreatedAt
+		b.mu.Unlock()
+		if ra != rb {
+			return ra < rb
+		}
+		return ca.After(cb)
+	})
+	victim := candidates[0].Meta.ID
+	if err := m.kill(victim, reason); err != nil {
+		return 0, false
+	}
+	return victim, true
+}
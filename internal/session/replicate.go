@@ -0,0 +1,66 @@
+package session
+
+import (
+	"io"
+	"os"
+	"sort"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+// Replicate calls fn with a record for every session, running ones
+// included, oldest first, for a standby keeping a copy of this node. Each
+// record carries the part of the session's output log past the standby's
+// offset for it, up to maxHistoryTranscript; the rest is sent on the next
+// call. A log shorter than the standby's offset has been replaced and is
+// sent again from the start.
+func (m *SessionManager) Replicate(offsets map[uint32]uint64, fn func(protocol.HistoryRecord) error) error {
+	m.mu.RLock()
+	all := make([]*Session, 0, len(m.sessions))
+	for _, s := range m.sessions {
+		all = append(all, s)
+	}
+	m.mu.RUnlock()
+	sort.Slice(all, func(i, j int) bool { return all[i].Meta.ID < all[j].Meta.ID })
+
+	for _, s := range all {
+		rec := protocol.HistoryRecord{Session: m.buildSessionInfo(s)}
+		s.mu.Lock()
+		removed := s.Meta.LogRemoved
+		s.mu.Unlock()
+		if !removed {
+			data, offset, err := readFrom(s.logPath, offsets[s.Meta.ID], maxHistoryTranscript)
+			if err != nil && !os.IsNotExist(err) {
+				return err
+			}
+			rec.Transcript, rec.Offset = data, offset
+		}
+		if err := fn(rec); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// readFrom reads at most limit bytes of path starting at offset, or at the
+// start when the file is shorter than offset. It returns where the data
+// starts.
+func readFrom(path string, offset uint64, limit int64) ([]byte, uint64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, 0, err
+	}
+	defer f.Close()
+	info, err := f.Stat()
+	if err != nil {
+		return nil, 0, err
+	}
+	if offset > uint64(info.Size()) {
+		offset = 0
+	}
+	if _, err := f.Seek(int64(offset), io.SeekStart); err != nil {
+		return nil, 0, err
+	}
+	data, err := io.ReadAll(io.LimitReader(f, limit))
+	return data, offset, err
+}
@@ -0,0 +1,179 @@
+package session
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+	"sort"
+	"strings"
+)
+
+// maxSuggestions bounds the similar commands listed in a CommandNotFoundError.
+const maxSuggestions = 5
+
+// commonBinDirs are where agent CLIs are often installed without being on a
+// service's PATH. A command found in one of them points at [launch]
+// path_prepend. Relative entries are under the node user's home directory.
+var commonBinDirs = []string{
+	".local/bin",
+	"bin",
+	".npm-global/bin",
+	".bun/bin",
+	".deno/bin",
+	".cargo/bin",
+	"go/bin",
+	"/usr/local/bin",
+	"/opt/homebrew/bin",
+	"/snap/bin",
+}
+
+// CommandNotFoundError is returned when a launch's command cannot be found
+// on the PATH its session would get. Besides the PATH searched, it carries
+// what would help fix the launch: similarly named commands on that PATH,
+// and places off it where the command is installed.
+type CommandNotFoundError struct {
+	Name        string
+	Path        string   // the PATH searched
+	Suggestions []string // similar command names on Path
+	Elsewhere   []string // the command, installed outside Path
+	Prepended   bool     // [launch] path_prepend is already configured
+}
+
+func (e *CommandNotFoundError) Error() string {
+	var b strings.Builder
+	fmt.Fprintf(&b, "command %q not found in PATH", e.Name)
+	fmt.Fprintf(&b, "\n  PATH: %s", e.Path)
+	if len(e.Suggestions) > 0 {
+		fmt.Fprintf(&b, "\n  did you mean: %s", strings.Join(e.Suggestions, ", "))
+	}
+	if len(e.Elsewhere) > 0 {
+		fmt.Fprintf(&b, "\n  installed outside PATH: %s", strings.Join(e.Elsewhere, ", "))
+		fmt.Fprintf(&b, "\n  add %s to [launch] path_prepend in the node's config.toml", filepath.Dir(e.Elsewhere[0]))
+	} else if !e.Prepended {
+		fmt.Fprintf(&b, "\n  if it is installed for a login shell only, set [launch] path_prepend or shell = \"bash -lc\" in the node's config.toml")
+	}
+	return b.String()
+}
+
+// lookPathIn resolves name like exec.LookPath, but against path rather than
+// the node's own PATH.
+func lookPathIn(name, path string) (string, error) {
+	if strings.Contains(name, "/") {
+		if _, err := os.Stat(name); err != nil {
+			return "", fmt.Errorf("command %q does not exist", name)
+		}
+		return name, nil
+	}
+	for _, dir := range filepath.SplitList(path) {
+		p := filepath.Join(pathDir(dir), name)
+		if isExecutable(p) {
+			return p, nil
+		}
+	}
+	return "", &CommandNotFoundError{
+		Name:        name,
+		Path:        path,
+		Suggestions: similarCommands(name, path),
+		Elsewhere:   installedElsewhere(name, path),
+	}
+}
+
+func isExecutable(p string) bool {
+	fi, err := os.Stat(p)
+	return err == nil && !fi.IsDir() && fi.Mode()&0o111 != 0
+}
+
+// similarCommands returns executables on path whose names are a small edit
+// away from name, or extend it (claude for claud, claude-code for claude),
+// closest first.
+func similarCommands(name, path string) []string {
+	type candidate struct {
+		name string
+		dist int
+	}
+	limit := max(1, len(name)/3)
+	seen := map[string]bool{name: true}
+	var found []candidate
+	for _, dir := range filepath.SplitList(path) {
+		entries, err := os.ReadDir(pathDir(dir))
+		if err != nil {
+			continue
+		}
+		for _, e := range entries {
+			n := e.Name()
+			if seen[n] || !isExecutable(filepath.Join(pathDir(dir), n)) {
+				continue
+			}
+			d := editDistance(name, n)
+			if d > limit && !(strings.HasPrefix(n, name) && len(name) >= 3) {
+				continue
+			}
+			seen[n] = true
+			found = append(found, candidate{n, d})
+		}
+	}
+	sort.Slice(found, func(i, j int) bool {
+		if found[i].dist != found[j].dist {
+			return found[i].dist < found[j].dist
+		}
+		return found[i].name < found[j].name
+	})
+	var names []string
+	for _, c := range found[:min(len(found), maxSuggestions)] {
+		names = append(names, c.name)
+	}
+	return names
+}
+
+// installedElsewhere returns name's full path in each of commonBinDirs that
+// is not on path and has it.
+func installedElsewhere(name, path string) []string {
+	onPath := filepath.SplitList(path)
+	home, _ := os.UserHomeDir()
+	var out []string
+	for _, dir := range commonBinDirs {
+		if !filepath.IsAbs(dir) {
+			if home == "" {
+				continue
+			}
+			dir = filepath.Join(home, dir)
+		}
+		if slices.Contains(onPath, dir) {
+			continue
+		}
+		if p := filepath.Join(dir, name); isExecutable(p) {
+			out = append(out, p)
+		}
+	}
+	return out
+}
+
+// pathDir is a PATH entry as a directory: empty means the current one.
+func pathDir(dir string) string {
+	if dir == "" {
+		return "."
+	}
+	return dir
+}
+
+// editDistance is the Levenshtein distance between a and b.
+func editDistance(a, b string) int {
+	prev := make([]int, len(b)+1)
+	cur := make([]int, len(b)+1)
+	for j := range prev {
+		prev[j] = j
+	}
+	for i := 1; i <= len(a); i++ {
+		cur[0] = i
+		for j := 1; j <= len(b); j++ {
+			cost := 1
+			if a[i-1] == b[j-1] {
+				cost = 0
+			}
+			cur[j] = min(prev[j]+1, cur[j-1]+1, prev[j-1]+cost)
+		}
+		prev, cur = cur, prev
+	}
+	return prev[len(b)]
+}
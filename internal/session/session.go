@@ -1,6 +1,8 @@
 package session
 
 import (
+	"bytes"
+	"cmp"
 	"encoding/json"
 	"errors"
 	"fmt"
@@ -11,6 +13,7 @@ import (
 	"path/filepath"
 	"regexp"
 	"sort"
+	"strconv"
 	"strings"
 	"sync"
 	"sync/atomic"
@@ -19,7 +22,9 @@ import (
 
 	"github.com/creack/pty"
 
+	"github.com/codewiresh/codewire/internal/charset"
 	"github.com/codewiresh/codewire/internal/protocol"
+	"github.com/codewiresh/codewire/internal/textwidth"
 )
 
 // namePattern validates session names: alphanumeric + hyphens, 1-32 chars.
@@ -34,13 +39,24 @@ var namePattern = regexp.MustCompile(`^[a-zA-Z0-9][a-zA-Z0-9-]{0,31}$`)
 type Broadcaster struct {
 	mu        sync.RWMutex
 	listeners map[uint64]chan []byte
+	chunks    map[uint64]chan OutputChunk // listeners from SubscribeAt
 	nextID    uint64
+	sent      uint64 // bytes sent so far: the output offset of the next Send
+}
+
+// OutputChunk is output sent by a Broadcaster, with the output offset it
+// starts at. A chunk dropped for a slow listener shows up as a gap between
+// the end of one chunk and the offset of the next.
+type OutputChunk struct {
+	Offset uint64
+	Data   []byte
 }
 
 // NewBroadcaster creates a ready-to-use Broadcaster.
 func NewBroadcaster() *Broadcaster {
 	return &Broadcaster{
 		listeners: make(map[uint64]chan []byte),
+		chunks:    make(map[uint64]chan OutputChunk),
 	}
 }
 
@@ -56,6 +72,19 @@ func (b *Broadcaster) Subscribe(bufSize int) (uint64, <-chan []byte) {
 	return id, ch
 }
 
+// SubscribeAt is Subscribe for listeners that track output offsets. It
+// also returns the offset the listener starts at, and each chunk carries
+// its own offset, so a listener can tell when chunks were dropped.
+func (b *Broadcaster) SubscribeAt(bufSize int) (uint64, <-chan OutputChunk, uint64) {
+	b.mu.Lock()
+	defer b.mu.Unlock()
+	id := b.nextID
+	b.nextID++
+	ch := make(chan OutputChunk, bufSize)
+	b.chunks[id] = ch
+	return id, ch, b.sent
+}
+
 // Unsubscribe removes and closes a listener by ID.
 func (b *Broadcaster) Unsubscribe(id uint64) {
 	b.mu.Lock()
@@ -64,19 +93,31 @@ func (b *Broadcaster) Unsubscribe(id uint64) {
 		close(ch)
 		delete(b.listeners, id)
 	}
+	if ch, ok := b.chunks[id]; ok {
+		close(ch)
+		delete(b.chunks, id)
+	}
 }
 
 // Send broadcasts data to every listener. Non-blocking: if a listener's
 // channel is full the message is silently dropped for that consumer.
 func (b *Broadcaster) Send(data []byte) {
-	b.mu.RLock()
-	defer b.mu.RUnlock()
+	b.mu.Lock()
+	defer b.mu.Unlock()
+	chunk := OutputChunk{Offset: b.sent, Data: data}
+	b.sent += uint64(len(data))
 	for _, ch := range b.listeners {
 		select {
 		case ch <- data:
 		default: // drop for slow consumers
 		}
 	}
+	for _, ch := range b.chunks {
+		select {
+		case ch <- chunk:
+		default: // drop for slow consumers
+		}
+	}
 }
 
 // ---------------------------------------------------------------------------
@@ -122,6 +163,43 @@ func (w *StatusWatcher) Changed() <-chan struct{} {
 	return w.waitCh
 }
 
+// ClientCounter counts the clients attached to a session and, like
+// StatusWatcher, notifies waiters when the count changes. The zero value is
+// ready to use.
+type ClientCounter struct {
+	mu     sync.Mutex
+	n      int32
+	waitCh chan struct{} // closed on change, then replaced
+}
+
+// Add changes the count by delta and wakes all current waiters.
+func (c *ClientCounter) Add(delta int32) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.n += delta
+	if c.waitCh != nil {
+		close(c.waitCh)
+	}
+	c.waitCh = make(chan struct{})
+}
+
+// Load returns the current count.
+func (c *ClientCounter) Load() int32 {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	return c.n
+}
+
+// Changed returns a channel that is closed when the count next changes.
+func (c *ClientCounter) Changed() <-chan struct{} {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	if c.waitCh == nil {
+		c.waitCh = make(chan struct{})
+	}
+	return c.waitCh
+}
+
 // ---------------------------------------------------------------------------
 // SessionStatus
 // ---------------------------------------------------------------------------
@@ -173,6 +251,70 @@ type SessionMeta struct {
 	ExitCode    *int       `json:"exit_code,omitempty"`
 	CompletedAt *time.Time `json:"completed_at,omitempty"`
 	Result      *string    `json:"result,omitempty"`
+
+	SnapshotCommand string `json:"snapshot_command,omitempty"`
+
+	// Workspace is the per-session directory created for --workspace, which
+	// is pruned after the node's workspace retention.
+	Workspace string `json:"workspace,omitempty"`
+
+	// Git checkout of WorkingDir at launch (empty outside a repository).
+	GitRoot   string `json:"git_root,omitempty"`
+	GitBranch string `json:"git_branch,omitempty"`
+	GitCommit string `json:"git_commit,omitempty"`
+
+	// Notes left by the launcher or teammates, and why the status last
+	// changed (e.g. "killed by request"); both are shown on attach.
+	Notes           []SessionNote `json:"notes,omitempty"`
+	StatusReason    string        `json:"status_reason,omitempty"`
+	StatusChangedAt *time.Time    `json:"status_changed_at,omitempty"`
+
+	// Bookmarks mark moments in the output log (see AddBookmark).
+	Bookmarks []SessionBookmark `json:"bookmarks,omitempty"`
+
+	// Limits enforced by watchExpiry: the session is killed at Deadline
+	// (its max runtime), or after IdleTimeout without input or output.
+	Deadline    *time.Time    `json:"deadline,omitempty"`
+	IdleTimeout time.Duration `json:"idle_timeout,omitempty"`
+	// IdleExtendedUntil holds off the idle limit until then (see Extend).
+	IdleExtendedUntil *time.Time `json:"idle_extended_until,omitempty"`
+
+	// LogURL is where ShipLogs uploaded the session's logs; LogRemoved is
+	// set once the local copies were deleted.
+	LogURL     string `json:"log_url,omitempty"`
+	LogRemoved bool   `json:"log_removed,omitempty"`
+
+	// ImportedFrom is the node:id of a session added by ImportHistory.
+	ImportedFrom string `json:"imported_from,omitempty"`
+
+	// Term is the TERM the session was started with.
+	Term string `json:"term,omitempty"`
+
+	// Encoding is the session's output encoding (see charset.Lookup);
+	// empty means UTF-8.
+	Encoding string `json:"encoding,omitempty"`
+
+	// Mirrors are the extra sinks the session's output is copied to.
+	Mirrors []string `json:"mirrors,omitempty"`
+
+	// UndeliveredInput is the queued input the session ended without
+	// receiving (see recordUndelivered).
+	UndeliveredInput []protocol.UndeliveredInput `json:"undelivered_input,omitempty"`
+
+	// History is what the node records on disk for the session:
+	// HistoryInputs or HistoryNone; empty means everything.
+	History string `json:"history,omitempty"`
+	// RecordInput is set when input written to the PTY, attach keystrokes
+	// included, is kept in inputs.jsonl (see LaunchOptions.RecordInput).
+	RecordInput bool `json:"record_input,omitempty"`
+
+	// Priority is the session's priority class (PriorityHigh or
+	// PriorityBackground); empty means PriorityNormal.
+	Priority string `json:"priority,omitempty"`
+
+	// Token is the name of the scoped token that launched the session;
+	// empty for the node token.
+	Token string `json:"token,omitempty"`
 }
 
 // ---------------------------------------------------------------------------
@@ -183,9 +325,10 @@ type SessionMeta struct {
 type Session struct {
 	Meta          SessionMeta
 	master        *os.File // PTY master fd (from creack/pty)
-	attachedCount atomic.Int32
+	attachedCount ClientCounter
 	broadcaster   *Broadcaster
 	inputCh       chan []byte // buffered channel for PTY input writes
+	pendingStdin  []byte      // stdin_data waiting to be injected (guarded by mu)
 	statusWatcher *StatusWatcher
 	logPath       string
 	mu            sync.Mutex // protects Meta.Status updates
@@ -194,8 +337,16 @@ type Session struct {
 	outputBytes  atomic.Uint64
 	outputLines  atomic.Uint64
 	lastOutputAt atomic.Int64 // unix nano
+	lastInputAt  atomic.Int64 // unix nano
 	eventLog     *EventLog
 	messageLog   *EventLog // JSONL at sessions/{id}/messages.jsonl
+	inputLog     *EventLog // JSONL at sessions/{id}/inputs.jsonl
+
+	// Extra output sinks (see AddMirror); outputDone is set once the PTY
+	// reader has exited.
+	mirrorMu   sync.Mutex
+	mirrors    []*outputMirror
+	outputDone bool
 }
 
 // ---------------------------------------------------------------------------
@@ -204,10 +355,12 @@ type Session struct {
 
 // AttachChannels groups the channels returned by SessionManager.Attach.
 type AttachChannels struct {
+	ClientID string // names the client in attach and detach events
 	OutputCh <-chan []byte
 	OutputID uint64 // for Broadcaster.Unsubscribe
 	InputCh  chan<- []byte
 	Status   *StatusWatcher
+	Clients  *ClientCounter // clients attached, this one included
 }
 
 // ---------------------------------------------------------------------------
@@ -223,25 +376,33 @@ type SessionManager struct {
 	dataDir       string
 	PersistCh     chan struct{} // exported: the node package drains this to trigger writes
 	Subscriptions *SubscriptionManager
+	events        *EventStore // nil: events are not kept beyond events.jsonl
+
+	persistMu sync.Mutex
+	persisted map[uint32][]byte // session ID → meta JSON last written (guarded by persistMu)
+	fsync     FsyncPolicy       // guarded by persistMu
 
 	pendingRequestsMu sync.Mutex
 	pendingRequests   map[string]chan ReplyData // requestID → reply channel
+
+	defaults LaunchDefaults
 }
 
 // NewSessionManager creates a SessionManager rooted at dataDir. It reads
 // sessions.json (if present) to restore the next session ID counter. If the
-// file is corrupt it is backed up and an empty session list is used.
+// file is missing or corrupt (a corrupt one is backed up) the counter is
+// restored from the journal instead, and an empty session list is used.
 func NewSessionManager(dataDir string) (*SessionManager, error) {
 	if err := os.MkdirAll(dataDir, 0o755); err != nil {
 		return nil, fmt.Errorf("creating data dir: %w", err)
 	}
 
-	var startID uint32 = 1
+	var metas []SessionMeta
+	loaded := false
 
 	metaPath := filepath.Join(dataDir, "sessions.json")
 	data, err := os.ReadFile(metaPath)
 	if err == nil {
-		var metas []SessionMeta
 		if jsonErr := json.Unmarshal(data, &metas); jsonErr != nil {
 			// Backup corrupt file
 			ts := time.Now().UTC().Format("20060102_150405")
@@ -252,17 +413,30 @@ func NewSessionManager(dataDir string) (*SessionManager, error) {
 				slog.Info("backed up corrupt sessions.json", "path", backupPath)
 			}
 			slog.Error("corrupt sessions.json — starting with empty session list", "err", jsonErr)
+			metas = nil
 		} else {
-			var maxID uint32
-			for _, m := range metas {
-				if m.ID > maxID {
-					maxID = m.ID
-				}
-			}
-			startID = maxID + 1
+			loaded = true
+		}
+	}
+	if !loaded {
+		// Without a usable sessions.json, the journal still knows which IDs
+		// were handed out. If neither exists we silently start from ID 1.
+		if replayed, jErr := ReplayJournal(dataDir, time.Time{}); jErr == nil {
+			metas = replayed
+			slog.Info("restored session IDs from journal", "sessions", len(metas))
+		} else if !os.IsNotExist(jErr) {
+			slog.Error("failed to replay session journal", "err", jErr)
+		}
+	}
+
+	persisted := make(map[uint32][]byte, len(metas))
+	var maxID uint32
+	for _, m := range metas {
+		maxID = max(maxID, m.ID)
+		if b, err := json.Marshal(m); err == nil {
+			persisted[m.ID] = b
 		}
 	}
-	// If the file does not exist we silently start from ID 1.
 
 	sm := &SessionManager{
 		sessions:        make(map[uint32]*Session),
@@ -270,9 +444,11 @@ func NewSessionManager(dataDir string) (*SessionManager, error) {
 		dataDir:         dataDir,
 		PersistCh:       make(chan struct{}, 1),
 		Subscriptions:   NewSubscriptionManager(),
+		persisted:       persisted,
+		fsync:           FsyncCritical,
 		pendingRequests: make(map[string]chan ReplyData),
 	}
-	sm.nextID.Store(startID)
+	sm.nextID.Store(maxID + 1)
 	return sm, nil
 }
 
@@ -401,10 +577,10 @@ func (m *SessionManager) SendMessage(fromID, toID uint32, body string) (string,
 	}
 
 	// Publish to subscriptions (on the recipient's session ID).
-	m.Subscriptions.Publish(toID, toSess.Meta.Tags, event)
+	m.publish(toID, toSess.Meta.Tags, event)
 	// Also publish on sender so listen can see sent messages.
 	if fromOK && fromID != toID {
-		m.Subscriptions.Publish(fromID, fromSess.Meta.Tags, event)
+		m.publish(fromID, fromSess.Meta.Tags, event)
 	}
 
 	return msgID, nil
@@ -468,13 +644,13 @@ func (m *SessionManager) SendRequest(fromID, toID uint32, body string) (string,
 	if toSess.messageLog != nil {
 		toSess.messageLog.Append(event)
 	}
-	m.Subscriptions.Publish(toID, toSess.Meta.Tags, event)
+	m.publish(toID, toSess.Meta.Tags, event)
 	// Also publish on sender (only if sender is a real session).
 	if fromOK && fromID != toID {
 		if fromSess.messageLog != nil {
 			fromSess.messageLog.Append(event)
 		}
-		m.Subscriptions.Publish(fromID, fromSess.Meta.Tags, event)
+		m.publish(fromID, fromSess.Meta.Tags, event)
 	}
 
 	// Register reply channel.
@@ -523,7 +699,7 @@ func (m *SessionManager) SendReply(fromID uint32, requestID string, body string)
 	if fromOK && fromSess.messageLog != nil {
 		fromSess.messageLog.Append(event)
 	}
-	m.Subscriptions.Publish(fromID, nil, event)
+	m.publish(fromID, nil, event)
 
 	// Send to the reply channel (non-blocking in case caller timed out).
 	select {
@@ -563,7 +739,7 @@ func FormatRequestPrompt(requestID string, fromName string, fromID uint32, body
 // session's PTY via SendInput.
 func (m *SessionManager) DeliverDirectMessagePrompt(toID uint32, fromName string, fromID uint32, body string) error {
 	prompt := FormatDirectMessagePrompt(fromName, fromID, body)
-	_, err := m.SendInput(toID, []byte(prompt))
+	_, err := m.sendInput(toID, []byte(prompt), "message")
 	return err
 }
 
@@ -571,7 +747,7 @@ func (m *SessionManager) DeliverDirectMessagePrompt(toID uint32, fromName string
 // via SendInput.
 func (m *SessionManager) DeliverRequestPrompt(toID uint32, requestID string, fromName string, fromID uint32, body string) error {
 	prompt := FormatRequestPrompt(requestID, fromName, fromID, body)
-	_, err := m.SendInput(toID, []byte(prompt))
+	_, err := m.sendInput(toID, []byte(prompt), "message")
 	return err
 }
 
@@ -583,38 +759,119 @@ func (m *SessionManager) triggerPersist() {
 	}
 }
 
+// LaunchOptions holds optional per-session settings for LaunchWithOptions.
+// The zero value launches a plain session.
+type LaunchOptions struct {
+	// SnapshotCommand is run through sh in the working directory when the
+	// session exits. Its output is stored as the session's snapshot.
+	SnapshotCommand string
+	// Script, when set, replaces command: it is run as a single command line
+	// through the node's [launch] shell, or DefaultShell.
+	Script string
+	// Workspace gives the session a fresh directory under the data dir
+	// instead of workingDir, filled from WorkspaceSource (a git URL or a
+	// template directory) when that is set.
+	Workspace       bool
+	WorkspaceSource string
+	// Remote is set for launches requested over the network, whose
+	// WorkspaceSource may only be an https or ssh git URL.
+	Remote bool
+	// Notes are attached to the session at launch (see AddNote).
+	Notes []string
+	// MaxRuntime and IdleTimeout limit the session (see watchExpiry). Zero
+	// falls back to the node's [launch] defaults.
+	MaxRuntime  time.Duration
+	IdleTimeout time.Duration
+	// Term sets TERM for the session (e.g. "xterm-256color"); empty falls
+	// back to the node's [launch] term, then the node's own TERM.
+	Term string
+	// Encoding is the character encoding the session writes; empty takes
+	// it from the session's locale (LC_ALL, LC_CTYPE or LANG), else UTF-8.
+	Encoding string
+	// Mirrors copy the session's output to extra sinks (see ParseMirror).
+	Mirrors []string
+	// Cols and Rows are the PTY's initial size, so a client attaching right
+	// away does not see the program draw for 80x24 first. Zero leaves the
+	// PTY at its default size.
+	Cols, Rows uint16
+	// History limits what is recorded on disk (HistoryInputs or
+	// HistoryNone); empty records everything but input.
+	History string
+	// RecordInput keeps the input written to the PTY in inputs.jsonl for
+	// replay. It includes attach keystrokes, such as passwords typed at a
+	// prompt, so it is off unless asked for; HistoryInputs implies it.
+	RecordInput bool
+	// Priority is the session's priority class (see ParsePriority).
+	Priority string
+	// Policy limits what the launch may run, for launches made with a
+	// scoped token (see CommandPolicy); nil allows everything.
+	Policy *CommandPolicy
+	// Deadline is when the requester stops waiting for the launch. Workspace
+	// preparation is cut short at it, and a launch not started by then
+	// fails with ErrDeadlineExceeded instead of running unseen. Zero means
+	// no deadline.
+	Deadline time.Time
+}
+
+// ErrDeadlineExceeded is returned by LaunchWithOptions when
+// LaunchOptions.Deadline passes before the session starts.
+var ErrDeadlineExceeded = errors.New("deadline exceeded before the session started")
+
+// History capture modes for sensitive sessions. Output that is not recorded
+// is still streamed to attached and watching clients, but cannot be
+// replayed, read with Logs or exported later.
+const (
+	HistoryAll    = "all"
+	HistoryInputs = "inputs" // PTY input only, no output log
+	HistoryNone   = "none"   // neither output nor input
+)
+
+// DefaultShell runs LaunchOptions.Script when no [launch] shell is configured.
+const DefaultShell = "sh -c"
+
 // Launch starts a new PTY session executing command in workingDir.
 // name is the session name (used for env injection; naming is done by the caller).
 // tags are optional labels for filtering/grouping.
 func (m *SessionManager) Launch(command []string, workingDir string, env []string, stdinData []byte, name string, tags ...string) (uint32, error) {
-	if len(command) == 0 {
-		return 0, fmt.Errorf("command must not be empty")
-	}
-
-	// Validate command binary.
-	cmdName := command[0]
-	if filepath.IsAbs(cmdName) {
-		if _, err := os.Stat(cmdName); err != nil {
-			return 0, fmt.Errorf("command %q does not exist", cmdName)
-		}
-	} else {
-		if _, err := exec.LookPath(cmdName); err != nil {
-			return 0, fmt.Errorf("command %q not found in PATH", cmdName)
-		}
-	}
+	return m.LaunchWithOptions(command, workingDir, env, stdinData, name, LaunchOptions{}, tags...)
+}
 
-	// Validate working directory.
-	info, err := os.Stat(workingDir)
+// LaunchWithOptions is Launch with additional per-session options.
+func (m *SessionManager) LaunchWithOptions(command []string, workingDir string, env []string, stdinData []byte, name string, opts LaunchOptions, tags ...string) (uint32, error) {
+	plan, err := m.PlanLaunch(command, workingDir, env, name, opts)
 	if err != nil {
-		return 0, fmt.Errorf("working directory %q does not exist", workingDir)
-	}
-	if !info.IsDir() {
-		return 0, fmt.Errorf("working directory %q is not a directory", workingDir)
+		return 0, err
+	}
+	history := plan.history
+	notes := plan.notes
+
+	// Fill the workspace before allocating an ID, so a failed clone does
+	// not use one up.
+	var workspace string
+	if opts.Workspace {
+		workspace, err = m.prepareWorkspace(opts.WorkspaceSource, opts.Remote, opts.Deadline)
+		if err != nil {
+			return 0, err
+		}
 	}
 
 	// Allocate ID (starts at 1).
 	id := m.nextID.Add(1) - 1
 
+	if workspace != "" {
+		if workspace, err = m.placeWorkspace(workspace, id); err != nil {
+			return 0, err
+		}
+		workingDir = workspace
+	}
+	// Until the process has started, a failed launch removes its workspace.
+	started := false
+	defer func() {
+		if workspace != "" && !started {
+			_ = os.RemoveAll(workspace)
+		}
+	}()
+
 	// Ensure log directory.
 	logDir := filepath.Join(m.dataDir, "sessions", fmt.Sprintf("%d", id))
 	if err := os.MkdirAll(logDir, 0o755); err != nil {
@@ -623,7 +880,7 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 	logPath := filepath.Join(logDir, "output.log")
 
 	// Build exec.Cmd.
-	cmd := exec.Command(command[0], command[1:]...)
+	cmd := &exec.Cmd{Path: plan.Path, Args: plan.Argv}
 	cmd.Dir = workingDir
 	extraEnv := []string{fmt.Sprintf("CW_SESSION_ID=%d", id)}
 	if name != "" {
@@ -632,13 +889,41 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 	if len(tags) > 0 {
 		extraEnv = append(extraEnv, "CW_COHORT_TAG="+tags[0])
 	}
-	cmd.Env = buildEnv(append(env, extraEnv...))
+	cmd.Env = buildEnv(append(plan.env, extraEnv...))
+
+	// Open output mirrors before the process starts so they see all of its
+	// output.
+	var mirrors []*outputMirror
+	closeMirrors := func() {
+		for _, mr := range mirrors {
+			close(mr.ch)
+		}
+	}
+	for _, spec := range opts.Mirrors {
+		mr, err := openMirror(id, spec, workingDir)
+		if err != nil {
+			closeMirrors()
+			return 0, err
+		}
+		mirrors = append(mirrors, mr)
+	}
+
+	if !opts.Deadline.IsZero() && !time.Now().Before(opts.Deadline) {
+		closeMirrors()
+		return 0, ErrDeadlineExceeded
+	}
 
 	// Start with a PTY.
-	ptmx, err := pty.Start(cmd)
+	var size *pty.Winsize
+	if opts.Cols > 0 && opts.Rows > 0 {
+		size = &pty.Winsize{Cols: opts.Cols, Rows: opts.Rows}
+	}
+	ptmx, err := pty.StartWithSize(cmd, size)
 	if err != nil {
+		closeMirrors()
 		return 0, fmt.Errorf("opening PTY: %w", err)
 	}
+	started = true
 
 	// Process ID.
 	var pid *uint32
@@ -647,8 +932,6 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 		pid = &p
 	}
 
-	displayCommand := strings.Join(command, " ")
-
 	broadcaster := NewBroadcaster()
 	inputCh := make(chan []byte, 256)
 	statusWatcher := NewStatusWatcher(StatusRunning())
@@ -667,19 +950,49 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 		slog.Error("failed to open message log", "id", id, "err", msgErr)
 	}
 
+	// Open input log.
+	var inputLog *EventLog
+	if plan.RecordInput {
+		var inErr error
+		inputLog, inErr = NewEventLog(filepath.Join(logDir, "inputs.jsonl"))
+		if inErr != nil {
+			slog.Error("failed to open input log", "id", id, "err", inErr)
+		}
+	}
+
 	if tags == nil {
 		tags = []string{}
 	}
 
+	createdAt := time.Now().UTC()
+	var deadline *time.Time
+	if plan.MaxRuntime > 0 {
+		d := createdAt.Add(plan.MaxRuntime)
+		deadline = &d
+	}
+
 	sess := &Session{
 		Meta: SessionMeta{
 			ID:         id,
-			Prompt:     displayCommand,
+			Prompt:     plan.Command,
 			WorkingDir: workingDir,
-			CreatedAt:  time.Now().UTC(),
+			CreatedAt:  createdAt,
 			Status:     StatusRunning().String(),
 			PID:        pid,
 			Tags:       tags,
+
+			SnapshotCommand: opts.SnapshotCommand,
+			Workspace:       workspace,
+			Notes:           notes,
+			Deadline:        deadline,
+			IdleTimeout:     plan.IdleTimeout,
+			Term:            plan.Term,
+			Encoding:        plan.encoding,
+			Mirrors:         opts.Mirrors,
+			History:         history,
+			RecordInput:     plan.RecordInput,
+			Priority:        plan.priority,
+			Token:           opts.Policy.name(),
 		},
 		master:        ptmx,
 		broadcaster:   broadcaster,
@@ -688,28 +1001,45 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 		logPath:       logPath,
 		eventLog:      eventLog,
 		messageLog:    messageLog,
+		inputLog:      inputLog,
+		mirrors:       mirrors,
 	}
 
 	m.mu.Lock()
 	m.sessions[id] = sess
 	m.mu.Unlock()
 
+	// Record which checkout the session operates on. git can be slow on
+	// network filesystems, so this runs off the launch path.
+	go m.recordGitInfo(sess, workingDir)
+
 	// Emit session.created event.
-	createdEvent := NewSessionCreatedEvent(command, workingDir, tags)
-	if eventLog != nil {
-		eventLog.Append(createdEvent)
+	m.emit(sess, tags, NewSessionCreatedEvent(command, workingDir, tags))
+
+	// Open log file, unless output is not to be recorded.
+	var logFile *os.File
+	if history == "" {
+		var logErr error
+		logFile, logErr = os.OpenFile(logPath, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o644)
+		if logErr != nil {
+			slog.Error("failed to open session log file", "id", id, "path", logPath, "err", logErr)
+		}
 	}
-	m.Subscriptions.Publish(id, tags, createdEvent)
-
-	// Open log file.
-	logFile, logErr := os.OpenFile(logPath, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o644)
-	if logErr != nil {
-		slog.Error("failed to open session log file", "id", id, "path", logPath, "err", logErr)
+	var checkpoints *checkpointWriter
+	if logFile != nil {
+		var cpErr error
+		checkpoints, cpErr = newCheckpointWriter(filepath.Join(logDir, checkpointFile))
+		if cpErr != nil {
+			slog.Error("failed to open checkpoint index", "id", id, "err", cpErr)
+		}
 	}
 
 	// Goroutine 1: PTY reader → log file + broadcast + output tracking.
 	go func() {
 		buf := make([]byte, 4096)
+		// Output size of the next session.output_summary event.
+		milestone := uint64(1)
+		var lastBytes, lastLines uint64
 		for {
 			n, readErr := ptmx.Read(buf)
 			if n > 0 {
@@ -718,9 +1048,12 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 				if logFile != nil {
 					if _, wErr := logFile.Write(data); wErr != nil {
 						slog.Error("log write error", "id", id, "err", wErr)
+					} else if checkpoints != nil {
+						checkpoints.Track(data)
 					}
 				}
 				broadcaster.Send(data)
+				sess.mirrorOutput(data)
 
 				// Track output stats.
 				sess.outputBytes.Add(uint64(n))
@@ -730,6 +1063,13 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 					}
 				}
 				sess.lastOutputAt.Store(time.Now().UTC().UnixNano())
+
+				if total := sess.outputBytes.Load(); total >= milestone {
+					lines := sess.outputLines.Load()
+					m.emit(sess, tags, NewOutputSummaryEvent(total-lastBytes, lines-lastLines, total, lines))
+					lastBytes, lastLines = total, lines
+					milestone = nextOutputMilestone(total)
+				}
 			}
 			if readErr != nil {
 				if readErr == io.EOF || isEIO(readErr) {
@@ -739,32 +1079,57 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 				break
 			}
 		}
+		sess.closeMirrors()
 		if logFile != nil {
 			logFile.Close()
 		}
+		if checkpoints != nil {
+			checkpoints.Close()
+		}
 		if eventLog != nil {
 			eventLog.Close()
 		}
 		slog.Info("output reader exited", "id", id)
 	}()
 
-	// Goroutine 2: input channel → PTY writer.
+	// Goroutine 2: input channel → PTY writer. Once the PTY can no longer
+	// be written, the rest of the input is drained and recorded as
+	// undelivered, so senders neither block on a full channel nor lose
+	// input without a trace.
 	go func() {
+		broken := false
 		for data := range inputCh {
+			if broken || statusWatcher.Get().State != "running" {
+				m.recordUndelivered(sess, "queued", data)
+				continue
+			}
 			if _, wErr := ptmx.Write(data); wErr != nil {
 				slog.Error("PTY write error", "id", id, "err", wErr)
-				break
+				m.recordUndelivered(sess, "queued", data)
+				broken = true
+				continue
+			}
+			sess.lastInputAt.Store(time.Now().UTC().UnixNano())
+			if inputLog != nil {
+				inputLog.Append(NewInputRecordEvent(data))
 			}
 		}
 		slog.Info("input writer exited", "id", id)
 	}()
 
 	// Inject stdinData into the session after a short delay.
+	// A kill before then records it as undelivered instead (see drainInput).
 	if len(stdinData) > 0 {
+		sess.pendingStdin = bytes.Clone(stdinData)
 		go func() {
 			time.Sleep(200 * time.Millisecond)
-			chunk := make([]byte, len(stdinData))
-			copy(chunk, stdinData)
+			sess.mu.Lock()
+			chunk := sess.pendingStdin
+			sess.pendingStdin = nil
+			sess.mu.Unlock()
+			if chunk == nil {
+				return
+			}
 			select {
 			case inputCh <- chunk:
 			default:
@@ -788,7 +1153,6 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 		slog.Info("session process exited", "id", id, "code", exitCode)
 
 		now := time.Now().UTC()
-		durationMs := now.Sub(sess.Meta.CreatedAt).Milliseconds()
 
 		sess.mu.Lock()
 		sess.Meta.ExitCode = &exitCode
@@ -796,25 +1160,40 @@ func (m *SessionManager) Launch(command []string, workingDir string, env []strin
 		sess.mu.Unlock()
 
 		// Capture result from output log before status change.
-		result := captureResult(sess.logPath, 200)
+		result := captureResult(sess.logPath, sess.Meta.Encoding, 200)
 		sess.mu.Lock()
 		sess.Meta.Result = result
 		sess.mu.Unlock()
 
-		statusWatcher.Set(StatusCompleted(exitCode))
+		// Snapshot the working directory before waiters see completion.
+		if opts.SnapshotCommand != "" {
+			snapPath := filepath.Join(logDir, snapshotFile)
+			if err := captureSnapshot(opts.SnapshotCommand, workingDir, snapPath); err != nil {
+				slog.Warn("working directory snapshot failed", "id", id, "err", err)
+			}
+		}
 
-		// Emit session.status event.
-		statusEvent := NewSessionStatusEvent("running", "completed", &exitCode, &durationMs)
-		if sess.eventLog != nil {
-			sess.eventLog.Append(statusEvent)
+		reason := fmt.Sprintf("process exited with code %d", exitCode)
+		if statusWatcher.Get().State == "killed" {
+			sess.mu.Lock()
+			reason = sess.Meta.StatusReason + "; " + reason
+			sess.mu.Unlock()
 		}
-		m.Subscriptions.Publish(id, tags, statusEvent)
+		sess.setStatusReason(reason)
+		m.setStatus(sess, StatusCompleted(exitCode))
+
+		// Emit session.status event.
+		m.emit(sess, tags, NewSessionStatusEvent("running", "completed", &exitCode, sess.Meta.CreatedAt, &now))
 
 		m.releaseName(id)
 	}()
 
+	if deadline != nil || plan.IdleTimeout > 0 {
+		go m.watchExpiry(sess, id)
+	}
+
 	slog.Info("session launched", "id", id)
-	m.triggerPersist()
+	m.persistNow()
 	return id, nil
 }
 
@@ -846,17 +1225,22 @@ func (m *SessionManager) Attach(id uint32) (*AttachChannels, error) {
 
 	sess.attachedCount.Add(1)
 	subID, ch := sess.broadcaster.Subscribe(4096)
+	clientID := strconv.FormatUint(subID, 10)
+	m.emit(sess, sess.Meta.Tags, NewAttachedEvent(clientID))
 
 	return &AttachChannels{
+		ClientID: clientID,
 		OutputCh: ch,
 		OutputID: subID,
 		InputCh:  sess.inputCh,
 		Status:   sess.statusWatcher,
+		Clients:  &sess.attachedCount,
 	}, nil
 }
 
-// Detach decrements the attached client count for a session.
-func (m *SessionManager) Detach(id uint32) error {
+// Detach decrements the attached client count for a session. clientID is
+// AttachChannels.ClientID of the client that attached.
+func (m *SessionManager) Detach(id uint32, clientID string) error {
 	m.mu.RLock()
 	sess, ok := m.sessions[id]
 	m.mu.RUnlock()
@@ -864,6 +1248,7 @@ func (m *SessionManager) Detach(id uint32) error {
 		return fmt.Errorf("session %d not found", id)
 	}
 	sess.attachedCount.Add(-1)
+	m.emit(sess, sess.Meta.Tags, NewDetachedEvent(clientID))
 	return nil
 }
 
@@ -880,6 +1265,11 @@ func (m *SessionManager) Resize(id uint32, cols, rows uint16) error {
 
 // Kill sends SIGTERM to the session's process and marks it killed.
 func (m *SessionManager) Kill(id uint32) error {
+	return m.kill(id, "killed by request")
+}
+
+// kill is Kill with the reason recorded in SessionMeta.StatusReason.
+func (m *SessionManager) kill(id uint32, reason string) error {
 	m.mu.RLock()
 	sess, ok := m.sessions[id]
 	m.mu.RUnlock()
@@ -887,19 +1277,69 @@ func (m *SessionManager) Kill(id uint32) error {
 		return fmt.Errorf("session %d not found", id)
 	}
 
-	sess.statusWatcher.Set(StatusKilled())
+	from := sess.statusWatcher.Get().State
+	sess.setStatusReason(reason)
+	m.setStatus(sess, StatusKilled())
+	if from == "running" {
+		now := time.Now().UTC()
+		m.emit(sess, sess.Meta.Tags, NewSessionStatusEvent(from, "killed", nil, sess.Meta.CreatedAt, &now))
+	}
+	m.drainInput(sess)
 
 	if sess.Meta.PID != nil {
 		_ = syscall.Kill(int(*sess.Meta.PID), syscall.SIGTERM)
 	}
 
+	m.persistNow()
+	m.releaseName(id)
+	return nil
+}
+
+// drainInput records the input still queued for a session that has just
+// been killed, so it shows up as undelivered rather than reaching a
+// process that is shutting down.
+func (m *SessionManager) drainInput(sess *Session) {
 	sess.mu.Lock()
-	sess.Meta.Status = StatusKilled().String()
+	stdin := sess.pendingStdin
+	sess.pendingStdin = nil
 	sess.mu.Unlock()
+	if stdin != nil {
+		m.recordUndelivered(sess, "stdin_data", stdin)
+	}
+	for {
+		select {
+		case data := <-sess.inputCh:
+			m.recordUndelivered(sess, "queued", data)
+		default:
+			return
+		}
+	}
+}
 
+// undeliveredPreview is how many columns of undelivered input are kept.
+const undeliveredPreview = 60
+
+// recordUndelivered notes input that never reached a session's terminal, in
+// its metadata (SessionInfo.UndeliveredInput) and as a
+// session.input_undelivered event.
+// Only sessions that record all history keep any of the input itself: a
+// short preview in the metadata and the bytes in the event.
+func (m *SessionManager) recordUndelivered(sess *Session, source string, data []byte) {
+	slog.Info("input not delivered", "id", sess.Meta.ID, "source", source, "bytes", len(data))
+	u := protocol.UndeliveredInput{Source: source, Bytes: len(data)}
+	sess.mu.Lock()
+	keep := cmp.Or(sess.Meta.History, HistoryAll) == HistoryAll
+	if keep {
+		u.Preview = textwidth.Truncate(string(data), undeliveredPreview)
+	} else {
+		data = nil
+	}
+	sess.Meta.UndeliveredInput = append(sess.Meta.UndeliveredInput, u)
+	tags := sess.Meta.Tags
+	sess.mu.Unlock()
 	m.triggerPersist()
-	m.releaseName(id)
-	return nil
+
+	m.emit(sess, tags, NewInputUndeliveredEvent(source, u.Bytes, data))
 }
 
 // KillAll kills every running session and returns the count killed.
@@ -919,20 +1359,98 @@ func (m *SessionManager) KillAll() int {
 	return len(ids)
 }
 
+// DataDir returns the directory the manager keeps its state in.
+func (m *SessionManager) DataDir() string {
+	return m.dataDir
+}
+
 // LogPath returns the path to a session's output log file.
 func (m *SessionManager) LogPath(id uint32) (string, error) {
 	m.mu.RLock()
-	_, ok := m.sessions[id]
+	sess, ok := m.sessions[id]
 	m.mu.RUnlock()
 	if !ok {
 		return "", fmt.Errorf("session %d not found", id)
 	}
+	sess.mu.Lock()
+	removed, url, history := sess.Meta.LogRemoved, sess.Meta.LogURL, sess.Meta.History
+	sess.mu.Unlock()
+	if removed {
+		return "", fmt.Errorf("session %d's log was shipped to %s and removed from this node", id, url)
+	}
+	if history != "" {
+		return "", fmt.Errorf("session %d's output is not recorded (history: %s)", id, history)
+	}
 	return filepath.Join(m.dataDir, "sessions", fmt.Sprintf("%d", id), "output.log"), nil
 }
 
+// LaunchedBy returns the name of the scoped token that launched a session,
+// empty for the node token, and whether the session exists.
+func (m *SessionManager) LaunchedBy(id uint32) (string, bool) {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return "", false
+	}
+	sess.mu.Lock()
+	defer sess.mu.Unlock()
+	return sess.Meta.Token, true
+}
+
+// Encoding returns the encoding a session's output is decoded from: its
+// canonical name, or "" for UTF-8 and unknown sessions.
+func (m *SessionManager) Encoding(id uint32) string {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return ""
+	}
+	return sess.Meta.Encoding
+}
+
+// CheckpointPath returns the path of a session's output checkpoint index.
+func (m *SessionManager) CheckpointPath(id uint32) (string, error) {
+	logPath, err := m.LogPath(id)
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(filepath.Dir(logPath), checkpointFile), nil
+}
+
+// ReadInputs returns every input chunk written to a session's PTY, in order,
+// from its inputs.jsonl log. If tail > 0 only the last tail records are
+// returned.
+func (m *SessionManager) ReadInputs(id uint32, tail int) ([]Event, error) {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return nil, fmt.Errorf("session %d not found", id)
+	}
+	sess.mu.Lock()
+	recorded := sess.Meta.RecordInput
+	sess.mu.Unlock()
+	if !recorded {
+		return nil, fmt.Errorf("session %d's input is not recorded (launch it with cw run --record-input)", id)
+	}
+	if sess.inputLog == nil {
+		return nil, nil
+	}
+	return sess.inputLog.ReadTail(tail)
+}
+
 // SendInput writes data to a session's PTY. It is non-blocking: if the input
 // channel is full the send fails with an error.
 func (m *SessionManager) SendInput(id uint32, data []byte) (int, error) {
+	return m.sendInput(id, data, "send")
+}
+
+// sendInput is SendInput with the source recorded in the session.input
+// event ("send", "message" or "shutdown"). Keystrokes from attached clients
+// go straight to the input channel and are not recorded as events.
+func (m *SessionManager) sendInput(id uint32, data []byte, source string) (int, error) {
 	m.mu.RLock()
 	sess, ok := m.sessions[id]
 	m.mu.RUnlock()
@@ -940,8 +1458,13 @@ func (m *SessionManager) SendInput(id uint32, data []byte) (int, error) {
 		return 0, fmt.Errorf("session %d not found", id)
 	}
 
+	if sess.statusWatcher.Get().State != "running" {
+		return 0, fmt.Errorf("session %d is not running", id)
+	}
+
 	select {
 	case sess.inputCh <- data:
+		m.emit(sess, sess.Meta.Tags, NewInputEvent(source, len(data)))
 		return len(data), nil
 	default:
 		return 0, fmt.Errorf("input channel full for session %d", id)
@@ -984,16 +1507,20 @@ func (m *SessionManager) GetStatus(id uint32) (protocol.SessionInfo, uint64, err
 	return info, outputSize, nil
 }
 
-// SubscribeOutput returns a broadcast subscription for a session's PTY output.
-func (m *SessionManager) SubscribeOutput(id uint32) (uint64, <-chan []byte, error) {
+// SubscribeOutput returns a broadcast subscription for a session's PTY
+// output, and the offset in its output log the subscription starts at.
+// The log holds at least the bytes before that offset, so a watcher can
+// replay them from the log and continue from the channel without a gap or
+// an overlap.
+func (m *SessionManager) SubscribeOutput(id uint32) (uint64, <-chan OutputChunk, uint64, error) {
 	m.mu.RLock()
 	sess, ok := m.sessions[id]
 	m.mu.RUnlock()
 	if !ok {
-		return 0, nil, fmt.Errorf("session %d not found", id)
+		return 0, nil, 0, fmt.Errorf("session %d not found", id)
 	}
-	subID, ch := sess.broadcaster.Subscribe(4096)
-	return subID, ch, nil
+	subID, ch, offset := sess.broadcaster.SubscribeAt(4096)
+	return subID, ch, offset, nil
 }
 
 // UnsubscribeOutput removes a broadcast subscription for a session.
@@ -1018,30 +1545,46 @@ func (m *SessionManager) SubscribeStatus(id uint32) (*StatusWatcher, error) {
 	return sess.statusWatcher, nil
 }
 
-// RefreshStatuses synchronises each session's Meta.Status with its
-// StatusWatcher and triggers persistence if anything changed.
-func (m *SessionManager) RefreshStatuses() {
-	changed := false
+// setStatus changes a session's status, wakes its watchers and schedules
+// persistence. Every status change goes through here, so Meta.Status never
+// needs to be polled back into sync with the StatusWatcher.
+func (m *SessionManager) setStatus(sess *Session, status SessionStatus) {
+	sess.statusWatcher.Set(status)
+	sess.mu.Lock()
+	sess.Meta.Status = status.String()
+	sess.mu.Unlock()
+	m.triggerPersist()
+}
 
-	m.mu.RLock()
-	for _, sess := range m.sessions {
-		current := sess.statusWatcher.Get().String()
-		sess.mu.Lock()
-		if sess.Meta.Status != current {
-			sess.Meta.Status = current
-			changed = true
-		}
-		sess.mu.Unlock()
-	}
-	m.mu.RUnlock()
+// PersistMeta appends the sessions that changed since the last call to the
+// journal, then atomically replaces dataDir/sessions.json with all session
+// metadata. Nothing is written when nothing changed.
+func (m *SessionManager) PersistMeta() {
+	m.persist(false)
+}
 
-	if changed {
-		m.triggerPersist()
-	}
+// Quiesce writes pending metadata changes, then runs fn while no further
+// changes are written, so fn sees sessions.json and the journal as one
+// consistent snapshot. Sessions keep running and producing output.
+func (m *SessionManager) Quiesce(fn func() error) error {
+	m.persist(true)
+	m.persistMu.Lock()
+	defer m.persistMu.Unlock()
+	return fn()
 }
 
-// PersistMeta writes all session metadata to dataDir/sessions.json.
-func (m *SessionManager) PersistMeta() {
+// persistNow persists a critical change (a launch or kill) straight away
+// instead of waiting for the debounced PersistMeta, so a crash right after
+// cannot lose the record of a running process.
+func (m *SessionManager) persistNow() {
+	m.persist(true)
+}
+
+func (m *SessionManager) persist(critical bool) {
+	m.persistMu.Lock()
+	defer m.persistMu.Unlock()
+	durable := m.fsync == FsyncAlways || (critical && m.fsync == FsyncCritical)
+
 	m.mu.RLock()
 	metas := make([]SessionMeta, 0, len(m.sessions))
 	for _, sess := range m.sessions {
@@ -1050,6 +1593,21 @@ func (m *SessionManager) PersistMeta() {
 		sess.mu.Unlock()
 	}
 	m.mu.RUnlock()
+	sort.Slice(metas, func(i, j int) bool { return metas[i].ID < metas[j].ID })
+
+	entries, state, err := m.journalChanges(metas, time.Now().UTC())
+	if err != nil {
+		slog.Error("failed to serialise session metadata", "err", err)
+		return
+	}
+	if len(entries) == 0 {
+		return
+	}
+	if err := appendJournal(m.dataDir, entries, durable); err != nil {
+		slog.Error("failed to append session journal", "err", err)
+	} else {
+		m.persisted = state
+	}
 
 	path := filepath.Join(m.dataDir, "sessions.json")
 	data, err := json.MarshalIndent(metas, "", "  ")
@@ -1057,7 +1615,7 @@ func (m *SessionManager) PersistMeta() {
 		slog.Error("failed to serialise session metadata", "err", err)
 		return
 	}
-	if err := os.WriteFile(path, data, 0o644); err != nil {
+	if err := writeFileAtomic(path, data, durable); err != nil {
 		slog.Error("failed to persist session metadata", "path", path, "err", err)
 	}
 }
@@ -1088,6 +1646,7 @@ func (m *SessionManager) buildSessionInfo(s *Session) protocol.SessionInfo {
 		OutputBytes:   &outputBytes,
 		OutputLines:   &outputLines,
 		AttachedCount: attachedCount,
+		Workspace:     s.Meta.Workspace,
 	}
 
 	// File-based output size.
@@ -1110,6 +1669,23 @@ func (m *SessionManager) buildSessionInfo(s *Session) protocol.SessionInfo {
 	if s.Meta.Result != nil {
 		info.LastOutputSnippet = s.Meta.Result
 	}
+	info.GitRoot, info.GitBranch, info.GitCommit = s.Meta.GitRoot, s.Meta.GitBranch, s.Meta.GitCommit
+	info.Notes = notesInfo(s.Meta.Notes)
+	info.Bookmarks = bookmarksInfo(s.Meta.Bookmarks)
+	info.LogURL = s.Meta.LogURL
+	info.ImportedFrom = s.Meta.ImportedFrom
+	info.Term = s.Meta.Term
+	info.Encoding = cmp.Or(s.Meta.Encoding, charset.UTF8)
+	info.Mirrors = s.Meta.Mirrors
+	info.History = cmp.Or(s.Meta.History, HistoryAll)
+	info.RecordInput = s.Meta.RecordInput
+	info.Priority = cmp.Or(s.Meta.Priority, PriorityNormal)
+	info.Token = s.Meta.Token
+	info.UndeliveredInput = s.Meta.UndeliveredInput
+	if s.Meta.StatusChangedAt != nil {
+		info.StatusReason = s.Meta.StatusReason
+		info.StatusChangedAt = s.Meta.StatusChangedAt.Format(time.RFC3339)
+	}
 	s.mu.Unlock()
 
 	// Last output timestamp.
@@ -1118,6 +1694,11 @@ func (m *SessionManager) buildSessionInfo(s *Session) protocol.SessionInfo {
 		info.LastOutputAt = &lastStr
 	}
 
+	if e, ok := s.expiry(); ok && status.State == "running" {
+		info.ExpiresAt = e.At.UTC().Format(time.RFC3339)
+		info.ExpiryLimit = e.Limit
+	}
+
 	return info
 }
 
@@ -1158,6 +1739,26 @@ func matchesTags(sessionTags, filterTags []string) bool {
 	return false
 }
 
+// ListRunning returns the running sessions that KillByTags(tags) would
+// terminate, or that KillAll would when tags is empty.
+func (m *SessionManager) ListRunning(tags []string) []protocol.SessionInfo {
+	m.mu.RLock()
+	defer m.mu.RUnlock()
+
+	infos := make([]protocol.SessionInfo, 0)
+	for _, s := range m.sessions {
+		if s.statusWatcher.Get().State != "running" {
+			continue
+		}
+		if len(tags) > 0 && !matchesTags(s.Meta.Tags, tags) {
+			continue
+		}
+		infos = append(infos, m.buildSessionInfo(s))
+	}
+	sort.Slice(infos, func(i, j int) bool { return infos[i].ID < infos[j].ID })
+	return infos
+}
+
 // KillByTags kills all running sessions matching any of the given tags.
 func (m *SessionManager) KillByTags(tags []string) int {
 	m.mu.RLock()
@@ -1218,7 +1819,7 @@ var ansiRegex = regexp.MustCompile(`\x1b\[[0-9;]*[a-zA-Z]|\x1b\][^\x07]*\x07|\x1
 // captureResult reads the tail of a log file, strips ANSI codes, and returns
 // the last maxLines lines. It reads from the end of the file to avoid loading
 // the entire file into memory.
-func captureResult(logPath string, maxLines int) *string {
+func captureResult(logPath, encoding string, maxLines int) *string {
 	f, err := os.Open(logPath)
 	if err != nil {
 		return nil
@@ -1243,7 +1844,8 @@ func captureResult(logPath string, maxLines int) *string {
 	}
 
 	// Strip ANSI escape codes.
-	clean := ansiRegex.ReplaceAllString(string(buf), "")
+	text, _ := charset.String(encoding, buf)
+	clean := ansiRegex.ReplaceAllString(text, "")
 
 	// Take last N lines.
 	lines := strings.Split(clean, "\n")
@@ -1264,6 +1866,21 @@ func captureResult(logPath string, maxLines int) *string {
 	return &result
 }
 
+// firstOutputMilestone is the output size, after the first byte, at which a
+// session.output_summary event is emitted; each one after is at double the
+// last.
+const firstOutputMilestone = 64 << 10
+
+// nextOutputMilestone returns the output size of the next
+// session.output_summary event after one at total bytes.
+func nextOutputMilestone(total uint64) uint64 {
+	next := uint64(firstOutputMilestone)
+	for next <= total {
+		next *= 2
+	}
+	return next
+}
+
 // isEIO returns true if err is an EIO (errno 5) wrapped in an *os.PathError.
 func isEIO(err error) bool {
 	var pe *os.PathError
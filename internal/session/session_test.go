@@ -1,8 +1,14 @@
 package session
 
 import (
+	"fmt"
+	"os"
+	"path/filepath"
 	"strings"
 	"testing"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
 )
 
 func TestBuildEnvStripsClaudeCode(t *testing.T) {
@@ -61,3 +67,157 @@ func TestBuildEnvOverridesExisting(t *testing.T) {
 		}
 	}
 }
+
+func TestHistoryModesSkipRecording(t *testing.T) {
+	dir := t.TempDir()
+	sm, err := NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	for _, mode := range []string{HistoryInputs, HistoryNone} {
+		id, err := sm.LaunchWithOptions([]string{"echo", "secret"}, "/tmp", nil, nil, "", LaunchOptions{History: mode})
+		if err != nil {
+			t.Fatalf("Launch(%s): %v", mode, err)
+		}
+		logDir := filepath.Join(dir, "sessions", fmt.Sprint(id))
+		if _, err := os.Stat(filepath.Join(logDir, "output.log")); !os.IsNotExist(err) {
+			t.Errorf("%s: expected no output.log, got %v", mode, err)
+		}
+		_, err = os.Stat(filepath.Join(logDir, "inputs.jsonl"))
+		if (mode == HistoryNone) != os.IsNotExist(err) {
+			t.Errorf("%s: unexpected inputs.jsonl state: %v", mode, err)
+		}
+		if _, err := sm.ReadInputs(id, 0); (mode == HistoryNone) != (err != nil) {
+			t.Errorf("%s: unexpected ReadInputs error: %v", mode, err)
+		}
+		if _, err := sm.LogPath(id); err == nil {
+			t.Errorf("%s: expected LogPath to report that output is not recorded", mode)
+		}
+		for _, info := range sm.List() {
+			if info.ID == id && info.History != mode {
+				t.Errorf("expected History %q, got %q", mode, info.History)
+			}
+		}
+	}
+
+	if _, err := sm.LaunchWithOptions([]string{"true"}, "/tmp", nil, nil, "", LaunchOptions{History: "output"}); err == nil {
+		t.Fatal("expected an unknown history mode to be rejected")
+	}
+	if _, err := sm.LaunchWithOptions([]string{"true"}, "/tmp", nil, nil, "", LaunchOptions{History: HistoryNone, RecordInput: true}); err == nil {
+		t.Fatal("expected input recording to be refused with history none")
+	}
+}
+
+func TestInputNotRecordedByDefault(t *testing.T) {
+	dir := t.TempDir()
+	sm, err := NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	id, err := sm.LaunchWithOptions([]string{"cat"}, "/tmp", nil, nil, "", LaunchOptions{})
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	defer sm.Kill(id)
+	if _, err := sm.SendInput(id, []byte("hunter2\n")); err != nil {
+		t.Fatalf("SendInput: %v", err)
+	}
+	time.Sleep(100 * time.Millisecond)
+	if _, err := os.Stat(filepath.Join(dir, "sessions", fmt.Sprint(id), "inputs.jsonl")); !os.IsNotExist(err) {
+		t.Fatalf("expected no inputs.jsonl without RecordInput, got %v", err)
+	}
+	if _, err := sm.ReadInputs(id, 0); err == nil {
+		t.Fatal("expected ReadInputs to report that input is not recorded")
+	}
+}
+
+func TestKillRecordsUndeliveredInput(t *testing.T) {
+	dir := t.TempDir()
+	sm, err := NewSessionManager(dir)
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	id, err := sm.LaunchWithOptions([]string{"sleep", "30"}, "/tmp", nil, []byte("never sent\n"), "", LaunchOptions{RecordInput: true})
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	// Killed before the stdin_data injection delay runs out.
+	if err := sm.Kill(id); err != nil {
+		t.Fatalf("Kill: %v", err)
+	}
+	if _, err := sm.SendInput(id, []byte("late\n")); err == nil {
+		t.Error("expected input to a killed session to be refused")
+	}
+
+	var info protocol.SessionInfo
+	for _, i := range sm.List() {
+		if i.ID == id {
+			info = i
+		}
+	}
+	if len(info.UndeliveredInput) != 1 {
+		t.Fatalf("undelivered input = %+v", info.UndeliveredInput)
+	}
+	if u := info.UndeliveredInput[0]; u.Source != "stdin_data" || u.Preview != "never sent\n" || u.Bytes != 11 {
+		t.Fatalf("undelivered input = %+v", u)
+	}
+
+	events, err := ReadEventLog(filepath.Join(dir, "sessions", fmt.Sprint(id), "events.jsonl"))
+	if err != nil {
+		t.Fatalf("ReadEventLog: %v", err)
+	}
+	found := false
+	for _, e := range events {
+		found = found || e.Type == EventUndelivered
+	}
+	if !found {
+		t.Fatal("no session.input_undelivered event")
+	}
+
+	// The injection must not deliver it after all.
+	time.Sleep(300 * time.Millisecond)
+	inputs, _ := sm.ReadInputs(id, 0)
+	if len(inputs) != 0 {
+		t.Fatalf("stdin_data was written after the kill: %+v", inputs)
+	}
+}
+
+func TestUndeliveredInputKeepsNoPreviewWithoutHistory(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	id, err := sm.LaunchWithOptions([]string{"sleep", "30"}, "/tmp", nil, []byte("hunter2\n"), "", LaunchOptions{History: HistoryNone})
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	if err := sm.Kill(id); err != nil {
+		t.Fatalf("Kill: %v", err)
+	}
+	for _, i := range sm.List() {
+		if i.ID != id {
+			continue
+		}
+		if len(i.UndeliveredInput) != 1 || i.UndeliveredInput[0].Bytes != 8 || i.UndeliveredInput[0].Preview != "" {
+			t.Fatalf("expected only the size of the input to be kept, got %+v", i.UndeliveredInput)
+		}
+	}
+}
+
+func TestBroadcasterChunkOffsets(t *testing.T) {
+	b := NewBroadcaster()
+	b.Send([]byte("before"))
+	id, ch, offset := b.SubscribeAt(1)
+	defer b.Unsubscribe(id)
+	if offset != 6 {
+		t.Fatalf("expected the subscription to start at 6, got %d", offset)
+	}
+	b.Send([]byte("abc"))
+	b.Send([]byte("dropped")) // the channel is full
+	<-ch
+	b.Send([]byte("xyz"))
+	chunk := <-ch
+	if chunk.Offset != 16 || string(chunk.Data) != "xyz" {
+		t.Fatalf("expected xyz at 16 after the dropped chunk, got %q at %d", chunk.Data, chunk.Offset)
+	}
+}
@@ -0,0 +1,84 @@
+package session
+
+import (
+	"log/slog"
+	"syscall"
+	"time"
+)
+
+// Shutdown winds down every running session when the node stops. wrapUp
+// (e.g. "/exit\r" or "\x03") is written to each session first, and the
+// sessions get grace to exit on their own, so agent CLIs can flush state
+// and print cost summaries. Sessions still running are then sent SIGTERM
+// and, killAfter later, SIGKILL — each time to the session's whole process
+// group, so tools the program started do not outlive it. Shutdown returns
+// once every session has exited or the last signal is sent.
+func (m *SessionManager) Shutdown(wrapUp []byte, grace, killAfter time.Duration) {
+	m.mu.RLock()
+	var running []*Session
+	for _, s := range m.sessions {
+		if s.statusWatcher.Get().State == "running" && s.Meta.PID != nil {
+			running = append(running, s)
+		}
+	}
+	m.mu.RUnlock()
+	if len(running) == 0 {
+		return
+	}
+
+	if len(wrapUp) > 0 {
+		slog.Info("sending wrap-up input to running sessions", "count", len(running), "grace", grace)
+		for _, s := range running {
+			if _, err := m.sendInput(s.Meta.ID, wrapUp, "shutdown"); err != nil {
+				slog.Warn("wrap-up input not sent", "id", s.Meta.ID, "err", err)
+			}
+		}
+		running = waitExited(running, grace)
+	}
+
+	for _, s := range running {
+		s.setStatusReason("node shutting down")
+		m.setStatus(s, StatusKilled())
+		signalGroup(s, syscall.SIGTERM)
+	}
+	m.persistNow()
+	running = waitExited(running, killAfter)
+
+	for _, s := range running {
+		slog.Warn("session ignored SIGTERM; sending SIGKILL", "id", s.Meta.ID)
+		signalGroup(s, syscall.SIGKILL)
+	}
+	waitExited(running, time.Second)
+	m.persistNow()
+}
+
+// waitExited waits up to timeout for the sessions' processes to exit and
+// returns the ones still alive.
+func waitExited(sessions []*Session, timeout time.Duration) []*Session {
+	deadline := time.Now().Add(timeout)
+	for {
+		alive := sessions[:0:0]
+		for _, s := range sessions {
+			s.mu.Lock()
+			exited := s.Meta.CompletedAt != nil
+			s.mu.Unlock()
+			if !exited {
+				alive = append(alive, s)
+			}
+		}
+		if len(alive) == 0 || !time.Now().Before(deadline) {
+			return alive
+		}
+		sessions = alive
+		time.Sleep(100 * time.Millisecond)
+	}
+}
+
+// signalGroup sends sig to the process group the session's program leads
+// (the PTY starts it in a new session), falling back to the program alone.
+func signalGroup(s *Session, sig syscall.Signal) {
+	pid := int(*s.Meta.PID)
+	if err := syscall.Kill(-pid, sig); err != nil {
+		_ = syscall.Kill(pid, sig)
+	}
+}
@@ -0,0 +1,44 @@
+package session
+
+import (
+	"strings"
+	"testing"
+	"time"
+)
+
+func TestShutdownWrapsUpThenSignals(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatalf("NewSessionManager: %v", err)
+	}
+	// One session exits on the wrap-up input, the other ignores it.
+	polite, err := sm.Launch([]string{"sh", "-c", "read line; echo bye"}, "/tmp", nil, nil, "")
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	stubborn, err := sm.Launch([]string{"sh", "-c", "sleep 30; true"}, "/tmp", nil, nil, "")
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	time.Sleep(300 * time.Millisecond)
+
+	start := time.Now()
+	sm.Shutdown([]byte("exit\n"), 2*time.Second, 2*time.Second)
+	if d := time.Since(start); d > 5*time.Second {
+		t.Fatalf("Shutdown took %s", d)
+	}
+
+	reasons := map[uint32]string{}
+	for _, info := range sm.List() {
+		if !strings.HasPrefix(info.Status, "completed") {
+			t.Fatalf("session %d still %s after Shutdown", info.ID, info.Status)
+		}
+		reasons[info.ID] = info.StatusReason
+	}
+	if strings.Contains(reasons[polite], "node shutting down") {
+		t.Errorf("expected session %d to exit on the wrap-up input, got reason %q", polite, reasons[polite])
+	}
+	if !strings.Contains(reasons[stubborn], "node shutting down") {
+		t.Errorf("expected session %d to be signalled, got reason %q", stubborn, reasons[stubborn])
+	}
+}
@@ -0,0 +1,69 @@
+package session
+
+import (
+	"fmt"
+	"strconv"
+	"strings"
+	"syscall"
+
+	"golang.org/x/sys/unix"
+)
+
+// signalNames lists the signals that may be sent to a session by name.
+var signalNames = map[string]syscall.Signal{
+	"HUP":   syscall.SIGHUP,
+	"INT":   syscall.SIGINT,
+	"QUIT":  syscall.SIGQUIT,
+	"KILL":  syscall.SIGKILL,
+	"USR1":  syscall.SIGUSR1,
+	"USR2":  syscall.SIGUSR2,
+	"TERM":  syscall.SIGTERM,
+	"CONT":  syscall.SIGCONT,
+	"STOP":  syscall.SIGSTOP,
+	"TSTP":  syscall.SIGTSTP,
+	"WINCH": syscall.SIGWINCH,
+}
+
+// ParseSignal accepts a signal name with or without the SIG prefix ("INT",
+// "sigint") or a signal number ("2").
+func ParseSignal(name string) (syscall.Signal, error) {
+	upper := strings.TrimPrefix(strings.ToUpper(strings.TrimSpace(name)), "SIG")
+	if sig, ok := signalNames[upper]; ok {
+		return sig, nil
+	}
+	if n, err := strconv.Atoi(upper); err == nil && n > 0 && n < 65 {
+		return syscall.Signal(n), nil
+	}
+	return 0, fmt.Errorf("unknown signal %q", name)
+}
+
+// Signal delivers sig to the foreground process group of a session's PTY —
+// the processes a Ctrl+C typed at the terminal would reach. Unlike Kill, the
+// session is not marked killed; it ends only if the program exits.
+func (m *SessionManager) Signal(id uint32, sig syscall.Signal) error {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return fmt.Errorf("session %d not found", id)
+	}
+	if state := sess.statusWatcher.Get().State; state != "running" {
+		return fmt.Errorf("session %d is not running (%s)", id, state)
+	}
+	if sess.Meta.PID == nil {
+		return fmt.Errorf("session %d has no process", id)
+	}
+
+	// The session leader heads its own process group (the PTY starts it in
+	// a new session); fall back to it if the foreground group is unknown.
+	pgrp := int(*sess.Meta.PID)
+	if sess.master != nil {
+		if fg, err := unix.IoctlGetInt(int(sess.master.Fd()), unix.TIOCGPGRP); err == nil && fg > 0 {
+			pgrp = fg
+		}
+	}
+	if err := syscall.Kill(-pgrp, sig); err != nil {
+		return fmt.Errorf("signalling session %d: %w", id, err)
+	}
+	return nil
+}
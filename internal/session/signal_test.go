@@ -0,0 +1,29 @@
+package session
+
+import (
+	"syscall"
+	"testing"
+)
+
+func TestParseSignal(t *testing.T) {
+	tests := []struct {
+		in   string
+		want syscall.Signal
+	}{
+		{"INT", syscall.SIGINT},
+		{"sigterm", syscall.SIGTERM},
+		{" usr1 ", syscall.SIGUSR1},
+		{"9", syscall.SIGKILL},
+	}
+	for _, tt := range tests {
+		got, err := ParseSignal(tt.in)
+		if err != nil || got != tt.want {
+			t.Errorf("ParseSignal(%q) = %v, %v; want %v", tt.in, got, err, tt.want)
+		}
+	}
+	for _, bad := range []string{"", "BOGUS", "0", "99"} {
+		if _, err := ParseSignal(bad); err == nil {
+			t.Errorf("ParseSignal(%q) should fail", bad)
+		}
+	}
+}
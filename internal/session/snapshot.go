@@ -0,0 +1,67 @@
+package session
+
+import (
+	"context"
+	"fmt"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"time"
+)
+
+// DefaultSnapshotCommand records what changed in a git working tree. It is
+// used when a snapshot is requested without an explicit command.
+const DefaultSnapshotCommand = "git status --short && git --no-pager diff"
+
+// snapshotFile is the per-session file holding the captured snapshot,
+// stored next to output.log.
+const snapshotFile = "snapshot.txt"
+
+// snapshotTimeout bounds how long a snapshot command may run.
+const snapshotTimeout = 30 * time.Second
+
+// captureSnapshot runs command through sh in workingDir and writes its
+// combined output to path. A non-zero exit is recorded in the file rather
+// than treated as an error, so e.g. running git outside a repository still
+// leaves a readable explanation behind.
+func captureSnapshot(command, workingDir, path string) error {
+	ctx, cancel := context.WithTimeout(context.Background(), snapshotTimeout)
+	defer cancel()
+
+	cmd := exec.CommandContext(ctx, "sh", "-c", command)
+	cmd.Dir = workingDir
+	cmd.Env = buildEnv(nil)
+	out, runErr := cmd.CombinedOutput()
+	if runErr != nil {
+		out = append(out, fmt.Sprintf("\n[snapshot command failed: %v]\n", runErr)...)
+	}
+	return os.WriteFile(path, out, 0o644)
+}
+
+// Snapshot returns the working directory snapshot captured when session id
+// exited. It fails if the session was launched without a snapshot or has not
+// finished yet.
+func (m *SessionManager) Snapshot(id uint32) (string, error) {
+	m.mu.RLock()
+	sess, ok := m.sessions[id]
+	m.mu.RUnlock()
+	if !ok {
+		return "", fmt.Errorf("session %d not found", id)
+	}
+
+	sess.mu.Lock()
+	snapshotCmd := sess.Meta.SnapshotCommand
+	sess.mu.Unlock()
+	if snapshotCmd == "" {
+		return "", fmt.Errorf("session %d was launched without a snapshot", id)
+	}
+
+	data, err := os.ReadFile(filepath.Join(filepath.Dir(sess.logPath), snapshotFile))
+	if err != nil {
+		if os.IsNotExist(err) {
+			return "", fmt.Errorf("session %d has no snapshot yet (still running)", id)
+		}
+		return "", fmt.Errorf("reading snapshot: %w", err)
+	}
+	return string(data), nil
+}
@@ -0,0 +1,189 @@
+package session
+
+import (
+	"context"
+	"fmt"
+	"log/slog"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"regexp"
+	"strconv"
+	"strings"
+	"time"
+)
+
+// workspacesDir holds per-session workspaces under the data directory.
+const workspacesDir = "workspaces"
+
+// pendingWorkspacePrefix names workspaces being filled, before the launch
+// has a session ID to name them after.
+const pendingWorkspacePrefix = ".pending-"
+
+// workspaceSetupTimeout bounds cloning or copying a workspace source.
+const workspaceSetupTimeout = 5 * time.Minute
+
+// Transports git may use to clone a workspace source (GIT_ALLOW_PROTOCOL).
+// ext:: runs commands and file:// reads the node's filesystem, so remote
+// clients get https and ssh only; ext is never allowed.
+const (
+	remoteGitProtocols = "https:ssh"
+	localGitProtocols  = "https:ssh:git:http:file"
+)
+
+// scpLikeURL matches git's user@host:path shorthand for ssh.
+var scpLikeURL = regexp.MustCompile(`^[A-Za-z0-9._-]+@[A-Za-z0-9.-]+:`)
+
+// checkWorkspaceSource validates where a workspace is filled from. Local
+// clients may name a git URL or a directory on the node; remote ones only
+// an https or ssh git URL.
+func checkWorkspaceSource(source string, remote bool) error {
+	switch {
+	case source == "":
+		return nil
+	case strings.HasPrefix(source, "-"):
+		return fmt.Errorf("invalid workspace source %q", source)
+	case remote:
+		if strings.HasPrefix(source, "https://") || strings.HasPrefix(source, "ssh://") || scpLikeURL.MatchString(source) {
+			return nil
+		}
+		return fmt.Errorf("workspace source %q: remote clients may only clone https:// or ssh:// git URLs", source)
+	case isGitURL(source):
+		return nil
+	}
+	info, err := os.Stat(source)
+	if err != nil || !info.IsDir() {
+		return fmt.Errorf("workspace source %q is neither a git URL nor a directory", source)
+	}
+	return nil
+}
+
+// prepareWorkspace makes a fresh workspace directory and fills it from
+// source: a git URL is cloned, a local directory is copied, and an empty
+// source leaves the directory empty. remote limits source as described at
+// checkWorkspaceSource. Filling it is abandoned at deadline, unless that is
+// zero. The directory has a pending name until placeWorkspace gives it the
+// session's.
+func (m *SessionManager) prepareWorkspace(source string, remote bool, deadline time.Time) (string, error) {
+	if err := checkWorkspaceSource(source, remote); err != nil {
+		return "", err
+	}
+	root := filepath.Join(m.dataDir, workspacesDir)
+	if err := os.MkdirAll(root, 0o755); err != nil {
+		return "", fmt.Errorf("creating workspaces dir: %w", err)
+	}
+	dir, err := os.MkdirTemp(root, pendingWorkspacePrefix)
+	if err != nil {
+		return "", fmt.Errorf("creating workspace: %w", err)
+	}
+	if err := os.Chmod(dir, 0o755); err != nil {
+		_ = os.RemoveAll(dir)
+		return "", fmt.Errorf("creating workspace: %w", err)
+	}
+
+	ctx, cancel := context.WithTimeout(context.Background(), workspaceSetupTimeout)
+	defer cancel()
+	if !deadline.IsZero() {
+		ctx, cancel = context.WithDeadline(ctx, deadline)
+		defer cancel()
+	}
+
+	var cmd *exec.Cmd
+	switch {
+	case source == "":
+		return dir, nil
+	case remote || isGitURL(source):
+		protocols := localGitProtocols
+		if remote {
+			protocols = remoteGitProtocols
+		}
+		cmd = exec.CommandContext(ctx, "git", "clone", "--quiet", "--", source, dir)
+		cmd.Env = append(os.Environ(), "GIT_ALLOW_PROTOCOL="+protocols)
+	default:
+		cmd = exec.CommandContext(ctx, "cp", "-a", "--", source+"/.", dir)
+	}
+
+	if out, err := cmd.CombinedOutput(); err != nil {
+		_ = os.RemoveAll(dir)
+		return "", fmt.Errorf("preparing workspace from %s: %v: %s", source, err, strings.TrimSpace(string(out)))
+	}
+	return dir, nil
+}
+
+// placeWorkspace renames a prepared workspace after session id.
+func (m *SessionManager) placeWorkspace(pending string, id uint32) (string, error) {
+	dir := filepath.Join(filepath.Dir(pending), strconv.FormatUint(uint64(id), 10))
+	_ = os.RemoveAll(dir) // left over from an earlier node with the same ID
+	if err := os.Rename(pending, dir); err != nil {
+		_ = os.RemoveAll(pending)
+		return "", fmt.Errorf("creating workspace: %w", err)
+	}
+	return dir, nil
+}
+
+// isGitURL reports whether source looks like something git clone should fetch
+// rather than a local template directory.
+func isGitURL(source string) bool {
+	return strings.Contains(source, "://") ||
+		strings.HasPrefix(source, "git@") ||
+		strings.HasSuffix(source, ".git")
+}
+
+// PruneWorkspaces removes workspaces of sessions that finished more than
+// retention ago. Workspaces left behind by sessions from an earlier node run,
+// and pending ones a crash interrupted, are aged by their modification time.
+// Running sessions are never touched.
+func (m *SessionManager) PruneWorkspaces(retention time.Duration) {
+	root := filepath.Join(m.dataDir, workspacesDir)
+	entries, err := os.ReadDir(root)
+	if err != nil {
+		return
+	}
+	now := time.Now()
+
+	for _, e := range entries {
+		if !e.IsDir() {
+			continue
+		}
+		var (
+			sess *Session
+			ok   bool
+		)
+		if !strings.HasPrefix(e.Name(), pendingWorkspacePrefix) {
+			id, err := strconv.ParseUint(e.Name(), 10, 32)
+			if err != nil {
+				continue
+			}
+			m.mu.RLock()
+			sess, ok = m.sessions[uint32(id)]
+			m.mu.RUnlock()
+		}
+
+		var finished time.Time
+		if ok {
+			sess.mu.Lock()
+			completedAt := sess.Meta.CompletedAt
+			sess.mu.Unlock()
+			if completedAt == nil {
+				continue // still running
+			}
+			finished = *completedAt
+		} else {
+			info, err := e.Info()
+			if err != nil {
+				continue
+			}
+			finished = info.ModTime()
+		}
+
+		if now.Sub(finished) < retention {
+			continue
+		}
+		path := filepath.Join(root, e.Name())
+		if err := os.RemoveAll(path); err != nil {
+			slog.Warn("failed to remove workspace", "path", path, "err", err)
+			continue
+		}
+		slog.Info("removed expired workspace", "path", path)
+	}
+}
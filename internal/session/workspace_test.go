@@ -0,0 +1,157 @@
+package session
+
+import (
+	"os"
+	"path/filepath"
+	"testing"
+	"time"
+)
+
+func TestCreateWorkspaceCopiesTemplate(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatal(err)
+	}
+	tmpl := t.TempDir()
+	if err := os.WriteFile(filepath.Join(tmpl, "README"), []byte("hello"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+
+	pending, err := sm.prepareWorkspace(tmpl, false, time.Time{})
+	if err != nil {
+		t.Fatalf("prepareWorkspace: %v", err)
+	}
+	dir, err := sm.placeWorkspace(pending, 7)
+	if err != nil {
+		t.Fatalf("placeWorkspace: %v", err)
+	}
+	if filepath.Base(dir) != "7" {
+		t.Errorf("workspace should be named after the session, got %s", dir)
+	}
+	data, err := os.ReadFile(filepath.Join(dir, "README"))
+	if err != nil || string(data) != "hello" {
+		t.Errorf("template not copied: %q, %v", data, err)
+	}
+
+	if _, err := sm.prepareWorkspace(filepath.Join(tmpl, "missing"), false, time.Time{}); err == nil {
+		t.Error("expected error for a missing template directory")
+	}
+	if _, err := sm.prepareWorkspace(tmpl, true, time.Time{}); err == nil {
+		t.Error("expected a remote client to be refused a local directory")
+	}
+}
+
+func TestCheckWorkspaceSource(t *testing.T) {
+	for _, tc := range []struct {
+		source string
+		remote bool
+		ok     bool
+	}{
+		{"https://github.com/codewiresh/codewire", true, true},
+		{"ssh://git@github.com/codewiresh/codewire.git", true, true},
+		{"git@github.com:codewiresh/codewire.git", true, true},
+		{"ext::sh -c touch% /tmp/pwned", true, false},
+		{"file:///etc", true, false},
+		{"/srv/templates/node-app.git", true, false},
+		{"--upload-pack=touch /tmp/pwned", true, false},
+		{"--upload-pack=touch /tmp/pwned", false, false},
+		{"https://github.com/codewiresh/codewire", false, true},
+		{"", true, true},
+	} {
+		err := checkWorkspaceSource(tc.source, tc.remote)
+		if (err == nil) != tc.ok {
+			t.Errorf("checkWorkspaceSource(%q, remote=%v) = %v, want ok %v", tc.source, tc.remote, err, tc.ok)
+		}
+	}
+}
+
+func TestFailedWorkspaceKeepsSessionID(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatal(err)
+	}
+	opts := LaunchOptions{Workspace: true, WorkspaceSource: "ext::sh -c false.git"}
+	if _, err := sm.LaunchWithOptions([]string{"true"}, "", nil, nil, "", opts); err == nil {
+		t.Fatal("expected the ext:: clone to fail")
+	}
+	id, err := sm.LaunchWithOptions([]string{"true"}, "/tmp", nil, nil, "", LaunchOptions{})
+	if err != nil {
+		t.Fatalf("Launch: %v", err)
+	}
+	if id != 1 {
+		t.Fatalf("failed workspace used up a session ID: next launch got %d", id)
+	}
+}
+
+func TestFailedLaunchRemovesWorkspace(t *testing.T) {
+	dataDir := t.TempDir()
+	sm, err := NewSessionManager(dataDir)
+	if err != nil {
+		t.Fatal(err)
+	}
+	tmpl := t.TempDir()
+	if err := os.WriteFile(filepath.Join(tmpl, "README"), []byte("hello"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	// Executable, so it is found on launch, but not a program exec can run.
+	bogus := filepath.Join(t.TempDir(), "bogus")
+	if err := os.WriteFile(bogus, []byte{0, 1, 2, 3}, 0o755); err != nil {
+		t.Fatal(err)
+	}
+
+	opts := LaunchOptions{Workspace: true, WorkspaceSource: tmpl}
+	if _, err := sm.LaunchWithOptions([]string{bogus}, "", nil, nil, "", opts); err == nil {
+		t.Fatal("expected the launch to fail")
+	}
+	entries, err := os.ReadDir(filepath.Join(dataDir, workspacesDir))
+	if err != nil && !os.IsNotExist(err) {
+		t.Fatal(err)
+	}
+	if len(entries) != 0 {
+		t.Fatalf("failed launch left its workspace behind: %v", entries)
+	}
+}
+
+func TestPruneWorkspacesRemovesExpiredOrphans(t *testing.T) {
+	sm, err := NewSessionManager(t.TempDir())
+	if err != nil {
+		t.Fatal(err)
+	}
+	old, err := sm.prepareWorkspace("", false, time.Time{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	if old, err = sm.placeWorkspace(old, 1); err != nil {
+		t.Fatal(err)
+	}
+	fresh, err := sm.prepareWorkspace("", false, time.Time{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	past := time.Now().Add(-2 * time.Hour)
+	if err := os.Chtimes(old, past, past); err != nil {
+		t.Fatal(err)
+	}
+
+	sm.PruneWorkspaces(time.Hour)
+
+	if _, err := os.Stat(old); !os.IsNotExist(err) {
+		t.Errorf("expired workspace should be removed, stat err = %v", err)
+	}
+	if _, err := os.Stat(fresh); err != nil {
+		t.Errorf("fresh workspace should be kept: %v", err)
+	}
+}
+
+func TestIsGitURL(t *testing.T) {
+	for src, want := range map[string]bool{
+		"https://github.com/codewiresh/codewire": true,
+		"git@github.com:codewiresh/codewire.git": true,
+		"../mirror/codewire.git":                 true,
+		"/srv/templates/node-app":                false,
+	} {
+		if got := isGitURL(src); got != want {
+			t.Errorf("isGitURL(%q) = %v, want %v", src, got, want)
+		}
+	}
+}
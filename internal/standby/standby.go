@@ -0,0 +1,238 @@
+// Package standby keeps a copy of another node — its primary — on a
+// standby node: the metadata of every session the primary has run and
+// their output logs, fetched over the primary's WebSocket listener every
+// few seconds. If the primary's host is lost, the standby still serves the
+// history, transcripts and last known status of its sessions (cw list,
+// status and logs with --replica). Running sessions are not moved: their
+// processes die with the primary.
+//
+// The copy lives in the standby's data directory under replica/: state.json
+// holds the sessions as last seen, sessions/<id>/output.log their logs.
+package standby
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"log/slog"
+	"os"
+	"path/filepath"
+	"sort"
+	"sync"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/client"
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+const stateFile = "state.json"
+
+// Store is a standby's copy of its primary.
+type Store struct {
+	dir     string
+	primary string
+
+	mu       sync.Mutex
+	sessions map[uint32]protocol.SessionInfo
+	syncedAt time.Time
+	lastErr  string
+}
+
+type state struct {
+	Primary  string                 `json:"primary"`
+	SyncedAt time.Time              `json:"synced_at"`
+	Sessions []protocol.SessionInfo `json:"sessions"`
+}
+
+// Open loads the copy kept in dir, creating it if needed. primary labels
+// where it comes from.
+func Open(dir, primary string) (*Store, error) {
+	if err := os.MkdirAll(dir, 0o755); err != nil {
+		return nil, fmt.Errorf("creating standby dir: %w", err)
+	}
+	s := &Store{dir: dir, primary: primary, sessions: make(map[uint32]protocol.SessionInfo)}
+	data, err := os.ReadFile(filepath.Join(dir, stateFile))
+	if errors.Is(err, os.ErrNotExist) {
+		return s, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	var st state
+	if err := json.Unmarshal(data, &st); err != nil {
+		return nil, fmt.Errorf("reading %s: %w", filepath.Join(dir, stateFile), err)
+	}
+	for _, info := range st.Sessions {
+		s.sessions[info.ID] = info
+	}
+	s.syncedAt = st.SyncedAt
+	return s, nil
+}
+
+// LogPath returns where the copy of a session's output log is kept.
+func (s *Store) LogPath(id uint32) string {
+	return filepath.Join(s.dir, "sessions", fmt.Sprintf("%d", id), "output.log")
+}
+
+// Offsets returns how much of each session's log the copy holds, for
+// protocol.Request.Offsets.
+func (s *Store) Offsets() map[uint32]uint64 {
+	s.mu.Lock()
+	ids := make([]uint32, 0, len(s.sessions))
+	for id := range s.sessions {
+		ids = append(ids, id)
+	}
+	s.mu.Unlock()
+	offsets := make(map[uint32]uint64, len(ids))
+	for _, id := range ids {
+		if fi, err := os.Stat(s.LogPath(id)); err == nil {
+			offsets[id] = uint64(fi.Size())
+		}
+	}
+	return offsets
+}
+
+// Apply records one session sent by the primary and appends its output.
+// Output that does not continue the copy is dropped, so the next sync asks
+// for it again; output from offset 0 replaces the copy, as does a session
+// whose ID the primary has reused. A log the primary no longer has is kept.
+func (s *Store) Apply(rec protocol.HistoryRecord) error {
+	info := rec.Session
+	path := s.LogPath(info.ID)
+
+	s.mu.Lock()
+	old, seen := s.sessions[info.ID]
+	s.mu.Unlock()
+	if seen && old.CreatedAt != info.CreatedAt {
+		if err := os.Remove(path); err != nil && !errors.Is(err, os.ErrNotExist) {
+			return err
+		}
+	}
+
+	if len(rec.Transcript) > 0 {
+		var size uint64
+		if fi, err := os.Stat(path); err == nil {
+			size = uint64(fi.Size())
+		}
+		switch rec.Offset {
+		case size:
+			if err := appendFile(path, rec.Transcript); err != nil {
+				return fmt.Errorf("session %d: %w", info.ID, err)
+			}
+		case 0:
+			if err := os.WriteFile(path, rec.Transcript, 0o644); err != nil {
+				return fmt.Errorf("session %d: %w", info.ID, err)
+			}
+		}
+	}
+
+	s.mu.Lock()
+	s.sessions[info.ID] = info
+	s.mu.Unlock()
+	return nil
+}
+
+func appendFile(path string, data []byte) error {
+	if err := os.MkdirAll(filepath.Dir(path), 0o755); err != nil {
+		return err
+	}
+	f, err := os.OpenFile(path, os.O_WRONLY|os.O_CREATE|os.O_APPEND, 0o644)
+	if err != nil {
+		return err
+	}
+	if _, err := f.Write(data); err != nil {
+		f.Close()
+		return err
+	}
+	return f.Close()
+}
+
+// save writes state.json, recording a complete sync at syncedAt unless
+// syncErr is set.
+func (s *Store) save(syncedAt time.Time, syncErr error) error {
+	s.mu.Lock()
+	if syncErr != nil {
+		s.lastErr = syncErr.Error()
+	} else {
+		s.lastErr = ""
+		s.syncedAt = syncedAt
+	}
+	st := state{Primary: s.primary, SyncedAt: s.syncedAt, Sessions: s.list()}
+	s.mu.Unlock()
+
+	data, err := json.MarshalIndent(st, "", "  ")
+	if err != nil {
+		return err
+	}
+	tmp := filepath.Join(s.dir, stateFile+".tmp")
+	if err := os.WriteFile(tmp, data, 0o644); err != nil {
+		return err
+	}
+	return os.Rename(tmp, filepath.Join(s.dir, stateFile))
+}
+
+// List returns the sessions as last seen on the primary, by ID.
+func (s *Store) List() []protocol.SessionInfo {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	return s.list()
+}
+
+func (s *Store) list() []protocol.SessionInfo {
+	out := make([]protocol.SessionInfo, 0, len(s.sessions))
+	for _, info := range s.sessions {
+		out = append(out, info)
+	}
+	sort.Slice(out, func(i, j int) bool { return out[i].ID < out[j].ID })
+	return out
+}
+
+// Get returns one session as last seen on the primary.
+func (s *Store) Get(id uint32) (protocol.SessionInfo, error) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	info, ok := s.sessions[id]
+	if !ok {
+		return info, fmt.Errorf("session %d not found in the replica of %s", id, s.primary)
+	}
+	return info, nil
+}
+
+// Status reports how far the copy is. It is nil for a nil Store, so nodes
+// that are not standbys report none.
+func (s *Store) Status() *protocol.StandbyStatus {
+	if s == nil {
+		return nil
+	}
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	st := &protocol.StandbyStatus{Primary: s.primary, LastError: s.lastErr, Sessions: len(s.sessions)}
+	if !s.syncedAt.IsZero() {
+		st.SyncedAt = s.syncedAt.UTC().Format(time.RFC3339)
+	}
+	return st
+}
+
+// Sync fetches what changed on the primary since the last sync.
+func Sync(primary *client.Target, s *Store) error {
+	err := client.Replicate(primary, s.Offsets(), s.Apply)
+	if saveErr := s.save(time.Now(), err); err == nil {
+		err = saveErr
+	}
+	return err
+}
+
+// Follow syncs s from primary every interval until ctx is cancelled.
+func Follow(ctx context.Context, primary *client.Target, s *Store, interval time.Duration) {
+	for {
+		if err := Sync(primary, s); err != nil {
+			slog.Warn("standby sync failed", "primary", s.primary, "err", err)
+		}
+		select {
+		case <-ctx.Done():
+			return
+		case <-time.After(interval):
+		}
+	}
+}
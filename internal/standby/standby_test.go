@@ -0,0 +1,89 @@
+package standby
+
+import (
+	"os"
+	"testing"
+	"time"
+
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+func record(id uint32, created, status string, offset uint64, out string) protocol.HistoryRecord {
+	return protocol.HistoryRecord{
+		Session:    protocol.SessionInfo{ID: id, CreatedAt: created, Status: status},
+		Transcript: []byte(out),
+		Offset:     offset,
+	}
+}
+
+func readLog(t *testing.T, s *Store, id uint32) string {
+	t.Helper()
+	data, err := os.ReadFile(s.LogPath(id))
+	if err != nil {
+		t.Fatal(err)
+	}
+	return string(data)
+}
+
+func TestApplyAppendsAndReplaces(t *testing.T) {
+	s, err := Open(t.TempDir(), "primary")
+	if err != nil {
+		t.Fatal(err)
+	}
+	const created = "2026-10-01T10:00:00Z"
+	steps := []struct {
+		rec  protocol.HistoryRecord
+		want string
+	}{
+		{record(1, created, "running", 0, "hello "), "hello "},
+		{record(1, created, "running", 6, "world\n"), "hello world\n"},
+		// Output that does not continue the copy is dropped.
+		{record(1, created, "running", 3, "stale"), "hello world\n"},
+		// The log was replaced on the primary.
+		{record(1, created, "completed (0)", 0, "new\n"), "new\n"},
+		// The log is gone from the primary (shipped and deleted).
+		{record(1, created, "completed (0)", 0, ""), "new\n"},
+		// The primary reused the ID.
+		{record(1, "2026-10-02T10:00:00Z", "running", 0, "reused\n"), "reused\n"},
+	}
+	for i, step := range steps {
+		if err := s.Apply(step.rec); err != nil {
+			t.Fatalf("step %d: %v", i, err)
+		}
+		if got := readLog(t, s, 1); got != step.want {
+			t.Fatalf("step %d: log = %q, want %q", i, got, step.want)
+		}
+	}
+	if off := s.Offsets()[1]; off != uint64(len("reused\n")) {
+		t.Fatalf("offset = %d", off)
+	}
+}
+
+func TestSaveAndReopen(t *testing.T) {
+	dir := t.TempDir()
+	s, err := Open(dir, "primary")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if err := s.Apply(record(4, "2026-10-01T10:00:00Z", "running", 0, "out\n")); err != nil {
+		t.Fatal(err)
+	}
+	if err := s.save(time.Now(), nil); err != nil {
+		t.Fatal(err)
+	}
+
+	s, err = Open(dir, "primary")
+	if err != nil {
+		t.Fatal(err)
+	}
+	info, err := s.Get(4)
+	if err != nil || info.Status != "running" {
+		t.Fatalf("reopened: %+v, %v", info, err)
+	}
+	if st := s.Status(); st.Sessions != 1 || st.SyncedAt == "" {
+		t.Fatalf("status: %+v", st)
+	}
+	if _, err := s.Get(5); err == nil {
+		t.Fatal("expected an error for an unknown session")
+	}
+}
@@ -3,6 +3,8 @@ package statusbar
 import (
 	"fmt"
 	"time"
+
+	"github.com/codewiresh/codewire/internal/textwidth"
 )
 
 type StatusBar struct {
@@ -12,6 +14,17 @@ type StatusBar struct {
 	Rows      uint16
 	Cols      uint16
 	Enabled   bool
+	Notice    string        // transient hint shown before the detach help
+	Latency   time.Duration // last measured round trip to the node; 0 if unknown
+	// Expires is when a session limit will end the session (zero if none);
+	// ExpiryLabel names it in the countdown, e.g. "ends" or "idle timeout".
+	Expires     time.Time
+	ExpiryLabel string
+	// Clients is how many clients are attached, this one included; shown
+	// when others are watching too.
+	Clients int
+	// Banner is a transient message, e.g. "2 clients attached".
+	Banner string
 }
 
 func New(sessionID uint32, cols, rows uint16) *StatusBar {
@@ -88,17 +101,27 @@ func (s *StatusBar) Draw() []byte {
 	elapsed := time.Since(s.Started)
 	age := formatDuration(uint64(elapsed.Seconds()))
 
-	content := fmt.Sprintf(" [cw] session %d | %s | %s | Ctrl+B d",
-		s.SessionID, s.Status, age)
+	content := fmt.Sprintf(" [cw] session %d | %s | %s", s.SessionID, s.Status, age)
+	if s.Latency > 0 {
+		content += fmt.Sprintf(" | %dms", s.Latency.Milliseconds())
+	}
+	if !s.Expires.IsZero() {
+		left := max(time.Until(s.Expires), 0)
+		content += fmt.Sprintf(" | %s in %s", s.ExpiryLabel, formatDuration(uint64(left.Seconds())))
+	}
+	if s.Clients > 1 {
+		content += fmt.Sprintf(" | %d clients", s.Clients)
+	}
+	if s.Banner != "" {
+		content += " | " + s.Banner
+	}
+	if s.Notice != "" {
+		content += " | " + s.Notice
+	}
+	content += " | Ctrl+B d"
 
 	// Pad or truncate to fill the row
-	cols := int(s.Cols)
-	var padded string
-	if len(content) >= cols {
-		padded = content[:cols]
-	} else {
-		padded = fmt.Sprintf("%-*s", cols, content)
-	}
+	padded := textwidth.Fit(content, int(s.Cols))
 
 	var out []byte
 	// Save cursor
@@ -3,6 +3,10 @@ package statusbar
 import (
 	"strings"
 	"testing"
+	"time"
+	"unicode/utf8"
+
+	"github.com/codewiresh/codewire/internal/textwidth"
 )
 
 func TestPtySizeReducesRows(t *testing.T) {
@@ -72,6 +76,35 @@ func TestDrawContainsSessionInfo(t *testing.T) {
 	}
 }
 
+func TestDrawShowsExpiryCountdown(t *testing.T) {
+	bar := New(1, 120, 24)
+	bar.Expires = time.Now().Add(90*time.Minute + 30*time.Second)
+	bar.ExpiryLabel = "ends"
+	if out := string(bar.Draw()); !strings.Contains(out, "ends in 1h30m") {
+		t.Fatalf("missing countdown: %q", out)
+	}
+	bar.Expires = time.Now().Add(-time.Second)
+	if out := string(bar.Draw()); !strings.Contains(out, "ends in 0s") {
+		t.Fatalf("countdown should stop at zero: %q", out)
+	}
+}
+
+func TestDrawShowsOtherClients(t *testing.T) {
+	bar := New(1, 120, 24)
+	bar.Clients = 1
+	if out := string(bar.Draw()); strings.Contains(out, "clients") {
+		t.Fatalf("a lone client should not be counted: %q", out)
+	}
+	bar.Clients = 2
+	bar.Banner = "2 clients attached"
+	out := string(bar.Draw())
+	for _, s := range []string{"| 2 clients |", "2 clients attached"} {
+		if !strings.Contains(out, s) {
+			t.Fatalf("should contain %q: %q", s, out)
+		}
+	}
+}
+
 func TestDisabledProducesEmptySetupAndDraw(t *testing.T) {
 	bar := New(1, 80, 3)
 	if len(bar.Setup()) != 0 {
@@ -117,3 +150,17 @@ func TestFormatDurationDisplay(t *testing.T) {
 		}
 	}
 }
+
+func TestDrawCutsMultibyteContentByColumns(t *testing.T) {
+	bar := New(1, 40, 24)
+	bar.Banner = "デプロイ完了 — 2 clients attached"
+	out := string(bar.Draw())
+	if !utf8.ValidString(out) {
+		t.Fatalf("status bar split a character: %q", out)
+	}
+	start := strings.Index(out, "\x1b[7m") + len("\x1b[7m")
+	end := strings.Index(out, "\x1b[0m")
+	if w := textwidth.Width(out[start:end]); w != 40 {
+		t.Errorf("status bar is %d columns wide, want 40", w)
+	}
+}
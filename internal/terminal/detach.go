@@ -31,16 +31,34 @@ const (
 // While waiting for 'd' after the prefix, terminal-injected escape sequences
 // (focus events, cursor position reports, mouse reports, etc.) are buffered
 // and forwarded without cancelling the pending detach.
+//
+// Other keys registered with Bind are recognised after the prefix too; they
+// are collected for TakePressed instead of being forwarded.
 type DetachDetector struct {
-	state  detectState
-	buf    []byte // buffered bytes during escape parsing
-	params []byte // CSI parameter bytes for Kitty detection
+	state   detectState
+	buf     []byte // buffered bytes during escape parsing
+	params  []byte // CSI parameter bytes for Kitty detection
+	bound   string // prefix keys besides 'd'
+	pressed []byte // bound keys seen since the last TakePressed
 }
 
 func NewDetachDetector() *DetachDetector {
 	return &DetachDetector{state: stateNormal}
 }
 
+// Bind registers keys (printable ASCII other than 'd') that act as prefix
+// commands, e.g. "i" for Ctrl+B i.
+func (d *DetachDetector) Bind(keys string) {
+	d.bound = keys
+}
+
+// TakePressed returns the bound prefix keys pressed since the last call.
+func (d *DetachDetector) TakePressed() []byte {
+	p := d.pressed
+	d.pressed = nil
+	return p
+}
+
 // Feed processes a single byte. Returns (detachDetected, bytesToForward).
 func (d *DetachDetector) Feed(b byte) (bool, []byte) {
 	switch d.state {
@@ -68,6 +86,11 @@ func (d *DetachDetector) Feed(b byte) (bool, []byte) {
 			d.state = stateNormal
 			return true, nil
 		}
+		if strings.IndexByte(d.bound, b) >= 0 {
+			d.state = stateNormal
+			d.pressed = append(d.pressed, b)
+			return false, nil
+		}
 		if b == 0x1b {
 			// Start of an escape sequence -- buffer and skip it.
 			d.state = stateSawPrefixEsc
@@ -114,6 +137,12 @@ func (d *DetachDetector) Feed(b byte) (bool, []byte) {
 					d.buf = d.buf[:0]
 					return true, nil
 				}
+				if key, ok := d.kittyBoundKey(); ok {
+					d.state = stateNormal
+					d.buf = d.buf[:0]
+					d.pressed = append(d.pressed, key)
+					return false, nil
+				}
 				// Some other Kitty key -- cancel SawPrefix.
 				d.state = stateNormal
 				fwd := make([]byte, 1+len(d.buf))
@@ -254,3 +283,16 @@ func (d *DetachDetector) isKittyD() bool {
 	cp, mod, ok := parseKitty(params)
 	return ok && cp == 100 && mod == 1
 }
+
+// kittyBoundKey checks if buf contains an unmodified Kitty key event for a
+// bound key, and returns the key.
+func (d *DetachDetector) kittyBoundKey() (byte, bool) {
+	if len(d.buf) < 4 {
+		return 0, false
+	}
+	cp, mod, ok := parseKitty(d.buf[2 : len(d.buf)-1])
+	if !ok || mod != 1 || cp >= 0x80 || strings.IndexByte(d.bound, byte(cp)) < 0 {
+		return 0, false
+	}
+	return byte(cp), true
+}
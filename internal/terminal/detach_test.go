@@ -215,3 +215,23 @@ func TestKittyCtrlBCancelRaw(t *testing.T) {
 	}
 	assertBytes(t, fwd, []byte{0x02, 'x'})
 }
+
+func TestBoundPrefixKey(t *testing.T) {
+	d := NewDetachDetector()
+	d.Bind("i")
+	detach, fwd := d.FeedBuf([]byte("ab\x02ic"))
+	assertFeed(t, detach, fwd, false, []byte("abc"))
+	assertBytes(t, d.TakePressed(), []byte("i"))
+	if p := d.TakePressed(); p != nil {
+		t.Errorf("expected pressed keys to be cleared, got %v", p)
+	}
+
+	// Kitty Ctrl+B then Kitty 'i' (codepoint 105).
+	detach, fwd = d.FeedBuf([]byte("\x1b[98;5u\x1b[105u"))
+	assertFeed(t, detach, fwd, false, []byte{})
+	assertBytes(t, d.TakePressed(), []byte("i"))
+
+	// Unbound keys still forward the prefix.
+	detach, fwd = d.FeedBuf([]byte("\x02x"))
+	assertFeed(t, detach, fwd, false, []byte("\x02x"))
+}
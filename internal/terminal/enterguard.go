@@ -0,0 +1,94 @@
+package terminal
+
+import "bytes"
+
+var (
+	pasteStart = []byte("\x1b[200~")
+	pasteEnd   = []byte("\x1b[201~")
+)
+
+// EnterGuard holds back Enter (CR) until it is confirmed, so a half-typed
+// prompt is not submitted by accident. It filters the bytes forwarded by
+// DetachDetector:
+//
+//   - The first Enter is held. A second Enter sends a single CR.
+//   - Ctrl+B followed by Enter sends a CR immediately.
+//   - Any other input while an Enter is held drops the held Enter and is
+//     forwarded as usual, so the user can keep typing.
+//   - Bracketed pastes (\x1b[200~ ... \x1b[201~) pass through unguarded.
+type EnterGuard struct {
+	pending   bool // an Enter is held awaiting confirmation
+	sawPrefix bool // previous byte was a forwarded Ctrl+B
+	inPaste   bool
+}
+
+func NewEnterGuard() *EnterGuard {
+	return &EnterGuard{}
+}
+
+// Pending reports whether an Enter is held awaiting confirmation.
+func (g *EnterGuard) Pending() bool {
+	return g.pending
+}
+
+// Filter processes forwarded input. Returns the bytes to send to the PTY.
+func (g *EnterGuard) Filter(buf []byte) []byte {
+	out := make([]byte, 0, len(buf))
+	for i := 0; i < len(buf); i++ {
+		rest := buf[i:]
+		if !g.inPaste && bytes.HasPrefix(rest, pasteStart) {
+			g.inPaste = true
+			g.pending = false
+			g.sawPrefix = false
+			out = append(out, pasteStart...)
+			i += len(pasteStart) - 1
+			continue
+		}
+		if g.inPaste {
+			if bytes.HasPrefix(rest, pasteEnd) {
+				g.inPaste = false
+				out = append(out, pasteEnd...)
+				i += len(pasteEnd) - 1
+				continue
+			}
+			out = append(out, buf[i])
+			continue
+		}
+
+		b := buf[i]
+		switch {
+		case g.sawPrefix && b == '\r':
+			// Ctrl+B Enter: send immediately without the prefix byte.
+			g.sawPrefix = false
+			g.pending = false
+			out = append(out, '\r')
+		case b == '\r':
+			if g.pending {
+				g.pending = false
+				out = append(out, '\r')
+			} else {
+				g.pending = true
+			}
+		case b == 0x02:
+			// DetachDetector forwards Ctrl+B together with the key that
+			// cancelled the detach; hold it to see if that key is Enter.
+			if g.sawPrefix {
+				out = append(out, 0x02)
+			}
+			g.sawPrefix = true
+		default:
+			if g.sawPrefix {
+				out = append(out, 0x02)
+				g.sawPrefix = false
+			}
+			g.pending = false
+			out = append(out, b)
+		}
+	}
+	if g.sawPrefix && len(buf) > 0 && buf[len(buf)-1] == 0x02 {
+		// A Ctrl+B at the end of a read was not part of a prefix pair.
+		out = append(out, 0x02)
+		g.sawPrefix = false
+	}
+	return out
+}
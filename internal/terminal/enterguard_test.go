@@ -0,0 +1,61 @@
+package terminal
+
+import (
+	"bytes"
+	"testing"
+)
+
+func TestEnterGuardHoldsFirstEnter(t *testing.T) {
+	g := NewEnterGuard()
+	if out := g.Filter([]byte("fix it\r")); !bytes.Equal(out, []byte("fix it")) {
+		t.Fatalf("got %q, want %q", out, "fix it")
+	}
+	if !g.Pending() {
+		t.Fatal("expected Enter to be held")
+	}
+	if out := g.Filter([]byte("\r")); !bytes.Equal(out, []byte("\r")) {
+		t.Fatalf("got %q, want CR", out)
+	}
+	if g.Pending() {
+		t.Fatal("expected no pending Enter after confirmation")
+	}
+}
+
+func TestEnterGuardOtherKeyDropsHeldEnter(t *testing.T) {
+	g := NewEnterGuard()
+	g.Filter([]byte("\r"))
+	if out := g.Filter([]byte("x")); !bytes.Equal(out, []byte("x")) {
+		t.Fatalf("got %q, want %q", out, "x")
+	}
+	if g.Pending() {
+		t.Fatal("held Enter should be dropped")
+	}
+}
+
+func TestEnterGuardPrefixEnterSendsImmediately(t *testing.T) {
+	g := NewEnterGuard()
+	if out := g.Filter([]byte{0x02, '\r'}); !bytes.Equal(out, []byte("\r")) {
+		t.Fatalf("got %q, want CR", out)
+	}
+}
+
+func TestEnterGuardForwardsLonePrefix(t *testing.T) {
+	g := NewEnterGuard()
+	if out := g.Filter([]byte{0x02, 'x'}); !bytes.Equal(out, []byte{0x02, 'x'}) {
+		t.Fatalf("got %q", out)
+	}
+	if out := g.Filter([]byte{0x02}); !bytes.Equal(out, []byte{0x02}) {
+		t.Fatalf("got %q", out)
+	}
+}
+
+func TestEnterGuardPassesBracketedPaste(t *testing.T) {
+	g := NewEnterGuard()
+	paste := []byte("\x1b[200~line one\rline two\r\x1b[201~")
+	if out := g.Filter(paste); !bytes.Equal(out, paste) {
+		t.Fatalf("got %q, want %q", out, paste)
+	}
+	if g.Pending() {
+		t.Fatal("paste should not leave a pending Enter")
+	}
+}
@@ -0,0 +1,96 @@
+package terminal
+
+import (
+	"fmt"
+	"sort"
+	"strings"
+)
+
+// namedKeys maps key names to the bytes a terminal sends for them (xterm
+// encodings, normal cursor mode).
+var namedKeys = map[string]string{
+	"enter":     "\r",
+	"return":    "\r",
+	"tab":       "\t",
+	"esc":       "\x1b",
+	"escape":    "\x1b",
+	"space":     " ",
+	"backspace": "\x7f",
+	"delete":    "\x1b[3~",
+	"up":        "\x1b[A",
+	"down":      "\x1b[B",
+	"right":     "\x1b[C",
+	"left":      "\x1b[D",
+	"home":      "\x1b[H",
+	"end":       "\x1b[F",
+	"pgup":      "\x1b[5~",
+	"pgdn":      "\x1b[6~",
+	"insert":    "\x1b[2~",
+	"shift-tab": "\x1b[Z",
+	"f1":        "\x1bOP",
+	"f2":        "\x1bOQ",
+	"f3":        "\x1bOR",
+	"f4":        "\x1bOS",
+	"f5":        "\x1b[15~",
+	"f6":        "\x1b[17~",
+	"f7":        "\x1b[18~",
+	"f8":        "\x1b[19~",
+	"f9":        "\x1b[20~",
+	"f10":       "\x1b[21~",
+	"f11":       "\x1b[23~",
+	"f12":       "\x1b[24~",
+}
+
+// keyAliases maps alternative spellings to namedKeys entries.
+var keyAliases = map[string]string{
+	"cr":       "enter",
+	"bs":       "backspace",
+	"del":      "delete",
+	"pageup":   "pgup",
+	"pagedown": "pgdn",
+	"backtab":  "shift-tab",
+}
+
+// KeyBytes translates a key name such as "ctrl-c", "esc", "tab" or "up" into
+// the bytes a terminal would send for it. Names are case-insensitive; Ctrl
+// combinations may be written ctrl-x, ctrl+x or c-x.
+func KeyBytes(name string) ([]byte, error) {
+	key := strings.ToLower(strings.TrimSpace(name))
+	if alias, ok := keyAliases[key]; ok {
+		key = alias
+	}
+	if seq, ok := namedKeys[key]; ok {
+		return []byte(seq), nil
+	}
+	for _, prefix := range []string{"ctrl-", "ctrl+", "c-"} {
+		if rest, ok := strings.CutPrefix(key, prefix); ok && len(rest) == 1 {
+			if b, ok := ctrlByte(rest[0]); ok {
+				return []byte{b}, nil
+			}
+		}
+	}
+	return nil, fmt.Errorf("unknown key %q (use ctrl-<letter> or one of: %s)", name, strings.Join(KeyNames(), ", "))
+}
+
+// ctrlByte returns the control code for Ctrl plus c: letters and @ [ \ ] ^ _.
+func ctrlByte(c byte) (byte, bool) {
+	switch {
+	case c >= 'a' && c <= 'z':
+		return c - 'a' + 1, true
+	case c >= '@' && c <= '_':
+		return c - '@', true
+	case c == '?':
+		return 0x7f, true
+	}
+	return 0, false
+}
+
+// KeyNames returns the named (non-Ctrl) keys KeyBytes accepts, sorted.
+func KeyNames() []string {
+	names := make([]string, 0, len(namedKeys))
+	for name := range namedKeys {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	return names
+}
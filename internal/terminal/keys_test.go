@@ -0,0 +1,33 @@
+package terminal
+
+import (
+	"bytes"
+	"testing"
+)
+
+func TestKeyBytes(t *testing.T) {
+	tests := []struct {
+		name string
+		want []byte
+	}{
+		{"ctrl-c", []byte{0x03}},
+		{"CTRL+D", []byte{0x04}},
+		{"c-[", []byte{0x1b}},
+		{"esc", []byte{0x1b}},
+		{"tab", []byte{'\t'}},
+		{"enter", []byte{'\r'}},
+		{"up", []byte("\x1b[A")},
+		{"PageDown", []byte("\x1b[6~")},
+	}
+	for _, tt := range tests {
+		got, err := KeyBytes(tt.name)
+		if err != nil || !bytes.Equal(got, tt.want) {
+			t.Errorf("KeyBytes(%q) = %q, %v; want %q", tt.name, got, err, tt.want)
+		}
+	}
+	for _, bad := range []string{"", "ctrl-", "ctrl-ab", "hyper"} {
+		if _, err := KeyBytes(bad); err == nil {
+			t.Errorf("KeyBytes(%q) should fail", bad)
+		}
+	}
+}
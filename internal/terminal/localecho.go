@@ -0,0 +1,81 @@
+package terminal
+
+import (
+	"fmt"
+	"time"
+)
+
+// maxPredicted caps how many unconfirmed characters are shown locally.
+const maxPredicted = 256
+
+// LocalEcho predicts the PTY's echo of typed characters so that remote
+// attaches over slow links feel responsive. Printable ASCII is written to the
+// local terminal as soon as it is typed; when the session's output arrives,
+// the echo that matches the prediction is dropped (it is already on screen).
+// If the output diverges — the program does not echo, redraws the line, or
+// the prediction is older than the expiry — the predicted characters are
+// erased and the real output is written instead.
+//
+// Only plain typing is predicted. Input containing control bytes or escape
+// sequences (Enter, arrows, Ctrl keys) is sent without prediction.
+type LocalEcho struct {
+	pending []byte
+	since   time.Time // when the oldest pending character was predicted
+}
+
+func NewLocalEcho() *LocalEcho {
+	return &LocalEcho{}
+}
+
+// Predict returns the bytes to write to the local terminal for typed input.
+func (e *LocalEcho) Predict(input []byte) []byte {
+	if len(input) == 0 || len(e.pending)+len(input) > maxPredicted {
+		return nil
+	}
+	for _, b := range input {
+		if b < 0x20 || b > 0x7e {
+			return nil
+		}
+	}
+	if len(e.pending) == 0 {
+		e.since = time.Now()
+	}
+	e.pending = append(e.pending, input...)
+	out := make([]byte, len(input))
+	copy(out, input)
+	return out
+}
+
+// Reconcile filters session output against pending predictions. Returns the
+// bytes to write to the local terminal.
+func (e *LocalEcho) Reconcile(output []byte) []byte {
+	i := 0
+	for i < len(output) && len(e.pending) > 0 && output[i] == e.pending[0] {
+		e.pending = e.pending[1:]
+		i++
+	}
+	if i == len(output) {
+		return nil
+	}
+	if len(e.pending) == 0 {
+		return output[i:]
+	}
+	return append(e.erase(), output[i:]...)
+}
+
+// Expire erases predictions older than maxAge that the session never echoed
+// (e.g. at a password prompt). Returns the bytes to write, or nil.
+func (e *LocalEcho) Expire(maxAge time.Duration) []byte {
+	if len(e.pending) == 0 || time.Since(e.since) < maxAge {
+		return nil
+	}
+	return e.erase()
+}
+
+// erase moves the cursor back over the pending predictions, clears to the
+// end of the line and forgets them.
+func (e *LocalEcho) erase() []byte {
+	n := len(e.pending)
+	e.pending = e.pending[:0]
+	return []byte(fmt.Sprintf("\x1b[%dD\x1b[K", n))
+}
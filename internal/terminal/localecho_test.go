@@ -0,0 +1,55 @@
+package terminal
+
+import (
+	"bytes"
+	"testing"
+	"time"
+)
+
+func TestLocalEchoConfirmedEchoIsDropped(t *testing.T) {
+	e := NewLocalEcho()
+	if out := e.Predict([]byte("ls")); !bytes.Equal(out, []byte("ls")) {
+		t.Fatalf("Predict = %q, want %q", out, "ls")
+	}
+	if out := e.Reconcile([]byte("l")); out != nil {
+		t.Fatalf("Reconcile = %q, want nothing", out)
+	}
+	if out := e.Reconcile([]byte("s\r\nfile\r\n")); !bytes.Equal(out, []byte("\r\nfile\r\n")) {
+		t.Fatalf("Reconcile = %q", out)
+	}
+}
+
+func TestLocalEchoMismatchErasesPrediction(t *testing.T) {
+	e := NewLocalEcho()
+	e.Predict([]byte("abc"))
+	out := e.Reconcile([]byte("\x1b[2Kprompt> abc"))
+	want := "\x1b[3D\x1b[K\x1b[2Kprompt> abc"
+	if string(out) != want {
+		t.Fatalf("Reconcile = %q, want %q", out, want)
+	}
+}
+
+func TestLocalEchoSkipsControlInput(t *testing.T) {
+	e := NewLocalEcho()
+	if out := e.Predict([]byte("\r")); out != nil {
+		t.Fatalf("Predict(CR) = %q, want nil", out)
+	}
+	if out := e.Predict([]byte("\x1b[A")); out != nil {
+		t.Fatalf("Predict(arrow) = %q, want nil", out)
+	}
+}
+
+func TestLocalEchoExpire(t *testing.T) {
+	e := NewLocalEcho()
+	e.Predict([]byte("secret"))
+	if out := e.Expire(time.Hour); out != nil {
+		t.Fatalf("Expire before deadline = %q", out)
+	}
+	e.since = time.Now().Add(-time.Minute)
+	if out := e.Expire(time.Second); string(out) != "\x1b[6D\x1b[K" {
+		t.Fatalf("Expire = %q", out)
+	}
+	if out := e.Reconcile([]byte("Password: ")); string(out) != "Password: " {
+		t.Fatalf("Reconcile after expiry = %q", out)
+	}
+}
@@ -0,0 +1,155 @@
+// Package textwidth measures, truncates and pads text by the columns it
+// takes in a terminal rather than by bytes or runes. It steps over grapheme
+// clusters — a character with its combining marks, or an emoji sequence —
+// so a cut never splits a character or leaves an accent without its letter.
+//
+// The clustering covers what session commands and names contain in
+// practice (combining marks, variation selectors, ZWJ emoji sequences,
+// skin tones and flags); it is not a full UAX #29 implementation.
+package textwidth
+
+import (
+	"strings"
+	"unicode"
+	"unicode/utf8"
+
+	"golang.org/x/text/width"
+)
+
+const ellipsis = "..."
+
+const (
+	zwj            = 0x200D
+	emojiVariation = 0xFE0F
+)
+
+// Width returns the number of columns s takes in a terminal.
+func Width(s string) int {
+	n := 0
+	for s != "" {
+		c, w := nextCluster(s)
+		n += w
+		s = s[len(c):]
+	}
+	return n
+}
+
+// Truncate shortens s to at most n columns, ending in "..." when it is cut
+// and n leaves room for it.
+func Truncate(s string, n int) string {
+	if Width(s) <= n {
+		return s
+	}
+	if n <= len(ellipsis) {
+		return head(s, n)
+	}
+	return head(s, n-len(ellipsis)) + ellipsis
+}
+
+// Tail returns the longest suffix of s that fits in n columns, for keeping
+// the end of a line without cutting a character.
+func Tail(s string, n int) string {
+	w := Width(s)
+	for s != "" && w > n {
+		c, cw := nextCluster(s)
+		w -= cw
+		s = s[len(c):]
+	}
+	return s
+}
+
+// Pad appends spaces to s until it takes n columns.
+func Pad(s string, n int) string {
+	if w := Width(s); w < n {
+		return s + strings.Repeat(" ", n-w)
+	}
+	return s
+}
+
+// Fit cuts or pads s to exactly n columns, without an ellipsis.
+func Fit(s string, n int) string {
+	return Pad(head(s, n), n)
+}
+
+// head returns the longest prefix of s that fits in n columns.
+func head(s string, n int) string {
+	end := 0
+	for rest := s; rest != ""; {
+		c, w := nextCluster(rest)
+		if w > n {
+			break
+		}
+		n -= w
+		end += len(c)
+		rest = rest[len(c):]
+	}
+	return s[:end]
+}
+
+// nextCluster returns the grapheme cluster at the start of s and its width.
+func nextCluster(s string) (string, int) {
+	base, size := utf8.DecodeRuneInString(s)
+	w := runeWidth(base)
+	end := size
+	if isRegionalIndicator(base) {
+		// A flag is a pair of regional indicators.
+		if r, n := utf8.DecodeRuneInString(s[end:]); isRegionalIndicator(r) {
+			return s[:end+n], 2
+		}
+		return s[:end], w
+	}
+	for end < len(s) {
+		r, n := utf8.DecodeRuneInString(s[end:])
+		switch {
+		case r == zwj:
+			end += n
+			// The joined character is drawn as part of this one.
+			if end < len(s) {
+				_, n = utf8.DecodeRuneInString(s[end:])
+				end += n
+			}
+		case r == emojiVariation:
+			end += n
+			w = 2
+		case extends(r):
+			end += n
+		default:
+			return s[:end], w
+		}
+	}
+	return s[:end], w
+}
+
+// runeWidth returns the columns a single character takes on its own.
+func runeWidth(r rune) int {
+	switch {
+	case r < 0x20 || (r >= 0x7F && r < 0xA0):
+		return 0
+	case unicode.In(r, unicode.Mn, unicode.Me, unicode.Cf):
+		return 0
+	}
+	switch width.LookupRune(r).Kind() {
+	case width.EastAsianWide, width.EastAsianFullwidth:
+		return 2
+	}
+	return 1
+}
+
+// extends reports whether r attaches to the character before it.
+func extends(r rune) bool {
+	switch {
+	case unicode.In(r, unicode.Mn, unicode.Me, unicode.Mc):
+		return true
+	case r >= 0xFE00 && r <= 0xFE0F, r >= 0xE0100 && r <= 0xE01EF: // variation selectors
+		return true
+	case r >= 0x1F3FB && r <= 0x1F3FF: // skin tone modifiers
+		return true
+	case r >= 0xE0020 && r <= 0xE007F: // emoji tag sequences
+		return true
+	}
+	return false
+}
+
+func isRegionalIndicator(r rune) bool {
+	return r >= 0x1F1E6 && r <= 0x1F1FF
+}
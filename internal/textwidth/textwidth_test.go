@@ -0,0 +1,82 @@
+package textwidth
+
+import "testing"
+
+func TestWidth(t *testing.T) {
+	for _, tc := range []struct {
+		s    string
+		want int
+	}{
+		{"claude -p fix", 13},
+		{"héllo", 5},
+		{"he\u0301llo", 5}, // e + combining acute
+		{"日本語", 6},
+		{"ｆｕｌｌ", 8},
+		{"👍", 2},
+		{"👍🏽", 2},
+		{"\U0001F469\u200D\U0001F4BB ok", 5}, // woman + ZWJ + laptop
+		{"🇯🇵", 2},
+		{"\u2764\uFE0F", 2}, // heart + emoji presentation
+	} {
+		if got := Width(tc.s); got != tc.want {
+			t.Errorf("Width(%q) = %d, want %d", tc.s, got, tc.want)
+		}
+	}
+}
+
+func TestTruncate(t *testing.T) {
+	for _, tc := range []struct {
+		s    string
+		n    int
+		want string
+	}{
+		{"short", 10, "short"},
+		{"claude -p 'fix the build'", 12, "claude -p..."},
+		{"日本語のプロンプト", 9, "日本語..."},
+		{"日本語のプロンプト", 10, "日本語..."}, // a wide char never straddles the cut
+		{"héhéhé", 5, "hé..."},
+		{"\U0001F469\u200D\U0001F4BB\U0001F469\u200D\U0001F4BB\U0001F469\u200D\U0001F4BB", 5, "\U0001F469\u200D\U0001F4BB..."},
+		{"abcdef", 2, "ab"},
+	} {
+		got := Truncate(tc.s, tc.n)
+		if got != tc.want {
+			t.Errorf("Truncate(%q, %d) = %q, want %q", tc.s, tc.n, got, tc.want)
+		}
+		if Width(got) > tc.n {
+			t.Errorf("Truncate(%q, %d) is %d columns wide", tc.s, tc.n, Width(got))
+		}
+	}
+}
+
+func TestTail(t *testing.T) {
+	for _, tc := range []struct {
+		s    string
+		n    int
+		want string
+	}{
+		{"short", 10, "short"},
+		{"progress 42%", 3, "42%"},
+		{"日本語のプロンプト", 5, "プト"}, // a wide char never straddles the cut
+		{"abhéhé", 3, "éhé"},
+	} {
+		if got := Tail(tc.s, tc.n); got != tc.want {
+			t.Errorf("Tail(%q, %d) = %q, want %q", tc.s, tc.n, got, tc.want)
+		}
+	}
+}
+
+func TestFit(t *testing.T) {
+	for _, tc := range []struct {
+		s    string
+		n    int
+		want string
+	}{
+		{"abc", 5, "abc  "},
+		{"abcdef", 4, "abcd"},
+		{"ab日本", 5, "ab日 "},
+	} {
+		if got := Fit(tc.s, tc.n); got != tc.want {
+			t.Errorf("Fit(%q, %d) = %q, want %q", tc.s, tc.n, got, tc.want)
+		}
+	}
+}
@@ -0,0 +1,65 @@
+// Package tmpl renders the small handlebars-style templates used for
+// recurring instructions sent to sessions: {{name}} is replaced by the value
+// of variable name. There is no logic — a template is text with holes.
+package tmpl
+
+import (
+	"fmt"
+	"os"
+	"regexp"
+	"sort"
+	"strings"
+)
+
+// placeholder matches {{name}} with optional spaces inside the braces.
+var placeholder = regexp.MustCompile(`\{\{\s*([A-Za-z_][A-Za-z0-9_.-]*)\s*\}\}`)
+
+// Render substitutes vars into text. Every placeholder must have a value;
+// the error lists all missing names at once.
+func Render(text string, vars map[string]string) (string, error) {
+	missing := map[string]bool{}
+	out := placeholder.ReplaceAllStringFunc(text, func(m string) string {
+		name := placeholder.FindStringSubmatch(m)[1]
+		v, ok := vars[name]
+		if !ok {
+			missing[name] = true
+			return m
+		}
+		return v
+	})
+	if len(missing) > 0 {
+		names := make([]string, 0, len(missing))
+		for name := range missing {
+			names = append(names, name)
+		}
+		sort.Strings(names)
+		return "", fmt.Errorf("template variables not set: %s", strings.Join(names, ", "))
+	}
+	return out, nil
+}
+
+// RenderFile reads a template file and renders it.
+func RenderFile(path string, vars map[string]string) (string, error) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return "", fmt.Errorf("reading template: %w", err)
+	}
+	out, err := Render(string(data), vars)
+	if err != nil {
+		return "", fmt.Errorf("%s: %w", path, err)
+	}
+	return out, nil
+}
+
+// ParseVars parses key=value pairs as given to --var.
+func ParseVars(pairs []string) (map[string]string, error) {
+	vars := make(map[string]string, len(pairs))
+	for _, pair := range pairs {
+		k, v, ok := strings.Cut(pair, "=")
+		if !ok || k == "" {
+			return nil, fmt.Errorf("invalid --var %q (expected name=value)", pair)
+		}
+		vars[k] = v
+	}
+	return vars, nil
+}
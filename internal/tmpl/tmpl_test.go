@@ -0,0 +1,36 @@
+package tmpl
+
+import (
+	"strings"
+	"testing"
+)
+
+func TestRender(t *testing.T) {
+	got, err := Render("Review PR #{{pr}} on {{ repo }}. {{pr}} again.", map[string]string{"pr": "123", "repo": "api"})
+	if err != nil {
+		t.Fatal(err)
+	}
+	if want := "Review PR #123 on api. 123 again."; got != want {
+		t.Errorf("got %q, want %q", got, want)
+	}
+}
+
+func TestRenderReportsAllMissing(t *testing.T) {
+	_, err := Render("{{b}} {{a}} {{b}}", nil)
+	if err == nil || !strings.Contains(err.Error(), "a, b") {
+		t.Fatalf("expected both missing names, got %v", err)
+	}
+}
+
+func TestParseVars(t *testing.T) {
+	vars, err := ParseVars([]string{"pr=123", "note=a=b", "empty="})
+	if err != nil {
+		t.Fatal(err)
+	}
+	if vars["pr"] != "123" || vars["note"] != "a=b" || vars["empty"] != "" {
+		t.Errorf("unexpected vars: %v", vars)
+	}
+	if _, err := ParseVars([]string{"novalue"}); err == nil {
+		t.Error("expected error for missing '='")
+	}
+}
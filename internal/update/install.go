@@ -6,7 +6,9 @@ import (
 	"fmt"
 	"io"
 	"net/http"
+	"net/url"
 	"os"
+	"path"
 	"path/filepath"
 	"runtime"
 	"strings"
@@ -104,26 +106,84 @@ func releaseURL(version, filename string) string {
 
 // SelfUpdate downloads the latest binary and replaces the running executable.
 func SelfUpdate(currentVersion, latestVersion string) error {
-	asset := AssetName(latestVersion)
-	if asset == "" {
-		return fmt.Errorf("unsupported platform: %s/%s", runtime.GOOS, runtime.GOARCH)
+	staged, err := Stage(latestVersion, currentVersion, false)
+	if err != nil {
+		return err
 	}
+	defer staged.Discard()
+	return staged.Install()
+}
 
-	// Fetch checksums
-	sumsURL := releaseURL(latestVersion, "SHA256SUMS")
-	expectedHash, err := fetchChecksum(sumsURL, asset)
-	if err != nil {
-		return fmt.Errorf("fetching checksums: %w", err)
+// Staged is a downloaded, signature- and checksum-verified cw binary waiting
+// to replace the running executable.
+type Staged struct {
+	Version string // release tag, also for a URL (from its asset name)
+	path    string
+	exe     string
+}
+
+// Stage downloads the binary for source and verifies its SHA-256 checksum
+// against a checksum file signed with the release key. source is either a
+// release tag such as "v0.3.1", checked against the release's SHA256SUMS
+// and SHA256SUMS.asc, or an https URL of a release asset named like
+// AssetName, checked against "<url>.sha256" and its signature
+// "<url>.sha256.asc". Either way the signed line must name the asset, so
+// a signed checksum cannot be reused for another version. Versions older
+// than current are refused unless allowDowngrade is set, so an old signed
+// release cannot be served to roll a node back. The binary is written next
+// to the running executable so Install can rename it into place atomically.
+func Stage(source, current string, allowDowngrade bool) (*Staged, error) {
+	var assetURL, expectedHash, version string
+	switch {
+	case strings.HasPrefix(source, "http://"):
+		return nil, fmt.Errorf("refusing to download %s over plain http; use an https URL", source)
+	case strings.HasPrefix(source, "https://"):
+		u, err := url.Parse(source)
+		if err != nil {
+			return nil, fmt.Errorf("invalid URL %s: %w", source, err)
+		}
+		asset := path.Base(u.Path)
+		if version = assetVersion(asset); version == "" {
+			return nil, fmt.Errorf("%s does not name a release asset for this platform (expected %s)", source, AssetName("<version>"))
+		}
+		if err := checkDowngrade(current, version, allowDowngrade); err != nil {
+			return nil, err
+		}
+		sums, err := fetchSigned(source + ".sha256")
+		if err != nil {
+			return nil, fmt.Errorf("fetching checksum: %w", err)
+		}
+		if expectedHash, err = findChecksum(sums, asset); err != nil {
+			return nil, err
+		}
+		assetURL = source
+	default:
+		if err := checkDowngrade(current, source, allowDowngrade); err != nil {
+			return nil, err
+		}
+		asset := AssetName(source)
+		if asset == "" {
+			return nil, fmt.Errorf("unsupported platform: %s/%s", runtime.GOOS, runtime.GOARCH)
+		}
+		sums, err := fetchSigned(releaseURL(source, "SHA256SUMS"))
+		if err != nil {
+			return nil, fmt.Errorf("fetching checksums: %w", err)
+		}
+		if expectedHash, err = findChecksum(sums, asset); err != nil {
+			return nil, err
+		}
+		assetURL = releaseURL(source, asset)
+		version = source
 	}
 
 	// Resolve current executable path
 	exe, err := os.Executable()
 	if err != nil {
-		return fmt.Errorf("resolving executable: %w", err)
+		return nil, fmt.Errorf("resolving executable: %w", err)
 	}
 	exe, err = filepath.EvalSymlinks(exe)
 	if err != nil {
-		return fmt.Errorf("resolving symlinks: %w", err)
+		return nil, fmt.Errorf("resolving symlinks: %w", err)
 	}
 
 	// Download binary to temp file in same directory (ensures same filesystem for rename)
@@ -131,75 +191,139 @@ func SelfUpdate(currentVersion, latestVersion string) error {
 	tmp, err := os.CreateTemp(dir, ".cw-update-*")
 	if err != nil {
 		if os.IsPermission(err) {
-			return fmt.Errorf("permission denied writing to %s — try: sudo cw update", dir)
+			return nil, fmt.Errorf("permission denied writing to %s — try: sudo cw update", dir)
 		}
-		return fmt.Errorf("creating temp file: %w", err)
+		return nil, fmt.Errorf("creating temp file: %w", err)
 	}
-	tmpPath := tmp.Name()
+	staged := &Staged{Version: version, path: tmp.Name(), exe: exe}
+	ok := false
 	defer func() {
 		tmp.Close()
-		os.Remove(tmpPath)
+		if !ok {
+			staged.Discard()
+		}
 	}()
 
-	assetURL := releaseURL(latestVersion, asset)
 	resp, err := http.Get(assetURL)
 	if err != nil {
-		return fmt.Errorf("downloading binary: %w", err)
+		return nil, fmt.Errorf("downloading binary: %w", err)
 	}
 	defer resp.Body.Close()
 
 	if resp.StatusCode != http.StatusOK {
-		return fmt.Errorf("download returned HTTP %d", resp.StatusCode)
+		return nil, fmt.Errorf("download returned HTTP %d", resp.StatusCode)
 	}
 
 	hasher := sha256.New()
 	if _, err := io.Copy(io.MultiWriter(tmp, hasher), resp.Body); err != nil {
-		return fmt.Errorf("writing binary: %w", err)
+		return nil, fmt.Errorf("writing binary: %w", err)
 	}
-	tmp.Close()
 
 	// Verify checksum
 	gotHash := hex.EncodeToString(hasher.Sum(nil))
 	if gotHash != expectedHash {
-		return fmt.Errorf("checksum mismatch: expected %s, got %s", expectedHash, gotHash)
+		return nil, fmt.Errorf("checksum mismatch: expected %s, got %s", expectedHash, gotHash)
 	}
 
-	// Atomic replace
-	if err := os.Chmod(tmpPath, 0o755); err != nil {
+	ok = true
+	return staged, nil
+}
+
+// Install atomically replaces the running executable with the staged binary.
+func (s *Staged) Install() error {
+	if err := os.Chmod(s.path, 0o755); err != nil {
 		return fmt.Errorf("setting permissions: %w", err)
 	}
-	if err := os.Rename(tmpPath, exe); err != nil {
+	if err := os.Rename(s.path, s.exe); err != nil {
 		if os.IsPermission(err) {
-			return fmt.Errorf("permission denied replacing %s — try: sudo cw update", exe)
+			return fmt.Errorf("permission denied replacing %s — try: sudo cw update", s.exe)
 		}
 		return fmt.Errorf("replacing binary: %w", err)
 	}
-
 	return nil
 }
 
-func fetchChecksum(sumsURL, assetName string) (string, error) {
-	resp, err := http.Get(sumsURL)
+// Discard removes the staged binary if it has not been installed.
+func (s *Staged) Discard() {
+	_ = os.Remove(s.path)
+}
+
+// maxChecksumFile bounds checksum and signature downloads.
+const maxChecksumFile = 1 << 20
+
+// fetchSigned downloads url and its armored detached signature url+".asc",
+// and returns the body once the signature checks out.
+func fetchSigned(url string) ([]byte, error) {
+	body, err := fetchFile(url)
 	if err != nil {
-		return "", err
+		return nil, err
 	}
-	defer resp.Body.Close()
-
-	if resp.StatusCode != http.StatusOK {
-		return "", fmt.Errorf("SHA256SUMS returned HTTP %d", resp.StatusCode)
+	sig, err := fetchFile(url + ".asc")
+	if err != nil {
+		return nil, fmt.Errorf("fetching signature: %w", err)
+	}
+	if err := verifySignature(body, sig); err != nil {
+		return nil, fmt.Errorf("%s: %w", url, err)
 	}
+	return body, nil
+}
 
-	body, err := io.ReadAll(resp.Body)
+func fetchFile(url string) ([]byte, error) {
+	resp, err := http.Get(url)
 	if err != nil {
-		return "", err
+		return nil, err
+	}
+	defer resp.Body.Close()
+
+	if resp.StatusCode != http.StatusOK {
+		return nil, fmt.Errorf("%s returned HTTP %d", url, resp.StatusCode)
 	}
+	return io.ReadAll(io.LimitReader(resp.Body, maxChecksumFile))
+}
 
+// findChecksum returns assetName's hash from a SHA256SUMS file.
+func findChecksum(body []byte, assetName string) (string, error) {
 	for _, line := range strings.Split(string(body), "\n") {
 		// Format: "<hash>  <filename>" or "<hash> <filename>"
 		fields := strings.Fields(line)
 		if len(fields) == 2 && fields[1] == assetName {
-			return fields[0], nil
+			return strings.ToLower(fields[0]), nil
 		}
 	}
 	return "", fmt.Errorf("no checksum found for %s", assetName)
 }
+
+// assetVersion returns the version in a release asset name for this
+// platform ("cw-v0.3.1-x86_64-unknown-linux-musl" is v0.3.1), or "" if
+// name is not one.
+func assetVersion(name string) string {
+	suffix := assetSuffix()
+	if suffix == "" {
+		return ""
+	}
+	rest, ok := strings.CutPrefix(name, "cw-")
+	if !ok {
+		return ""
+	}
+	version, ok := strings.CutSuffix(rest, "-"+suffix)
+	if !ok || !IsRelease(version) {
+		return ""
+	}
+	return version
+}
+
+// checkDowngrade refuses to stage version when it is older than current,
+// or cannot be compared with it, unless allowDowngrade is set. A dev build
+// has no version to protect.
+func checkDowngrade(current, version string, allowDowngrade bool) error {
+	if allowDowngrade || !IsRelease(current) {
+		return nil
+	}
+	if !IsRelease(version) {
+		return fmt.Errorf("cannot tell whether %s is older than the running %s; pass --allow-downgrade to install it anyway", version, current)
+	}
+	if IsNewer(version, current) {
+		return fmt.Errorf("refusing to downgrade from %s to %s; pass --allow-downgrade to install an older release", current, version)
+	}
+	return nil
+}
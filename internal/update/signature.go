@@ -0,0 +1,28 @@
+package update
+
+import (
+	"bytes"
+	_ "embed"
+	"fmt"
+
+	"github.com/ProtonMail/go-crypto/openpgp"
+)
+
+// releaseKey is the armored public key release checksums are signed with,
+// a copy of GPG_PUBLIC_KEY.asc at the repository root.
+//
+//go:embed release_key.asc
+var releaseKey []byte
+
+// verifySignature checks that armoredSig is a detached signature of data
+// made with the release key.
+func verifySignature(data, armoredSig []byte) error {
+	keyring, err := openpgp.ReadArmoredKeyRing(bytes.NewReader(releaseKey))
+	if err != nil {
+		return fmt.Errorf("reading release key: %w", err)
+	}
+	if _, err := openpgp.CheckArmoredDetachedSignature(keyring, bytes.NewReader(data), bytes.NewReader(armoredSig), nil); err != nil {
+		return fmt.Errorf("signature verification failed: %w", err)
+	}
+	return nil
+}
@@ -1,11 +1,13 @@
 package update
 
 import (
+	"cmp"
 	"encoding/json"
 	"fmt"
 	"net/http"
 	"os"
 	"path/filepath"
+	"regexp"
 	"strconv"
 	"strings"
 	"time"
@@ -14,10 +16,17 @@ import (
 )
 
 const (
-	githubAPI  = "https://api.github.com/repos/codewiresh/codewire/releases/latest"
-	cacheTTL   = 24 * time.Hour
-	cacheFile  = "update-check.json"
-	fetchTimeout = 5 * time.Second
+	githubAPI         = "https://api.github.com/repos/codewiresh/codewire/releases/latest"
+	githubReleasesAPI = "https://api.github.com/repos/codewiresh/codewire/releases?per_page=20"
+	cacheTTL          = 24 * time.Hour
+	cacheFile         = "update-check.json"
+	fetchTimeout      = 5 * time.Second
+)
+
+// Release channels accepted by FetchChannelVersion.
+const (
+	ChannelStable = "stable" // latest non-prerelease
+	ChannelBeta   = "beta"   // newest release, including prereleases
 )
 
 type cacheEntry struct {
@@ -28,6 +37,7 @@ type cacheEntry struct {
 
 type githubRelease struct {
 	TagName string `json:"tag_name"`
+	Draft   bool   `json:"draft"`
 }
 
 // FetchLatestVersion queries the GitHub Releases API and returns the latest tag_name.
@@ -35,6 +45,18 @@ func FetchLatestVersion() (string, error) {
 	return fetchLatestVersionFrom(githubAPI)
 }
 
+// FetchChannelVersion returns the newest release tag on channel.
+func FetchChannelVersion(channel string) (string, error) {
+	switch channel {
+	case "", ChannelStable:
+		return FetchLatestVersion()
+	case ChannelBeta:
+		return fetchNewestReleaseFrom(githubReleasesAPI)
+	default:
+		return "", fmt.Errorf("unknown channel %q (want %s or %s)", channel, ChannelStable, ChannelBeta)
+	}
+}
+
 // httpClient is the client used for API requests. Tests may override this.
 var httpClient = &http.Client{Timeout: fetchTimeout}
 
@@ -65,50 +87,141 @@ func fetchLatestVersionFrom(url string) (string, error) {
 	return rel.TagName, nil
 }
 
-// IsNewer returns true if latest is a higher semver than current.
-// Returns false on any parse error.
+// fetchNewestReleaseFrom lists releases (newest first) and returns the first
+// published tag, prereleases included.
+func fetchNewestReleaseFrom(url string) (string, error) {
+	req, err := http.NewRequest("GET", url, nil)
+	if err != nil {
+		return "", err
+	}
+	req.Header.Set("Accept", "application/vnd.github+json")
+
+	resp, err := httpClient.Do(req)
+	if err != nil {
+		return "", err
+	}
+	defer resp.Body.Close()
+
+	if resp.StatusCode != http.StatusOK {
+		return "", fmt.Errorf("github API returned %d", resp.StatusCode)
+	}
+
+	var rels []githubRelease
+	if err := json.NewDecoder(resp.Body).Decode(&rels); err != nil {
+		return "", err
+	}
+	for _, rel := range rels {
+		if !rel.Draft && rel.TagName != "" {
+			return rel.TagName, nil
+		}
+	}
+	return "", fmt.Errorf("no published releases found")
+}
+
+// IsNewer returns true if latest has higher semver precedence than current,
+// so "v1.2.0" is newer than "v1.2.0-rc1". Returns false on any parse error.
 func IsNewer(current, latest string) bool {
-	cMaj, cMin, cPatch, ok := parseSemver(current)
+	c, ok := parseSemver(current)
 	if !ok {
 		return false
 	}
-	lMaj, lMin, lPatch, ok := parseSemver(latest)
+	l, ok := parseSemver(latest)
 	if !ok {
 		return false
 	}
-	if lMaj != cMaj {
-		return lMaj > cMaj
-	}
-	if lMin != cMin {
-		return lMin > cMin
-	}
-	return lPatch > cPatch
+	return l.compare(c) > 0
 }
 
-// parseSemver parses "v0.2.48" or "0.2.48" into (major, minor, patch, ok).
-func parseSemver(s string) (int, int, int, bool) {
+// IsRelease reports whether version is one IsNewer can compare, as opposed
+// to "dev" or another unversioned build.
+func IsRelease(version string) bool {
+	_, ok := parseSemver(version)
+	return ok
+}
+
+// semver is a parsed version: major.minor.patch and the dot-separated
+// prerelease identifiers after "-", if any.
+type semver struct {
+	major, minor, patch int
+	pre                 []string
+}
+
+// gitDescribeSuffix matches what git describe appends to the tag of a build
+// made after it ("-2-gf2fe21a", "-dirty"); such builds count as the tag.
+var gitDescribeSuffix = regexp.MustCompile(`(-\d+-g[0-9a-f]+)?(-dirty)?$`)
+
+// parseSemver parses "v0.2.48", "0.2.48" or "v1.2.0-rc.1". Build metadata
+// ("+...") is ignored.
+func parseSemver(s string) (semver, bool) {
 	s = strings.TrimPrefix(s, "v")
-	// Strip git-describe suffix (e.g. "0.2.52-2-gf2fe21a" → "0.2.52")
+	if idx := strings.Index(s, "+"); idx != -1 {
+		s = s[:idx]
+	}
+	s = gitDescribeSuffix.ReplaceAllString(s, "")
+	var v semver
 	if idx := strings.Index(s, "-"); idx != -1 {
+		v.pre = strings.Split(s[idx+1:], ".")
 		s = s[:idx]
+		for _, id := range v.pre {
+			if id == "" {
+				return semver{}, false
+			}
+		}
 	}
 	parts := strings.SplitN(s, ".", 3)
 	if len(parts) != 3 {
-		return 0, 0, 0, false
+		return semver{}, false
 	}
-	maj, err := strconv.Atoi(parts[0])
-	if err != nil {
-		return 0, 0, 0, false
+	for i, p := range []*int{&v.major, &v.minor, &v.patch} {
+		n, err := strconv.Atoi(parts[i])
+		if err != nil || n < 0 {
+			return semver{}, false
+		}
+		*p = n
 	}
-	min, err := strconv.Atoi(parts[1])
-	if err != nil {
-		return 0, 0, 0, false
+	return v, true
+}
+
+// compare orders versions by semver precedence: a prerelease comes before
+// its release, and prerelease identifiers compare numerically when both are
+// numbers, else as strings, with numbers first.
+func (v semver) compare(o semver) int {
+	if c := cmp.Compare(v.major, o.major); c != 0 {
+		return c
 	}
-	patch, err := strconv.Atoi(parts[2])
-	if err != nil {
-		return 0, 0, 0, false
+	if c := cmp.Compare(v.minor, o.minor); c != 0 {
+		return c
+	}
+	if c := cmp.Compare(v.patch, o.patch); c != 0 {
+		return c
+	}
+	switch {
+	case len(v.pre) == 0 && len(o.pre) == 0:
+		return 0
+	case len(v.pre) == 0:
+		return 1
+	case len(o.pre) == 0:
+		return -1
+	}
+	for i := 0; i < len(v.pre) && i < len(o.pre); i++ {
+		a, aErr := strconv.Atoi(v.pre[i])
+		b, bErr := strconv.Atoi(o.pre[i])
+		var c int
+		switch {
+		case aErr == nil && bErr == nil:
+			c = cmp.Compare(a, b)
+		case aErr == nil:
+			c = -1
+		case bErr == nil:
+			c = 1
+		default:
+			c = strings.Compare(v.pre[i], o.pre[i])
+		}
+		if c != 0 {
+			return c
+		}
 	}
-	return maj, min, patch, true
+	return cmp.Compare(len(v.pre), len(o.pre))
 }
 
 // BackgroundCheck starts a goroutine that checks for updates and returns a
@@ -1,35 +1,47 @@
 package update
 
 import (
+	"bytes"
 	"encoding/json"
 	"net/http"
 	"net/http/httptest"
+	"os"
+	"strings"
 	"testing"
+
+	"github.com/ProtonMail/go-crypto/openpgp"
+	"github.com/ProtonMail/go-crypto/openpgp/armor"
 )
 
 func TestParseSemver(t *testing.T) {
 	tests := []struct {
-		input            string
-		maj, min, patch  int
-		ok               bool
+		input           string
+		maj, min, patch int
+		pre             string
+		ok              bool
 	}{
-		{"v0.2.48", 0, 2, 48, true},
-		{"0.2.48", 0, 2, 48, true},
-		{"v1.0.0", 1, 0, 0, true},
-		{"v10.20.30", 10, 20, 30, true},
-		{"dev", 0, 0, 0, false},
-		{"", 0, 0, 0, false},
-		{"v1.2", 0, 0, 0, false},
-		{"v1.2.three", 0, 0, 0, false},
-		{"v0.2.52-2-gf2fe21a", 0, 2, 52, true},
-		{"0.2.52-2-gf2fe21a", 0, 2, 52, true},
-		{"v1.0.0-dirty", 1, 0, 0, true},
+		{"v0.2.48", 0, 2, 48, "", true},
+		{"0.2.48", 0, 2, 48, "", true},
+		{"v1.0.0", 1, 0, 0, "", true},
+		{"v10.20.30", 10, 20, 30, "", true},
+		{"dev", 0, 0, 0, "", false},
+		{"", 0, 0, 0, "", false},
+		{"v1.2", 0, 0, 0, "", false},
+		{"v1.2.three", 0, 0, 0, "", false},
+		{"v0.2.52-2-gf2fe21a", 0, 2, 52, "", true},
+		{"0.2.52-2-gf2fe21a", 0, 2, 52, "", true},
+		{"v1.0.0-dirty", 1, 0, 0, "", true},
+		{"v1.2.0-rc1", 1, 2, 0, "rc1", true},
+		{"v1.2.0-beta.2+build.5", 1, 2, 0, "beta.2", true},
+		{"v1.2.0-rc1-3-gabc1234", 1, 2, 0, "rc1", true},
+		{"v1.2.0-", 0, 0, 0, "", false},
 	}
 	for _, tt := range tests {
-		maj, min, patch, ok := parseSemver(tt.input)
-		if ok != tt.ok || maj != tt.maj || min != tt.min || patch != tt.patch {
-			t.Errorf("parseSemver(%q) = (%d,%d,%d,%v), want (%d,%d,%d,%v)",
-				tt.input, maj, min, patch, ok, tt.maj, tt.min, tt.patch, tt.ok)
+		v, ok := parseSemver(tt.input)
+		pre := strings.Join(v.pre, ".")
+		if ok != tt.ok || v.major != tt.maj || v.minor != tt.min || v.patch != tt.patch || pre != tt.pre {
+			t.Errorf("parseSemver(%q) = (%d,%d,%d,%q,%v), want (%d,%d,%d,%q,%v)",
+				tt.input, v.major, v.minor, v.patch, pre, ok, tt.maj, tt.min, tt.patch, tt.pre, tt.ok)
 		}
 	}
 }
@@ -50,6 +62,14 @@ func TestIsNewer(t *testing.T) {
 		{"bad", "worse", false},
 		{"v0.2.52-2-gf2fe21a", "v0.2.57", true},
 		{"v0.2.57", "v0.2.57", false},
+		{"v1.2.0-rc1", "v1.2.0", true},
+		{"v1.2.0", "v1.2.0-rc1", false},
+		{"v1.2.0-rc.1", "v1.2.0-rc.2", true},
+		{"v1.2.0-rc.2", "v1.2.0-rc.10", true},
+		{"v1.2.0-alpha", "v1.2.0-alpha.1", true},
+		{"v1.2.0-alpha.1", "v1.2.0-beta", true},
+		{"v1.2.0-1", "v1.2.0-alpha", true},
+		{"v1.2.0-beta.1", "v1.2.0-beta.1", false},
 	}
 	for _, tt := range tests {
 		got := IsNewer(tt.current, tt.latest)
@@ -150,3 +170,137 @@ func TestDetectInstallMethod(t *testing.T) {
 		t.Error("String() returned empty")
 	}
 }
+
+func TestFindChecksum(t *testing.T) {
+	sums := []byte("ABCDEF0123  cw-v0.3.0-x86_64-unknown-linux-musl\n")
+	got, err := findChecksum(sums, "cw-v0.3.0-x86_64-unknown-linux-musl")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got != "abcdef0123" {
+		t.Errorf("got %q, want %q", got, "abcdef0123")
+	}
+	// A signed checksum for one asset does not vouch for another.
+	if _, err := findChecksum(sums, "cw-v0.4.0-x86_64-unknown-linux-musl"); err == nil {
+		t.Error("expected a checksum naming another asset to be refused")
+	}
+	if _, err := findChecksum([]byte("abcdef0123\n"), "cw-v0.3.0-x86_64-unknown-linux-musl"); err == nil {
+		t.Error("expected a bare checksum to be refused")
+	}
+}
+
+func TestAssetVersion(t *testing.T) {
+	if assetSuffix() == "" {
+		t.Skip("unsupported platform")
+	}
+	if got := assetVersion(AssetName("v0.3.1")); got != "v0.3.1" {
+		t.Errorf("assetVersion(%q) = %q, want v0.3.1", AssetName("v0.3.1"), got)
+	}
+	for _, name := range []string{"cw-linux-amd64", "cw-v0.3.1-other-platform", "cw-dev-" + assetSuffix()} {
+		if got := assetVersion(name); got != "" {
+			t.Errorf("assetVersion(%q) = %q, want none", name, got)
+		}
+	}
+}
+
+func TestCheckDowngrade(t *testing.T) {
+	for _, tc := range []struct {
+		current, version string
+		allow, ok        bool
+	}{
+		{"v0.3.0", "v0.3.1", false, true},
+		{"v0.3.0", "v0.3.0", false, true},
+		{"v0.3.1", "v0.3.0", false, false},
+		{"v0.3.1", "v0.3.0", true, true},
+		{"v0.3.1", "main", false, false},
+		{"dev", "v0.1.0", false, true},
+	} {
+		if err := checkDowngrade(tc.current, tc.version, tc.allow); (err == nil) != tc.ok {
+			t.Errorf("checkDowngrade(%s, %s, %v) = %v, want ok %v", tc.current, tc.version, tc.allow, err, tc.ok)
+		}
+	}
+}
+
+func TestReleaseKeyMatchesRepo(t *testing.T) {
+	repoKey, err := os.ReadFile("../../GPG_PUBLIC_KEY.asc")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !bytes.Equal(repoKey, releaseKey) {
+		t.Fatal("internal/update/release_key.asc is out of date with GPG_PUBLIC_KEY.asc")
+	}
+	if _, err := openpgp.ReadArmoredKeyRing(bytes.NewReader(releaseKey)); err != nil {
+		t.Fatalf("release key does not parse: %v", err)
+	}
+}
+
+func TestVerifySignature(t *testing.T) {
+	signer, err := openpgp.NewEntity("test", "", "test@example.com", nil)
+	if err != nil {
+		t.Fatal(err)
+	}
+	var pub bytes.Buffer
+	w, _ := armor.Encode(&pub, openpgp.PublicKeyType, nil)
+	if err := signer.Serialize(w); err != nil {
+		t.Fatal(err)
+	}
+	w.Close()
+	saved := releaseKey
+	releaseKey = pub.Bytes()
+	defer func() { releaseKey = saved }()
+
+	sums := []byte("abcdef0123  cw-v0.3.0-x86_64-unknown-linux-musl\n")
+	var sig bytes.Buffer
+	if err := openpgp.ArmoredDetachSign(&sig, signer, bytes.NewReader(sums), nil); err != nil {
+		t.Fatal(err)
+	}
+	if err := verifySignature(sums, sig.Bytes()); err != nil {
+		t.Fatalf("valid signature rejected: %v", err)
+	}
+	tampered := []byte("0000000000  cw-v0.3.0-x86_64-unknown-linux-musl\n")
+	if err := verifySignature(tampered, sig.Bytes()); err == nil {
+		t.Fatal("expected a signature over different data to be rejected")
+	}
+	releaseKey = saved
+	if err := verifySignature(sums, sig.Bytes()); err == nil {
+		t.Fatal("expected a signature by another key to be rejected")
+	}
+}
+
+func TestStageRefusesPlainHTTP(t *testing.T) {
+	if _, err := Stage("http://example.com/cw", "v0.3.0", false); err == nil {
+		t.Fatal("expected plain http to be refused")
+	}
+	if _, err := Stage("https://example.com/cw-linux-amd64", "v0.3.0", false); err == nil {
+		t.Fatal("expected a URL that names no release asset to be refused")
+	}
+}
+
+func TestFetchNewestReleaseSkipsDrafts(t *testing.T) {
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		json.NewEncoder(w).Encode([]githubRelease{
+			{TagName: "v0.4.0", Draft: true},
+			{TagName: "v0.4.0-beta.1"},
+			{TagName: "v0.3.2"},
+		})
+	}))
+	defer srv.Close()
+
+	saved := httpClient
+	httpClient = srv.Client()
+	defer func() { httpClient = saved }()
+
+	got, err := fetchNewestReleaseFrom(srv.URL)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got != "v0.4.0-beta.1" {
+		t.Errorf("got %q, want %q", got, "v0.4.0-beta.1")
+	}
+}
+
+func TestFetchChannelVersionUnknown(t *testing.T) {
+	if _, err := FetchChannelVersion("nightly"); err == nil {
+		t.Fatal("expected error for unknown channel")
+	}
+}
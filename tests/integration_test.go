@@ -2,15 +2,18 @@ package tests
 
 import (
 	"context"
+	"crypto/ed25519"
 	"encoding/json"
 	"fmt"
 	"net"
 	"os"
 	"path/filepath"
 	"strings"
+	"syscall"
 	"testing"
 	"time"
 
+	"github.com/codewiresh/codewire/internal/auth"
 	"github.com/codewiresh/codewire/internal/client"
 	"github.com/codewiresh/codewire/internal/connection"
 	"github.com/codewiresh/codewire/internal/node"
@@ -287,6 +290,56 @@ func TestSessionCompletesNaturally(t *testing.T) {
 	t.Fatalf("session %d not found in list", id)
 }
 
+func TestSnapshotOnCompletion(t *testing.T) {
+	dir := tempDir(t, "snapshot")
+	sock := startTestNode(t, dir)
+
+	workDir := tempDir(t, "snapshot-wd")
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:            "Launch",
+		Command:         []string{"bash", "-c", "echo changed > file.txt"},
+		WorkingDir:      workDir,
+		SnapshotCommand: "echo SNAPSHOT_MARKER; cat file.txt",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	time.Sleep(2 * time.Second)
+
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:     "GetStatus",
+		ID:       uint32Ptr(id),
+		Snapshot: true,
+	})
+	if resp.Type != "SessionStatus" {
+		t.Fatalf("expected SessionStatus, got %s: %s", resp.Type, resp.Message)
+	}
+	if resp.Snapshot == nil {
+		t.Fatal("expected snapshot in response")
+	}
+	if !strings.Contains(*resp.Snapshot, "SNAPSHOT_MARKER") || !strings.Contains(*resp.Snapshot, "changed") {
+		t.Fatalf("unexpected snapshot: %q", *resp.Snapshot)
+	}
+
+	// Sessions launched without a snapshot report an error when asked for one.
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"bash", "-c", "true"},
+		WorkingDir: "/tmp",
+	})
+	plainID := *resp.ID
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:     "GetStatus",
+		ID:       uint32Ptr(plainID),
+		Snapshot: true,
+	})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error for session without snapshot, got %s", resp.Type)
+	}
+}
+
 func TestLogs(t *testing.T) {
 	dir := tempDir(t, "logs")
 	sock := startTestNode(t, dir)
@@ -322,6 +375,647 @@ func TestLogs(t *testing.T) {
 	}
 }
 
+func TestLaunchShellScript(t *testing.T) {
+	dir := tempDir(t, "shell-script")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Script:     "echo 'first half' && echo \"second $((20 + 22))\"",
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	time.Sleep(500 * time.Millisecond)
+
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:   "Logs",
+		ID:     uint32Ptr(id),
+		Follow: boolPtr(false),
+	})
+	if resp.Type != "LogData" {
+		t.Fatalf("expected LogData, got %s: %s", resp.Type, resp.Message)
+	}
+	if !strings.Contains(resp.Data, "first half") || !strings.Contains(resp.Data, "second 42") {
+		t.Fatalf("script output missing, got: %q", resp.Data)
+	}
+}
+
+func TestLaunchBatch(t *testing.T) {
+	dir := tempDir(t, "launch-batch")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type: "LaunchBatch",
+		Launches: []protocol.Request{
+			{Type: "Launch", Command: []string{"echo", "one"}, WorkingDir: "/tmp", Name: "batch-one"},
+			{Type: "Launch", Command: []string{"no-such-binary-xyz"}, WorkingDir: "/tmp"},
+			{Type: "Launch", Script: "echo two", WorkingDir: "/tmp"},
+		},
+	})
+	if resp.Type != "BatchLaunched" || resp.Results == nil {
+		t.Fatalf("expected BatchLaunched, got %s: %s", resp.Type, resp.Message)
+	}
+	results := *resp.Results
+	if len(results) != 3 {
+		t.Fatalf("expected 3 results, got %d", len(results))
+	}
+	if results[0].ID == nil || results[0].Name != "batch-one" {
+		t.Errorf("first launch should succeed with its name: %+v", results[0])
+	}
+	if results[1].ID != nil || results[1].Error == "" {
+		t.Errorf("second launch should fail: %+v", results[1])
+	}
+	if results[2].ID == nil {
+		t.Errorf("third launch should succeed: %+v", results[2])
+	}
+}
+
+func TestPingAnswersRepeatedlyOnOneConnection(t *testing.T) {
+	dir := tempDir(t, "ping")
+	sock := startTestNode(t, dir)
+
+	conn, reader, writer := connectRaw(t, sock)
+	defer conn.Close()
+
+	for seq := uint64(1); seq <= 3; seq++ {
+		if err := writer.SendRequest(&protocol.Request{Type: "Ping", Seq: seq}); err != nil {
+			t.Fatalf("send ping %d: %v", seq, err)
+		}
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			t.Fatalf("connection closed before pong %d: %v", seq, err)
+		}
+		var r protocol.Response
+		if err := json.Unmarshal(f.Payload, &r); err != nil {
+			t.Fatalf("parse pong: %v", err)
+		}
+		if r.Type != "Pong" || r.Seq != seq {
+			t.Fatalf("expected Pong %d, got %s %d", seq, r.Type, r.Seq)
+		}
+	}
+}
+
+func TestKillWithSignalInterruptsWithoutKilling(t *testing.T) {
+	dir := tempDir(t, "kill-signal")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"bash", "-c", "trap 'echo got-int' INT; while true; do sleep 0.1; done"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+	time.Sleep(500 * time.Millisecond)
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: &id, Signal: "INT"})
+	if resp.Type != "Signaled" {
+		t.Fatalf("expected Signaled, got %s: %s", resp.Type, resp.Message)
+	}
+
+	deadline := time.Now().Add(5 * time.Second)
+	for {
+		resp = requestResponse(t, sock, &protocol.Request{Type: "Logs", ID: &id, Follow: boolPtr(false)})
+		if strings.Contains(resp.Data, "got-int") {
+			break
+		}
+		if time.Now().After(deadline) {
+			t.Fatalf("trap did not fire; logs: %q", resp.Data)
+		}
+		time.Sleep(100 * time.Millisecond)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+	if resp.Info == nil || resp.Info.Status != "running" {
+		t.Fatalf("session should still be running: %+v", resp.Info)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: &id, Signal: "NOPE"})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error for unknown signal, got %s", resp.Type)
+	}
+}
+
+func TestNotesAndStatusReasonShownOnAttach(t *testing.T) {
+	dir := tempDir(t, "notes")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sleep", "30"},
+		WorkingDir: "/tmp",
+		Notes:      []string{"refactoring the parser"},
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "AddNote", ID: &id, Notes: []string{"do not merge yet"}})
+	if resp.Type != "NoteAdded" {
+		t.Fatalf("expected NoteAdded, got %s: %s", resp.Type, resp.Message)
+	}
+	resp = requestResponse(t, sock, &protocol.Request{Type: "AddNote", ID: &id, Notes: []string{"  "}})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error for empty note, got %s", resp.Type)
+	}
+
+	conn, reader, writer := connectRaw(t, sock)
+	if err := writer.SendRequest(&protocol.Request{Type: "Attach", ID: &id, IncludeHistory: boolPtr(false)}); err != nil {
+		t.Fatalf("send attach: %v", err)
+	}
+	f, err := reader.ReadFrame()
+	if err != nil || f == nil || f.Type != protocol.FrameControl {
+		t.Fatalf("read attach confirmation: %v", err)
+	}
+	var attached protocol.Response
+	if err := json.Unmarshal(f.Payload, &attached); err != nil {
+		t.Fatalf("unmarshal: %v", err)
+	}
+	conn.Close()
+	if attached.Type != "Attached" || attached.Info == nil {
+		t.Fatalf("expected Attached with info, got %+v", attached)
+	}
+	notes := attached.Info.Notes
+	if len(notes) != 2 || notes[0].Text != "refactoring the parser" || notes[1].Text != "do not merge yet" {
+		t.Fatalf("unexpected notes: %+v", notes)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: &id})
+	if resp.Type != "Killed" {
+		t.Fatalf("expected Killed, got %s: %s", resp.Type, resp.Message)
+	}
+	resp = requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+	if resp.Info == nil || !strings.HasPrefix(resp.Info.StatusReason, "killed by request") {
+		t.Fatalf("expected kill reason, got %+v", resp.Info)
+	}
+}
+
+func TestMaxRuntimeWarnsThenKills(t *testing.T) {
+	dir := tempDir(t, "max-runtime")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sleep", "30"},
+		WorkingDir: "/tmp",
+		MaxRuntime: "3s",
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+	if resp.Info == nil || resp.Info.ExpiryLimit != "max_runtime" || resp.Info.ExpiresAt == "" {
+		t.Fatalf("expected max_runtime expiry, got %+v", resp.Info)
+	}
+
+	deadline := time.Now().Add(10 * time.Second)
+	for {
+		resp = requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+		if resp.Info != nil && resp.Info.Status != "running" {
+			break
+		}
+		if time.Now().After(deadline) {
+			t.Fatal("session was not killed at its max runtime")
+		}
+		time.Sleep(200 * time.Millisecond)
+	}
+	if !strings.Contains(resp.Info.StatusReason, "max runtime") {
+		t.Fatalf("unexpected status reason: %q", resp.Info.StatusReason)
+	}
+
+	events, err := os.ReadFile(filepath.Join(dir, "sessions", fmt.Sprint(id), "events.jsonl"))
+	if err != nil {
+		t.Fatalf("reading events: %v", err)
+	}
+	if !strings.Contains(string(events), `"session.expiring"`) {
+		t.Fatalf("no session.expiring event in %s", events)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:        "Launch",
+		Command:     []string{"true"},
+		WorkingDir:  "/tmp",
+		IdleTimeout: "soon",
+	})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error for invalid idle timeout, got %s", resp.Type)
+	}
+}
+
+func TestExtendPushesBackDeadline(t *testing.T) {
+	dir := tempDir(t, "extend")
+	sock := startTestNode(t, dir)
+
+	launch := func(maxRuntime string) uint32 {
+		resp := requestResponse(t, sock, &protocol.Request{
+			Type:       "Launch",
+			Command:    []string{"sleep", "30"},
+			WorkingDir: "/tmp",
+			MaxRuntime: maxRuntime,
+		})
+		if resp.Type != "Launched" || resp.ID == nil {
+			t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+		}
+		return *resp.ID
+	}
+	id := launch("1h")
+
+	resp := requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+	before, err := time.Parse(time.RFC3339, resp.Info.ExpiresAt)
+	if err != nil {
+		t.Fatalf("parsing expires_at %q: %v", resp.Info.ExpiresAt, err)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Extend", ID: &id, Extra: "30m"})
+	if resp.Type != "Extended" {
+		t.Fatalf("expected Extended, got %s: %s", resp.Type, resp.Message)
+	}
+	resp = requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+	after, _ := time.Parse(time.RFC3339, resp.Info.ExpiresAt)
+	if got := after.Sub(before); got != 30*time.Minute {
+		t.Fatalf("deadline moved by %s, want 30m", got)
+	}
+
+	events, err := os.ReadFile(filepath.Join(dir, "sessions", fmt.Sprint(id), "events.jsonl"))
+	if err != nil {
+		t.Fatalf("reading events: %v", err)
+	}
+	if !strings.Contains(string(events), `"session.extended"`) {
+		t.Fatalf("no session.extended event in %s", events)
+	}
+
+	unlimited := launch("")
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Extend", ID: &unlimited, Extra: "1h"})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error extending a session without limits, got %s", resp.Type)
+	}
+}
+
+func TestGuardrailRejectsLaunchOverMaxSessions(t *testing.T) {
+	dir := tempDir(t, "guardrails")
+	if err := os.WriteFile(filepath.Join(dir, "config.toml"), []byte("[guardrails]\nmax_sessions = 1\n"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	sock := startTestNode(t, dir)
+
+	launch := &protocol.Request{Type: "Launch", Command: []string{"sleep", "30"}, WorkingDir: "/tmp"}
+	resp := requestResponse(t, sock, launch)
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+
+	resp = requestResponse(t, sock, launch)
+	if resp.Type != "Error" || resp.Guardrail == nil {
+		t.Fatalf("expected guardrail Error, got %s: %s", resp.Type, resp.Message)
+	}
+	if resp.Guardrail.Guardrail != "max_sessions" || resp.Guardrail.Limit != 1 || resp.Guardrail.Value != 1 {
+		t.Fatalf("unexpected breach: %+v", resp.Guardrail)
+	}
+}
+
+func TestLaunchSetsTerm(t *testing.T) {
+	dir := tempDir(t, "term")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sh", "-c", "echo term=$TERM"},
+		WorkingDir: "/tmp",
+		Term:       "vt100",
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+	time.Sleep(500 * time.Millisecond)
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Logs", ID: &id, Follow: boolPtr(false)})
+	if !strings.Contains(resp.Data, "term=vt100") {
+		t.Fatalf("expected TERM=vt100 in output, got %q", resp.Data)
+	}
+	resp = requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+	if resp.Info == nil || resp.Info.Term != "vt100" {
+		t.Fatalf("expected Term vt100 in status, got %+v", resp.Info)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Launch", Command: []string{"true"}, WorkingDir: "/tmp", Term: "bad term"})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error for invalid TERM, got %s", resp.Type)
+	}
+}
+
+func TestAttachDownconvertsColors(t *testing.T) {
+	dir := tempDir(t, "colors")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sh", "-c", "printf '\\033[38;2;255;0;0mred\\033[0m\\n'; sleep 5"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+	time.Sleep(500 * time.Millisecond)
+
+	conn, reader, writer := connectRaw(t, sock)
+	defer conn.Close()
+	if err := writer.SendRequest(&protocol.Request{Type: "Attach", ID: &id, IncludeHistory: boolPtr(true), Colors: "256"}); err != nil {
+		t.Fatalf("send attach: %v", err)
+	}
+	var data []byte
+	deadline := time.Now().Add(3 * time.Second)
+	for time.Now().Before(deadline) && !strings.Contains(string(data), "red") {
+		conn.SetReadDeadline(deadline)
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			break
+		}
+		if f.Type == protocol.FrameData {
+			data = append(data, f.Payload...)
+		}
+	}
+	if !strings.Contains(string(data), "\x1b[38;5;196mred") {
+		t.Fatalf("expected 256-color red in replay, got %q", data)
+	}
+	if strings.Contains(string(data), "38;2;") {
+		t.Fatalf("truecolor sequence was not converted: %q", data)
+	}
+}
+
+func TestLaunchStartsAtRequestedSize(t *testing.T) {
+	dir := tempDir(t, "launch-size")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sh", "-c", "echo SIZE=$(stty size)"},
+		WorkingDir: "/tmp",
+		Cols:       uint16Ptr(132),
+		Rows:       uint16Ptr(43),
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	deadline := time.Now().Add(5 * time.Second)
+	for {
+		resp = requestResponse(t, sock, &protocol.Request{Type: "Logs", ID: &id, Follow: boolPtr(false)})
+		if strings.Contains(resp.Data, "SIZE=") {
+			break
+		}
+		if time.Now().After(deadline) {
+			t.Fatalf("no size in logs: %q", resp.Data)
+		}
+		time.Sleep(100 * time.Millisecond)
+	}
+	if !strings.Contains(resp.Data, "SIZE=43 132") {
+		t.Fatalf("expected the PTY to start at 132x43, got %q", resp.Data)
+	}
+}
+
+func TestAttachReplaysHistoryLines(t *testing.T) {
+	dir := tempDir(t, "attach-history")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sh", "-c", "for i in 1 2 3 4 5; do echo LINE_$i; done; sleep 5"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+	time.Sleep(500 * time.Millisecond)
+
+	attach := func(req *protocol.Request) string {
+		conn, reader, writer := connectRaw(t, sock)
+		defer conn.Close()
+		if err := writer.SendRequest(req); err != nil {
+			t.Fatalf("send attach: %v", err)
+		}
+		var data []byte
+		deadline := time.Now().Add(time.Second)
+		for time.Now().Before(deadline) {
+			conn.SetReadDeadline(deadline)
+			f, err := reader.ReadFrame()
+			if err != nil || f == nil {
+				break
+			}
+			if f.Type == protocol.FrameData {
+				data = append(data, f.Payload...)
+			}
+		}
+		return string(data)
+	}
+
+	got := attach(&protocol.Request{Type: "Attach", ID: &id, IncludeHistory: boolPtr(true), HistoryLines: uintPtr(3)})
+	if !strings.Contains(got, "LINE_3") || !strings.Contains(got, "LINE_5") || strings.Contains(got, "LINE_2") {
+		t.Fatalf("expected only the last three lines replayed, got %q", got)
+	}
+
+	got = attach(&protocol.Request{Type: "Attach", ID: &id, IncludeHistory: boolPtr(false)})
+	if strings.Contains(got, "LINE_") {
+		t.Fatalf("expected no replay with include_history false, got %q", got)
+	}
+}
+
+func TestIdentifyProvesNodeKey(t *testing.T) {
+	dir := tempDir(t, "identify")
+	startTestNode(t, dir)
+
+	name, pub, err := client.Identify(&client.Target{Local: dir})
+	if err != nil {
+		t.Fatalf("Identify: %v", err)
+	}
+	key, err := auth.LoadOrGenerateNodeKey(dir)
+	if err != nil {
+		t.Fatalf("LoadOrGenerateNodeKey: %v", err)
+	}
+	if name == "" || !key.Public().(ed25519.PublicKey).Equal(pub) {
+		t.Fatalf("Identify returned %q %x, want the key in node.key", name, pub)
+	}
+
+	sock := filepath.Join(dir, "codewire.sock")
+	resp := requestResponse(t, sock, &protocol.Request{Type: "Identify", Nonce: []byte("short")})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error for a short nonce, got %s", resp.Type)
+	}
+}
+
+func TestRemoteConnectProvesIdentityBeforeToken(t *testing.T) {
+	dir := tempDir(t, "remote-auth")
+	l, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		t.Fatal(err)
+	}
+	addr := l.Addr().String()
+	l.Close()
+	if err := os.WriteFile(filepath.Join(dir, "config.toml"), []byte(fmt.Sprintf("[node]\nlisten = %q\n", addr)), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	startTestNode(t, dir)
+	for i := 0; ; i++ {
+		conn, err := net.Dial("tcp", addr)
+		if err == nil {
+			conn.Close()
+			break
+		}
+		if i == 50 {
+			t.Fatalf("websocket listener not up: %v", err)
+		}
+		time.Sleep(100 * time.Millisecond)
+	}
+	token, err := auth.LoadOrGenerateToken(dir)
+	if err != nil {
+		t.Fatal(err)
+	}
+	key, err := auth.LoadOrGenerateNodeKey(dir)
+	if err != nil {
+		t.Fatal(err)
+	}
+	url := "http://" + addr
+
+	target := &client.Target{URL: url, Token: token, NodeKey: key.Public().(ed25519.PublicKey)}
+	reader, writer, err := target.Connect()
+	if err != nil {
+		t.Fatalf("Connect: %v", err)
+	}
+	if err := writer.SendRequest(&protocol.Request{Type: "ListSessions"}); err != nil {
+		t.Fatal(err)
+	}
+	if f, err := reader.ReadFrame(); err != nil || f == nil {
+		t.Fatalf("no response after authenticating: %v", err)
+	}
+	reader.Close()
+	writer.Close()
+
+	if _, _, err := (&client.Target{URL: url, Token: "wrong"}).Connect(); err == nil || !strings.Contains(err.Error(), "unauthorized") {
+		t.Fatalf("expected a wrong token to be refused, got %v", err)
+	}
+	otherPub, _, _ := ed25519.GenerateKey(nil)
+	if _, _, err := (&client.Target{URL: url, Token: token, NodeKey: otherPub}).Connect(); err == nil || !strings.Contains(err.Error(), "has changed") {
+		t.Fatalf("expected a mismatched pinned key to be refused, got %v", err)
+	}
+}
+
+func TestHistoryExportImportBetweenNodes(t *testing.T) {
+	srcSock := startTestNode(t, tempDir(t, "history-src"))
+	dstSock := startTestNode(t, tempDir(t, "history-dst"))
+
+	resp := requestResponse(t, srcSock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sh", "-c", "echo exported-output; exit 3"},
+		WorkingDir: "/tmp",
+		Tags:       []string{"nightly"},
+	})
+	if resp.Type != "Launched" || resp.ID == nil {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+	for i := 0; i < 50; i++ {
+		resp = requestResponse(t, srcSock, &protocol.Request{Type: "GetStatus", ID: &id})
+		if resp.Info != nil && resp.Info.ExitCode != nil {
+			break
+		}
+		time.Sleep(100 * time.Millisecond)
+	}
+
+	conn, reader, writer := connectRaw(t, srcSock)
+	defer conn.Close()
+	if err := writer.SendRequest(&protocol.Request{Type: "ExportHistory", Transcripts: true}); err != nil {
+		t.Fatalf("send export: %v", err)
+	}
+	var records []protocol.HistoryRecord
+	for {
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			t.Fatalf("read export: %v", err)
+		}
+		var r protocol.Response
+		if err := json.Unmarshal(f.Payload, &r); err != nil {
+			t.Fatalf("unmarshal: %v", err)
+		}
+		if r.Type == "HistoryEnd" {
+			break
+		}
+		if r.Type != "HistoryRecord" || r.Record == nil {
+			t.Fatalf("expected HistoryRecord, got %s: %s", r.Type, r.Message)
+		}
+		records = append(records, *r.Record)
+	}
+	if len(records) != 1 || !strings.Contains(string(records[0].Transcript), "exported-output") {
+		t.Fatalf("unexpected export: %+v", records)
+	}
+
+	rec := records[0]
+	rec.Node = "src"
+	resp = requestResponse(t, dstSock, &protocol.Request{Type: "ImportHistory", Record: &rec})
+	if resp.Type != "HistoryImported" || resp.ID == nil {
+		t.Fatalf("expected HistoryImported, got %s: %s", resp.Type, resp.Message)
+	}
+	newID := *resp.ID
+	resp = requestResponse(t, dstSock, &protocol.Request{Type: "GetStatus", ID: &newID})
+	info := resp.Info
+	if info == nil || info.ExitCode == nil || *info.ExitCode != 3 || info.ImportedFrom != fmt.Sprintf("src:%d", id) {
+		t.Fatalf("unexpected imported session: %+v", info)
+	}
+	if len(info.Tags) != 1 || info.Tags[0] != "nightly" {
+		t.Fatalf("expected tags to survive import, got %v", info.Tags)
+	}
+
+	resp = requestResponse(t, dstSock, &protocol.Request{Type: "Logs", ID: &newID, Follow: boolPtr(false)})
+	if resp.Type != "LogData" || !strings.Contains(resp.Data, "exported-output") {
+		t.Fatalf("expected imported transcript in logs, got %s: %q", resp.Type, resp.Data)
+	}
+}
+
+func TestKillDryRunListsWithoutKilling(t *testing.T) {
+	dir := tempDir(t, "kill-dry-run")
+	sock := startTestNode(t, dir)
+
+	for _, tag := range []string{"doomed", "doomed", "spared"} {
+		resp := requestResponse(t, sock, &protocol.Request{
+			Type:       "Launch",
+			Command:    []string{"sleep", "30"},
+			WorkingDir: "/tmp",
+			Tags:       []string{tag},
+		})
+		if resp.Type != "Launched" {
+			t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+		}
+	}
+
+	resp := requestResponse(t, sock, &protocol.Request{Type: "KillByTags", Tags: []string{"doomed"}, DryRun: true})
+	if resp.Type != "KillMatches" || resp.Sessions == nil || len(*resp.Sessions) != 2 {
+		t.Fatalf("expected 2 KillMatches, got %s %v", resp.Type, resp.Sessions)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "KillAll", DryRun: true})
+	if resp.Type != "KillMatches" || resp.Sessions == nil || len(*resp.Sessions) != 3 {
+		t.Fatalf("expected 3 KillMatches, got %s %v", resp.Type, resp.Sessions)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "ListSessions"})
+	for _, s := range *resp.Sessions {
+		if s.Status != "running" {
+			t.Errorf("dry run must not kill session %d (status %s)", s.ID, s.Status)
+		}
+	}
+}
+
 func TestAttachAndReceiveOutput(t *testing.T) {
 	dir := tempDir(t, "attach")
 	sock := startTestNode(t, dir)
@@ -424,10 +1118,116 @@ loop:
 		}
 	}
 
-	output := string(collected)
-	if !strings.Contains(output, "ATTACH_TEST_") {
-		t.Fatalf("attached client should receive PTY output, got: %q", output)
+	output := string(collected)
+	if !strings.Contains(output, "ATTACH_TEST_") {
+		t.Fatalf("attached client should receive PTY output, got: %q", output)
+	}
+}
+
+func TestAttachCompletionCarriesExitCode(t *testing.T) {
+	dir := tempDir(t, "attach-exit")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"bash", "-c", "sleep 1; exit 3"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	conn, reader, writer := connectRaw(t, sock)
+	defer conn.Close()
+
+	if err := writer.SendRequest(&protocol.Request{
+		Type:           "Attach",
+		ID:             uint32Ptr(id),
+		IncludeHistory: boolPtr(false),
+	}); err != nil {
+		t.Fatalf("send attach: %v", err)
+	}
+
+	deadline := time.Now().Add(10 * time.Second)
+	for time.Now().Before(deadline) {
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			t.Fatalf("connection closed before completion frame: %v", err)
+		}
+		if f.Type != protocol.FrameControl {
+			continue
+		}
+		var r protocol.Response
+		if err := json.Unmarshal(f.Payload, &r); err != nil {
+			t.Fatalf("parse control frame: %v", err)
+		}
+		if r.Type != "Error" {
+			continue
+		}
+		if r.Status != "completed" {
+			t.Fatalf("expected status completed, got %q (%s)", r.Status, r.Message)
+		}
+		if r.ExitCode == nil || *r.ExitCode != 3 {
+			t.Fatalf("expected exit_code 3, got %v", r.ExitCode)
+		}
+		return
+	}
+	t.Fatal("timed out waiting for completion frame")
+}
+
+func TestAttachIdleTimeoutDetaches(t *testing.T) {
+	dir := tempDir(t, "attach-idle")
+	cfg := "[node]\nname = \"idle-test\"\nattach_idle_timeout = \"2s\"\n"
+	if err := os.WriteFile(filepath.Join(dir, "config.toml"), []byte(cfg), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"bash", "-c", "sleep 30"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	conn, reader, writer := connectRaw(t, sock)
+	defer conn.Close()
+
+	if err := writer.SendRequest(&protocol.Request{
+		Type:           "Attach",
+		ID:             uint32Ptr(id),
+		IncludeHistory: boolPtr(false),
+	}); err != nil {
+		t.Fatalf("send attach: %v", err)
+	}
+
+	var seen []string
+	deadline := time.Now().Add(10 * time.Second)
+	for time.Now().Before(deadline) {
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			t.Fatalf("connection closed before detach (seen %v): %v", seen, err)
+		}
+		if f.Type != protocol.FrameControl {
+			continue
+		}
+		var r protocol.Response
+		if err := json.Unmarshal(f.Payload, &r); err != nil {
+			t.Fatalf("parse control frame: %v", err)
+		}
+		seen = append(seen, r.Type)
+		if r.Type == "Detached" {
+			if len(seen) < 3 || seen[len(seen)-2] != "IdleWarning" {
+				t.Fatalf("expected IdleWarning before Detached, got %v", seen)
+			}
+			return
+		}
 	}
+	t.Fatalf("timed out waiting for idle detach (seen %v)", seen)
 }
 
 func TestAttachSendInput(t *testing.T) {
@@ -811,6 +1611,63 @@ loop:
 	requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: uint32Ptr(id)})
 }
 
+func TestAttachNotifiesClientCount(t *testing.T) {
+	dir := tempDir(t, "attach-clients")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sleep", "30"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+	defer requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: uint32Ptr(id)})
+
+	attach := func() connection.FrameReader {
+		conn, reader, writer := connectRaw(t, sock)
+		t.Cleanup(func() { conn.Close() })
+		conn.SetReadDeadline(time.Now().Add(10 * time.Second))
+		if err := writer.SendRequest(&protocol.Request{Type: "Attach", ID: uint32Ptr(id), IncludeHistory: boolPtr(false)}); err != nil {
+			t.Fatalf("send attach: %v", err)
+		}
+		return reader
+	}
+	// waitClients reads control frames until a Clients count of want.
+	waitClients := func(label string, reader connection.FrameReader, want uint) {
+		t.Helper()
+		for {
+			f, err := reader.ReadFrame()
+			if err != nil || f == nil {
+				t.Fatalf("%s: connection closed before Clients %d: %v", label, want, err)
+			}
+			if f.Type != protocol.FrameControl {
+				continue
+			}
+			var r protocol.Response
+			json.Unmarshal(f.Payload, &r)
+			if r.Type == "Clients" && r.Count != nil && *r.Count == want {
+				return
+			}
+		}
+	}
+
+	first := attach()
+	time.Sleep(200 * time.Millisecond)
+	second := attach()
+	waitClients("first", first, 2)
+	waitClients("second", second, 2)
+
+	resp = requestResponse(t, sock, &protocol.Request{Type: "ListSessions"})
+	for _, s := range *resp.Sessions {
+		if s.ID == id && s.AttachedCount != 2 {
+			t.Fatalf("expected attached_count 2, got %d", s.AttachedCount)
+		}
+	}
+}
+
 func TestSendInputCrossSession(t *testing.T) {
 	dir := tempDir(t, "cross-input")
 	sock := startTestNode(t, dir)
@@ -862,6 +1719,84 @@ func TestSendInputCrossSession(t *testing.T) {
 	requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: uint32Ptr(id)})
 }
 
+func TestVersionAndUpgradeValidation(t *testing.T) {
+	dir := tempDir(t, "upgrade")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{Type: "Version"})
+	if resp.Type != "Version" || resp.Version == "" {
+		t.Fatalf("expected Version with a version, got %s %q", resp.Type, resp.Version)
+	}
+
+	// Without a target the upgrade must be rejected before anything is
+	// downloaded or drained.
+	resp = requestResponse(t, sock, &protocol.Request{Type: "Upgrade"})
+	if resp.Type != "Error" {
+		t.Fatalf("expected Error, got %s", resp.Type)
+	}
+
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"true"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("node should still accept sessions, got %s: %s", resp.Type, resp.Message)
+	}
+}
+
+func TestInputHistoryRecorded(t *testing.T) {
+	dir := tempDir(t, "input-history")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:        "Launch",
+		Command:     []string{"bash", "-c", "cat"},
+		WorkingDir:  "/tmp",
+		RecordInput: true,
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	time.Sleep(500 * time.Millisecond)
+
+	for _, chunk := range []string{"first\n", "second\n"} {
+		resp = requestResponse(t, sock, &protocol.Request{
+			Type: "SendInput",
+			ID:   uint32Ptr(id),
+			Data: []byte(chunk),
+		})
+		if resp.Type != "InputSent" {
+			t.Fatalf("expected InputSent, got %s: %s", resp.Type, resp.Message)
+		}
+	}
+
+	time.Sleep(500 * time.Millisecond)
+
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:   "Logs",
+		ID:     uint32Ptr(id),
+		Inputs: true,
+	})
+	if resp.Type != "InputLog" || resp.Inputs == nil {
+		t.Fatalf("expected InputLog, got %s: %s", resp.Type, resp.Message)
+	}
+	inputs := *resp.Inputs
+	if len(inputs) != 2 {
+		t.Fatalf("expected 2 input records, got %d", len(inputs))
+	}
+	if string(inputs[0].Data) != "first\n" || string(inputs[1].Data) != "second\n" {
+		t.Fatalf("unexpected input records: %q, %q", inputs[0].Data, inputs[1].Data)
+	}
+	if inputs[0].Timestamp == "" {
+		t.Fatal("input record missing timestamp")
+	}
+
+	requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: uint32Ptr(id)})
+}
+
 func TestGetSessionStatus(t *testing.T) {
 	dir := tempDir(t, "status")
 	sock := startTestNode(t, dir)
@@ -998,6 +1933,141 @@ loop:
 	}
 }
 
+func TestWatchSessionCoalescesOutput(t *testing.T) {
+	dir := tempDir(t, "watch-coalesce")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"bash", "-c", "sleep 0.5; for i in $(seq 1 500); do echo line$i; done; sleep 0.5"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	conn, reader, writer := connectRaw(t, sock)
+	defer conn.Close()
+	if err := writer.SendRequest(&protocol.Request{
+		Type:           "WatchSession",
+		ID:             uint32Ptr(id),
+		IncludeHistory: boolPtr(false),
+		MaxRate:        2,
+	}); err != nil {
+		t.Fatalf("send watch: %v", err)
+	}
+
+	var output string
+	var updates int
+	var lastSeq uint64
+	conn.SetReadDeadline(time.Now().Add(5 * time.Second))
+	for {
+		f, err := reader.ReadFrame()
+		if err != nil || f == nil {
+			t.Fatalf("watch ended early: %v", err)
+		}
+		var r protocol.Response
+		if err := json.Unmarshal(f.Payload, &r); err != nil {
+			t.Fatalf("parse watch response: %v", err)
+		}
+		if r.Seq != lastSeq+1 {
+			t.Fatalf("expected seq %d, got %d", lastSeq+1, r.Seq)
+		}
+		lastSeq = r.Seq
+		if r.Output != nil {
+			output += *r.Output
+			updates++
+		}
+		if r.Done != nil && *r.Done {
+			break
+		}
+	}
+
+	if !strings.Contains(output, "line1\r\n") || !strings.Contains(output, "line500") {
+		t.Fatalf("missing output: %q", output)
+	}
+	if updates > 3 {
+		t.Fatalf("expected output coalesced into at most 3 updates at 2/s, got %d", updates)
+	}
+}
+
+func TestWatchSessionResumesFromCursor(t *testing.T) {
+	dir := tempDir(t, "watch-resume")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"bash", "-c", "echo first-1; echo first-2; sleep 1.5; echo second-1; echo second-2"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+
+	// watch reads updates until stop reports true, returning the output and
+	// the last cursor.
+	watch := func(req *protocol.Request, stop func(output string, done bool) bool) (string, uint64) {
+		conn, reader, writer := connectRaw(t, sock)
+		defer conn.Close()
+		if err := writer.SendRequest(req); err != nil {
+			t.Fatalf("send watch: %v", err)
+		}
+		var output string
+		var cursor uint64
+		conn.SetReadDeadline(time.Now().Add(5 * time.Second))
+		for {
+			f, err := reader.ReadFrame()
+			if err != nil || f == nil {
+				t.Fatalf("watch ended early: %v (output %q)", err, output)
+			}
+			var r protocol.Response
+			if err := json.Unmarshal(f.Payload, &r); err != nil {
+				t.Fatalf("parse watch response: %v", err)
+			}
+			if r.Type != "WatchUpdate" {
+				t.Fatalf("unexpected response: %s: %s", r.Type, r.Message)
+			}
+			if r.Output != nil {
+				output += *r.Output
+			}
+			if r.Cursor != 0 {
+				cursor = r.Cursor
+			}
+			if stop(output, r.Done != nil && *r.Done) {
+				return output, cursor
+			}
+		}
+	}
+
+	first, cursor := watch(&protocol.Request{
+		Type:           "WatchSession",
+		ID:             uint32Ptr(id),
+		IncludeHistory: boolPtr(true),
+	}, func(output string, done bool) bool { return strings.Contains(output, "first-2") })
+	if cursor != uint64(len(first)) {
+		t.Fatalf("expected cursor %d after the history, got %d", len(first), cursor)
+	}
+
+	// Output written while disconnected is replayed once, then the rest.
+	time.Sleep(2 * time.Second)
+	rest, _ := watch(&protocol.Request{
+		Type:           "WatchSession",
+		ID:             uint32Ptr(id),
+		IncludeHistory: boolPtr(true),
+		ResumeFrom:     &cursor,
+	}, func(output string, done bool) bool { return done })
+	if strings.Contains(rest, "first-") {
+		t.Fatalf("resumed watch repeated output: %q", rest)
+	}
+
+	logs := requestResponse(t, sock, &protocol.Request{Type: "Logs", ID: uint32Ptr(id), Follow: boolPtr(false)})
+	if first+rest != logs.Data {
+		t.Fatalf("watch output %q + %q does not add up to the log %q", first, rest, logs.Data)
+	}
+}
+
 func TestLaunchWithEnv(t *testing.T) {
 	dir := tempDir(t, "launch-env")
 	sock := startTestNode(t, dir)
@@ -1145,6 +2215,51 @@ func TestEventDrivenPersistence(t *testing.T) {
 	}
 }
 
+func TestIdleNodeStaysIdle(t *testing.T) {
+	dir := tempDir(t, "idle")
+	sock := startTestNode(t, dir)
+
+	// A session with a limit that has already finished: nothing should be
+	// left waking up once its status has been recorded.
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:        "Launch",
+		Command:     []string{"true"},
+		WorkingDir:  "/tmp",
+		IdleTimeout: "1h",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	id := *resp.ID
+	deadline := time.Now().Add(3 * time.Second)
+	for {
+		status := requestResponse(t, sock, &protocol.Request{Type: "GetStatus", ID: &id})
+		if status.Info != nil && strings.HasPrefix(status.Info.Status, "completed") {
+			break
+		}
+		if time.Now().After(deadline) {
+			t.Fatal("session did not complete")
+		}
+		time.Sleep(100 * time.Millisecond)
+	}
+	time.Sleep(time.Second) // let the debounced persist run
+
+	cpuTime := func() time.Duration {
+		var ru syscall.Rusage
+		if err := syscall.Getrusage(syscall.RUSAGE_SELF, &ru); err != nil {
+			t.Fatalf("getrusage: %v", err)
+		}
+		return time.Duration(ru.Utime.Nano() + ru.Stime.Nano())
+	}
+	before := cpuTime()
+	time.Sleep(6 * time.Second)
+	// Mostly the Go runtime's own housekeeping; any polling loop in the
+	// node would show up well above this.
+	if used := cpuTime() - before; used > 50*time.Millisecond {
+		t.Fatalf("idle node used %v of CPU in 6s", used)
+	}
+}
+
 func TestCorruptSessionsJsonRecovery(t *testing.T) {
 	dir := tempDir(t, "corrupt-sessions")
 	sessionsJSON := filepath.Join(dir, "sessions.json")
@@ -1791,3 +2906,107 @@ func TestHookDenied(t *testing.T) {
 		t.Fatal("timeout waiting for Hook() to return")
 	}
 }
+
+func TestRequestPastDeadlineIsDropped(t *testing.T) {
+	dir := tempDir(t, "deadline")
+	sock := startTestNode(t, dir)
+
+	// A request whose time ran out before it reached the node is refused.
+	expired := uint64(0)
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sleep", "30"},
+		WorkingDir: "/tmp",
+		TimeoutMs:  &expired,
+	})
+	if resp.Type != "Error" || !strings.Contains(resp.Message, "deadline exceeded") {
+		t.Fatalf("expected a deadline error, got %s: %s", resp.Type, resp.Message)
+	}
+	list := requestResponse(t, sock, &protocol.Request{Type: "ListSessions"})
+	if list.Sessions == nil || len(*list.Sessions) != 0 {
+		t.Fatalf("expected no session to be launched, got %+v", list.Sessions)
+	}
+
+	// A wait is cut short at the requester's deadline.
+	resp = requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sleep", "30"},
+		WorkingDir: "/tmp",
+	})
+	id := *resp.ID
+	defer requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: uint32Ptr(id)})
+	start := time.Now()
+	waitSecs := uint64(60)
+	timeoutMs := uint64(500)
+	requestResponse(t, sock, &protocol.Request{
+		Type:           "Wait",
+		ID:             uint32Ptr(id),
+		TimeoutSeconds: &waitSecs,
+		TimeoutMs:      &timeoutMs,
+	})
+	if d := time.Since(start); d > 5*time.Second {
+		t.Fatalf("wait ignored the deadline, took %s", d)
+	}
+}
+
+func TestSummarizeNode(t *testing.T) {
+	dir := tempDir(t, "summary")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Command:    []string{"sleep", "30"},
+		WorkingDir: "/tmp",
+		Tags:       []string{"worker"},
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+	defer requestResponse(t, sock, &protocol.Request{Type: "Kill", ID: resp.ID})
+
+	sum := client.SummarizeNode(&client.Target{Local: dir, Timeout: 5 * time.Second})
+	if sum.Health != "ok" || sum.Running != 1 || sum.Total != 1 || sum.Tags["worker"] != 1 {
+		t.Fatalf("unexpected summary: %+v", sum)
+	}
+
+	down := client.SummarizeNode(&client.Target{Local: t.TempDir(), Timeout: time.Second})
+	if down.Health != "unreachable" || down.Error == "" {
+		t.Fatalf("expected an unreachable node, got %+v", down)
+	}
+}
+
+func TestValidateLaunchDoesNotSpawn(t *testing.T) {
+	dir := tempDir(t, "validate-launch")
+	sock := startTestNode(t, dir)
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type: "ValidateLaunch",
+		Launches: []protocol.Request{
+			{Name: "ok", Command: []string{"sh", "-c", "true"}, WorkingDir: "/tmp", MaxRuntime: "1h"},
+			{Name: "missing", Command: []string{"no-such-binary-cw-test"}, WorkingDir: "/tmp"},
+			{Name: "nodir", Command: []string{"true"}, WorkingDir: "/no/such/dir"},
+			{Name: "ok", Command: []string{"true"}, WorkingDir: "/tmp"},
+		},
+	})
+	if resp.Type != "LaunchValidated" || len(resp.Plans) != 4 {
+		t.Fatalf("expected 4 plans, got %s: %+v", resp.Type, resp)
+	}
+	ok := resp.Plans[0]
+	if !ok.OK || !strings.HasSuffix(ok.Path, "/sh") || ok.WorkingDir != "/tmp" || ok.MaxRuntime != "1h0m0s" {
+		t.Errorf("valid launch: %+v", ok)
+	}
+	for i, want := range []string{"", "no-such-binary-cw-test", "/no/such/dir", `name "ok" is also used`} {
+		p := resp.Plans[i]
+		if want == "" {
+			continue
+		}
+		if p.OK || len(p.Problems) == 0 || !strings.Contains(strings.Join(p.Problems, "; "), want) {
+			t.Errorf("launch %d: expected a problem mentioning %q, got %+v", i+1, want, p)
+		}
+	}
+
+	list := requestResponse(t, sock, &protocol.Request{Type: "ListSessions"})
+	if list.Sessions == nil || len(*list.Sessions) != 0 {
+		t.Fatalf("dry run started sessions: %+v", list.Sessions)
+	}
+}
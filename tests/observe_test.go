@@ -0,0 +1,100 @@
+package tests
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"net"
+	"net/http"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+	"time"
+
+	"nhooyr.io/websocket"
+
+	"github.com/codewiresh/codewire/internal/auth"
+	"github.com/codewiresh/codewire/internal/protocol"
+)
+
+func TestObserveStreamsOutputReadOnly(t *testing.T) {
+	dir := tempDir(t, "observe")
+	l, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		t.Fatal(err)
+	}
+	addr := l.Addr().String()
+	l.Close()
+	if err := os.WriteFile(filepath.Join(dir, "config.toml"), []byte(fmt.Sprintf("[node]\nlisten = %q\n", addr)), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	sock := startTestNode(t, dir)
+	for i := 0; ; i++ {
+		resp, err := http.Get("http://" + addr + "/healthz")
+		if err == nil {
+			resp.Body.Close()
+			break
+		}
+		if i == 50 {
+			t.Fatalf("websocket listener not up: %v", err)
+		}
+		time.Sleep(100 * time.Millisecond)
+	}
+
+	observeToken, err := auth.LoadOrGenerateObserveToken(dir)
+	if err != nil {
+		t.Fatal(err)
+	}
+
+	resp := requestResponse(t, sock, &protocol.Request{
+		Type:       "Launch",
+		Name:       "build",
+		Command:    []string{"bash", "-c", "echo step-1; sleep 1; echo step-2"},
+		WorkingDir: "/tmp",
+	})
+	if resp.Type != "Launched" {
+		t.Fatalf("expected Launched, got %s: %s", resp.Type, resp.Message)
+	}
+
+	ctx, cancel := context.WithTimeout(context.Background(), 10*time.Second)
+	defer cancel()
+
+	// The observe token opens nothing but /observe.
+	if _, _, err := websocket.Dial(ctx, "ws://"+addr+"/ws", &websocket.DialOptions{
+		HTTPHeader: http.Header{"Authorization": {"Bearer " + observeToken}},
+	}); err == nil {
+		t.Fatal("observe token accepted on /ws")
+	}
+	if _, _, err := websocket.Dial(ctx, "ws://"+addr+"/observe/build?token=wrong", nil); err == nil {
+		t.Fatal("bad token accepted on /observe")
+	}
+
+	conn, _, err := websocket.Dial(ctx, "ws://"+addr+"/observe/build?token="+observeToken, nil)
+	if err != nil {
+		t.Fatalf("dial observe: %v", err)
+	}
+	defer conn.CloseNow()
+
+	var output strings.Builder
+	for {
+		typ, data, err := conn.Read(ctx)
+		if err != nil {
+			var ce websocket.CloseError
+			if !errors.As(err, &ce) || ce.Code != websocket.StatusNormalClosure {
+				t.Fatalf("observe ended with %v (output %q)", err, output.String())
+			}
+			if !strings.Contains(ce.Reason, "completed") {
+				t.Errorf("close reason %q, want the final status", ce.Reason)
+			}
+			break
+		}
+		if typ != websocket.MessageBinary {
+			t.Fatalf("got a %v message, want binary", typ)
+		}
+		output.Write(data)
+	}
+	if !strings.Contains(output.String(), "step-1") || !strings.Contains(output.String(), "step-2") {
+		t.Fatalf("observed output %q", output.String())
+	}
+}